@@ -0,0 +1,80 @@
+//! Output pipeline benchmarks on large answers, built by repeating the fixtures in
+//! `benches/fixtures` to the size of a big IRR answer. Run with `cargo bench`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::hint::black_box;
+
+use whois_cli::{ColorScheme, MarkdownRenderer, OutputColorizer, RirHyperlinkProcessor, Theme};
+
+const IRR_ROUTES: &str = include_str!("fixtures/irr-routes.txt");
+const RIPE_AUT_NUM: &str = include_str!("fixtures/ripe-aut-num.txt");
+const REPORT: &str = include_str!("fixtures/report.md");
+
+/// `fixture` repeated until it is at least `size` bytes, as one answer
+fn answer(fixture: &str, size: usize) -> String {
+    let mut answer = String::with_capacity(size + fixture.len());
+    while answer.len() < size {
+        answer.push_str(fixture);
+        answer.push('\n');
+    }
+    answer
+}
+
+fn sizes() -> [(&'static str, usize); 2] {
+    [("64KiB", 64 << 10), ("4MiB", 4 << 20)]
+}
+
+fn colorize(c: &mut Criterion) {
+    colored::control::set_override(true);
+    let mut group = c.benchmark_group("colorize_ripe");
+    group.sample_size(20);
+    for (fixture_name, fixture) in [("irr-routes", IRR_ROUTES), ("aut-num", RIPE_AUT_NUM)] {
+        for (size_name, size) in sizes() {
+            let text = answer(fixture, size);
+            group.throughput(Throughput::Bytes(text.len() as u64));
+            group.bench_with_input(BenchmarkId::new(fixture_name, size_name), &text, |b, text| {
+                b.iter(|| OutputColorizer::colorize(black_box(text), ColorScheme::Ripe))
+            });
+        }
+    }
+    group.finish();
+}
+
+fn hyperlinks(c: &mut Criterion) {
+    colored::control::set_override(true);
+    let processor = RirHyperlinkProcessor::new();
+    let mut group = c.benchmark_group("hyperlinks");
+    group.sample_size(20);
+    for (size_name, size) in sizes() {
+        let text = answer(IRR_ROUTES, size);
+        group.throughput(Throughput::Bytes(text.len() as u64));
+        group.bench_with_input(BenchmarkId::new("irr-routes", size_name), &text, |b, text| {
+            b.iter(|| processor.process(black_box(text)))
+        });
+        group.bench_with_input(BenchmarkId::new("irr-routes-colored", size_name), &text, |b, text| {
+            b.iter(|| OutputColorizer::colorize_linked(black_box(text), ColorScheme::Ripe, Theme::Default, true))
+        });
+    }
+    group.finish();
+}
+
+fn markdown(c: &mut Criterion) {
+    colored::control::set_override(true);
+    let mut group = c.benchmark_group("markdown");
+    group.sample_size(20);
+    for (size_name, size) in sizes() {
+        let text = answer(REPORT, size);
+        group.throughput(Throughput::Bytes(text.len() as u64));
+        group.bench_with_input(BenchmarkId::new("detect", size_name), &text, |b, text| {
+            b.iter(|| MarkdownRenderer::is_markdown(black_box(text)))
+        });
+        group.bench_with_input(BenchmarkId::new("render", size_name), &text, |b, text| {
+            let mut renderer = MarkdownRenderer::new(false);
+            b.iter(|| renderer.render(black_box(text)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, colorize, hyperlinks, markdown);
+criterion_main!(benches);
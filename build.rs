@@ -0,0 +1,19 @@
+use std::process::Command;
+
+/// Build details for `whois version`: target triple, profile and git commit
+/// Release pipelines building from a source tarball set `WHOIS_GIT_COMMIT` instead
+fn main() {
+    println!("cargo:rerun-if-env-changed=WHOIS_GIT_COMMIT");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+
+    let target = std::env::var("TARGET").unwrap_or_default();
+    let profile = std::env::var("PROFILE").unwrap_or_default();
+    let commit = std::env::var("WHOIS_GIT_COMMIT").ok().filter(|commit| !commit.is_empty()).or_else(|| {
+        let output = Command::new("git").args(["rev-parse", "--short=12", "HEAD"]).output().ok()?;
+        output.status.success().then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    });
+    println!("cargo:rustc-env=WHOIS_BUILD_TARGET={}", target);
+    println!("cargo:rustc-env=WHOIS_BUILD_PROFILE={}", profile);
+    println!("cargo:rustc-env=WHOIS_GIT_COMMIT={}", commit.unwrap_or_else(|| "unknown".to_string()));
+}
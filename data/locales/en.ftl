@@ -0,0 +1,267 @@
+# Client messages: section headings, labels and warnings
+# Every message here must also be in the other catalogs
+
+## Warnings and errors
+warn-psl = { $error }, using the built-in public suffix list
+warn-history-compare = Cannot compare with version { $revision }: { $error }
+error-no-history = No version history for { $key } on { $server }: { $reason }
+error-lint-problems = { $count } problem(s) in { $file }
+error-probe-failures = { $count } conformance check(s) failed on { $server }
+warn-whois-failed-ripestat = WHOIS lookup failed ({ $error }), showing RIPEstat data only
+warn-no-inverse = { $server } does not take -i/-T, the answer is for the plain query
+warn-related-skipped = { $server } has no less/more-specific query flags, --related skipped
+warn-related-failed = Related networks lookup failed: { $error }
+warn-contacts-skipped = { $server } has no org/POC query prefixes, --contacts skipped
+warn-stat-skipped = --stat applies to ASN, IP and prefix queries, skipped for { $target }
+warn-stat-failed = RIPEstat summary failed: { $error }
+warn-ct-skipped = --ct applies to domain queries, skipped for { $target }
+warn-ct-failed = Certificate transparency lookup failed: { $error }
+warn-mail-skipped = --mail-check applies to domain queries, skipped for { $target }
+warn-mail-failed = Mail policy check failed: { $error }
+warn-empty-response = Empty response received. Please check if your query is correct.
+warn-markdown-failed = Markdown rendering failed: { $error }
+warn-ripestat-skipped = Skipping RIPEstat lookups: { $error }
+warn-prefixes-failed = Announced prefixes lookup failed: { $error }
+warn-neighbours-failed = BGP neighbours lookup failed: { $error }
+warn-copy-not-found = Field not found, nothing copied: { $field }
+warn-copy-failed = Clipboard copy failed: { $error }
+partial-response = Partial response: interrupted before the server finished
+partial-reset = Partial response: the server reset the connection before it finished
+query-failed = Query failed
+invalid-target = Invalid target
+export-failed = Export failed
+output-failed = Output failed
+interrupted = Interrupted
+interrupted-printing = Interrupted, printing results gathered so far (Ctrl-C again to quit)
+targets-not-finished = { $count } of { $total } target(s) not finished
+
+## IANA TLD delegation card
+tld-heading = Delegation of .{ $tld }
+tld-operator = Registry operator
+tld-whois = WHOIS server
+tld-no-whois = none
+tld-status = Status
+tld-created = Created
+tld-changed = Changed
+tld-nameservers = Name servers ({ $count })
+tld-dnssec = DNSSEC
+tld-signed = signed
+tld-unsigned = unsigned
+
+## Certificate transparency
+ct-heading = Certificates logged for { $domain } ({ $count })
+ct-shown = { $shown } of { $total }
+ct-none = No certificates found in CT logs
+ct-not-before = Not before
+ct-not-after = Not after
+ct-issuer = Issuer
+ct-sans = SANs
+
+## Mail policy
+mail-heading = Mail policy for { $domain }
+mail-none = none
+mail-null-mx = null MX: the domain accepts no mail
+mail-no-mx = no MX records: mail is delivered to the domain's A/AAAA records, if any
+mail-no-spf = no SPF record: anyone can send mail as this domain
+mail-spf-multiple = { $count } SPF records: receivers treat this as a permanent error
+mail-no-dmarc = no DMARC record at _dmarc: spoofed mail is not rejected
+mail-dmarc-multiple = { $count } DMARC records: receivers ignore them all
+mail-spf-fail-all = SPF fails unlisted senders (-all)
+mail-spf-softfail-all = SPF only soft-fails unlisted senders (~all)
+mail-spf-neutral-all = SPF is neutral about unlisted senders (?all)
+mail-spf-pass-all = SPF allows any sender (+all)
+mail-spf-no-all = SPF has no `all` mechanism: unlisted senders are neutral
+mail-spf-lookups = SPF needs { $count } DNS lookups, over the limit of { $limit }
+mail-spf-ptr = SPF uses the deprecated ptr mechanism
+mail-dmarc-reject = DMARC rejects failing mail (p=reject)
+mail-dmarc-quarantine = DMARC quarantines failing mail (p=quarantine)
+mail-dmarc-monitor = DMARC only monitors (p=none): failing mail is delivered
+mail-dmarc-invalid = DMARC policy p={ $policy } is invalid
+mail-dmarc-no-policy = DMARC record has no p= policy
+mail-dmarc-pct = DMARC policy applies to { $pct }% of failing mail (pct={ $pct })
+mail-dmarc-no-rua = DMARC has no rua= address: no aggregate reports are sent
+
+## RIPEstat
+stat-heading = RIPEstat summary for { $resource }
+stat-origin = Origin
+stat-visibility = Visibility
+stat-visibility-value = { $family } { $seeing }/{ $total } RIS peers
+stat-first-seen = First seen
+stat-announced = Announced
+stat-announced-value = { $v4 } IPv4 and { $v6 } IPv6 prefixes
+stat-abuse = Abuse contact
+stat-no-abuse = none registered
+prefixes-heading = Prefixes announced by AS{ $asn } ({ $count })
+prefixes-prefix = Prefix
+prefixes-first-seen = First seen
+prefixes-last-seen = Last seen
+neighbours-heading = BGP neighbours of AS{ $asn } ({ $count })
+neighbours-side = Side
+neighbours-power = Power
+neighbours-v4 = v4 peers
+neighbours-v6 = v6 peers
+
+## Object history
+history-heading = Version history of { $key } ({ $count })
+history-revision = rev#
+history-date = Date
+history-operation = Operation
+history-hint = Use `whois show-version N { $key }` to see a version and what it changed
+history-remote-heading = Archived records of { $key } from { $archive } ({ $count })
+history-registrar = Registrar
+history-changes = Changes from version { $from } to { $to }
+history-no-changes = No changes
+
+## Organisations, maintainers and contacts
+portfolio-heading = Resources of { $handle } ({ $count })
+portfolio-type = Type
+portfolio-resource = Resource
+portfolio-name = Name
+portfolio-via = Found via
+audit-heading = Audit of { $maintainer }
+audit-auth-ok = Authentication: { $count } auth line(s), none flagged
+audit-auth-flagged = Authentication: { $flagged } of { $count } auth line(s) flagged
+audit-protected = Protected objects: { $count }
+contacts-heading = Contacts for { $target } ({ $count })
+contacts-none = No org or POC records beyond those in the answer
+
+## Related networks and lint
+related-heading = Related networks for { $target } ({ $count })
+related-none = No less- or more-specific networks found
+related-more = more specific
+related-less = less specific
+lint-heading = Lint: { $count } warning(s)
+
+## Ownership correlation
+correlate-heading = Shared across { $count } targets
+correlate-kind = Kind
+correlate-value = Value
+correlate-targets = Targets
+correlate-none = No values shared between the targets
+correlate-org = Org
+correlate-email = Email
+correlate-maintainer = Maintainer
+correlate-nameserver = Name server
+compare-heading = { $target } on { $count } servers
+compare-server = Server
+compare-lines = Lines
+compare-modified = Modified
+compare-status = Status
+compare-reference = Reference
+compare-identical = Identical
+compare-differs = Differs by +{ $added } −{ $removed }
+irr-status-heading = IRR mirror serials
+irr-status-source = Source
+irr-status-server = Server
+irr-status-serial = Serial
+irr-status-state = State
+irr-status-none = No server reported serials; is it running IRRd?
+irr-status-authoritative = authoritative
+irr-status-current = current
+irr-status-behind = { $count } changes behind
+irr-status-unknown = authoritative serial unknown
+irrd-prefixes = { $count } prefix(es) originated by { $target } on { $server }
+irrd-members = { $count } member(s) of { $name } on { $server }
+compare-stale = Stale, differs by +{ $added } −{ $removed }
+compare-empty = No object
+compare-failed = Failed: { $error }
+compare-diff = { $reference } → { $server }
+
+## Domain portfolio audit
+registration-heading = Domain portfolio: { $count } domain(s), { $alerts } expiring within { $days } days
+registration-domain = Domain
+registration-registrar = Registrar
+registration-expires = Expires
+registration-days = Days
+registration-lock = Lock
+registration-dnssec = DNSSEC
+registration-nameservers = Name servers
+
+## PGP verification
+pgp-verified = PGP: verified, signed by { $fingerprint }
+pgp-verified-partial = PGP: only the signed block is verified, signed by { $fingerprint }; { $lines } line(s) outside it are NOT signed
+pgp-untrusted = PGP: unverified, good signature by untrusted key { $fingerprint }
+pgp-bad = PGP: unverified, BAD signature by key { $key }
+pgp-missing-key = PGP: unverified, public key { $key } is not in the keyring
+pgp-unsigned = PGP: unverified, the response is not signed
+pgp-failed = PGP: unverified, { $reason }
+
+## Attribute glossary for --explain
+explain-heading = Attribute glossary
+gloss-admin-c = administrative contact: NIC handle of the person or role responsible for the object
+gloss-tech-c = technical contact: NIC handle of whoever runs the network or service
+gloss-abuse-c = abuse contact: role object whose abuse-mailbox receives spam and attack reports
+gloss-zone-c = zone contact: NIC handle of whoever maintains the DNS zone
+gloss-abuse-mailbox = address that receives abuse reports
+gloss-nic-hdl = NIC handle: unique ID of a person or role, used in admin-c/tech-c
+gloss-mnt-by = maintainer that protects this object; only it can change or delete it
+gloss-mnt-lower = maintainer allowed to create more-specific address blocks inside this one
+gloss-mnt-routes = maintainer allowed to create route objects for this space
+gloss-mnt-domains = maintainer allowed to create reverse DNS (domain) objects for this space
+gloss-mnt-ref = maintainer allowed to reference this organisation from other objects
+gloss-mnt-irt = incident response team (irt object) responsible for this space
+gloss-mnt-nfy = address notified when objects protected by this maintainer change
+gloss-upd-to = address notified when an update fails this maintainer's authentication
+gloss-auth = credential that authorises updates, e.g. a PGP key or SSO account
+gloss-notify = address notified whenever this object changes
+gloss-ref-nfy = address notified when other objects start or stop referencing this organisation
+gloss-org = organisation object that holds this resource
+gloss-sponsoring-org = LIR that sponsors this resource for an end user
+gloss-org-type = kind of organisation: LIR, RIR, OTHER, ...
+gloss-netname = short name of the network; not unique
+gloss-as-name = short name of the autonomous system
+gloss-origin = AS that is allowed to announce this prefix in BGP
+gloss-status = allocation or assignment type, e.g. ALLOCATED PA or ASSIGNED PI
+gloss-member-of = set this object claims membership of; the set must accept it via mbrs-by-ref
+gloss-members = ASNs, sets or prefixes that make up this set
+gloss-mp-members = members of any address family, including IPv6 prefixes
+gloss-mbrs-by-ref = maintainers whose objects may join this set with member-of; ANY allows everyone
+gloss-import = routing policy: what this AS accepts from a neighbour (IPv4)
+gloss-export = routing policy: what this AS announces to a neighbour (IPv4)
+gloss-mp-import = routing policy for any address family: what this AS accepts
+gloss-mp-export = routing policy for any address family: what this AS announces
+gloss-default = neighbour this AS sends traffic to when it has no more specific route
+gloss-mp-default = default route policy for any address family
+gloss-holes = parts of a route that are not announced
+gloss-pingable = address that should answer ping, for checking reachability
+gloss-ping-hdl = contact for questions about the pingable address
+gloss-aggr-mtd = how more-specific routes are aggregated into this one
+gloss-geoloc = latitude and longitude of the network
+gloss-geofeed = URL of an RFC 8805 CSV with the geolocation of the network's prefixes
+gloss-language = language used by the network's users
+gloss-created = when the object was created in the database
+gloss-last-modified = when the object last changed in the database
+gloss-changed = address and date of an update (legacy attribute)
+gloss-source = database the object comes from, e.g. RIPE or RIPE-NONAUTH
+gloss-nserver = name server the domain is delegated to
+gloss-ds-rdata = DNSSEC delegation signer record published in the parent zone
+gloss-certif = armored public key of a key-cert object
+gloss-fingerpr = fingerprint of the key in this key-cert
+gloss-irt-nfy = address notified when the irt object is referenced or changes
+gloss-assignment-size = prefix length of the assignments made from this allocation
+probe-heading = Conformance of { $server }
+probe-advertised = Advertised
+probe-version = Version
+probe-schemes = Schemes
+probe-summary = { $passed } passed, { $warnings } warnings, { $failed } failed
+probe-answered = answered with a capability line
+probe-plain-server = no capability line: a plain WHOIS server, which is allowed
+probe-extra-lines = capability line followed by { $count } other lines
+probe-kept-open = no answer and the connection stayed open; servers must answer or close
+probe-not-advertised = not advertised
+probe-legacy-ignored = no capability line for a v1.0 probe; older clients see a plain server
+probe-unasked-color = ANSI colors in an answer that did not ask for them
+probe-header-in-plain = protocol header lines in an answer to a plain query
+probe-empty = empty answer to { $query }
+probe-lines = { $count } lines
+probe-colored = colored with scheme { $scheme }
+probe-not-colored = asked for scheme { $scheme } but got no colors
+probe-markdown-applied = X-WHOIS-MARKDOWN-APPLIED: { $applied }
+probe-markdown-unmarked = no X-WHOIS-MARKDOWN-APPLIED line, so clients must guess the format
+probe-unknown-ignored = unknown header ignored
+probe-unknown-changed = an unknown header changed the answer; servers must ignore headers they do not know
+probe-decoded = { $encoding } answer decoded to the plain answer
+probe-decoded-differs = { $encoding } answer decoded, but differs from the plain answer
+probe-unknown-version = unknown protocol version { $version }
+probe-unknown-fields = unknown capability fields: { $fields }
+probe-v10-extensions = v1.0 does not define markdown or images; announce v1.1
@@ -0,0 +1,267 @@
+# 客户端消息：分节标题、标签和警告
+# 此处每条消息都必须与英文目录一一对应
+
+## 警告和错误
+warn-psl = { $error }，改用内置的公共后缀列表
+warn-history-compare = 无法与版本 { $revision } 比较：{ $error }
+error-no-history = { $server } 上没有 { $key } 的版本历史：{ $reason }
+error-lint-problems = { $file } 中有 { $count } 个问题
+error-probe-failures = { $server } 有 { $count } 项一致性检查失败
+warn-whois-failed-ripestat = WHOIS 查询失败（{ $error }），仅显示 RIPEstat 数据
+warn-no-inverse = { $server } 不支持 -i/-T，结果为普通查询的结果
+warn-related-skipped = { $server } 不支持上级/下级网段查询参数，已跳过 --related
+warn-related-failed = 相关网段查询失败：{ $error }
+warn-contacts-skipped = { $server } 没有组织/POC 查询前缀，已跳过 --contacts
+warn-stat-skipped = --stat 仅适用于 ASN、IP 和前缀查询，已跳过 { $target }
+warn-stat-failed = RIPEstat 摘要获取失败：{ $error }
+warn-ct-skipped = --ct 仅适用于域名查询，已跳过 { $target }
+warn-ct-failed = 证书透明度查询失败：{ $error }
+warn-mail-skipped = --mail-check 仅适用于域名查询，已跳过 { $target }
+warn-mail-failed = 邮件策略检查失败：{ $error }
+warn-empty-response = 收到空响应，请检查查询内容是否正确。
+warn-markdown-failed = Markdown 渲染失败：{ $error }
+warn-ripestat-skipped = 跳过 RIPEstat 查询：{ $error }
+warn-prefixes-failed = 宣告前缀查询失败：{ $error }
+warn-neighbours-failed = BGP 邻居查询失败：{ $error }
+warn-copy-not-found = 未找到字段，未复制任何内容：{ $field }
+warn-copy-failed = 复制到剪贴板失败：{ $error }
+partial-response = 部分响应：服务器返回完成前已中断
+partial-reset = 部分响应：服务器在返回完成前重置了连接
+query-failed = 查询失败
+invalid-target = 无效的查询目标
+export-failed = 导出失败
+output-failed = 输出失败
+interrupted = 已中断
+interrupted-printing = 已中断，正在输出已获得的结果（再按一次 Ctrl-C 退出）
+targets-not-finished = { $total } 个目标中有 { $count } 个未完成
+
+## IANA 顶级域委派摘要
+tld-heading = .{ $tld } 的委派信息
+tld-operator = 注册局运营者
+tld-whois = WHOIS 服务器
+tld-no-whois = 无
+tld-status = 状态
+tld-created = 创建时间
+tld-changed = 修改时间
+tld-nameservers = 域名服务器（{ $count }）
+tld-dnssec = DNSSEC
+tld-signed = 已签名
+tld-unsigned = 未签名
+
+## 证书透明度
+ct-heading = CT 日志中 { $domain } 的证书（{ $count }）
+ct-shown = { $total } 张中的 { $shown } 张
+ct-none = CT 日志中未找到证书
+ct-not-before = 生效时间
+ct-not-after = 失效时间
+ct-issuer = 颁发者
+ct-sans = 备用名称
+
+## 邮件策略
+mail-heading = { $domain } 的邮件策略
+mail-none = 无
+mail-null-mx = 空 MX：该域名不接收邮件
+mail-no-mx = 没有 MX 记录：邮件将投递到该域名的 A/AAAA 记录（如有）
+mail-no-spf = 没有 SPF 记录：任何人都可以冒用该域名发信
+mail-spf-multiple = 有 { $count } 条 SPF 记录：收件方会视为永久错误
+mail-no-dmarc = _dmarc 下没有 DMARC 记录：伪造邮件不会被拒收
+mail-dmarc-multiple = 有 { $count } 条 DMARC 记录：收件方会全部忽略
+mail-spf-fail-all = SPF 拒绝未列出的发件方（-all）
+mail-spf-softfail-all = SPF 仅对未列出的发件方软失败（~all）
+mail-spf-neutral-all = SPF 对未列出的发件方保持中立（?all）
+mail-spf-pass-all = SPF 允许任何发件方（+all）
+mail-spf-no-all = SPF 没有 `all` 机制：未列出的发件方视为中立
+mail-spf-lookups = SPF 需要 { $count } 次 DNS 查询，超过 { $limit } 次的上限
+mail-spf-ptr = SPF 使用了已弃用的 ptr 机制
+mail-dmarc-reject = DMARC 拒收未通过验证的邮件（p=reject）
+mail-dmarc-quarantine = DMARC 隔离未通过验证的邮件（p=quarantine）
+mail-dmarc-monitor = DMARC 仅监控（p=none）：未通过验证的邮件仍会投递
+mail-dmarc-invalid = DMARC 策略 p={ $policy } 无效
+mail-dmarc-no-policy = DMARC 记录缺少 p= 策略
+mail-dmarc-pct = DMARC 策略仅作用于 { $pct }% 未通过验证的邮件（pct={ $pct }）
+mail-dmarc-no-rua = DMARC 没有 rua= 地址：不会发送汇总报告
+
+## RIPEstat
+stat-heading = { $resource } 的 RIPEstat 摘要
+stat-origin = 起源 AS
+stat-visibility = 可见度
+stat-visibility-value = { $family } { $seeing }/{ $total } 个 RIS 对等体
+stat-first-seen = 首次出现
+stat-announced = 宣告
+stat-announced-value = { $v4 } 个 IPv4 前缀和 { $v6 } 个 IPv6 前缀
+stat-abuse = 滥用联系人
+stat-no-abuse = 未登记
+prefixes-heading = AS{ $asn } 宣告的前缀（{ $count }）
+prefixes-prefix = 前缀
+prefixes-first-seen = 首次出现
+prefixes-last-seen = 最后出现
+neighbours-heading = AS{ $asn } 的 BGP 邻居（{ $count }）
+neighbours-side = 方向
+neighbours-power = 强度
+neighbours-v4 = v4 对等体
+neighbours-v6 = v6 对等体
+
+## 对象历史
+history-heading = { $key } 的版本历史（{ $count }）
+history-revision = 版本
+history-date = 日期
+history-operation = 操作
+history-hint = 使用 `whois show-version N { $key }` 查看某个版本及其改动
+history-remote-heading = { $archive } 存档的 { $key } 记录（{ $count }）
+history-registrar = 注册商
+history-changes = 版本 { $from } 到 { $to } 的改动
+history-no-changes = 无改动
+
+## 组织、维护者和联系人
+portfolio-heading = { $handle } 持有的资源（{ $count }）
+portfolio-type = 类型
+portfolio-resource = 资源
+portfolio-name = 名称
+portfolio-via = 来源
+audit-heading = { $maintainer } 的审计
+audit-auth-ok = 认证：{ $count } 条 auth，均无问题
+audit-auth-flagged = 认证：{ $count } 条 auth 中有 { $flagged } 条被标记
+audit-protected = 受保护对象：{ $count }
+contacts-heading = { $target } 的联系人（{ $count }）
+contacts-none = 除结果中已有的记录外，没有其他组织或 POC 记录
+
+## 相关网段和检查
+related-heading = { $target } 的相关网段（{ $count }）
+related-none = 未找到上级或下级网段
+related-more = 下级网段
+related-less = 上级网段
+lint-heading = 检查：{ $count } 条警告
+
+## 归属关联
+correlate-heading = { $count } 个目标之间的共同值
+correlate-kind = 类型
+correlate-value = 值
+correlate-targets = 目标
+correlate-none = 目标之间没有共同值
+correlate-org = 组织
+correlate-email = 邮箱
+correlate-maintainer = 维护者
+correlate-nameserver = 域名服务器
+compare-heading = { $target }，共 { $count } 个服务器
+compare-server = 服务器
+compare-lines = 行数
+compare-modified = 修改日期
+compare-status = 状态
+compare-reference = 基准
+compare-identical = 一致
+compare-differs = 不同：+{ $added } −{ $removed }
+irr-status-heading = IRR 镜像序列号
+irr-status-source = 数据源
+irr-status-server = 服务器
+irr-status-serial = 序列号
+irr-status-state = 状态
+irr-status-none = 没有服务器报告序列号；它运行的是 IRRd 吗？
+irr-status-authoritative = 权威
+irr-status-current = 最新
+irr-status-behind = 落后 { $count } 个变更
+irr-status-unknown = 权威序列号未知
+irrd-prefixes = { $target } 在 { $server } 上宣告的 { $count } 个前缀
+irrd-members = { $name } 在 { $server } 上的 { $count } 个成员
+compare-stale = 过时，不同：+{ $added } −{ $removed }
+compare-empty = 无对象
+compare-failed = 失败：{ $error }
+compare-diff = { $reference } → { $server }
+
+## 域名资产审计
+registration-heading = 域名资产：{ $count } 个域名，{ $alerts } 个将在 { $days } 天内到期
+registration-domain = 域名
+registration-registrar = 注册商
+registration-expires = 到期日
+registration-days = 剩余天数
+registration-lock = 转移锁
+registration-dnssec = DNSSEC
+registration-nameservers = 域名服务器
+
+## PGP 验证
+pgp-verified = PGP：已验证，签名者 { $fingerprint }
+pgp-verified-partial = PGP：仅签名块已验证，签名者 { $fingerprint }；签名块之外的 { $lines } 行未签名
+pgp-untrusted = PGP：未验证，签名有效但密钥 { $fingerprint } 不受信任
+pgp-bad = PGP：未验证，密钥 { $key } 的签名无效
+pgp-missing-key = PGP：未验证，公钥 { $key } 不在密钥环中
+pgp-unsigned = PGP：未验证，响应未签名
+pgp-failed = PGP：未验证，{ $reason }
+
+## --explain 属性说明
+explain-heading = 属性说明
+gloss-admin-c = 管理联系人：负责该对象的个人或角色的 NIC 句柄
+gloss-tech-c = 技术联系人：实际运营该网络或服务者的 NIC 句柄
+gloss-abuse-c = 滥用联系人：其 abuse-mailbox 接收垃圾邮件和攻击报告的角色对象
+gloss-zone-c = 区域联系人：维护该 DNS 区域者的 NIC 句柄
+gloss-abuse-mailbox = 接收滥用报告的邮箱地址
+gloss-nic-hdl = NIC 句柄：个人或角色的唯一标识，用于 admin-c/tech-c
+gloss-mnt-by = 保护该对象的维护者；只有它可以修改或删除此对象
+gloss-mnt-lower = 可以在此地址块内创建更细分地址块的维护者
+gloss-mnt-routes = 可以为该地址空间创建路由对象的维护者
+gloss-mnt-domains = 可以为该地址空间创建反向 DNS（domain）对象的维护者
+gloss-mnt-ref = 可以在其他对象中引用该组织的维护者
+gloss-mnt-irt = 负责该地址空间的事件响应团队（irt 对象）
+gloss-mnt-nfy = 该维护者保护的对象发生变更时接收通知的地址
+gloss-upd-to = 更新未通过该维护者认证时接收通知的地址
+gloss-auth = 授权更新的凭据，例如 PGP 密钥或 SSO 账户
+gloss-notify = 该对象每次变更时接收通知的地址
+gloss-ref-nfy = 其他对象开始或停止引用该组织时接收通知的地址
+gloss-org = 持有该资源的组织对象
+gloss-sponsoring-org = 为最终用户担保该资源的 LIR
+gloss-org-type = 组织类型：LIR、RIR、OTHER 等
+gloss-netname = 网络的简称；不要求唯一
+gloss-as-name = 自治系统的简称
+gloss-origin = 允许在 BGP 中宣告该前缀的 AS
+gloss-status = 分配类型，例如 ALLOCATED PA 或 ASSIGNED PI
+gloss-member-of = 该对象声明所属的集合；集合需通过 mbrs-by-ref 接受
+gloss-members = 组成该集合的 ASN、集合或前缀
+gloss-mp-members = 任意地址族的成员，包括 IPv6 前缀
+gloss-mbrs-by-ref = 其对象可通过 member-of 加入该集合的维护者；ANY 表示任何人
+gloss-import = 路由策略：该 AS 从邻居接受哪些路由（IPv4）
+gloss-export = 路由策略：该 AS 向邻居宣告哪些路由（IPv4）
+gloss-mp-import = 任意地址族的路由策略：该 AS 接受哪些路由
+gloss-mp-export = 任意地址族的路由策略：该 AS 宣告哪些路由
+gloss-default = 该 AS 没有更具体路由时转发流量的邻居
+gloss-mp-default = 任意地址族的默认路由策略
+gloss-holes = 路由中不宣告的部分
+gloss-pingable = 应响应 ping 的地址，用于检查可达性
+gloss-ping-hdl = 关于 pingable 地址的联系人
+gloss-aggr-mtd = 更细分的路由如何聚合为此路由
+gloss-geoloc = 网络的经纬度
+gloss-geofeed = RFC 8805 格式 CSV 的 URL，内含各前缀的地理位置
+gloss-language = 该网络用户使用的语言
+gloss-created = 对象在数据库中创建的时间
+gloss-last-modified = 对象在数据库中最后修改的时间
+gloss-changed = 更新的邮箱和日期（旧式属性）
+gloss-source = 对象所属的数据库，例如 RIPE 或 RIPE-NONAUTH
+gloss-nserver = 该域名委派到的域名服务器
+gloss-ds-rdata = 在上级区域发布的 DNSSEC 委派签名者记录
+gloss-certif = key-cert 对象的 ASCII 封装公钥
+gloss-fingerpr = 该 key-cert 中密钥的指纹
+gloss-irt-nfy = irt 对象被引用或变更时接收通知的地址
+gloss-assignment-size = 从该分配中划出的地址块的前缀长度
+probe-heading = { $server } 的协议一致性
+probe-advertised = 声明
+probe-version = 版本
+probe-schemes = 配色
+probe-summary = 通过 { $passed } 项，警告 { $warnings } 项，失败 { $failed } 项
+probe-answered = 返回了能力声明行
+probe-plain-server = 无能力声明行：普通 WHOIS 服务器，这是允许的
+probe-extra-lines = 能力声明行后还有 { $count } 行其他内容
+probe-kept-open = 没有应答且连接未关闭；服务器必须应答或关闭连接
+probe-not-advertised = 未声明
+probe-legacy-ignored = v1.0 探测没有返回能力声明行；旧客户端会把它当作普通服务器
+probe-unasked-color = 未请求着色的应答中含有 ANSI 颜色
+probe-header-in-plain = 普通查询的应答中含有协议头行
+probe-empty = { $query } 的应答为空
+probe-lines = { $count } 行
+probe-colored = 已按配色 { $scheme } 着色
+probe-not-colored = 请求了配色 { $scheme }，但应答没有颜色
+probe-markdown-applied = X-WHOIS-MARKDOWN-APPLIED: { $applied }
+probe-markdown-unmarked = 没有 X-WHOIS-MARKDOWN-APPLIED 行，客户端只能猜测格式
+probe-unknown-ignored = 已忽略未知头
+probe-unknown-changed = 未知头改变了应答；服务器必须忽略不认识的头
+probe-decoded = { $encoding } 应答解码后与普通应答一致
+probe-decoded-differs = { $encoding } 应答可以解码，但与普通应答不同
+probe-unknown-version = 未知的协议版本 { $version }
+probe-unknown-fields = 未知的能力字段：{ $fields }
+probe-v10-extensions = v1.0 未定义 markdown 或 images；请声明 v1.1
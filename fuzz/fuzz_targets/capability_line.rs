@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use whois_cli::protocol::WhoisColorProtocol;
+
+fuzz_target!(|response: &str| {
+    let _ = WhoisColorProtocol.parse_capability_response(response);
+});
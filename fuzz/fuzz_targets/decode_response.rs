@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use whois_cli::compression::decode_response;
+
+fuzz_target!(|bytes: &[u8]| {
+    let _ = decode_response(bytes.to_vec());
+});
@@ -0,0 +1,9 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use whois_cli::markdown::{split_mixed, MarkdownRenderer};
+
+fuzz_target!(|text: &str| {
+    let _ = MarkdownRenderer::is_markdown(text);
+    let _ = split_mixed(text);
+});
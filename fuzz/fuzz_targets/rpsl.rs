@@ -0,0 +1,12 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use whois_cli::rpsl;
+
+fuzz_target!(|response: &str| {
+    let _ = rpsl::render(&rpsl::parse(response));
+    let _ = rpsl::dedup_objects(response);
+    let _ = rpsl::group_by_type(response);
+    let _ = rpsl::fold_policy(response);
+    let _ = rpsl::align(response);
+});
@@ -0,0 +1,38 @@
+/*
+ * C interface of whois-cli's query engine, built with
+ *   cargo rustc --lib --release --features ffi --crate-type cdylib
+ *
+ * Strings are UTF-8 and NUL-terminated.
+ */
+#ifndef WHOIS_H
+#define WHOIS_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/*
+ * Look up `target` and return a JSON record, the same as `whois --format json` prints.
+ *
+ * `options` is a JSON object or NULL; all fields are optional:
+ *   {"server": "whois.ripe.net", "port": 43, "timeout_ms": 10000,
+ *    "follow_referrals": true, "dn42": false, "bgptools": false, "asn": false}
+ * A timeout_ms of 0, invalid JSON or options that are not UTF-8 give an error record.
+ *
+ * A failed lookup returns {"target": ..., "error": ..., "kind": ...} where kind is one of
+ * "timeout", "rate-limited", "deadline-exceeded", "cancelled", "interrupted",
+ * "connection-reset" or "error".
+ *
+ * The result must be released with whois_string_free. NULL is returned only when
+ * `target` is NULL or not valid UTF-8. The call blocks until the lookup is finished.
+ */
+char *whois_query(const char *target, const char *options);
+
+/* Release a string returned by whois_query; NULL is ignored. */
+void whois_string_free(char *string);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* WHOIS_H */
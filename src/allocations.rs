@@ -0,0 +1,106 @@
+//! Which RIR holds an address or ASN, from the IANA address space and AS number registries
+//!
+//! The tables are coarse: legacy space administered by several RIRs is given to the one
+//! holding most of it. They only decide which server a bulk run expects a target to hit;
+//! the answer itself still comes from following IANA's referral.
+
+use std::net::IpAddr;
+
+const ARIN: &str = "whois.arin.net";
+const RIPE: &str = "whois.ripe.net";
+const APNIC: &str = "whois.apnic.net";
+const LACNIC: &str = "whois.lacnic.net";
+const AFRINIC: &str = "whois.afrinic.net";
+
+/// IPv4 /8 blocks by first octet; private, loopback and multicast space is left out
+const IPV4: &[(u8, u8, &str)] = &[
+    (1, 1, APNIC), (2, 2, RIPE), (3, 4, ARIN), (5, 5, RIPE), (6, 9, ARIN), (11, 13, ARIN), (14, 14, APNIC),
+    (15, 24, ARIN), (25, 25, RIPE), (26, 26, ARIN), (27, 27, APNIC), (28, 30, ARIN), (31, 31, RIPE),
+    (32, 35, ARIN), (36, 36, APNIC), (37, 37, RIPE), (38, 38, ARIN), (39, 39, APNIC), (40, 40, ARIN),
+    (41, 41, AFRINIC), (42, 43, APNIC), (44, 45, ARIN), (46, 46, RIPE), (47, 48, ARIN), (49, 49, APNIC),
+    (50, 50, ARIN), (51, 51, RIPE), (52, 52, ARIN), (53, 53, RIPE), (54, 56, ARIN), (57, 57, RIPE),
+    (58, 61, APNIC), (62, 62, RIPE), (63, 76, ARIN), (77, 95, RIPE), (96, 100, ARIN), (101, 101, APNIC),
+    (102, 102, AFRINIC), (103, 103, APNIC), (104, 104, ARIN), (105, 105, AFRINIC), (106, 106, APNIC),
+    (107, 108, ARIN), (109, 109, RIPE), (110, 126, APNIC), (128, 132, ARIN), (133, 133, APNIC),
+    (134, 140, ARIN), (141, 141, RIPE), (142, 149, ARIN), (150, 150, APNIC), (151, 151, RIPE),
+    (152, 152, ARIN), (153, 153, APNIC), (154, 154, AFRINIC), (155, 162, ARIN), (163, 163, APNIC),
+    (164, 170, ARIN), (171, 171, APNIC), (172, 174, ARIN), (175, 175, APNIC), (176, 176, RIPE),
+    (177, 177, LACNIC), (178, 178, RIPE), (179, 179, LACNIC), (180, 180, APNIC), (181, 181, LACNIC),
+    (182, 183, APNIC), (184, 184, ARIN), (185, 185, RIPE), (186, 187, LACNIC), (188, 188, RIPE),
+    (189, 191, LACNIC), (192, 192, ARIN), (193, 195, RIPE), (196, 197, AFRINIC), (198, 199, ARIN),
+    (200, 201, LACNIC), (202, 203, APNIC), (204, 209, ARIN), (210, 211, APNIC), (212, 213, RIPE),
+    (214, 216, ARIN), (217, 217, RIPE), (218, 223, APNIC),
+];
+
+/// IPv6 allocations by first 16 bits; `2001::/16` is split between all RIRs and left out
+const IPV6: &[(u16, u16, &str)] = &[
+    (0x2003, 0x2003, RIPE),
+    (0x2400, 0x24ff, APNIC),
+    (0x2600, 0x26ff, ARIN),
+    (0x2800, 0x28ff, LACNIC),
+    (0x2a00, 0x2aff, RIPE),
+    (0x2c00, 0x2cff, AFRINIC),
+];
+
+/// AS number blocks; 32-bit ASNs are handed out in one range per RIR
+const ASNS: &[(u32, u32, &str)] = &[
+    (1, 1876, ARIN), (1877, 1901, RIPE), (1902, 2042, ARIN), (2043, 2043, RIPE), (2044, 2046, ARIN),
+    (2047, 2047, RIPE), (2048, 2106, ARIN), (2107, 2136, RIPE), (2137, 2584, ARIN), (2585, 2614, RIPE),
+    (2615, 2772, ARIN), (2773, 2822, RIPE), (2823, 2829, ARIN), (2830, 2879, RIPE), (2880, 3153, ARIN),
+    (3154, 3353, RIPE), (3354, 4607, ARIN), (4608, 4865, APNIC), (4866, 5376, ARIN), (5377, 5631, RIPE),
+    (5632, 6655, ARIN), (6656, 6911, RIPE), (6912, 7466, ARIN), (7467, 7722, APNIC), (7723, 8191, ARIN),
+    (8192, 9215, RIPE), (9216, 10239, APNIC), (10240, 12287, ARIN), (12288, 13311, RIPE),
+    (13312, 15359, ARIN), (15360, 16383, RIPE), (16384, 17407, ARIN), (17408, 18431, APNIC),
+    (18432, 20479, ARIN), (20480, 21503, RIPE), (21504, 23455, ARIN), (23552, 24575, APNIC),
+    (24576, 25599, RIPE), (25600, 26591, ARIN), (26592, 26623, LACNIC), (26624, 27647, ARIN),
+    (27648, 28671, LACNIC), (28672, 31743, RIPE), (31744, 33791, ARIN), (33792, 35839, RIPE),
+    (35840, 36863, ARIN), (36864, 37887, AFRINIC), (37888, 38911, APNIC), (38912, 39935, RIPE),
+    (39936, 40959, ARIN), (40960, 45055, RIPE), (45056, 46079, APNIC), (46080, 47103, ARIN),
+    (47104, 52223, RIPE), (52224, 53247, LACNIC), (53248, 55295, ARIN), (55296, 56319, APNIC),
+    (56320, 58367, RIPE), (58368, 59391, APNIC), (59392, 61439, RIPE), (61440, 61951, LACNIC),
+    (61952, 62463, RIPE), (62464, 63487, ARIN), (63488, 63999, APNIC), (64000, 64495, ARIN),
+    (131072, 196607, APNIC), (196608, 262143, RIPE), (262144, 327679, LACNIC),
+    (327680, 393215, AFRINIC), (393216, 458751, ARIN),
+];
+
+fn find<T: PartialOrd>(table: &[(T, T, &'static str)], value: T) -> Option<&'static str> {
+    table.iter().find(|(first, last, _)| *first <= value && value <= *last).map(|(_, _, rir)| *rir)
+}
+
+/// WHOIS server of the RIR an ASN (`AS3333`), address or prefix is allocated to
+/// `None` for domains, handles and unallocated or reserved numbers
+pub fn rir_server(target: &str) -> Option<&'static str> {
+    let target = target.trim();
+    if let Some(asn) = target.get(..2).filter(|prefix| prefix.eq_ignore_ascii_case("AS")).and_then(|_| target[2..].parse::<u32>().ok()) {
+        return find(ASNS, asn);
+    }
+    match target.split('/').next()?.parse::<IpAddr>().ok()? {
+        IpAddr::V4(address) => find(IPV4, address.octets()[0]),
+        IpAddr::V6(address) => find(IPV6, address.segments()[0]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rir_server() {
+        assert_eq!(rir_server("8.8.8.8"), Some(ARIN));
+        assert_eq!(rir_server("193.0.0.0/21"), Some(RIPE));
+        assert_eq!(rir_server("1.1.1.1"), Some(APNIC));
+        assert_eq!(rir_server("200.160.2.3"), Some(LACNIC));
+        assert_eq!(rir_server("196.216.2.1"), Some(AFRINIC));
+        assert_eq!(rir_server("2a00:1450::1"), Some(RIPE));
+        assert_eq!(rir_server("2600::/12"), Some(ARIN));
+        assert_eq!(rir_server("AS3333"), Some(RIPE));
+        assert_eq!(rir_server("as15169"), Some(ARIN));
+        assert_eq!(rir_server("AS4608"), Some(APNIC));
+        assert_eq!(rir_server("AS263000"), Some(LACNIC));
+
+        assert_eq!(rir_server("10.0.0.1"), None);
+        assert_eq!(rir_server("AS64512"), None);
+        assert_eq!(rir_server("example.com"), None);
+        assert_eq!(rir_server("ASSET-EXAMPLE"), None);
+    }
+}
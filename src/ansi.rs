@@ -0,0 +1,415 @@
+use std::borrow::Cow;
+use std::ops::Range;
+
+/// Terminal color as carried by ANSI SGR sequences
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnsiColor {
+    /// One of the 16 basic palette entries
+    Basic(u8),
+    /// 256-color or truecolor value
+    Rgb(u8, u8, u8),
+}
+
+/// RGB values used for the 16 basic palette entries
+pub const BASIC_PALETTE: [(u8, u8, u8); 16] = [
+    (0x00, 0x00, 0x00),
+    (0xcd, 0x31, 0x31),
+    (0x0d, 0xbc, 0x79),
+    (0xe5, 0xe5, 0x10),
+    (0x24, 0x72, 0xc8),
+    (0xbc, 0x3f, 0xbc),
+    (0x11, 0xa8, 0xcd),
+    (0xe5, 0xe5, 0xe5),
+    (0x66, 0x66, 0x66),
+    (0xf1, 0x4c, 0x4c),
+    (0x23, 0xd1, 0x8b),
+    (0xf5, 0xf5, 0x43),
+    (0x3b, 0x8e, 0xea),
+    (0xd6, 0x70, 0xd6),
+    (0x29, 0xb8, 0xdb),
+    (0xff, 0xff, 0xff),
+];
+
+impl AnsiColor {
+    /// Resolve the color to concrete RGB values
+    pub fn to_rgb(self) -> (u8, u8, u8) {
+        match self {
+            AnsiColor::Basic(n) => BASIC_PALETTE[(n & 0x0f) as usize],
+            AnsiColor::Rgb(r, g, b) => (r, g, b),
+        }
+    }
+
+    /// Format the color as a CSS/SVG hex string
+    pub fn to_hex(self) -> String {
+        let (r, g, b) = self.to_rgb();
+        format!("#{:02x}{:02x}{:02x}", r, g, b)
+    }
+}
+
+/// Text attributes in effect for a run of characters
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AnsiStyle {
+    pub fg: Option<AnsiColor>,
+    pub bg: Option<AnsiColor>,
+    pub bold: bool,
+    pub dim: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub strikethrough: bool,
+}
+
+impl AnsiStyle {
+    /// Check if no attributes are set
+    pub fn is_plain(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Apply a list of SGR parameters to this style
+    pub fn apply_sgr(&mut self, params: &[u16]) {
+        if params.is_empty() {
+            *self = Self::default();
+            return;
+        }
+
+        let mut i = 0;
+        while i < params.len() {
+            match params[i] {
+                0 => *self = Self::default(),
+                1 => self.bold = true,
+                2 => self.dim = true,
+                3 => self.italic = true,
+                4 => self.underline = true,
+                9 => self.strikethrough = true,
+                22 => {
+                    self.bold = false;
+                    self.dim = false;
+                }
+                23 => self.italic = false,
+                24 => self.underline = false,
+                29 => self.strikethrough = false,
+                n @ 30..=37 => self.fg = Some(AnsiColor::Basic((n - 30) as u8)),
+                39 => self.fg = None,
+                n @ 40..=47 => self.bg = Some(AnsiColor::Basic((n - 40) as u8)),
+                49 => self.bg = None,
+                n @ 90..=97 => self.fg = Some(AnsiColor::Basic((n - 90 + 8) as u8)),
+                n @ 100..=107 => self.bg = Some(AnsiColor::Basic((n - 100 + 8) as u8)),
+                n @ (38 | 48) => {
+                    let (color, consumed) = parse_extended_color(&params[i + 1..]);
+                    if let Some(color) = color {
+                        if n == 38 {
+                            self.fg = Some(color);
+                        } else {
+                            self.bg = Some(color);
+                        }
+                    }
+                    i += consumed;
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+    }
+}
+
+/// A run of text sharing the same style and hyperlink target
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnsiSegment {
+    pub text: String,
+    pub style: AnsiStyle,
+    pub link: Option<String>,
+}
+
+/// Parse the arguments following a 38/48 SGR code
+/// Returns the color and the number of parameters consumed
+fn parse_extended_color(params: &[u16]) -> (Option<AnsiColor>, usize) {
+    match params.first() {
+        Some(5) if params.len() >= 2 => (Some(palette_256(params[1] as u8)), 2),
+        Some(2) if params.len() >= 4 => (
+            Some(AnsiColor::Rgb(params[1] as u8, params[2] as u8, params[3] as u8)),
+            4,
+        ),
+        _ => (None, params.len()),
+    }
+}
+
+/// Map a 256-color palette index to a color
+pub fn palette_256(index: u8) -> AnsiColor {
+    match index {
+        0..=15 => AnsiColor::Basic(index),
+        16..=231 => {
+            let index = index - 16;
+            let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+            AnsiColor::Rgb(level(index / 36), level((index / 6) % 6), level(index % 6))
+        }
+        _ => {
+            let gray = 8 + (index - 232) * 10;
+            AnsiColor::Rgb(gray, gray, gray)
+        }
+    }
+}
+
+/// Split ANSI-colored text into styled segments
+/// Understands SGR color sequences and OSC 8 hyperlinks; other escape
+/// sequences are dropped
+pub fn parse_segments(input: &str) -> Vec<AnsiSegment> {
+    let mut segments: Vec<AnsiSegment> = Vec::new();
+    let mut style = AnsiStyle::default();
+    let mut link: Option<String> = None;
+    let mut text = String::new();
+    let mut chars = input.chars().peekable();
+
+    let flush = |text: &mut String, segments: &mut Vec<AnsiSegment>, style: AnsiStyle, link: &Option<String>| {
+        if !text.is_empty() {
+            segments.push(AnsiSegment {
+                text: std::mem::take(text),
+                style,
+                link: link.clone(),
+            });
+        }
+    };
+
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            text.push(c);
+            continue;
+        }
+
+        match chars.peek() {
+            Some('[') => {
+                chars.next();
+                // Control Sequence Introducer: collect parameters up to the final byte
+                let mut params = String::new();
+                let mut final_byte = None;
+                for next in chars.by_ref() {
+                    if ('@'..='~').contains(&next) {
+                        final_byte = Some(next);
+                        break;
+                    }
+                    params.push(next);
+                }
+
+                if final_byte == Some('m') {
+                    let codes: Vec<u16> = params
+                        .split(';')
+                        .map(|p| p.parse().unwrap_or(0))
+                        .collect();
+                    let mut new_style = style;
+                    new_style.apply_sgr(&codes);
+                    if new_style != style {
+                        flush(&mut text, &mut segments, style, &link);
+                        style = new_style;
+                    }
+                }
+            }
+            Some(']') => {
+                chars.next();
+                // Operating System Command: terminated by BEL or ST (ESC \)
+                let mut payload = String::new();
+                while let Some(next) = chars.next() {
+                    if next == '\x07' {
+                        break;
+                    }
+                    if next == '\x1b' && chars.peek() == Some(&'\\') {
+                        chars.next();
+                        break;
+                    }
+                    payload.push(next);
+                }
+
+                // OSC 8 hyperlink: "8;params;uri" (empty uri closes the link)
+                if let Some(rest) = payload.strip_prefix("8;") {
+                    let uri = rest.split_once(';').map(|(_, uri)| uri).unwrap_or("");
+                    flush(&mut text, &mut segments, style, &link);
+                    link = if uri.is_empty() { None } else { Some(uri.to_string()) };
+                }
+            }
+            _ => {
+                // Unknown escape, drop the next character with it
+                chars.next();
+            }
+        }
+    }
+
+    flush(&mut text, &mut segments, style, &link);
+    segments
+}
+
+/// Piece of ANSI-colored text, borrowed from it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnsiToken<'a> {
+    /// Visible text
+    Text(&'a str),
+    /// A whole escape sequence: SGR and other CSI sequences, OSC sequences such as OSC 8
+    /// hyperlinks with their terminator, or ESC and the character after it
+    Escape(&'a str),
+}
+
+/// Split text into visible runs and escape sequences without copying it; the tokens put
+/// back together are the input
+pub fn tokens(input: &str) -> impl Iterator<Item = AnsiToken<'_>> {
+    let mut rest = input;
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let length = match rest.find('\x1b') {
+            Some(0) => escape_length(rest),
+            Some(text) => {
+                let (text, after) = rest.split_at(text);
+                rest = after;
+                return Some(AnsiToken::Text(text));
+            }
+            None => rest.len(),
+        };
+        let (token, after) = rest.split_at(length);
+        rest = after;
+        Some(if token.starts_with('\x1b') { AnsiToken::Escape(token) } else { AnsiToken::Text(token) })
+    })
+}
+
+/// Length of the escape sequence at the start of `text`, which starts with ESC
+fn escape_length(text: &str) -> usize {
+    let mut chars = text.char_indices().skip(1);
+    match chars.next() {
+        // Control Sequence Introducer: parameters up to the final byte
+        Some((_, '[')) => chars.find(|(_, c)| ('@'..='~').contains(c)).map_or(text.len(), |(index, _)| index + 1),
+        // Operating System Command: terminated by BEL or ST (ESC \)
+        Some((_, ']')) => {
+            let body = &text[2..];
+            match (body.find('\x07'), body.find("\x1b\\")) {
+                (Some(bel), Some(st)) if st < bel => 2 + st + 2,
+                (Some(bel), _) => 2 + bel + 1,
+                (None, Some(st)) => 2 + st + 2,
+                (None, None) => text.len(),
+            }
+        }
+        Some((index, c)) => index + c.len_utf8(),
+        None => text.len(),
+    }
+}
+
+/// Remove escape sequences, keeping only the visible text
+pub fn strip_ansi(input: &str) -> String {
+    tokens(input)
+        .filter_map(|token| match token {
+            AnsiToken::Text(text) => Some(text),
+            AnsiToken::Escape(_) => None,
+        })
+        .collect()
+}
+
+/// Visible text, borrowed when there are no escape sequences to remove
+pub fn plain(input: &str) -> Cow<'_, str> {
+    if input.contains('\x1b') {
+        Cow::Owned(strip_ansi(input))
+    } else {
+        Cow::Borrowed(input)
+    }
+}
+
+/// Byte range of `input` holding the visible bytes `visible` of its plain text: from the
+/// first of them, after the escape sequences before it, to just past the last of them
+pub fn raw_range(input: &str, visible: Range<usize>) -> Range<usize> {
+    let mut start = None;
+    let mut end = if visible.is_empty() { None } else { Some(input.len()) };
+    let (mut raw, mut seen) = (0, 0);
+    for token in tokens(input) {
+        if let AnsiToken::Text(text) = token {
+            let span = seen..seen + text.len();
+            if start.is_none() && span.contains(&visible.start) {
+                start = Some(raw + visible.start - seen);
+            }
+            if !visible.is_empty() && span.contains(&(visible.end - 1)) {
+                end = Some(raw + visible.end - seen);
+                break;
+            }
+            seen = span.end;
+        }
+        raw += match token {
+            AnsiToken::Text(text) | AnsiToken::Escape(text) => text.len(),
+        };
+    }
+    let start = start.unwrap_or(input.len());
+    start..end.unwrap_or(start).max(start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokens() {
+        let line = "\x1b[96maut-num\x1b[0m: \x1b]8;;https://example.net\x1b\\\x1b[1;91mAS3333\x1b[0m\x1b]8;;\x07";
+        let pieces: Vec<AnsiToken> = tokens(line).collect();
+        assert_eq!(pieces[0], AnsiToken::Escape("\x1b[96m"));
+        assert_eq!(pieces[1], AnsiToken::Text("aut-num"));
+        assert_eq!(pieces[4], AnsiToken::Escape("\x1b]8;;https://example.net\x1b\\"));
+        assert_eq!(pieces.last(), Some(&AnsiToken::Escape("\x1b]8;;\x07")));
+        assert_eq!(pieces.iter().map(|token| match token { AnsiToken::Text(text) | AnsiToken::Escape(text) => *text }).collect::<String>(), line);
+        assert_eq!(strip_ansi(line), "aut-num: AS3333");
+        assert_eq!(strip_ansi("cut \x1b[3"), "cut ");
+
+        // "AS3333" is visible bytes 9..15
+        let range = raw_range(line, 9..15);
+        assert_eq!(&line[range], "AS3333");
+        let colored = "\x1b[32mnetname:\x1b[0m \x1b[1mEX\x1b[0mAMPLE";
+        assert_eq!(&colored[raw_range(colored, 9..16)], "EX\x1b[0mAMPLE");
+        assert_eq!(raw_range("plain", 2..4), 2..4);
+    }
+
+    #[test]
+    fn test_parse_plain_text() {
+        let segments = parse_segments("aut-num: AS3333");
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].text, "aut-num: AS3333");
+        assert!(segments[0].style.is_plain());
+        assert_eq!(segments[0].link, None);
+    }
+
+    #[test]
+    fn test_parse_sgr_colors() {
+        let segments = parse_segments("\x1b[96maut-num\x1b[0m: \x1b[1;91mAS3333\x1b[0m");
+        assert_eq!(segments.len(), 3);
+        assert_eq!(segments[0].style.fg, Some(AnsiColor::Basic(14)));
+        assert_eq!(segments[1].text, ": ");
+        assert!(segments[1].style.is_plain());
+        assert_eq!(segments[2].style.fg, Some(AnsiColor::Basic(9)));
+        assert!(segments[2].style.bold);
+    }
+
+    #[test]
+    fn test_parse_truecolor() {
+        let segments = parse_segments("\x1b[38;2;91;207;250mblue\x1b[0m");
+        assert_eq!(segments[0].style.fg, Some(AnsiColor::Rgb(91, 207, 250)));
+        assert_eq!(segments[0].style.fg.unwrap().to_hex(), "#5bcffa");
+    }
+
+    #[test]
+    fn test_palette_256() {
+        assert_eq!(palette_256(9), AnsiColor::Basic(9));
+        assert_eq!(palette_256(16), AnsiColor::Rgb(0, 0, 0));
+        assert_eq!(palette_256(231), AnsiColor::Rgb(255, 255, 255));
+        assert_eq!(palette_256(232), AnsiColor::Rgb(8, 8, 8));
+    }
+
+    #[test]
+    fn test_parse_osc8_hyperlink() {
+        let segments = parse_segments("aut-num: \x1b]8;;https://example.com\x1b\\AS3333\x1b]8;;\x1b\\");
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[1].text, "AS3333");
+        assert_eq!(segments[1].link.as_deref(), Some("https://example.com"));
+    }
+
+    #[test]
+    fn test_strip_ansi() {
+        assert_eq!(strip_ansi("\x1b[96maut-num\x1b[0m: \x1b[1;91mAS3333\x1b[0m"), "aut-num: AS3333");
+        assert_eq!(strip_ansi("plain"), "plain");
+    }
+
+    #[test]
+    fn test_parse_drops_unknown_sequences() {
+        let segments = parse_segments("a\x1b[2Kb");
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].text, "ab");
+    }
+}
@@ -0,0 +1,274 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use rusqlite::{params, Connection};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::store::{now, Store};
+
+/// Archive layout version, bumped when the row formats change
+const ARCHIVE_FORMAT: u32 = 1;
+const MANIFEST_NAME: &str = "manifest.json";
+const RESPONSES_NAME: &str = "responses.jsonl";
+const JOBS_NAME: &str = "jobs.jsonl";
+const CAPABILITIES_NAME: &str = "capabilities.jsonl";
+
+/// Summary written as `manifest.json` at the start of an archive
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub format: u32,
+    pub exported_at: i64,
+    pub responses: usize,
+    pub job_entries: usize,
+    pub capabilities: usize,
+}
+
+/// Rows added by an import; rows already present are not counted
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    pub responses: usize,
+    pub job_entries: usize,
+    pub capabilities: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ResponseRow {
+    target: String,
+    host: String,
+    port: u16,
+    response: String,
+    fetched_at: i64,
+    /// Missing in archives written before answers were cached by lookup
+    #[serde(default)]
+    lookup: Option<String>,
+    #[serde(default)]
+    server_colored: bool,
+    #[serde(default)]
+    markdown: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JobRow {
+    job: String,
+    target: String,
+    output: Option<String>,
+    error: Option<String>,
+    recorded_at: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CapabilityRow {
+    address: String,
+    capabilities: String,
+    probed_at: i64,
+}
+
+/// Write every table of the store to a `.tar.gz` archive of JSON lines
+pub fn export(store: &Store, path: &Path) -> Result<Manifest> {
+    let connection = store.connection();
+    let responses = rows(
+        &connection,
+        "SELECT target, host, port, response, fetched_at, lookup, server_colored, markdown FROM responses ORDER BY fetched_at",
+        |row| {
+            Ok(ResponseRow {
+                target: row.get(0)?,
+                host: row.get(1)?,
+                port: row.get(2)?,
+                response: row.get(3)?,
+                fetched_at: row.get(4)?,
+                lookup: row.get(5)?,
+                server_colored: row.get(6)?,
+                markdown: row.get(7)?,
+            })
+        },
+    )?;
+    let jobs = rows(&connection, "SELECT job, target, output, error, recorded_at FROM jobs ORDER BY recorded_at", |row| {
+        Ok(JobRow { job: row.get(0)?, target: row.get(1)?, output: row.get(2)?, error: row.get(3)?, recorded_at: row.get(4)? })
+    })?;
+    let capabilities = rows(&connection, "SELECT address, capabilities, probed_at FROM capabilities", |row| {
+        Ok(CapabilityRow { address: row.get(0)?, capabilities: row.get(1)?, probed_at: row.get(2)? })
+    })?;
+    drop(connection);
+
+    let manifest = Manifest {
+        format: ARCHIVE_FORMAT,
+        exported_at: now(),
+        responses: responses.len(),
+        job_entries: jobs.len(),
+        capabilities: capabilities.len(),
+    };
+    let file = File::create(path).with_context(|| format!("Failed to create archive {}", path.display()))?;
+    let mut archive = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+    append(&mut archive, MANIFEST_NAME, serde_json::to_vec_pretty(&manifest)?)?;
+    append(&mut archive, RESPONSES_NAME, json_lines(&responses)?)?;
+    append(&mut archive, JOBS_NAME, json_lines(&jobs)?)?;
+    append(&mut archive, CAPABILITIES_NAME, json_lines(&capabilities)?)?;
+    archive
+        .into_inner()
+        .and_then(|encoder| encoder.finish())
+        .with_context(|| format!("Failed to write archive {}", path.display()))?;
+    Ok(manifest)
+}
+
+/// Merge an archive into the store, skipping rows it already holds
+/// Capabilities are only replaced by newer probes
+pub fn import(store: &Store, path: &Path) -> Result<ImportSummary> {
+    let file = File::open(path).with_context(|| format!("Failed to open archive {}", path.display()))?;
+    let mut archive = tar::Archive::new(GzDecoder::new(file));
+    let mut connection = store.connection();
+    let transaction = connection.transaction().context("Failed to start import")?;
+    let mut summary = ImportSummary::default();
+    let mut manifest: Option<Manifest> = None;
+
+    for entry in archive.entries().context("Failed to read archive")? {
+        let mut entry = entry.context("Failed to read archive entry")?;
+        let name = entry.path()?.to_string_lossy().to_string();
+        match name.as_str() {
+            MANIFEST_NAME => {
+                let mut contents = Vec::new();
+                entry.read_to_end(&mut contents)?;
+                let found: Manifest = serde_json::from_slice(&contents).context("Invalid archive manifest")?;
+                if found.format > ARCHIVE_FORMAT {
+                    anyhow::bail!("Archive format {} is newer than this version supports ({})", found.format, ARCHIVE_FORMAT);
+                }
+                manifest = Some(found);
+            }
+            RESPONSES_NAME => {
+                for row in read_lines::<ResponseRow>(&mut entry)? {
+                    summary.responses += transaction.execute(
+                        "INSERT INTO responses (target, host, port, response, fetched_at, lookup, server_colored, markdown)
+                         SELECT ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8 WHERE NOT EXISTS (
+                             SELECT 1 FROM responses WHERE target = ?1 AND host = ?2 AND port = ?3 AND fetched_at = ?5 AND response = ?4)",
+                        params![row.target, row.host, row.port, row.response, row.fetched_at, row.lookup, row.server_colored, row.markdown],
+                    )?;
+                }
+            }
+            JOBS_NAME => {
+                for row in read_lines::<JobRow>(&mut entry)? {
+                    summary.job_entries += transaction.execute(
+                        "INSERT INTO jobs (job, target, output, error, recorded_at)
+                         SELECT ?1, ?2, ?3, ?4, ?5 WHERE NOT EXISTS (
+                             SELECT 1 FROM jobs WHERE job = ?1 AND target = ?2 AND recorded_at = ?5)",
+                        params![row.job, row.target, row.output, row.error, row.recorded_at],
+                    )?;
+                }
+            }
+            CAPABILITIES_NAME => {
+                for row in read_lines::<CapabilityRow>(&mut entry)? {
+                    summary.capabilities += transaction.execute(
+                        "INSERT INTO capabilities (address, capabilities, probed_at) VALUES (?1, ?2, ?3)
+                         ON CONFLICT (address) DO UPDATE SET capabilities = excluded.capabilities, probed_at = excluded.probed_at
+                         WHERE excluded.probed_at > capabilities.probed_at",
+                        params![row.address, row.capabilities, row.probed_at],
+                    )?;
+                }
+            }
+            _ => {}
+        }
+    }
+    if manifest.is_none() {
+        anyhow::bail!("{} is not a whois cache archive (no {})", path.display(), MANIFEST_NAME);
+    }
+    // Archives carry answers only; their fingerprints are computed here
+    crate::store::fill_fingerprints(&transaction)?;
+    transaction.commit().context("Failed to commit import")?;
+    Ok(summary)
+}
+
+fn rows<T>(connection: &Connection, sql: &str, map: impl FnMut(&rusqlite::Row<'_>) -> rusqlite::Result<T>) -> Result<Vec<T>> {
+    let mut statement = connection.prepare(sql)?;
+    let rows = statement.query_map([], map)?.collect::<rusqlite::Result<Vec<T>>>()?;
+    Ok(rows)
+}
+
+fn json_lines<T: Serialize>(rows: &[T]) -> Result<Vec<u8>> {
+    let mut output = Vec::new();
+    for row in rows {
+        serde_json::to_writer(&mut output, row)?;
+        output.push(b'\n');
+    }
+    Ok(output)
+}
+
+fn read_lines<T: DeserializeOwned>(reader: impl Read) -> Result<Vec<T>> {
+    BufReader::new(reader)
+        .lines()
+        .filter(|line| !matches!(line, Ok(line) if line.trim().is_empty()))
+        .map(|line| serde_json::from_str(&line?).context("Invalid archive row"))
+        .collect()
+}
+
+fn append(archive: &mut tar::Builder<GzEncoder<File>>, name: &str, contents: Vec<u8>) -> Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(contents.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(now().max(0) as u64);
+    header.set_cksum();
+    archive
+        .append_data(&mut header, name, contents.as_slice())
+        .with_context(|| format!("Failed to add {} to archive", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{CapabilityCache, ServerCapabilities};
+    use crate::store::StoredAnswer;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("whois-archive-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_export_import_roundtrip() {
+        let source = Store::open(&temp_path("source.sqlite")).unwrap();
+        let answer = StoredAnswer {
+            host: "whois.ripe.net".to_string(),
+            port: 43,
+            response: "aut-num: AS3333\n".to_string(),
+            server_colored: false,
+            markdown: None,
+        };
+        source.record_response("AS3333", "-h whois.ripe.net:43 AS3333", &answer).unwrap();
+        source.record_job("run.jsonl", "AS3333", Some("ok"), None).unwrap();
+        source.put("whois.ripe.net:43", &ServerCapabilities::default());
+
+        let archive = temp_path("case.tar.gz");
+        let manifest = export(&source, &archive).unwrap();
+        assert_eq!((manifest.responses, manifest.job_entries, manifest.capabilities), (1, 1, 1));
+
+        let target = Store::open(&temp_path("target.sqlite")).unwrap();
+        let summary = import(&target, &archive).unwrap();
+        assert_eq!(summary, ImportSummary { responses: 1, job_entries: 1, capabilities: 1 });
+        let (cached, _) = target.cached_response("-h whois.ripe.net:43 AS3333", std::time::Duration::from_secs(60)).unwrap().unwrap();
+        assert_eq!(cached, answer);
+        // Importing twice adds nothing
+        assert_eq!(import(&target, &archive).unwrap(), ImportSummary::default());
+
+        for path in [source.path(), target.path(), archive.as_path()] {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    #[test]
+    fn test_import_rejects_foreign_archive() {
+        let archive = temp_path("foreign.tar.gz");
+        let file = File::create(&archive).unwrap();
+        let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+        append(&mut builder, "notes.txt", b"hello".to_vec()).unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+
+        let store = Store::open(&temp_path("foreign.sqlite")).unwrap();
+        assert!(import(&store, &archive).is_err());
+        let _ = std::fs::remove_file(store.path());
+        let _ = std::fs::remove_file(&archive);
+    }
+}
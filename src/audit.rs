@@ -0,0 +1,145 @@
+use anyhow::Result;
+
+use crate::query::WhoisQuery;
+use crate::rpsl::{self, Block, RpslObject};
+use crate::servers::WhoisServer;
+use crate::tr;
+
+/// `auth:` schemes that should be replaced, with the reason shown in the audit
+const WEAK_AUTH_SCHEMES: &[(&str, &str)] = &[
+    ("NONE", "no authentication at all"),
+    ("MAIL-FROM", "trusts the sender address of update emails"),
+    ("CRYPT-PW", "DES crypt password, removed from most databases"),
+    ("MD5-PW", "password hash, prefer PGP/X.509 keys or SSO"),
+];
+
+/// A maintainer `auth:` line judged weak
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthFinding {
+    /// The `auth:` value as published (hashes are usually filtered)
+    pub value: String,
+    pub reason: &'static str,
+}
+
+/// Objects protected by a maintainer, with a review of its authentication
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MaintainerAudit {
+    pub maintainer: String,
+    /// Number of `auth:` lines on the maintainer
+    pub auth_count: usize,
+    pub weak_auth: Vec<AuthFinding>,
+    /// (class, key) of every object with `mnt-by:` the maintainer
+    pub objects: Vec<(String, String)>,
+}
+
+impl MaintainerAudit {
+    /// Audit a maintainer object and the answer to its inverse `mnt-by` query
+    pub fn new(mntner: &RpslObject, protected: &str) -> Self {
+        let auth: Vec<&str> = mntner.values("auth").collect();
+        let weak_auth = auth
+            .iter()
+            .filter_map(|value| {
+                let scheme = value.split_whitespace().next().unwrap_or_default().to_uppercase();
+                let (_, reason) = WEAK_AUTH_SCHEMES.iter().find(|(weak, _)| scheme == *weak)?;
+                Some(AuthFinding {
+                    value: value.to_string(),
+                    reason,
+                })
+            })
+            .collect();
+        let objects = rpsl::parse(protected)
+            .into_iter()
+            .filter_map(|block| match block {
+                Block::Object(object) => Some((object.class().to_lowercase(), object.key().to_string())),
+                Block::Text(_) => None,
+            })
+            .collect();
+        Self {
+            maintainer: mntner.key().to_string(),
+            auth_count: auth.len(),
+            weak_auth,
+            objects,
+        }
+    }
+
+    /// Object count per class, most common first
+    pub fn counts(&self) -> Vec<(&str, usize)> {
+        let mut counts: Vec<(&str, usize)> = Vec::new();
+        for (class, _) in &self.objects {
+            match counts.iter_mut().find(|(known, _)| known == class) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((class, 1)),
+            }
+        }
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        counts
+    }
+
+    /// Audit report as `%` comment lines
+    pub fn render(&self) -> String {
+        let mut lines = vec![format!("% ─── {} ───", tr!("audit-heading", maintainer = self.maintainer))];
+        if self.weak_auth.is_empty() {
+            lines.push(format!("% {}", tr!("audit-auth-ok", count = self.auth_count)));
+        } else {
+            lines.push(format!("% {}", tr!("audit-auth-flagged", flagged = self.weak_auth.len(), count = self.auth_count)));
+            lines.extend(
+                self.weak_auth
+                    .iter()
+                    .map(|finding| format!("%   WEAK  {:<28} {}", finding.value, finding.reason)),
+            );
+        }
+
+        lines.push(format!("% {}", tr!("audit-protected", count = self.objects.len())));
+        lines.extend(self.counts().iter().map(|(class, count)| format!("%   {:<14} {}", class, count)));
+        lines.push("%".to_string());
+        lines.extend(self.objects.iter().map(|(class, key)| format!("% {:<14} {}", class, key)));
+        lines.join("\n")
+    }
+}
+
+/// Fetch a maintainer and everything it protects with RIPE-style queries
+pub fn audit(query: &WhoisQuery, server: &WhoisServer, maintainer: &str) -> Result<MaintainerAudit> {
+    let response = query.query_direct(&format!("-r -T mntner {}", maintainer), server)?;
+    let mntner = rpsl::parse(&response).into_iter().find_map(|block| match block {
+        Block::Object(object) if object.class().eq_ignore_ascii_case("mntner") => Some(object),
+        _ => None,
+    });
+    let Some(mntner) = mntner else {
+        anyhow::bail!("Maintainer {} not found on {}", maintainer, server.host);
+    };
+    let protected = query.query_direct(&format!("-r -i mnt-by {}", maintainer), server)?;
+    Ok(MaintainerAudit::new(&mntner, &protected))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mntner(text: &str) -> RpslObject {
+        match rpsl::parse(text).remove(0) {
+            Block::Object(object) => object,
+            Block::Text(_) => panic!("expected an object"),
+        }
+    }
+
+    #[test]
+    fn test_weak_auth_flagged() {
+        let mntner = mntner("mntner: FOO-MNT\nauth: MD5-PW # Filtered\nauth: PGPKEY-1290F9D2\nauth: SSO # Filtered\nsource: RIPE\n");
+        let audit = MaintainerAudit::new(&mntner, "");
+        assert_eq!(audit.auth_count, 3);
+        assert_eq!(audit.weak_auth.len(), 1);
+        assert_eq!(audit.weak_auth[0].value, "MD5-PW # Filtered");
+        assert!(audit.render().contains("% Authentication: 1 of 3 auth line(s) flagged"));
+    }
+
+    #[test]
+    fn test_protected_object_counts() {
+        let protected = "% Information related to 'FOO-MNT'\n\nroute: 192.0.2.0/24\norigin: AS64500\n\nroute: 198.51.100.0/24\norigin: AS64500\n\naut-num: AS64500\n";
+        let audit = MaintainerAudit::new(&mntner("mntner: FOO-MNT\nauth: SSO # Filtered\n"), protected);
+        assert_eq!(audit.counts(), vec![("route", 2), ("aut-num", 1)]);
+        let report = audit.render();
+        assert!(report.contains("none flagged"));
+        assert!(report.contains("% Protected objects: 3"));
+        assert!(report.ends_with("% aut-num        AS64500"));
+    }
+}
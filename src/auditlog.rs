@@ -0,0 +1,148 @@
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::classify::Classification;
+use crate::dates;
+use crate::query::QueryResult;
+
+/// Outcome of a lookup as recorded in the audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AuditStatus {
+    Answer,
+    Empty,
+    RateLimited,
+    /// Cut off by a deadline or Ctrl-C
+    Partial,
+    /// Served from the `--cache` database without a query
+    Cached,
+    Error,
+}
+
+/// One line of the audit log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// RFC 3339 UTC time the lookup finished
+    pub timestamp: String,
+    pub target: String,
+    /// Servers queried, in referral order; the last one gave the answer
+    pub servers: Vec<String>,
+    /// Size of the answer as received
+    pub bytes: usize,
+    pub duration_ms: u64,
+    pub status: AuditStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl AuditEntry {
+    /// Entry for a finished lookup; `cached` marks answers taken from the cache
+    pub fn new(target: &str, lookup: &Result<QueryResult>, duration: Duration, cached: bool) -> Self {
+        let mut entry = Self {
+            timestamp: dates::rfc3339(SystemTime::now()),
+            target: target.to_string(),
+            servers: Vec::new(),
+            bytes: 0,
+            duration_ms: duration.as_millis() as u64,
+            status: AuditStatus::Error,
+            error: None,
+        };
+        match lookup {
+            Ok(result) => {
+                entry.servers = result.timing.hops.iter().map(|hop| hop.server.clone()).collect();
+                let answered_by = result.server_used.address();
+                if entry.servers.last() != Some(&answered_by) {
+                    entry.servers.push(answered_by);
+                }
+                entry.bytes = result.response.len();
+                entry.status = if cached {
+                    AuditStatus::Cached
+                } else if result.partial {
+                    AuditStatus::Partial
+                } else {
+                    match result.classification {
+                        Classification::Answer => AuditStatus::Answer,
+                        Classification::Empty => AuditStatus::Empty,
+                        Classification::RateLimited => AuditStatus::RateLimited,
+                    }
+                };
+            }
+            Err(err) => entry.error = Some(format!("{:#}", err)),
+        }
+        entry
+    }
+}
+
+/// Append-only record of every lookup, one JSON line each, for `--audit-log`
+/// Lines are flushed as lookups finish so the log survives a killed run
+pub struct AuditLog {
+    file: Mutex<File>,
+}
+
+impl AuditLog {
+    /// Open the log for appending, creating it if needed
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open audit log {}", path.display()))?;
+        Ok(Self { file: Mutex::new(file) })
+    }
+
+    /// Append an entry
+    pub fn record(&self, entry: &AuditEntry) -> Result<()> {
+        let mut line = serde_json::to_string(entry).context("Failed to serialize audit entry")?;
+        line.push('\n');
+
+        // One write per line keeps entries from parallel lookups whole
+        let mut file = self.file.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        file.write_all(line.as_bytes())
+            .and_then(|_| file.flush())
+            .context("Failed to write audit log")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::servers::WhoisServer;
+    use crate::timing::HopTiming;
+
+    #[test]
+    fn test_entries() {
+        let mut result = QueryResult::new("aut-num: AS3333\n".to_string(), WhoisServer::custom("whois.ripe.net", 43));
+        for server in ["whois.iana.org:43", "whois.ripe.net:43"] {
+            result.timing.hops.push(HopTiming { server: server.to_string(), ..Default::default() });
+        }
+        let entry = AuditEntry::new("AS3333", &Ok(result), Duration::from_millis(120), false);
+        assert_eq!(entry.servers, ["whois.iana.org:43", "whois.ripe.net:43"]);
+        assert_eq!((entry.bytes, entry.duration_ms, entry.status), (16, 120, AuditStatus::Answer));
+
+        let failed = AuditEntry::new("AS0", &Err(anyhow::anyhow!("timed out")), Duration::ZERO, false);
+        assert_eq!(failed.status, AuditStatus::Error);
+        let line = serde_json::to_string(&failed).unwrap();
+        assert!(line.contains(r#""servers":[],"bytes":0,"duration_ms":0,"status":"error","error":"timed out"}"#));
+    }
+
+    #[test]
+    fn test_log_appends() {
+        let path = std::env::temp_dir().join(format!("whois-audit-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        for _ in 0..2 {
+            let log = AuditLog::open(&path).unwrap();
+            log.record(&AuditEntry::new("AS0", &Err(anyhow::anyhow!("refused")), Duration::ZERO, false)).unwrap();
+        }
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let entries: Vec<AuditEntry> = contents.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].error.as_deref(), Some("refused"));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
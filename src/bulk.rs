@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::{Mutex, MutexGuard};
+use std::thread;
+
+use anyhow::Result;
+
+/// Result of querying a single target in a multi-target run
+#[derive(Debug)]
+pub struct BulkOutcome<T> {
+    pub target: String,
+    pub result: Result<T>,
+}
+
+/// Worker count used for `--parallel` when no explicit concurrency is given
+pub const DEFAULT_CONCURRENCY: usize = 8;
+
+/// Runs a query function over several targets and aggregates the results
+/// Outcomes are always returned in the order the targets were given
+pub struct BulkRunner {
+    parallel: bool,
+    concurrency: usize,
+}
+
+/// Targets not yet started, plus how many are in flight per server key
+struct Schedule {
+    pending: Vec<usize>,
+    in_flight: HashMap<String, usize>,
+}
+
+impl Schedule {
+    /// Pick the next target, preferring servers with nothing in flight
+    fn next(&mut self, keys: &[String]) -> Option<usize> {
+        if self.pending.is_empty() {
+            return None;
+        }
+        let position = self
+            .pending
+            .iter()
+            .position(|&index| !self.in_flight.contains_key(&keys[index]))
+            .unwrap_or(0);
+        let index = self.pending.remove(position);
+        *self.in_flight.entry(keys[index].clone()).or_default() += 1;
+        Some(index)
+    }
+
+    fn done(&mut self, key: &str) {
+        if let Some(count) = self.in_flight.get_mut(key) {
+            *count -= 1;
+            if *count == 0 {
+                self.in_flight.remove(key);
+            }
+        }
+    }
+}
+
+impl BulkRunner {
+    pub fn new(parallel: bool) -> Self {
+        Self {
+            parallel,
+            concurrency: DEFAULT_CONCURRENCY,
+        }
+    }
+
+    /// Limit the number of targets queried at once in parallel mode
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Query every target, sequentially or in parallel
+    pub fn run<T, F>(&self, targets: &[String], query: F) -> Vec<BulkOutcome<T>>
+    where
+        T: Send,
+        F: Fn(&str) -> Result<T> + Sync,
+    {
+        self.run_keyed(targets, |target| target.to_string(), query)
+    }
+
+    /// Query every target, spreading work across servers
+    /// `key` maps a target to the server it will be sent to; in parallel mode workers
+    /// prefer targets whose server has no query in flight, so one busy server does not
+    /// hold up the whole run
+    pub fn run_keyed<T, K, F>(&self, targets: &[String], key: K, query: F) -> Vec<BulkOutcome<T>>
+    where
+        T: Send,
+        K: Fn(&str) -> String,
+        F: Fn(&str) -> Result<T> + Sync,
+    {
+        if !self.parallel || self.concurrency < 2 || targets.len() < 2 {
+            return targets
+                .iter()
+                .map(|target| BulkOutcome {
+                    target: target.clone(),
+                    result: query(target),
+                })
+                .collect();
+        }
+
+        let keys: Vec<String> = targets.iter().map(|target| key(target)).collect();
+        let schedule = Mutex::new(Schedule {
+            pending: (0..targets.len()).collect(),
+            in_flight: HashMap::new(),
+        });
+        let results: Mutex<Vec<Option<Result<T>>>> =
+            Mutex::new(targets.iter().map(|_| None).collect());
+
+        let workers = self.concurrency.min(targets.len());
+        let (query, keys, schedule, results) = (&query, &keys, &schedule, &results);
+        thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(move || loop {
+                    let next = lock(schedule).next(keys);
+                    let Some(index) = next else { break };
+                    let result = catch_unwind(AssertUnwindSafe(|| query(&targets[index])))
+                        .unwrap_or_else(|_| Err(anyhow::anyhow!("Query thread panicked")));
+                    lock(results)[index] = Some(result);
+                    lock(schedule).done(&keys[index]);
+                });
+            }
+        });
+
+        let results = results.lock().map(|mut results| std::mem::take(&mut *results)).unwrap_or_default();
+        targets
+            .iter()
+            .zip(results)
+            .map(|(target, result)| BulkOutcome {
+                target: target.clone(),
+                result: result.unwrap_or_else(|| Err(anyhow::anyhow!("Query was not run"))),
+            })
+            .collect()
+    }
+}
+
+/// Lock a mutex, recovering the data if a worker panicked while holding it
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn targets() -> Vec<String> {
+        vec!["example.com".to_string(), "AS3333".to_string(), "fail".to_string()]
+    }
+
+    fn fake_query(target: &str) -> Result<String> {
+        if target == "fail" {
+            anyhow::bail!("lookup failed");
+        }
+        Ok(target.to_lowercase())
+    }
+
+    #[test]
+    fn test_sequential_preserves_order() {
+        let outcomes = BulkRunner::new(false).run(&targets(), fake_query);
+        let names: Vec<&str> = outcomes.iter().map(|o| o.target.as_str()).collect();
+        assert_eq!(names, vec!["example.com", "AS3333", "fail"]);
+        assert_eq!(outcomes[1].result.as_ref().unwrap(), "as3333");
+        assert!(outcomes[2].result.is_err());
+    }
+
+    #[test]
+    fn test_parallel_preserves_order() {
+        let outcomes = BulkRunner::new(true).run(&targets(), fake_query);
+        let names: Vec<&str> = outcomes.iter().map(|o| o.target.as_str()).collect();
+        assert_eq!(names, vec!["example.com", "AS3333", "fail"]);
+        assert_eq!(outcomes[0].result.as_ref().unwrap(), "example.com");
+        assert!(outcomes[2].result.is_err());
+    }
+
+    #[test]
+    fn test_concurrency_limit() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::time::Duration;
+
+        let running = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+        let targets: Vec<String> = (0..8).map(|i| format!("AS{}", i)).collect();
+        let outcomes = BulkRunner::new(true).with_concurrency(2).run(&targets, |target| {
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(now, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(10));
+            running.fetch_sub(1, Ordering::SeqCst);
+            Ok(target.to_string())
+        });
+
+        assert_eq!(outcomes.len(), 8);
+        assert_eq!(outcomes[5].result.as_ref().unwrap(), "AS5");
+        assert!(peak.load(Ordering::SeqCst) <= 2);
+    }
+
+    #[test]
+    fn test_schedule_prefers_idle_servers() {
+        let keys = vec!["ripe".to_string(), "ripe".to_string(), "arin".to_string()];
+        let mut schedule = Schedule {
+            pending: vec![0, 1, 2],
+            in_flight: HashMap::new(),
+        };
+        assert_eq!(schedule.next(&keys), Some(0));
+        // RIPE is busy, so the ARIN target goes next
+        assert_eq!(schedule.next(&keys), Some(2));
+        schedule.done("ripe");
+        assert_eq!(schedule.next(&keys), Some(1));
+        assert_eq!(schedule.next(&keys), None);
+    }
+
+    #[test]
+    fn test_empty_targets() {
+        let outcomes = BulkRunner::new(true).run(&[], fake_query);
+        assert!(outcomes.is_empty());
+    }
+}
@@ -0,0 +1,172 @@
+use serde::{Deserialize, Serialize};
+
+/// What a WHOIS response turned out to contain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Classification {
+    /// The response holds actual data
+    Answer,
+    /// The server answered but had no matching objects
+    Empty,
+    /// The server refused the query because of rate limiting
+    RateLimited,
+}
+
+/// Heuristics deciding when a response counts as empty
+/// Built-in values are used for any field the config leaves out
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct EmptyResultPolicy {
+    /// Case-insensitive phrases that mark a response as empty
+    pub indicators: Vec<String>,
+    /// Responses shorter than this many characters are candidates for the short-response rule
+    pub short_response_length: usize,
+    /// A short response is empty if its non-comment content is shorter than this
+    pub short_content_length: usize,
+    /// Treat responses made only of `%`/`#` comment lines as empty
+    pub comments_only_is_empty: bool,
+}
+
+impl Default for EmptyResultPolicy {
+    fn default() -> Self {
+        Self {
+            indicators: [
+                "no found",
+                "no match",
+                "not found",
+                "no data found",
+                "no entries found",
+                "no records found",
+                "no such domain",
+                "no whois server is known",
+                "object does not exist",
+                "%error: no objects found",
+                "% no objects found",
+            ]
+            .iter()
+            .map(|indicator| indicator.to_string())
+            .collect(),
+            short_response_length: 30,
+            short_content_length: 10,
+            comments_only_is_empty: true,
+        }
+    }
+}
+
+impl EmptyResultPolicy {
+    /// Check if a WHOIS response is effectively empty or indicates no results
+    pub fn is_empty(&self, response: &str) -> bool {
+        let response = response.trim();
+
+        // Obviously empty
+        if response.is_empty() {
+            return true;
+        }
+
+        // Configured empty response indicators (case-insensitive)
+        let response_lower = response.to_lowercase();
+        if self
+            .indicators
+            .iter()
+            .any(|indicator| response_lower.contains(&indicator.to_lowercase()))
+        {
+            return true;
+        }
+
+        // Lines that are not comments (lines starting with % or #)
+        let content_lines: Vec<&str> = response
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .filter(|line| !line.starts_with('%') && !line.starts_with('#'))
+            .collect();
+
+        if content_lines.is_empty() {
+            return self.comments_only_is_empty;
+        }
+
+        // Very short responses with minimal content are likely just headers/boilerplate
+        response.len() < self.short_response_length
+            && content_lines.join(" ").len() < self.short_content_length
+    }
+}
+
+/// Check if a WHOIS response is a rate-limit refusal rather than an answer
+pub fn is_rate_limited(response: &str) -> bool {
+    let response_lower = response.to_lowercase();
+    let indicators = [
+        "rate limit exceeded",
+        "query rate limit",
+        "too many queries",
+        "too many requests",
+        "exceeded the maximum allowable number",
+        "queries from your ip address have passed the daily limit",
+        "%err-201",
+        "%error:201: access denied",
+    ];
+    indicators.iter().any(|indicator| response_lower.contains(indicator))
+}
+
+/// Classify a response using the given empty-result policy
+/// Server colors are ignored, so colored comment lines still count as comments
+pub fn classify_response(response: &str, policy: &EmptyResultPolicy) -> Classification {
+    let response = &*crate::ansi::plain(response);
+    if is_rate_limited(response) {
+        Classification::RateLimited
+    } else if policy.is_empty(response) {
+        Classification::Empty
+    } else {
+        Classification::Answer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_rate_limited() {
+        assert!(is_rate_limited("%ERROR:201: access denied for 192.0.2.1"));
+        assert!(is_rate_limited("Query rate limit exceeded. Please try again later."));
+        assert!(!is_rate_limited("aut-num: AS3333\nas-name: RIPE-NCC-AS"));
+    }
+
+    #[test]
+    fn test_terse_registry_policy() {
+        // A terse ccTLD answer trips the default short-response rule
+        let response = "% nic.example\nactive";
+        assert!(EmptyResultPolicy::default().is_empty(response));
+
+        let terse = EmptyResultPolicy {
+            short_response_length: 0,
+            ..Default::default()
+        };
+        assert!(!terse.is_empty(response));
+    }
+
+    #[test]
+    fn test_custom_indicators() {
+        let policy = EmptyResultPolicy {
+            indicators: vec!["Domain Status: free".to_string()],
+            ..Default::default()
+        };
+        assert!(policy.is_empty("Domain Status: FREE\nsome more text here to be long"));
+        // Default indicators are replaced, not extended
+        assert!(!policy.is_empty("No match for the query, but long enough text"));
+    }
+
+    #[test]
+    fn test_classify_response() {
+        let policy = EmptyResultPolicy::default();
+        assert_eq!(classify_response("% No entries found", &policy), Classification::Empty);
+        assert_eq!(classify_response("Too many queries from your IP", &policy), Classification::RateLimited);
+        assert_eq!(
+            classify_response("aut-num: AS3333\nas-name: RIPE-NCC-AS\n", &policy),
+            Classification::Answer
+        );
+        assert_eq!(
+            classify_response("\x1b[90m% This is the RIPE Database query service.\x1b[0m\n\x1b[90m% See https://apps.db.ripe.net/docs\x1b[0m\n", &policy),
+            Classification::Empty
+        );
+    }
+}
@@ -1,14 +1,114 @@
-use clap::Parser;
+use std::io::IsTerminal;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::{ArgAction, Parser, ValueEnum};
+use tracing::level_filters::LevelFilter;
+
+use crate::colorize::Theme;
+use crate::deadline::parse_duration;
+use crate::graphics::ImageProtocol;
+use crate::i18n::Locale;
+use crate::rules::CleanupLevel;
+use crate::servers::{Language, RecordType, WhoisServer};
+use crate::source::Source;
+
+/// Output format for query results
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Colorized terminal output
+    Text,
+    /// Standalone HTML document with embedded styling
+    Html,
+    /// Machine-readable JSON with the raw response, classification and timings
+    Json,
+    /// SVG image of the colored output (requires the `render` feature)
+    Svg,
+    /// PNG image of the colored output (requires the `render-png` feature)
+    Png,
+    /// Comma-separated values (with `whois audit`)
+    Csv,
+}
+
+/// Commands written as leading words before the targets (`whois history AS3333`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// List the versions of a RIPE database object, or with `--remote` the archived records of a domain
+    History { key: String },
+    /// Show one version of an object and what changed since the previous one
+    ShowVersion { revision: u32, key: String },
+    /// Look up an organisation, and with `--resources` everything it holds
+    Org { handle: String },
+    /// Look up a maintainer, and with `--audit` review it and everything it protects
+    Mnt { handle: String },
+    /// Show what the SQLite cache holds
+    CacheStats,
+    /// Empty the SQLite cache, or only entries older than the given age
+    CachePurge { older_than: Option<Duration> },
+    /// Write the SQLite cache to a `.tar.gz` archive
+    CacheExport { file: PathBuf },
+    /// Merge a `.tar.gz` archive into the SQLite cache
+    CacheImport { file: PathBuf },
+    /// Check a local RPSL object file against the built-in class schemas
+    LintFile { file: PathBuf },
+    /// Print an empty object of a class to fill in, RIPE or with `--42` DN42 flavored
+    Template { class: String },
+    /// Show IANA's delegation record of a top-level domain
+    Tld { tld: String },
+    /// Store a secret in the system keyring, read from a prompt or stdin
+    AuthSet { account: String },
+    /// Delete a secret from the system keyring
+    AuthRemove { account: String },
+    /// Print the version, with build details when `verbose`
+    Version { verbose: bool },
+    /// Interactive prompt running query after query in one session
+    Repl,
+    /// Query several targets and report the owners' details they share
+    Correlate { targets: Vec<String> },
+    /// Report registrar, expiry, transfer lock, name servers and DNSSEC of domains
+    /// given inline or with `--file`
+    DomainAudit { domains: Vec<String> },
+    /// Query one object on every `--servers` server and show how the answers differ
+    Compare { target: String },
+    /// Report how far IRR mirrors lag behind the authoritative serial of each source
+    IrrStatus { sources: Vec<String> },
+    /// List the prefixes an AS, or every AS of an as-set, originates in the IRR
+    PrefixList { target: String },
+    /// List the members of an as-set or route-set, nested sets resolved
+    AsSet { name: String },
+    /// Show the newest cached answer for a target from the given day (days since 1970-01-01) or earlier
+    Show { target: String, as_of: i64 },
+    /// Run the WHOIS-COLOR conformance checks against a server, looking up `query` where one is needed
+    ProbeServer { server: String, query: String },
+}
 
 #[derive(Parser)]
 #[command(
     author = "Pysio",
     version = env!("CARGO_PKG_VERSION"),
-    about = "A simple WHOIS query tool with advanced features"
+    about = "A simple WHOIS query tool with advanced features",
+    disable_version_flag = true,
+    args_override_self = true
 )]
 pub struct Cli {
-    /// Domain name or IP address to query
-    pub domain: String,
+    /// Domain names, IP addresses or ASNs to query, or a command:
+    /// `history KEY`, `show-version N KEY`, `org HANDLE`, `mnt HANDLE`,
+    /// `cache stats`, `cache purge [AGE]`, `cache export FILE`, `cache import FILE`, `lint-file FILE`, `template CLASS`, `tld TLD`,
+    /// `auth set ACCOUNT`, `auth remove ACCOUNT`, `version`, `repl`, `correlate TARGET TARGET...`,
+    /// `audit [DOMAIN...] [--file FILE]`, `compare TARGET --servers LIST`,
+    /// `irr-status [SOURCE...] [--servers LIST]`, `prefix-list ASN|AS-SET`, `as-set NAME`,
+    /// `show TARGET --as-of DATE`, `probe-server HOST[:PORT] [QUERY]`
+    #[arg(required_unless_present = "version")]
+    pub domain: Vec<String>,
+
+    /// Apply the flags of a `[preset.NAME]` table from the config; flags given here take precedence
+    #[arg(long, value_name = "NAME")]
+    pub preset: Option<String>,
+
+    /// Print version; with --verbose also features, target, git commit and protocol versions
+    #[arg(short = 'V', long)]
+    pub version: bool,
 
     /// WHOIS server to use (bypasses IANA lookup)
     #[arg(short, long)]
@@ -18,13 +118,67 @@ pub struct Cli {
     #[arg(short, long, default_value_t = 43)]
     pub port: u16,
 
-    /// Display verbose output
-    #[arg(short, long)]
-    pub verbose: bool,
+    /// Make WHOIS connections from this local address, e.g. a DN42 tunnel address
+    #[arg(long, value_name = "ADDR", conflicts_with = "interface")]
+    pub source_ip: Option<IpAddr>,
+
+    /// Make WHOIS connections from the address of this network interface
+    #[arg(long, value_name = "NAME")]
+    pub interface: Option<String>,
+
+    /// Resolve WHOIS server names over this DNS-over-HTTPS JSON endpoint when the system resolver fails;
+    /// also used by `--mail-check`
+    #[arg(long, value_name = "URL")]
+    pub doh: Option<String>,
+
+    /// Increase diagnostic output on stderr (-v, -vv, -vvv)
+    #[arg(short, long, action = ArgAction::Count)]
+    pub verbose: u8,
+
+    /// Only print errors; suppresses warnings and progress indicators
+    #[arg(short, long, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// Read configuration from FILE instead of the default location
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Write JSON traces of every connection, probe and referral to FILE
+    #[arg(long, value_name = "FILE")]
+    pub trace_file: Option<PathBuf>,
+
+    /// Print per-phase timings (DNS, connect, write, first byte) for every server queried
+    #[arg(long)]
+    pub timing: bool,
+
+    /// Write Prometheus metrics (lookups, upstream queries, errors, latency) to FILE when done
+    #[arg(long, value_name = "FILE")]
+    pub metrics_file: Option<PathBuf>,
+
+    /// Append a JSON line per lookup (time, target, servers, bytes, duration, status) to FILE
+    #[arg(long, value_name = "FILE")]
+    pub audit_log: Option<PathBuf>,
+
+    /// Highlight lines naming an organisation, maintainer or org handle listed in this YAML file
+    /// (default: `known_orgs` in the config, or known-orgs.yaml next to it)
+    #[arg(long, value_name = "FILE")]
+    pub known_orgs: Option<PathBuf>,
+    
+    /// Treat bare numbers as AS numbers (`3333` is sent as `AS3333`)
+    #[arg(long)]
+    pub asn: bool,
+    
+    /// Query the registrable domain of host names (`mail.example.co.uk` → `example.co.uk`)
+    #[arg(long)]
+    pub apex: bool,
     
     /// Query DN42 information from lantian.pub
     #[arg(long = "42")]
     pub dn42: bool,
+
+    /// Answer DN42 lookups from a dn42 registry checkout, or a git URL cloned and pulled daily
+    #[arg(long, value_name = "PATH|URL", help = "Answer DN42 lookups from a local dn42 registry checkout, or a git URL to clone")]
+    pub dn42_registry: Option<String>,
     
     /// Query from bgp.tools
     #[arg(long)]
@@ -38,14 +192,191 @@ pub struct Cli {
     #[arg(long, hide = true)]
     pub mtf: bool,
 
+    /// Color field names with the stripes of a flag
+    #[arg(long, visible_alias = "palette", value_name = "NAME", help = "Color field names with a flag palette: trans, rainbow, bi, nb, lesbian, pan, ace, aro, genderfluid, or one from [palettes] in the config")]
+    pub pride: Option<String>,
+
+    /// Colors for status, DNSSEC and expiration values
+    #[arg(long, value_enum, value_name = "SCHEME", help = "Colors for status, DNSSEC and expiration values [default: default]")]
+    pub scheme: Option<Theme>,
+
+    /// Mark status, DNSSEC and expiration values with [ok], [!] or [x]
+    #[arg(long, help = "Mark status, DNSSEC and expiration values with [ok], [!] or [x] instead of relying on color")]
+    pub symbols: bool,
+
     /// Disable hyperlinks in terminal output (hyperlinks are enabled by default)
     #[arg(long, help = "Disable clickable hyperlinks for RIR database results")]
     pub no_hyperlinks: bool,
 
+    /// Do not wrap long lines to the terminal width
+    #[arg(long, help = "Do not wrap long lines to the terminal width")]
+    pub no_wrap: bool,
+
+    /// Wrap long lines at this width instead of the terminal width
+    #[arg(long, value_name = "COLUMNS", help = "Wrap long lines at COLUMNS, also when not writing to a terminal")]
+    pub width: Option<usize>,
+
     /// Disable server-side coloring protocol (server-side rendering is default)
     #[arg(long, help = "Disable server-side coloring and use client-side only")]
     pub no_server_color: bool,
 
+    /// Remove all ANSI escape sequences from the output, including the server's colors
+    #[arg(long, help = "Remove all color and hyperlink escape codes from the output, also those sent by the server")]
+    pub strip_ansi: bool,
+
+    /// Strip legal disclaimers and terms-of-use blocks using the server's cleanup rules
+    #[arg(long, help = "Remove legal disclaimers (e.g. ARIN and Verisign terms of use)")]
+    pub no_disclaimer: bool,
+
+    /// Remove terms-of-use blocks and comment banners for compact interactive output
+    #[arg(long, visible_alias = "short", help = "Remove legal text and comment banners, keeping genuine remarks")]
+    pub no_legal: bool,
+
+    /// Collapse objects repeated across sources, noting where else they appeared
+    #[arg(long, help = "Collapse identical objects returned by several sources (e.g. RADB mirrors)")]
+    pub dedup: bool,
+
+    /// Reorder objects so routes, aut-nums, networks and contacts each appear together
+    #[arg(long, help = "Group objects by type (routes, aut-num, contacts, ...) with section headers")]
+    pub group_by_type: bool,
+
+    /// Summarize long import/export lists with line and peer counts
+    #[arg(long, help = "Summarize long import/export lists with line and peer counts")]
+    pub fold_policy: bool,
+
+    /// Show import/export lists in full, even when folding is enabled in the config
+    #[arg(long, conflicts_with = "fold_policy", help = "Show every import/export line, overriding fold_policy in the config")]
+    pub full_policy: bool,
+
+    /// Check the response for suspicious values and list them after it
+    #[arg(long, help = "Warn about suspicious data: bad email syntax, phone prefixes, dates, route origins")]
+    pub lint: bool,
+
+    /// Check inline or detached PGP signatures and mark the response verified or unverified
+    #[arg(long, help = "Verify PGP-signed responses with gpg and mark them verified/unverified")]
+    pub verify: bool,
+
+    /// Line up attribute values in one column per object
+    #[arg(long, help = "Align attribute values of each object into one column")]
+    pub align: bool,
+
+    /// Summarize inetnum/NetRange/route netblocks as CIDR prefixes, ranges and sizes
+    #[arg(long, help = "Annotate address ranges with their CIDR prefixes, range and host counts")]
+    pub cidr: bool,
+
+    /// Also fetch covering and more-specific networks for IP/prefix queries (RIPE `-L`/`-M`)
+    #[arg(long, help = "Show the less- and more-specific networks around an IP or prefix as a hierarchy")]
+    pub related: bool,
+
+    /// Also fetch the org and POC records an ARIN answer refers to
+    #[arg(long, help = "Show the org and abuse/tech POC records referenced by ARIN answers inline")]
+    pub contacts: bool,
+
+    /// Add a table of the prefixes an ASN announces, from RIPEstat
+    #[arg(long, help = "For ASN queries, list announced prefixes (from RIPEstat)")]
+    pub prefixes: bool,
+
+    /// Add a table of an ASN's BGP neighbours, from RIPEstat
+    #[arg(long, help = "For ASN queries, list upstream/downstream BGP neighbours (from RIPEstat)")]
+    pub peers: bool,
+
+    /// Add routing status, visibility and abuse contact from the RIPEstat Data API (HTTPS)
+    #[arg(long, help = "Add a RIPEstat summary (routing status, announced prefixes, abuse contact) for ASN/IP queries")]
+    pub stat: bool,
+
+    /// Add the certificates logged for a domain in certificate transparency logs (crt.sh)
+    #[arg(long, help = "For domain queries, list recent certificates from CT logs (issuer, SANs, validity)")]
+    pub ct: bool,
+
+    /// Add the domain's MX, SPF and DMARC records, resolved over DNS-over-HTTPS, and flag weak policies
+    #[arg(long, help = "For domain queries, check MX, SPF and DMARC records and flag missing or weak policies")]
+    pub mail_check: bool,
+
+    /// Append a short explanation of the cryptic RPSL attributes in the answer
+    #[arg(long, help = "Explain attributes such as mnt-routes or mbrs-by-ref after the answer, in the --ui-lang/locale language")]
+    pub explain: bool,
+
+    /// With `whois org HANDLE`, list the organisation's inetnum, inet6num and aut-num objects
+    #[arg(long, help = "List all resources of an organisation (with `whois org HANDLE`)")]
+    pub resources: bool,
+
+    /// With `whois mnt HANDLE`, flag weak auth and count the objects the maintainer protects
+    #[arg(long, help = "Audit a maintainer's auth and protected objects (with `whois mnt HANDLE`)")]
+    pub audit: bool,
+
+    /// With `whois audit`, read domains from FILE: a list with one per line, or a zone file
+    #[arg(long, value_name = "FILE")]
+    pub file: Option<PathBuf>,
+
+    /// With `whois audit`, warn about domains expiring within DAYS (default: 30)
+    #[arg(long, value_name = "DAYS")]
+    pub expiring_within: Option<i64>,
+
+    /// With `whois compare`, the comma-separated servers to query; the first that answers is the reference.
+    /// With `whois irr-status`, the IRR mirrors to check
+    #[arg(long, value_name = "LIST", value_delimiter = ',')]
+    pub servers: Vec<String>,
+
+    /// With `whois show`, the day (YYYY-MM-DD) whose newest cached snapshot is shown
+    #[arg(long, value_name = "DATE")]
+    pub as_of: Option<String>,
+
+    /// With `whois compare`, show the differences side by side instead of as a unified diff
+    #[arg(long)]
+    pub side_by_side: bool,
+
+    /// With `whois history DOMAIN`, fetch archived records from the WHOIS history API set in the config
+    #[arg(long)]
+    pub remote: bool,
+
+    /// Answer language for registries with localized output (JPRS, JPNIC, KRNIC)
+    #[arg(long, value_enum, help = "Request answers in English or the registry's local language")]
+    pub lang: Option<Language>,
+
+    /// Language of client messages; defaults to `LC_ALL`, `LC_MESSAGES` or `LANG`
+    #[arg(long, value_enum, help = "Language of headings, labels and warnings (default: from LC_ALL, LC_MESSAGES or LANG)")]
+    pub ui_lang: Option<Locale>,
+
+    /// Find objects that refer to the target through an attribute, e.g. `-i origin AS37100`
+    #[arg(short = 'i', long, value_name = "ATTRIBUTE", help = "Inverse lookup on RIPE, APNIC, AFRINIC and IRRd servers (e.g. origin, mnt-by)")]
+    pub inverse: Option<String>,
+
+    /// Only return objects of these comma-separated types, e.g. `-T route,route6`
+    #[arg(short = 'T', long = "type", value_name = "TYPES", help = "Object types to return on RIPE, APNIC, AFRINIC and IRRd servers")]
+    pub object_types: Option<String>,
+
+    /// Restrict ARIN lookups to one record type, sent as ARIN's `n`, `a`, `o` or `p` query prefix
+    #[arg(long, value_enum, help = "Restrict ARIN lookups to networks, ASNs, orgs or POCs")]
+    pub arin_type: Option<RecordType>,
+
+    /// Ask ARIN for full output, sent as ARIN's `+` query prefix
+    #[arg(long, help = "Ask whois.arin.net for full output, e.g. an org's networks and POCs")]
+    pub arin_full: bool,
+
+    /// Do not follow referrals embedded in answers (ARIN `ReferralServer:`, "please query X" remarks)
+    #[arg(long, help = "Do not follow referrals to other WHOIS/RWhois servers found in answers")]
+    pub no_referral: bool,
+
+    /// Open a new connection per query even in bulk runs
+    #[arg(long, help = "Do not reuse -k persistent connections to RADB/RIPE-style servers in bulk runs")]
+    pub no_keepalive: bool,
+
+    /// When a server resets the connection mid-answer, query again and compare to detect truncation
+    #[arg(long)]
+    pub retry_reset: bool,
+
+    /// Overall time limit per target, covering referrals, probes and fallbacks
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration, help = "Give up on a target after this long in total, e.g. 30s, 1500ms, 2m")]
+    pub deadline: Option<Duration>,
+
+    /// Store answers, job results and capability probes in SQLite and reuse fresh answers
+    #[arg(long, help = "Reuse answers from the SQLite cache and record new ones (feature `sqlite`)")]
+    pub cache: bool,
+
+    /// How old a cached answer may be before it is fetched again
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration, requires = "cache")]
+    pub cache_ttl: Option<Duration>,
+
     /// Enable Markdown formatting from server
     #[arg(long, help = "Request Markdown-formatted output from server")]
     pub markdown: bool,
@@ -53,12 +384,164 @@ pub struct Cli {
     /// Enable image display in terminal
     #[arg(long, help = "Enable inline image display in terminal")]
     pub images: bool,
+
+    /// Terminal graphics protocol for `--images`
+    #[arg(long, value_enum, value_name = "PROTOCOL", default_value_t = ImageProtocol::Auto, help = "Image protocol: auto, kitty, iterm, sixel or blocks")]
+    pub image_protocol: ImageProtocol,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text, help = "Output format (text, html, json, svg, png, csv)")]
+    pub format: OutputFormat,
+
+    /// Copy the value of a field to the clipboard (e.g. abuse-mailbox)
+    #[arg(long, value_name = "FIELD", help = "Copy the value(s) of FIELD to the system clipboard")]
+    pub copy: Option<String>,
+
+    /// Query multiple targets in parallel
+    #[arg(long, help = "Query multiple targets in parallel instead of one after another")]
+    pub parallel: bool,
+
+    /// Maximum number of targets queried at once (implies --parallel when above 1)
+    #[arg(long, value_name = "N")]
+    pub concurrency: Option<usize>,
+
+    /// Minimum delay in milliseconds between queries to the same server
+    #[arg(long, value_name = "MS", default_value_t = 0)]
+    pub delay: u64,
+
+    /// Maximum concurrent connections to any single server
+    #[arg(long, value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+    pub per_host: u64,
+
+    /// Record finished targets and their results in FILE so an interrupted run can be resumed
+    #[arg(long, value_name = "FILE")]
+    pub job_state: Option<PathBuf>,
+
+    /// Resume the run recorded in --job-state, skipping targets that already succeeded
+    #[arg(long, requires = "job_state")]
+    pub resume: bool,
+
+    /// Write output to a file instead of stdout
+    #[arg(short, long, value_name = "FILE")]
+    pub output: Option<String>,
 }
 
+/// Age of cached answers that are reused without `--cache-ttl`
+const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
 impl Cli {
+    /// Parse arguments (program name first) with a preset's flags put before them,
+    /// so that flags given on the command line override the preset's
+    pub fn parse_with_preset(args: &[String], preset: &[String]) -> Result<Self, clap::Error> {
+        let (program, rest) = args.split_first().map_or(("whois", &[][..]), |(program, rest)| (program.as_str(), rest));
+        Self::try_parse_from(std::iter::once(program).chain(preset.iter().map(String::as_str)).chain(rest.iter().map(String::as_str)))
+    }
+
+    /// Days before expiry `whois audit` starts warning about a domain
+    pub fn expiry_window(&self) -> i64 {
+        self.expiring_within.unwrap_or(crate::registration::DEFAULT_WARNING_DAYS)
+    }
+
+    /// Local side of WHOIS connections, from `--source-ip` or `--interface`
+    pub fn source(&self) -> Option<Source> {
+        self.source_ip.map(Source::Ip).or_else(|| self.interface.clone().map(Source::Interface))
+    }
+
+    /// Maximum age of a cached answer
+    pub fn cache_ttl(&self) -> Duration {
+        self.cache_ttl.unwrap_or(DEFAULT_CACHE_TTL)
+    }
+
+    /// Command given as the first words instead of targets, if any
+    pub fn command(&self) -> anyhow::Result<Option<Command>> {
+        let command = self.leading_command()?;
+        if self.format == OutputFormat::Csv && !matches!(command, Some(Command::DomainAudit { .. })) {
+            anyhow::bail!("--format csv is only available with whois audit");
+        }
+        Ok(command)
+    }
+
+    /// Command named by `--version` or the leading words
+    fn leading_command(&self) -> anyhow::Result<Option<Command>> {
+        if self.version {
+            return Ok(Some(Command::Version { verbose: self.verbose > 0 }));
+        }
+        let words: Vec<&str> = self.domain.iter().map(String::as_str).collect();
+        match words.as_slice() {
+            ["history", key] => Ok(Some(Command::History { key: key.to_string() })),
+            ["history", ..] => anyhow::bail!("Usage: whois history KEY"),
+            ["show-version", revision, key] => {
+                let revision = revision
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("Invalid version number: {}", revision))?;
+                Ok(Some(Command::ShowVersion { revision, key: key.to_string() }))
+            }
+            ["show-version", ..] => anyhow::bail!("Usage: whois show-version N KEY"),
+            ["org", handle] => Ok(Some(Command::Org { handle: handle.to_string() })),
+            ["org", ..] => anyhow::bail!("Usage: whois org HANDLE [--resources]"),
+            ["mnt", handle] => Ok(Some(Command::Mnt { handle: handle.to_string() })),
+            ["mnt", ..] => anyhow::bail!("Usage: whois mnt HANDLE [--audit]"),
+            ["cache", "stats"] => Ok(Some(Command::CacheStats)),
+            ["cache", "purge"] => Ok(Some(Command::CachePurge { older_than: None })),
+            ["cache", "purge", age] => {
+                let older_than = parse_duration(age).map_err(|err| anyhow::anyhow!("Invalid age {}: {}", age, err))?;
+                Ok(Some(Command::CachePurge { older_than: Some(older_than) }))
+            }
+            ["cache", "export", file] => Ok(Some(Command::CacheExport { file: PathBuf::from(file) })),
+            ["cache", "import", file] => Ok(Some(Command::CacheImport { file: PathBuf::from(file) })),
+            ["cache", ..] => anyhow::bail!("Usage: whois cache stats | purge [AGE] | export FILE | import FILE"),
+            ["lint-file", file] => Ok(Some(Command::LintFile { file: PathBuf::from(file) })),
+            ["lint-file", ..] => anyhow::bail!("Usage: whois lint-file FILE"),
+            ["template", class] => Ok(Some(Command::Template { class: class.to_lowercase() })),
+            ["template", ..] => anyhow::bail!("Usage: whois template CLASS [--42]"),
+            ["tld", tld] => Ok(Some(Command::Tld { tld: crate::tld::normalize(tld) })),
+            ["tld", ..] => anyhow::bail!("Usage: whois tld TLD"),
+            ["auth", "set", account] => Ok(Some(Command::AuthSet { account: crate::secrets::account_name(account)? })),
+            ["auth", "remove", account] => Ok(Some(Command::AuthRemove { account: crate::secrets::account_name(account)? })),
+            ["auth", ..] => anyhow::bail!("Usage: whois auth set ACCOUNT | remove ACCOUNT"),
+            ["version"] => Ok(Some(Command::Version { verbose: true })),
+            ["repl"] => Ok(Some(Command::Repl)),
+            ["correlate", targets @ ..] if targets.len() >= 2 => {
+                Ok(Some(Command::Correlate { targets: targets.iter().map(|target| target.to_string()).collect() }))
+            }
+            ["correlate", ..] => anyhow::bail!("Usage: whois correlate TARGET TARGET..."),
+            ["audit", domains @ ..] if !domains.is_empty() || self.file.is_some() => {
+                Ok(Some(Command::DomainAudit { domains: domains.iter().map(|domain| domain.to_string()).collect() }))
+            }
+            ["audit"] => anyhow::bail!("Usage: whois audit [DOMAIN...] [--file FILE] [--expiring-within DAYS]"),
+            ["compare", target] if self.servers.len() >= 2 => Ok(Some(Command::Compare { target: target.to_string() })),
+            ["irr-status", sources @ ..] => {
+                Ok(Some(Command::IrrStatus { sources: sources.iter().map(|source| source.to_uppercase()).collect() }))
+            }
+            ["prefix-list", target] => Ok(Some(Command::PrefixList { target: target.to_uppercase() })),
+            ["prefix-list", ..] => anyhow::bail!("Usage: whois prefix-list ASN|AS-SET"),
+            ["as-set", name] => Ok(Some(Command::AsSet { name: name.to_uppercase() })),
+            ["as-set", ..] => anyhow::bail!("Usage: whois as-set NAME"),
+            ["show", target] => {
+                let date = self.as_of.as_deref().ok_or_else(|| anyhow::anyhow!("Usage: whois show TARGET --as-of YYYY-MM-DD"))?;
+                let as_of = crate::dates::parse_day(date).ok_or_else(|| anyhow::anyhow!("Invalid date {}, expected YYYY-MM-DD", date))?;
+                Ok(Some(Command::Show { target: target.to_string(), as_of }))
+            }
+            ["show", ..] => anyhow::bail!("Usage: whois show TARGET --as-of YYYY-MM-DD"),
+            ["probe-server", server] => {
+                Ok(Some(Command::ProbeServer { server: server.to_string(), query: crate::conformance::DEFAULT_QUERY.to_string() }))
+            }
+            ["probe-server", server, query] => Ok(Some(Command::ProbeServer { server: server.to_string(), query: query.to_string() })),
+            ["probe-server", ..] => anyhow::bail!("Usage: whois probe-server HOST[:PORT] [QUERY]"),
+            ["compare", ..] => anyhow::bail!("Usage: whois compare TARGET --servers SERVER,SERVER... [--side-by-side]"),
+            _ => Ok(None),
+        }
+    }
+
+    /// Language of client messages: `--ui-lang en|zh`, otherwise `LC_ALL`, `LC_MESSAGES` or `LANG`
+    /// `--lang` only picks the language of the answer
+    pub fn locale(&self) -> Locale {
+        self.ui_lang.or_else(Locale::from_env).unwrap_or_default()
+    }
+
     /// Check if colored output should be used
     pub fn use_color(&self) -> bool {
-        !self.no_color
+        !self.no_color && !self.strip_ansi
     }
 
     /// Check if MTF colors should be used
@@ -66,9 +549,39 @@ impl Cli {
         self.mtf
     }
 
-    /// Check if DN42 mode should be used
-    pub fn use_dn42(&self) -> bool {
-        self.dn42 || self.domain.to_uppercase().starts_with("AS42424")
+    /// Palette overlay selected with `--pride`, or `trans` for `--mtf`
+    pub fn palette_name(&self) -> Option<&str> {
+        self.pride.as_deref().or(self.mtf.then_some("trans"))
+    }
+
+    /// Check if DN42 mode should be used for a target
+    pub fn use_dn42(&self, target: &str) -> bool {
+        self.dn42 || target.to_uppercase().starts_with("AS42424")
+    }
+
+    /// Check if progress indicators should be shown (interactive stderr only)
+    pub fn show_progress(&self) -> bool {
+        !self.quiet && self.verbose == 0 && std::io::stderr().is_terminal()
+    }
+
+    /// Log level selected by `-q` and the number of `-v` flags
+    pub fn log_level(&self) -> LevelFilter {
+        crate::logging::level_from_flags(self.quiet, self.verbose)
+    }
+
+    /// Check if more than one target was given
+    pub fn is_multi_target(&self) -> bool {
+        self.domain.len() > 1
+    }
+
+    /// Check if targets should be queried in parallel
+    pub fn use_parallel(&self) -> bool {
+        self.parallel || self.concurrency.is_some_and(|n| n > 1)
+    }
+
+    /// Number of targets to query at once in parallel mode
+    pub fn worker_count(&self) -> usize {
+        self.concurrency.unwrap_or(crate::bulk::DEFAULT_CONCURRENCY)
     }
 
     /// Check if BGP tools mode should be used
@@ -78,7 +591,19 @@ impl Cli {
 
     /// Check if hyperlinks should be used
     pub fn use_hyperlinks(&self) -> bool {
-        !self.no_hyperlinks
+        !self.no_hyperlinks && !self.strip_ansi
+    }
+
+    /// Width to wrap text output at: `--width`, or the terminal width when stdout is a terminal
+    pub fn wrap_width(&self) -> Option<usize> {
+        if self.no_wrap || self.format != OutputFormat::Text {
+            return None;
+        }
+        self.width.or_else(|| {
+            (self.output.is_none() && std::io::stdout().is_terminal())
+                .then(crate::layout::terminal_width)
+                .flatten()
+        })
     }
 
     /// Check if server-side coloring should be used (default: true)
@@ -95,6 +620,73 @@ impl Cli {
     pub fn use_images(&self) -> bool {
         self.images
     }
+
+    /// Check if output should be exported as HTML
+    pub fn use_html(&self) -> bool {
+        self.format == OutputFormat::Html
+    }
+
+    /// The target with the query flags that change the answer, e.g. `-i origin AS37100`
+    pub fn query_key(&self, target: &str) -> String {
+        let mut flags = Vec::new();
+        if let Some(attribute) = &self.inverse {
+            flags.push(format!("-i {}", attribute));
+        }
+        if let Some(types) = &self.object_types {
+            flags.push(format!("-T {}", types));
+        }
+        if let Some(record_type) = self.arin_type {
+            flags.push(format!("--arin-type {:?}", record_type).to_lowercase());
+        }
+        if self.arin_full {
+            flags.push("--arin-full".to_string());
+        }
+        flags.push(target.to_string());
+        flags.join(" ")
+    }
+
+    /// Key of a target's answer in the cache: the query key with the server asked first
+    /// and the options that select the server or the language of the answer
+    pub fn cache_key(&self, target: &str, server: &WhoisServer) -> String {
+        let mut flags = vec![format!("-h {}:{}", server.host, server.port)];
+        if self.use_dn42(target) {
+            flags.push("--42".to_string());
+        }
+        if self.use_bgptools() {
+            flags.push("--bgptools".to_string());
+        }
+        if let Some(language) = self.lang {
+            flags.push(format!("--lang {:?}", language).to_lowercase());
+        }
+        flags.push(self.query_key(target));
+        flags.join(" ")
+    }
+
+    /// Which response cleanup rules to apply
+    pub fn cleanup_level(&self) -> CleanupLevel {
+        if self.no_legal {
+            CleanupLevel::Short
+        } else if self.no_disclaimer {
+            CleanupLevel::NoDisclaimer
+        } else {
+            CleanupLevel::Standard
+        }
+    }
+
+    /// Check if bulk queries should share persistent connections
+    pub fn use_keepalive(&self) -> bool {
+        self.is_multi_target() && !self.no_keepalive
+    }
+
+    /// Check if output should be JSON
+    pub fn use_json(&self) -> bool {
+        self.format == OutputFormat::Json
+    }
+
+    /// Check if output should be rendered as an image (SVG or PNG)
+    pub fn use_image_export(&self) -> bool {
+        matches!(self.format, OutputFormat::Svg | OutputFormat::Png)
+    }
 }
 
 #[cfg(test)]
@@ -103,18 +695,87 @@ mod tests {
 
     fn create_test_cli(domain: &str) -> Cli {
         Cli {
-            domain: domain.to_string(),
+            domain: vec![domain.to_string()],
+            preset: None,
+            version: false,
             server: None,
             port: 43,
-            verbose: false,
+            source_ip: None,
+            doh: None,
+            interface: None,
+            verbose: 0,
+            quiet: false,
+            config: None,
+            trace_file: None,
+            timing: false,
+            metrics_file: None,
+            audit_log: None,
+            known_orgs: None,
             dn42: false,
+            dn42_registry: None,
             bgptools: false,
             no_color: false,
             mtf: false,
+            pride: None,
+            scheme: None,
+            symbols: false,
             no_hyperlinks: false,
+            no_wrap: false,
+            width: None,
             no_server_color: false,
+            strip_ansi: false,
+            no_disclaimer: false,
+            no_legal: false,
+            asn: false,
+            apex: false,
+            dedup: false,
+            group_by_type: false,
+            fold_policy: false,
+            full_policy: false,
+            align: false,
+            lint: false,
+            verify: false,
+            cidr: false,
+            related: false,
+            contacts: false,
+            prefixes: false,
+            peers: false,
+            stat: false,
+            ct: false,
+            mail_check: false,
+            explain: false,
+            resources: false,
+            audit: false,
+            file: None,
+            expiring_within: None,
+            servers: vec![],
+            as_of: None,
+            side_by_side: false,
+            remote: false,
+            lang: None,
+            ui_lang: None,
+            inverse: None,
+            object_types: None,
+            arin_type: None,
+            arin_full: false,
+            no_referral: false,
+            no_keepalive: false,
+            retry_reset: false,
+            deadline: None,
+            cache: false,
+            cache_ttl: None,
             markdown: false,
             images: false,
+            image_protocol: ImageProtocol::Auto,
+            format: OutputFormat::Text,
+            copy: None,
+            parallel: false,
+            concurrency: None,
+            delay: 0,
+            per_host: 1,
+            job_state: None,
+            resume: false,
+            output: None,
         }
     }
 
@@ -138,36 +799,33 @@ mod tests {
         
         cli.mtf = true;
         assert!(cli.use_mtf_colors());
+        assert_eq!(cli.palette_name(), Some("trans"));
+        assert_eq!(Cli::parse_from(["whois", "--pride", "bi", "AS1"]).palette_name(), Some("bi"));
+        assert_eq!(Cli::parse_from(["whois", "--palette", "nb", "AS1"]).palette_name(), Some("nb"));
     }
 
     #[test]
     fn test_use_dn42_explicit_flag() {
         let mut cli = create_test_cli("example.com");
-        assert!(!cli.use_dn42());
+        assert!(!cli.use_dn42("example.com"));
         
         cli.dn42 = true;
-        assert!(cli.use_dn42());
+        assert!(cli.use_dn42("example.com"));
     }
 
     #[test]
     fn test_use_dn42_auto_detection() {
         let cli = create_test_cli("AS4242420000");
-        assert!(cli.use_dn42());
-        
-        let cli = create_test_cli("as4242420000");
-        assert!(cli.use_dn42());
-        
-        let cli = create_test_cli("AS4242421234");
-        assert!(cli.use_dn42());
+        assert!(cli.use_dn42("AS4242420000"));
+        assert!(cli.use_dn42("as4242420000"));
+        assert!(cli.use_dn42("AS4242421234"));
     }
 
     #[test]
     fn test_use_dn42_not_triggered() {
         let cli = create_test_cli("AS15169");
-        assert!(!cli.use_dn42());
-        
-        let cli = create_test_cli("example.com");
-        assert!(!cli.use_dn42());
+        assert!(!cli.use_dn42("AS15169"));
+        assert!(!cli.use_dn42("example.com"));
     }
 
     #[test]
@@ -179,6 +837,32 @@ mod tests {
         assert!(cli.use_bgptools());
     }
 
+    #[test]
+    fn test_show_progress_suppressed() {
+        let mut cli = create_test_cli("example.com");
+        cli.quiet = true;
+        assert!(!cli.show_progress());
+
+        cli.quiet = false;
+        cli.verbose = 1;
+        assert!(!cli.show_progress());
+    }
+
+    #[test]
+    fn test_verbosity_levels() {
+        let cli = Cli::try_parse_from(["whois", "example.com"]).unwrap();
+        assert_eq!(cli.log_level(), LevelFilter::WARN);
+
+        let cli = Cli::try_parse_from(["whois", "-vv", "example.com"]).unwrap();
+        assert_eq!(cli.verbose, 2);
+        assert_eq!(cli.log_level(), LevelFilter::DEBUG);
+
+        let cli = Cli::try_parse_from(["whois", "-q", "example.com"]).unwrap();
+        assert_eq!(cli.log_level(), LevelFilter::ERROR);
+
+        assert!(Cli::try_parse_from(["whois", "-q", "-v", "example.com"]).is_err());
+    }
+
     #[test]
     fn test_use_hyperlinks_default() {
         let cli = create_test_cli("example.com");
@@ -192,6 +876,16 @@ mod tests {
         assert!(!cli.use_hyperlinks());
     }
 
+    #[test]
+    fn test_strip_ansi_disables_decoration() {
+        let mut cli = create_test_cli("example.com");
+        cli.strip_ansi = true;
+        assert!(!cli.use_color());
+        assert!(!cli.use_hyperlinks());
+        // Server colors are still requested; they are removed from the output
+        assert!(cli.use_server_color());
+    }
+
     #[test]
     fn test_port_default() {
         let cli = create_test_cli("example.com");
@@ -201,7 +895,42 @@ mod tests {
     #[test]
     fn test_domain_assignment() {
         let cli = create_test_cli("test.example.com");
-        assert_eq!(cli.domain, "test.example.com");
+        assert_eq!(cli.domain, vec!["test.example.com"]);
+        assert!(!cli.is_multi_target());
+    }
+
+    #[test]
+    fn test_concurrency_implies_parallel() {
+        let cli = Cli::try_parse_from(["whois", "a.com", "b.com"]).unwrap();
+        assert!(!cli.use_parallel());
+
+        let cli = Cli::try_parse_from(["whois", "--concurrency", "4", "--delay", "500", "a.com", "b.com"]).unwrap();
+        assert!(cli.use_parallel());
+        assert_eq!(cli.worker_count(), 4);
+        assert_eq!(cli.delay, 500);
+        assert_eq!(cli.per_host, 1);
+
+        assert!(Cli::try_parse_from(["whois", "--per-host", "0", "a.com"]).is_err());
+    }
+
+    #[test]
+    fn test_resume_requires_job_state() {
+        assert!(Cli::try_parse_from(["whois", "--resume", "a.com"]).is_err());
+        let cli = Cli::try_parse_from(["whois", "--job-state", "job.jsonl", "--resume", "a.com"]).unwrap();
+        assert!(cli.resume);
+    }
+
+    #[test]
+    fn test_multiple_targets() {
+        let cli = Cli::try_parse_from(["whois", "example.com", "example.org", "AS3333"]).unwrap();
+        assert_eq!(cli.domain, vec!["example.com", "example.org", "AS3333"]);
+        assert!(cli.is_multi_target());
+        assert!(!cli.parallel);
+    }
+
+    #[test]
+    fn test_targets_required() {
+        assert!(Cli::try_parse_from(["whois"]).is_err());
     }
 
     #[test]
@@ -231,6 +960,188 @@ mod tests {
         assert!(cli.use_images());
     }
 
+    #[test]
+    fn test_use_html() {
+        let mut cli = create_test_cli("example.com");
+        assert!(!cli.use_html()); // Default is text
+
+        cli.format = OutputFormat::Html;
+        assert!(cli.use_html());
+        assert!(!cli.use_image_export());
+    }
+
+    #[test]
+    fn test_cleanup_level() {
+        let cli = create_test_cli("example.com");
+        assert_eq!(cli.cleanup_level(), CleanupLevel::Standard);
+
+        let cli = Cli::parse_from(["whois", "--no-disclaimer", "example.com"]);
+        assert_eq!(cli.cleanup_level(), CleanupLevel::NoDisclaimer);
+
+        let cli = Cli::parse_from(["whois", "--no-disclaimer", "--short", "example.com"]);
+        assert_eq!(cli.cleanup_level(), CleanupLevel::Short);
+    }
+
+    #[test]
+    fn test_use_json() {
+        let cli = Cli::parse_from(["whois", "--format", "json", "example.com"]);
+        assert!(cli.use_json());
+        assert!(!cli.use_html());
+        assert!(!cli.use_image_export());
+    }
+
+    #[test]
+    fn test_query_and_cache_keys() {
+        let cli = Cli::parse_from(["whois", "-i", "origin", "-T", "route", "AS37100"]);
+        assert_eq!(cli.query_key("AS37100"), "-i origin -T route AS37100");
+        assert_eq!(Cli::parse_from(["whois", "AS37100"]).query_key("AS37100"), "AS37100");
+        let ripe = WhoisServer::new("whois.ripe.net", 43, "RIPE");
+        assert_eq!(cli.cache_key("AS37100", &ripe), "-h whois.ripe.net:43 -i origin -T route AS37100");
+        let cli = Cli::parse_from(["whois", "--bgptools", "--lang", "local", "AS37100"]);
+        assert_eq!(cli.cache_key("AS37100", &ripe), "-h whois.ripe.net:43 --bgptools --lang local AS37100");
+        assert_eq!(Cli::parse_from(["whois", "AS4242420000"]).cache_key("AS4242420000", &ripe), "-h whois.ripe.net:43 --42 AS4242420000");
+    }
+
+    #[test]
+    fn test_deadline() {
+        let cli = Cli::parse_from(["whois", "--deadline", "1500ms", "example.com"]);
+        assert_eq!(cli.deadline, Some(Duration::from_millis(1500)));
+        assert!(Cli::try_parse_from(["whois", "--deadline", "soon", "example.com"]).is_err());
+    }
+
+    #[test]
+    fn test_command_words() {
+        assert_eq!(Cli::parse_from(["whois", "AS3333"]).command().unwrap(), None);
+        assert_eq!(
+            Cli::parse_from(["whois", "history", "AS3333"]).command().unwrap(),
+            Some(Command::History { key: "AS3333".to_string() })
+        );
+        assert_eq!(
+            Cli::parse_from(["whois", "show-version", "3", "AS3333"]).command().unwrap(),
+            Some(Command::ShowVersion { revision: 3, key: "AS3333".to_string() })
+        );
+        assert!(Cli::parse_from(["whois", "show-version", "x", "AS3333"]).command().is_err());
+        assert!(Cli::parse_from(["whois", "history"]).command().is_err());
+        assert_eq!(
+            Cli::parse_from(["whois", "org", "ORG-EX1-RIPE", "--resources"]).command().unwrap(),
+            Some(Command::Org { handle: "ORG-EX1-RIPE".to_string() })
+        );
+        assert_eq!(
+            Cli::parse_from(["whois", "mnt", "FOO-MNT", "--audit"]).command().unwrap(),
+            Some(Command::Mnt { handle: "FOO-MNT".to_string() })
+        );
+        assert_eq!(
+            Cli::parse_from(["whois", "cache", "purge", "2m"]).command().unwrap(),
+            Some(Command::CachePurge { older_than: Some(Duration::from_secs(120)) })
+        );
+        assert_eq!(
+            Cli::parse_from(["whois", "cache", "export", "case-42.tar.gz"]).command().unwrap(),
+            Some(Command::CacheExport { file: PathBuf::from("case-42.tar.gz") })
+        );
+        assert!(Cli::parse_from(["whois", "cache", "clear"]).command().is_err());
+        assert!(Cli::parse_from(["whois", "cache", "import"]).command().is_err());
+        assert_eq!(
+            Cli::parse_from(["whois", "lint-file", "route6.rpsl"]).command().unwrap(),
+            Some(Command::LintFile { file: PathBuf::from("route6.rpsl") })
+        );
+        assert_eq!(
+            Cli::parse_from(["whois", "template", "Route6", "--42"]).command().unwrap(),
+            Some(Command::Template { class: "route6".to_string() })
+        );
+        assert_eq!(
+            Cli::parse_from(["whois", "tld", ".IO"]).command().unwrap(),
+            Some(Command::Tld { tld: "io".to_string() })
+        );
+        assert!(Cli::parse_from(["whois", "tld"]).command().is_err());
+        assert_eq!(
+            Cli::parse_from(["whois", "auth", "set", "WHOIS.IPAM.INTERNAL"]).command().unwrap(),
+            Some(Command::AuthSet { account: "whois.ipam.internal".to_string() })
+        );
+        assert!(Cli::parse_from(["whois", "auth", "get", "peeringdb"]).command().is_err());
+        assert_eq!(Cli::parse_from(["whois", "version"]).command().unwrap(), Some(Command::Version { verbose: true }));
+        assert_eq!(
+            Cli::parse_from(["whois", "correlate", "a.com", "192.0.2.0/24"]).command().unwrap(),
+            Some(Command::Correlate { targets: vec!["a.com".to_string(), "192.0.2.0/24".to_string()] })
+        );
+        assert!(Cli::parse_from(["whois", "correlate", "a.com"]).command().is_err());
+        assert_eq!(
+            Cli::parse_from(["whois", "audit", "--file", "domains.txt"]).command().unwrap(),
+            Some(Command::DomainAudit { domains: vec![] })
+        );
+        assert!(Cli::parse_from(["whois", "audit"]).command().is_err());
+        assert!(Cli::parse_from(["whois", "example.com", "--format", "csv"]).command().is_err());
+        assert_eq!(
+            Cli::parse_from(["whois", "compare", "AS3333", "--servers", "whois.ripe.net,whois.radb.net"]).command().unwrap(),
+            Some(Command::Compare { target: "AS3333".to_string() })
+        );
+        assert!(Cli::parse_from(["whois", "compare", "AS3333", "--servers", "whois.ripe.net"]).command().is_err());
+        assert_eq!(
+            Cli::parse_from(["whois", "irr-status", "ripe", "arin"]).command().unwrap(),
+            Some(Command::IrrStatus { sources: vec!["RIPE".to_string(), "ARIN".to_string()] })
+        );
+        assert_eq!(
+            Cli::parse_from(["whois", "prefix-list", "as3333"]).command().unwrap(),
+            Some(Command::PrefixList { target: "AS3333".to_string() })
+        );
+        assert!(Cli::parse_from(["whois", "as-set"]).command().is_err());
+        assert_eq!(
+            Cli::parse_from(["whois", "show", "example.com", "--as-of", "2024-06-01"]).command().unwrap(),
+            Some(Command::Show { target: "example.com".to_string(), as_of: crate::dates::days_from_civil(2024, 6, 1).unwrap() })
+        );
+        assert!(Cli::parse_from(["whois", "show", "example.com"]).command().is_err());
+        assert!(Cli::parse_from(["whois", "show", "example.com", "--as-of", "June"]).command().is_err());
+        assert_eq!(
+            Cli::parse_from(["whois", "probe-server", "localhost:4343"]).command().unwrap(),
+            Some(Command::ProbeServer { server: "localhost:4343".to_string(), query: "example.com".to_string() })
+        );
+        assert_eq!(Cli::parse_from(["whois", "-V"]).command().unwrap(), Some(Command::Version { verbose: false }));
+        assert_eq!(Cli::parse_from(["whois", "--version", "--verbose"]).command().unwrap(), Some(Command::Version { verbose: true }));
+    }
+
+    #[test]
+    fn test_preset_flags_yield_to_command_line() {
+        let preset = ["--server".to_string(), "whois.radb.net".to_string(), "--dedup".to_string()];
+        let args: Vec<String> = ["whois", "--preset", "irr", "-s", "whois.altdb.net", "AS-FOO"].iter().map(|s| s.to_string()).collect();
+        let cli = Cli::parse_with_preset(&args, &preset).unwrap();
+        assert_eq!(cli.server.as_deref(), Some("whois.altdb.net"));
+        assert!(cli.dedup);
+        assert_eq!(cli.domain, ["AS-FOO"]);
+    }
+
+    #[test]
+    fn test_locale_from_ui_lang() {
+        assert_eq!(Cli::parse_from(["whois", "--ui-lang", "zh", "AS3333"]).locale(), Locale::Zh);
+        let cli = Cli::parse_from(["whois", "--ui-lang", "en", "--lang", "local", "AS3333"]);
+        assert_eq!((cli.locale(), cli.lang), (Locale::En, Some(Language::Local)));
+        assert!(Cli::try_parse_from(["whois", "--lang", "zh", "AS3333"]).is_err());
+    }
+
+    #[test]
+    fn test_use_keepalive() {
+        assert!(!Cli::parse_from(["whois", "AS1"]).use_keepalive());
+        assert!(Cli::parse_from(["whois", "AS1", "AS2"]).use_keepalive());
+        assert!(!Cli::parse_from(["whois", "--no-keepalive", "AS1", "AS2"]).use_keepalive());
+    }
+
+    #[test]
+    fn test_use_image_export() {
+        let mut cli = create_test_cli("example.com");
+        assert!(!cli.use_image_export());
+
+        cli.format = OutputFormat::Svg;
+        assert!(cli.use_image_export());
+
+        cli.format = OutputFormat::Png;
+        assert!(cli.use_image_export());
+    }
+
+    #[test]
+    fn test_wrap_width() {
+        assert_eq!(Cli::parse_from(["whois", "--width", "60", "AS1"]).wrap_width(), Some(60));
+        assert_eq!(Cli::parse_from(["whois", "--width", "60", "--no-wrap", "AS1"]).wrap_width(), None);
+        assert_eq!(Cli::parse_from(["whois", "--width", "60", "--format", "json", "AS1"]).wrap_width(), None);
+    }
+
     #[test]
     fn test_all_flags_together() {
         let mut cli = create_test_cli("AS4242420000");
@@ -239,13 +1150,13 @@ mod tests {
         cli.no_color = true;
         cli.mtf = true;
         cli.no_hyperlinks = true;
-        cli.verbose = true;
+        cli.verbose = 1;
         cli.no_server_color = true;
         cli.markdown = true;
         cli.images = true;
         
         // DN42 should be true due to both flag and auto-detection
-        assert!(cli.use_dn42());
+        assert!(cli.use_dn42("AS4242420000"));
         assert!(cli.use_bgptools());
         assert!(!cli.use_color());
         assert!(cli.use_mtf_colors());
@@ -253,6 +1164,6 @@ mod tests {
         assert!(!cli.use_server_color());
         assert!(cli.use_markdown());
         assert!(cli.use_images());
-        assert!(cli.verbose);
+        assert_eq!(cli.log_level(), LevelFilter::INFO);
     }
 } 
\ No newline at end of file
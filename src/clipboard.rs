@@ -0,0 +1,131 @@
+use anyhow::Result;
+
+/// Extract all values of a field from a WHOIS response
+/// Matching is case-insensitive and duplicate values are dropped
+pub fn extract_field_values(response: &str, field: &str) -> Vec<String> {
+    let field = field.trim().trim_end_matches(':').to_lowercase();
+    let mut values: Vec<String> = Vec::new();
+
+    for line in response.lines() {
+        if line.starts_with('%') || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().to_lowercase() != field {
+                continue;
+            }
+
+            let value = value.trim();
+            if !value.is_empty() && !values.iter().any(|v| v == value) {
+                values.push(value.to_string());
+            }
+        }
+    }
+
+    values
+}
+
+/// Set in the environment of the helper process that keeps X11 and Wayland clipboard contents
+pub const HOLDER_ENV: &str = "WHOIS_CLIPBOARD_HOLDER";
+
+/// Place text on the system clipboard
+#[cfg(all(feature = "clipboard", any(windows, target_os = "macos")))]
+pub fn copy_to_clipboard(text: &str) -> Result<()> {
+    use anyhow::Context;
+
+    let mut clipboard = arboard::Clipboard::new()
+        .context("Failed to access the system clipboard")?;
+    clipboard
+        .set_text(text.to_string())
+        .context("Failed to copy to the system clipboard")
+}
+
+/// Place text on the system clipboard
+/// On X11 and Wayland the contents are served by the process that set them, so a helper
+/// process (this executable with `HOLDER_ENV` set) keeps them after whois exits
+#[cfg(all(feature = "clipboard", unix, not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))))]
+pub fn copy_to_clipboard(text: &str) -> Result<()> {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    use anyhow::Context;
+
+    // Fail here rather than in the helper, whose errors nobody sees
+    arboard::Clipboard::new().context("Failed to access the system clipboard")?;
+    let executable = std::env::current_exe().context("Failed to find the whois executable")?;
+    let mut holder = Command::new(executable)
+        .env(HOLDER_ENV, "1")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("Failed to start the clipboard holder")?;
+    let mut stdin = holder.stdin.take().context("Failed to pass the text to the clipboard holder")?;
+    stdin.write_all(text.as_bytes()).context("Failed to pass the text to the clipboard holder")
+}
+
+/// Body of the helper process: own the clipboard with the text from stdin until another
+/// program replaces it
+#[cfg(all(feature = "clipboard", unix, not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))))]
+pub fn hold_clipboard() -> Result<()> {
+    use std::io::Read;
+
+    use anyhow::Context;
+    use arboard::SetExtLinux;
+
+    let mut text = String::new();
+    std::io::stdin().read_to_string(&mut text).context("Failed to read the text to copy")?;
+    arboard::Clipboard::new()
+        .context("Failed to access the system clipboard")?
+        .set()
+        .wait()
+        .text(text)
+        .context("Failed to copy to the system clipboard")
+}
+
+/// Clipboard access is unavailable without the `clipboard` feature
+#[cfg(not(feature = "clipboard"))]
+pub fn copy_to_clipboard(_text: &str) -> Result<()> {
+    anyhow::bail!("Clipboard support requires the `clipboard` feature")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"% Abuse contact for 'AS3333' is 'abuse@ripe.net'
+
+aut-num:        AS3333
+as-name:        RIPE-NCC-AS
+admin-c:        BRD-RIPE
+tech-c:         OPS4-RIPE
+tech-c:         GII-RIPE
+
+role:           Abuse Contact
+abuse-mailbox:  abuse@ripe.net
+"#;
+
+    #[test]
+    fn test_extract_single_field() {
+        assert_eq!(extract_field_values(SAMPLE, "abuse-mailbox"), vec!["abuse@ripe.net"]);
+        assert_eq!(extract_field_values(SAMPLE, "as-name"), vec!["RIPE-NCC-AS"]);
+    }
+
+    #[test]
+    fn test_extract_case_insensitive() {
+        assert_eq!(extract_field_values(SAMPLE, "AUT-NUM"), vec!["AS3333"]);
+        assert_eq!(extract_field_values(SAMPLE, "aut-num:"), vec!["AS3333"]);
+    }
+
+    #[test]
+    fn test_extract_multiple_values() {
+        assert_eq!(extract_field_values(SAMPLE, "tech-c"), vec!["OPS4-RIPE", "GII-RIPE"]);
+    }
+
+    #[test]
+    fn test_extract_ignores_comments_and_missing() {
+        assert!(extract_field_values(SAMPLE, "abuse contact for 'as3333' is 'abuse@ripe.net'").is_empty());
+        assert!(extract_field_values(SAMPLE, "org").is_empty());
+    }
+}
@@ -1,4 +1,14 @@
+use clap::ValueEnum;
 use colored::*;
+use serde::Deserialize;
+use std::borrow::Cow;
+
+use crate::dates;
+use crate::hyperlink::{self, LINK_END};
+use crate::palettes::Palette;
+
+/// Days before an expiration date at which it is flagged
+pub const EXPIRY_WARNING_DAYS: i64 = 30;
 
 #[derive(Debug, Clone, Copy)]
 pub enum ColorScheme {
@@ -8,6 +18,123 @@ pub enum ColorScheme {
     None,
 }
 
+/// Colors used for values that carry a meaning (good, warning, bad)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Theme {
+    /// Red, yellow and green
+    #[default]
+    Default,
+    /// Bold bright colors, no dimmed text
+    HighContrast,
+    /// Blue, orange and vermillion, told apart with red-green color blindness
+    #[value(alias = "colorblind")]
+    Deuteranopia,
+}
+
+/// Meaning of a value, shown by its color and, with `--symbols`, a marker
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tone {
+    Good,
+    Warning,
+    Bad,
+}
+
+impl Tone {
+    /// Marker put in front of the value with `--symbols`
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Tone::Good => "[ok]",
+            Tone::Warning => "[!]",
+            Tone::Bad => "[x]",
+        }
+    }
+
+    /// Text in the color of this tone for a theme
+    pub fn paint(self, text: &str, theme: Theme) -> String {
+        let mut painted = String::with_capacity(text.len() + 16);
+        self.ink(theme).write(&mut painted, text);
+        painted
+    }
+
+    fn ink(self, theme: Theme) -> Ink {
+        match (theme, self) {
+            (Theme::Default, Tone::Good) => Ink::new(Color::BrightGreen),
+            (Theme::Default, Tone::Warning) => Ink::new(Color::BrightYellow),
+            (Theme::Default, Tone::Bad) => Ink::new(Color::BrightRed),
+            (Theme::HighContrast, Tone::Good) => Ink::new(Color::BrightGreen).bold(),
+            (Theme::HighContrast, Tone::Warning) => Ink::new(Color::BrightYellow).bold(),
+            (Theme::HighContrast, Tone::Bad) => Ink::new(Color::BrightRed).bold().reversed(),
+            // Okabe-Ito sky blue, orange and vermillion
+            (Theme::Deuteranopia, Tone::Good) => Ink::new(Color::TrueColor { r: 86, g: 180, b: 233 }),
+            (Theme::Deuteranopia, Tone::Warning) => Ink::new(Color::TrueColor { r: 230, g: 159, b: 0 }).bold(),
+            (Theme::Deuteranopia, Tone::Bad) => Ink::new(Color::TrueColor { r: 213, g: 94, b: 0 }).bold(),
+        }
+    }
+}
+
+/// A color and style written straight into an output buffer, byte for byte what `colored`
+/// writes but without the two `String`s a `ColoredString` costs per token
+#[derive(Debug, Clone, Copy)]
+struct Ink {
+    color: Color,
+    bold: bool,
+    reversed: bool,
+}
+
+impl Ink {
+    const fn new(color: Color) -> Self {
+        Self { color, bold: false, reversed: false }
+    }
+
+    const fn bold(mut self) -> Self {
+        self.bold = true;
+        self
+    }
+
+    const fn reversed(mut self) -> Self {
+        self.reversed = true;
+        self
+    }
+
+    /// Append `text` in this ink, or plain when colors are off (`NO_COLOR`, not a terminal, ...)
+    fn write(self, out: &mut String, text: &str) {
+        if !control::SHOULD_COLORIZE.should_colorize() {
+            out.push_str(text);
+            return;
+        }
+        let start = out.len();
+        out.push_str("\x1B[");
+        if self.bold {
+            out.push_str("1;");
+        }
+        if self.reversed {
+            out.push_str("7;");
+        }
+        out.push_str(&self.color.to_fg_str());
+        out.push('m');
+        if text.contains(RESET) {
+            // As `colored` does, restore the style after a reset inside the text
+            let style = out[start..].to_string();
+            out.push_str(&text.replace(RESET, &format!("{}{}", RESET, style)));
+        } else {
+            out.push_str(text);
+        }
+        out.push_str(RESET);
+    }
+}
+
+const RESET: &str = "\x1B[0m";
+
+/// Lower case text, borrowed when it already is
+fn lowercase(text: &str) -> Cow<'_, str> {
+    if text.chars().any(char::is_uppercase) {
+        Cow::Owned(text.to_lowercase())
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
 pub struct OutputColorizer;
 
 impl OutputColorizer {
@@ -22,14 +149,144 @@ impl OutputColorizer {
 
     /// Apply colorization based on the scheme
     pub fn colorize(output: &str, scheme: ColorScheme) -> String {
+        Self::colorize_themed(output, scheme, Theme::Default)
+    }
+
+    /// Apply colorization based on the scheme, with the semantic colors of a theme
+    pub fn colorize_themed(output: &str, scheme: ColorScheme, theme: Theme) -> String {
+        Self::colorize_linked(output, scheme, theme, false)
+    }
+
+    /// Apply colorization and, with `links`, RIR database hyperlinks in the same pass, so
+    /// values are colored by their own text rather than by link escape codes
+    pub fn colorize_linked(output: &str, scheme: ColorScheme, theme: Theme, links: bool) -> String {
         match scheme {
-            ColorScheme::Ripe => Self::colorize_ripe(output),
+            ColorScheme::Ripe => Self::colorize_ripe(output, theme, links),
             ColorScheme::BgpTools => Self::colorize_bgptools(output),
-            ColorScheme::Mtf => Self::colorize_mtf(output),
+            ColorScheme::Mtf => Self::colorize_palette(output, &Palette::trans(), theme, links),
+            ColorScheme::None if links => hyperlink::RirHyperlinkProcessor::new().process(output),
             ColorScheme::None => output.to_string(),
         }
     }
 
+    /// Call `f` with each line and, with `links` on a terminal that shows them, the URL and
+    /// linked length of its value
+    fn each_line<'a>(output: &'a str, links: bool, mut f: impl FnMut(&'a str, Option<(String, usize)>)) {
+        if links && hyperlink::terminal_supports_hyperlinks() {
+            hyperlink::for_each_line(output, |line, rir| f(line, rir.and_then(|rir| hyperlink::attribute_link(rir, line))));
+        } else {
+            output.lines().for_each(|line| f(line, None));
+        }
+    }
+
+    /// Put a marker such as `[!]` in front of values with a meaning, so it does not rest on color alone
+    /// Runs on plain text, before colorization
+    pub fn mark_symbols(output: &str) -> String {
+        let lines: Vec<String> = output
+            .lines()
+            .map(|line| {
+                if line.starts_with('%') || line.starts_with('#') {
+                    return line.to_string();
+                }
+                if let Some((field, rest)) = line.split_once(':') {
+                    let value = rest.trim_start();
+                    if let Some(tone) = Self::value_tone(field.trim(), value) {
+                        let spacing = &rest[..rest.len() - value.len()];
+                        return format!("{}:{}{} {}", field, spacing, tone.symbol(), value);
+                    }
+                    if !field.trim().is_empty() {
+                        return line.to_string();
+                    }
+                }
+                match Self::line_tone(line) {
+                    Some(tone) => format!("{} {}", tone.symbol(), line),
+                    None => line.to_string(),
+                }
+            })
+            .collect();
+        lines.join("\n")
+    }
+
+    /// Meaning of a field value: status, DNSSEC state or how close an expiration date is
+    pub fn value_tone(field: &str, value: &str) -> Option<Tone> {
+        let field = lowercase(field);
+        let value = Self::strip_symbol(value.trim());
+        if value.is_empty() {
+            return None;
+        }
+        if field == "status" || field == "domain status" {
+            Self::status_tone(value)
+        } else if field.contains("dnssec") {
+            let value = value.to_lowercase();
+            if value.contains("unsigned") || value == "no" {
+                Some(Tone::Bad)
+            } else if value.contains("signed") || value.contains("yes") {
+                Some(Tone::Good)
+            } else {
+                Some(Tone::Bad)
+            }
+        } else if field.contains("expir") || field == "paid-till" {
+            Self::expiry_tone(value, dates::today())
+        } else {
+            None
+        }
+    }
+
+    /// Meaning of a line that is not a field: errors and availability notices
+    fn line_tone(line: &str) -> Option<Tone> {
+        let line_lower = lowercase(line);
+        if line_lower.contains("error") || line_lower.contains("not found") || line_lower.contains("no match") {
+            Some(Tone::Bad)
+        } else if line_lower.contains("available") {
+            Some(Tone::Good)
+        } else {
+            None
+        }
+    }
+
+    fn status_tone(value: &str) -> Option<Tone> {
+        let value = value.to_lowercase();
+        // Some registries spell statuses with spaces; EPP statuses are often followed by an ICANN link
+        let status = match value.as_str() {
+            "client ok" | "pending delete" => value.replace(' ', ""),
+            _ => value.split_whitespace().next().unwrap_or_default().to_string(),
+        };
+        match status.as_str() {
+            "ok" | "active" | "assigned" | "allocated" | "clientok" => Some(Tone::Good),
+            "inactive" | "pendingdelete" | "redemptionperiod" | "serverhold" | "clienthold" => Some(Tone::Bad),
+            _ => None,
+        }
+    }
+
+    /// `Bad` for a date in the past, `Warning` within `EXPIRY_WARNING_DAYS`
+    /// Only `YYYY-MM-DD` dates (with `-`, `.` or `/`) are understood
+    fn expiry_tone(value: &str, today: i64) -> Option<Tone> {
+        let days = dates::parse_day(value)?;
+        if days < today {
+            Some(Tone::Bad)
+        } else if days - today <= EXPIRY_WARNING_DAYS {
+            Some(Tone::Warning)
+        } else {
+            None
+        }
+    }
+
+    /// Value without a marker added by `mark_symbols`
+    fn strip_symbol(value: &str) -> &str {
+        [Tone::Good, Tone::Warning, Tone::Bad]
+            .iter()
+            .find_map(|tone| value.strip_prefix(tone.symbol()).map(str::trim_start))
+            .unwrap_or(value)
+    }
+
+    /// Ink of comment lines: dark grey, or plain white in high contrast
+    fn muted(theme: Theme) -> Ink {
+        match theme {
+            Theme::HighContrast => Ink::new(Color::White),
+            _ => Ink::new(Color::BrightBlack),
+        }
+    }
+
     /// Detect if the output is in BGP Tools format
     fn is_bgp_tools_format(output: &str) -> bool {
         let lines: Vec<&str> = output.lines().collect();
@@ -44,288 +301,299 @@ impl OutputColorizer {
         false
     }
 
-    /// Colorize RIPE format output (field: value pairs)
-    fn colorize_ripe(output: &str) -> String {
-        let mut colored_lines = Vec::new();
-        let mut in_comment_block = false;
+    /// Colorize RIPE format output (field: value pairs), written into one buffer so
+    /// multi-megabyte IRR answers cost no allocation per line or token
+    fn colorize_ripe(output: &str, theme: Theme, links: bool) -> String {
+        let mut colored = String::with_capacity(output.len() * 2);
+        Self::each_line(output, links, |line, link| {
+            Self::write_ripe_line(&mut colored, line, link, theme);
+            colored.push('\n');
+        });
+        colored.pop();
+        colored
+    }
+
+    fn write_ripe_line(out: &mut String, line: &str, link: Option<(String, usize)>, theme: Theme) {
+        // Handle comment lines
+        if line.starts_with('%') || line.starts_with('#') || line.starts_with("remarks:") {
+            Self::muted(theme).write(out, line);
+            return;
+        }
         
-        for line in output.lines() {
-            // Handle comment lines
-            if line.starts_with('%') || line.starts_with('#') || line.starts_with("remarks:") {
-                colored_lines.push(line.bright_black().to_string());
-                in_comment_block = true;
-                continue;
-            }
-            
-            // Comment block state management
-            if in_comment_block && line.trim().is_empty() {
-                colored_lines.push(line.to_string());
-                continue;
-            }
-            
-            if in_comment_block && !line.trim().is_empty() {
-                in_comment_block = false;
-            }
-            
-            // Handle empty lines
-            if line.trim().is_empty() {
-                colored_lines.push(line.to_string());
-                continue;
-            }
-            
-            // Handle field: value pairs
-            if line.contains(':') {
-                if let Some(colored_line) = Self::colorize_field_value_pair(line) {
-                    colored_lines.push(colored_line);
-                    continue;
-                }
-            }
-            
-            // Handle special cases
-            colored_lines.push(Self::colorize_special_lines(line));
+        // Handle empty lines
+        if line.trim().is_empty() {
+            out.push_str(line);
+            return;
         }
         
-        colored_lines.join("\n")
-    }
-
-    /// Colorize a field: value pair
-    fn colorize_field_value_pair(line: &str) -> Option<String> {
-        let parts: Vec<&str> = line.splitn(2, ':').collect();
-        if parts.len() != 2 {
-            return None;
+        // Handle field: value pairs
+        if let Some((field, rest)) = line.split_once(':') {
+            Self::write_field_value_pair(out, field, rest, link, theme);
+            return;
         }
-
-        let field = parts[0].trim();
-        let value = parts[1].trim();
         
-        let colored_field = Self::colorize_field_name(field);
-        let colored_value = Self::colorize_field_value(field, value);
+        // Handle special cases
+        Self::write_special_line(out, line, theme);
+    }
+
+    /// Colorize a field: value pair, keeping the spacing that aligns the value
+    fn write_field_value_pair(out: &mut String, field: &str, rest: &str, link: Option<(String, usize)>, theme: Theme) {
+        let indent = &field[..field.len() - field.trim_start().len()];
+        let field = field.trim();
+        let value = rest.trim();
+        let spacing = if value.is_empty() { "" } else { &rest[..rest.len() - rest.trim_start().len()] };
         
-        Some(format!("{}: {}", colored_field, colored_value))
+        out.push_str(indent);
+        Self::field_ink(field, theme).write(out, field);
+        out.push(':');
+        out.push_str(spacing);
+        Self::write_field_value(out, field, value, link, theme);
     }
 
-    /// Colorize field names based on their type
-    fn colorize_field_name(field: &str) -> String {
-        match field.to_lowercase().as_str() {
+    /// Ink of a field name, by the field's type
+    fn field_ink(field: &str, theme: Theme) -> Ink {
+        let ink = match lowercase(field).as_ref() {
             // Network and AS fields
             "aut-num" | "as-block" | "inet6num" | "inetnum" | "route" | "route6" | "netname" =>
-                field.bright_cyan().to_string(),
+                Ink::new(Color::BrightCyan),
             
             // Domain fields
             "domain" | "domain name" =>
-                field.bright_cyan().bold().to_string(),
+                Ink::new(Color::BrightCyan).bold(),
             
             // DNS fields
             "nserver" | "name server" | "nameserver" | "name servers" =>
-                field.yellow().bold().to_string(),
+                Ink::new(Color::Yellow).bold(),
             
             // Status fields
             "domain status" | "status" =>
-                field.bright_yellow().to_string(),
+                Ink::new(Color::BrightYellow),
             
             // Registrar fields
             "registrar" | "sponsoring registrar" | "registrar iana id" | "reseller" =>
-                field.bright_blue().to_string(),
+                Ink::new(Color::BrightBlue),
             
             // Registry fields
             "registry domain id" | "registrar whois server" | "registrar url" =>
-                field.blue().to_string(),
+                Ink::new(Color::Blue),
             
             // Date fields
             "creation date" | "created" | "created on" | "registration date" |
             "updated date" | "last modified" | "last update" | "changed" |
             "expiration date" | "expiry date" | "registry expiry date" | 
             "registrar registration expiration date" =>
-                field.bright_magenta().to_string(),
+                Ink::new(Color::BrightMagenta),
             
             // Privacy fields
             "privacy" | "whois privacy" | "domain privacy" =>
-                field.bright_red().to_string(),
+                Ink::new(Color::BrightRed),
             
             // Name fields
             "as-name" | "org-name" | "role" | "person" | "registrant name" | 
             "admin name" | "tech name" =>
-                field.bright_green().to_string(),
+                Ink::new(Color::BrightGreen),
             
             // Organization fields
             "org" | "organisation" | "org-type" | "registrant organization" | "registrant" =>
-                field.yellow().to_string(),
+                Ink::new(Color::Yellow),
             
             // Contact fields
             "admin-c" | "tech-c" | "abuse-c" | "nic-hdl" | "abuse-mailbox" |
             "registrant contact" | "admin contact" | "technical contact" | "billing contact" =>
-                field.green().to_string(),
+                Ink::new(Color::Green),
             
             // Maintainer fields
             "mnt-by" | "mnt-ref" | "mnt-domains" | "mnt-lower" | "mnt-routes" =>
-                field.bright_blue().to_string(),
+                Ink::new(Color::BrightBlue),
             
             // Routing fields
             "import" | "export" | "mp-import" | "mp-export" | "default" | "peer" =>
-                field.magenta().to_string(),
+                Ink::new(Color::Magenta),
             
             // Location fields
             "country" | "address" | "source" | "registrant country" | 
             "admin country" | "tech country" =>
-                field.bright_white().to_string(),
+                Ink::new(Color::BrightWhite),
             
             // Communication fields
             "e-mail" | "email" | "phone" | "registrant email" | "admin email" | "tech email" =>
-                field.blue().to_string(),
+                Ink::new(Color::Blue),
             
             // DNSSEC fields
             "dnssec" | "ds record" =>
-                field.magenta().bold().to_string(),
+                Ink::new(Color::Magenta).bold(),
             
             // Special org field
             "sponsoring-org" =>
-                field.bright_yellow().to_string(),
+                Ink::new(Color::BrightYellow),
             
             // Default
-            _ => field.white().to_string(),
+            _ => Ink::new(Color::White),
+        };
+        match theme {
+            Theme::HighContrast => ink.bold(),
+            _ => ink,
         }
     }
 
     /// Colorize field values based on content and context
-    fn colorize_field_value(field: &str, value: &str) -> String {
-        let field_lower = field.to_lowercase();
+    /// `link` is the URL of the value and the length of the value prefix it covers
+    fn write_field_value(out: &mut String, field: &str, value: &str, link: Option<(String, usize)>, theme: Theme) {
+        match (Self::value_ink(field, value, theme), link) {
+            (Some(ink), Some((url, length))) if length <= value.len() => {
+                hyperlink::push_link_start(out, &url);
+                ink.write(out, &value[..length]);
+                out.push_str(LINK_END);
+                if length < value.len() {
+                    ink.write(out, &value[length..]);
+                }
+            }
+            (Some(ink), _) => ink.write(out, value),
+            (None, _) => Self::write_routing_policy(out, value),
+        }
+    }
+
+    /// Ink of a field value, by its field and content; `None` for routing policies,
+    /// which are colored token by token
+    fn value_ink(field: &str, value: &str, theme: Theme) -> Option<Ink> {
+        let field_lower = lowercase(field);
+        
+        // Values with a meaning: status, DNSSEC, upcoming expiration
+        if let Some(tone) = Self::value_tone(field, value) {
+            return Some(tone.ink(theme));
+        }
         
         // Domain names
         if field_lower == "domain" || field_lower == "domain name" {
-            return value.bright_white().bold().to_string();
+            return Some(Ink::new(Color::BrightWhite).bold());
         }
         
         // AS Numbers
         if field_lower == "aut-num" {
-            return value.bright_red().bold().to_string();
+            return Some(Ink::new(Color::BrightRed).bold());
         }
         
         // Status values
         if field_lower == "status" || field_lower == "domain status" {
-            return Self::colorize_status_value(value);
+            return Some(Self::status_value_ink(value));
         }
         
         // Source registry
         if field_lower == "source" {
-            return value.bright_blue().to_string();
+            return Some(Ink::new(Color::BrightBlue));
         }
         
         // Country codes
         if field_lower == "country" || field_lower.contains("country") {
-            return value.yellow().to_string();
+            return Some(Ink::new(Color::Yellow));
         }
         
         // Name servers
         if field_lower.contains("name server") || field_lower.contains("nserver") || 
            field_lower == "nameserver" {
-            return value.bright_green().to_string();
+            return Some(Ink::new(Color::BrightGreen));
         }
         
         // Registrar information
         if field_lower.contains("registrar") {
-            return value.bright_blue().bold().to_string();
-        }
-        
-        // DNSSEC status
-        if field_lower.contains("dnssec") {
-            return if value.to_lowercase().contains("signed") || value.to_lowercase().contains("yes") {
-                value.bright_green().to_string()
-            } else {
-                value.bright_red().to_string()
-            };
+            return Some(Ink::new(Color::BrightBlue).bold());
         }
         
         // Dates
         if field_lower.contains("date") || field_lower.contains("created") || 
            field_lower.contains("changed") || field_lower.contains("expir") || 
            field_lower.contains("update") {
-            return value.bright_magenta().to_string();
+            return Some(Ink::new(Color::BrightMagenta));
         }
         
         // Email addresses
         if value.contains('@') {
-            return value.bright_yellow().to_string();
+            return Some(Ink::new(Color::BrightYellow));
         }
         
         // Phone numbers
         if field_lower.contains("phone") {
-            return value.bright_white().to_string();
+            return Some(Ink::new(Color::BrightWhite));
         }
         
         // AS numbers in values
-        if value.starts_with("AS") && value.len() > 2 && value[2..].chars().all(|c| c.is_digit(10)) {
-            return value.bright_red().to_string();
+        if Self::is_single_asn(value) {
+            return Some(Ink::new(Color::BrightRed));
         }
         
         // Import/Export specialized coloring
         if (field == "import" || field == "export") && value.contains("AS") {
-            return Self::colorize_routing_policy(value);
+            return None;
         }
         
         // IP addresses and CIDR blocks
         if Self::looks_like_ip_or_cidr(value) {
-            return value.bright_cyan().to_string();
+            return Some(Ink::new(Color::BrightCyan));
         }
         
         // Maintainer values
         if field.starts_with("mnt-") {
             return if value.contains("-") {
-                value.bright_blue().to_string()
+                Some(Ink::new(Color::BrightBlue))
             } else {
-                value.white().to_string()
+                Some(Ink::new(Color::White))
             };
         }
         
         // Names
         if field_lower == "as-name" || field_lower == "org-name" || field_lower == "netname" {
-            return value.bright_white().bold().to_string();
+            return Some(Ink::new(Color::BrightWhite).bold());
         }
         
         // Person/role names
         if field_lower == "role" || field_lower == "person" || 
            field_lower.contains("registrant name") {
-            return value.bright_green().bold().to_string();
+            return Some(Ink::new(Color::BrightGreen).bold());
         }
         
         // Handles
         if field.ends_with("-c") {
-            return value.green().to_string();
+            return Some(Ink::new(Color::Green));
         }
         
         // Default
-        value.white().to_string()
+        match theme {
+            Theme::HighContrast => Some(Ink::new(Color::BrightWhite)),
+            _ => Some(Ink::new(Color::White)),
+        }
     }
 
-    /// Colorize status values
-    fn colorize_status_value(value: &str) -> String {
-        match value.to_uppercase().as_str() {
-            "ASSIGNED" | "ALLOCATED" => value.bright_green().to_string(),
-            "AVAILABLE" => value.bright_cyan().to_string(),
-            "RESERVED" => value.yellow().to_string(),
-            "CLIENT DELETE PROHIBITED" | "CLIENT TRANSFER PROHIBITED" | 
-            "CLIENT UPDATE PROHIBITED" => value.bright_yellow().to_string(),
-            "INACTIVE" | "PENDING DELETE" => value.bright_red().to_string(),
-            "OK" | "ACTIVE" | "CLIENT OK" => value.bright_green().to_string(),
-            _ => value.bright_yellow().to_string(),
+    /// Ink of status values without a meaning of their own
+    fn status_value_ink(value: &str) -> Ink {
+        if value.eq_ignore_ascii_case("available") {
+            Ink::new(Color::BrightCyan)
+        } else if value.eq_ignore_ascii_case("reserved") {
+            Ink::new(Color::Yellow)
+        } else {
+            Ink::new(Color::BrightYellow)
         }
     }
 
+    /// `AS` followed by digits only
+    fn is_single_asn(value: &str) -> bool {
+        value.starts_with("AS") && value.len() > 2 && value[2..].chars().all(|c| c.is_ascii_digit())
+    }
+
     /// Colorize routing policy lines (import/export)
-    fn colorize_routing_policy(value: &str) -> String {
-        let mut colored_parts = Vec::new();
-        let parts: Vec<&str> = value.split_whitespace().collect();
-        
-        for part in parts {
-            if part.starts_with("AS") && part.len() > 2 && part[2..].chars().all(|c| c.is_digit(10)) {
-                colored_parts.push(part.bright_red().to_string());
+    fn write_routing_policy(out: &mut String, value: &str) {
+        for (index, part) in value.split_whitespace().enumerate() {
+            if index > 0 {
+                out.push(' ');
+            }
+            let ink = if Self::is_single_asn(part) {
+                Ink::new(Color::BrightRed)
             } else if matches!(part, "from" | "to" | "accept" | "announce") {
-                colored_parts.push(part.bright_cyan().to_string());
+                Ink::new(Color::BrightCyan)
             } else {
-                colored_parts.push(part.white().to_string());
-            }
+                Ink::new(Color::White)
+            };
+            ink.write(out, part);
         }
-        
-        colored_parts.join(" ")
     }
 
     /// Check if a string looks like an IP address or CIDR block
@@ -334,17 +602,13 @@ impl OutputColorizer {
     }
 
     /// Colorize special lines (errors, availability, etc.)
-    fn colorize_special_lines(line: &str) -> String {
-        let line_lower = line.to_lowercase();
-        
-        if line_lower.contains("error") || line_lower.contains("not found") || 
-           line_lower.contains("no match") {
-            line.bright_red().to_string()
-        } else if line_lower.contains("available") {
-            line.bright_green().to_string()
-        } else {
-            line.white().to_string()
-        }
+    fn write_special_line(out: &mut String, line: &str, theme: Theme) {
+        let ink = match Self::line_tone(line) {
+            Some(tone) => tone.ink(theme),
+            None if theme == Theme::HighContrast => Ink::new(Color::BrightWhite),
+            None => Ink::new(Color::White),
+        };
+        ink.write(out, line);
     }
 
     /// Colorize BGP Tools format output (table format)
@@ -395,31 +659,132 @@ impl OutputColorizer {
         colored_lines.join("\n")
     }
 
-    /// MTF flag coloring (trans flag pattern)
-    fn colorize_mtf(output: &str) -> String {
+    /// Palette overlay: field names and comment lines take the stripe color of their line,
+    /// values keep their usual colors and the original alignment is preserved
+    /// With `links`, values link to the RIR databases as in `colorize_linked`
+    pub fn colorize_palette(output: &str, palette: &Palette, theme: Theme, links: bool) -> String {
         let mut colored_lines = Vec::new();
         let mut line_count = 0;
         
-        for line in output.lines() {
-            if line.trim().is_empty() {
+        Self::each_line(output, links, |line, link| {
+            if line.trim().is_empty() || palette.colors.is_empty() {
                 colored_lines.push(line.to_string());
-                continue;
+                return;
             }
             
-            // Trans flag pattern: blue, pink, white, pink, blue
-            let colored_line = match line_count % 5 {
-                0 => line.truecolor(91, 207, 250).to_string(),   // Blue #5BCFFA
-                1 => line.truecolor(245, 171, 185).to_string(),  // Pink #F5ABB9
-                2 => line.truecolor(255, 255, 255).to_string(),  // White #FFFFFF
-                3 => line.truecolor(245, 171, 185).to_string(),  // Pink #F5ABB9
-                4 => line.truecolor(91, 207, 250).to_string(),   // Blue #5BCFFA
-                _ => unreachable!(),
-            };
-            
-            colored_lines.push(colored_line);
+            let (r, g, b) = palette.colors[line_count % palette.colors.len()];
             line_count += 1;
-        }
+            
+            if line.starts_with('%') || line.starts_with('#') {
+                let comment = line.truecolor(r, g, b);
+                colored_lines.push(match theme {
+                    Theme::HighContrast => comment.to_string(),
+                    _ => comment.dimmed().to_string(),
+                });
+                return;
+            }
+            match line.split_once(':') {
+                Some((field, rest)) if !field.trim().is_empty() && !rest.starts_with("//") => {
+                    let value = rest.trim_start();
+                    let spacing = &rest[..rest.len() - value.len()];
+                    let mut colored_line = format!("{}:{}", field.truecolor(r, g, b).bold(), spacing);
+                    Self::write_field_value(&mut colored_line, field.trim(), value.trim_end(), link, theme);
+                    colored_lines.push(colored_line);
+                }
+                _ => colored_lines.push(line.truecolor(r, g, b).to_string()),
+            }
+        });
         
         colored_lines.join("\n")
     }
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_palette_keeps_alignment() {
+        let input = "% comment\naut-num:        AS3333\n\nremarks:        https://example.net\nsource:         RIPE";
+        let output = OutputColorizer::colorize_palette(input, &Palette::builtin("rainbow").unwrap(), Theme::HighContrast, false);
+        assert_eq!(crate::ansi::strip_ansi(&output), input);
+    }
+
+    #[test]
+    fn test_field_alignment_survives_coloring() {
+        let input = "aut-num:        AS3333\nas-name:\tRIPE-NCC-AS\nremarks:\ndescr:x";
+        let output = OutputColorizer::colorize(input, ColorScheme::Ripe);
+        assert_eq!(crate::ansi::strip_ansi(&output), input);
+    }
+
+    #[test]
+    fn test_ink_writes_what_colored_writes() {
+        colored::control::set_override(true);
+        let mut written = String::new();
+        Ink::new(Color::BrightRed).bold().reversed().write(&mut written, "AS3333");
+        assert_eq!(written, "AS3333".bright_red().bold().reversed().to_string());
+        assert_eq!(Tone::Good.paint("ok", Theme::Deuteranopia), "ok".truecolor(86, 180, 233).to_string());
+        let nested = format!("{} link", "inner".green());
+        assert_eq!(Tone::Bad.paint(&nested, Theme::Default), nested.bright_red().to_string());
+
+        let colored = OutputColorizer::colorize("import:         from AS1103 accept ANY\nsource:         RIPE", ColorScheme::Ripe);
+        let expected = format!(
+            "{}:{}{} {} {} {}\n{}:{}{}",
+            "import".magenta(),
+            " ".repeat(9),
+            "from".bright_cyan(),
+            "AS1103".bright_red(),
+            "accept".bright_cyan(),
+            "ANY".white(),
+            "source".bright_white(),
+            " ".repeat(9),
+            "RIPE".bright_blue()
+        );
+        assert_eq!(colored, expected);
+    }
+
+    #[test]
+    fn test_colorize_linked() {
+        colored::control::set_override(true);
+        let input = "route:          193.0.0.0/21\norigin:         AS3333\nsource:         RIPE";
+        let output = OutputColorizer::colorize_linked(input, ColorScheme::Ripe, Theme::Default, true);
+        assert_eq!(crate::ansi::strip_ansi(&output), input);
+        let segments = crate::ansi::parse_segments(&output);
+        let origin = segments.iter().find(|segment| segment.text == "AS3333").unwrap();
+        // Colored as an ASN, which the link escape codes used to hide from the colorizer
+        assert_eq!(origin.style.fg, Some(crate::ansi::AnsiColor::Basic(9)));
+        assert!(origin.link.as_deref().unwrap().ends_with("searchtext=AS3333"));
+        assert!(segments.iter().find(|segment| segment.text == "RIPE").unwrap().link.is_none());
+    }
+
+    #[test]
+    fn test_value_tones() {
+        assert_eq!(OutputColorizer::value_tone("Domain Status", "ok https://icann.org/epp#ok"), Some(Tone::Good));
+        assert_eq!(OutputColorizer::value_tone("status", "PENDING DELETE"), Some(Tone::Bad));
+        assert_eq!(OutputColorizer::value_tone("status", "clientTransferProhibited"), None);
+        assert_eq!(OutputColorizer::value_tone("DNSSEC", "unsigned"), Some(Tone::Bad));
+        assert_eq!(OutputColorizer::value_tone("DNSSEC", "signedDelegation"), Some(Tone::Good));
+        assert_eq!(OutputColorizer::value_tone("source", "RIPE"), None);
+    }
+
+    #[test]
+    fn test_expiry_tone() {
+        let today = dates::days_from_civil(2026, 10, 16).unwrap();
+        assert_eq!(OutputColorizer::expiry_tone("2026-10-01T00:00:00Z", today), Some(Tone::Bad));
+        assert_eq!(OutputColorizer::expiry_tone("2026-11-05T12:00:00Z", today), Some(Tone::Warning));
+        assert_eq!(OutputColorizer::expiry_tone("2027.10.16", today), None);
+        assert_eq!(OutputColorizer::expiry_tone("16-Oct-2026", today), None);
+    }
+
+    #[test]
+    fn test_mark_symbols() {
+        let input = "% status: comment\nDomain Status: ok\nDNSSEC:       unsigned\nRegistrar: Example\nNo match for \"EXAMPLE.TEST\".";
+        let marked = OutputColorizer::mark_symbols(input);
+        assert_eq!(
+            marked,
+            "% status: comment\nDomain Status: [ok] ok\nDNSSEC:       [x] unsigned\nRegistrar: Example\n[x] No match for \"EXAMPLE.TEST\"."
+        );
+        let colored = OutputColorizer::colorize_themed(&marked, ColorScheme::Ripe, Theme::Deuteranopia);
+        assert!(colored.contains(&"[x] unsigned".truecolor(213, 94, 0).bold().to_string()));
+    }
+}
@@ -0,0 +1,244 @@
+use colored::*;
+use unicode_width::UnicodeWidthChar;
+
+use crate::dates;
+use crate::fingerprint::fingerprint;
+use crate::history::{diff_lines, object_text, DiffLine};
+use crate::i18n::pad;
+use crate::tr;
+
+/// Columns taken by the marker between the two sides of a side-by-side diff
+const GUTTER: usize = 3;
+
+/// One server's answer to the compared query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Answer {
+    pub server: String,
+    /// Object lines without `%` comments, or why the query failed
+    pub text: Result<String, String>,
+}
+
+impl Answer {
+    pub fn new(server: impl Into<String>, response: Result<String, String>) -> Self {
+        Self { server: server.into(), text: response.map(|response| object_text(&response)) }
+    }
+}
+
+/// How an answer compares with the reference, the first server that answered
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Reference,
+    Identical,
+    /// Lines only in this answer and only in the reference's
+    Differs { added: usize, removed: usize },
+    /// Differs and was last modified before the reference, as a lagging mirror would be
+    Stale { added: usize, removed: usize },
+    Empty,
+    Failed(String),
+}
+
+/// Newest `last-modified:` or `changed:` date of the objects in an answer, as days since 1970-01-01
+pub fn last_modified(text: &str) -> Option<i64> {
+    text.lines()
+        .filter_map(|line| {
+            let (attribute, value) = line.split_once(':')?;
+            match attribute.trim().to_lowercase().as_str() {
+                "last-modified" => dates::parse_day(value),
+                // RPSL `changed: hostmaster@example.net 20030311`
+                "changed" => {
+                    let date = value.split_whitespace().last()?;
+                    let date = date.get(..8).filter(|date| date.chars().all(|c| c.is_ascii_digit()))?;
+                    dates::days_from_civil(date[..4].parse().ok()?, date[4..6].parse().ok()?, date[6..].parse().ok()?)
+                }
+                _ => None,
+            }
+        })
+        .max()
+}
+
+/// Index of the reference answer: the first one that is not empty or failed
+pub fn reference(answers: &[Answer]) -> Option<usize> {
+    answers.iter().position(|answer| matches!(&answer.text, Ok(text) if !text.is_empty()))
+}
+
+/// Verdict for every answer against the reference
+pub fn verdicts(answers: &[Answer]) -> Vec<Verdict> {
+    let reference = reference(answers);
+    let reference_text = reference.and_then(|index| answers[index].text.as_ref().ok());
+    answers
+        .iter()
+        .enumerate()
+        .map(|(index, answer)| match (&answer.text, reference_text) {
+            (Err(error), _) => Verdict::Failed(error.clone()),
+            (Ok(text), _) if text.is_empty() => Verdict::Empty,
+            _ if Some(index) == reference => Verdict::Reference,
+            (Ok(text), Some(reference_text)) => {
+                let diff = diff_lines(reference_text, text);
+                let added = diff.iter().filter(|line| matches!(line, DiffLine::Added(_))).count();
+                let removed = diff.iter().filter(|line| matches!(line, DiffLine::Removed(_))).count();
+                match (added + removed, last_modified(text), last_modified(reference_text)) {
+                    (0, _, _) => Verdict::Identical,
+                    // Only banners, timestamps or spacing differ
+                    _ if fingerprint(text) == fingerprint(reference_text) => Verdict::Identical,
+                    (_, Some(modified), Some(newest)) if modified < newest => Verdict::Stale { added, removed },
+                    _ => Verdict::Differs { added, removed },
+                }
+            }
+            (Ok(_), None) => Verdict::Empty,
+        })
+        .collect()
+}
+
+/// Table with each server's line count, last modification and verdict
+pub fn render_summary(target: &str, answers: &[Answer], verdicts: &[Verdict]) -> String {
+    let width = answers.iter().map(|answer| answer.server.len()).max().unwrap_or(0).max(tr!("compare-server").len());
+    let mut lines = vec![
+        format!("% ─── {} ───", tr!("compare-heading", target = target, count = answers.len())),
+        format!(
+            "% {} {} {} {}",
+            pad(&tr!("compare-server"), width),
+            pad(&tr!("compare-lines"), 6),
+            pad(&tr!("compare-modified"), 10),
+            tr!("compare-status")
+        ),
+    ];
+    for (answer, verdict) in answers.iter().zip(verdicts) {
+        let text = answer.text.as_deref().unwrap_or_default();
+        let modified = last_modified(text).map(dates::format_day);
+        let status = match verdict {
+            Verdict::Reference => tr!("compare-reference"),
+            Verdict::Identical => tr!("compare-identical").green().to_string(),
+            Verdict::Differs { added, removed } => tr!("compare-differs", added = added, removed = removed).yellow().to_string(),
+            Verdict::Stale { added, removed } => tr!("compare-stale", added = added, removed = removed).red().bold().to_string(),
+            Verdict::Empty => tr!("compare-empty").yellow().to_string(),
+            Verdict::Failed(error) => tr!("compare-failed", error = error).red().to_string(),
+        };
+        lines.push(format!(
+            "% {:<width$} {:<6} {:<10} {}",
+            answer.server,
+            if answer.text.is_ok() { text.lines().count().to_string() } else { "-".to_string() },
+            modified.as_deref().unwrap_or("-"),
+            status,
+            width = width
+        ));
+    }
+    lines.join("\n")
+}
+
+/// Unified diff of an answer against the reference's, removed lines red and added lines green
+pub fn render_unified(reference: &str, server: &str, diff: &[DiffLine]) -> String {
+    let mut lines = vec![
+        format!("% ─── {} ───", tr!("compare-diff", reference = reference, server = server)),
+        format!("--- {}", reference).red().to_string(),
+        format!("+++ {}", server).green().to_string(),
+    ];
+    lines.extend(diff.iter().map(|line| match line {
+        DiffLine::Same(text) => format!("  {}", text),
+        DiffLine::Added(text) => format!("+ {}", text).green().to_string(),
+        DiffLine::Removed(text) => format!("- {}", text).red().to_string(),
+    }));
+    lines.join("\n")
+}
+
+/// Side-by-side diff in `width` columns: the reference on the left, with `|` on changed
+/// lines, `<` on lines only the reference has and `>` on lines only the other server has
+pub fn render_side_by_side(reference: &str, server: &str, diff: &[DiffLine], width: usize) -> String {
+    let column = (width.saturating_sub(GUTTER) / 2).max(20);
+    let mut lines = vec![
+        format!("% ─── {} ───", tr!("compare-diff", reference = reference, server = server)),
+        format!("{} {} {}", pad(&clip(reference, column), column), " ", clip(server, column)).bold().to_string(),
+    ];
+    let row = |left: Option<&str>, marker: char, right: Option<&str>| {
+        let left = pad(&clip(left.unwrap_or_default(), column), column);
+        let right = clip(right.unwrap_or_default(), column);
+        match marker {
+            '|' => format!("{} {} {}", left.red(), "|".yellow(), right.green()),
+            '<' => format!("{} {}", left.red(), "<".red()),
+            '>' => format!("{} {} {}", " ".repeat(column), ">".green(), right.green()),
+            _ => format!("{}   {}", left, right).trim_end().to_string(),
+        }
+    };
+
+    let mut index = 0;
+    while index < diff.len() {
+        if let DiffLine::Same(text) = &diff[index] {
+            lines.push(row(Some(text), ' ', Some(text)));
+            index += 1;
+            continue;
+        }
+        // Pair a run of removed lines with the added lines that follow it
+        let mut removed = Vec::new();
+        let mut added = Vec::new();
+        while let Some(DiffLine::Removed(text)) = diff.get(index) {
+            removed.push(text.as_str());
+            index += 1;
+        }
+        while let Some(DiffLine::Added(text)) = diff.get(index) {
+            added.push(text.as_str());
+            index += 1;
+        }
+        for pair in 0..removed.len().max(added.len()) {
+            lines.push(match (removed.get(pair), added.get(pair)) {
+                (Some(left), Some(right)) => row(Some(left), '|', Some(right)),
+                (Some(left), None) => row(Some(left), '<', None),
+                (None, right) => row(None, '>', right.copied()),
+            });
+        }
+    }
+    lines.join("\n")
+}
+
+/// Cut text to at most `width` display columns
+fn clip(text: &str, width: usize) -> String {
+    let mut used = 0;
+    text.chars()
+        .take_while(|c| {
+            used += c.width().unwrap_or(0);
+            used <= width
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ansi::strip_ansi;
+
+    const RIPE: &str = "% This is the RIPE Database query service.\n\naut-num:        AS3333\nas-name:        RIPE-NCC-AS\nmnt-by:         RIPE-NCC-MNT\nlast-modified:  2024-05-02T09:00:00Z\nsource:         RIPE\n";
+    const MIRROR: &str = "aut-num:        AS3333\nas-name:        RIPE-NCC-AS\nlast-modified:  2023-01-10T09:00:00Z\nsource:         RIPE\n";
+
+    #[test]
+    fn test_verdicts() {
+        let answers = vec![
+            Answer::new("whois.ripe.net:43", Ok(RIPE.to_string())),
+            Answer::new("whois.radb.net:43", Ok(MIRROR.to_string())),
+            Answer::new("rr.example.net:43", Ok(RIPE.replace("RIPE-NCC-MNT", "RIPE-NCC-MNT  ").to_string())),
+            Answer::new("whois.altdb.net:43", Ok("% No entries found\n".to_string())),
+            Answer::new("irr.example.org:43", Err("Timed out".to_string())),
+        ];
+        assert_eq!(
+            verdicts(&answers),
+            [
+                Verdict::Reference,
+                Verdict::Stale { added: 1, removed: 2 },
+                Verdict::Identical,
+                Verdict::Empty,
+                Verdict::Failed("Timed out".to_string()),
+            ]
+        );
+        assert_eq!(last_modified("changed: hostmaster@example.net 20030311\nchanged: hostmaster@example.net 20010101"), dates::days_from_civil(2003, 3, 11));
+
+        let summary = strip_ansi(&render_summary("AS3333", &answers, &verdicts(&answers)));
+        assert!(summary.starts_with("% ─── AS3333 on 5 servers ───\n"));
+        assert!(summary.contains("% whois.radb.net:43  4      2023-01-10 Stale, differs by +1 −2"));
+    }
+
+    #[test]
+    fn test_side_by_side() {
+        let diff = diff_lines("a: 1\nb: 2\nc: 3", "a: 1\nb: 20\nd: 4\nc: 3");
+        let rendered = strip_ansi(&render_side_by_side("left", "right", &diff, 43));
+        let rows: Vec<&str> = rendered.lines().skip(2).collect();
+        assert_eq!(rows, ["a: 1                   a: 1", "b: 2                 | b: 20", "                     > d: 4", "c: 3                   c: 3"]);
+        assert_eq!(clip("源源源", 5), "源源");
+    }
+}
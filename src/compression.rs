@@ -0,0 +1,164 @@
+use std::io::Read;
+
+use anyhow::{Context, Result};
+use flate2::read::{DeflateDecoder, GzDecoder, ZlibDecoder};
+use tracing::debug;
+
+use crate::protocol::{CONTENT_ENCODING_PREFIX, ENCODING_PREFIX};
+
+/// Largest answer accepted, compressed or decoded, so a hostile server cannot exhaust memory
+pub const MAX_RESPONSE_BYTES: usize = 64 * 1024 * 1024;
+
+/// Encodings the client can decode, in order of preference
+pub const SUPPORTED_ENCODINGS: &[ContentEncoding] = &[ContentEncoding::Gzip, ContentEncoding::Deflate];
+
+/// Compression applied to a response body
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentEncoding {
+    Gzip,
+    Deflate,
+}
+
+impl ContentEncoding {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ContentEncoding::Gzip => "gzip",
+            ContentEncoding::Deflate => "deflate",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "gzip" | "x-gzip" => Some(ContentEncoding::Gzip),
+            "deflate" => Some(ContentEncoding::Deflate),
+            _ => None,
+        }
+    }
+}
+
+/// Recognize a compressed body by its magic bytes
+/// Only zlib headers with a non-text second byte are recognized, so plain text starting with "x" is left alone
+pub fn detect(bytes: &[u8]) -> Option<ContentEncoding> {
+    match bytes {
+        [0x1f, 0x8b, ..] => Some(ContentEncoding::Gzip),
+        [0x78, 0x01 | 0x9c | 0xda, ..] => Some(ContentEncoding::Deflate),
+        _ => None,
+    }
+}
+
+/// Decompress a body; `deflate` accepts both zlib-wrapped and raw streams
+pub fn decompress(bytes: &[u8], encoding: ContentEncoding) -> Result<Vec<u8>> {
+    // One byte over the limit tells a body that is too large from one that just fits
+    let limit = MAX_RESPONSE_BYTES as u64 + 1;
+    let mut output = Vec::new();
+    match encoding {
+        ContentEncoding::Gzip => {
+            GzDecoder::new(bytes)
+                .take(limit)
+                .read_to_end(&mut output)
+                .context("Failed to decode gzip response")?;
+        }
+        ContentEncoding::Deflate => {
+            if ZlibDecoder::new(bytes).take(limit).read_to_end(&mut output).is_err() {
+                output.clear();
+                DeflateDecoder::new(bytes)
+                    .take(limit)
+                    .read_to_end(&mut output)
+                    .context("Failed to decode deflate response")?;
+            }
+        }
+    }
+    if output.len() > MAX_RESPONSE_BYTES {
+        anyhow::bail!("Decoded response is larger than {} MiB", MAX_RESPONSE_BYTES / (1024 * 1024));
+    }
+    Ok(output)
+}
+
+/// Decode a raw response
+/// An `X-WHOIS-CONTENT-ENCODING` (or `X-WHOIS-ENCODING`) line announces the encoding of the rest of the body;
+/// without one, compressed bodies are recognized by their magic bytes
+pub fn decode_response(bytes: Vec<u8>) -> Result<Vec<u8>> {
+    let (encoding, body) = match split_encoding_line(&bytes) {
+        Some((name, body)) => match ContentEncoding::from_name(name) {
+            Some(encoding) => (Some(encoding), body),
+            None if name.trim().eq_ignore_ascii_case("identity") => return Ok(body.to_vec()),
+            None => anyhow::bail!("Unsupported response encoding: {}", name.trim()),
+        },
+        None => (detect(&bytes), bytes.as_slice()),
+    };
+
+    match encoding {
+        Some(encoding) => {
+            let decoded = decompress(body, encoding)?;
+            debug!("Decoded {} response: {} -> {} bytes", encoding.name(), body.len(), decoded.len());
+            Ok(decoded)
+        }
+        None => Ok(bytes),
+    }
+}
+
+/// Split off a leading `X-WHOIS-CONTENT-ENCODING: <name>` or `X-WHOIS-ENCODING: <name>` line
+fn split_encoding_line(bytes: &[u8]) -> Option<(&str, &[u8])> {
+    let rest = bytes
+        .strip_prefix(CONTENT_ENCODING_PREFIX.as_bytes())
+        .or_else(|| bytes.strip_prefix(ENCODING_PREFIX.as_bytes()))?;
+    let end = rest.iter().position(|&byte| byte == b'\n')?;
+    let name = std::str::from_utf8(&rest[..end]).ok()?.trim_end_matches('\r');
+    Some((name, &rest[end + 1..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::{GzEncoder, ZlibEncoder};
+    use flate2::Compression;
+    use std::io::Write;
+
+    const TEXT: &str = "route: 192.0.2.0/24\norigin: AS64500\nsource: RADB\n";
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn test_decode_sniffed_gzip() {
+        let decoded = decode_response(gzip(TEXT.as_bytes())).unwrap();
+        assert_eq!(decoded, TEXT.as_bytes());
+    }
+
+    #[test]
+    fn test_decode_announced_deflate() {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(TEXT.as_bytes()).unwrap();
+        let mut body = b"X-WHOIS-CONTENT-ENCODING: deflate\r\n".to_vec();
+        body.extend(encoder.finish().unwrap());
+        assert_eq!(decode_response(body).unwrap(), TEXT.as_bytes());
+
+        let identity = b"X-WHOIS-CONTENT-ENCODING: identity\r\nplain\n".to_vec();
+        assert_eq!(decode_response(identity).unwrap(), b"plain\n");
+        assert!(decode_response(b"X-WHOIS-CONTENT-ENCODING: br\r\n...".to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_decompression_bomb_refused() {
+        let bomb = gzip(&vec![b'a'; MAX_RESPONSE_BYTES + 1]);
+        assert!(bomb.len() < 1024 * 1024);
+        assert!(decode_response(bomb).unwrap_err().to_string().contains("larger than 64 MiB"));
+    }
+
+    #[test]
+    fn test_decode_short_encoding_header() {
+        let mut body = b"X-WHOIS-ENCODING: gzip\r\n".to_vec();
+        body.extend(gzip(TEXT.as_bytes()));
+        assert_eq!(decode_response(body).unwrap(), TEXT.as_bytes());
+    }
+
+    #[test]
+    fn test_plain_text_untouched() {
+        assert_eq!(decode_response(TEXT.as_bytes().to_vec()).unwrap(), TEXT.as_bytes());
+        assert_eq!(detect(b"x^2 is not compressed"), None);
+        assert_eq!(ContentEncoding::from_name("GZIP"), Some(ContentEncoding::Gzip));
+    }
+}
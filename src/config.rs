@@ -0,0 +1,423 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::classify::EmptyResultPolicy;
+use crate::colorize::Theme;
+use crate::ct::CtConfig;
+use crate::palettes::{self, Palette};
+use crate::pgp::PgpConfig;
+use crate::remotehistory::HistoryApiConfig;
+use crate::rules::ResponseRule;
+use crate::servers::{default_fallback_chain, FallbackStep, ServerProfile, ServerRegistry};
+
+/// Environment variable pointing at an alternative config file
+pub const CONFIG_ENV: &str = "WHOIS_CONFIG";
+
+/// User configuration loaded from `config.toml`
+///
+/// ```toml
+/// pride = "sunset"
+/// scheme = "deuteranopia"
+/// symbols = true
+/// fold_policy = true
+/// doh_url = "https://cloudflare-dns.com/dns-query"
+///
+/// [[fallback]]
+/// server = "whois.radb.net"
+/// on = ["empty", "timeout"]
+///
+/// [[fallback]]
+/// server = "whois.altdb.net"
+/// on = ["empty", "timeout", "rate-limited"]
+///
+/// [servers."whois.nic.example".empty_result]
+/// short_response_length = 0
+///
+/// [[servers."whois.nic.example".rules]]
+/// pattern = "^% Terms of use"
+/// action = "drop-block"
+/// disclaimer = true
+///
+/// [pgp]
+/// keyring = "~/.config/whois/dn42.gpg"
+/// trusted_keys = ["0123456789ABCDEF0123456789ABCDEF01234567"]
+///
+/// [ct]
+/// limit = 50
+///
+/// [server_overrides]
+/// "whois.ripe.net" = "10.0.0.5:4343"
+///
+/// [history_api]
+/// api_key = "at_0123456789abcdef"
+///
+/// [preset.irr]
+/// server = "whois.radb.net"
+/// flags = ["--dedup", "--group-by-type"]
+///
+/// [palettes]
+/// sunset = ["#FF6B35", "#F7C59F", "#EFEFD0", "#004E89"]
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Servers tried in order when a lookup comes back empty, times out or is rate limited
+    pub fallback: Option<Vec<FallbackStep>>,
+    /// Global empty-result heuristics
+    pub empty_result: Option<EmptyResultPolicy>,
+    /// Cleanup rules applied to every server, replacing the built-in ones
+    pub rules: Option<Vec<ResponseRule>>,
+    /// Per-server profiles keyed by host name
+    pub servers: HashMap<String, ServerProfile>,
+    /// Addresses connections to a server (`host` or `host:port`) go to instead, e.g. a lab mirror
+    pub server_overrides: HashMap<String, String>,
+    /// Public Suffix List file used to find registrable domains
+    pub public_suffix_list: Option<PathBuf>,
+    /// SQLite cache database used with `--cache` (feature `sqlite`)
+    pub cache_db: Option<PathBuf>,
+    /// Known organisations highlighted in answers, instead of `known-orgs.yaml` next to the config
+    pub known_orgs: Option<PathBuf>,
+    /// Audit log of every lookup, as with `--audit-log`
+    pub audit_log: Option<PathBuf>,
+    /// dn42 registry checkout or git URL, as with `--dn42-registry`
+    pub dn42_registry: Option<String>,
+    /// Palette used when `--pride` is not given
+    pub pride: Option<String>,
+    /// Color scheme used when `--scheme` is not given
+    pub scheme: Option<Theme>,
+    /// Always mark values with symbols, as with `--symbols`
+    pub symbols: bool,
+    /// Summarize long import/export lists, as with `--fold-policy`
+    pub fold_policy: bool,
+    /// Signature verification and trusted keys for `--verify`
+    pub pgp: PgpConfig,
+    /// Certificate transparency search used by `--ct`
+    pub ct: CtConfig,
+    /// Historical WHOIS archive for `whois history DOMAIN --remote`
+    pub history_api: HistoryApiConfig,
+    /// DNS-over-HTTPS JSON endpoint for `--mail-check` lookups
+    pub doh_url: Option<String>,
+    /// Color palettes for `--pride`, as lists of `#RRGGBB` stripes
+    pub palettes: HashMap<String, Vec<String>>,
+    /// Named flag combinations for `--preset`
+    pub preset: HashMap<String, Preset>,
+}
+
+/// Flags applied together with `--preset NAME`; flags on the command line take precedence
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Preset {
+    /// As with `-s`
+    pub server: Option<String>,
+    /// As with `-p`
+    pub port: Option<u16>,
+    /// Any other command line flags, e.g. `["--dedup", "-i", "origin"]`
+    pub flags: Vec<String>,
+}
+
+impl Preset {
+    /// The preset as command line arguments
+    pub fn args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(server) = &self.server {
+            args.extend(["--server".to_string(), server.clone()]);
+        }
+        if let Some(port) = self.port {
+            args.extend(["--port".to_string(), port.to_string()]);
+        }
+        args.extend(self.flags.iter().cloned());
+        args
+    }
+}
+
+impl Config {
+    /// Load the config from an explicit path, `$WHOIS_CONFIG`, or the default location
+    /// An explicit path must exist; a missing default file yields the default config
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        if let Some(path) = path {
+            return Self::from_file(path);
+        }
+        if let Ok(path) = std::env::var(CONFIG_ENV) {
+            return Self::from_file(Path::new(&path));
+        }
+        match Self::default_path() {
+            Some(path) if path.exists() => Self::from_file(&path),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    /// Parse a config file
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        Self::parse(&contents)
+            .with_context(|| format!("Invalid config file {}", path.display()))
+    }
+
+    /// Parse config file contents
+    pub fn parse(contents: &str) -> Result<Self> {
+        Ok(toml::from_str(contents)?)
+    }
+
+    /// Default config location: `$XDG_CONFIG_HOME/whois/config.toml`, falling back to
+    /// `~/.config/whois/config.toml` (`%APPDATA%\whois\config.toml` on Windows)
+    pub fn default_path() -> Option<PathBuf> {
+        let base = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| {
+                if cfg!(windows) {
+                    std::env::var_os("APPDATA").map(PathBuf::from)
+                } else {
+                    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config"))
+                }
+            })?;
+        Some(base.join("whois").join("config.toml"))
+    }
+
+    /// Server registry with the configured policies and profiles
+    /// Configured settings override the built-in ones field by field
+    pub fn registry(&self) -> ServerRegistry {
+        let mut registry = ServerRegistry::default();
+        if let Some(policy) = &self.empty_result {
+            registry.empty_result = policy.clone();
+        }
+        if let Some(rules) = &self.rules {
+            registry.rules = rules.clone();
+        }
+        for (host, profile) in &self.servers {
+            let entry = registry.profiles.entry(host.to_lowercase()).or_default();
+            if let Some(policy) = &profile.empty_result {
+                entry.empty_result = Some(policy.clone());
+            }
+            if let Some(rules) = &profile.rules {
+                entry.rules = Some(rules.clone());
+            }
+            if let Some(lang) = &profile.lang {
+                entry.lang = Some(lang.clone());
+            }
+            if let Some(persistent) = profile.persistent {
+                entry.persistent = Some(persistent);
+            }
+            if let Some(irrd) = profile.irrd {
+                entry.irrd = Some(irrd);
+            }
+            if let Some(hierarchy) = &profile.hierarchy {
+                entry.hierarchy = Some(hierarchy.clone());
+            }
+            if let Some(flags) = profile.flags {
+                entry.flags = Some(flags);
+            }
+            if let Some(rdap) = &profile.rdap {
+                entry.rdap = Some(rdap.clone());
+            }
+            if let Some(prefer_rdap) = profile.prefer_rdap {
+                entry.prefer_rdap = Some(prefer_rdap);
+            }
+            if let Some(syntax) = &profile.query_syntax {
+                entry.query_syntax = Some(syntax.clone());
+            }
+            if let Some(token) = &profile.auth_token {
+                entry.auth_token = Some(token.clone());
+            }
+            if let Some(keyring) = profile.auth_keyring {
+                entry.auth_keyring = Some(keyring);
+            }
+            if let Some(charset) = &profile.charset {
+                entry.charset = Some(charset.clone());
+            }
+        }
+        registry
+    }
+
+    /// Palette by name: one from the config, or a built-in one
+    pub fn palette(&self, name: &str) -> Result<Palette> {
+        if let Some(colors) = self.palettes.get(name) {
+            return Palette::from_hex(colors).with_context(|| format!("Invalid palette {}", name));
+        }
+        Palette::builtin(name).with_context(|| {
+            let mut known = palettes::names();
+            known.extend(self.palettes.keys().map(String::as_str));
+            format!("Unknown palette {} (known: {})", name, known.join(", "))
+        })
+    }
+
+    /// A preset defined in the config
+    pub fn preset(&self, name: &str) -> Result<&Preset> {
+        self.preset.get(name).with_context(|| {
+            let mut known: Vec<&str> = self.preset.keys().map(String::as_str).collect();
+            known.sort();
+            match known.is_empty() {
+                true => format!("Unknown preset {} (none defined, add a [preset.{}] table to the config)", name, name),
+                false => format!("Unknown preset {} (known: {})", name, known.join(", ")),
+            }
+        })
+    }
+
+    /// Fallback chain from the config, or the built-in chain
+    pub fn fallback_chain(&self) -> Vec<FallbackStep> {
+        self.fallback.clone().unwrap_or_else(default_fallback_chain)
+    }
+}
+
+/// Cache directory shared by the image cache and the dn42 registry clone: `$XDG_CACHE_HOME/whois`,
+/// falling back to `~/.cache/whois` (`%LOCALAPPDATA%\whois` on Windows)
+pub fn cache_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| {
+            if cfg!(windows) {
+                std::env::var_os("LOCALAPPDATA").map(PathBuf::from)
+            } else {
+                std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache"))
+            }
+        })?;
+    Some(base.join("whois"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::servers::FallbackCondition;
+
+    #[test]
+    fn test_empty_config_uses_default_chain() {
+        let config = Config::parse("").unwrap();
+        assert_eq!(config.fallback_chain(), default_fallback_chain());
+    }
+
+    #[test]
+    fn test_parse_fallback_chain() {
+        let config = Config::parse(
+            r#"
+            [[fallback]]
+            server = "whois.radb.net"
+            on = ["empty", "timeout"]
+
+            [[fallback]]
+            server = "bgp.tools"
+            name = "BGP.tools"
+            port = 43
+            on = ["rate-limited"]
+            "#,
+        )
+        .unwrap();
+
+        let chain = config.fallback_chain();
+        assert_eq!(chain.len(), 2);
+        assert_eq!(chain[0].port, 43);
+        assert_eq!(chain[0].on, vec![FallbackCondition::Empty, FallbackCondition::Timeout]);
+        assert_eq!(chain[1].to_server().name, "BGP.tools");
+        assert!(chain[1].applies_to(FallbackCondition::RateLimited));
+    }
+
+    #[test]
+    fn test_empty_chain_disables_fallback() {
+        let config = Config::parse("fallback = []").unwrap();
+        assert!(config.fallback_chain().is_empty());
+    }
+
+    #[test]
+    fn test_parse_empty_result_policies() {
+        let config = Config::parse(
+            r#"
+            [empty_result]
+            short_content_length = 4
+
+            [servers."Whois.Nic.Example".empty_result]
+            short_response_length = 0
+            "#,
+        )
+        .unwrap();
+
+        let registry = config.registry();
+        assert_eq!(registry.empty_result.short_content_length, 4);
+        // Unset fields keep their built-in values
+        assert_eq!(registry.empty_result.short_response_length, 30);
+        assert_eq!(registry.empty_policy("whois.nic.example").short_response_length, 0);
+    }
+
+    #[test]
+    fn test_server_rules_override_builtin() {
+        let config = Config::parse(
+            r#"
+            rules = []
+
+            [servers."whois.arin.net".empty_result]
+            short_response_length = 0
+
+            [[servers."whois.nic.example".rules]]
+            pattern = "^% Terms"
+            action = "drop-block"
+            "#,
+        )
+        .unwrap();
+
+        let registry = config.registry();
+        assert!(registry.rules.is_empty());
+        // Setting only the ARIN empty-result policy keeps its built-in rules
+        assert!(registry.rules_for("whois.arin.net").any(|rule| rule.name == "arin-terms"));
+        assert_eq!(
+            registry.clean_response("whois.nic.example", "% Terms\n% of use\n\ndomain: nic.example\n", Default::default()),
+            "domain: nic.example\n"
+        );
+    }
+
+    #[test]
+    fn test_server_auth_token() {
+        let config = Config::parse("[servers.\"WHOIS.IPAM.INTERNAL\"]\nauth_token = \"s3cret\"\n").unwrap();
+        let registry = config.registry();
+        assert_eq!(registry.auth_token("whois.ipam.internal").as_deref(), Some("s3cret"));
+        assert_eq!(registry.auth_token("whois.ripe.net"), None);
+    }
+
+    #[test]
+    fn test_palettes() {
+        let config = Config::parse("[palettes]\nbi = [\"#D60270\", \"#9B4F96\", \"#0038A8\"]\nbroken = [\"blue\"]\n").unwrap();
+        assert_eq!(config.palette("bi").unwrap().colors.len(), 3);
+        assert_eq!(config.palette("mtf").unwrap(), Palette::trans());
+        assert!(config.palette("broken").is_err());
+        assert!(config.palette("plaid").unwrap_err().to_string().contains("known: trans, rainbow, bi"));
+    }
+
+    #[test]
+    fn test_accessibility_settings() {
+        let config = Config::parse("scheme = \"high-contrast\"\nsymbols = true\n").unwrap();
+        assert_eq!(config.scheme, Some(Theme::HighContrast));
+        assert!(config.symbols);
+        assert!(Config::parse("scheme = \"sepia\"").is_err());
+    }
+
+    #[test]
+    fn test_pgp_settings() {
+        let config = Config::parse("[pgp]\nverify = true\ntrusted_keys = [\"0123456789ABCDEF\"]\n").unwrap();
+        assert!(config.pgp.verify);
+        assert_eq!(config.pgp.trusted_keys, vec!["0123456789ABCDEF"]);
+        assert!(config.pgp.keyring.is_none());
+    }
+
+    #[test]
+    fn test_ct_settings() {
+        assert_eq!(Config::default().ct.url, crate::ct::CRTSH_URL);
+        let config = Config::parse("[ct]\nlimit = 5\n").unwrap();
+        assert_eq!((config.ct.url.as_str(), config.ct.limit), (crate::ct::CRTSH_URL, 5));
+        let config = Config::parse("[history_api]\nkeyring = true\n\n[server_overrides]\n\"whois.ripe.net\" = \"10.0.0.5:4343\"\n").unwrap();
+        assert_eq!(config.server_overrides["whois.ripe.net"], "10.0.0.5:4343");
+        assert_eq!((config.history_api.url.as_str(), config.history_api.keyring), (crate::remotehistory::WHOISXMLAPI_URL, true));
+    }
+
+    #[test]
+    fn test_presets() {
+        let config = Config::parse("[preset.irr]\nserver = \"whois.radb.net\"\nflags = [\"--dedup\"]\n").unwrap();
+        assert_eq!(config.preset("irr").unwrap().args(), ["--server", "whois.radb.net", "--dedup"]);
+        assert!(config.preset("rir").unwrap_err().to_string().contains("known: irr"));
+        assert!(Config::parse("[preset.irr]\nsever = \"whois.radb.net\"\n").is_err());
+    }
+
+    #[test]
+    fn test_unknown_condition_is_rejected() {
+        assert!(Config::parse("[[fallback]]\nserver = \"x\"\non = [\"sometimes\"]").is_err());
+    }
+}
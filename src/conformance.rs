@@ -0,0 +1,285 @@
+use anyhow::Result;
+
+use crate::ansi::strip_ansi;
+use crate::colorize::{Theme, Tone};
+use crate::error::QueryError;
+use crate::fingerprint::fingerprint;
+use crate::protocol::{
+    split_markdown_marker, ServerCapabilities, WhoisColorProtocol, ACCEPT_ENCODING_PREFIX, CAPABILITY_PROBE,
+    CAPABILITY_RESPONSE_PREFIX, COLOR_REQUEST_PREFIX, MARKDOWN_REQUEST_PREFIX,
+};
+use crate::tr;
+
+/// Query sent when `whois probe-server` is given none
+pub const DEFAULT_QUERY: &str = "example.com";
+/// Protocol versions a capability line may announce
+const KNOWN_VERSIONS: &[&str] = &["v1.0", "v1.1", "v1.2"];
+/// Capability fields this client understands
+const KNOWN_FIELDS: &[&str] = &["schemes", "markdown", "images", "encoding", "paging", "auth", "auth-required"];
+/// Header no version of the protocol defines, which servers must ignore
+const UNKNOWN_HEADER: &str = "X-WHOIS-CONFORMANCE-TEST: ignore-me\r\n";
+
+/// How a server did on one check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Pass,
+    Warn,
+    Fail,
+    /// The server does not claim the feature the check is about
+    Skip,
+}
+
+impl Outcome {
+    /// `[ok]`, `[!]`, `[x]` or `[-]`, colored and padded to one width
+    fn marker(self) -> String {
+        let tone = match self {
+            Outcome::Pass => Tone::Good,
+            Outcome::Warn => Tone::Warning,
+            Outcome::Fail => Tone::Bad,
+            Outcome::Skip => return "[-] ".to_string(),
+        };
+        format!("{}{}", tone.paint(tone.symbol(), Theme::Default), " ".repeat(4 - tone.symbol().len()))
+    }
+}
+
+/// One conformance check and what the server did
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub name: &'static str,
+    pub outcome: Outcome,
+    pub detail: String,
+}
+
+impl Check {
+    fn new(name: &'static str, outcome: Outcome, detail: impl Into<String>) -> Self {
+        Self { name, outcome, detail: detail.into() }
+    }
+}
+
+/// Result of `whois probe-server`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub address: String,
+    /// The `X-WHOIS-COLOR-SUPPORT` line, without its prefix
+    pub capability_line: Option<String>,
+    pub capabilities: ServerCapabilities,
+    pub checks: Vec<Check>,
+}
+
+impl Report {
+    /// Number of failed checks
+    pub fn failures(&self) -> usize {
+        self.checks.iter().filter(|check| check.outcome == Outcome::Fail).count()
+    }
+
+    /// Capabilities, then one line per check with the totals
+    pub fn render(&self) -> String {
+        let capabilities = &self.capabilities;
+        let list = |items: &[String]| if items.is_empty() { "-".to_string() } else { items.join(",") };
+        let mut lines = vec![
+            format!("% ─── {} ───", tr!("probe-heading", server = self.address.as_str())),
+            format!("% {:<16}{}", tr!("probe-advertised"), self.capability_line.as_deref().unwrap_or("-")),
+            format!("% {:<16}{}", tr!("probe-version"), capabilities.protocol_version),
+            format!("% {:<16}{}", tr!("probe-schemes"), list(&capabilities.color_schemes)),
+            format!("% {:<16}{}", "markdown", capabilities.supports_markdown),
+            format!("% {:<16}{}", "images", list(&capabilities.image_formats)),
+            format!("% {:<16}{}", "encoding", list(&capabilities.encodings)),
+            format!("% {:<16}{}", "paging", capabilities.supports_paging),
+            format!("% {:<16}{}{}", "auth", list(&capabilities.auth_schemes), if capabilities.auth_required { " (required)" } else { "" }),
+            String::new(),
+        ];
+        let width = self.checks.iter().map(|check| check.name.len()).max().unwrap_or(0);
+        lines.extend(
+            self.checks
+                .iter()
+                .map(|check| format!("% {} {:<width$}  {}", check.outcome.marker(), check.name, check.detail, width = width)),
+        );
+        let count = |outcome: Outcome| self.checks.iter().filter(|check| check.outcome == outcome).count();
+        lines.push(format!(
+            "% {}",
+            tr!("probe-summary", passed = count(Outcome::Pass), warnings = count(Outcome::Warn), failed = count(Outcome::Fail))
+        ));
+        lines.join("\n")
+    }
+}
+
+/// Run the conformance suite; `exchange` sends one request on a new connection and returns
+/// the whole answer, and `query` is looked up in the query checks
+pub fn run(address: &str, query: &str, exchange: &mut dyn FnMut(&str) -> Result<String>) -> Result<Report> {
+    let mut checks = Vec::new();
+    let protocol = WhoisColorProtocol;
+
+    // A probe must be answered, or the connection closed, like any query
+    let (capability_line, capabilities) = match exchange(&format!("{}\r\n", CAPABILITY_PROBE)) {
+        Ok(answer) => {
+            let line = find_capability_line(&answer);
+            let extra = answer.lines().filter(|text| !text.trim().is_empty()).count().saturating_sub(1);
+            checks.push(match &line {
+                Some(_) if extra > 0 => Check::new("capability-probe", Outcome::Warn, tr!("probe-extra-lines", count = extra)),
+                Some(_) => Check::new("capability-probe", Outcome::Pass, tr!("probe-answered")),
+                None => Check::new("capability-probe", Outcome::Pass, tr!("probe-plain-server")),
+            });
+            (line, protocol.parse_capability_response(&answer))
+        }
+        Err(err) if matches!(err.downcast_ref::<QueryError>(), Some(QueryError::Timeout { .. })) => {
+            checks.push(Check::new("capability-probe", Outcome::Fail, tr!("probe-kept-open")));
+            (None, ServerCapabilities::default())
+        }
+        Err(err) => return Err(err),
+    };
+    let enhanced = capabilities.supports_color;
+    let skip = |name: &'static str| Check::new(name, Outcome::Skip, tr!("probe-not-advertised"));
+
+    checks.push(match &capability_line {
+        Some(line) => version_check(line, &capabilities),
+        None => skip("version-header"),
+    });
+    checks.push(if enhanced {
+        // A v1.1 server still has to recognise the probe older clients send
+        match exchange("X-WHOIS-COLOR-PROBE: v1.0\r\n\r\n") {
+            Ok(answer) if find_capability_line(&answer).is_some() => Check::new("legacy-probe", Outcome::Pass, tr!("probe-answered")),
+            Ok(_) => Check::new("legacy-probe", Outcome::Fail, tr!("probe-legacy-ignored")),
+            Err(err) => Check::new("legacy-probe", Outcome::Fail, format!("{:#}", err)),
+        }
+    } else {
+        skip("legacy-probe")
+    });
+
+    let plain = exchange(&format!("{}\r\n", query));
+    checks.push(match &plain {
+        Ok(answer) if answer.contains("\x1b[") => Check::new("plain-query", Outcome::Fail, tr!("probe-unasked-color")),
+        Ok(answer) if answer.lines().any(|line| line.starts_with("X-WHOIS-")) => {
+            Check::new("plain-query", Outcome::Warn, tr!("probe-header-in-plain"))
+        }
+        Ok(answer) if answer.trim().is_empty() => Check::new("plain-query", Outcome::Warn, tr!("probe-empty", query = query)),
+        Ok(answer) => Check::new("plain-query", Outcome::Pass, tr!("probe-lines", count = answer.lines().count())),
+        Err(err) => Check::new("plain-query", Outcome::Fail, format!("{:#}", err)),
+    });
+    let plain_fingerprint = plain.as_ref().ok().map(|answer| fingerprint(&strip_ansi(answer)));
+    let same_answer = |answer: &str| Some(fingerprint(&strip_ansi(split_markdown_marker(answer).1))) == plain_fingerprint;
+
+    checks.push(match capabilities.color_schemes.first() {
+        Some(scheme) if enhanced => match exchange(&format!("{}scheme={}\r\n{}\r\n", COLOR_REQUEST_PREFIX, scheme, query)) {
+            Ok(answer) if protocol.is_server_colored(&answer) => Check::new("color", Outcome::Pass, tr!("probe-colored", scheme = scheme.as_str())),
+            Ok(_) => Check::new("color", Outcome::Warn, tr!("probe-not-colored", scheme = scheme.as_str())),
+            Err(err) => Check::new("color", Outcome::Fail, format!("{:#}", err)),
+        },
+        _ => skip("color"),
+    });
+    checks.push(if capabilities.supports_markdown {
+        match exchange(&format!("{}true\r\n{}\r\n", MARKDOWN_REQUEST_PREFIX, query)) {
+            Ok(answer) => match split_markdown_marker(&answer).0 {
+                Some(applied) => Check::new("markdown", Outcome::Pass, tr!("probe-markdown-applied", applied = applied.to_string())),
+                None => Check::new("markdown", Outcome::Warn, tr!("probe-markdown-unmarked")),
+            },
+            Err(err) => Check::new("markdown", Outcome::Fail, format!("{:#}", err)),
+        }
+    } else {
+        skip("markdown")
+    });
+    checks.push(if enhanced {
+        match exchange(&format!("{}{}\r\n", UNKNOWN_HEADER, query)) {
+            Ok(answer) if same_answer(&answer) => Check::new("unknown-header", Outcome::Pass, tr!("probe-unknown-ignored")),
+            Ok(_) => Check::new("unknown-header", Outcome::Fail, tr!("probe-unknown-changed")),
+            Err(err) => Check::new("unknown-header", Outcome::Fail, format!("{:#}", err)),
+        }
+    } else {
+        skip("unknown-header")
+    });
+    checks.push(match capabilities.encodings.first() {
+        // The exchange decodes compressed answers, so a bad body shows up as an error
+        Some(encoding) if enhanced => match exchange(&format!("{}{}\r\n{}\r\n", ACCEPT_ENCODING_PREFIX, encoding, query)) {
+            Ok(answer) if same_answer(&answer) => Check::new("encoding", Outcome::Pass, tr!("probe-decoded", encoding = encoding.as_str())),
+            Ok(_) => Check::new("encoding", Outcome::Warn, tr!("probe-decoded-differs", encoding = encoding.as_str())),
+            Err(err) => Check::new("encoding", Outcome::Fail, format!("{:#}", err)),
+        },
+        _ => skip("encoding"),
+    });
+
+    Ok(Report { address: address.to_string(), capability_line, capabilities, checks })
+}
+
+/// The capability line of an answer, without its prefix
+fn find_capability_line(answer: &str) -> Option<String> {
+    answer.lines().find_map(|line| line.trim().strip_prefix(CAPABILITY_RESPONSE_PREFIX)).map(str::to_string)
+}
+
+/// Whether a capability line announces a known version with fields that version defines
+fn version_check(line: &str, capabilities: &ServerCapabilities) -> Check {
+    let version = capabilities.protocol_version.as_str();
+    if !KNOWN_VERSIONS.contains(&version) {
+        return Check::new("version-header", Outcome::Fail, tr!("probe-unknown-version", version = version));
+    }
+    let unknown: Vec<&str> = line
+        .split_whitespace()
+        .skip(1)
+        .filter_map(|field| field.split_once('=').map(|(name, _)| name).or(Some(field)))
+        .filter(|name| !KNOWN_FIELDS.contains(name))
+        .collect();
+    if !unknown.is_empty() {
+        return Check::new("version-header", Outcome::Warn, tr!("probe-unknown-fields", fields = unknown.join(", ")));
+    }
+    if version == "v1.0" && (capabilities.supports_markdown || capabilities.supports_images) {
+        return Check::new("version-header", Outcome::Warn, tr!("probe-v10-extensions"));
+    }
+    Check::new("version-header", Outcome::Pass, version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A v1.1 server that colors when asked, ignores unknown headers and marks Markdown answers
+    fn server(request: &str) -> Result<String> {
+        let query = request.lines().last().unwrap_or_default();
+        Ok(if request.starts_with("X-WHOIS-COLOR-PROBE: v1.1") {
+            "X-WHOIS-COLOR-SUPPORT: v1.1 schemes=ripe markdown=true images=png\r\n".to_string()
+        } else if request.starts_with("X-WHOIS-COLOR-PROBE") {
+            "% unknown object\r\n".to_string()
+        } else if request.starts_with(COLOR_REQUEST_PREFIX) {
+            format!("\x1b[32mdomain:\x1b[0m {}\r\n", query)
+        } else if request.starts_with(MARKDOWN_REQUEST_PREFIX) {
+            format!("X-WHOIS-MARKDOWN-APPLIED: true\n**domain:** {}\r\n", query)
+        } else {
+            format!("domain: {}\r\n", query)
+        })
+    }
+
+    #[test]
+    fn test_run() {
+        let report = run("127.0.0.1:4343", "example.com", &mut server).unwrap();
+        let outcomes: Vec<(&str, Outcome)> = report.checks.iter().map(|check| (check.name, check.outcome)).collect();
+        assert_eq!(
+            outcomes,
+            [
+                ("capability-probe", Outcome::Pass),
+                ("version-header", Outcome::Pass),
+                ("legacy-probe", Outcome::Fail),
+                ("plain-query", Outcome::Pass),
+                ("color", Outcome::Pass),
+                ("markdown", Outcome::Pass),
+                ("unknown-header", Outcome::Pass),
+                ("encoding", Outcome::Skip),
+            ]
+        );
+        assert_eq!(report.failures(), 1);
+        assert!(strip_ansi(&report.render()).ends_with("% 6 passed, 0 warnings, 1 failed"));
+
+        // A plain server answers the probe like a query
+        let plain = run("127.0.0.1:4343", "example.com", &mut |request: &str| Ok(format!("% no match for {}", request.trim()))).unwrap();
+        assert_eq!(plain.checks[0].outcome, Outcome::Pass);
+        assert!(plain.checks[1..].iter().all(|check| matches!(check.outcome, Outcome::Skip | Outcome::Pass)));
+    }
+
+    #[test]
+    fn test_version_check() {
+        let line = "v1.0 schemes=ripe markdown=true";
+        let capabilities = WhoisColorProtocol.parse_capability_response(&format!("{}{}", CAPABILITY_RESPONSE_PREFIX, line));
+        assert_eq!(version_check(line, &capabilities).outcome, Outcome::Warn);
+        let line = "v1.1 schemes=ripe colour-depth=256";
+        let capabilities = WhoisColorProtocol.parse_capability_response(&format!("{}{}", CAPABILITY_RESPONSE_PREFIX, line));
+        assert!(version_check(line, &capabilities).detail.contains("colour-depth"));
+        let capabilities = WhoisColorProtocol.parse_capability_response(&format!("{}v2", CAPABILITY_RESPONSE_PREFIX));
+        assert_eq!(version_check("v2", &capabilities).outcome, Outcome::Fail);
+    }
+}
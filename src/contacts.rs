@@ -0,0 +1,122 @@
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+use crate::servers::RecordType;
+use crate::tr;
+
+/// Most follow-up queries `--contacts` sends for one answer
+pub const MAX_CONTACT_QUERIES: usize = 6;
+
+/// Org or POC record referenced by an answer but not part of it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContactRef {
+    pub record_type: RecordType,
+    pub handle: String,
+    /// Role of a POC, e.g. `Abuse`; `None` for orgs
+    pub role: Option<String>,
+}
+
+impl ContactRef {
+    fn label(&self) -> String {
+        match (&self.record_type, &self.role) {
+            (RecordType::Org, _) => format!("Org {}", self.handle),
+            (_, Some(role)) => format!("{} POC {}", role, self.handle),
+            _ => format!("POC {}", self.handle),
+        }
+    }
+}
+
+/// Org and POC handles an ARIN answer refers to, in the order they are worth fetching:
+/// the org, then abuse contacts, then the rest; records the answer already has are skipped
+pub fn references(response: &str) -> Vec<ContactRef> {
+    // POC attributes of ARIN org and net records, e.g. `OrgAbuseHandle`, `RTechHandle`
+    static POC_ATTRIBUTE: OnceLock<Regex> = OnceLock::new();
+    let poc_attribute = POC_ATTRIBUTE.get_or_init(|| Regex::new(r"^(?:Org|R)(Abuse|Tech|NOC|Routing|DNS|Admin)Handle$").unwrap());
+    // `Organization: Google LLC (GOGL)`
+    static ORGANIZATION: OnceLock<Regex> = OnceLock::new();
+    let organization = ORGANIZATION.get_or_init(|| Regex::new(r"\(([A-Za-z0-9-]+)\)\s*$").unwrap());
+
+    let fields: Vec<(&str, &str)> = response
+        .lines()
+        .filter(|line| !line.starts_with(['#', '%']))
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim(), value.trim()))
+        .filter(|(_, value)| !value.is_empty())
+        .collect();
+    let present = |name: &str, handle: &str| fields.iter().any(|(other, value)| *other == name && value.eq_ignore_ascii_case(handle));
+
+    let mut references: Vec<ContactRef> = Vec::new();
+    let mut add = |reference: ContactRef| {
+        if !references.iter().any(|known| known.handle.eq_ignore_ascii_case(&reference.handle)) {
+            references.push(reference);
+        }
+    };
+    for (name, value) in &fields {
+        if *name != "Organization" {
+            continue;
+        }
+        if let Some(handle) = organization.captures(value).map(|captures| captures[1].to_string()) {
+            if !present("OrgId", &handle) {
+                add(ContactRef { record_type: RecordType::Org, handle, role: None });
+            }
+        }
+    }
+    let mut pocs: Vec<ContactRef> = fields
+        .iter()
+        .filter_map(|(name, value)| {
+            let role = poc_attribute.captures(name)?[1].to_string();
+            (!present("Handle", value)).then(|| ContactRef { record_type: RecordType::Poc, handle: value.to_string(), role: Some(role) })
+        })
+        .collect();
+    pocs.sort_by_key(|poc| poc.role.as_deref() != Some("Abuse"));
+    pocs.into_iter().for_each(add);
+    references
+}
+
+/// Fetched records as a section after the answer; comment banners of the follow-up answers are left out
+pub fn render(target: &str, records: &[(ContactRef, String)]) -> String {
+    let mut sections = vec![format!("% ─── {} ───", tr!("contacts-heading", target = target, count = records.len()))];
+    if records.is_empty() {
+        sections.push(format!("% {}", tr!("contacts-none")));
+    }
+    for (reference, response) in records {
+        let body: Vec<&str> = response.lines().filter(|line| !line.starts_with(['#', '%'])).collect();
+        sections.push(format!("\n% {}\n{}", reference.label(), body.join("\n").trim()));
+    }
+    sections.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NET: &str = "#\n# ARIN WHOIS data and services are subject to the Terms of Use\n#\n\n\
+                       NetRange:       8.8.8.0 - 8.8.8.255\nNetHandle:      NET-8-8-8-0-2\n\
+                       Organization:   Google LLC (GOGL)\n\n\
+                       OrgTechHandle: ZG39-ARIN\nOrgAbuseHandle: ABUSE5250-ARIN\nOrgNOCHandle: ZG39-ARIN\n";
+
+    #[test]
+    fn test_references() {
+        let references = references(NET);
+        let handles: Vec<&str> = references.iter().map(|reference| reference.handle.as_str()).collect();
+        assert_eq!(handles, ["GOGL", "ABUSE5250-ARIN", "ZG39-ARIN"]);
+        assert_eq!(references[1].label(), "Abuse POC ABUSE5250-ARIN");
+
+        // Records already in the answer are not fetched again
+        let full = format!("{}\nOrgName: Google LLC\nOrgId: GOGL\n\nName: Abuse\nHandle: ABUSE5250-ARIN\n", NET);
+        let handles: Vec<String> = super::references(&full).into_iter().map(|reference| reference.handle).collect();
+        assert_eq!(handles, ["ZG39-ARIN"]);
+    }
+
+    #[test]
+    fn test_render() {
+        let poc = ContactRef { record_type: RecordType::Poc, handle: "ABUSE5250-ARIN".to_string(), role: Some("Abuse".to_string()) };
+        let response = "# ARIN WHOIS data\n\nName:           Abuse\nHandle:         ABUSE5250-ARIN\n\n# end\n";
+        assert_eq!(
+            render("8.8.8.8", &[(poc, response.to_string())]),
+            "% ─── Contacts for 8.8.8.8 (1) ───\n\n% Abuse POC ABUSE5250-ARIN\nName:           Abuse\nHandle:         ABUSE5250-ARIN"
+        );
+        assert!(render("8.8.8.8", &[]).ends_with("% No org or POC records beyond those in the answer"));
+    }
+}
@@ -0,0 +1,153 @@
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+use crate::i18n::pad;
+use crate::portfolio::is_registry_maintainer;
+use crate::tr;
+
+/// Kind of a value that can tie targets to the same owner
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Indicator {
+    Org,
+    Email,
+    Maintainer,
+    NameServer,
+}
+
+impl Indicator {
+    fn label(self) -> String {
+        match self {
+            Indicator::Org => tr!("correlate-org"),
+            Indicator::Email => tr!("correlate-email"),
+            Indicator::Maintainer => tr!("correlate-maintainer"),
+            Indicator::NameServer => tr!("correlate-nameserver"),
+        }
+    }
+}
+
+/// A value found in the answers of several targets
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shared {
+    pub indicator: Indicator,
+    pub value: String,
+    /// Targets whose answers hold the value, in query order
+    pub targets: Vec<String>,
+}
+
+/// Ownership indicators of an answer: org handles and names, contact emails,
+/// maintainers and name servers, normalized so they compare across registries
+pub fn indicators(response: &str) -> Vec<(Indicator, String)> {
+    static EMAIL: OnceLock<Regex> = OnceLock::new();
+    let email = EMAIL.get_or_init(|| Regex::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}").unwrap());
+
+    let mut found: Vec<(Indicator, String)> = Vec::new();
+    for line in response.lines().filter(|line| !line.starts_with(['%', '#', ' ', '\t', '+'])) {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let name = name.trim().to_lowercase();
+        let value = value.trim();
+        if value.is_empty() {
+            continue;
+        }
+        // Registrar contacts are shared by every domain of the registrar
+        if name.contains("registrar") {
+            continue;
+        }
+        let indicator = match name.as_str() {
+            "org" | "orgid" | "registrant organization" | "registrant organisation" => Some((Indicator::Org, value.to_uppercase())),
+            "mnt-by" | "mnt-lower" | "mnt-routes" | "mnt-domains" if !is_registry_maintainer(value) => {
+                Some((Indicator::Maintainer, value.to_uppercase()))
+            }
+            "name server" | "nserver" | "nameserver" | "nameservers" => value
+                .split_whitespace()
+                .next()
+                .map(|host| (Indicator::NameServer, host.trim_end_matches('.').to_lowercase())),
+            name if name.contains("mail") => email.find(value).map(|found| (Indicator::Email, found.as_str().to_lowercase())),
+            _ => None,
+        };
+        if let Some(indicator) = indicator {
+            if !found.contains(&indicator) {
+                found.push(indicator);
+            }
+        }
+    }
+    found
+}
+
+/// Indicators found in the answers of at least two targets, the most widely shared first
+pub fn correlate(answers: &[(String, String)]) -> Vec<Shared> {
+    let mut shared: Vec<Shared> = Vec::new();
+    for (target, response) in answers {
+        for (indicator, value) in indicators(response) {
+            match shared.iter_mut().find(|known| known.indicator == indicator && known.value == value) {
+                Some(known) if !known.targets.contains(target) => known.targets.push(target.clone()),
+                Some(_) => {}
+                None => shared.push(Shared { indicator, value, targets: vec![target.clone()] }),
+            }
+        }
+    }
+    shared.retain(|entry| entry.targets.len() > 1);
+    shared.sort_by(|a, b| {
+        b.targets.len().cmp(&a.targets.len()).then(a.indicator.cmp(&b.indicator)).then(a.value.cmp(&b.value))
+    });
+    shared
+}
+
+/// Report of shared indicators as `%` comment lines
+pub fn render(shared: &[Shared], target_count: usize) -> String {
+    let mut lines = vec![
+        format!("% ─── {} ───", tr!("correlate-heading", count = target_count)),
+        format!("% {} {} {}", pad(&tr!("correlate-kind"), 12), pad(&tr!("correlate-value"), 36), tr!("correlate-targets")),
+    ];
+    if shared.is_empty() {
+        lines.push(format!("% {}", tr!("correlate-none")));
+    }
+    lines.extend(
+        shared
+            .iter()
+            .map(|entry| format!("% {} {:<36} {}", pad(&entry.indicator.label(), 12), entry.value, entry.targets.join(", "))),
+    );
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_indicators() {
+        let response = "Domain Name: EXAMPLE.COM\nRegistrar Abuse Contact Email: abuse@registrar.example\n\
+                        Registrant Organization: Example Inc\nRegistrant Email: Hostmaster@Example.com\n\
+                        Name Server: NS1.EXAMPLE.NET.\nName Server: ns1.example.net\n\
+                        mnt-by:         RIPE-NCC-HM-MNT\nmnt-by:         EXAMPLE-MNT\n";
+        assert_eq!(
+            indicators(response),
+            [
+                (Indicator::Org, "EXAMPLE INC".to_string()),
+                (Indicator::Email, "hostmaster@example.com".to_string()),
+                (Indicator::NameServer, "ns1.example.net".to_string()),
+                (Indicator::Maintainer, "EXAMPLE-MNT".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_correlate_targets() {
+        let answers = [
+            ("a.com".to_string(), "Name Server: ns1.example.net\nRegistrant Email: a@example.com\n".to_string()),
+            ("b.com".to_string(), "Name Server: NS1.EXAMPLE.NET\nRegistrant Email: b@example.com\n".to_string()),
+            ("192.0.2.0/24".to_string(), "inetnum: 192.0.2.0 - 192.0.2.255\nabuse-mailbox: a@example.com\nnserver: ns1.example.net\n".to_string()),
+        ];
+        let shared = correlate(&answers);
+        assert_eq!(shared.len(), 2);
+        assert_eq!((shared[0].indicator, shared[0].targets.len()), (Indicator::NameServer, 3));
+        assert_eq!(shared[1].targets, ["a.com", "192.0.2.0/24"]);
+
+        let report = render(&shared, 3);
+        assert!(report.starts_with("% ─── Shared across 3 targets ───\n"));
+        assert!(report.ends_with("% Email        a@example.com                        a.com, 192.0.2.0/24"));
+        assert!(render(&[], 2).ends_with("% No values shared between the targets"));
+    }
+}
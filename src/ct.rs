@@ -0,0 +1,229 @@
+#[cfg(feature = "net")]
+use std::time::Duration;
+
+#[cfg(feature = "net")]
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::Value;
+#[cfg(feature = "net")]
+use tracing::info;
+
+use crate::i18n::pad;
+use crate::tr;
+
+/// Base URL of crt.sh, the default certificate transparency search
+pub const CRTSH_URL: &str = "https://crt.sh";
+const DEFAULT_LIMIT: usize = 20;
+/// crt.sh often needs longer than a WHOIS server for popular domains
+#[cfg(feature = "net")]
+const DEFAULT_TIMEOUT_SECONDS: u64 = 20;
+
+/// Certificate transparency search for `--ct`, the `[ct]` table of the config
+///
+/// ```toml
+/// [ct]
+/// url = "https://crt.example.internal"
+/// limit = 50
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CtConfig {
+    /// Base URL of a crt.sh-compatible API (`?q=DOMAIN&output=json`)
+    pub url: String,
+    /// Most certificates listed, newest first
+    pub limit: usize,
+}
+
+impl Default for CtConfig {
+    fn default() -> Self {
+        Self { url: CRTSH_URL.to_string(), limit: DEFAULT_LIMIT }
+    }
+}
+
+/// A certificate logged for a domain
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Certificate {
+    pub id: u64,
+    /// Issuer's organisation and common name, e.g. `Let's Encrypt R3`
+    pub issuer: String,
+    pub sans: Vec<String>,
+    /// As logged, e.g. `2024-03-01T12:00:00`
+    pub not_before: String,
+    pub not_after: String,
+}
+
+/// Client for a crt.sh-compatible certificate search
+#[cfg(feature = "net")]
+pub struct CtLog {
+    base_url: String,
+    timeout: Duration,
+}
+
+#[cfg(feature = "net")]
+impl Default for CtLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "net")]
+impl CtLog {
+    pub fn new() -> Self {
+        Self {
+            base_url: CRTSH_URL.to_string(),
+            timeout: Duration::from_secs(DEFAULT_TIMEOUT_SECONDS),
+        }
+    }
+
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Certificates logged for a domain, newest first
+    pub fn certificates(&self, domain: &str) -> Result<Vec<Certificate>> {
+        let url = format!("{}/", self.base_url);
+        info!("Fetching certificates for {} from {}", domain, self.base_url);
+        let agent = ureq::AgentBuilder::new()
+            .timeout(self.timeout)
+            .user_agent(concat!("whois-cli/", env!("CARGO_PKG_VERSION")))
+            .build();
+        let body: Value = agent
+            .get(&url)
+            .query("q", domain)
+            .query("output", "json")
+            .query("deduplicate", "Y")
+            .call()
+            .with_context(|| format!("Certificate search at {} failed", self.base_url))?
+            .into_json()
+            .with_context(|| format!("Invalid certificate search response from {}", self.base_url))?;
+        Ok(parse_certificates(&body))
+    }
+}
+
+/// Certificates of a crt.sh JSON answer, newest first; entries logged twice
+/// (precertificate and final certificate) are listed once
+pub fn parse_certificates(body: &Value) -> Vec<Certificate> {
+    let mut certificates: Vec<Certificate> = Vec::new();
+    for entry in body.as_array().into_iter().flatten() {
+        let Some(id) = entry["id"].as_u64() else {
+            continue;
+        };
+        let text = |key: &str| entry[key].as_str().unwrap_or_default().to_string();
+        let mut sans: Vec<String> = Vec::new();
+        for name in text("name_value").lines().chain(std::iter::once(text("common_name").as_str())) {
+            let name = name.trim().to_lowercase();
+            if !name.is_empty() && !sans.contains(&name) {
+                sans.push(name);
+            }
+        }
+        let certificate = Certificate {
+            id,
+            issuer: issuer_label(&text("issuer_name")),
+            sans,
+            not_before: text("not_before"),
+            not_after: text("not_after"),
+        };
+        let duplicate = certificates.iter().any(|known| {
+            known.id == certificate.id
+                || (known.issuer == certificate.issuer && known.not_before == certificate.not_before && known.sans == certificate.sans)
+        });
+        if !duplicate {
+            certificates.push(certificate);
+        }
+    }
+    certificates.sort_by(|a, b| b.not_before.cmp(&a.not_before).then(b.id.cmp(&a.id)));
+    certificates
+}
+
+/// Short issuer name from a distinguished name: `C=US, O=Let's Encrypt, CN=R3` is `Let's Encrypt R3`,
+/// and the organisation is left out when the common name already starts with it
+fn issuer_label(name: &str) -> String {
+    let field = |key: &str| {
+        name.split(", ")
+            .find_map(|part| part.strip_prefix(key).and_then(|rest| rest.strip_prefix('=')))
+            .map(|value| value.trim_matches('"').to_string())
+    };
+    match (field("O"), field("CN")) {
+        (Some(org), Some(cn)) if !cn.starts_with(org.split_whitespace().next().unwrap_or(&org)) => format!("{} {}", org, cn),
+        (_, Some(cn)) => cn,
+        (Some(org), None) => org,
+        (None, None) => name.to_string(),
+    }
+}
+
+/// Certificate table as `%` comment lines, at most `limit` rows
+pub fn render(domain: &str, certificates: &[Certificate], limit: usize) -> String {
+    let shown = &certificates[..certificates.len().min(limit)];
+    let count = if shown.len() < certificates.len() {
+        tr!("ct-shown", shown = shown.len(), total = certificates.len())
+    } else {
+        certificates.len().to_string()
+    };
+    let mut lines = vec![
+        format!("% ─── {} ───", tr!("ct-heading", domain = domain, count = count)),
+        format!("% {} {} {} {}", pad(&tr!("ct-not-before"), 11), pad(&tr!("ct-not-after"), 11), pad(&tr!("ct-issuer"), 32), tr!("ct-sans")),
+    ];
+    if certificates.is_empty() {
+        lines.push(format!("% {}", tr!("ct-none")));
+    }
+    lines.extend(shown.iter().map(|certificate| {
+        let date = |value: &str| value.get(..10).unwrap_or(value).to_string();
+        format!(
+            "% {:<11} {:<11} {:<32} {}",
+            date(&certificate.not_before),
+            date(&certificate.not_after),
+            certificate.issuer,
+            certificate.sans.join(", ")
+        )
+    }));
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_certificates() {
+        let body = json!([
+            {"id": 10, "issuer_name": "C=US, O=Let's Encrypt, CN=R3", "common_name": "example.com",
+             "name_value": "example.com\nwww.example.com", "not_before": "2024-01-01T00:00:00", "not_after": "2024-03-31T00:00:00"},
+            {"id": 11, "issuer_name": "C=US, O=Let's Encrypt, CN=R3", "common_name": "example.com",
+             "name_value": "example.com\nwww.example.com", "not_before": "2024-01-01T00:00:00", "not_after": "2024-03-31T00:00:00"},
+            {"id": 12, "issuer_name": "C=US, O=DigiCert Inc, CN=DigiCert Global G2 TLS RSA SHA256 2020 CA1",
+             "common_name": "www.example.org", "name_value": "WWW.example.org", "not_before": "2024-02-01T00:00:00", "not_after": "2025-03-01T23:59:59"},
+            {"issuer_name": "no id"}
+        ]);
+        let certificates = parse_certificates(&body);
+        assert_eq!(certificates.len(), 2);
+        assert_eq!(certificates[0].issuer, "DigiCert Global G2 TLS RSA SHA256 2020 CA1");
+        assert_eq!(certificates[0].sans, ["www.example.org"]);
+        assert_eq!(certificates[1].issuer, "Let's Encrypt R3");
+        assert_eq!(certificates[1].sans, ["example.com", "www.example.com"]);
+    }
+
+    #[test]
+    fn test_render_table() {
+        let certificate = |id, not_before: &str| Certificate {
+            id,
+            issuer: "Let's Encrypt R3".to_string(),
+            sans: vec!["example.com".to_string(), "www.example.com".to_string()],
+            not_before: not_before.to_string(),
+            not_after: "2024-03-31T00:00:00".to_string(),
+        };
+        let table = render("example.com", &[certificate(2, "2024-01-02T00:00:00"), certificate(1, "2024-01-01T00:00:00")], 1);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines[0], "% ─── Certificates logged for example.com (1 of 2) ───");
+        assert!(lines[2].starts_with("% 2024-01-02  2024-03-31  Let's Encrypt R3"));
+        assert!(lines[2].ends_with("example.com, www.example.com"));
+        assert_eq!(lines.len(), 3);
+        assert!(render("example.com", &[], 20).ends_with("% No certificates found in CT logs"));
+    }
+}
@@ -0,0 +1,106 @@
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Day set with `set_today`; `i64::MIN` while the system clock is used
+static TODAY: AtomicI64 = AtomicI64::new(i64::MIN);
+
+/// Days since 1970-01-01 of a calendar date
+pub fn days_from_civil(year: i64, month: u32, day: u32) -> Option<i64> {
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    Some(era * 146097 + day_of_era - 719468)
+}
+
+/// Calendar date of a day since 1970-01-01
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// `YYYY-MM-DD` of a day counted from 1970-01-01
+pub fn format_day(days: i64) -> String {
+    let (year, month, day) = civil_from_days(days);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// RFC 3339 UTC timestamp with milliseconds, e.g. `2024-03-01T12:00:00.000Z`
+pub fn rfc3339(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = since_epoch.as_secs() as i64;
+    let (year, month, day) = civil_from_days(seconds / 86400);
+    let time_of_day = seconds % 86400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        time_of_day / 3600,
+        time_of_day % 3600 / 60,
+        time_of_day % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+/// Day of a value starting with a `YYYY-MM-DD` date (`-`, `.` or `/` between the parts),
+/// such as `2024-03-01T12:00:00Z`, as days since 1970-01-01
+pub fn parse_day(value: &str) -> Option<i64> {
+    let date = value.trim().get(..10)?;
+    let parts: Vec<&str> = date.split(['-', '.', '/']).collect();
+    let [year, month, day] = parts.as_slice() else {
+        return None;
+    };
+    if year.len() != 4 {
+        return None;
+    }
+    days_from_civil(year.parse().ok()?, month.parse().ok()?, day.parse().ok()?)
+}
+
+/// Current day as days since 1970-01-01 (UTC)
+pub fn today() -> i64 {
+    match TODAY.load(Ordering::Relaxed) {
+        i64::MIN => SystemTime::now().duration_since(UNIX_EPOCH).map(|now| now.as_secs() as i64 / 86400).unwrap_or(0),
+        day => day,
+    }
+}
+
+/// Use `day` as the current day instead of the system clock, which wasm32 in a browser lacks
+pub fn set_today(day: i64) {
+    TODAY.store(day, Ordering::Relaxed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_day() {
+        assert_eq!(days_from_civil(1970, 1, 1), Some(0));
+        assert_eq!(parse_day("2000-03-01T00:00:00Z"), Some(11017));
+        assert_eq!(parse_day("2000.03.01"), parse_day("2000/03/01"));
+        assert_eq!(parse_day("01-Mar-2000"), None);
+        assert_eq!(parse_day("2000-13-01"), None);
+    }
+
+    #[test]
+    fn test_rfc3339() {
+        assert_eq!(civil_from_days(11017), (2000, 3, 1));
+        assert_eq!(civil_from_days(days_from_civil(2024, 2, 29).unwrap()), (2024, 2, 29));
+        let time = UNIX_EPOCH + std::time::Duration::from_millis(951_868_800_250 + 3_723_000);
+        assert_eq!(rfc3339(time), "2000-03-01T01:02:03.250Z");
+    }
+}
@@ -0,0 +1,143 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+
+use crate::error::QueryError;
+
+/// Set once the user interrupts the run; every lookup in the process stops at its next step
+static CANCELLED: AtomicBool = AtomicBool::new(false);
+
+/// Ask all running lookups to stop
+pub fn cancel() {
+    CANCELLED.store(true, Ordering::SeqCst);
+}
+
+/// Whether `cancel` has been called
+pub fn is_cancelled() -> bool {
+    CANCELLED.load(Ordering::SeqCst)
+}
+
+/// Wall-clock budget for one lookup, shared by resolution, probes, referrals and fallbacks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Deadline {
+    expires: Option<Instant>,
+    budget: Duration,
+}
+
+impl Deadline {
+    /// A lookup with no overall limit
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    /// A lookup that must finish within `budget` from now
+    /// Fails for a budget too long for the system clock to represent
+    pub fn after(budget: Duration) -> Result<Self> {
+        let expires = Instant::now()
+            .checked_add(budget)
+            .ok_or_else(|| anyhow::anyhow!("Deadline of {} seconds is too far in the future", budget.as_secs()))?;
+        Ok(Self { expires: Some(expires), budget })
+    }
+
+    /// Time left, or `None` without a deadline
+    pub fn remaining(&self) -> Option<Duration> {
+        self.expires.map(|expires| expires.saturating_duration_since(Instant::now()))
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.remaining() == Some(Duration::ZERO)
+    }
+
+    /// Timeout for the next step against `server`: the per-step timeout capped by the time left
+    /// Fails once the deadline has passed or the run was cancelled
+    pub fn step_timeout(&self, timeout: Duration, server: &str) -> Result<Duration> {
+        if is_cancelled() {
+            return Err(QueryError::Cancelled.into());
+        }
+        match self.remaining() {
+            Some(Duration::ZERO) => Err(self.exceeded(server).into()),
+            Some(remaining) => Ok(timeout.min(remaining)),
+            None => Ok(timeout),
+        }
+    }
+
+    /// Replace a step's error with the reason the lookup stopped, if it was cut short
+    /// An interrupted read keeps its error so the bytes received are not lost
+    pub fn explain(&self, err: anyhow::Error, server: &str) -> anyhow::Error {
+        if matches!(err.downcast_ref::<QueryError>(), Some(QueryError::Interrupted { .. })) {
+            err
+        } else if is_cancelled() {
+            QueryError::Cancelled.into()
+        } else if self.is_expired() {
+            self.exceeded(server).into()
+        } else {
+            err
+        }
+    }
+
+    fn exceeded(&self, server: &str) -> QueryError {
+        QueryError::DeadlineExceeded {
+            server: server.to_string(),
+            budget: self.budget,
+        }
+    }
+}
+
+/// Parse a duration such as `30s`, `1500ms`, `2m`, `12h`, `7d` or a bare number of seconds
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let split = value.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number.parse().map_err(|_| format!("invalid duration: {:?}", value))?;
+    let seconds = match unit.trim() {
+        "ms" => number / 1000.0,
+        "" | "s" => number,
+        "m" => number * 60.0,
+        "h" => number * 3600.0,
+        "d" => number * 86400.0,
+        _ => return Err(format!("invalid duration unit in {:?} (use ms, s, m, h or d)", value)),
+    };
+    if seconds <= 0.0 {
+        return Err("duration must be positive".to_string());
+    }
+    Duration::try_from_secs_f64(seconds)
+        .ok()
+        .filter(|&duration| Instant::now().checked_add(duration).is_some())
+        .ok_or_else(|| format!("duration too long: {:?}", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_step_timeout_capped_by_deadline() {
+        let timeout = Duration::from_secs(10);
+        assert_eq!(Deadline::none().step_timeout(timeout, "x:43").unwrap(), timeout);
+
+        let deadline = Deadline::after(Duration::from_secs(2)).unwrap();
+        assert!(deadline.step_timeout(timeout, "x:43").unwrap() <= Duration::from_secs(2));
+
+        let expired = Deadline::after(Duration::ZERO).unwrap();
+        assert!(Deadline::after(Duration::MAX).is_err());
+        let err = expired.step_timeout(timeout, "x:43").unwrap_err();
+        assert!(matches!(err.downcast_ref::<QueryError>(), Some(QueryError::DeadlineExceeded { .. })));
+        let explained = expired.explain(anyhow::anyhow!("read failed"), "x:43");
+        assert!(explained.downcast_ref::<QueryError>().is_some());
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("1500ms"), Ok(Duration::from_millis(1500)));
+        assert_eq!(parse_duration("2m"), Ok(Duration::from_secs(120)));
+        assert_eq!(parse_duration("5"), Ok(Duration::from_secs(5)));
+        assert!(parse_duration("0s").is_err());
+        assert_eq!(parse_duration("7d"), Ok(Duration::from_secs(7 * 86400)));
+        assert!(parse_duration("5w").is_err());
+        assert!(parse_duration("soon").is_err());
+        assert!(parse_duration("999999999999999d").is_err());
+        assert!(parse_duration("999999999999999999999").is_err());
+    }
+}
@@ -0,0 +1,197 @@
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use tracing::{debug, info, warn};
+
+use crate::config::cache_dir;
+use crate::netblock::Netblock;
+
+/// How long a cloned registry is used before it is pulled again
+pub const REGISTRY_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Object directories searched for names that are not ASNs, addresses or domains
+const HANDLE_DIRECTORIES: &[&str] = &[
+    "mntner", "person", "role", "organisation", "as-set", "route-set", "as-block", "key-cert", "tinc-key", "registry",
+];
+
+/// Object lookups answered from the flat files of a dn42 registry checkout
+pub struct Dn42Registry {
+    root: PathBuf,
+}
+
+impl Dn42Registry {
+    /// Registry in a local checkout, i.e. a directory with `data/` in it
+    pub fn new(root: PathBuf) -> Result<Self> {
+        if !root.join("data").is_dir() {
+            anyhow::bail!("{} is not a dn42 registry checkout (no data/ directory)", root.display());
+        }
+        Ok(Self { root })
+    }
+
+    /// Registry at a path, or at a git URL cloned into the cache directory and pulled once a day
+    /// A failed pull keeps using the existing clone, so lookups also work offline
+    pub fn open(location: &str) -> Result<Self> {
+        if !is_remote(location) {
+            return Self::new(PathBuf::from(location));
+        }
+        let dir = Self::default_dir().context("No cache directory for the dn42 registry clone")?;
+        if dir.join(".git").is_dir() {
+            let age = std::fs::metadata(dir.join(".git").join("FETCH_HEAD"))
+                .or_else(|_| std::fs::metadata(dir.join(".git").join("HEAD")))
+                .and_then(|meta| meta.modified())
+                .ok()
+                .and_then(|modified| modified.elapsed().ok());
+            if age.is_none_or(|age| age >= REGISTRY_TTL) {
+                info!("Updating dn42 registry in {}", dir.display());
+                if let Err(err) = git(&["-C", &dir.to_string_lossy(), "pull", "--ff-only", "--quiet"]) {
+                    warn!("Could not update the dn42 registry ({:#}), using the existing clone", err);
+                }
+            }
+        } else {
+            info!("Cloning dn42 registry {} into {}", location, dir.display());
+            if let Some(parent) = dir.parent() {
+                std::fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+            }
+            git(&["clone", "--depth", "1", "--quiet", location, &dir.to_string_lossy()])?;
+        }
+        Self::new(dir)
+    }
+
+    /// Clone location: `$XDG_CACHE_HOME/whois/dn42-registry`, falling back to
+    /// `~/.cache/whois/dn42-registry` (`%LOCALAPPDATA%\whois\dn42-registry` on Windows)
+    pub fn default_dir() -> Option<PathBuf> {
+        Some(cache_dir()?.join("dn42-registry"))
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Objects for a target in WHOIS form: an aut-num for an ASN, the most specific
+    /// inetnum and route for an address or prefix, a dns object for a domain, or any
+    /// object named like a handle; `None` when the registry has nothing
+    pub fn lookup(&self, target: &str) -> Result<Option<String>> {
+        let target = target.trim();
+        let upper = target.to_uppercase();
+        let paths = if upper.starts_with("AS") && upper[2..].parse::<u32>().is_ok() {
+            self.existing(&[("aut-num", upper)])
+        } else if let Some(query) = parse_network(target) {
+            let (inetnum, route) = if query.start.is_ipv4() { ("inetnum", "route") } else { ("inet6num", "route6") };
+            [inetnum, route].iter().filter_map(|dir| self.most_specific(dir, &query)).collect()
+        } else if target.contains('.') {
+            self.existing(&[("dns", target.to_lowercase())])
+        } else {
+            let names: Vec<(&str, String)> = HANDLE_DIRECTORIES.iter().map(|dir| (*dir, upper.clone())).collect();
+            self.existing(&names)
+        };
+        if paths.is_empty() {
+            return Ok(None);
+        }
+
+        let mut objects = Vec::new();
+        for path in paths {
+            let relative = path.strip_prefix(&self.root).unwrap_or(&path).display().to_string();
+            debug!("Reading dn42 registry object {}", relative);
+            let text = std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+            objects.push(format!("% Information related to '{}':\n{}", relative, text.trim_end()));
+        }
+        Ok(Some(format!("{}\n", objects.join("\n\n"))))
+    }
+
+    /// Files that exist among `data/<dir>/<name>`
+    fn existing(&self, names: &[(&str, String)]) -> Vec<PathBuf> {
+        names
+            .iter()
+            .map(|(dir, name)| self.root.join("data").join(dir).join(name))
+            .filter(|path| path.is_file() && !path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.')))
+            .collect()
+    }
+
+    /// Most specific prefix file of a directory (`172.20.0.0_24`) covering the query
+    fn most_specific(&self, dir: &str, query: &Netblock) -> Option<PathBuf> {
+        let entries = std::fs::read_dir(self.root.join("data").join(dir)).ok()?;
+        entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let block = Netblock::parse(&entry.file_name().to_string_lossy().replace('_', "/"))?;
+                block.contains(query).then(|| (block.last() - block.first(), entry.path()))
+            })
+            .min_by_key(|(size, _)| *size)
+            .map(|(_, path)| path)
+    }
+}
+
+/// Whether a registry location is a git URL rather than a path
+fn is_remote(location: &str) -> bool {
+    location.contains("://") || location.starts_with("git@")
+}
+
+fn git(args: &[&str]) -> Result<()> {
+    let output = Command::new("git").args(args).output().context("Failed to run git")?;
+    if !output.status.success() {
+        anyhow::bail!("git {} failed: {}", args.first().unwrap_or(&""), String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(())
+}
+
+/// A prefix, or an address as a single-address block
+fn parse_network(text: &str) -> Option<Netblock> {
+    if text.contains('/') {
+        return Netblock::parse(text);
+    }
+    let address = text.parse::<IpAddr>().ok()?;
+    Netblock::parse(&format!("{}/{}", address, if address.is_ipv4() { 32 } else { 128 }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry() -> Dn42Registry {
+        let root = std::env::temp_dir().join(format!("whois-dn42-{}", std::process::id()));
+        let files = [
+            ("aut-num/AS4242420000", "aut-num:            AS4242420000\nas-name:            EXAMPLE-AS\nsource:             DN42\n"),
+            ("inetnum/172.20.0.0_14", "inetnum:            172.20.0.0 - 172.23.255.255\ncidr:               172.20.0.0/14\n"),
+            ("inetnum/172.20.1.0_24", "inetnum:            172.20.1.0 - 172.20.1.255\ncidr:               172.20.1.0/24\n"),
+            ("route/172.20.1.0_24", "route:              172.20.1.0/24\norigin:             AS4242420000\n"),
+            ("inet6num/fd42:d42:d42::_48", "inet6num:           fd42:0d42:0d42:0000:0000:0000:0000:0000 - fd42:0d42:0d42:ffff:ffff:ffff:ffff:ffff\n"),
+            ("dns/example.dn42", "domain:             example.dn42\n"),
+            ("mntner/EXAMPLE-MNT", "mntner:             EXAMPLE-MNT\n"),
+        ];
+        for (path, contents) in files {
+            let path = root.join("data").join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+        Dn42Registry::new(root).unwrap()
+    }
+
+    #[test]
+    fn test_lookups() {
+        let registry = registry();
+        let aut_num = registry.lookup("as4242420000").unwrap().unwrap();
+        assert!(aut_num.starts_with("% Information related to 'data/aut-num/AS4242420000':\naut-num:"));
+
+        let address = registry.lookup("172.20.1.7").unwrap().unwrap();
+        assert!(address.contains("'data/inetnum/172.20.1.0_24'") && address.contains("'data/route/172.20.1.0_24'"));
+        assert!(!address.contains("172.20.0.0_14"));
+        let prefix = registry.lookup("172.21.0.0/16").unwrap().unwrap();
+        assert!(prefix.contains("172.20.0.0_14") && !prefix.contains("route"));
+
+        assert!(registry.lookup("fd42:d42:d42::1").unwrap().unwrap().contains("inet6num:"));
+        assert!(registry.lookup("example.dn42").unwrap().unwrap().contains("domain:"));
+        assert!(registry.lookup("example-mnt").unwrap().unwrap().contains("mntner:"));
+        assert_eq!(registry.lookup("AS4242429999").unwrap(), None);
+        let _ = std::fs::remove_dir_all(registry.root());
+    }
+
+    #[test]
+    fn test_rejects_non_registry() {
+        assert!(Dn42Registry::new(std::env::temp_dir().join("whois-no-such-registry")).is_err());
+        assert!(is_remote("https://git.dn42.dev/dn42/registry.git"));
+        assert!(!is_remote("/srv/dn42/registry"));
+    }
+}
@@ -0,0 +1,59 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use serde::Deserialize;
+
+use crate::deadline::Deadline;
+use crate::normalize::normalize_target;
+use crate::query::{QueryResult, WhoisQuery};
+
+/// Lookup options for programs embedding the library (the C ABI and the Python module),
+/// given to `whois_query` as a JSON object whose fields are all optional
+///
+/// ```json
+/// {"server": "whois.ripe.net", "port": 43, "timeout_ms": 10000, "follow_referrals": false}
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct QueryOptions {
+    /// Server to ask instead of selecting one for the target
+    pub server: Option<String>,
+    pub port: u16,
+    /// Time budget of the whole lookup, referrals and fallbacks included
+    pub timeout_ms: Option<u64>,
+    pub follow_referrals: bool,
+    /// Query DN42 (lantian.pub) instead of the public registries
+    pub dn42: bool,
+    /// Query bgp.tools
+    pub bgptools: bool,
+    /// Treat bare numbers as ASNs
+    pub asn: bool,
+}
+
+impl Default for QueryOptions {
+    fn default() -> Self {
+        Self {
+            server: None,
+            port: 43,
+            timeout_ms: None,
+            follow_referrals: true,
+            dn42: false,
+            bgptools: false,
+            asn: false,
+        }
+    }
+}
+
+/// Normalize `target` as the command line does and look it up
+/// Returns the normalized target with the result; an unusable timeout is an error, not a panic
+pub fn lookup(target: &str, options: &QueryOptions) -> Result<(String, QueryResult)> {
+    let target = normalize_target(target, options.asn)?;
+    let deadline = match options.timeout_ms {
+        Some(0) => anyhow::bail!("timeout_ms must be positive"),
+        Some(ms) => Some(Deadline::after(Duration::from_millis(ms))?),
+        None => None,
+    };
+    let query = WhoisQuery::new().with_follow_referrals(options.follow_referrals).with_deadline(deadline.unwrap_or_default());
+    let result = query.query(&target, options.dn42, options.bgptools, options.server.as_deref(), options.port)?;
+    Ok((target, result))
+}
@@ -0,0 +1,60 @@
+use std::fmt;
+use std::time::Duration;
+
+/// Query failures that callers may want to react to, e.g. by trying another server
+/// Returned inside `anyhow::Error`; use `downcast_ref::<QueryError>()` to inspect
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryError {
+    /// The server did not answer within the timeout
+    Timeout { server: String },
+    /// The server refused the query because of rate limiting
+    RateLimited { server: String },
+    /// The lookup ran out of its overall time budget (`--deadline`)
+    DeadlineExceeded { server: String, budget: Duration },
+    /// The run was interrupted, e.g. by Ctrl-C
+    Cancelled,
+    /// The run was interrupted while an answer was arriving; `received` holds what arrived so far
+    Interrupted { server: String, received: String },
+    /// The server reset the connection while an answer was arriving; `received` holds what arrived before
+    ConnectionReset { server: String, received: String },
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueryError::Timeout { server } => write!(f, "Timed out waiting for WHOIS server: {}", server),
+            QueryError::RateLimited { server } => write!(f, "Rate limited by WHOIS server: {}", server),
+            QueryError::DeadlineExceeded { server, budget } => {
+                write!(f, "Deadline of {:.1}s exceeded at WHOIS server: {}", budget.as_secs_f64(), server)
+            }
+            QueryError::Cancelled => write!(f, "Query cancelled"),
+            QueryError::Interrupted { server, received } => {
+                write!(f, "Interrupted while reading from WHOIS server: {} ({} bytes received)", server, received.len())
+            }
+            QueryError::ConnectionReset { server, received } => {
+                write!(f, "Connection reset by WHOIS server: {} ({} bytes received)", server, received.len())
+            }
+        }
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+impl QueryError {
+    /// Stable name of the failure for structured output, e.g. `rate-limited`
+    pub fn kind(&self) -> &'static str {
+        match self {
+            QueryError::Timeout { .. } => "timeout",
+            QueryError::RateLimited { .. } => "rate-limited",
+            QueryError::DeadlineExceeded { .. } => "deadline-exceeded",
+            QueryError::Cancelled => "cancelled",
+            QueryError::Interrupted { .. } => "interrupted",
+            QueryError::ConnectionReset { .. } => "connection-reset",
+        }
+    }
+}
+
+/// `QueryError::kind` of a failure, or `error` for failures that are not a `QueryError`
+pub fn kind_of(error: &anyhow::Error) -> &'static str {
+    error.downcast_ref::<QueryError>().map_or("error", QueryError::kind)
+}
@@ -0,0 +1,133 @@
+//! C ABI for embedding the query engine in other languages
+//!
+//! Build the shared library with
+//! `cargo rustc --lib --release --features ffi --crate-type cdylib`; `include/whois.h`
+//! declares the functions. Strings are UTF-8 and NUL-terminated.
+
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use anyhow::Context;
+
+use crate::embed::{self, QueryOptions};
+use crate::json;
+
+/// Look up `target` and serialize the outcome as `whois --format json` does
+/// `options` is a JSON object of `QueryOptions` fields, or empty for the defaults
+pub fn query_json(target: &str, options: &str) -> String {
+    let options = match options.trim() {
+        "" => Ok(QueryOptions::default()),
+        options => serde_json::from_str(options).context("Invalid query options"),
+    };
+    options.and_then(|options| embed::lookup(target, &options)).map_or_else(
+        |err| json::error_record(target, &err),
+        |(target, result)| json::result_record(&target, &result),
+    )
+}
+
+/// Borrow a C string; `None` for NULL or invalid UTF-8
+unsafe fn borrow<'a>(string: *const c_char) -> Option<&'a str> {
+    if string.is_null() {
+        return None;
+    }
+    CStr::from_ptr(string).to_str().ok()
+}
+
+/// Look up `target` with `options` (a JSON object, or NULL for the defaults) and return
+/// the JSON record of the answer or of the failure, to be released with `whois_string_free`
+/// Returns NULL only when `target` is NULL or not UTF-8; `options` that are not UTF-8 give an error record
+///
+/// # Safety
+///
+/// `target` and `options` must each be NULL or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn whois_query(target: *const c_char, options: *const c_char) -> *mut c_char {
+    let Some(target) = borrow(target) else {
+        return ptr::null_mut();
+    };
+    let options = match borrow(options) {
+        Some(options) => options,
+        None if options.is_null() => "",
+        None => {
+            let record = json::error_record(target, &anyhow::anyhow!("options is not UTF-8"));
+            return CString::new(record).map_or(ptr::null_mut(), CString::into_raw);
+        }
+    };
+    // A panic must not unwind into the caller's frames
+    let record = panic::catch_unwind(AssertUnwindSafe(|| query_json(target, options)))
+        .unwrap_or_else(|_| json::error_record(target, &anyhow::anyhow!("Internal error")));
+    CString::new(record).map_or(ptr::null_mut(), CString::into_raw)
+}
+
+/// Release a string returned by `whois_query`; NULL is ignored
+///
+/// # Safety
+///
+/// `string` must be NULL or a pointer returned by `whois_query` that was not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn whois_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    /// Call `whois_query` as C code would and parse the record
+    fn call(target: &str, options: Option<&str>) -> serde_json::Value {
+        let target = CString::new(target).unwrap();
+        let options = options.map(|options| CString::new(options).unwrap());
+        unsafe {
+            let record = whois_query(target.as_ptr(), options.as_ref().map_or(ptr::null(), |options| options.as_ptr()));
+            let value = serde_json::from_str(CStr::from_ptr(record).to_str().unwrap()).unwrap();
+            whois_string_free(record);
+            value
+        }
+    }
+
+    #[test]
+    fn test_whois_query() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = std::thread::spawn(move || {
+            let (mut socket, _) = listener.accept().unwrap();
+            let mut request = [0u8; 512];
+            let read = socket.read(&mut request).unwrap();
+            socket.write_all(b"aut-num:        AS64500\nas-name:        EXAMPLE-AS\n").unwrap();
+            String::from_utf8_lossy(&request[..read]).to_string()
+        });
+
+        let options = format!(r#"{{"server": "127.0.0.1", "port": {}, "timeout_ms": 5000, "follow_referrals": false}}"#, port);
+        let record = call("as64500", Some(&options));
+        assert_eq!(server.join().unwrap(), "AS64500\r\n");
+        assert_eq!(record["target"], "AS64500");
+        assert_eq!(record["classification"], "answer");
+        assert_eq!(record["response"], "aut-num:        AS64500\nas-name:        EXAMPLE-AS\n");
+    }
+
+    #[test]
+    fn test_whois_query_failures() {
+        let record = call("AS64500", Some(r#"{"colour": true}"#));
+        assert_eq!(record["kind"], "error");
+        assert!(record["error"].as_str().unwrap().starts_with("Invalid query options"));
+        let record = call("AS64500", Some(r#"{"timeout_ms": 0}"#));
+        assert_eq!(record["error"], "timeout_ms must be positive");
+        let record = call("AS64500", Some(r#"{"timeout_ms": -1}"#));
+        assert!(record["error"].as_str().unwrap().starts_with("Invalid query options"));
+
+        unsafe {
+            let record = whois_query(c"AS64500".as_ptr(), c"{\"port\": \xff}".as_ptr());
+            let value: serde_json::Value = serde_json::from_str(CStr::from_ptr(record).to_str().unwrap()).unwrap();
+            whois_string_free(record);
+            assert_eq!(value["error"], "options is not UTF-8");
+
+            assert!(whois_query(ptr::null(), ptr::null()).is_null());
+            whois_string_free(ptr::null_mut());
+        }
+    }
+}
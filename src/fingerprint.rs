@@ -0,0 +1,63 @@
+use crate::ansi::strip_ansi;
+
+/// Lines that change from one lookup to the next without the data changing,
+/// matched case-insensitively
+const VOLATILE: &[&str] = &[
+    "last update of whois database",
+    "whois lookup made at",
+    "query time",
+    "queried at",
+    "timestamp:",
+    "response generated",
+];
+
+/// An answer reduced to what identifies its data: `%` and `#` comments, `>>> <<<` banners,
+/// blank lines and volatile lines are dropped, attribute names lowercased and whitespace collapsed
+pub fn normalized(response: &str) -> String {
+    strip_ansi(response)
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with(['%', '#']) && !line.starts_with(">>>"))
+        .filter(|line| {
+            let lower = line.to_lowercase();
+            !VOLATILE.iter().any(|volatile| lower.contains(volatile))
+        })
+        .map(|line| match line.split_once(':') {
+            Some((name, value)) if name.len() <= 40 => format!("{}: {}", name.trim().to_lowercase(), collapse(value)).trim_end().to_string(),
+            _ => collapse(line),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Fingerprint of an answer's data as 16 hex digits; answers that only differ in comments,
+/// banners, timestamps or spacing share it
+pub fn fingerprint(response: &str) -> String {
+    // 64-bit FNV-1a: stable across builds and platforms, unlike the std hasher
+    let hash = normalized(response)
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3));
+    format!("{:016x}", hash)
+}
+
+fn collapse(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint_ignores_volatile_lines() {
+        let first = "% This query was served by the RIPE Database Query Service version 1.112 (ABERDEEN)\n\n\
+                     aut-num:        AS3333\nas-name:        RIPE-NCC-AS\n";
+        let second = "% This query was served by the RIPE Database Query Service version 1.113 (HEREFORD)\n\
+                      AUT-NUM: AS3333\nas-name:   RIPE-NCC-AS  \n>>> Last update of whois database: 2024-03-01T00:00:00Z <<<\n";
+        assert_eq!(normalized(first), "aut-num: AS3333\nas-name: RIPE-NCC-AS");
+        assert_eq!(fingerprint(first), fingerprint(second));
+        assert_ne!(fingerprint(first), fingerprint("aut-num: AS3333\nas-name: OTHER-AS\n"));
+        assert_eq!(fingerprint(""), "cbf29ce484222325");
+        assert_eq!(normalized("WHOIS lookup made at 10:22:11 05-Mar-2024\n    Domain name:\n        example.co.uk\n"), "domain name:\nexample.co.uk");
+    }
+}
@@ -0,0 +1,116 @@
+use crate::i18n::pad;
+use crate::tr;
+
+/// Width of the attribute column of the glossary
+const NAME_WIDTH: usize = 16;
+
+/// Attributes explained by `--explain`; their glosses are the `gloss-<attribute>` catalog messages
+const ATTRIBUTES: &[&str] = &[
+    "admin-c",
+    "tech-c",
+    "abuse-c",
+    "zone-c",
+    "abuse-mailbox",
+    "nic-hdl",
+    "mnt-by",
+    "mnt-lower",
+    "mnt-routes",
+    "mnt-domains",
+    "mnt-ref",
+    "mnt-irt",
+    "mnt-nfy",
+    "upd-to",
+    "auth",
+    "notify",
+    "ref-nfy",
+    "org",
+    "sponsoring-org",
+    "org-type",
+    "netname",
+    "as-name",
+    "origin",
+    "status",
+    "member-of",
+    "members",
+    "mp-members",
+    "mbrs-by-ref",
+    "import",
+    "export",
+    "mp-import",
+    "mp-export",
+    "default",
+    "mp-default",
+    "holes",
+    "pingable",
+    "ping-hdl",
+    "aggr-mtd",
+    "geoloc",
+    "geofeed",
+    "language",
+    "created",
+    "last-modified",
+    "changed",
+    "source",
+    "nserver",
+    "ds-rdata",
+    "certif",
+    "fingerpr",
+    "irt-nfy",
+    "assignment-size",
+];
+
+/// Glossary attributes used in an answer, in order of first appearance
+pub fn explained(response: &str) -> Vec<&'static str> {
+    let mut found: Vec<&'static str> = Vec::new();
+    for line in response.lines().filter(|line| !line.starts_with(['%', '#', ' ', '\t', '+'])) {
+        let Some((name, _)) = line.split_once(':') else {
+            continue;
+        };
+        let name = name.trim().to_lowercase();
+        if let Some(attribute) = ATTRIBUTES.iter().find(|attribute| **attribute == name) {
+            if !found.contains(attribute) {
+                found.push(attribute);
+            }
+        }
+    }
+    found
+}
+
+/// Glossary of the attributes as `%` comment lines, in the selected locale
+pub fn render(attributes: &[&str]) -> String {
+    let mut lines = vec![format!("% ─── {} ───", tr!("explain-heading"))];
+    lines.extend(
+        attributes
+            .iter()
+            .map(|attribute| format!("% {} {}", pad(attribute, NAME_WIDTH), tr!(&format!("gloss-{}", attribute)))),
+    );
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i18n::{has_message, Locale};
+
+    #[test]
+    fn test_every_attribute_has_a_gloss() {
+        for attribute in ATTRIBUTES {
+            let id = format!("gloss-{}", attribute);
+            assert!(has_message(Locale::En, &id) && has_message(Locale::Zh, &id), "{} is not in both catalogs", id);
+        }
+    }
+
+    #[test]
+    fn test_explained_attributes() {
+        let response = "% Information related to '193.0.0.0 - 193.0.7.255'\n\
+                        inetnum:        193.0.0.0 - 193.0.7.255\nnetname:        RIPE-NCC\n\
+                        remarks:        mnt-by: is not an attribute here\n\
+                        admin-c:        BRD-RIPE\nmnt-by:         RIPE-NCC-MNT\nmnt-routes:     RIPE-NCC-MNT\nMnt-By:         OTHER-MNT\n";
+        let attributes = explained(response);
+        assert_eq!(attributes, ["netname", "admin-c", "mnt-by", "mnt-routes"]);
+
+        let glossary = render(&attributes);
+        assert!(glossary.starts_with("% ─── Attribute glossary ───\n% netname          short name of the network; not unique\n"));
+        assert!(glossary.ends_with("% mnt-routes       maintainer allowed to create route objects for this space"));
+    }
+}
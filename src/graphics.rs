@@ -0,0 +1,220 @@
+use clap::ValueEnum;
+
+#[cfg(feature = "images")]
+use anyhow::{Context, Result};
+#[cfg(feature = "images")]
+use base64::Engine;
+#[cfg(feature = "images")]
+use image::{imageops::FilterType, DynamicImage, ImageFormat};
+
+/// Assumed size of a terminal cell in pixels, used to size images in columns
+pub const CELL_WIDTH: u32 = 8;
+pub const CELL_HEIGHT: u32 = 16;
+/// Base64 payload bytes per Kitty graphics escape
+#[cfg(feature = "images")]
+const KITTY_CHUNK: usize = 4096;
+
+/// Terminal graphics protocol used to show images
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum ImageProtocol {
+    /// Detect from the terminal's environment
+    #[default]
+    Auto,
+    /// Kitty graphics protocol (kitty, Ghostty, WezTerm)
+    Kitty,
+    /// iTerm2 inline images (iTerm2, WezTerm, VS Code)
+    Iterm,
+    /// DEC Sixel (foot, mlterm, xterm -ti vt340, Windows Terminal)
+    Sixel,
+    /// Unicode half blocks, works everywhere
+    Blocks,
+}
+
+impl ImageProtocol {
+    /// Resolve `Auto` from the current environment; other protocols are returned unchanged
+    pub fn resolve(self) -> Self {
+        match self {
+            ImageProtocol::Auto => detect(|name| std::env::var(name).ok()),
+            protocol => protocol,
+        }
+    }
+}
+
+/// Guess the graphics protocol of the terminal from its environment variables
+pub fn detect(env: impl Fn(&str) -> Option<String>) -> ImageProtocol {
+    let term = env("TERM").unwrap_or_default();
+    let program = env("TERM_PROGRAM").unwrap_or_default();
+    if env("KITTY_WINDOW_ID").is_some() || term == "xterm-kitty" || term == "xterm-ghostty" || program == "ghostty" {
+        ImageProtocol::Kitty
+    } else if program == "iTerm.app"
+        || program == "WezTerm"
+        || program == "vscode"
+        || env("LC_TERMINAL").as_deref() == Some("iTerm2")
+    {
+        ImageProtocol::Iterm
+    } else if term.starts_with("foot") || term.starts_with("mlterm") || term.starts_with("yaft") || env("WT_SESSION").is_some() {
+        ImageProtocol::Sixel
+    } else {
+        ImageProtocol::Blocks
+    }
+}
+
+/// Escape sequence that shows an encoded image (PNG, JPEG, GIF) at most `max_columns` wide
+/// `Blocks` and `Auto` have no escape sequence; they are drawn by viuer
+#[cfg(feature = "images")]
+pub fn encode(protocol: ImageProtocol, data: &[u8], max_columns: u32) -> Result<String> {
+    let image = image::load_from_memory(data).context("Failed to decode image")?;
+    let columns = image.width().div_ceil(CELL_WIDTH).clamp(1, max_columns);
+    match protocol {
+        ImageProtocol::Kitty => kitty(&image, data, columns),
+        ImageProtocol::Iterm => Ok(iterm(data, columns)),
+        ImageProtocol::Sixel => Ok(sixel(&image, columns)),
+        ImageProtocol::Auto | ImageProtocol::Blocks => anyhow::bail!("{:?} images are drawn by viuer", protocol),
+    }
+}
+
+/// Kitty graphics: PNG data sent in chunks, scaled to `columns` cells
+#[cfg(feature = "images")]
+fn kitty(image: &DynamicImage, data: &[u8], columns: u32) -> Result<String> {
+    let png = if data.starts_with(b"\x89PNG") {
+        data.to_vec()
+    } else {
+        let mut png = std::io::Cursor::new(Vec::new());
+        image.write_to(&mut png, ImageFormat::Png).context("Failed to convert image to PNG")?;
+        png.into_inner()
+    };
+    let payload = base64::engine::general_purpose::STANDARD.encode(png);
+    let chunks: Vec<&[u8]> = payload.as_bytes().chunks(KITTY_CHUNK).collect();
+    let mut output = String::new();
+    for (index, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(index + 1 < chunks.len());
+        let chunk = std::str::from_utf8(chunk).unwrap_or_default();
+        if index == 0 {
+            output.push_str(&format!("\x1b_Ga=T,f=100,c={},m={};{}\x1b\\", columns, more, chunk));
+        } else {
+            output.push_str(&format!("\x1b_Gm={};{}\x1b\\", more, chunk));
+        }
+    }
+    Ok(output)
+}
+
+/// iTerm2 inline image: the file itself, decoded by the terminal
+#[cfg(feature = "images")]
+fn iterm(data: &[u8], columns: u32) -> String {
+    format!(
+        "\x1b]1337;File=inline=1;size={};width={};preserveAspectRatio=1:{}\x07",
+        data.len(),
+        columns,
+        base64::engine::general_purpose::STANDARD.encode(data)
+    )
+}
+
+/// Sixel: resized to `columns` cells and quantized to a 6x6x6 color cube
+/// Transparent pixels are left unpainted
+#[cfg(feature = "images")]
+fn sixel(image: &DynamicImage, columns: u32) -> String {
+    let pixels = image
+        .resize(columns * CELL_WIDTH, 24 * CELL_HEIGHT, FilterType::Triangle)
+        .to_rgba8();
+    let (width, height) = pixels.dimensions();
+    let level = |value: u8| (value as usize * 5 + 127) / 255;
+    let color = |x: u32, y: u32| {
+        let [r, g, b, a] = pixels.get_pixel(x, y).0;
+        (a >= 128).then(|| level(r) * 36 + level(g) * 6 + level(b))
+    };
+
+    let mut output = format!("\x1bPq\"1;1;{};{}", width, height);
+    for index in 0..216 {
+        output.push_str(&format!("#{};2;{};{};{}", index, index / 36 * 20, index / 6 % 6 * 20, index % 6 * 20));
+    }
+    for top in (0..height).step_by(6) {
+        // Six-pixel-high band: one row of sixels per color used in it
+        let mut rows: Vec<(usize, Vec<u8>)> = Vec::new();
+        for x in 0..width {
+            for bit in 0..6.min(height - top) {
+                let Some(index) = color(x, top + bit) else {
+                    continue;
+                };
+                let position = match rows.iter().position(|(known, _)| *known == index) {
+                    Some(position) => position,
+                    None => {
+                        rows.push((index, vec![0; width as usize]));
+                        rows.len() - 1
+                    }
+                };
+                rows[position].1[x as usize] |= 1 << bit;
+            }
+        }
+        for (number, (index, sixels)) in rows.iter().enumerate() {
+            if number > 0 {
+                output.push('$');
+            }
+            output.push_str(&format!("#{}", index));
+            push_runs(&mut output, sixels);
+        }
+        output.push('-');
+    }
+    output.push_str("\x1b\\");
+    output
+}
+
+/// Sixel characters with runs of more than three compressed as `!<count><char>`
+#[cfg(feature = "images")]
+fn push_runs(output: &mut String, sixels: &[u8]) {
+    let mut index = 0;
+    while index < sixels.len() {
+        let run = sixels[index..].iter().take_while(|&&value| value == sixels[index]).count();
+        let character = char::from(63 + sixels[index]);
+        if run > 3 {
+            output.push_str(&format!("!{}{}", run, character));
+        } else {
+            output.extend(std::iter::repeat_n(character, run));
+        }
+        index += run;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_terminal() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| vars.iter().find(|(key, _)| *key == name).map(|(_, value)| value.to_string())
+        };
+        assert_eq!(detect(env(&[("TERM", "xterm-kitty")])), ImageProtocol::Kitty);
+        assert_eq!(detect(env(&[("TERM_PROGRAM", "iTerm.app"), ("TERM", "xterm-256color")])), ImageProtocol::Iterm);
+        assert_eq!(detect(env(&[("TERM", "foot")])), ImageProtocol::Sixel);
+        assert_eq!(detect(env(&[("TERM", "xterm-256color")])), ImageProtocol::Blocks);
+        assert_eq!(ImageProtocol::Sixel.resolve(), ImageProtocol::Sixel);
+    }
+
+    #[cfg(feature = "images")]
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let image = image::RgbaImage::from_pixel(width, height, image::Rgba([255, 0, 0, 255]));
+        let mut png = std::io::Cursor::new(Vec::new());
+        DynamicImage::ImageRgba8(image).write_to(&mut png, ImageFormat::Png).unwrap();
+        png.into_inner()
+    }
+
+    #[cfg(feature = "images")]
+    #[test]
+    fn test_sixel_encoding() {
+        let sixel = encode(ImageProtocol::Sixel, &png(8, 6), 80).unwrap();
+        assert!(sixel.starts_with("\x1bPq\"1;1;8;6"));
+        // One red band of eight full columns
+        assert!(sixel.ends_with("#180!8~-\x1b\\"));
+    }
+
+    #[cfg(feature = "images")]
+    #[test]
+    fn test_kitty_and_iterm_encoding() {
+        let data = png(40, 20);
+        let kitty = encode(ImageProtocol::Kitty, &data, 80).unwrap();
+        assert!(kitty.starts_with("\x1b_Ga=T,f=100,c=5,m=0;"));
+        let iterm = encode(ImageProtocol::Iterm, &data, 2).unwrap();
+        assert!(iterm.starts_with(&format!("\x1b]1337;File=inline=1;size={};width=2;", data.len())));
+        assert!(encode(ImageProtocol::Blocks, &data, 80).is_err());
+    }
+}
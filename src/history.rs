@@ -0,0 +1,175 @@
+use std::sync::OnceLock;
+
+#[cfg(feature = "net")]
+use anyhow::Result;
+use colored::*;
+use regex::Regex;
+
+use crate::i18n::pad;
+#[cfg(feature = "net")]
+use crate::query::WhoisQuery;
+#[cfg(feature = "net")]
+use crate::servers::WhoisServer;
+use crate::tr;
+
+/// One entry of a RIPE `--list-versions` answer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectVersion {
+    pub revision: u32,
+    /// Date and time as the server prints it (`2016-03-01 10:12`)
+    pub date: String,
+    /// `ADD/UPD` or `DEL`
+    pub operation: String,
+}
+
+/// Versions listed in a `--list-versions` answer, oldest first
+pub fn parse_versions(response: &str) -> Vec<ObjectVersion> {
+    static ROW: OnceLock<Regex> = OnceLock::new();
+    let row = ROW.get_or_init(|| Regex::new(r"^(\d+)\s+(\d{4}-\d{2}-\d{2} \d{2}:\d{2})\s+(\S+)").unwrap());
+    response
+        .lines()
+        .filter_map(|line| {
+            let captures = row.captures(line.trim())?;
+            Some(ObjectVersion {
+                revision: captures[1].parse().ok()?,
+                date: captures[2].to_string(),
+                operation: captures[3].to_string(),
+            })
+        })
+        .collect()
+}
+
+/// List the versions of an object kept by a RIPE database server
+#[cfg(feature = "net")]
+pub fn list_versions(query: &WhoisQuery, server: &WhoisServer, key: &str) -> Result<(Vec<ObjectVersion>, String)> {
+    let response = query.query_direct(&format!("--list-versions {}", key), server)?;
+    Ok((parse_versions(&response), response))
+}
+
+/// Fetch one version of an object, without the server's `%` comments
+#[cfg(feature = "net")]
+pub fn show_version(query: &WhoisQuery, server: &WhoisServer, revision: u32, key: &str) -> Result<String> {
+    let response = query.query_direct(&format!("--show-version {} {}", revision, key), server)?;
+    if let Some(error) = response.lines().find(|line| line.starts_with("%ERROR")) {
+        anyhow::bail!("Version {} of {} not available: {}", revision, key, error.trim_start_matches('%'));
+    }
+    Ok(object_text(&response))
+}
+
+/// Object lines of an answer, dropping comments and surrounding blank lines
+pub fn object_text(response: &str) -> String {
+    let lines: Vec<&str> = response.lines().filter(|line| !line.starts_with('%')).collect();
+    lines.join("\n").trim().to_string()
+}
+
+/// Version table, newest last like the server lists them
+pub fn render_versions(key: &str, versions: &[ObjectVersion]) -> String {
+    let mut lines = vec![
+        format!("% ─── {} ───", tr!("history-heading", key = key, count = versions.len())),
+        format!("% {} {} {}", pad(&tr!("history-revision"), 6), pad(&tr!("history-date"), 18), tr!("history-operation")),
+    ];
+    lines.extend(
+        versions
+            .iter()
+            .map(|version| format!("% {:<6} {:<18} {}", version.revision, version.date, version.operation)),
+    );
+    lines.push(format!("% {}", tr!("history-hint", key = key)));
+    lines.join("\n")
+}
+
+/// A line of a diff between two object versions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffLine {
+    Same(String),
+    Added(String),
+    Removed(String),
+}
+
+/// Line diff of two texts, based on their longest common subsequence
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    // common[i][j]: length of the LCS of old[i..] and new[j..]
+    let mut common = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut diff = Vec::new();
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            diff.push(DiffLine::Same(old[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || common[i + 1][j] >= common[i][j + 1]) {
+            // Removals first, so a changed value reads as `-` old then `+` new
+            diff.push(DiffLine::Removed(old[i].to_string()));
+            i += 1;
+        } else {
+            diff.push(DiffLine::Added(new[j].to_string()));
+            j += 1;
+        }
+    }
+    diff
+}
+
+/// Unified-style diff with removed lines in red and added lines in green
+pub fn render_diff(from: u32, to: u32, diff: &[DiffLine]) -> String {
+    let mut lines = vec![format!("% ─── {} ───", tr!("history-changes", from = from, to = to))];
+    if diff.iter().all(|line| matches!(line, DiffLine::Same(_))) {
+        lines.push(format!("% {}", tr!("history-no-changes")));
+    }
+    lines.extend(diff.iter().map(|line| match line {
+        DiffLine::Same(text) => format!("  {}", text),
+        DiffLine::Added(text) => format!("+ {}", text).green().to_string(),
+        DiffLine::Removed(text) => format!("- {}", text).red().to_string(),
+    }));
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VERSIONS: &str = "% Version history for AUT-NUM object \"AS3333\"\n% You can use \"--show-version rev#\" to get an exact version of the object.\n\nrev#  Date              Op.\n\n1     2002-08-08 14:56  ADD/UPD\n2     2003-03-11 12:14  ADD/UPD\n3     2004-10-01 09:00  DEL\n";
+
+    #[test]
+    fn test_parse_versions() {
+        let versions = parse_versions(VERSIONS);
+        assert_eq!(versions.len(), 3);
+        assert_eq!(versions[1].date, "2003-03-11 12:14");
+        assert_eq!(versions[2].operation, "DEL");
+        assert!(parse_versions("%ERROR:101: no entries found\n").is_empty());
+        assert!(render_versions("AS3333", &versions).contains("% 2      2003-03-11 12:14   ADD/UPD"));
+    }
+
+    #[test]
+    fn test_diff_lines() {
+        let old = "aut-num: AS3333\nas-name: OLD\nsource: RIPE";
+        let new = "aut-num: AS3333\nas-name: NEW\nremarks: added\nsource: RIPE";
+        assert_eq!(
+            diff_lines(old, new),
+            vec![
+                DiffLine::Same("aut-num: AS3333".to_string()),
+                DiffLine::Removed("as-name: OLD".to_string()),
+                DiffLine::Added("as-name: NEW".to_string()),
+                DiffLine::Added("remarks: added".to_string()),
+                DiffLine::Same("source: RIPE".to_string()),
+            ]
+        );
+        assert!(render_diff(1, 2, &diff_lines(old, old)).contains("% No changes"));
+    }
+
+    #[test]
+    fn test_object_text() {
+        let response = "% Version 2 of object \"AS3333\"\n% This version was a UPDATE operation on 2003-03-11 12:14\n\naut-num: AS3333\nsource: RIPE\n\n";
+        assert_eq!(object_text(response), "aut-num: AS3333\nsource: RIPE");
+    }
+}
@@ -0,0 +1,219 @@
+use crate::ansi::{parse_segments, AnsiColor, AnsiStyle, BASIC_PALETTE};
+
+/// HTML export for colorized WHOIS output
+/// Converts ANSI SGR color sequences into styled spans and OSC 8
+/// hyperlinks into real <a> elements, producing a standalone document
+pub struct HtmlRenderer;
+
+/// Base stylesheet for the exported document
+const HTML_STYLE: &str = r#"body { background: #1e1e1e; color: #d4d4d4; margin: 0; padding: 1em; }
+pre { font-family: "DejaVu Sans Mono", Menlo, Consolas, monospace; font-size: 13px; line-height: 1.35; white-space: pre-wrap; }
+a { color: inherit; text-decoration: underline; }
+.b { font-weight: bold; }
+.d { opacity: 0.7; }
+.i { font-style: italic; }
+.u { text-decoration: underline; }
+.s { text-decoration: line-through; }
+"#;
+
+/// Build the embedded stylesheet, including classes for the basic palette
+/// Pages showing fragments from `render_fragment` need it as well
+pub fn stylesheet() -> String {
+    let mut css = String::from(HTML_STYLE);
+    for n in 0..BASIC_PALETTE.len() as u8 {
+        let hex = AnsiColor::Basic(n).to_hex();
+        css.push_str(&format!(".fg-{} {{ color: {}; }} .bg-{} {{ background: {}; }}\n", n, hex, n, hex));
+    }
+    css
+}
+
+/// Build the opening <span> tag for a style
+fn open_tag(style: &AnsiStyle) -> String {
+    let mut classes = Vec::new();
+    let mut styles = Vec::new();
+
+    match style.fg {
+        Some(AnsiColor::Basic(n)) => classes.push(format!("fg-{}", n)),
+        Some(color) => styles.push(format!("color: {}", color.to_hex())),
+        None => {}
+    }
+    match style.bg {
+        Some(AnsiColor::Basic(n)) => classes.push(format!("bg-{}", n)),
+        Some(color) => styles.push(format!("background: {}", color.to_hex())),
+        None => {}
+    }
+    if style.bold {
+        classes.push("b".to_string());
+    }
+    if style.dim {
+        classes.push("d".to_string());
+    }
+    if style.italic {
+        classes.push("i".to_string());
+    }
+    if style.underline {
+        classes.push("u".to_string());
+    }
+    if style.strikethrough {
+        classes.push("s".to_string());
+    }
+
+    let mut tag = String::from("<span");
+    if !classes.is_empty() {
+        tag.push_str(&format!(" class=\"{}\"", classes.join(" ")));
+    }
+    if !styles.is_empty() {
+        tag.push_str(&format!(" style=\"{}\"", styles.join("; ")));
+    }
+    tag.push('>');
+    tag
+}
+
+/// URI schemes an OSC 8 link may use to become an `<a>`; answers come from servers,
+/// so `javascript:`, `data:` and the like are rendered as plain text
+const LINK_SCHEMES: &[&str] = &["http://", "https://", "mailto:"];
+
+fn is_safe_link(uri: &str) -> bool {
+    LINK_SCHEMES.iter().any(|scheme| uri.get(..scheme.len()).is_some_and(|prefix| prefix.eq_ignore_ascii_case(scheme)))
+}
+
+/// Escape text for inclusion in HTML
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+impl HtmlRenderer {
+    /// Render ANSI-colored terminal output as a standalone HTML document
+    pub fn render_document(output: &str, title: &str) -> String {
+        format!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>WHOIS: {}</title>\n<style>\n{}</style>\n</head>\n<body>\n<pre>{}</pre>\n</body>\n</html>\n",
+            escape_html(title),
+            stylesheet(),
+            Self::render_fragment(output)
+        )
+    }
+
+    /// Convert ANSI-colored terminal output into an HTML fragment
+    pub fn render_fragment(output: &str) -> String {
+        let mut html = String::with_capacity(output.len() * 2);
+        let mut current_link: Option<String> = None;
+
+        for segment in parse_segments(output) {
+            let link = segment.link.filter(|uri| is_safe_link(uri));
+            if link != current_link {
+                if current_link.is_some() {
+                    html.push_str("</a>");
+                }
+                if let Some(uri) = &link {
+                    html.push_str(&format!("<a href=\"{}\">", escape_html(uri)));
+                }
+                current_link = link;
+            }
+
+            let text = escape_html(&segment.text);
+            if segment.style.is_plain() {
+                html.push_str(&text);
+            } else {
+                html.push_str(&open_tag(&segment.style));
+                html.push_str(&text);
+                html.push_str("</span>");
+            }
+        }
+
+        if current_link.is_some() {
+            html.push_str("</a>");
+        }
+
+        html
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_html() {
+        assert_eq!(escape_html("<a & b>"), "&lt;a &amp; b&gt;");
+        assert_eq!(escape_html("\"quoted\""), "&quot;quoted&quot;");
+    }
+
+    #[test]
+    fn test_render_plain_text() {
+        let html = HtmlRenderer::render_fragment("aut-num: AS3333");
+        assert_eq!(html, "aut-num: AS3333");
+    }
+
+    #[test]
+    fn test_render_basic_colors() {
+        let html = HtmlRenderer::render_fragment("\x1b[96maut-num\x1b[0m: \x1b[1;91mAS3333\x1b[0m");
+        assert_eq!(
+            html,
+            "<span class=\"fg-14\">aut-num</span>: <span class=\"fg-9 b\">AS3333</span>"
+        );
+    }
+
+    #[test]
+    fn test_render_truecolor() {
+        let html = HtmlRenderer::render_fragment("\x1b[38;2;91;207;250mblue\x1b[0m");
+        assert_eq!(html, "<span style=\"color: #5bcffa\">blue</span>");
+    }
+
+    #[test]
+    fn test_render_osc8_hyperlink() {
+        let input = "aut-num: \x1b]8;;https://example.com/?q=AS3333&x=1\x1b\\AS3333\x1b]8;;\x1b\\";
+        let html = HtmlRenderer::render_fragment(input);
+        assert_eq!(
+            html,
+            "aut-num: <a href=\"https://example.com/?q=AS3333&amp;x=1\">AS3333</a>"
+        );
+    }
+
+    #[test]
+    fn test_render_colored_hyperlink() {
+        let input = "\x1b[91m\x1b]8;;https://example.com\x1b\\AS3333\x1b]8;;\x1b\\\x1b[0m";
+        let html = HtmlRenderer::render_fragment(input);
+        assert_eq!(
+            html,
+            "<a href=\"https://example.com\"><span class=\"fg-9\">AS3333</span></a>"
+        );
+    }
+
+    #[test]
+    fn test_render_unsafe_link_as_text() {
+        for uri in ["javascript:alert(document.cookie)", "JavaScript:alert(1)", "data:text/html,<script>alert(1)</script>", " javascript:alert(1)"] {
+            let input = format!("abuse-c: \x1b]8;;{}\x1b\\AB123\x1b]8;;\x1b\\", uri);
+            assert_eq!(HtmlRenderer::render_fragment(&input), "abuse-c: AB123", "{}", uri);
+        }
+        let html = HtmlRenderer::render_fragment("\x1b]8;;MAILTO:abuse@example.net\x1b\\abuse\x1b]8;;\x1b\\");
+        assert_eq!(html, "<a href=\"MAILTO:abuse@example.net\">abuse</a>");
+    }
+
+    #[test]
+    fn test_render_escapes_markup() {
+        let html = HtmlRenderer::render_fragment("descr: <script>alert(1)</script>");
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("&lt;script&gt;"));
+    }
+
+    #[test]
+    fn test_render_document() {
+        let doc = HtmlRenderer::render_document("\x1b[92mok\x1b[0m", "AS3333");
+        assert!(doc.starts_with("<!DOCTYPE html>"));
+        assert!(doc.contains("<title>WHOIS: AS3333</title>"));
+        assert!(doc.contains(".fg-10 {"));
+        assert!(doc.contains("<span class=\"fg-10\">ok</span>"));
+        assert!(doc.trim_end().ends_with("</html>"));
+    }
+}
@@ -1,7 +1,11 @@
 use regex::Regex;
+use std::borrow::Cow;
 use std::env;
+use std::sync::OnceLock;
 use urlencoding::encode;
 
+use crate::ansi;
+
 /// Represents Regional Internet Registry URLs
 pub struct RirUrls;
 
@@ -18,7 +22,7 @@ impl RirUrls {
                 // LACNIC uses a different parameter format
                 format!("https://query.milacnic.lacnic.net/home?searchtext={}", encoded_term)
             },
-            "AFRINIC" => format!("https://afrinic.net/whois?searchtext={}", encoded_term),
+            "AFRINIC" => format!("https://whois.afrinic.net/search?searchtext={}", encoded_term),
             _ => {
                 // Fallback to RIPE for unknown RIRs
                 format!("https://apps.db.ripe.net/db-web-ui/query?searchtext={}", encoded_term)
@@ -32,21 +36,12 @@ pub fn detect_rir_from_source(response: &str) -> Vec<&'static str> {
     let mut rirs = Vec::new();
     
     // Use regex to find all source fields
-    let source_regex = Regex::new(r"(?m)^source:\s*([A-Z-]+)").unwrap();
+    static SOURCE: OnceLock<Regex> = OnceLock::new();
+    let source_regex = SOURCE.get_or_init(|| Regex::new(r"(?m)^source:\s*([A-Z-]+)").unwrap());
     
     for caps in source_regex.captures_iter(response) {
         if let Some(source) = caps.get(1) {
-            let source_value = source.as_str().trim();
-            let rir = match source_value {
-                "RIPE" => Some("ripe"),
-                "ARIN" => Some("arin"),
-                "APNIC" => Some("apnic"),
-                "LACNIC" => Some("lacnic"),
-                "AFRINIC" => Some("afrinic"),
-                _ => None,
-            };
-            
-            if let Some(rir) = rir {
+            if let Some(rir) = rir_of_source(source.as_str().trim()) {
                 if !rirs.contains(&rir) {
                     rirs.push(rir);
                 }
@@ -57,6 +52,25 @@ pub fn detect_rir_from_source(response: &str) -> Vec<&'static str> {
     rirs
 }
 
+/// RIR of a `source:` value
+fn rir_of_source(source: &str) -> Option<&'static str> {
+    match source {
+        "RIPE" => Some("ripe"),
+        "ARIN" => Some("arin"),
+        "APNIC" => Some("apnic"),
+        "LACNIC" => Some("lacnic"),
+        "AFRINIC" => Some("afrinic"),
+        _ => None,
+    }
+}
+
+/// RIR of a `source:` line, like `source:         RIPE # Filtered`
+fn source_line_rir(line: &str) -> Option<&'static str> {
+    let value = line.strip_prefix("source:")?.trim_start();
+    let end = value.find(|c: char| !(c.is_ascii_uppercase() || c == '-')).unwrap_or(value.len());
+    rir_of_source(&value[..end])
+}
+
 /// Legacy function - detect which RIR the response is from (fallback method)
 pub fn detect_rir(response: &str) -> Option<&'static str> {
     // First try source-based detection
@@ -100,14 +114,16 @@ pub fn detect_rir(response: &str) -> Option<&'static str> {
     None
 }
 
-/// Check if the WHOIS response is from any RIR
+/// Check if the WHOIS response is from any RIR; server colors are ignored
 pub fn is_rir_response(response: &str) -> bool {
-    !detect_rir_from_source(response).is_empty() || detect_rir(response).is_some()
+    let response = ansi::plain(response);
+    !detect_rir_from_source(&response).is_empty() || detect_rir(&response).is_some()
 }
 
 /// Check if the WHOIS response is from RIPE NCC
 pub fn is_ripe_response(response: &str) -> bool {
-    detect_rir_from_source(response).contains(&"ripe") || detect_rir(response) == Some("ripe")
+    let response = ansi::plain(response);
+    detect_rir_from_source(&response).contains(&"ripe") || detect_rir(&response) == Some("ripe")
 }
 
 /// Check if terminal supports hyperlinks (OSC 8) - improved Windows detection
@@ -180,66 +196,160 @@ pub fn create_hyperlink(url: &str, text: &str) -> String {
         return text.to_string();
     }
 
-    format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, text)
+    osc8(url, text)
 }
 
-/// Split response into blocks by RIR source
-fn split_response_by_source(response: &str) -> Vec<(String, &'static str)> {
-    let mut blocks = Vec::new();
-    let lines: Vec<&str> = response.lines().collect();
-    let mut current_block = String::new();
-    let mut current_rir = None;
-    
-    for line in lines {
-        // Check if this line contains a source field
-        if let Some(caps) = Regex::new(r"^source:\s*([A-Z-]+)").unwrap().captures(line) {
-            if let Some(source) = caps.get(1) {
-                let source_value = source.as_str().trim();
-                let rir = match source_value {
-                    "RIPE" => Some("ripe"),
-                    "ARIN" => Some("arin"), 
-                    "APNIC" => Some("apnic"),
-                    "LACNIC" => Some("lacnic"),
-                    "AFRINIC" => Some("afrinic"),
-                    _ => None,
-                };
-                
-                // If we found a new RIR source and have a current block, save it
-                if let Some(current) = current_rir {
-                    if rir != Some(current) && !current_block.trim().is_empty() {
-                        blocks.push((current_block.clone(), current));
-                        current_block.clear();
-                    }
-                }
-                
-                current_rir = rir;
+/// OSC 8 escape sequence linking `text` to `url`
+fn osc8(url: &str, text: &str) -> String {
+    let mut link = String::with_capacity(url.len() + text.len() + 12);
+    push_link_start(&mut link, url);
+    link.push_str(text);
+    link.push_str(LINK_END);
+    link
+}
+
+/// Append the opening OSC 8 sequence of a hyperlink to `url`; `LINK_END` closes it
+pub fn push_link_start(out: &mut String, url: &str) {
+    out.push_str("\x1b]8;;");
+    out.push_str(url);
+    out.push_str("\x1b\\");
+}
+
+/// Closing OSC 8 sequence of a hyperlink
+pub const LINK_END: &str = "\x1b]8;;\x1b\\";
+
+/// Call `f` with each line of an answer and the RIR its object belongs to: the RIR of the
+/// object's own `source:` line, else that of the object before it, else the answer's
+/// Lines may carry server colors
+pub fn for_each_line<'a>(response: &'a str, mut f: impl FnMut(&'a str, Option<&'static str>)) {
+    let mut fallback = None;
+    let mut previous = None;
+    let mut object: Vec<(&str, Cow<str>)> = Vec::new();
+    let mut lines = response.lines().peekable();
+    while lines.peek().is_some() {
+        // An object runs to the blank line that ends it
+        object.clear();
+        for line in lines.by_ref() {
+            let plain = ansi::plain(line);
+            let blank = plain.trim().is_empty();
+            object.push((line, plain));
+            if blank {
+                break;
             }
         }
-        
-        current_block.push_str(line);
-        current_block.push('\n');
-    }
-    
-    // Add the last block
-    if let Some(rir) = current_rir {
-        if !current_block.trim().is_empty() {
-            blocks.push((current_block, rir));
-        }
-    } else if !current_block.trim().is_empty() {
-        // Fallback: try to detect RIR from content
-        if let Some(rir) = detect_rir(&current_block) {
-            blocks.push((current_block, rir));
+        let has_attributes = || object.iter().any(|(_, plain)| !plain.trim().is_empty() && !plain.starts_with(['%', '#']));
+        let rir = object
+            .iter()
+            .find_map(|(_, plain)| source_line_rir(plain))
+            .or(previous)
+            // Banners need no RIR, so the answer is only searched for objects that may link
+            .or_else(|| if has_attributes() { *fallback.get_or_insert_with(|| detect_rir(&ansi::plain(response))) } else { None });
+        previous = rir;
+        for (line, _) in &object {
+            f(line, rir);
         }
     }
-    
-    // If no blocks were created, treat entire response as one block
-    if blocks.is_empty() {
-        if let Some(rir) = detect_rir(response) {
-            blocks.push((response.to_string(), rir));
-        }
+}
+
+/// URL for the value of an attribute line of an RIR object, and the length of the value
+/// prefix it covers (`193.0.0.0 - 193.0.7.255` of an inetnum, not the comment after it)
+pub fn attribute_link(rir: &str, line: &str) -> Option<(String, usize)> {
+    let (field, rest) = line.split_once(':')?;
+    let value = rest.trim_start();
+    if value.len() == rest.len() {
+        return None;
+    }
+    let (_, pattern) = rir_patterns(rir).iter().find(|(name, _)| *name == field)?;
+    let found = pattern.find(value)?;
+    Some((RirUrls::get_url(rir, found.as_str()), found.end()))
+}
+
+/// Attributes of an RIR's objects whose values link to its database, with the pattern of
+/// the part of the value that is linked
+fn rir_patterns(rir: &str) -> &'static [(&'static str, Regex)] {
+    /// Compile the value patterns of one RIR, anchored at the start of the value
+    fn compile(patterns: &[(&'static str, &str)]) -> Vec<(&'static str, Regex)> {
+        patterns.iter().map(|(field, pattern)| (*field, Regex::new(&format!("^(?:{})", pattern)).unwrap())).collect()
+    }
+    const ASN: &str = r"AS\d+";
+    const IPV4_RANGE: &str = r"[0-9]+\.[0-9]+\.[0-9]+\.[0-9]+\s*-\s*[0-9]+\.[0-9]+\.[0-9]+\.[0-9]+";
+    const IPV6_PREFIX: &str = r"[0-9a-fA-F:]+/\d+";
+    const HANDLE: &str = r"[A-Z0-9-]+";
+    const ORG: &str = r"ORG-[A-Z0-9-]+";
+
+    static RIPE: OnceLock<Vec<(&'static str, Regex)>> = OnceLock::new();
+    static ARIN: OnceLock<Vec<(&'static str, Regex)>> = OnceLock::new();
+    static APNIC: OnceLock<Vec<(&'static str, Regex)>> = OnceLock::new();
+    static LACNIC: OnceLock<Vec<(&'static str, Regex)>> = OnceLock::new();
+    static AFRINIC: OnceLock<Vec<(&'static str, Regex)>> = OnceLock::new();
+    match rir {
+        "ripe" => RIPE.get_or_init(|| {
+            compile(&[
+                ("aut-num", ASN),
+                ("origin", ASN),
+                ("inetnum", IPV4_RANGE),
+                ("inet6num", IPV6_PREFIX),
+                ("route", r"[0-9]+\.[0-9]+\.[0-9]+\.[0-9]+/\d+"),
+                ("route6", IPV6_PREFIX),
+                ("organisation", ORG),
+                ("org", ORG),
+                ("nic-hdl", HANDLE),
+                ("admin-c", HANDLE),
+                ("tech-c", HANDLE),
+                ("mntner", r"[A-Z][A-Z0-9-]*"),
+                ("mnt-by", r"[A-Z][A-Z0-9-]*"),
+                ("domain", r"[a-zA-Z0-9.-]+\.arpa"),
+                ("as-block", r"AS\d+\s*-\s*AS\d+"),
+            ])
+        }),
+        "arin" => ARIN.get_or_init(|| {
+            compile(&[
+                ("NetRange", r"[0-9.-]+"),
+                ("CIDR", r"[0-9./]+"),
+                ("OriginAS", ASN),
+                ("OrgId", HANDLE),
+                ("NetName", HANDLE),
+                ("aut-num", ASN),
+                ("origin", ASN),
+                ("inetnum", IPV4_RANGE),
+                ("inet6num", IPV6_PREFIX),
+            ])
+        }),
+        "apnic" => APNIC.get_or_init(|| {
+            compile(&[
+                ("aut-num", ASN),
+                ("origin", ASN),
+                ("inetnum", IPV4_RANGE),
+                ("inet6num", IPV6_PREFIX),
+                ("nic-hdl", HANDLE),
+                ("admin-c", HANDLE),
+                ("tech-c", HANDLE),
+            ])
+        }),
+        "lacnic" => LACNIC.get_or_init(|| {
+            compile(&[
+                ("aut-num", ASN),
+                ("origin", ASN),
+                ("inetnum", IPV4_RANGE),
+                ("inet6num", IPV6_PREFIX),
+                ("nic-hdl", HANDLE),
+            ])
+        }),
+        "afrinic" => AFRINIC.get_or_init(|| {
+            compile(&[
+                ("aut-num", ASN),
+                ("origin", ASN),
+                ("inetnum", IPV4_RANGE),
+                ("inet6num", IPV6_PREFIX),
+                ("nic-hdl", HANDLE),
+                ("admin-c", HANDLE),
+                ("tech-c", HANDLE),
+                ("mnt-by", HANDLE),
+                ("org", ORG),
+            ])
+        }),
+        _ => &[],
     }
-    
-    blocks
 }
 
 /// Hyperlink processor for RIR database responses
@@ -250,151 +360,37 @@ impl RirHyperlinkProcessor {
         Self
     }
 
-    /// Process RIR response and add hyperlinks - handles multi-RIR responses
+    /// Process RIR response and add hyperlinks - handles multi-RIR responses, linking each
+    /// object to the database of its own `source:`, and server-colored responses
+    /// `OutputColorizer::colorize_linked` adds the same links while coloring
     pub fn process(&self, response: &str) -> String {
         if !terminal_supports_hyperlinks() {
             return response.to_string();
         }
         
-        // Split response into blocks by RIR source
-        let blocks = split_response_by_source(response);
-        
-        if blocks.is_empty() {
-            return response.to_string();
-        }
-        
-        let mut processed_blocks = Vec::new();
-        
-        for (block, rir) in blocks {
-            let mut processed_block = block;
-            
-            // Apply RIR-specific patterns
-            match rir {
-                "ripe" => self.process_ripe(&mut processed_block),
-                "arin" => self.process_arin(&mut processed_block),
-                "apnic" => self.process_apnic(&mut processed_block),
-                "lacnic" => self.process_lacnic(&mut processed_block),
-                "afrinic" => self.process_afrinic(&mut processed_block),
-                _ => {}
-            }
-            
-            processed_blocks.push(processed_block);
-        }
-        
-        processed_blocks.join("")
-    }
-
-    fn apply_patterns(&self, processed: &mut String, patterns: Vec<(&str, &str)>, rir: &str) {
-        for (pattern_str, _) in patterns {
-            if let Ok(pattern) = Regex::new(pattern_str) {
-                *processed = pattern.replace_all(processed, |caps: &regex::Captures| {
-                    let _full_match = caps.get(0).unwrap().as_str();
-                    let prefix = caps.get(1).unwrap().as_str();
-                    let value = caps.get(2).unwrap().as_str();
-                    
-                    // Generate URL for the detected RIR
-                    let url = RirUrls::get_url(rir, value);
-                    let hyperlinked_value = create_hyperlink(&url, value);
-                    
-                    format!("{}{}", prefix, hyperlinked_value)
-                }).to_string();
+        let mut processed = String::with_capacity(response.len() + response.len() / 4);
+        // Each line starts `rest`; the ending after it is copied as it is, `\r\n`, `\n` or none
+        let mut rest = response;
+        for_each_line(response, |line, rir| {
+            // Server-colored lines are matched on their visible text, and the link is put
+            // around the value's text inside the server's escape codes
+            let plain = ansi::plain(line);
+            match rir.and_then(|rir| attribute_link(rir, &plain)) {
+                Some((url, length)) => {
+                    let start = plain.len() - plain.split_once(':').map_or("", |(_, rest)| rest.trim_start()).len();
+                    let range = ansi::raw_range(line, start..start + length);
+                    processed.push_str(&line[..range.start]);
+                    processed.push_str(&osc8(&url, &line[range.clone()]));
+                    processed.push_str(&line[range.end..]);
+                }
+                None => processed.push_str(line),
             }
-        }
-    }
-
-    fn process_ripe(&self, processed: &mut String) {
-        let patterns = vec![
-            // ASN patterns
-            (r"(?m)^(aut-num:\s+)(AS\d+)", ""),
-            (r"(?m)^(origin:\s+)(AS\d+)", ""),
-            
-            // IP network patterns
-            (r"(?m)^(inetnum:\s+)([0-9]+\.[0-9]+\.[0-9]+\.[0-9]+\s*-\s*[0-9]+\.[0-9]+\.[0-9]+\.[0-9]+)", ""),
-            (r"(?m)^(inet6num:\s+)([0-9a-fA-F:]+/\d+)", ""),
-            (r"(?m)^(route:\s+)([0-9]+\.[0-9]+\.[0-9]+\.[0-9]+/\d+)", ""),
-            (r"(?m)^(route6:\s+)([0-9a-fA-F:]+/\d+)", ""),
-            
-            // Organization patterns
-            (r"(?m)^(organisation:\s+)(ORG-[A-Z0-9-]+)", ""),
-            (r"(?m)^(org:\s+)(ORG-[A-Z0-9-]+)", ""),
-            
-            // Person/Role patterns
-            (r"(?m)^(nic-hdl:\s+)([A-Z0-9-]+)", ""),
-            (r"(?m)^(admin-c:\s+)([A-Z0-9-]+)", ""),
-            (r"(?m)^(tech-c:\s+)([A-Z0-9-]+)", ""),
-            
-            // Maintainer patterns
-            (r"(?m)^(mntner:\s+)([A-Z][A-Z0-9-]*)", ""),
-            (r"(?m)^(mnt-by:\s+)([A-Z][A-Z0-9-]*)", ""),
-            
-            // Domain patterns
-            (r"(?m)^(domain:\s+)([a-zA-Z0-9.-]+\.arpa)", ""),
-            
-            // AS-block patterns
-            (r"(?m)^(as-block:\s+)(AS\d+\s*-\s*AS\d+)", ""),
-        ];
-
-        self.apply_patterns(processed, patterns, "RIPE");
-    }
-
-    fn process_arin(&self, processed: &mut String) {
-        let patterns = vec![
-            // ARIN-specific patterns
-            (r"(?m)^(NetRange:\s+)([0-9.-]+)", ""),
-            (r"(?m)^(CIDR:\s+)([0-9./]+)", ""),
-            (r"(?m)^(OriginAS:\s+)(AS\d+)", ""),
-            (r"(?m)^(OrgId:\s+)([A-Z0-9-]+)", ""),
-            (r"(?m)^(NetName:\s+)([A-Z0-9-]+)", ""),
-            
-            // Common ASN and IP patterns
-            (r"(?m)^(aut-num:\s+)(AS\d+)", ""),
-            (r"(?m)^(origin:\s+)(AS\d+)", ""),
-            (r"(?m)^(inetnum:\s+)([0-9]+\.[0-9]+\.[0-9]+\.[0-9]+\s*-\s*[0-9]+\.[0-9]+\.[0-9]+\.[0-9]+)", ""),
-            (r"(?m)^(inet6num:\s+)([0-9a-fA-F:]+/\d+)", ""),
-        ];
-
-        self.apply_patterns(processed, patterns, "ARIN");
-    }
-
-    fn process_apnic(&self, processed: &mut String) {
-        let patterns = vec![
-            // Common patterns for APNIC
-            (r"(?m)^(aut-num:\s+)(AS\d+)", ""),
-            (r"(?m)^(origin:\s+)(AS\d+)", ""),
-            (r"(?m)^(inetnum:\s+)([0-9]+\.[0-9]+\.[0-9]+\.[0-9]+\s*-\s*[0-9]+\.[0-9]+\.[0-9]+\.[0-9]+)", ""),
-            (r"(?m)^(inet6num:\s+)([0-9a-fA-F:]+/\d+)", ""),
-            (r"(?m)^(nic-hdl:\s+)([A-Z0-9-]+)", ""),
-            (r"(?m)^(admin-c:\s+)([A-Z0-9-]+)", ""),
-            (r"(?m)^(tech-c:\s+)([A-Z0-9-]+)", ""),
-        ];
-
-        self.apply_patterns(processed, patterns, "APNIC");
-    }
-
-    fn process_lacnic(&self, processed: &mut String) {
-        let patterns = vec![
-            // Common patterns for LACNIC
-            (r"(?m)^(aut-num:\s+)(AS\d+)", ""),
-            (r"(?m)^(origin:\s+)(AS\d+)", ""),
-            (r"(?m)^(inetnum:\s+)([0-9]+\.[0-9]+\.[0-9]+\.[0-9]+\s*-\s*[0-9]+\.[0-9]+\.[0-9]+\.[0-9]+)", ""),
-            (r"(?m)^(inet6num:\s+)([0-9a-fA-F:]+/\d+)", ""),
-            (r"(?m)^(nic-hdl:\s+)([A-Z0-9-]+)", ""),
-        ];
-
-        self.apply_patterns(processed, patterns, "LACNIC");
-    }
-
-    fn process_afrinic(&self, processed: &mut String) {
-        let patterns = vec![
-            // Common patterns for AFRINIC
-            (r"(?m)^(aut-num:\s+)(AS\d+)", ""),
-            (r"(?m)^(origin:\s+)(AS\d+)", ""),
-            (r"(?m)^(inetnum:\s+)([0-9]+\.[0-9]+\.[0-9]+\.[0-9]+\s*-\s*[0-9]+\.[0-9]+\.[0-9]+\.[0-9]+)", ""),
-            (r"(?m)^(inet6num:\s+)([0-9a-fA-F:]+/\d+)", ""),
-            (r"(?m)^(nic-hdl:\s+)([A-Z0-9-]+)", ""),
-        ];
-
-        self.apply_patterns(processed, patterns, "AFRINIC");
+            let after = &rest[line.len()..];
+            let ending = ["\r\n", "\n"].into_iter().find(|ending| after.starts_with(ending)).unwrap_or("");
+            processed.push_str(ending);
+            rest = &after[ending.len()..];
+        });
+        processed
     }
 }
 
@@ -429,17 +425,45 @@ source: RIPE
     }
 
     #[test]
-    fn test_split_response_by_source() {
-        let multi_rir_response = r#"
-as-block: AS137530 - AS138553
-source: APNIC
+    fn test_for_each_line() {
+        let multi_rir_response = "% Banner\n\nas-block: AS137530 - AS138553\nsource: APNIC\n\naut-num: AS3333  \nsource: RIPE\n\nremarks: trailing\n";
+        let mut rirs = Vec::new();
+        for_each_line(multi_rir_response, |line, rir| rirs.push((line, rir)));
+        assert_eq!(rirs.len(), 9);
+        assert_eq!(rirs[0], ("% Banner", None));
+        assert_eq!(rirs[2], ("as-block: AS137530 - AS138553", Some("apnic")));
+        // Lines take the RIR of their own object, not of the object before it
+        assert_eq!(rirs[5], ("aut-num: AS3333  ", Some("ripe")));
+        assert_eq!(rirs[8], ("remarks: trailing", Some("ripe")));
+    }
 
-aut-num: AS3333  
-source: RIPE
-        "#;
-        
-        let blocks = split_response_by_source(multi_rir_response);
-        assert_eq!(blocks.len(), 2);
+    #[test]
+    fn test_attribute_link() {
+        let (url, length) = attribute_link("ripe", "inetnum:        193.0.0.0 - 193.0.7.255 # RIPE NCC").unwrap();
+        assert_eq!(length, "193.0.0.0 - 193.0.7.255".len());
+        assert!(url.ends_with("searchtext=193.0.0.0%20-%20193.0.7.255"));
+        assert_eq!(attribute_link("ripe", "descr:          AS3333"), None);
+        assert_eq!(attribute_link("ripe", "aut-num:AS3333"), None);
+        assert_eq!(attribute_link("arin", "OriginAS:       AS3333").map(|(_, length)| length), Some(6));
+
+        let processed = RirHyperlinkProcessor::new().process("aut-num:        AS3333\nsource:         RIPE");
+        let segments = crate::ansi::parse_segments(&processed);
+        assert_eq!(segments[1].text, "AS3333");
+        assert!(segments[1].link.as_deref().unwrap().contains("searchtext=AS3333"));
+
+        // Answers the server colored get the same links, inside the server's colors
+        let colored = "\x1b[96maut-num\x1b[0m:        \x1b[1;91mAS3333\x1b[0m\n\x1b[97msource\x1b[0m:         \x1b[94mRIPE\x1b[0m";
+        assert!(is_rir_response(colored));
+        let processed = RirHyperlinkProcessor::new().process(colored);
+        assert_eq!(crate::ansi::strip_ansi(&processed), "aut-num:        AS3333\nsource:         RIPE");
+        let segments = crate::ansi::parse_segments(&processed);
+        let asn = segments.iter().find(|segment| segment.text == "AS3333").unwrap();
+        assert!(asn.style.bold);
+        assert!(asn.link.as_deref().unwrap().contains("searchtext=AS3333"));
+
+        // Line endings are kept as they were
+        let crlf = "aut-num:        AS3333\r\n\r\nsource:         RIPE\r\n";
+        assert_eq!(crate::ansi::strip_ansi(&RirHyperlinkProcessor::new().process(crlf)), crlf);
     }
 
     #[test]
@@ -472,7 +496,7 @@ source: RIPE
         assert!(lacnic_url.contains("AS3333"));
         
         let afrinic_url = RirUrls::get_url("AFRINIC", "AS3333");
-        assert!(afrinic_url.contains("afrinic.net"));
+        assert!(afrinic_url.starts_with("https://whois.afrinic.net/search?"));
         assert!(afrinic_url.contains("AS3333"));
     }
 } 
\ No newline at end of file
@@ -0,0 +1,174 @@
+use std::sync::OnceLock;
+
+use clap::ValueEnum;
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource};
+use unic_langid::LanguageIdentifier;
+use unicode_width::UnicodeWidthStr;
+
+const EN: &str = include_str!("../data/locales/en.ftl");
+const ZH: &str = include_str!("../data/locales/zh.ftl");
+
+/// Language of client-generated text: labels, headings and warnings
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Locale {
+    /// English
+    #[default]
+    En,
+    /// Chinese
+    Zh,
+}
+
+impl Locale {
+    /// Locale of a POSIX or BCP 47 tag such as `zh_CN.UTF-8` or `zh-Hant`;
+    /// `C` and `POSIX` are English, other languages have no catalog yet
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let language = tag.split(['_', '-', '.', '@']).next().unwrap_or(tag).to_lowercase();
+        match language.as_str() {
+            "en" | "c" | "posix" => Some(Locale::En),
+            "zh" => Some(Locale::Zh),
+            _ => None,
+        }
+    }
+
+    /// Locale from `LC_ALL`, `LC_MESSAGES` and `LANG`, the first one set winning as in POSIX
+    pub fn from_env() -> Option<Self> {
+        let tag = ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty())?;
+        Self::from_tag(&tag)
+    }
+
+    fn source(self) -> &'static str {
+        match self {
+            Locale::En => EN,
+            Locale::Zh => ZH,
+        }
+    }
+
+    fn tag(self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::Zh => "zh",
+        }
+    }
+}
+
+static LOCALE: OnceLock<Locale> = OnceLock::new();
+
+/// Select the locale for the rest of the run; only the first call has an effect
+pub fn set_locale(locale: Locale) {
+    let _ = LOCALE.set(locale);
+}
+
+/// Locale selected with `set_locale`, English until then
+pub fn locale() -> Locale {
+    LOCALE.get().copied().unwrap_or_default()
+}
+
+fn bundle(locale: Locale) -> &'static FluentBundle<FluentResource> {
+    static EN_BUNDLE: OnceLock<FluentBundle<FluentResource>> = OnceLock::new();
+    static ZH_BUNDLE: OnceLock<FluentBundle<FluentResource>> = OnceLock::new();
+    let cell = match locale {
+        Locale::En => &EN_BUNDLE,
+        Locale::Zh => &ZH_BUNDLE,
+    };
+    cell.get_or_init(|| {
+        let langid: LanguageIdentifier = locale.tag().parse().expect("valid language tag");
+        let mut bundle = FluentBundle::new_concurrent(vec![langid]);
+        // Unicode isolation marks around arguments would show up in terminals and break alignment
+        bundle.set_use_isolating(false);
+        let resource = FluentResource::try_new(locale.source().to_string()).expect("valid built-in catalog");
+        bundle.add_resource(resource).expect("no duplicate messages in the built-in catalog");
+        bundle
+    })
+}
+
+/// Message in a locale, `None` when its catalog lacks it
+fn format(locale: Locale, id: &str, args: &[(&str, String)]) -> Option<String> {
+    let bundle = bundle(locale);
+    let pattern = bundle.get_message(id)?.value()?;
+    let mut fluent_args = FluentArgs::new();
+    for (name, value) in args {
+        fluent_args.set(*name, value.clone());
+    }
+    let mut errors = Vec::new();
+    Some(bundle.format_pattern(pattern, Some(&fluent_args), &mut errors).into_owned())
+}
+
+/// Whether the catalog of a locale has a message
+pub fn has_message(locale: Locale, id: &str) -> bool {
+    bundle(locale).has_message(id)
+}
+
+/// Message in the selected locale, falling back to English and then to the message id
+/// Use the `tr!` macro rather than calling this directly
+pub fn message(id: &str, args: &[(&str, String)]) -> String {
+    format(locale(), id, args)
+        .or_else(|| format(Locale::En, id, args))
+        .unwrap_or_else(|| id.to_string())
+}
+
+/// Text padded with spaces to a display width, for table columns holding translated labels
+pub fn pad(text: &str, width: usize) -> String {
+    format!("{}{}", text, " ".repeat(width.saturating_sub(text.width())))
+}
+
+/// Translated client message: `tr!("id")` or `tr!("id", name = value, ...)`,
+/// with values formatted by `Display` into the message's `{ $name }` placeables
+#[macro_export]
+macro_rules! tr {
+    ($id:expr) => {
+        $crate::i18n::message($id, &[])
+    };
+    ($id:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::message($id, &[$((stringify!($name), $value.to_string())),+])
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(source: &str) -> Vec<String> {
+        let mut ids: Vec<String> = source
+            .lines()
+            .filter(|line| line.starts_with(|c: char| c.is_ascii_lowercase()))
+            .filter_map(|line| line.split_once(" =").map(|(id, _)| id.to_string()))
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn test_catalogs_match() {
+        assert_eq!(ids(EN), ids(ZH));
+        assert!(ids(EN).len() > 10);
+        // Both catalogs parse
+        for locale in [Locale::En, Locale::Zh] {
+            assert!(bundle(locale).has_message("warn-empty-response"));
+        }
+    }
+
+    #[test]
+    fn test_format_messages() {
+        let args = [("domain", "example.com".to_string()), ("count", "3".to_string())];
+        assert_eq!(format(Locale::En, "ct-heading", &args).as_deref(), Some("Certificates logged for example.com (3)"));
+        assert_eq!(format(Locale::Zh, "ct-heading", &args).as_deref(), Some("CT 日志中 example.com 的证书（3）"));
+        assert_eq!(format(Locale::Zh, "no-such-message", &[]), None);
+        assert_eq!(message("no-such-message", &[]), "no-such-message");
+        assert_eq!(tr!("ct-heading", domain = "example.com", count = 3), "Certificates logged for example.com (3)");
+    }
+
+    #[test]
+    fn test_locale_tags() {
+        assert_eq!(Locale::from_tag("zh_CN.UTF-8"), Some(Locale::Zh));
+        assert_eq!(Locale::from_tag("zh-Hant"), Some(Locale::Zh));
+        assert_eq!(Locale::from_tag("en_US"), Some(Locale::En));
+        assert_eq!(Locale::from_tag("C.UTF-8"), Some(Locale::En));
+        assert_eq!(Locale::from_tag("de_DE.UTF-8"), None);
+        assert_eq!(pad("来源", 6), "来源  ");
+        assert_eq!(pad("Source", 8), "Source  ");
+    }
+}
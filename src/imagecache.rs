@@ -0,0 +1,249 @@
+use std::io::Read;
+use std::fs::FileTimes;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Context, Result};
+use tracing::{debug, info};
+
+use crate::config::cache_dir;
+
+/// Largest image downloaded
+pub const MAX_IMAGE_BYTES: u64 = 5 * 1024 * 1024;
+/// Total size of the cache; the least recently used images are removed beyond it
+pub const MAX_CACHE_BYTES: u64 = 100 * 1024 * 1024;
+/// How long a downloaded image is reused before it is fetched again
+pub const IMAGE_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+/// Content types accepted for remote images
+const IMAGE_TYPES: &[&str] = &["image/png", "image/jpeg", "image/gif", "image/webp"];
+
+/// On-disk cache of images referenced by URL in Markdown answers
+pub struct ImageCache {
+    dir: PathBuf,
+    max_bytes: u64,
+    max_total_bytes: u64,
+    timeout: Duration,
+    /// Refuse plain-HTTP URLs; only cleared by the tests, which serve images locally
+    https_only: bool,
+}
+
+impl ImageCache {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            max_bytes: MAX_IMAGE_BYTES,
+            max_total_bytes: MAX_CACHE_BYTES,
+            timeout: Duration::from_secs(10),
+            https_only: true,
+        }
+    }
+
+    /// Default location: `$XDG_CACHE_HOME/whois/images`, falling back to
+    /// `~/.cache/whois/images` (`%LOCALAPPDATA%\whois\images` on Windows)
+    pub fn default_dir() -> Option<PathBuf> {
+        Some(cache_dir()?.join("images"))
+    }
+
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    pub fn with_max_total_bytes(mut self, max_total_bytes: u64) -> Self {
+        self.max_total_bytes = max_total_bytes;
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Cache file of a URL
+    pub fn path_for(&self, url: &str) -> PathBuf {
+        self.dir.join(format!("{:016x}", fnv1a(url.as_bytes())))
+    }
+
+    /// Local copy of an image, downloaded unless a fresh one is cached
+    /// A failed download falls back to a stale copy when there is one
+    pub fn fetch(&self, url: &str) -> Result<PathBuf> {
+        if self.https_only && !url.get(..8).is_some_and(|scheme| scheme.eq_ignore_ascii_case("https://")) {
+            anyhow::bail!("Refusing to download {} over an insecure connection", url);
+        }
+        let path = self.path_for(url);
+        let age = std::fs::metadata(&path)
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok());
+        if age.is_some_and(|age| age < IMAGE_TTL) {
+            debug!("Using cached image {} for {}", path.display(), url);
+            touch(&path);
+            return Ok(path);
+        }
+        match self.download(url, &path) {
+            Ok(()) => Ok(path),
+            Err(err) if age.is_some() => {
+                debug!("Download of {} failed ({:#}), using stale copy", url, err);
+                touch(&path);
+                Ok(path)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    fn download(&self, url: &str, path: &Path) -> Result<()> {
+        info!("Downloading image {}", url);
+        let agent = ureq::AgentBuilder::new()
+            .timeout(self.timeout)
+            .user_agent(concat!("whois-cli/", env!("CARGO_PKG_VERSION")))
+            .build();
+        let response = agent.get(url).call().with_context(|| format!("Image request failed: {}", url))?;
+
+        let content_type = response.content_type().to_lowercase();
+        if !IMAGE_TYPES.contains(&content_type.as_str()) {
+            anyhow::bail!("{} is not an image ({})", url, content_type);
+        }
+        if let Some(length) = response.header("Content-Length").and_then(|length| length.parse::<u64>().ok()) {
+            if length > self.max_bytes {
+                anyhow::bail!("Image {} is too large ({} bytes, limit {})", url, length, self.max_bytes);
+            }
+        }
+        let mut data = Vec::new();
+        response
+            .into_reader()
+            .take(self.max_bytes + 1)
+            .read_to_end(&mut data)
+            .with_context(|| format!("Failed to read image {}", url))?;
+        if data.len() as u64 > self.max_bytes {
+            anyhow::bail!("Image {} is larger than {} bytes", url, self.max_bytes);
+        }
+
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create image cache {}", self.dir.display()))?;
+        std::fs::write(path, &data).with_context(|| format!("Failed to cache image {}", path.display()))?;
+        self.evict(path);
+        Ok(())
+    }
+
+    /// Remove the least recently used images until the cache fits `max_total_bytes`,
+    /// keeping `keep`, the image just downloaded
+    fn evict(&self, keep: &Path) {
+        let Ok(entries) = std::fs::read_dir(&self.dir) else {
+            return;
+        };
+        let mut images: Vec<(SystemTime, u64, PathBuf)> = entries
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                let meta = entry.metadata().ok()?;
+                let used = meta.accessed().or_else(|_| meta.modified()).ok()?;
+                meta.is_file().then(|| (used, meta.len(), entry.path()))
+            })
+            .collect();
+        let mut total: u64 = images.iter().map(|(_, len, _)| len).sum();
+        images.sort();
+        for (_, len, path) in images {
+            if total <= self.max_total_bytes {
+                break;
+            }
+            if path != keep && std::fs::remove_file(&path).is_ok() {
+                debug!("Evicted cached image {}", path.display());
+                total -= len;
+            }
+        }
+    }
+}
+
+/// Mark a cached image as used now, for eviction; the modification time, which the
+/// TTL is measured from, stays as it is
+fn touch(path: &Path) {
+    let touched = std::fs::File::options()
+        .write(true)
+        .open(path)
+        .and_then(|file| file.set_times(FileTimes::new().set_accessed(SystemTime::now())));
+    if let Err(err) = touched {
+        debug!("Cannot update the access time of {}: {}", path.display(), err);
+    }
+}
+
+/// 64-bit FNV-1a, stable across builds so cache file names stay valid
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::net::TcpListener;
+
+    /// Serve one HTTP response with the given content type and body
+    fn http_server(content_type: &'static str, body: &'static [u8]) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/logo.png", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let (mut socket, _) = listener.accept().unwrap();
+            let mut request = [0u8; 1024];
+            let _ = socket.read(&mut request);
+            let head = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                content_type,
+                body.len()
+            );
+            socket.write_all(head.as_bytes()).unwrap();
+            socket.write_all(body).unwrap();
+        });
+        url
+    }
+
+    fn temp_cache(name: &str) -> ImageCache {
+        let dir = std::env::temp_dir().join(format!("whois-images-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        ImageCache { https_only: false, ..ImageCache::new(dir) }
+    }
+
+    #[test]
+    fn test_download_and_reuse() {
+        let cache = temp_cache("reuse");
+        let url = http_server("image/png", b"\x89PNG fake");
+        let path = cache.fetch(&url).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"\x89PNG fake");
+        // The server is gone; the cached copy is used
+        assert_eq!(cache.fetch(&url).unwrap(), path);
+        let _ = std::fs::remove_dir_all(&cache.dir);
+    }
+
+    #[test]
+    fn test_rejects_non_images_and_large_files() {
+        let cache = temp_cache("reject");
+        assert!(cache.fetch(&http_server("text/html", b"<html></html>")).is_err());
+        let cache = cache.with_max_bytes(4);
+        assert!(cache.fetch(&http_server("image/png", b"\x89PNG too large")).is_err());
+        assert!(!cache.dir.exists());
+    }
+
+    #[test]
+    fn test_rejects_plain_http() {
+        let cache = ImageCache::new(std::env::temp_dir().join("whois-images-http"));
+        let err = cache.fetch("http://example.com/logo.png").unwrap_err();
+        assert!(err.to_string().contains("insecure"));
+        assert!(cache.fetch("HTTP://example.com/logo.png").is_err());
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        // Room for two of the three 10-byte images
+        let cache = temp_cache("evict").with_max_total_bytes(25);
+        let first = cache.fetch(&http_server("image/png", b"\x89PNG one..")).unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+        let second = cache.fetch(&http_server("image/png", b"\x89PNG two..")).unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+        // Using the first image again makes the second the least recently used
+        touch(&first);
+        std::thread::sleep(Duration::from_millis(20));
+        let third = cache.fetch(&http_server("image/png", b"\x89PNG three")).unwrap();
+        assert!(first.exists());
+        assert!(!second.exists());
+        assert!(third.exists());
+        let _ = std::fs::remove_dir_all(&cache.dir);
+    }
+}
@@ -0,0 +1,343 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use tracing::debug;
+
+use crate::bulk::BulkRunner;
+use crate::compression::MAX_RESPONSE_BYTES;
+use crate::query::WhoisQuery;
+use crate::rpsl::{self, Block};
+use crate::servers::{ServerRegistry, WhoisServer};
+
+/// Deepest nesting of sets followed when expanding with RPSL text queries
+const MAX_SET_DEPTH: usize = 16;
+
+/// Queries written to a session before their answers are read, so neither side's
+/// socket buffer fills up while the other is still writing
+const PIPELINE_DEPTH: usize = 64;
+
+/// How long a session waits to connect and for each answer
+const SESSION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Answer to an IRRd `!` query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reply {
+    /// `A<length>` followed by the data and `C`
+    Data(String),
+    /// `C` alone: the query succeeded without data
+    Empty,
+    /// `D`: the key was not found
+    NotFound,
+}
+
+/// Read an IRRd reply; `F` errors and anything that is not IRRd framing fail
+pub fn parse_reply(response: &str) -> Result<Reply> {
+    let response = response.trim_start_matches(['\r', '\n']);
+    let (status, rest) = response.split_once('\n').unwrap_or((response, ""));
+    let status = status.trim_end();
+    match status.chars().next() {
+        Some('A') => {
+            let length: usize = status[1..].parse().map_err(|_| anyhow::anyhow!("Invalid IRRd reply: {}", status))?;
+            let data = rest.get(..length).unwrap_or(rest);
+            Ok(Reply::Data(data.trim_end().to_string()))
+        }
+        Some('C') => Ok(Reply::Empty),
+        Some('D') => Ok(Reply::NotFound),
+        Some('F') => anyhow::bail!("IRRd error: {}", status[1..].trim()),
+        _ => anyhow::bail!("Not an IRRd reply: {}", status),
+    }
+}
+
+/// Words of a reply's data, e.g. the prefixes of `!g` or the members of `!i`
+fn words(reply: Reply) -> Vec<String> {
+    match reply {
+        Reply::Data(data) => data.split_whitespace().map(str::to_string).collect(),
+        Reply::Empty | Reply::NotFound => Vec::new(),
+    }
+}
+
+/// Whether a server speaks the `!` protocol: its profile says so, or it answered a `!v`
+/// version probe like IRRd does; probes are remembered for the rest of the run
+pub fn is_irrd(query: &WhoisQuery, registry: &ServerRegistry, server: &WhoisServer) -> bool {
+    static PROBED: OnceLock<Mutex<HashMap<String, bool>>> = OnceLock::new();
+    if let Some(irrd) = registry.speaks_irrd(&server.host) {
+        return irrd;
+    }
+    let probed = PROBED.get_or_init(Default::default);
+    if let Some(irrd) = probed.lock().ok().and_then(|probed| probed.get(&server.address()).copied()) {
+        return irrd;
+    }
+    let irrd = query
+        .query_direct("!v", server)
+        .ok()
+        .and_then(|response| parse_reply(&response).ok())
+        .is_some_and(|reply| matches!(reply, Reply::Data(version) if version.contains("IRRd")));
+    debug!("{} {} IRRd queries", server.address(), if irrd { "takes" } else { "does not take" });
+    if let Ok(mut probed) = probed.lock() {
+        probed.insert(server.address(), irrd);
+    }
+    irrd
+}
+
+/// A connection in IRRd's multiple-command mode (`!!`): the server keeps it open, so many
+/// queries are written back to back and the answers told apart by their `A<length>` framing
+pub struct Session {
+    address: String,
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl Session {
+    pub fn open(server: &WhoisServer) -> Result<Self> {
+        let address = server.address();
+        let (stream, _, _) = crate::timing::timed_connect(&address, SESSION_TIMEOUT)?;
+        let reader = BufReader::new(stream.try_clone().context("Failed to clone IRRd connection")?);
+        let mut session = Self { address, reader, writer: stream };
+        // IRRd does not answer `!!` itself
+        session.writer.write_all(b"!!\n").context("Failed to write to IRRd server")?;
+        debug!("Opened IRRd session with {}", session.address);
+        Ok(session)
+    }
+
+    /// Answers to `queries`, in order
+    pub fn pipeline(&mut self, queries: &[String]) -> Result<Vec<Reply>> {
+        let mut replies = Vec::with_capacity(queries.len());
+        for batch in queries.chunks(PIPELINE_DEPTH) {
+            let lines: String = batch.iter().map(|query| format!("{}\n", query)).collect();
+            self.writer.write_all(lines.as_bytes()).context("Failed to write to IRRd server")?;
+            for _ in batch {
+                replies.push(self.read_reply()?);
+            }
+        }
+        Ok(replies)
+    }
+
+    /// Members of a set with nested sets resolved (`!iSET,1`)
+    pub fn expand_set(&mut self, name: &str) -> Result<Vec<String>> {
+        match self.pipeline(&[format!("!i{},1", name)])?.remove(0) {
+            Reply::NotFound => anyhow::bail!("{} not found on {}", name, self.address),
+            reply => Ok(words(reply)),
+        }
+    }
+
+    /// One framed answer: a status line, and for `A<length>` that many bytes of data and a `C` line
+    fn read_reply(&mut self) -> Result<Reply> {
+        let mut status = String::new();
+        while status.trim().is_empty() {
+            status.clear();
+            if self.read_line(&mut status)? == 0 {
+                anyhow::bail!("{} closed the IRRd session", self.address);
+            }
+        }
+        let Some(length) = status.trim_end().strip_prefix('A') else {
+            return parse_reply(&status);
+        };
+        let length: usize = length.parse().map_err(|_| anyhow::anyhow!("Invalid IRRd reply: {}", status.trim_end()))?;
+        if length > MAX_RESPONSE_BYTES {
+            anyhow::bail!("IRRd reply from {} is larger than {} MiB", self.address, MAX_RESPONSE_BYTES / (1024 * 1024));
+        }
+        let mut data = vec![0; length];
+        self.reader.read_exact(&mut data).map_err(|err| crate::timing::read_error(err, &self.address))?;
+        let mut end = String::new();
+        self.read_line(&mut end)?;
+        if end.trim().is_empty() {
+            end.clear();
+            self.read_line(&mut end)?;
+        }
+        if end.trim_end() != "C" {
+            anyhow::bail!("Unexpected end of IRRd reply from {}: {}", self.address, end.trim_end());
+        }
+        Ok(Reply::Data(String::from_utf8_lossy(&data).trim_end().to_string()))
+    }
+
+    fn read_line(&mut self, line: &mut String) -> Result<usize> {
+        self.reader.read_line(line).map_err(|err| crate::timing::read_error(err, &self.address))
+    }
+}
+
+/// Prefixes originated by an AS, or by every AS of an as-set, IPv4 first per AS. On IRRd
+/// one `!!` session carries the `!i`, `!g` and `!6` queries; elsewhere sets are expanded with
+/// RPSL text queries and each AS's route and route6 objects found with `-i origin`
+pub fn prefix_list(query: &WhoisQuery, server: &WhoisServer, target: &str, irrd: bool) -> Result<Vec<String>> {
+    let mut session = if irrd { Some(Session::open(server)?) } else { None };
+    let members = match &mut session {
+        _ if !is_set_name(target) => vec![target.to_uppercase()],
+        Some(session) => session.expand_set(target)?,
+        None => expand_set(query, server, target, false)?,
+    };
+    // Route-sets list prefixes themselves; only ASNs need their routes looked up
+    let (asns, mut prefixes): (Vec<String>, Vec<String>) = members.into_iter().partition(|member| !member.contains('/'));
+    let found: Vec<String> = match &mut session {
+        Some(session) => {
+            let queries: Vec<String> = asns.iter().flat_map(|asn| [format!("!g{}", asn), format!("!6{}", asn)]).collect();
+            session.pipeline(&queries)?.into_iter().flat_map(words).collect()
+        }
+        None => {
+            let mut found = Vec::new();
+            for outcome in BulkRunner::new(true).run(&asns, |asn| routes_of(query, server, asn)) {
+                found.extend(outcome.result.with_context(|| format!("Prefixes of {}", outcome.target))?);
+            }
+            found
+        }
+    };
+    for prefix in found {
+        if !prefixes.contains(&prefix) {
+            prefixes.push(prefix);
+        }
+    }
+    Ok(prefixes)
+}
+
+/// Prefixes of the route and route6 objects with `origin: ASN`, IPv4 first
+fn routes_of(query: &WhoisQuery, server: &WhoisServer, asn: &str) -> Result<Vec<String>> {
+    let response = query.query_direct(&format!("-K -i origin {}", asn), server)?;
+    let mut prefixes: Vec<String> = Vec::new();
+    for object in objects(&response).filter(|object| matches!(object.class(), "route" | "route6")) {
+        let prefix = object.key().split_whitespace().next().unwrap_or_default().to_string();
+        if !prefixes.contains(&prefix) {
+            prefixes.push(prefix);
+        }
+    }
+    prefixes.sort_by_key(|prefix| prefix.contains(':'));
+    Ok(prefixes)
+}
+
+/// Members of an as-set or route-set, nested sets resolved: `!i SET,1` on IRRd, otherwise
+/// the set objects fetched one by one and their `members:` followed
+pub fn expand_set(query: &WhoisQuery, server: &WhoisServer, name: &str, irrd: bool) -> Result<Vec<String>> {
+    if irrd {
+        return Session::open(server)?.expand_set(name);
+    }
+    let mut members: Vec<String> = Vec::new();
+    let mut seen: Vec<String> = Vec::new();
+    let mut pending = vec![(name.to_uppercase(), 0)];
+    while let Some((set, depth)) = pending.pop() {
+        if seen.contains(&set) {
+            continue;
+        }
+        seen.push(set.clone());
+        let response = query.query_direct(&set, server)?;
+        let Some(object) = objects(&response).find(|object| object.key().eq_ignore_ascii_case(&set)) else {
+            if depth == 0 {
+                anyhow::bail!("{} not found on {}", name, server.host);
+            }
+            debug!("Nested set {} not found on {}", set, server.host);
+            continue;
+        };
+        let listed: Vec<String> = object
+            .values("members")
+            .chain(object.values("mp-members"))
+            .flat_map(|value| value.split([',', ' ', '\n']))
+            .map(|member| member.trim().to_uppercase())
+            .filter(|member| !member.is_empty())
+            .collect();
+        for member in listed {
+            if is_set_name(&member) {
+                if depth < MAX_SET_DEPTH {
+                    pending.push((member, depth + 1));
+                }
+            } else if !members.contains(&member) {
+                members.push(member);
+            }
+        }
+    }
+    Ok(members)
+}
+
+/// Whether a name refers to an as-set or route-set, including hierarchical ones (`AS3333:AS-FOO`)
+pub fn is_set_name(name: &str) -> bool {
+    name.split(':').any(|part| part.starts_with("AS-") || part.starts_with("RS-"))
+}
+
+fn objects(response: &str) -> impl Iterator<Item = rpsl::RpslObject> {
+    rpsl::parse(response).into_iter().filter_map(|block| match block {
+        Block::Object(object) => Some(object),
+        Block::Text(_) => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_parse_reply() {
+        assert_eq!(parse_reply("A28\n192.0.2.0/24 198.51.100.0/24\nC\n").unwrap(), Reply::Data("192.0.2.0/24 198.51.100.0/24".to_string()));
+        assert_eq!(parse_reply("C\n").unwrap(), Reply::Empty);
+        assert_eq!(parse_reply("D\n").unwrap(), Reply::NotFound);
+        assert_eq!(parse_reply("F Invalid query\n").unwrap_err().to_string(), "IRRd error: Invalid query");
+        assert!(parse_reply("%ERROR:101: no entries found\n").is_err());
+        assert!(is_set_name("AS3333:AS-CUSTOMERS") && is_set_name("RS-EXAMPLE") && !is_set_name("AS3333"));
+    }
+
+    /// Server answering each query with the canned response for it; after `!!` it keeps
+    /// answering on the same connection. Returns the server and a count of its connections
+    fn irr_server(answers: &'static [(&'static str, &'static str)]) -> (WhoisServer, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let connections = Arc::new(AtomicUsize::new(0));
+        let counter = connections.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                counter.fetch_add(1, Ordering::SeqCst);
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut writer = stream;
+                let mut keepalive = false;
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap_or(0) > 0 {
+                    let query = line.trim().to_string();
+                    line.clear();
+                    if query == "!!" {
+                        keepalive = true;
+                        continue;
+                    }
+                    let answer = answers.iter().find(|(asked, _)| *asked == query).map_or("%ERROR:101: no entries found\n", |(_, answer)| answer);
+                    let _ = writer.write_all(answer.as_bytes());
+                    if !keepalive {
+                        break;
+                    }
+                }
+            }
+        });
+        (WhoisServer::custom("127.0.0.1", port), connections)
+    }
+
+    #[test]
+    fn test_session_and_text_paths() {
+        let (server, connections) = irr_server(&[
+            ("!gAS64500", "A13\n192.0.2.0/24\nC\n"),
+            ("!6AS64500", "C\n"),
+            ("!gAS64501", "D\n"),
+            ("!6AS64501", "A14\n2001:db8::/32\nC\n"),
+            ("!iAS-EXAMPLE,1", "A15\nAS64500 AS64501\nC\n"),
+            ("!gAS64502", "A99999999999\n"),
+            ("-K -i origin AS64500", "route6:         2001:db8::/32\norigin:         AS64500\n\nroute:          192.0.2.0/24\norigin:         AS64500\n"),
+            ("AS-EXAMPLE", "as-set:         AS-EXAMPLE\nmembers:        AS64500, AS-NESTED\nsource:         TEST\n"),
+            ("AS-NESTED", "as-set:         AS-NESTED\nmembers:        AS64501\n                AS-EXAMPLE\nsource:         TEST\n"),
+        ]);
+        let query = WhoisQuery::new();
+        // Five queries over one connection
+        assert_eq!(prefix_list(&query, &server, "AS-EXAMPLE", true).unwrap(), ["192.0.2.0/24", "2001:db8::/32"]);
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+        let mut session = Session::open(&server).unwrap();
+        let replies = session.pipeline(&["!gAS64501".to_string(), "!gAS64500".to_string()]).unwrap();
+        assert_eq!(replies, [Reply::NotFound, Reply::Data("192.0.2.0/24".to_string())]);
+        assert!(session.expand_set("AS-MISSING").is_err());
+        let err = session.pipeline(&["!gAS64502".to_string()]).unwrap_err();
+        assert!(err.to_string().contains("larger than"), "{}", err);
+        // The server handles one connection at a time
+        drop(session);
+
+        assert_eq!(prefix_list(&query, &server, "as64500", false).unwrap(), ["192.0.2.0/24", "2001:db8::/32"]);
+        assert_eq!(expand_set(&query, &server, "AS-EXAMPLE", false).unwrap(), ["AS64500", "AS64501"]);
+        assert!(expand_set(&query, &server, "AS-MISSING", false).is_err());
+        assert!(!is_irrd(&query, &ServerRegistry::default(), &server));
+    }
+}
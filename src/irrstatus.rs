@@ -0,0 +1,196 @@
+use std::sync::OnceLock;
+
+use colored::*;
+use regex::Regex;
+
+use crate::i18n::pad;
+use crate::tr;
+
+/// Mirrors asked when `--servers` is not given
+pub const DEFAULT_MIRRORS: &[&str] = &["whois.radb.net", "rr.ntt.net", "rr.level3.net"];
+
+/// Servers holding the authoritative copy of an IRR source
+const AUTHORITATIVE: &[(&str, &str)] = &[
+    ("AFRINIC", "whois.afrinic.net"),
+    ("ALTDB", "whois.altdb.net"),
+    ("APNIC", "whois.apnic.net"),
+    ("ARIN", "rr.arin.net"),
+    ("BBOI", "irr.bboi.net"),
+    ("JPIRR", "jpirr.nic.ad.jp"),
+    ("LACNIC", "irr.lacnic.net"),
+    ("LEVEL3", "rr.level3.net"),
+    ("NTTCOM", "rr.ntt.net"),
+    ("RADB", "whois.radb.net"),
+    ("RIPE", "whois.ripe.net"),
+    ("TC", "whois.bgp.net.br"),
+];
+
+/// Serial range a server reports for one source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceSerial {
+    pub source: String,
+    /// Whether the server offers the source for mirroring (`Y`)
+    pub mirrorable: bool,
+    pub first: u64,
+    /// Serial of the newest change the server has applied
+    pub last: u64,
+    /// Serial of the last export, when the server makes exports
+    pub last_export: Option<u64>,
+}
+
+/// Server holding the authoritative copy of `source`, if known
+pub fn authoritative_server(source: &str) -> Option<&'static str> {
+    AUTHORITATIVE.iter().find(|(name, _)| name.eq_ignore_ascii_case(source)).map(|(_, server)| *server)
+}
+
+/// Query asking an IRRd server for the serials of `source`, or of every source it has
+pub fn serial_query(source: Option<&str>) -> String {
+    format!("!j-{}", source.unwrap_or("*"))
+}
+
+/// Query asking a RIPE database server, which does not know `!j`, for its sources
+pub const SOURCES_QUERY: &str = "-q sources";
+
+/// Serial ranges of an answer: IRRd `!j` lines (`RADB:N:0-123456:123400`) or
+/// RIPE `-q sources` lines (`RIPE:3:N:1-4567890`)
+pub fn parse_serials(response: &str) -> Vec<SourceSerial> {
+    static SERIAL: OnceLock<Regex> = OnceLock::new();
+    let serial = SERIAL.get_or_init(|| Regex::new(r"^([A-Za-z0-9-]+):(?:\d+:)?([YN]):(\d+)-(\d+)(?::(\d+))?\s*$").unwrap());
+
+    response
+        .lines()
+        .filter_map(|line| serial.captures(line.trim()))
+        .filter_map(|captures| {
+            Some(SourceSerial {
+                source: captures[1].to_uppercase(),
+                mirrorable: &captures[2] == "Y",
+                first: captures[3].parse().ok()?,
+                last: captures[4].parse().ok()?,
+                last_export: captures.get(5).and_then(|export| export.as_str().parse().ok()),
+            })
+        })
+        .collect()
+}
+
+/// One mirror's copy of a source compared with the authoritative serial
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MirrorStatus {
+    pub source: String,
+    pub server: String,
+    pub serial: u64,
+    /// Changes the mirror has not applied yet; `None` when the authoritative serial is unknown
+    pub behind: Option<u64>,
+    pub authoritative: bool,
+}
+
+/// Status of every source each mirror carries, limited to `sources` when not empty;
+/// `mirrors` and `authoritative` pair a server with the serials it reported.
+/// Sorted by source, then freshest mirror first
+pub fn statuses(mirrors: &[(String, Vec<SourceSerial>)], authoritative: &[(String, SourceSerial)], sources: &[String]) -> Vec<MirrorStatus> {
+    let wanted = |source: &str| sources.is_empty() || sources.iter().any(|wanted| wanted.eq_ignore_ascii_case(source));
+    let mut statuses: Vec<MirrorStatus> = Vec::new();
+    for (server, serials) in mirrors {
+        for serial in serials.iter().filter(|serial| wanted(&serial.source)) {
+            let reference = authoritative.iter().find(|(_, reference)| reference.source == serial.source);
+            statuses.push(MirrorStatus {
+                source: serial.source.clone(),
+                server: server.clone(),
+                serial: serial.last,
+                behind: reference.map(|(_, reference)| reference.last.saturating_sub(serial.last)),
+                authoritative: reference.is_some_and(|(host, _)| host == server),
+            });
+        }
+    }
+    // The authoritative server answers even when no mirror was asked about it
+    for (server, reference) in authoritative.iter().filter(|(_, reference)| wanted(&reference.source)) {
+        if !statuses.iter().any(|status| status.source == reference.source && &status.server == server) {
+            statuses.push(MirrorStatus {
+                source: reference.source.clone(),
+                server: server.clone(),
+                serial: reference.last,
+                behind: Some(0),
+                authoritative: true,
+            });
+        }
+    }
+    statuses.sort_by(|a, b| {
+        a.source
+            .cmp(&b.source)
+            .then(b.authoritative.cmp(&a.authoritative))
+            .then(b.serial.cmp(&a.serial))
+            .then(a.server.cmp(&b.server))
+    });
+    statuses
+}
+
+/// Serial table as `%` comment lines
+pub fn render(statuses: &[MirrorStatus]) -> String {
+    let mut lines = vec![
+        format!("% ─── {} ───", tr!("irr-status-heading")),
+        format!(
+            "% {} {} {} {}",
+            pad(&tr!("irr-status-source"), 10),
+            pad(&tr!("irr-status-server"), 22),
+            pad(&tr!("irr-status-serial"), 12),
+            tr!("irr-status-state")
+        ),
+    ];
+    if statuses.is_empty() {
+        lines.push(format!("% {}", tr!("irr-status-none")));
+    }
+    for status in statuses {
+        let state = match status.behind {
+            _ if status.authoritative => tr!("irr-status-authoritative").bold().to_string(),
+            Some(0) => tr!("irr-status-current").green().to_string(),
+            Some(behind) => tr!("irr-status-behind", count = behind).yellow().to_string(),
+            None => tr!("irr-status-unknown"),
+        };
+        lines.push(format!("% {:<10} {:<22} {:<12} {}", status.source, status.server, status.serial, state));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ansi::strip_ansi;
+
+    #[test]
+    fn test_parse_serials() {
+        let irrd = "A56\nRADB:N:0-2345678:2345000\nRIPE:Y:1-41250310\nNTTCOM:N:1-1234\nC\n";
+        let serials = parse_serials(irrd);
+        assert_eq!(serials.len(), 3);
+        assert_eq!(
+            serials[0],
+            SourceSerial { source: "RADB".to_string(), mirrorable: false, first: 0, last: 2345678, last_export: Some(2345000) }
+        );
+        assert!(serials[1].mirrorable);
+
+        let ripe = "% This is the RIPE Database query service.\n\nRIPE:3:N:1-41250400\nRIPE-NONAUTH:3:N:1-41250400\n";
+        let sources: Vec<(String, u64)> = parse_serials(ripe).into_iter().map(|serial| (serial.source, serial.last)).collect();
+        assert_eq!(sources, [("RIPE".to_string(), 41250400), ("RIPE-NONAUTH".to_string(), 41250400)]);
+        assert!(parse_serials("D\n").is_empty());
+        assert_eq!(authoritative_server("ripe"), Some("whois.ripe.net"));
+    }
+
+    #[test]
+    fn test_statuses() {
+        let mirrors = vec![
+            ("whois.radb.net".to_string(), parse_serials("RIPE:N:1-41250310\nRADB:N:0-500\n")),
+            ("rr.ntt.net".to_string(), parse_serials("RIPE:N:1-41250400\nRADB:N:0-480\n")),
+        ];
+        let authoritative = vec![
+            ("whois.ripe.net".to_string(), parse_serials("RIPE:3:N:1-41250400").remove(0)),
+            ("whois.radb.net".to_string(), parse_serials("RADB:N:0-500").remove(0)),
+        ];
+        let ripe = statuses(&mirrors, &authoritative, &["ripe".to_string()]);
+        let rows: Vec<(&str, Option<u64>, bool)> =
+            ripe.iter().map(|status| (status.server.as_str(), status.behind, status.authoritative)).collect();
+        assert_eq!(rows, [("whois.ripe.net", Some(0), true), ("rr.ntt.net", Some(0), false), ("whois.radb.net", Some(90), false)]);
+
+        let table = strip_ansi(&render(&ripe));
+        assert!(table.ends_with("% RIPE       whois.radb.net         41250310     90 changes behind"));
+        let unknown = strip_ansi(&render(&super::statuses(&mirrors, &[], &[])));
+        assert!(unknown.lines().skip(2).all(|line| line.ends_with("authoritative serial unknown")));
+    }
+}
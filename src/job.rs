@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// One finished target in a job state file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobEntry {
+    pub target: String,
+    /// Rendered output, present when the query succeeded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    /// Error message, present when the query failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Progress of a bulk run, persisted as one JSON line per finished target
+/// Lines are appended and flushed as targets complete, so an interrupted run
+/// loses at most the targets that were in flight
+pub struct JobState {
+    completed: HashMap<String, String>,
+    file: Mutex<File>,
+}
+
+impl JobState {
+    /// Start a new job, discarding any previous state in the file
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create job state file {}", path.display()))?;
+        Ok(Self {
+            completed: HashMap::new(),
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Continue a job, loading the targets that already succeeded
+    /// Failed targets are queried again; a missing file starts a new job
+    pub fn resume(path: &Path) -> Result<Self> {
+        let mut completed = HashMap::new();
+        if path.exists() {
+            let reader = BufReader::new(
+                File::open(path)
+                    .with_context(|| format!("Failed to open job state file {}", path.display()))?,
+            );
+            for line in reader.lines() {
+                let line = line.context("Failed to read job state file")?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                // A run killed mid-write can leave a truncated last line
+                let Ok(entry) = serde_json::from_str::<JobEntry>(&line) else {
+                    continue;
+                };
+                match entry.output {
+                    Some(output) => {
+                        completed.insert(entry.target, output);
+                    }
+                    None => {
+                        completed.remove(&entry.target);
+                    }
+                }
+            }
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open job state file {}", path.display()))?;
+
+        // Start on a fresh line after a truncated entry
+        let contents = std::fs::read(path).unwrap_or_default();
+        if contents.last().is_some_and(|&byte| byte != b'\n') {
+            file.write_all(b"\n").context("Failed to write job state file")?;
+        }
+
+        Ok(Self {
+            completed,
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Output recorded for a target by an earlier run
+    pub fn completed(&self, target: &str) -> Option<&str> {
+        self.completed.get(target).map(String::as_str)
+    }
+
+    /// Number of targets already finished
+    pub fn completed_count(&self) -> usize {
+        self.completed.len()
+    }
+
+    /// Append the result of a target
+    pub fn record(&self, target: &str, result: &Result<String>) -> Result<()> {
+        let entry = JobEntry {
+            target: target.to_string(),
+            output: result.as_ref().ok().cloned(),
+            error: result.as_ref().err().map(|err| format!("{:#}", err)),
+        };
+        let mut line = serde_json::to_string(&entry).context("Failed to serialize job entry")?;
+        line.push('\n');
+
+        let mut file = self.file.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        file.write_all(line.as_bytes())
+            .and_then(|_| file.flush())
+            .context("Failed to write job state file")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("whois-job-{}-{}.jsonl", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_resume_skips_successful_targets() {
+        let path = temp_path("resume");
+        let state = JobState::create(&path).unwrap();
+        state.record("AS3333", &Ok("aut-num: AS3333".to_string())).unwrap();
+        state.record("AS0", &Err(anyhow::anyhow!("timed out"))).unwrap();
+        drop(state);
+
+        let resumed = JobState::resume(&path).unwrap();
+        assert_eq!(resumed.completed("AS3333"), Some("aut-num: AS3333"));
+        assert_eq!(resumed.completed("AS0"), None);
+        assert_eq!(resumed.completed_count(), 1);
+
+        resumed.record("AS0", &Ok("aut-num: AS0".to_string())).unwrap();
+        drop(resumed);
+        let resumed = JobState::resume(&path).unwrap();
+        assert_eq!(resumed.completed_count(), 2);
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_resume_ignores_truncated_line() {
+        let path = temp_path("truncated");
+        std::fs::write(&path, "{\"target\":\"AS1\",\"output\":\"ok\"}\n{\"target\":\"AS2\",\"out").unwrap();
+
+        let resumed = JobState::resume(&path).unwrap();
+        assert_eq!(resumed.completed("AS1"), Some("ok"));
+        assert_eq!(resumed.completed("AS2"), None);
+
+        resumed.record("AS2", &Ok("ok".to_string())).unwrap();
+        drop(resumed);
+        let resumed = JobState::resume(&path).unwrap();
+        assert_eq!(resumed.completed("AS2"), Some("ok"));
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_create_discards_previous_state() {
+        let path = temp_path("create");
+        std::fs::write(&path, "{\"target\":\"AS1\",\"output\":\"ok\"}\n").unwrap();
+
+        drop(JobState::create(&path).unwrap());
+        let resumed = JobState::resume(&path).unwrap();
+        assert_eq!(resumed.completed_count(), 0);
+
+        std::fs::remove_file(&path).ok();
+    }
+}
@@ -0,0 +1,169 @@
+use serde::Serialize;
+
+use crate::ansi::strip_ansi;
+use crate::classify::Classification;
+use crate::error::kind_of;
+use crate::fingerprint::fingerprint;
+use crate::query::{FallbackInfo, QueryResult};
+use crate::timing::{HopTiming, QueryTiming};
+
+/// JSON representation of a successful lookup
+#[derive(Debug, Serialize)]
+pub struct JsonResult<'a> {
+    pub target: &'a str,
+    pub server: String,
+    pub classification: Classification,
+    pub server_colored: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fallback: Option<&'a FallbackInfo>,
+    /// Present when the run was interrupted before the answer was complete
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub partial: bool,
+    pub timing: JsonTiming,
+    /// Hash of the answer's data, unchanged by comments, timestamps and spacing
+    pub fingerprint: String,
+    /// Raw response with any server-side ANSI coloring removed
+    pub response: String,
+}
+
+/// JSON representation of a failed lookup
+#[derive(Debug, Serialize)]
+pub struct JsonError<'a> {
+    pub target: &'a str,
+    pub error: String,
+    /// `timeout`, `rate-limited`, `deadline-exceeded`, `cancelled`, `interrupted`,
+    /// `connection-reset`, or `error` for anything else
+    pub kind: &'static str,
+}
+
+/// Lookup timings in milliseconds
+#[derive(Debug, Serialize)]
+pub struct JsonTiming {
+    pub total_ms: f64,
+    pub hops: Vec<JsonHop>,
+}
+
+/// Per-hop timings in milliseconds
+#[derive(Debug, Serialize)]
+pub struct JsonHop {
+    pub server: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub probe_ms: Option<f64>,
+    pub dns_ms: f64,
+    pub connect_ms: f64,
+    pub write_ms: f64,
+    pub first_byte_ms: f64,
+    pub total_ms: f64,
+}
+
+impl From<&QueryTiming> for JsonTiming {
+    fn from(timing: &QueryTiming) -> Self {
+        Self {
+            total_ms: millis(timing.total),
+            hops: timing.hops.iter().map(JsonHop::from).collect(),
+        }
+    }
+}
+
+impl From<&HopTiming> for JsonHop {
+    fn from(hop: &HopTiming) -> Self {
+        Self {
+            server: hop.server.clone(),
+            probe_ms: hop.probe.map(millis),
+            dns_ms: millis(hop.dns),
+            connect_ms: millis(hop.connect),
+            write_ms: millis(hop.write),
+            first_byte_ms: millis(hop.first_byte),
+            total_ms: millis(hop.total),
+        }
+    }
+}
+
+fn millis(duration: std::time::Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Serialize a successful lookup
+pub fn result_record(target: &str, result: &QueryResult) -> String {
+    let record = JsonResult {
+        target,
+        server: result.server_used.address(),
+        classification: result.classification,
+        server_colored: result.server_colored,
+        fallback: result.fallback.as_ref(),
+        partial: result.partial,
+        timing: JsonTiming::from(&result.timing),
+        fingerprint: fingerprint(&result.response),
+        response: strip_ansi(&result.response),
+    };
+    serde_json::to_string_pretty(&record).unwrap_or_default()
+}
+
+/// Serialize a failed lookup
+pub fn error_record(target: &str, error: &anyhow::Error) -> String {
+    let record = JsonError {
+        target,
+        error: format!("{:#}", error),
+        kind: kind_of(error),
+    };
+    serde_json::to_string_pretty(&record).unwrap_or_default()
+}
+
+/// Combine per-target records: a single object for one target, an array otherwise
+pub fn combine(records: &[String], multi_target: bool) -> String {
+    if multi_target {
+        format!("[\n{}\n]", records.join(",\n"))
+    } else {
+        records.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::QueryError;
+    use crate::servers::WhoisServer;
+
+    #[test]
+    fn test_result_record_fields() {
+        let mut result = QueryResult::new(
+            "\x1b[32maut-num:\x1b[0m AS3333\n".to_string(),
+            WhoisServer::default(),
+        );
+        result.classification = Classification::Answer;
+
+        let value: serde_json::Value = serde_json::from_str(&result_record("AS3333", &result)).unwrap();
+        assert_eq!(value["target"], "AS3333");
+        assert_eq!(value["server"], "whois.ripe.net:43");
+        assert_eq!(value["classification"], "answer");
+        assert_eq!(value["response"], "aut-num: AS3333\n");
+        assert_eq!(value["fingerprint"], fingerprint("aut-num:        AS3333"));
+        assert!(value.get("fallback").is_none());
+        assert!(value["timing"]["hops"].as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_combine_records() {
+        let records = vec![
+            error_record("a.example", &anyhow::anyhow!("timed out")),
+            error_record("b.example", &anyhow::anyhow!("refused")),
+        ];
+        let value: serde_json::Value = serde_json::from_str(&combine(&records, true)).unwrap();
+        assert_eq!(value.as_array().unwrap().len(), 2);
+        assert_eq!(value[1]["error"], "refused");
+
+        let single: serde_json::Value = serde_json::from_str(&combine(&records[..1], false)).unwrap();
+        assert_eq!(single["target"], "a.example");
+    }
+
+    #[test]
+    fn test_error_record_kind() {
+        let limited = anyhow::Error::new(QueryError::RateLimited { server: "whois.example".to_string() }).context("Lookup failed");
+        let value: serde_json::Value = serde_json::from_str(&error_record("AS3333", &limited)).unwrap();
+        assert_eq!(value["kind"], "rate-limited");
+        assert_eq!(value["error"], "Lookup failed: Rate limited by WHOIS server: whois.example");
+
+        let other: serde_json::Value = serde_json::from_str(&error_record("AS3333", &anyhow::anyhow!("refused"))).unwrap();
+        assert_eq!(other["kind"], "error");
+    }
+}
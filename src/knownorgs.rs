@@ -0,0 +1,164 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::Deserialize;
+
+use crate::ansi::strip_ansi;
+use crate::colorize::Theme;
+use crate::config::Config;
+
+const FILE_NAME: &str = "known-orgs.yaml";
+
+/// Attributes naming an organisation handle
+const ORG_ATTRIBUTES: &[&str] = &["org", "organisation", "sponsoring-org", "orgid"];
+/// Attributes naming a maintainer
+const MAINTAINER_ATTRIBUTES: &[&str] = &["mntner", "mnt-by", "mnt-lower", "mnt-routes", "mnt-domains", "mnt-ref", "mnt-irt"];
+/// Attributes holding an organisation's name
+const NAME_ATTRIBUTES: &[&str] = &["org-name", "orgname", "owner", "descr", "custname", "registrant", "registrant organization"];
+
+/// An organisation from the known-organisations file
+///
+/// ```yaml
+/// - name: Example Networks
+///   aliases: ["Example Networks Ltd"]
+///   orgs: [ORG-EN1-RIPE, EXAMP-ARIN]
+///   maintainers: [EXAMPLE-MNT]
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KnownOrg {
+    /// Shown next to matching lines; also matched against organisation names
+    pub name: String,
+    /// Other names the organisation is registered under
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// Organisation handles
+    #[serde(default)]
+    pub orgs: Vec<String>,
+    /// Maintainer handles
+    #[serde(default)]
+    pub maintainers: Vec<String>,
+}
+
+impl KnownOrg {
+    fn matches(&self, attribute: &str, value: &str) -> bool {
+        let equal = |candidate: &String| candidate.trim().eq_ignore_ascii_case(value);
+        if ORG_ATTRIBUTES.contains(&attribute) {
+            self.orgs.iter().any(equal)
+        } else if MAINTAINER_ATTRIBUTES.contains(&attribute) {
+            self.maintainers.iter().any(equal)
+        } else if NAME_ATTRIBUTES.contains(&attribute) {
+            std::iter::once(&self.name).chain(&self.aliases).any(equal)
+        } else {
+            false
+        }
+    }
+}
+
+/// Organisations the user cares about, e.g. their own company and its customers,
+/// kept in `known-orgs.yaml` next to the config file or at `known_orgs` in the config
+#[derive(Debug, Clone, Default)]
+pub struct KnownOrgs {
+    orgs: Vec<KnownOrg>,
+}
+
+impl KnownOrgs {
+    pub fn parse(yaml: &str) -> Result<Self> {
+        let orgs: Option<Vec<KnownOrg>> = serde_yaml::from_str(yaml)?;
+        Ok(Self { orgs: orgs.unwrap_or_default() })
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read known organisations from {}", path.display()))?;
+        Self::parse(&contents).with_context(|| format!("Invalid known organisations file {}", path.display()))
+    }
+
+    /// `known-orgs.yaml` next to the config file
+    pub fn default_path() -> Option<PathBuf> {
+        Some(Config::default_path()?.parent()?.join(FILE_NAME))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.orgs.is_empty()
+    }
+
+    /// Known organisation an `attribute: value` line refers to
+    pub fn match_line(&self, line: &str) -> Option<&KnownOrg> {
+        if line.starts_with(['%', '#', ' ', '\t', '+']) {
+            return None;
+        }
+        let (attribute, value) = line.split_once(':')?;
+        let attribute = attribute.trim().to_lowercase();
+        let value = value.trim();
+        if value.is_empty() {
+            return None;
+        }
+        self.orgs.iter().find(|org| org.matches(&attribute, value))
+    }
+
+    /// Names of the known organisations an answer refers to, in order of first appearance
+    pub fn matches(&self, response: &str) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        for org in response.lines().filter_map(|line| self.match_line(line)) {
+            if !names.contains(&org.name.as_str()) {
+                names.push(&org.name);
+            }
+        }
+        names
+    }
+
+    /// Mark the lines of rendered output that refer to a known organisation
+    pub fn highlight(&self, output: &str, theme: Theme) -> String {
+        output
+            .lines()
+            .map(|line| match self.match_line(&strip_ansi(line)) {
+                Some(org) => format!("{}  {}", line, paint(&format!("◀ {}", org.name), theme)),
+                None => line.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// A color used for nothing else, so known organisations stand out
+fn paint(text: &str, theme: Theme) -> String {
+    match theme {
+        Theme::Default => text.bright_magenta().bold().to_string(),
+        Theme::HighContrast => text.bright_magenta().bold().reversed().to_string(),
+        // Okabe-Ito reddish purple
+        Theme::Deuteranopia => text.truecolor(204, 121, 167).bold().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORGS: &str = "- name: Example Networks\n  aliases: [\"Example Networks Ltd\"]\n  orgs: [ORG-EN1-RIPE]\n  maintainers: [EXAMPLE-MNT]\n\
+                        - name: Customer\n  maintainers: [CUSTOMER-MNT]\n";
+
+    #[test]
+    fn test_match_lines() {
+        let known = KnownOrgs::parse(ORGS).unwrap();
+        assert_eq!(known.match_line("mnt-by:         example-mnt").map(|org| org.name.as_str()), Some("Example Networks"));
+        assert_eq!(known.match_line("org-name:       EXAMPLE NETWORKS LTD").map(|org| org.name.as_str()), Some("Example Networks"));
+        assert_eq!(known.match_line("mnt-routes:     CUSTOMER-MNT").map(|org| org.name.as_str()), Some("Customer"));
+        // Handles only count in attributes of their kind
+        assert_eq!(known.match_line("remarks:        EXAMPLE-MNT"), None);
+        assert_eq!(known.match_line("org:            EXAMPLE-MNT"), None);
+
+        let response = "inetnum:        192.0.2.0 - 192.0.2.255\norg:            ORG-EN1-RIPE\nmnt-by:         CUSTOMER-MNT\nmnt-by:         EXAMPLE-MNT\n";
+        assert_eq!(known.matches(response), ["Example Networks", "Customer"]);
+        assert!(KnownOrgs::parse("- name: X\n  org: [ORG-X]\n").is_err());
+        assert!(KnownOrgs::parse("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_highlight() {
+        let known = KnownOrgs::parse(ORGS).unwrap();
+        let output = known.highlight("netname:        EXAMPLE\nmnt-by:         EXAMPLE-MNT", Theme::Default);
+        assert_eq!(strip_ansi(&output), "netname:        EXAMPLE\nmnt-by:         EXAMPLE-MNT  ◀ Example Networks");
+    }
+}
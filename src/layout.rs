@@ -0,0 +1,264 @@
+use std::sync::OnceLock;
+
+use regex::Regex;
+use unicode_width::UnicodeWidthChar;
+
+/// Narrowest width wrapping is applied at; smaller values are raised to it
+pub const MIN_WIDTH: usize = 20;
+
+/// Width of the terminal: `$COLUMNS` when set, otherwise asked from the terminal on stdout
+#[cfg(feature = "net")]
+pub fn terminal_width() -> Option<usize> {
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.trim().parse::<usize>().ok())
+        .filter(|&columns| columns > 0)
+        .or_else(|| terminal_size::terminal_size().map(|(width, _)| width.0 as usize))
+}
+
+/// Wrap every line of a whois answer that is wider than `width`
+/// Attribute values continue under the value column; comment lines repeat their `%` marker
+pub fn wrap(text: &str, width: usize) -> String {
+    map_lines(text, |line| wrap_line(line, width, &continuation(line)))
+}
+
+/// Wrap rendered Markdown: list items and quotes continue under their text
+/// Fenced code blocks are left as they are
+pub fn wrap_paragraphs(text: &str, width: usize) -> String {
+    let mut in_code = false;
+    map_lines(text, |line| {
+        if visible(line).trim_start().starts_with("```") {
+            in_code = !in_code;
+            return line.to_string();
+        }
+        if in_code {
+            line.to_string()
+        } else {
+            wrap_line(line, width, &" ".repeat(text_column(line)))
+        }
+    })
+}
+
+/// Display width of a line, ignoring escape sequences
+pub fn display_width(line: &str) -> usize {
+    tokenize(line).iter().map(|token| token.spaces + token.width).sum()
+}
+
+/// Start of the continuation lines of a whois line: spaces up to the value of an
+/// `attribute: value` line, the marker and indentation of a `%` or `#` comment,
+/// or the indentation of any other line
+pub fn continuation(line: &str) -> String {
+    static ATTRIBUTE: OnceLock<Regex> = OnceLock::new();
+    let attribute = ATTRIBUTE.get_or_init(|| Regex::new(r"^(\s*[A-Za-z0-9][\w./ -]{0,40}?:\s+)\S").unwrap());
+    let plain = visible(line).replace('\t', &" ".repeat(8));
+    match attribute.captures(&plain).and_then(|captures| captures.get(1)) {
+        Some(name) => " ".repeat(str_width(name.as_str())),
+        None => plain[..plain.len() - plain.trim_start_matches(['%', '#', ' ']).len()].to_string(),
+    }
+}
+
+/// Column where the text of a rendered Markdown line starts, after indentation, bullets and quote bars
+fn text_column(line: &str) -> usize {
+    static MARKER: OnceLock<Regex> = OnceLock::new();
+    let marker = MARKER.get_or_init(|| Regex::new(r"^\s*(▍ )*(• |\d+\. )?").unwrap());
+    let plain = visible(line);
+    marker.find(&plain).map(|found| str_width(found.as_str())).unwrap_or(0)
+}
+
+/// Break a line at spaces so no part is wider than `width`, starting continuation lines with `prefix`
+/// Words wider than the line, such as long URLs, are never split
+pub fn wrap_line(line: &str, width: usize, prefix: &str) -> String {
+    let width = width.max(MIN_WIDTH);
+    if display_width(line) <= width {
+        return line.to_string();
+    }
+    // A value column past the middle would leave too little room for the value
+    let prefix = if str_width(prefix) <= width / 2 { prefix } else { "" };
+    let indent = str_width(prefix);
+
+    let mut output = String::with_capacity(line.len() + 16);
+    let mut column = 0;
+    // SGR sequences in effect, restored after each break so colors carry over
+    let mut style = String::new();
+    for (index, token) in tokenize(line).into_iter().enumerate() {
+        if index > 0 && token.width > 0 && column + token.spaces + token.width > width && column > indent {
+            if !style.is_empty() {
+                output.push_str("\x1b[0m");
+            }
+            output.push('\n');
+            output.push_str(prefix);
+            output.push_str(&style);
+            column = indent;
+        } else {
+            output.push_str(&" ".repeat(token.spaces));
+            column += token.spaces;
+        }
+        output.push_str(&token.text);
+        column += token.width;
+        for sgr in &token.sgr {
+            if sgr == "\x1b[0m" || sgr == "\x1b[m" {
+                style.clear();
+            } else {
+                style.push_str(sgr);
+            }
+        }
+    }
+    output
+}
+
+/// A word with the spaces before it
+struct Token {
+    spaces: usize,
+    text: String,
+    width: usize,
+    /// SGR sequences inside the word, in order
+    sgr: Vec<String>,
+}
+
+/// Split a line into words, keeping escape sequences with the word they touch
+fn tokenize(line: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut current = Token { spaces: 0, text: String::new(), width: 0, sgr: Vec::new() };
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            let escape = read_escape(&mut chars);
+            if escape.starts_with("\x1b[") && escape.ends_with('m') {
+                current.sgr.push(escape.clone());
+            }
+            current.text.push_str(&escape);
+        } else if c == ' ' {
+            if current.text.is_empty() {
+                current.spaces += 1;
+            } else {
+                // Escape sequences alone become a zero-width token that never causes a break
+                tokens.push(std::mem::replace(&mut current, Token { spaces: 1, text: String::new(), width: 0, sgr: Vec::new() }));
+            }
+        } else {
+            current.text.push(c);
+            current.width += char_width(c);
+        }
+    }
+    if !current.text.is_empty() || current.spaces > 0 {
+        tokens.push(current);
+    }
+    tokens
+}
+
+/// Rest of an escape sequence after ESC: CSI up to its final byte, or OSC/DCS/APC up to BEL or ST
+fn read_escape(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> String {
+    let mut escape = String::from('\x1b');
+    let Some(kind) = chars.next() else {
+        return escape;
+    };
+    escape.push(kind);
+    match kind {
+        '[' => {
+            for c in chars.by_ref() {
+                escape.push(c);
+                if ('\x40'..='\x7e').contains(&c) {
+                    break;
+                }
+            }
+        }
+        ']' | 'P' | '_' | '^' | 'X' => {
+            while let Some(c) = chars.next() {
+                escape.push(c);
+                if c == '\x07' {
+                    break;
+                }
+                if c == '\x1b' && chars.peek() == Some(&'\\') {
+                    escape.push(chars.next().unwrap());
+                    break;
+                }
+            }
+        }
+        _ => {}
+    }
+    escape
+}
+
+/// Visible text of a line
+fn visible(line: &str) -> String {
+    let mut text = String::with_capacity(line.len());
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            read_escape(&mut chars);
+        } else {
+            text.push(c);
+        }
+    }
+    text
+}
+
+fn str_width(text: &str) -> usize {
+    text.chars().map(char_width).sum()
+}
+
+/// Columns taken by a character; tabs are counted as a full tab stop
+fn char_width(c: char) -> usize {
+    if c == '\t' {
+        8
+    } else {
+        c.width().unwrap_or(0)
+    }
+}
+
+fn map_lines(text: &str, wrap: impl FnMut(&str) -> String) -> String {
+    text.split('\n').map(wrap).collect::<Vec<_>>().join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attribute_values_hang_under_value_column() {
+        let line = "descr:          Reseaux IP Europeens Network Coordination Centre (RIPE NCC) Amsterdam";
+        let wrapped = wrap(line, 40);
+        let lines: Vec<&str> = wrapped.lines().collect();
+        assert_eq!(lines[0], "descr:          Reseaux IP Europeens");
+        assert!(lines[1..].iter().all(|line| line.starts_with(&" ".repeat(16)) && !line[16..].starts_with(' ')));
+        assert!(lines.iter().all(|line| display_width(line) <= 40));
+        assert_eq!(wrapped.split_whitespace().collect::<Vec<_>>(), line.split_whitespace().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_short_lines_and_long_words_untouched() {
+        assert_eq!(wrap("aut-num: AS3333\n\nsource: RIPE\n", 40), "aut-num: AS3333\n\nsource: RIPE\n");
+        let url = format!("remarks: https://example.net/{}", "a".repeat(60));
+        assert_eq!(wrap(&url, 40), url);
+    }
+
+    #[test]
+    fn test_colors_survive_breaks() {
+        let line = "\x1b[96mremarks\x1b[0m:        \x1b[32mone two three four five six seven eight nine ten\x1b[0m";
+        let wrapped = wrap(line, 40);
+        let lines: Vec<&str> = wrapped.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with("\x1b[0m"));
+        assert!(lines[1].starts_with(&format!("{}\x1b[32m", " ".repeat(16))));
+        assert!(lines.iter().all(|line| display_width(line) <= 40));
+        assert_eq!(visible(&wrapped).split_whitespace().count(), 11);
+    }
+
+    #[test]
+    fn test_comments_and_markdown_items() {
+        let comment = "% This query was served by the RIPE Database Query Service version 1.112";
+        let wrapped = wrap(comment, 40);
+        assert!(wrapped.lines().skip(1).all(|line| line.starts_with("% ") && !line.starts_with("%  ")));
+
+        let markdown = "  • first item with enough words to need a second line here\n```\nlet code = \"is never wrapped even when it is long\";\n```\n";
+        let wrapped = wrap_paragraphs(markdown, 30);
+        assert!(wrapped.lines().nth(1).unwrap().starts_with("    "));
+        assert!(wrapped.contains("let code = \"is never wrapped even when it is long\";"));
+    }
+
+    #[test]
+    fn test_display_width_ignores_escapes() {
+        assert_eq!(display_width("\x1b[1;91mAS3333\x1b[0m"), 6);
+        assert_eq!(display_width("\x1b]8;;https://example.net\x1b\\link\x1b]8;;\x1b\\"), 4);
+        assert_eq!(display_width("日本"), 4);
+    }
+}
@@ -1,15 +1,141 @@
+#[cfg(feature = "net")]
 pub mod cli;
+#[cfg(feature = "net")]
 pub mod query;
 pub mod colorize;
+pub mod i18n;
+pub mod palettes;
 pub mod servers;
 pub mod hyperlink;
 pub mod protocol;
 pub mod markdown;
+pub mod graphics;
+#[cfg(feature = "images")]
+pub mod imagecache;
+pub mod html;
+pub mod ansi;
+pub mod clipboard;
+pub mod bulk;
+pub mod allocations;
+#[cfg(feature = "net")]
+pub mod progress;
+pub mod logging;
+#[cfg(feature = "net")]
+pub mod timing;
+pub mod metrics;
+pub mod ratelimit;
+pub mod job;
+pub mod config;
+pub mod error;
+pub mod classify;
+#[cfg(feature = "net")]
+pub mod json;
+#[cfg(feature = "net")]
+pub mod embed;
+pub mod rules;
+pub mod rpsl;
+pub mod referral;
+#[cfg(feature = "net")]
+pub mod rwhois;
+#[cfg(feature = "net")]
+pub mod persistent;
+pub mod compression;
+pub mod deadline;
+pub mod normalize;
+pub mod psl;
+pub mod netblock;
+pub mod registration;
+pub mod related;
+pub mod ripestat;
+pub mod history;
+pub mod remotehistory;
+#[cfg(feature = "net")]
+pub mod portfolio;
+#[cfg(feature = "net")]
+pub mod audit;
+#[cfg(feature = "net")]
+pub mod auditlog;
+pub mod layout;
+pub mod dates;
+pub mod glossary;
+pub mod knownorgs;
+pub mod lint;
+pub mod schema;
+pub mod dn42;
+pub mod pgp;
+pub mod contacts;
+#[cfg(feature = "net")]
+pub mod correlate;
+pub mod compare;
+pub mod conformance;
+pub mod fingerprint;
+#[cfg(feature = "net")]
+pub mod irrd;
+pub mod irrstatus;
+pub mod ct;
+#[cfg(feature = "net")]
+pub mod mailcheck;
+#[cfg(feature = "net")]
+pub mod rdap;
+#[cfg(feature = "net")]
+pub mod repl;
+#[cfg(feature = "net")]
+pub mod resolve;
+pub mod secrets;
+#[cfg(feature = "net")]
+pub mod source;
+#[cfg(feature = "net")]
+pub mod tld;
+pub mod version;
+#[cfg(feature = "render")]
+pub mod render;
+#[cfg(feature = "sqlite")]
+pub mod store;
+#[cfg(feature = "sqlite")]
+pub mod archive;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "python")]
+pub mod python;
 
-pub use cli::Cli;
-pub use query::{WhoisQuery, QueryResult};
-pub use colorize::{ColorScheme, OutputColorizer};
-pub use servers::{ServerSelector, WhoisServer};
+#[cfg(feature = "net")]
+pub use cli::{Cli, Command, OutputFormat};
+#[cfg(feature = "net")]
+pub use query::{WhoisQuery, QueryResult, FallbackInfo, ProgressCallback};
+pub use colorize::{ColorScheme, OutputColorizer, Theme, Tone};
+pub use palettes::Palette;
+pub use servers::{FallbackCondition, FallbackStep, HierarchyFlags, Language, LanguageSupport, QueryFlags, QuerySyntax, RecordType, ServerProfile, ServerRegistry, ServerSelector, WhoisServer};
 pub use hyperlink::{RirHyperlinkProcessor, RipeHyperlinkProcessor, is_ripe_response, is_rir_response, terminal_supports_hyperlinks};
-pub use protocol::{WhoisColorProtocol, ServerCapabilities};
-pub use markdown::MarkdownRenderer; 
\ No newline at end of file
+pub use ansi::strip_ansi;
+pub use protocol::{CapabilityCache, MemoryCapabilityCache, WhoisColorProtocol, ServerCapabilities};
+pub use compression::ContentEncoding;
+pub use deadline::Deadline;
+pub use netblock::Netblock;
+pub use related::RelatedNetworks;
+#[cfg(feature = "net")]
+pub use ripestat::RipeStat;
+pub use markdown::MarkdownRenderer;
+pub use graphics::ImageProtocol;
+pub use html::HtmlRenderer;
+pub use bulk::{BulkOutcome, BulkRunner};
+pub use ratelimit::HostLimiter;
+#[cfg(feature = "net")]
+pub use persistent::{ConnectionPool, PersistentConnection};
+pub use job::{JobEntry, JobState};
+#[cfg(feature = "net")]
+pub use auditlog::{AuditEntry, AuditLog, AuditStatus};
+pub use config::{Config, Preset};
+pub use knownorgs::{KnownOrg, KnownOrgs};
+pub use dn42::Dn42Registry;
+pub use error::QueryError;
+pub use classify::{Classification, EmptyResultPolicy};
+pub use rules::{CleanupLevel, ResponseRule, RuleAction};
+#[cfg(feature = "net")]
+pub use progress::{QueryProgress, TargetProgress};
+#[cfg(feature = "net")]
+pub use timing::{HopTiming, QueryTiming};
+pub use metrics::Metrics;
+#[cfg(feature = "render")]
+pub use render::ImageRenderer;
+#[cfg(feature = "sqlite")]
+pub use store::{Store, StoredAnswer}; 
\ No newline at end of file
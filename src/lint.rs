@@ -0,0 +1,193 @@
+use std::fmt;
+
+use crate::dates;
+use crate::ripestat;
+use crate::tr;
+
+/// International calling codes by ISO 3166 country code, for the phone prefix check
+const CALLING_CODES: &[(&str, &str)] = &[
+    ("US", "1"), ("CA", "1"), ("PR", "1"), ("RU", "7"), ("KZ", "7"),
+    ("EG", "20"), ("ZA", "27"), ("GR", "30"), ("NL", "31"), ("BE", "32"),
+    ("FR", "33"), ("ES", "34"), ("PT", "351"), ("LU", "352"), ("IE", "353"),
+    ("IS", "354"), ("FI", "358"), ("BG", "359"), ("HU", "36"), ("LT", "370"),
+    ("LV", "371"), ("EE", "372"), ("MD", "373"), ("UA", "380"), ("RS", "381"),
+    ("HR", "385"), ("SI", "386"), ("IT", "39"), ("RO", "40"), ("CH", "41"),
+    ("CZ", "420"), ("SK", "421"), ("AT", "43"), ("GB", "44"), ("DK", "45"),
+    ("SE", "46"), ("NO", "47"), ("PL", "48"), ("DE", "49"), ("MX", "52"),
+    ("AR", "54"), ("BR", "55"), ("CL", "56"), ("CO", "57"), ("MY", "60"),
+    ("AU", "61"), ("ID", "62"), ("PH", "63"), ("NZ", "64"), ("SG", "65"),
+    ("TH", "66"), ("JP", "81"), ("KR", "82"), ("VN", "84"), ("CN", "86"),
+    ("TR", "90"), ("IN", "91"), ("PK", "92"), ("IR", "98"), ("HK", "852"),
+    ("TW", "886"), ("AE", "971"), ("IL", "972"), ("SA", "966"), ("NG", "234"),
+    ("KE", "254"),
+];
+
+/// Attributes holding the creation date of an object
+const CREATED: &[&str] = &["created", "creation date", "regdate", "registration date", "registered"];
+/// Attributes holding the last change of an object
+const MODIFIED: &[&str] = &["last-modified", "updated date", "updated", "last modified", "last-update"];
+
+/// Suspicious data found in a response
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintWarning {
+    /// First line of the object, e.g. `person: John Doe`
+    pub object: String,
+    pub attribute: String,
+    pub message: String,
+}
+
+impl fmt::Display for LintWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({}): {}", self.object, self.attribute, self.message)
+    }
+}
+
+/// Check every object of a response; `target` is the query, used to match route origins
+pub fn lint(response: &str, target: &str) -> Vec<LintWarning> {
+    let asn = ripestat::parse_asn(&target.to_uppercase());
+    let mut warnings = Vec::new();
+    for block in response.split("\n\n") {
+        let fields = fields(block);
+        let Some((class, key)) = fields.first() else {
+            continue;
+        };
+        let object = format!("{}: {}", class, key);
+        let mut warn = |attribute: &str, message: String| {
+            warnings.push(LintWarning { object: object.clone(), attribute: attribute.to_string(), message });
+        };
+
+        for (name, value) in &fields {
+            let lower = name.to_lowercase();
+            if lower.contains("mail") && !is_redacted(value) && !is_valid_email(value) {
+                warn(name, format!("\"{}\" is not a valid email address", value));
+            }
+            if lower.contains("phone") || lower.contains("fax") {
+                // `Registrant Phone` goes with `Registrant Country`, `phone` with `country`
+                let owner = lower.split_whitespace().next().filter(|_| lower.contains(' '));
+                let country = fields.iter().find(|(other, _)| {
+                    let other = other.to_lowercase();
+                    match owner {
+                        Some(owner) => other == format!("{} country", owner),
+                        None => other == "country",
+                    }
+                });
+                if let Some(message) = country.and_then(|(_, country)| phone_mismatch(value, country)) {
+                    warn(name, message);
+                }
+            }
+        }
+
+        let created = find(&fields, CREATED);
+        let modified = find(&fields, MODIFIED);
+        if let (Some((created_name, created)), Some((_, modified))) = (created, modified) {
+            if let (Some(created_day), Some(modified_day)) = (dates::parse_day(created), dates::parse_day(modified)) {
+                if created_day > modified_day {
+                    warn(created_name, format!("created {} is later than last modified {}", created, modified));
+                }
+            }
+        }
+
+        if let Some(asn) = asn.filter(|_| class.eq_ignore_ascii_case("route") || class.eq_ignore_ascii_case("route6")) {
+            if let Some((_, origin)) = find(&fields, &["origin"]) {
+                if ripestat::parse_asn(&origin.to_uppercase()) != Some(asn) {
+                    warn("origin", format!("origin {} does not match the queried AS{}", origin, asn));
+                }
+            }
+        }
+    }
+    warnings
+}
+
+/// Warnings as `%` comment lines, shown after the response
+pub fn render(warnings: &[LintWarning]) -> String {
+    let mut lines = vec![format!("% ─── {} ───", tr!("lint-heading", count = warnings.len()))];
+    lines.extend(warnings.iter().map(|warning| format!("% [!] {}", warning)));
+    lines.join("\n")
+}
+
+/// `name: value` lines of a block, skipping comments and continuation lines
+fn fields(block: &str) -> Vec<(&str, &str)> {
+    block
+        .lines()
+        .filter(|line| !line.starts_with(['%', '#', ' ', '\t', '+']))
+        .filter_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            let (name, value) = (name.trim(), value.trim());
+            (!name.is_empty() && name.len() <= 40 && !value.is_empty()).then_some((name, value))
+        })
+        .collect()
+}
+
+/// First value of any of the attributes
+fn find<'a>(fields: &[(&'a str, &'a str)], names: &[&str]) -> Option<(&'a str, &'a str)> {
+    fields.iter().copied().find(|(name, _)| names.iter().any(|known| name.eq_ignore_ascii_case(known)))
+}
+
+/// Values hidden by the registry, such as `REDACTED FOR PRIVACY` or a pointer to a web form
+fn is_redacted(value: &str) -> bool {
+    let lower = value.to_lowercase();
+    !value.contains('@') && (value.contains(' ') || lower.contains("redacted") || lower.contains("privacy"))
+}
+
+pub(crate) fn is_valid_email(value: &str) -> bool {
+    let Some((local, domain)) = value.split_once('@') else {
+        return false;
+    };
+    let label_ok = |label: &str| {
+        !label.is_empty() && !label.starts_with('-') && !label.ends_with('-') && label.chars().all(|c| c.is_alphanumeric() || c == '-')
+    };
+    !local.is_empty()
+        && !local.contains(char::is_whitespace)
+        && !local.starts_with('.')
+        && !local.ends_with('.')
+        && !local.contains("..")
+        && domain.contains('.')
+        && domain.split('.').all(label_ok)
+}
+
+/// Message when an international phone number does not start with the calling code of the country
+fn phone_mismatch(phone: &str, country: &str) -> Option<String> {
+    let country = country.trim().to_uppercase();
+    let (_, code) = CALLING_CODES.iter().find(|(known, _)| *known == country)?;
+    let digits: String = phone.strip_prefix('+')?.chars().take_while(|c| !c.is_alphabetic()).filter(char::is_ascii_digit).collect();
+    if digits.is_empty() || digits.starts_with(code) {
+        return None;
+    }
+    Some(format!("{} does not start with +{}, the calling code of {}", phone, code, country))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_email_and_phone_checks() {
+        let response = "person:  John Doe\ne-mail:  john@@example.net\nphone:   +44 20 7946 0000\ncountry: DE\n\n\
+                        Registrant Email: Please query the RDDS service of the Registrar of Record\n\
+                        Registrant Phone: +1.5555550100\nRegistrant Country: US\nAdmin Email: admin@example.com\n";
+        let warnings = lint(response, "example.com");
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0].attribute, "e-mail");
+        assert_eq!(warnings[1].to_string(), "person: John Doe (phone): +44 20 7946 0000 does not start with +49, the calling code of DE");
+    }
+
+    #[test]
+    fn test_dates_and_route_origin() {
+        let response = "route:         192.0.2.0/24\norigin:        AS64501\ncreated:       2024-05-01T00:00:00Z\nlast-modified: 2023-01-01T00:00:00Z\n\n\
+                        route:         198.51.100.0/24\norigin:        AS64500\n";
+        let warnings = lint(response, "as64500");
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].message.starts_with("created 2024-05-01"));
+        assert_eq!(warnings[1].message, "origin AS64501 does not match the queried AS64500");
+        assert!(lint(response, "192.0.2.1").iter().all(|warning| warning.attribute != "origin"));
+    }
+
+    #[test]
+    fn test_valid_email() {
+        assert!(is_valid_email("noc@ripe.net"));
+        assert!(is_valid_email("first.last+tag@sub.example.co.uk"));
+        assert!(!is_valid_email("noc@localhost"));
+        assert!(!is_valid_email("noc@-bad.example"));
+        assert!(!is_valid_email("a..b@example.net"));
+    }
+}
@@ -0,0 +1,77 @@
+use std::fs::File;
+use std::io::IsTerminal;
+use std::path::Path;
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::prelude::*;
+
+/// Install a stderr subscriber for diagnostics at the given level
+/// If a trace file is given, every event and span (with timings) is also written to it as JSON lines
+/// Library users can install their own subscriber instead
+pub fn init(level: LevelFilter, trace_file: Option<&Path>) -> Result<()> {
+    let stderr_layer = tracing_subscriber::fmt::layer()
+        .with_writer(std::io::stderr)
+        .with_ansi(std::io::stderr().is_terminal())
+        .with_target(false)
+        .without_time()
+        .with_filter(level);
+
+    let trace_layer = match trace_file {
+        Some(path) => {
+            let file = File::create(path)
+                .with_context(|| format!("Failed to create trace file {}", path.display()))?;
+            Some(
+                tracing_subscriber::fmt::layer()
+                    .json()
+                    .with_writer(Mutex::new(file))
+                    .with_span_events(FmtSpan::CLOSE)
+                    .with_current_span(true)
+                    .with_span_list(true)
+                    .with_filter(LevelFilter::TRACE),
+            )
+        }
+        None => None,
+    };
+
+    tracing_subscriber::registry()
+        .with(stderr_layer)
+        .with(trace_layer)
+        .try_init()
+        .map_err(|err| anyhow::anyhow!("Failed to initialize logging: {}", err))
+}
+
+/// Map the `-q` flag and `-v` count to a log level
+pub fn level_from_flags(quiet: bool, verbosity: u8) -> LevelFilter {
+    if quiet {
+        return LevelFilter::ERROR;
+    }
+    match verbosity {
+        0 => LevelFilter::WARN,
+        1 => LevelFilter::INFO,
+        2 => LevelFilter::DEBUG,
+        _ => LevelFilter::TRACE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_level_from_flags() {
+        assert_eq!(level_from_flags(false, 0), LevelFilter::WARN);
+        assert_eq!(level_from_flags(false, 1), LevelFilter::INFO);
+        assert_eq!(level_from_flags(false, 2), LevelFilter::DEBUG);
+        assert_eq!(level_from_flags(false, 3), LevelFilter::TRACE);
+        assert_eq!(level_from_flags(false, 9), LevelFilter::TRACE);
+    }
+
+    #[test]
+    fn test_quiet_overrides_verbosity() {
+        assert_eq!(level_from_flags(true, 0), LevelFilter::ERROR);
+        assert_eq!(level_from_flags(true, 3), LevelFilter::ERROR);
+    }
+}
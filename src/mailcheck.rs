@@ -0,0 +1,331 @@
+use std::net::IpAddr;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+use tracing::info;
+
+use crate::colorize::Tone;
+use crate::tr;
+
+/// DNS-over-HTTPS JSON API used for the lookups, so they also work where port 53 is filtered
+pub const DOH_URL: &str = "https://dns.google/resolve";
+const DEFAULT_TIMEOUT_SECONDS: u64 = 10;
+/// RFC 7208 limit on SPF terms that cause DNS lookups
+const SPF_LOOKUP_LIMIT: usize = 10;
+
+const TYPE_A: u64 = 1;
+const TYPE_MX: u64 = 15;
+const TYPE_TXT: u64 = 16;
+const TYPE_AAAA: u64 = 28;
+
+/// MX, SPF and DMARC records of a domain
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MailRecords {
+    /// (preference, exchange)
+    pub mx: Vec<(u16, String)>,
+    /// TXT records of the domain starting with `v=spf1`
+    pub spf: Vec<String>,
+    /// TXT records of `_dmarc.<domain>` starting with `v=DMARC1`
+    pub dmarc: Vec<String>,
+}
+
+/// A problem or a good sign in a domain's mail setup
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub tone: Tone,
+    pub message: String,
+}
+
+impl Finding {
+    fn new(tone: Tone, message: impl Into<String>) -> Self {
+        Self { tone, message: message.into() }
+    }
+}
+
+/// Client for a DNS-over-HTTPS JSON API (`?name=…&type=…`, as Google and Cloudflare serve it)
+pub struct DohResolver {
+    base_url: String,
+    timeout: Duration,
+}
+
+impl Default for DohResolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DohResolver {
+    pub fn new() -> Self {
+        Self {
+            base_url: DOH_URL.to_string(),
+            timeout: Duration::from_secs(DEFAULT_TIMEOUT_SECONDS),
+        }
+    }
+
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Answer records of one type as presentation text; empty for NXDOMAIN or no data
+    pub fn lookup(&self, name: &str, record_type: &str) -> Result<Vec<String>> {
+        info!("Resolving {} {} over {}", name, record_type, self.base_url);
+        let agent = ureq::AgentBuilder::new()
+            .timeout(self.timeout)
+            .user_agent(concat!("whois-cli/", env!("CARGO_PKG_VERSION")))
+            .build();
+        let body: Value = agent
+            .get(&self.base_url)
+            .query("name", name)
+            .query("type", record_type)
+            .set("Accept", "application/dns-json")
+            .call()
+            .with_context(|| format!("DNS lookup of {} {} failed", name, record_type))?
+            .into_json()
+            .with_context(|| format!("Invalid DNS-over-HTTPS response for {} {}", name, record_type))?;
+        answers(&body, record_type)
+    }
+
+    /// MX, SPF and DMARC records of a domain
+    pub fn mail_records(&self, domain: &str) -> Result<MailRecords> {
+        let mx = self.lookup(domain, "MX")?.iter().filter_map(|record| parse_mx(record)).collect();
+        let spf = self.lookup(domain, "TXT")?.into_iter().filter(|record| is_spf(record)).collect();
+        let dmarc = self
+            .lookup(&format!("_dmarc.{}", domain), "TXT")?
+            .into_iter()
+            .filter(|record| record.to_lowercase().starts_with("v=dmarc1"))
+            .collect();
+        Ok(MailRecords { mx, spf, dmarc })
+    }
+
+    /// IPv4 and IPv6 addresses of a host, IPv6 first as the system resolver would order them
+    pub fn addresses(&self, host: &str) -> Result<Vec<IpAddr>> {
+        let mut addresses = Vec::new();
+        for record_type in ["AAAA", "A"] {
+            addresses.extend(self.lookup(host, record_type)?.iter().filter_map(|data| data.parse::<IpAddr>().ok()));
+        }
+        Ok(addresses)
+    }
+}
+
+/// Answer data of a DoH JSON response; `Status` 0 is NOERROR and 3 is NXDOMAIN
+fn answers(body: &Value, record_type: &str) -> Result<Vec<String>> {
+    match body["Status"].as_u64() {
+        Some(0) | Some(3) => {}
+        Some(status) => anyhow::bail!("DNS lookup failed with rcode {}", status),
+        None => anyhow::bail!("DNS-over-HTTPS response has no status"),
+    }
+    let wanted = match record_type {
+        "A" => TYPE_A,
+        "AAAA" => TYPE_AAAA,
+        "MX" => TYPE_MX,
+        _ => TYPE_TXT,
+    };
+    Ok(body["Answer"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|answer| answer["type"].as_u64() == Some(wanted))
+        .filter_map(|answer| answer["data"].as_str())
+        .map(|data| if wanted == TYPE_TXT { unquote_txt(data) } else { data.to_string() })
+        .collect())
+}
+
+/// TXT data as one string: Cloudflare quotes the character strings (`"v=spf1 " "-all"`), Google does not
+fn unquote_txt(data: &str) -> String {
+    if !data.starts_with('"') {
+        return data.to_string();
+    }
+    let mut text = String::new();
+    let mut quoted = false;
+    let mut escaped = false;
+    for c in data.chars() {
+        match c {
+            _ if escaped => {
+                text.push(c);
+                escaped = false;
+            }
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            _ if quoted => text.push(c),
+            _ => {}
+        }
+    }
+    text
+}
+
+/// `10 mx1.example.com.` as (10, `mx1.example.com`)
+fn parse_mx(record: &str) -> Option<(u16, String)> {
+    let (preference, exchange) = record.trim().split_once(char::is_whitespace)?;
+    Some((preference.parse().ok()?, exchange.trim().trim_end_matches('.').to_lowercase()))
+}
+
+fn is_spf(record: &str) -> bool {
+    let lower = record.to_lowercase();
+    lower == "v=spf1" || lower.starts_with("v=spf1 ")
+}
+
+/// Missing and misconfigured MX, SPF and DMARC records, worst first
+pub fn check(records: &MailRecords) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    // RFC 7505: a single `0 .` MX says the domain takes no mail
+    let null_mx = records.mx.len() == 1 && records.mx[0].1.is_empty();
+    if null_mx {
+        findings.push(Finding::new(Tone::Good, tr!("mail-null-mx")));
+    } else if records.mx.is_empty() {
+        findings.push(Finding::new(Tone::Warning, tr!("mail-no-mx")));
+    }
+
+    match records.spf.as_slice() {
+        [] => findings.push(Finding::new(Tone::Bad, tr!("mail-no-spf"))),
+        [spf] => findings.extend(check_spf(spf)),
+        many => findings.push(Finding::new(Tone::Bad, tr!("mail-spf-multiple", count = many.len()))),
+    }
+
+    match records.dmarc.as_slice() {
+        [] => findings.push(Finding::new(Tone::Bad, tr!("mail-no-dmarc"))),
+        [dmarc] => findings.extend(check_dmarc(dmarc)),
+        many => findings.push(Finding::new(Tone::Bad, tr!("mail-dmarc-multiple", count = many.len()))),
+    }
+    findings.sort_by_key(|finding| match finding.tone {
+        Tone::Bad => 0,
+        Tone::Warning => 1,
+        Tone::Good => 2,
+    });
+    findings
+}
+
+fn check_spf(spf: &str) -> Vec<Finding> {
+    let terms: Vec<String> = spf.split_whitespace().skip(1).map(str::to_lowercase).collect();
+    let mut findings = Vec::new();
+    match terms.iter().find(|term| term.trim_start_matches(['+', '-', '~', '?']) == "all").map(String::as_str) {
+        Some("-all") => findings.push(Finding::new(Tone::Good, tr!("mail-spf-fail-all"))),
+        Some("~all") => findings.push(Finding::new(Tone::Warning, tr!("mail-spf-softfail-all"))),
+        Some("?all") => findings.push(Finding::new(Tone::Bad, tr!("mail-spf-neutral-all"))),
+        Some(_) => findings.push(Finding::new(Tone::Bad, tr!("mail-spf-pass-all"))),
+        None if terms.iter().any(|term| term.starts_with("redirect=")) => {}
+        None => findings.push(Finding::new(Tone::Warning, tr!("mail-spf-no-all"))),
+    }
+    let lookups = terms
+        .iter()
+        .map(|term| term.trim_start_matches(['+', '-', '~', '?']))
+        .filter(|term| {
+            let name = term.split([':', '/', '=']).next().unwrap_or(term);
+            matches!(name, "include" | "a" | "mx" | "ptr" | "exists" | "redirect")
+        })
+        .count();
+    if lookups > SPF_LOOKUP_LIMIT {
+        findings.push(Finding::new(Tone::Bad, tr!("mail-spf-lookups", count = lookups, limit = SPF_LOOKUP_LIMIT)));
+    }
+    if terms.iter().any(|term| term.trim_start_matches(['+', '-', '~', '?']).starts_with("ptr")) {
+        findings.push(Finding::new(Tone::Warning, tr!("mail-spf-ptr")));
+    }
+    findings
+}
+
+fn check_dmarc(dmarc: &str) -> Vec<Finding> {
+    let tags: Vec<(String, String)> = dmarc
+        .split(';')
+        .filter_map(|tag| tag.split_once('='))
+        .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+        .collect();
+    let tag = |name: &str| tags.iter().find(|(tag, _)| tag == name).map(|(_, value)| value.as_str());
+    let mut findings = Vec::new();
+    match tag("p").map(str::to_lowercase).as_deref() {
+        Some("reject") => findings.push(Finding::new(Tone::Good, tr!("mail-dmarc-reject"))),
+        Some("quarantine") => findings.push(Finding::new(Tone::Good, tr!("mail-dmarc-quarantine"))),
+        Some("none") => findings.push(Finding::new(Tone::Warning, tr!("mail-dmarc-monitor"))),
+        Some(other) => findings.push(Finding::new(Tone::Bad, tr!("mail-dmarc-invalid", policy = other))),
+        None => findings.push(Finding::new(Tone::Bad, tr!("mail-dmarc-no-policy"))),
+    }
+    if let Some(pct) = tag("pct").and_then(|pct| pct.parse::<u8>().ok()).filter(|pct| *pct < 100) {
+        findings.push(Finding::new(Tone::Warning, tr!("mail-dmarc-pct", pct = pct)));
+    }
+    if tag("rua").is_none() {
+        findings.push(Finding::new(Tone::Warning, tr!("mail-dmarc-no-rua")));
+    }
+    findings
+}
+
+/// Records and findings as `%` comment lines
+pub fn render(domain: &str, records: &MailRecords, findings: &[Finding]) -> String {
+    let mut lines = vec![format!("% ─── {} ───", tr!("mail-heading", domain = domain))];
+    let mut field = |name: &str, values: Vec<String>| {
+        let value = if values.is_empty() { tr!("mail-none") } else { values.join(", ") };
+        lines.push(format!("% {:<8}{}", format!("{}:", name), value));
+    };
+    field("MX", records.mx.iter().map(|(preference, exchange)| format!("{} {}", preference, if exchange.is_empty() { "." } else { exchange })).collect());
+    field("SPF", records.spf.clone());
+    field("DMARC", records.dmarc.clone());
+    lines.extend(findings.iter().map(|finding| format!("% {} {}", finding.tone.symbol(), finding.message)));
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn records(spf: &str, dmarc: &str) -> MailRecords {
+        MailRecords {
+            mx: vec![(10, "mx1.example.com".to_string())],
+            spf: vec![spf.to_string()],
+            dmarc: vec![dmarc.to_string()],
+        }
+    }
+
+    #[test]
+    fn test_doh_answers() {
+        let body = json!({"Status": 0, "Answer": [
+            {"name": "example.com.", "type": 16, "data": "\"v=spf1 include:_spf.example.net \" \"-all\""},
+            {"name": "example.com.", "type": 16, "data": "google-site-verification=abc"},
+            {"name": "example.com.", "type": 5, "data": "alias.example.net."}
+        ]});
+        assert_eq!(answers(&body, "TXT").unwrap(), ["v=spf1 include:_spf.example.net -all", "google-site-verification=abc"]);
+        assert!(answers(&json!({"Status": 3}), "MX").unwrap().is_empty());
+        let body = json!({"Status": 0, "Answer": [
+            {"name": "whois.ripe.net.", "type": 5, "data": "whois.ripe.net.cdn.example."},
+            {"name": "whois.ripe.net.cdn.example.", "type": 1, "data": "193.0.6.135"}
+        ]});
+        assert_eq!(answers(&body, "A").unwrap(), ["193.0.6.135"]);
+        assert!(answers(&body, "AAAA").unwrap().is_empty());
+        assert!(answers(&json!({"Status": 2}), "MX").is_err());
+        assert_eq!(parse_mx("10 MX1.Example.com."), Some((10, "mx1.example.com".to_string())));
+        assert_eq!(parse_mx("0 ."), Some((0, String::new())));
+        assert!(is_spf("v=spf1 -all") && !is_spf("v=spf10"));
+    }
+
+    #[test]
+    fn test_check_policies() {
+        let good = check(&records("v=spf1 mx -all", "v=DMARC1; p=reject; rua=mailto:dmarc@example.com"));
+        assert!(good.iter().all(|finding| finding.tone == Tone::Good));
+
+        let weak = check(&records("v=spf1 +all", "v=DMARC1; p=none; pct=50"));
+        let messages: Vec<&str> = weak.iter().map(|finding| finding.message.as_str()).collect();
+        assert_eq!(messages[0], "SPF allows any sender (+all)");
+        assert!(messages.contains(&"DMARC only monitors (p=none): failing mail is delivered"));
+        assert!(messages.contains(&"DMARC policy applies to 50% of failing mail (pct=50)"));
+
+        let includes: Vec<String> = (0..11).map(|i| format!("include:spf{}.example.net", i)).collect();
+        let heavy = check(&records(&format!("v=spf1 {} ~all", includes.join(" ")), "v=DMARC1; p=reject; rua=mailto:d@example.com"));
+        assert!(heavy.iter().any(|finding| finding.message == "SPF needs 11 DNS lookups, over the limit of 10"));
+
+        let missing = check(&MailRecords::default());
+        assert_eq!(missing.iter().filter(|finding| finding.tone == Tone::Bad).count(), 2);
+        assert!(missing.iter().any(|finding| finding.message.starts_with("no MX records")));
+    }
+
+    #[test]
+    fn test_render() {
+        let records = records("v=spf1 -all", "v=DMARC1; p=reject");
+        let rendered = render("example.com", &records, &check(&records));
+        assert!(rendered.starts_with("% ─── Mail policy for example.com ───\n% MX:     10 mx1.example.com\n% SPF:    v=spf1 -all\n"));
+        assert!(rendered.ends_with("% [ok] DMARC rejects failing mail (p=reject)"));
+    }
+}
@@ -1,27 +1,1011 @@
-use anyhow::Result;
+use std::io::Write;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
 use colored::*;
 use clap::Parser;
+use tracing::{info, warn};
 
-use whois_cli::{Cli, WhoisQuery, OutputColorizer, ColorScheme, RirHyperlinkProcessor, is_rir_response, MarkdownRenderer};
+use whois_cli::ansi::strip_ansi;
+use whois_cli::{audit, compare, conformance, contacts, correlate, irrd, irrstatus, ct, dates, deadline, glossary, i18n, history, json, layout, lint, logging, mailcheck, pgp, markdown, netblock, portfolio, registration, related, remotehistory, resolve, ripestat, repl, rpsl, schema, secrets, source, timing, tld, version};
+use whois_cli::normalize::normalize_target;
+use whois_cli::psl::{extract_domain, SuffixList};
+use whois_cli::clipboard::{copy_to_clipboard, extract_field_values};
+use whois_cli::{AuditEntry, AuditLog, BulkRunner, CapabilityCache, KnownOrgs, MemoryCapabilityCache, Config, ConnectionPool, Deadline, Dn42Registry, QueryError, RipeStat, HostLimiter, JobState, Metrics, ServerRegistry, WhoisServer, Cli, Command, OutputFormat, ProgressCallback, QueryProgress, QueryResult, WhoisQuery, OutputColorizer, Palette, Theme, RirHyperlinkProcessor, is_rir_response, MarkdownRenderer, HtmlRenderer};
+use whois_cli::markdown::MixedBlock;
+use whois_cli::tr;
+#[cfg(feature = "render")]
+use whois_cli::ImageRenderer;
+#[cfg(feature = "sqlite")]
+use whois_cli::{archive, fingerprint, store, Store, StoredAnswer};
 
 fn main() -> Result<()> {
-    let args = Cli::parse();
+    // The helper process `--copy` starts to keep the X11 or Wayland clipboard contents
+    #[cfg(all(feature = "clipboard", unix, not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))))]
+    if std::env::var_os(whois_cli::clipboard::HOLDER_ENV).is_some() {
+        return whois_cli::clipboard::hold_clipboard();
+    }
+    let mut args = Cli::parse();
+    // Before the config is read, so a broken config still lets users report their build
+    if let Some(Command::Version { verbose }) = args.command()? {
+        println!("{}", version_text(verbose));
+        return Ok(());
+    }
+    
+    let config = Config::load(args.config.as_deref())?;
+    if let Some(name) = &args.preset {
+        let preset = config.preset(name)?.args();
+        args = Cli::parse_with_preset(&std::env::args().collect::<Vec<_>>(), &preset).unwrap_or_else(|err| err.exit());
+    }
+    logging::init(args.log_level(), args.trace_file.as_deref())?;
+    if let Some(local) = args.source() {
+        source::set(local)?;
+    }
+    if let Some(url) = &args.doh {
+        resolve::set_doh(url.as_str())?;
+    }
+    resolve::set_overrides(&config.server_overrides)?;
+    i18n::set_locale(args.locale());
+    
+    // HTML and image export need the ANSI sequences even when stdout is redirected to a file
+    if args.use_html() || args.use_image_export() {
+        colored::control::set_override(true);
+    }
+    
+    let command = args.command()?;
+    let suffixes = SuffixList::load(config.public_suffix_list.as_deref()).unwrap_or_else(|err| {
+        warn!("{}", tr!("warn-psl", error = format!("{:#}", err)));
+        SuffixList::builtin()
+    });
+    
+    if let Some(Command::Repl) = command {
+        return run_repl(&args, config, suffixes);
+    }
+    if let Some(Command::Correlate { targets }) = command {
+        let session = open_session(&args, config, suffixes)?;
+        let (report, complete) = correlate_targets(&args, &session, &targets)?;
+        write_output(args.output.as_deref(), format!("{}\n", report).as_bytes())?;
+        if !complete {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    if let Some(Command::DomainAudit { domains }) = command {
+        let session = open_session(&args, config, suffixes)?;
+        let (report, status) = audit_domains(&args, &session, &domains)?;
+        write_output(args.output.as_deref(), format!("{}\n", report).as_bytes())?;
+        if status != 0 {
+            std::process::exit(status);
+        }
+        return Ok(());
+    }
+    if let Some(command) = command {
+        if let Err(err) = run_command(&args, &config, command) {
+            eprintln!("{}: {:#}", tr!("query-failed").bright_red(), err);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    
+    let (targets, invalid) = clean_targets(&args, &suffixes, &args.domain);
+    if targets.is_empty() {
+        std::process::exit(1);
+    }
+    args.domain = targets;
+    
+    // Query every target and collect the rendered results in order
+    let job = match &args.job_state {
+        Some(path) if args.resume => Some(JobState::resume(path)?),
+        Some(path) => Some(JobState::create(path)?),
+        None => None,
+    };
+    if let Some(job) = job.as_ref().filter(|job| job.completed_count() > 0) {
+        info!("Resuming job: {} target(s) already done", job.completed_count());
+    }
+    
+    // The first Ctrl-C stops the lookups and prints what is done; a second one exits at once
+    if let Err(err) = ctrlc::set_handler(|| {
+        if deadline::is_cancelled() {
+            std::process::exit(130);
+        }
+        deadline::cancel();
+        eprintln!("{}", tr!("interrupted-printing").yellow());
+    }) {
+        warn!("Cannot install Ctrl-C handler: {}", err);
+    }
+    
+    let session = open_session(&args, config, suffixes)?;
+    let metrics = &session.metrics;
+    let progress = QueryProgress::new(args.domain.len(), args.show_progress());
+    let runner = BulkRunner::new(args.use_parallel()).with_concurrency(args.worker_count());
+    // Keyed by the server each target's lookup is expected to reach, not the one it starts at
+    let router = WhoisQuery::new().with_suffix_list(session.suffixes.clone());
+    let server_key = |target: &str| {
+        router
+            .queue_key(target, args.use_dn42(target), args.use_bgptools(), args.server.as_deref(), args.port)
+            .unwrap_or_default()
+    };
+    let outcomes = runner.run_keyed(&args.domain, server_key, |target| {
+        if let Some(output) = job.as_ref().and_then(|job| job.completed(target)) {
+            return Ok(output.to_string());
+        }
+        if deadline::is_cancelled() {
+            return Err(QueryError::Cancelled.into());
+        }
+        let target_progress = progress.start_target(target);
+        let result = query_target(&args, &session, target, target_progress.callback());
+        target_progress.finish();
+        if let Some(metrics) = metrics {
+            metrics.record_lookup(result.is_ok());
+        }
+        // Interrupted and partial results stay pending so --resume retries them
+        if let Some(job) = job.as_ref().filter(|_| !deadline::is_cancelled()) {
+            if let Err(err) = job.record(target, &result) {
+                warn!("{:#}", err);
+            }
+            #[cfg(feature = "sqlite")]
+            record_job(&session, args.job_state.as_deref(), target, &result);
+        }
+        result
+    });
+    progress.finish();
+    copy_fields(&args, &session, &args.domain);
+    
+    if let (Some(metrics), Some(path)) = (metrics, &args.metrics_file) {
+        if let Err(err) = metrics.write_textfile(path) {
+            warn!("{:#}", err);
+        }
+    }
+    
+    let mut sections = Vec::new();
+    let mut failures = invalid;
+    let mut cancelled = 0;
+    for outcome in outcomes {
+        if is_cancelled_error(&outcome.result) {
+            cancelled += 1;
+            continue;
+        }
+        match outcome.result {
+            Ok(output) => {
+                if args.use_json() {
+                    sections.push(output);
+                } else if args.is_multi_target() {
+                    sections.push(format!("{}\n{}", section_header(&outcome.target), output.trim_end()));
+                } else {
+                    sections.push(output);
+                }
+            }
+            Err(err) => {
+                failures += 1;
+                if args.use_json() {
+                    sections.push(json::error_record(&outcome.target, &err));
+                }
+                if args.is_multi_target() {
+                    eprintln!("{} ({}): {}", tr!("query-failed").bright_red(), outcome.target, err);
+                } else {
+                    eprintln!("{}: {}", tr!("query-failed").bright_red(), err);
+                }
+            }
+        }
+    }
+    
+    let interrupted = deadline::is_cancelled();
+    if interrupted && cancelled > 0 {
+        eprintln!("{}: {}", tr!("interrupted").yellow(), tr!("targets-not-finished", count = cancelled, total = args.domain.len()));
+    }
+    
+    if sections.is_empty() {
+        std::process::exit(if interrupted { 130 } else { 1 });
+    }
+    
+    let mut output = if args.use_json() {
+        json::combine(&sections, args.is_multi_target())
+    } else {
+        sections.join("\n\n")
+    };
+    if args.strip_ansi {
+        output = strip_ansi(&output);
+    }
     
-    if args.verbose {
-        println!("{}: {}", "Query".bright_green(), args.domain.bright_white());
+    if args.use_html() {
+        output = HtmlRenderer::render_document(&output, &args.domain.join(", "));
     }
     
+    let payload = if args.use_image_export() {
+        match export_image(args.format, &output) {
+            Ok(payload) => payload,
+            Err(err) => {
+                eprintln!("{}: {}", tr!("export-failed").bright_red(), err);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        format!("{}\n", output).into_bytes()
+    };
+    
+    if let Err(err) = write_output(args.output.as_deref(), &payload) {
+        eprintln!("{}: {:#}", tr!("output-failed").bright_red(), err);
+        std::process::exit(1);
+    }
+    
+    if interrupted {
+        std::process::exit(130);
+    }
+    if failures > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Run a command such as `history` instead of querying targets
+fn run_command(args: &Cli, config: &Config, command: Command) -> Result<()> {
+    if !args.use_color() {
+        colored::control::set_override(false);
+    }
+    let server = match &args.server {
+        Some(host) => WhoisServer::from_spec(host, args.port)?,
+        None => WhoisServer::default(),
+    };
+    let query = WhoisQuery::new()
+        .with_registry(config.registry())
+        .with_deadline(args.deadline.map(Deadline::after).transpose()?.unwrap_or_default());
+    let colorize = |text: &str| {
+        if args.use_color() {
+            OutputColorizer::colorize(text, OutputColorizer::detect_scheme(text))
+        } else {
+            text.to_string()
+        }
+    };
+    
+    let output = match command {
+        Command::History { key } if args.remote => {
+            let api = &config.history_api;
+            let deadline = args.deadline.map(Deadline::after).transpose()?.unwrap_or_default();
+            let timeout = deadline.step_timeout(Duration::from_secs(30), &api.url)?;
+            let domain = key.to_lowercase();
+            let records = remotehistory::HistoryApi::new(api.url.as_str(), api.api_key()?).with_timeout(timeout).records(&domain)?;
+            remotehistory::render(&domain, api.host(), &records)
+        }
+        Command::History { key } => {
+            let (versions, response) = history::list_versions(&query, &server, &key)?;
+            if versions.is_empty() {
+                let reason = response.lines().find(|line| line.starts_with("%ERROR")).unwrap_or("no versions listed");
+                anyhow::bail!("{}", tr!("error-no-history", key = key, server = server.host, reason = reason.trim_start_matches('%')));
+            }
+            colorize(&history::render_versions(&key, &versions))
+        }
+        Command::ShowVersion { revision, key } => {
+            let object = history::show_version(&query, &server, revision, &key)?;
+            let mut output = colorize(&object);
+            if revision > 1 {
+                match history::show_version(&query, &server, revision - 1, &key) {
+                    Ok(previous) => {
+                        let diff = history::diff_lines(&previous, &object);
+                        output = format!("{}\n\n{}", output, history::render_diff(revision - 1, revision, &diff));
+                    }
+                    Err(err) => warn!("{}", tr!("warn-history-compare", revision = revision - 1, error = format!("{:#}", err))),
+                }
+            }
+            output
+        }
+        Command::Org { handle } if args.resources => {
+            let resources = portfolio::lookup(&query, &server, &handle)?;
+            colorize(&portfolio::render(&handle, &resources))
+        }
+        Command::Org { handle } => colorize(query.query_direct(&handle, &server)?.trim_end()),
+        Command::Mnt { handle } if args.audit => colorize(&audit::audit(&query, &server, &handle)?.render()),
+        Command::Mnt { handle } => colorize(query.query_direct(&handle, &server)?.trim_end()),
+        Command::LintFile { file } => {
+            let text = std::fs::read_to_string(&file).with_context(|| format!("Failed to read {}", file.display()))?;
+            let problems = schema::validate(&text);
+            if !problems.is_empty() {
+                write_output(args.output.as_deref(), format!("{}\n", schema::render(&file.display().to_string(), &problems)).as_bytes())?;
+                anyhow::bail!("{}", tr!("error-lint-problems", count = problems.len(), file = file.display()));
+            }
+            let objects = rpsl::parse(&text).iter().filter(|block| matches!(block, rpsl::Block::Object(_))).count();
+            format!("{}: {} object(s), no problems found", file.display(), objects)
+        }
+        Command::Template { class } => {
+            let flavor = if args.dn42 { schema::Flavor::Dn42 } else { schema::Flavor::Ripe };
+            schema::template(&class, flavor).with_context(|| {
+                format!("No template for {} (known: {})", class, schema::class_names(flavor).join(", "))
+            })?
+        }
+        Command::Tld { tld } => {
+            // TLD delegations live at IANA, not at the default server
+            let iana = match &args.server {
+                Some(_) => server,
+                None => WhoisServer::iana(),
+            };
+            let (delegation, record) = tld::lookup(&query, &iana, &tld)?;
+            format!("{}\n\n{}", colorize(&delegation.render()), colorize(record.trim_end()))
+        }
+        Command::AuthSet { account } => {
+            let secret = secrets::read_secret(&account)?;
+            secrets::store(&account, &secret)?;
+            format!("Stored the secret for {} in the system keyring", account)
+        }
+        Command::AuthRemove { account } => {
+            secrets::remove(&account)?;
+            format!("Removed the secret for {} from the system keyring", account)
+        }
+        Command::Version { verbose } => version_text(verbose),
+        Command::Repl => anyhow::bail!("Already in a whois repl session"),
+        Command::Correlate { .. } => unreachable!("correlate needs a session and runs before commands"),
+        Command::DomainAudit { .. } => unreachable!("audit needs a session and runs before commands"),
+        Command::Compare { target } => compare_servers(args, config, &target)?,
+        Command::IrrStatus { sources } => irr_status(args, &query, &sources),
+        Command::PrefixList { target } => {
+            let server = irr_server(args)?;
+            let irrd = irrd::is_irrd(&query, query.registry(), &server);
+            let prefixes = irrd::prefix_list(&query, &server, &target, irrd)?;
+            let heading = tr!("irrd-prefixes", target = target, count = prefixes.len(), server = server.host);
+            std::iter::once(format!("% {}", heading)).chain(prefixes).collect::<Vec<_>>().join("\n")
+        }
+        Command::AsSet { name } => {
+            let server = irr_server(args)?;
+            let irrd = irrd::is_irrd(&query, query.registry(), &server);
+            let members = irrd::expand_set(&query, &server, &name, irrd)?;
+            let heading = tr!("irrd-members", name = name, count = members.len(), server = server.host);
+            std::iter::once(format!("% {}", heading)).chain(members).collect::<Vec<_>>().join("\n")
+        }
+        Command::CacheStats | Command::CachePurge { .. } | Command::CacheExport { .. } | Command::CacheImport { .. } => {
+            cache_command(config, command)?
+        }
+        Command::Show { .. } => colorize(&cache_command(config, command)?),
+        Command::ProbeServer { server, query } => {
+            let address = WhoisServer::from_spec(&server, args.port)?.address();
+            let mut exchange = |request: &str| {
+                timing::timed_exchange(&address, request.as_bytes(), Duration::from_secs(10), None).map(|(response, _)| response)
+            };
+            let report = conformance::run(&address, &query, &mut exchange)?;
+            if report.failures() > 0 {
+                write_output(args.output.as_deref(), format!("{}\n", report.render()).as_bytes())?;
+                anyhow::bail!("{}", tr!("error-probe-failures", count = report.failures(), server = address));
+            }
+            report.render()
+        }
+    };
+    let output = match args.wrap_width() {
+        Some(width) => layout::wrap(&output, width),
+        None => output,
+    };
+    let output = if args.strip_ansi { strip_ansi(&output) } else { output };
+    write_output(args.output.as_deref(), format!("{}\n", output).as_bytes())
+}
+
+/// `whois -V`, or the build report of `whois version`
+fn version_text(verbose: bool) -> String {
+    match verbose {
+        true => version::report(),
+        false => format!("whois-cli {}", env!("CARGO_PKG_VERSION")),
+    }
+}
+
+/// Interactive session answering query after query from one warm session:
+/// connections, server capabilities and the cache stay open between queries
+fn run_repl(args: &Cli, config: Config, suffixes: SuffixList) -> Result<()> {
+    // Flags given to `whois repl` become the first session flags
+    let mut flags: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(index) = flags.iter().position(|word| word == "repl") {
+        flags.remove(index);
+    }
+    if let Some(name) = &args.preset {
+        flags.splice(0..0, config.preset(name)?.args());
+    }
+    let mut session = open_session(args, config, suffixes)?;
+    session.pool.get_or_insert_with(|| Arc::new(ConnectionPool::new()));
+    session.capabilities = Some(Arc::new(MemoryCapabilityCache::default()));
+    
+    let mut prompt = repl::Prompt::open(repl::ReplState::new(flags))?;
+    if prompt.is_interactive() {
+        eprintln!("whois-cli {}, type .help for commands and Ctrl-D to leave", env!("CARGO_PKG_VERSION"));
+    }
+    while let Some(line) = prompt.next_line()? {
+        let input = match repl::parse_line(&line) {
+            Ok(Some(input)) => input,
+            Ok(None) => continue,
+            Err(err) => {
+                eprintln!("{:#}", err);
+                continue;
+            }
+        };
+        match input {
+            repl::Input::Quit => break,
+            repl::Input::Help => println!("{}", repl::help()),
+            repl::Input::Show if prompt.state().flags().is_empty() => println!("No session flags"),
+            repl::Input::Show => println!("{}", prompt.state().flags().join(" ")),
+            repl::Input::Unset => prompt.state_mut().unset(),
+            repl::Input::Set(flags) => {
+                if let Err(err) = prompt.state_mut().set(&flags) {
+                    eprintln!("{:#}", err);
+                }
+            }
+            repl::Input::Query(words) => {
+                let line_args = match prompt.state().args(&words) {
+                    Ok(line_args) => line_args,
+                    Err(err) => {
+                        eprintln!("{:#}", err);
+                        continue;
+                    }
+                };
+                match line_args.command() {
+                    Ok(Some(Command::Correlate { targets })) => {
+                        match correlate_targets(&line_args, &session, &targets) {
+                            Ok((report, _)) => println!("{}", report),
+                            Err(err) => eprintln!("{}: {:#}", tr!("query-failed").bright_red(), err),
+                        }
+                        continue;
+                    }
+                    Ok(Some(Command::DomainAudit { domains })) => {
+                        match audit_domains(&line_args, &session, &domains) {
+                            Ok((report, _)) => println!("{}", report),
+                            Err(err) => eprintln!("{}: {:#}", tr!("query-failed").bright_red(), err),
+                        }
+                        continue;
+                    }
+                    Ok(Some(command)) => {
+                        if let Err(err) = run_command(&line_args, &session.config, command) {
+                            eprintln!("{}: {:#}", tr!("query-failed").bright_red(), err);
+                        }
+                        continue;
+                    }
+                    Ok(None) => {}
+                    Err(err) => {
+                        eprintln!("{:#}", err);
+                        continue;
+                    }
+                }
+                let (targets, _) = clean_targets(&line_args, &session.suffixes, &line_args.domain);
+                prompt.state_mut().remember(&targets);
+                let progress = QueryProgress::new(targets.len(), line_args.show_progress());
+                for target in &targets {
+                    let target_progress = progress.start_target(target);
+                    let result = query_target(&line_args, &session, target, target_progress.callback());
+                    target_progress.finish();
+                    if let Some(metrics) = &session.metrics {
+                        metrics.record_lookup(result.is_ok());
+                    }
+                    match result {
+                        Ok(output) if targets.len() > 1 && !line_args.use_json() => {
+                            println!("{}\n{}", section_header(target), output.trim_end());
+                        }
+                        Ok(output) => println!("{}", output.trim_end()),
+                        Err(err) => eprintln!("{} ({}): {:#}", tr!("query-failed").bright_red(), target, err),
+                    }
+                }
+                progress.finish();
+                copy_fields(&line_args, &session, &targets);
+            }
+        }
+    }
+    
+    if let Err(err) = prompt.save_history() {
+        warn!("{:#}", err);
+    }
+    if let (Some(metrics), Some(path)) = (&session.metrics, &args.metrics_file) {
+        metrics.write_textfile(path)?;
+    }
+    Ok(())
+}
+
+/// Query `target` on every `--servers` server at once and report how the answers differ
+/// from the first one that answered
+/// Each server gets its own query and deadline, as each target of a normal run does
+fn compare_servers(args: &Cli, config: &Config, target: &str) -> Result<String> {
+    let addresses: Vec<String> = args
+        .servers
+        .iter()
+        .map(|spec| WhoisServer::from_spec(spec, args.port).map(|server| server.address()))
+        .collect::<Result<_>>()?;
+    let registry = Arc::new(config.registry());
+    let outcomes = BulkRunner::new(true).run(&addresses, |address| {
+        let deadline = args.deadline.map(Deadline::after).transpose()?.unwrap_or_default();
+        let query = WhoisQuery::new().with_registry(registry.clone()).with_deadline(deadline);
+        query.query_direct(target, &WhoisServer::from_spec(address, args.port)?)
+    });
+    let answers: Vec<compare::Answer> = outcomes
+        .into_iter()
+        .map(|outcome| compare::Answer::new(outcome.target, outcome.result.map_err(|err| format!("{:#}", err))))
+        .collect();
+    let verdicts = compare::verdicts(&answers);
+    let mut sections = vec![compare::render_summary(target, &answers, &verdicts)];
+    if let Some(reference) = compare::reference(&answers) {
+        let (reference_server, reference_text) = (&answers[reference].server, answers[reference].text.as_deref().unwrap_or_default());
+        let width = layout::terminal_width().unwrap_or(160);
+        for (answer, verdict) in answers.iter().zip(&verdicts) {
+            if !matches!(verdict, compare::Verdict::Differs { .. } | compare::Verdict::Stale { .. }) {
+                continue;
+            }
+            let diff = history::diff_lines(reference_text, answer.text.as_deref().unwrap_or_default());
+            sections.push(if args.side_by_side {
+                compare::render_side_by_side(reference_server, &answer.server, &diff, width)
+            } else {
+                compare::render_unified(reference_server, &answer.server, &diff)
+            });
+        }
+    }
+    Ok(sections.join("\n\n"))
+}
+
+/// Server for IRR lookups: `--server`, otherwise RADB, which mirrors most IRR databases
+fn irr_server(args: &Cli) -> Result<WhoisServer> {
+    match &args.server {
+        Some(host) => WhoisServer::from_spec(host, args.port),
+        None => Ok(WhoisServer::radb()),
+    }
+}
+
+/// Ask IRR mirrors for the serial of every source they carry and compare it with the
+/// serial the source's authoritative server reports
+fn irr_status(args: &Cli, query: &WhoisQuery, sources: &[String]) -> String {
+    let mirrors: Vec<String> = match args.servers.as_slice() {
+        [] => irrstatus::DEFAULT_MIRRORS.iter().map(|mirror| mirror.to_string()).collect(),
+        servers => servers.to_vec(),
+    };
+    let runner = BulkRunner::new(true);
+    let mirror_serials: Vec<(String, Vec<irrstatus::SourceSerial>)> = runner
+        .run(&mirrors, |mirror| {
+            WhoisServer::from_spec(mirror, args.port).and_then(|server| query.query_direct(&irrstatus::serial_query(None), &server))
+        })
+        .into_iter()
+        .filter_map(|outcome| match outcome.result {
+            Ok(response) => Some((outcome.target, irrstatus::parse_serials(&response))),
+            Err(err) => {
+                warn!("{}: {:#}", outcome.target, err);
+                None
+            }
+        })
+        .collect();
+
+    let mut checked: Vec<String> = match sources {
+        [] => mirror_serials.iter().flat_map(|(_, serials)| serials.iter().map(|serial| serial.source.clone())).collect(),
+        sources => sources.to_vec(),
+    };
+    checked.sort();
+    checked.dedup();
+    checked.retain(|source| irrstatus::authoritative_server(source).is_some());
+    let authoritative: Vec<(String, irrstatus::SourceSerial)> = runner
+        .run(&checked, |source| {
+            let host = irrstatus::authoritative_server(source).unwrap_or_default();
+            let server = WhoisServer::from_spec(host, args.port)?;
+            let find = |response: String| irrstatus::parse_serials(&response).into_iter().find(|serial| serial.source == source);
+            // RIPE database servers do not know `!j` and list their sources instead
+            let serial = match query.query_direct(&irrstatus::serial_query(Some(source)), &server).map(find)? {
+                Some(serial) => Some(serial),
+                None => query.query_direct(irrstatus::SOURCES_QUERY, &server).map(find)?,
+            };
+            Ok(serial.map(|serial| (host.to_string(), serial)))
+        })
+        .into_iter()
+        .filter_map(|outcome| match outcome.result {
+            Ok(serial) => serial,
+            Err(err) => {
+                warn!("{}: {:#}", outcome.target, err);
+                None
+            }
+        })
+        .collect();
+    irrstatus::render(&irrstatus::statuses(&mirror_serials, &authoritative, sources))
+}
+
+/// Query every target in bulk and report org handles, emails, maintainers and name servers
+/// found in the answers of more than one target; also tells whether every target answered
+fn correlate_targets(args: &Cli, session: &Session, targets: &[String]) -> Result<(String, bool)> {
+    let (targets, invalid) = clean_targets(args, &session.suffixes, targets);
+    if targets.len() < 2 {
+        anyhow::bail!("whois correlate needs at least two valid targets");
+    }
+    let deadline = args.deadline.map(Deadline::after).transpose()?.unwrap_or_default();
+    let progress = QueryProgress::new(targets.len(), args.show_progress());
+    let runner = BulkRunner::new(true).with_concurrency(args.worker_count());
+    let outcomes = runner.run(&targets, |target| {
+        let target_progress = progress.start_target(target);
+        let result = query_handler(args, session, deadline, target_progress.callback()).query(
+            target,
+            args.use_dn42(target),
+            args.use_bgptools(),
+            args.server.as_deref(),
+            args.port,
+        );
+        target_progress.finish();
+        result.map(|result| strip_ansi(&result.response))
+    });
+    progress.finish();
+    
+    let mut answers = Vec::new();
+    for outcome in outcomes {
+        match outcome.result {
+            Ok(response) => answers.push((outcome.target, response)),
+            Err(err) => eprintln!("{} ({}): {:#}", tr!("query-failed").bright_red(), outcome.target, err),
+        }
+    }
+    if answers.len() < 2 {
+        anyhow::bail!("Fewer than two targets answered, nothing to correlate");
+    }
+    let report = correlate::render(&correlate::correlate(&answers), answers.len());
+    let report = if args.use_color() { OutputColorizer::colorize(&report, OutputColorizer::detect_scheme(&report)) } else { report };
+    Ok((report, invalid == 0 && answers.len() == targets.len()))
+}
+
+/// Look up the registration of every domain in bulk and report it as a table, CSV or JSON;
+/// the exit status is 1 when a lookup failed and 2 when a domain expires within the window
+fn audit_domains(args: &Cli, session: &Session, domains: &[String]) -> Result<(String, i32)> {
+    let mut raw = domains.to_vec();
+    if let Some(path) = &args.file {
+        let text = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        raw.extend(registration::read_domains(&text));
+    }
+    let (targets, invalid) = clean_targets(args, &session.suffixes, &raw);
+    let mut domains: Vec<String> = Vec::new();
+    for target in targets {
+        let apex = apex_domain(&session.suffixes, target);
+        if !domains.contains(&apex) {
+            domains.push(apex);
+        }
+    }
+    if domains.is_empty() {
+        anyhow::bail!("whois audit found no valid domains to look up");
+    }
+    let window = args.expiry_window();
+    let today = dates::today();
+    let deadline = args.deadline.map(Deadline::after).transpose()?.unwrap_or_default();
+    let progress = QueryProgress::new(domains.len(), args.show_progress());
+    let runner = BulkRunner::new(true).with_concurrency(args.worker_count());
+    let outcomes = runner.run(&domains, |domain| {
+        let target_progress = progress.start_target(domain);
+        let result = query_handler(args, session, deadline, target_progress.callback()).query(
+            domain,
+            args.use_dn42(domain),
+            args.use_bgptools(),
+            args.server.as_deref(),
+            args.port,
+        );
+        target_progress.finish();
+        result.map(|result| strip_ansi(&result.response))
+    });
+    progress.finish();
+    
+    let mut registrations: Vec<registration::Registration> = outcomes
+        .into_iter()
+        .map(|outcome| match outcome.result {
+            Ok(response) => registration::Registration::parse(&outcome.target, &response, today, window),
+            Err(err) => registration::Registration::failed(&outcome.target, &err),
+        })
+        .collect();
+    registration::sort(&mut registrations);
+    
+    let report = match args.format {
+        OutputFormat::Csv => registration::to_csv(&registrations),
+        OutputFormat::Json => serde_json::to_string_pretty(&registrations)?,
+        _ => {
+            let report = registration::render(&registrations, window);
+            if args.use_color() { OutputColorizer::colorize(&report, OutputColorizer::detect_scheme(&report)) } else { report }
+        }
+    };
+    let status = if invalid > 0 || registrations.iter().any(|entry| entry.standing == registration::Standing::Error) {
+        1
+    } else if registrations.iter().any(|entry| entry.standing.is_alert()) {
+        2
+    } else {
+        0
+    };
+    Ok((report, status))
+}
+
+/// Open the SQLite cache at `cache_db` or next to the config file
+#[cfg(feature = "sqlite")]
+fn open_store(config: &Config) -> Result<Arc<Store>> {
+    let path = config
+        .cache_db
+        .clone()
+        .or_else(Store::default_path)
+        .context("No location for the cache database, set cache_db in the config")?;
+    Ok(Arc::new(Store::open(&path)?))
+}
+
+/// A fresh enough answer from the cache, if `--cache` is on
+#[cfg(feature = "sqlite")]
+fn cached_result(args: &Cli, session: &Session, cache_key: &str) -> Option<QueryResult> {
+    let store = session.store.as_ref()?;
+    let cached = store.cached_response(cache_key, args.cache_ttl());
+    if let Some(metrics) = &session.metrics {
+        metrics.record_cache(matches!(cached, Ok(Some(_))));
+    }
+    match cached {
+        Ok(Some((answer, age))) => {
+            info!("Using cached answer for {} from {} ({} old)", cache_key, answer.host, store::format_age(age.as_secs() as i64));
+            let mut result = QueryResult::new_with_color(answer.response, WhoisServer::custom(answer.host, answer.port), answer.server_colored);
+            result.markdown = answer.markdown;
+            Some(result)
+        }
+        Ok(None) => None,
+        Err(err) => {
+            warn!("{:#}", err);
+            None
+        }
+    }
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn cached_result(_args: &Cli, _session: &Session, _cache_key: &str) -> Option<QueryResult> {
+    None
+}
+
+/// Keep a new answer in the cache, if `--cache` is on
+#[cfg(feature = "sqlite")]
+fn record_result(session: &Session, query_key: &str, cache_key: &str, result: &QueryResult) {
+    let Some(store) = &session.store else {
+        return;
+    };
+    if result.partial {
+        return;
+    }
+    // Compared by fingerprint, so rotating banners and timestamps do not count as changes
+    match store.last_fingerprint(cache_key) {
+        Ok(Some(previous)) if previous != fingerprint::fingerprint(&result.response) => {
+            info!("The answer for {} changed since it was last cached", query_key)
+        }
+        Ok(_) => {}
+        Err(err) => warn!("{:#}", err),
+    }
+    let answer = StoredAnswer {
+        host: result.server_used.host.clone(),
+        port: result.server_used.port,
+        response: result.response.clone(),
+        server_colored: result.server_colored,
+        markdown: result.markdown,
+    };
+    if let Err(err) = store.record_response(query_key, cache_key, &answer) {
+        warn!("{:#}", err);
+    }
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn record_result(_session: &Session, _query_key: &str, _cache_key: &str, _result: &QueryResult) {}
+
+/// Mirror a bulk-job entry into the cache so past jobs can be queried
+#[cfg(feature = "sqlite")]
+fn record_job(session: &Session, job: Option<&std::path::Path>, target: &str, result: &Result<String>) {
+    let (Some(store), Some(job)) = (&session.store, job) else {
+        return;
+    };
+    let (output, error) = match result {
+        Ok(output) => (Some(output.as_str()), None),
+        Err(err) => (None, Some(format!("{:#}", err))),
+    };
+    if let Err(err) = store.record_job(&job.display().to_string(), target, output, error.as_deref()) {
+        warn!("{:#}", err);
+    }
+}
+
+/// `whois cache stats` and `whois cache purge`
+#[cfg(feature = "sqlite")]
+fn cache_command(config: &Config, command: Command) -> Result<String> {
+    let store = open_store(config)?;
+    match command {
+        Command::CachePurge { older_than } => {
+            let removed = store.purge(older_than)?;
+            Ok(format!("Removed {} entries from {}", removed, store.path().display()))
+        }
+        Command::CacheExport { file } => {
+            let manifest = archive::export(&store, &file)?;
+            Ok(format!(
+                "Exported {} responses, {} job entries and {} server capabilities to {}",
+                manifest.responses,
+                manifest.job_entries,
+                manifest.capabilities,
+                file.display()
+            ))
+        }
+        Command::CacheImport { file } => {
+            let added = archive::import(&store, &file)?;
+            Ok(format!(
+                "Imported {} responses, {} job entries and {} server capabilities into {}",
+                added.responses,
+                added.job_entries,
+                added.capabilities,
+                store.path().display()
+            ))
+        }
+        Command::Show { target, as_of } => {
+            // Everything fetched during that day counts
+            let (host, port, response, fetched_at) = store.snapshot_before(&target, (as_of + 1) * 86400)?.with_context(|| {
+                format!("No snapshot of {} from {} or earlier in {}", target, dates::format_day(as_of), store.path().display())
+            })?;
+            let fetched = dates::rfc3339(std::time::UNIX_EPOCH + Duration::from_secs(fetched_at.max(0) as u64));
+            let age = store::format_age((as_of + 1) * 86400 - fetched_at);
+            Ok(format!(
+                "% Snapshot of {} from {}:{}, fetched {} ({} before the end of {})\n\n{}",
+                target,
+                host,
+                port,
+                fetched,
+                age,
+                dates::format_day(as_of),
+                response.trim_end()
+            ))
+        }
+        _ => Ok(store.stats()?.render(store.path())),
+    }
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn cache_command(_config: &Config, _command: Command) -> Result<String> {
+    anyhow::bail!("The cache requires the `sqlite` feature")
+}
+
+/// Whether a lookup stopped because the run was interrupted
+fn is_cancelled_error<T>(result: &Result<T>) -> bool {
+    matches!(result, Err(err) if matches!(err.downcast_ref::<QueryError>(), Some(QueryError::Cancelled)))
+}
+
+/// State shared by every lookup in a run
+struct Session {
+    config: Config,
+    /// Palette overlay from `--pride`, `--mtf` or the config
+    palette: Option<Palette>,
+    /// Local dn42 registry answering DN42 lookups
+    dn42_registry: Option<Arc<Dn42Registry>>,
+    /// Semantic colors from `--scheme` or the config
+    theme: Theme,
+    /// Mark values with symbols, from `--symbols` or the config
+    symbols: bool,
+    metrics: Option<Arc<Metrics>>,
+    /// Log of every lookup opened with `--audit-log` or the config
+    audit_log: Option<Arc<AuditLog>>,
+    /// Organisations whose lines are highlighted, from `--known-orgs` or the config
+    known_orgs: Option<Arc<KnownOrgs>>,
+    limiter: Arc<HostLimiter>,
+    pool: Option<Arc<ConnectionPool>>,
+    /// Server capabilities kept across lookups when the SQLite cache is not open
+    capabilities: Option<Arc<dyn CapabilityCache>>,
+    suffixes: Arc<SuffixList>,
+    /// Server profiles from the config, built once for every lookup of the run
+    registry: Arc<ServerRegistry>,
+    /// SQLite cache opened with `--cache`
+    #[cfg(feature = "sqlite")]
+    store: Option<Arc<Store>>,
+    /// Values of the `--copy` field by target, copied together once the targets are done
+    copied: Mutex<HashMap<String, Vec<String>>>,
+}
+
+/// Query handler set up from the flags and the session
+fn query_handler(args: &Cli, session: &Session, deadline: Deadline, progress: ProgressCallback) -> WhoisQuery {
+    let mut query_handler = WhoisQuery::new()
+        .with_progress(progress)
+        .with_limiter(session.limiter.clone())
+        .with_suffix_list(session.suffixes.clone())
+        .with_fallback_chain(session.config.fallback_chain())
+        .with_registry(session.registry.clone())
+        .with_cleanup(args.cleanup_level())
+        .with_follow_referrals(!args.no_referral)
+        .with_reset_retry(args.retry_reset)
+        .with_full_output(args.arin_full)
+        .with_deadline(deadline);
+    if let Some(metrics) = &session.metrics {
+        query_handler = query_handler.with_metrics(metrics.clone());
+    }
+    if let Some(language) = args.lang {
+        query_handler = query_handler.with_language(language);
+    }
+    if let Some(record_type) = args.arin_type {
+        query_handler = query_handler.with_record_type(record_type);
+    }
+    if let Some(attribute) = &args.inverse {
+        query_handler = query_handler.with_inverse(attribute.as_str());
+    }
+    if let Some(types) = &args.object_types {
+        query_handler = query_handler.with_object_types(types.as_str());
+    }
+    if let Some(pool) = &session.pool {
+        query_handler = query_handler.with_connection_pool(pool.clone());
+    }
+    if let Some(capabilities) = &session.capabilities {
+        query_handler = query_handler.with_capability_cache(capabilities.clone());
+    }
+    #[cfg(feature = "sqlite")]
+    if let Some(store) = &session.store {
+        query_handler = query_handler.with_capability_cache(store.clone());
+    }
+    query_handler
+}
+
+/// Shared state of a run: the cache, registries, logs, limiter and connection pool
+fn open_session(args: &Cli, config: Config, suffixes: SuffixList) -> Result<Session> {
+    #[cfg(not(feature = "sqlite"))]
+    if args.cache {
+        anyhow::bail!("--cache requires the `sqlite` feature");
+    }
+    #[cfg(feature = "sqlite")]
+    let store = if args.cache { Some(open_store(&config)?) } else { None };
+    
+    let palette = args.palette_name().or(config.pride.as_deref()).map(|name| config.palette(name)).transpose()?;
+    let dn42_registry = args.dn42_registry.as_deref().or(config.dn42_registry.as_deref()).map(Dn42Registry::open).transpose()?.map(Arc::new);
+    let audit_log = args.audit_log.as_deref().or(config.audit_log.as_deref()).map(AuditLog::open).transpose()?.map(Arc::new);
+    // The default file is optional; a file named explicitly must exist
+    let known_orgs = match args.known_orgs.as_deref().or(config.known_orgs.as_deref()) {
+        Some(path) => Some(KnownOrgs::load(path)?),
+        None => KnownOrgs::default_path().filter(|path| path.exists()).map(|path| KnownOrgs::load(&path)).transpose()?,
+    };
+    Ok(Session {
+        palette,
+        dn42_registry,
+        theme: args.scheme.or(config.scheme).unwrap_or_default(),
+        symbols: args.symbols || config.symbols,
+        #[cfg(feature = "sqlite")]
+        store,
+        registry: Arc::new(config.registry()),
+        config,
+        metrics: args.metrics_file.as_ref().map(|_| Arc::new(Metrics::new())),
+        audit_log,
+        known_orgs: known_orgs.filter(|known| !known.is_empty()).map(Arc::new),
+        limiter: Arc::new(HostLimiter::new(args.per_host as usize, Duration::from_millis(args.delay))),
+        pool: args.use_keepalive().then(|| Arc::new(ConnectionPool::new())),
+        capabilities: None,
+        suffixes: Arc::new(suffixes),
+        copied: Mutex::default(),
+    })
+}
+
+/// Clean up targets before anything is sent; malformed ones are reported and skipped
+/// Pasted URLs and email addresses are reduced to the domain that can be looked up
+/// Returns the targets to query and the number rejected
+fn clean_targets(args: &Cli, suffixes: &SuffixList, raw: &[String]) -> (Vec<String>, usize) {
+    let mut invalid = 0;
+    let mut targets = Vec::new();
+    for target in raw {
+        let target = match extract_domain(target, Some(suffixes)) {
+            Some((indicator, domain)) => {
+                info!("Extracted {} from {} {}", domain, indicator, target);
+                domain
+            }
+            None => target.clone(),
+        };
+        match normalize_target(&target, args.asn) {
+            Ok(normalized) if args.apex => targets.push(apex_domain(suffixes, normalized)),
+            Ok(normalized) => targets.push(normalized),
+            Err(err) => {
+                invalid += 1;
+                eprintln!("{}: {}", tr!("invalid-target").bright_red(), err);
+            }
+        }
+    }
+    (targets, invalid)
+}
+
+/// Registrable domain of a host name for `--apex`; other targets are returned unchanged
+fn apex_domain(suffixes: &SuffixList, target: String) -> String {
+    if !target.contains('.') || target.contains(['@', ' ']) || target.parse::<std::net::IpAddr>().is_ok() || target.contains('/') {
+        return target;
+    }
+    match suffixes.registrable_domain(&target) {
+        Some(apex) if apex != target => {
+            info!("Registrable domain of {}: {}", target, apex);
+            apex
+        }
+        _ => target,
+    }
+}
+
+/// Query a single target and return its rendered output
+fn query_target(args: &Cli, session: &Session, target: &str, progress: ProgressCallback) -> Result<String> {
+    info!("Query: {}", target);
+    
     // Auto-detect DN42 ASNs for verbose output
-    if args.use_dn42() && args.verbose {
+    if args.use_dn42(target) {
         if args.dn42 {
-            println!("{}: {}", "Using DN42 server (from --42 flag)".bright_cyan(), args.domain.bright_white());
+            info!("Using DN42 server (from --42 flag): {}", target);
         } else {
-            println!("{}: {}", "Detected DN42 ASN pattern".bright_blue(), args.domain.bright_white());
+            info!("Detected DN42 ASN pattern: {}", target);
         }
     }
     
     // Create query handler
-    let query_handler = WhoisQuery::new(args.verbose);
+    let deadline = args.deadline.map(Deadline::after).transpose()?.unwrap_or_default();
+    let query_handler = query_handler(args, session, deadline, progress);
     
     // Determine preferred color scheme for server-side coloring
     let preferred_scheme = if args.use_mtf_colors() {
@@ -31,83 +1015,384 @@ fn main() -> Result<()> {
     };
 
     // Perform the query with enhanced protocol (v1.1) by default
-    let result = match query_handler.query_with_enhanced_protocol(
-        &args.domain,
-        args.use_dn42(),
-        args.use_bgptools(),
-        args.use_server_color(),
-        args.use_markdown(),
-        args.use_images(),
-        args.server.as_deref(),
-        args.port,
-        preferred_scheme,
-    ) {
-        Ok(result) => result,
-        Err(err) => {
-            eprintln!("{}: {}", "Query failed".bright_red(), err);
-            std::process::exit(1);
+    let query_key = args.query_key(target);
+    let first_server = query_handler.first_server(target, args.use_dn42(target), args.use_bgptools(), args.server.as_deref(), args.port)?;
+    let cache_key = args.cache_key(target, &first_server);
+    let started = Instant::now();
+    let mut cached = false;
+    let lookup = match cached_result(args, session, &cache_key) {
+        Some(result) => {
+            cached = true;
+            Ok(result)
+        }
+        None => {
+            let lookup = match session.dn42_registry.as_deref().filter(|_| args.use_dn42(target)) {
+                Some(registry) => dn42_lookup(registry, target),
+                None => query_handler.query_with_enhanced_protocol(
+                    target,
+                    args.use_dn42(target),
+                    args.use_bgptools(),
+                    args.use_server_color(),
+                    args.use_markdown(),
+                    args.use_images(),
+                    args.server.as_deref(),
+                    args.port,
+                    preferred_scheme,
+                ),
+            };
+            if let Ok(result) = &lookup {
+                record_result(session, &query_key, &cache_key, result);
+            }
+            lookup
+        }
+    };
+    if let Some(audit_log) = &session.audit_log {
+        if let Err(err) = audit_log.record(&AuditEntry::new(target, &lookup, started.elapsed(), cached)) {
+            warn!("{:#}", err);
         }
+    }
+    // RIPEstat is reached over HTTPS, so `--stat` still answers when port 43 is blocked
+    let mut result = match lookup {
+        Err(err) if args.stat && !deadline::is_cancelled() => {
+            match stat_summary(target, &deadline) {
+                Ok(Some(summary)) if !args.use_json() => {
+                    warn!("{}", tr!("warn-whois-failed-ripestat", error = format!("{:#}", err)));
+                    return Ok(summary);
+                }
+                _ => return Err(err),
+            }
+        }
+        lookup => lookup?,
     };
     
-    if args.verbose {
-        println!("{}: {}", "Final server used".bright_cyan(), result.server_used.host.yellow());
-        if result.server_colored {
-            println!("{}: {}", "Server-side coloring".bright_green(), "enabled".bright_green());
+    if args.timing {
+        eprintln!("{} {}: {}", "Timing".bright_cyan(), target, result.timing.summary());
+    }
+    let flags = query_handler.registry().query_flags(&result.server_used.host);
+    if (args.inverse.is_some() && !flags.inverse) || (args.object_types.is_some() && !flags.types) {
+        warn!("{}", tr!("warn-no-inverse", server = result.server_used.host));
+    }
+    
+    // Before any reshaping, which would break the signed text
+    let verification = (args.verify || session.config.pgp.verify).then(|| pgp::verify(&strip_ansi(&result.response), &session.config.pgp));
+    if args.dedup {
+        result.response = rpsl::dedup_objects(&result.response);
+    }
+    if args.group_by_type {
+        result.response = rpsl::group_by_type(&result.response);
+    }
+    if (args.fold_policy || session.config.fold_policy) && !args.full_policy {
+        result.response = rpsl::fold_policy(&result.response);
+    }
+    if args.cidr {
+        result.response = netblock::annotate(&result.response);
+    }
+    if args.related && related::is_network_target(target) {
+        match query_handler.query_related(target, &result.server_used) {
+            Ok(Some(networks)) => {
+                result.response = format!("{}\n\n{}\n", result.response.trim_end(), networks.render(target));
+            }
+            Ok(None) => warn!("{}", tr!("warn-related-skipped", server = result.server_used.host)),
+            Err(err) => warn!("{}", tr!("warn-related-failed", error = format!("{:#}", err))),
+        }
+    }
+    if args.contacts {
+        match query_handler.query_contacts(&result.response, &result.server_used) {
+            Some(records) => {
+                result.response = format!("{}\n\n{}\n", result.response.trim_end(), contacts::render(target, &records));
+            }
+            None => warn!("{}", tr!("warn-contacts-skipped", server = result.server_used.host)),
+        }
+    }
+    if args.stat {
+        match stat_summary(target, &deadline) {
+            Ok(Some(summary)) => result.response = format!("{}\n\n{}\n", result.response.trim_end(), summary),
+            Ok(None) => warn!("{}", tr!("warn-stat-skipped", target = target)),
+            Err(err) => warn!("{}", tr!("warn-stat-failed", error = format!("{:#}", err))),
+        }
+    }
+    if args.ct {
+        match certificates(target, &session.config, &deadline) {
+            Ok(Some(table)) => result.response = format!("{}\n\n{}\n", result.response.trim_end(), table),
+            Ok(None) => warn!("{}", tr!("warn-ct-skipped", target = target)),
+            Err(err) => warn!("{}", tr!("warn-ct-failed", error = format!("{:#}", err))),
+        }
+    }
+    if args.mail_check {
+        match mail_policy(target, &session.config, &deadline) {
+            Ok(Some(summary)) => result.response = format!("{}\n\n{}\n", result.response.trim_end(), summary),
+            Ok(None) => warn!("{}", tr!("warn-mail-skipped", target = target)),
+            Err(err) => warn!("{}", tr!("warn-mail-failed", error = format!("{:#}", err))),
+        }
+    }
+    if let Some(asn) = ripestat::parse_asn(target).filter(|_| args.prefixes || args.peers) {
+        for table in asn_overview(args, asn, &deadline) {
+            result.response = format!("{}\n\n{}\n", result.response.trim_end(), table);
+        }
+    }
+    if args.explain {
+        let attributes = glossary::explained(&strip_ansi(&result.response));
+        if !attributes.is_empty() {
+            result.response = format!("{}\n\n{}\n", result.response.trim_end(), glossary::render(&attributes));
         }
     }
     
-    // Handle output
-    if !result.response.trim().is_empty() {
-        let mut output = result.response.clone();
-        let mut is_markdown_content = false;
-        
+    info!("Final server used: {}", result.server_used.host);
+    if result.server_colored {
+        info!("Server-side coloring: enabled");
+    }
+    
+    // JSON keeps the raw response and reports the classification instead of failing on empty answers
+    if args.use_json() {
+        collect_field(args, session, target, &result.response);
+        return Ok(json::result_record(target, &result));
+    }
+    
+    if result.response.trim().is_empty() {
+        anyhow::bail!("{}", tr!("warn-empty-response"));
+    }
+    
+    // Collect the field to copy before any rendering touches the response
+    collect_field(args, session, target, &result.response);
+    
+    let mut output = result.response.clone();
+    let mut is_markdown_content = false;
+    let markdown_renderer = || {
+        MarkdownRenderer::new(args.use_images())
+            .with_image_protocol(args.image_protocol)
+            .with_width(args.wrap_width())
+            .with_hyperlinks(args.use_hyperlinks())
+    };
+    
+    // Marked Markdown sections inside a plain answer are rendered on their own, unless the
+    // server said the whole answer is Markdown
+    let mixed = if args.use_markdown() && result.markdown != Some(true) { markdown::split_mixed(&output) } else { None };
+    
+    if let Some(blocks) = mixed {
+        info!("Rendering {} Markdown section(s) inside a plain answer", blocks.iter().filter(|block| matches!(block, MixedBlock::Markdown(_))).count());
+        let rir = is_rir_response(&output);
+        output = blocks
+            .into_iter()
+            .map(|block| match block {
+                MixedBlock::Markdown(text) => markdown_renderer().render(&text).unwrap_or_else(|err| {
+                    warn!("{}", tr!("warn-markdown-failed", error = err));
+                    text
+                }),
+                MixedBlock::Plain(text) => decorate_plain(args, session, &result, &text, rir),
+            })
+            .collect();
+    } else {
         // Check if response contains Markdown and render it
-        if args.use_markdown() && MarkdownRenderer::is_markdown(&output) {
-            if args.verbose {
-                println!("{}", "Rendering Markdown content".bright_cyan());
-            }
-            let mut markdown_renderer = MarkdownRenderer::new(args.use_images());
-            match markdown_renderer.render(&output) {
+        // The server's X-WHOIS-MARKDOWN-APPLIED marker wins over guessing from the text
+        if args.use_markdown() && result.markdown.unwrap_or_else(|| MarkdownRenderer::is_markdown(&output)) {
+            info!("Rendering Markdown content");
+            match markdown_renderer().render(&output) {
                 Ok(rendered) => {
                     output = rendered;
                     is_markdown_content = true;
                 }
                 Err(err) => {
-                    if args.verbose {
-                        println!("{}: {}", "Markdown rendering failed".bright_yellow(), err);
-                    }
+                    warn!("{}", tr!("warn-markdown-failed", error = err));
                     // Fall back to original output
                 }
             }
         }
-        
-        // Apply hyperlinks if enabled, response is from any RIR, and not already rendered as Markdown
-        if args.use_hyperlinks() && !is_markdown_content && is_rir_response(&output) {
-            let hyperlink_processor = RirHyperlinkProcessor::new();
-            output = hyperlink_processor.process(&output);
+        // Markdown has its own coloring and links
+        if !is_markdown_content {
+            output = decorate_plain(args, session, &result, &output, is_rir_response(&output));
         }
-        
-        // Apply client-side coloring if server-side is disabled OR server didn't provide colors
-        // Skip if already rendered as Markdown (which has its own coloring)
-        if args.use_color() && !is_markdown_content && (!args.use_server_color() || !result.server_colored) {
-            let scheme = if args.use_mtf_colors() {
-                ColorScheme::Mtf
-            } else {
-                OutputColorizer::detect_scheme(&output)
-            };
-            output = OutputColorizer::colorize(&output, scheme);
-            
-            if args.verbose && args.use_server_color() && !result.server_colored {
-                println!("{}", "Server coloring not available, using client-side coloring".bright_yellow());
-            }
-        } else if args.verbose && result.server_colored && !is_markdown_content {
-            println!("{}", "Using server-provided coloring".bright_cyan());
+    }
+    
+    if let Some(known_orgs) = session.known_orgs.as_deref().filter(|_| !is_markdown_content) {
+        output = known_orgs.highlight(&output, session.theme);
+    }
+    
+    // Wrap after coloring so continuation lines keep their colors; Markdown was wrapped while rendering
+    if let Some(width) = args.wrap_width().filter(|_| !is_markdown_content) {
+        output = layout::wrap(&output, width);
+    }
+    
+    if result.partial {
+        let note = if result.connection_reset { tr!("partial-reset") } else { tr!("partial-response") };
+        output = format!("{}\n{}", output.trim_end(), format!("% {}", note).yellow());
+    }
+    if args.lint {
+        let warnings = lint::lint(&strip_ansi(&result.response), target);
+        if !warnings.is_empty() {
+            output = format!("{}\n\n{}", output.trim_end(), lint::render(&warnings).yellow());
         }
+    }
+    if let Some(verification) = verification {
+        output = format!("{}\n{}", output.trim_end(), verification.tone().paint(&verification.render(), session.theme));
+    }
+    
+    Ok(output)
+}
+
+/// Hyperlinks and client-side colors for plain WHOIS text
+/// `rir` tells whether the whole answer came from an RIR, as parts of it may lack `source:` lines
+fn decorate_plain(args: &Cli, session: &Session, result: &QueryResult, text: &str, rir: bool) -> String {
+    let mut output = if args.align { rpsl::align(text) } else { text.to_string() };
+    if session.symbols {
+        output = OutputColorizer::mark_symbols(&output);
+    }
+    
+    // Hyperlinks if enabled and response is from any RIR
+    let links = args.use_hyperlinks() && rir;
+    
+    // Apply client-side coloring if server-side is disabled OR server didn't provide colors;
+    // hyperlinks are added in the same pass
+    if args.use_color() && (!args.use_server_color() || !result.server_colored) {
+        output = match &session.palette {
+            Some(palette) => OutputColorizer::colorize_palette(&output, palette, session.theme, links),
+            None => OutputColorizer::colorize_linked(&output, OutputColorizer::detect_scheme(&output), session.theme, links),
+        };
         
-        println!("{}", output);
-        Ok(())
+        if args.use_server_color() && !result.server_colored {
+            info!("Server coloring not available, using client-side coloring");
+        }
+        return output;
+    }
+    if result.server_colored {
+        info!("Using server-provided coloring");
+    }
+    if links {
+        output = RirHyperlinkProcessor::new().process(&output);
+    }
+    output
+}
+
+/// Answer a DN42 lookup from the flat files of a registry checkout
+fn dn42_lookup(registry: &Dn42Registry, target: &str) -> Result<QueryResult> {
+    info!("Looking up {} in the dn42 registry at {}", target, registry.root().display());
+    let response = match registry.lookup(target)? {
+        Some(response) => response,
+        None => format!("% No entries found for '{}' in the dn42 registry\n", target),
+    };
+    Ok(QueryResult::new(response, WhoisServer::new(registry.root().display().to_string(), 0, "DN42 registry")))
+}
+
+/// RIPEstat summary block for an ASN, IP or prefix; `None` for other targets
+fn stat_summary(target: &str, deadline: &Deadline) -> Result<Option<String>> {
+    if ripestat::parse_asn(target).is_none() && !related::is_network_target(target) {
+        return Ok(None);
+    }
+    let timeout = deadline.step_timeout(Duration::from_secs(10), ripestat::RIPESTAT_URL)?;
+    let summary = RipeStat::new().with_timeout(timeout).summary(target)?;
+    Ok(Some(summary.render()))
+}
+
+/// Table of the certificates logged for a domain; `None` for other targets
+fn certificates(target: &str, config: &Config, deadline: &Deadline) -> Result<Option<String>> {
+    if !related::is_domain_target(target) {
+        return Ok(None);
+    }
+    let timeout = deadline.step_timeout(Duration::from_secs(20), &config.ct.url)?;
+    let certificates = ct::CtLog::new().with_base_url(config.ct.url.as_str()).with_timeout(timeout).certificates(target)?;
+    Ok(Some(ct::render(target, &certificates, config.ct.limit)))
+}
+
+/// MX, SPF and DMARC records of a domain with the problems found; `None` for other targets
+fn mail_policy(target: &str, config: &Config, deadline: &Deadline) -> Result<Option<String>> {
+    if !related::is_domain_target(target) {
+        return Ok(None);
+    }
+    let url = resolve::doh_url().or(config.doh_url.as_deref()).unwrap_or(mailcheck::DOH_URL);
+    let timeout = deadline.step_timeout(Duration::from_secs(10), url)?;
+    let records = mailcheck::DohResolver::new().with_base_url(url).with_timeout(timeout).mail_records(target)?;
+    Ok(Some(mailcheck::render(target, &records, &mailcheck::check(&records))))
+}
+
+/// Prefix and neighbour tables for an ASN from RIPEstat; failures are logged and skipped
+fn asn_overview(args: &Cli, asn: u32, deadline: &Deadline) -> Vec<String> {
+    let mut tables = Vec::new();
+    let stat = match deadline.step_timeout(Duration::from_secs(10), ripestat::RIPESTAT_URL) {
+        Ok(timeout) => RipeStat::new().with_timeout(timeout),
+        Err(err) => {
+            warn!("{}", tr!("warn-ripestat-skipped", error = format!("{:#}", err)));
+            return tables;
+        }
+    };
+    if args.prefixes {
+        match stat.announced_prefixes(asn) {
+            Ok(prefixes) => tables.push(ripestat::render_prefixes(asn, &prefixes)),
+            Err(err) => warn!("{}", tr!("warn-prefixes-failed", error = format!("{:#}", err))),
+        }
+    }
+    if args.peers {
+        match stat.neighbours(asn) {
+            Ok(neighbours) => tables.push(ripestat::render_neighbours(asn, &neighbours)),
+            Err(err) => warn!("{}", tr!("warn-neighbours-failed", error = format!("{:#}", err))),
+        }
+    }
+    tables
+}
+
+/// Keep the values of the `--copy` field of a target's answer, if requested
+fn collect_field(args: &Cli, session: &Session, target: &str, response: &str) {
+    let Some(field) = &args.copy else {
+        return;
+    };
+    let values = extract_field_values(&strip_ansi(response), field);
+    if values.is_empty() {
+        warn!("{}", tr!("warn-copy-not-found", field = field));
     } else {
-        eprintln!("{}", "Empty response received. Please check if your query is correct.".bright_red());
-        std::process::exit(1);
+        session.copied.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).insert(target.to_string(), values);
+    }
+}
+
+/// Copy the collected `--copy` values of `targets` to the clipboard at once, in target order
+fn copy_fields(args: &Cli, session: &Session, targets: &[String]) {
+    let Some(field) = &args.copy else {
+        return;
+    };
+    let mut copied = session.copied.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let mut values: Vec<String> = Vec::new();
+    for value in targets.iter().filter_map(|target| copied.remove(target)).flatten() {
+        if !values.contains(&value) {
+            values.push(value);
+        }
+    }
+    copied.clear();
+    if values.is_empty() {
+        return;
+    }
+    match copy_to_clipboard(&values.join("\n")) {
+        Ok(()) => info!("Copied to clipboard: {} ({} value(s))", field, values.len()),
+        Err(err) => warn!("{}", tr!("warn-copy-failed", error = format!("{:#}", err))),
+    }
+}
+
+/// Header line separating results when several targets are queried
+fn section_header(target: &str) -> String {
+    format!("{} {} {}", "═══".bright_black(), target.bright_white().bold(), "═══".bright_black())
+}
+
+/// Render the colored output as an SVG or PNG image
+#[cfg(feature = "render")]
+fn export_image(format: OutputFormat, output: &str) -> Result<Vec<u8>> {
+    let renderer = ImageRenderer::new();
+    match format {
+        OutputFormat::Png => renderer.render_png(output),
+        _ => Ok(renderer.render_svg(output).into_bytes()),
+    }
+}
+
+/// Image export is unavailable without the `render` feature
+#[cfg(not(feature = "render"))]
+fn export_image(_format: OutputFormat, _output: &str) -> Result<Vec<u8>> {
+    anyhow::bail!("SVG/PNG export requires the `render` feature")
+}
+
+/// Write the final payload to a file, or to stdout if no file was given
+fn write_output(path: Option<&str>, payload: &[u8]) -> Result<()> {
+    match path {
+        Some(path) => std::fs::write(path, payload)
+            .with_context(|| format!("Failed to write output to {}", path)),
+        None => {
+            let mut stdout = std::io::stdout();
+            stdout.write_all(payload).context("Failed to write to stdout")?;
+            stdout.flush().context("Failed to flush stdout")
+        }
     }
 }
@@ -1,34 +1,143 @@
-use anyhow::{Context, Result};
+use anyhow::Result;
 use colored::*;
-use pulldown_cmark::{Parser, Event, Tag, CodeBlockKind, HeadingLevel};
+use pulldown_cmark::{Parser, Event, Tag, CodeBlockKind, HeadingLevel, Options};
 use regex::Regex;
+use std::sync::OnceLock;
 
+use crate::graphics::ImageProtocol;
+use crate::hyperlink::{create_hyperlink, terminal_supports_hyperlinks};
+use crate::layout;
+use crate::protocol::{MARKDOWN_BEGIN_LINE, MARKDOWN_END_LINE};
+#[cfg(feature = "images")]
+use anyhow::Context;
+#[cfg(feature = "images")]
+use crate::graphics;
+#[cfg(feature = "images")]
+use crate::imagecache::ImageCache;
+#[cfg(feature = "images")]
+use tracing::debug;
 #[cfg(feature = "images")]
 use viuer::{Config as ViuerConfig, print_from_file};
 
+/// Distinct Markdown constructs needed before an answer is rendered as Markdown
+pub const MARKDOWN_THRESHOLD: usize = 2;
+/// Bytes at the start of an answer looked at to tell whether it is Markdown
+const MARKDOWN_SCAN_BYTES: usize = 256 * 1024;
+
+/// Largest image width in terminal columns
+#[cfg(feature = "images")]
+const IMAGE_COLUMNS: u32 = 80;
+
+/// Part of an answer that mixes plain WHOIS text with Markdown sections
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MixedBlock {
+    Plain(String),
+    Markdown(String),
+}
+
+/// Split an answer into plain text and the Markdown sections marked inside it:
+/// lines between `X-WHOIS-MARKDOWN-BEGIN` and `X-WHOIS-MARKDOWN-END`, or a ```` ```markdown ```` fence
+/// `None` when the answer has no such section
+pub fn split_mixed(text: &str) -> Option<Vec<MixedBlock>> {
+    let mut blocks = Vec::new();
+    let mut plain = String::new();
+    // Open section and the line that closes it
+    let mut section: Option<(String, &str)> = None;
+    for line in text.split_inclusive('\n') {
+        let trimmed = line.trim();
+        match &mut section {
+            Some((markdown, end)) if trimmed == *end => {
+                blocks.push(MixedBlock::Markdown(std::mem::take(markdown)));
+                section = None;
+            }
+            Some((markdown, _)) => markdown.push_str(line),
+            None => {
+                let end = if trimmed == MARKDOWN_BEGIN_LINE {
+                    Some(MARKDOWN_END_LINE)
+                } else if trimmed.eq_ignore_ascii_case("```markdown") || trimmed.eq_ignore_ascii_case("```md") {
+                    Some("```")
+                } else {
+                    None
+                };
+                match end {
+                    Some(end) => {
+                        if !plain.is_empty() {
+                            blocks.push(MixedBlock::Plain(std::mem::take(&mut plain)));
+                        }
+                        section = Some((String::new(), end));
+                    }
+                    None => plain.push_str(line),
+                }
+            }
+        }
+    }
+    // A section that is never closed runs to the end of the answer
+    if let Some((markdown, _)) = section {
+        blocks.push(MixedBlock::Markdown(markdown));
+    }
+    if !plain.is_empty() {
+        blocks.push(MixedBlock::Plain(plain));
+    }
+    blocks.iter().any(|block| matches!(block, MixedBlock::Markdown(_))).then_some(blocks)
+}
+
 /// Markdown renderer for terminal output with image support
 pub struct MarkdownRenderer {
     /// Whether to enable image display
     enable_images: bool,
+    /// How images are drawn, resolved from the terminal when `Auto`
+    image_protocol: ImageProtocol,
+    /// Width paragraphs are wrapped at, `None` to leave lines as they are
+    width: Option<usize>,
+    /// Whether links become OSC 8 hyperlinks on terminals that support them
+    hyperlinks: bool,
 }
 
 impl MarkdownRenderer {
     pub fn new(enable_images: bool) -> Self {
         Self {
             enable_images,
+            image_protocol: ImageProtocol::Blocks,
+            width: None,
+            hyperlinks: true,
         }
     }
 
+    pub fn with_image_protocol(mut self, protocol: ImageProtocol) -> Self {
+        self.image_protocol = protocol.resolve();
+        self
+    }
+
+    pub fn with_width(mut self, width: Option<usize>) -> Self {
+        self.width = width;
+        self
+    }
+
+    pub fn with_hyperlinks(mut self, hyperlinks: bool) -> Self {
+        self.hyperlinks = hyperlinks;
+        self
+    }
+
     /// Render markdown text to colored terminal output
     pub fn render(&mut self, markdown: &str) -> Result<String> {
-        let parser = Parser::new(markdown);
+        // GitHub-flavored extensions that server-generated Markdown may use
+        let options = Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS | Options::ENABLE_FOOTNOTES;
+        let parser = Parser::new_ext(markdown, options);
         let mut output = String::new();
         let mut in_code_block = false;
         let mut in_emphasis = false;
         let mut in_strong = false;
+        let mut in_strikethrough = false;
+        // Footnote labels in order of first appearance, numbered from 1
+        let mut footnotes: Vec<String> = Vec::new();
+        // Destination of the open link and where its text starts in the output
+        let mut link: Option<(String, usize)> = None;
         let mut in_heading = false;
         let mut heading_level = HeadingLevel::H1;
-        let mut list_stack: Vec<bool> = Vec::new(); // true for ordered, false for unordered
+        // Per nesting level: next number of an ordered list, and the indentation of its items
+        let mut list_stack: Vec<(Option<u64>, usize)> = Vec::new();
+        // Output length right after the last list marker, so an item's first paragraph stays on its line
+        let mut item_text_start = usize::MAX;
 
         for event in parser {
             match event {
@@ -40,23 +149,33 @@ impl MarkdownRenderer {
                             output.push('\n');
                         }
                         Tag::Paragraph => {
-                            if !output.is_empty() && !output.ends_with('\n') {
+                            if !output.is_empty() && !output.ends_with('\n') && output.len() != item_text_start {
                                 output.push('\n');
                             }
                         }
                         Tag::List(start_number) => {
-                            list_stack.push(start_number.is_some());
-                            output.push('\n');
+                            // Nested lists start inside their parent item, indented under its text
+                            let indent = match list_stack.last() {
+                                Some((number, indent)) => indent + marker_width(number.map(|next| next.saturating_sub(1))),
+                                None => 0,
+                            };
+                            if list_stack.is_empty() || !output.ends_with('\n') {
+                                output.push('\n');
+                            }
+                            list_stack.push((start_number, indent));
                         }
                         Tag::Item => {
-                            let indent = "  ".repeat(list_stack.len().saturating_sub(1));
-                            if let Some(&is_ordered) = list_stack.last() {
-                                if is_ordered {
-                                    output.push_str(&format!("{}1. ", indent));
-                                } else {
-                                    output.push_str(&format!("{}• ", indent));
+                            if let Some((number, indent)) = list_stack.last_mut() {
+                                output.push_str(&" ".repeat(*indent));
+                                match number {
+                                    Some(next) => {
+                                        output.push_str(&format!("{}. ", next));
+                                        *next += 1;
+                                    }
+                                    None => output.push_str("• "),
                                 }
                             }
+                            item_text_start = output.len();
                         }
                         Tag::Emphasis => {
                             in_emphasis = true;
@@ -64,6 +183,17 @@ impl MarkdownRenderer {
                         Tag::Strong => {
                             in_strong = true;
                         }
+                        Tag::Strikethrough => {
+                            in_strikethrough = true;
+                        }
+                        Tag::FootnoteDefinition(label) => {
+                            if !output.ends_with('\n') {
+                                output.push('\n');
+                            }
+                            let number = footnote_number(&mut footnotes, &label);
+                            output.push_str(&format!("{} ", superscript(number)).dimmed().to_string());
+                            item_text_start = output.len();
+                        }
                         Tag::CodeBlock(kind) => {
                             in_code_block = true;
                             output.push('\n');
@@ -78,13 +208,9 @@ impl MarkdownRenderer {
                             }
                         }
                         // Inline code is handled in Event::Code, not as a Tag
-                        Tag::Link(_link_type, dest_url, title) => {
-                            // Handle hyperlinks
-                            if !title.is_empty() {
-                                output.push_str(&format!("{} ({})", title.bright_blue().underline(), dest_url.bright_black()));
-                            } else {
-                                output.push_str(&dest_url.bright_blue().underline().to_string());
-                            }
+                        Tag::Link(_link_type, dest_url, _title) => {
+                            // The link text is rendered first and wrapped when the link ends
+                            link = Some((dest_url.to_string(), output.len()));
                         }
                         Tag::Image(_link_type, dest_url, title) => {
                             self.handle_image(&mut output, dest_url.as_ref(), title.as_ref())?;
@@ -106,10 +232,15 @@ impl MarkdownRenderer {
                         }
                         Tag::List(_) => {
                             list_stack.pop();
-                            output.push('\n');
+                            // A nested list ends with its parent item
+                            if list_stack.is_empty() {
+                                output.push('\n');
+                            }
                         }
                         Tag::Item => {
-                            output.push('\n');
+                            if !output.ends_with('\n') {
+                                output.push('\n');
+                            }
                         }
                         Tag::Emphasis => {
                             in_emphasis = false;
@@ -117,6 +248,15 @@ impl MarkdownRenderer {
                         Tag::Strong => {
                             in_strong = false;
                         }
+                        Tag::Strikethrough => {
+                            in_strikethrough = false;
+                        }
+                        Tag::Link(_, _, _) => {
+                            if let Some((url, start)) = link.take() {
+                                let text = output.split_off(start);
+                                output.push_str(&self.render_link(&url, &text));
+                            }
+                        }
                         Tag::CodeBlock(_) => {
                             in_code_block = false;
                             output.push_str("```\n\n");
@@ -145,6 +285,8 @@ impl MarkdownRenderer {
                         text.bold().to_string()
                     } else if in_emphasis {
                         text.italic().to_string()
+                    } else if in_strikethrough {
+                        text.strikethrough().to_string()
                     } else {
                         text.to_string()
                     };
@@ -164,17 +306,48 @@ impl MarkdownRenderer {
                 Event::HardBreak => {
                     output.push('\n');
                 }
+                Event::TaskListMarker(checked) => {
+                    // The checkbox takes the place of the bullet
+                    if output.len() == item_text_start && output.ends_with("• ") {
+                        output.truncate(output.len() - "• ".len());
+                    }
+                    if checked {
+                        output.push_str(&format!("{} ", "✓".green()));
+                    } else {
+                        output.push_str("☐ ");
+                    }
+                    item_text_start = output.len();
+                }
+                Event::FootnoteReference(label) => {
+                    let number = footnote_number(&mut footnotes, &label);
+                    output.push_str(&superscript(number).dimmed().to_string());
+                }
                 Event::Rule => {
-                    output.push_str(&"─".repeat(80).bright_black().to_string());
+                    output.push_str(&"─".repeat(self.width.unwrap_or(80).min(80)).bright_black().to_string());
                     output.push('\n');
                 }
-                _ => {}
             }
         }
 
+        if let Some(width) = self.width {
+            output = layout::wrap_paragraphs(&output, width);
+        }
         Ok(output)
     }
 
+    /// A link as an OSC 8 hyperlink when the terminal supports it, otherwise as `text (url)`
+    fn render_link(&self, url: &str, text: &str) -> String {
+        let label = if text.is_empty() { url } else { text };
+        let styled = label.bright_blue().underline().to_string();
+        if self.hyperlinks && terminal_supports_hyperlinks() {
+            create_hyperlink(url, &styled)
+        } else if label == url {
+            styled
+        } else {
+            format!("{} ({})", styled, url.bright_black())
+        }
+    }
+
     /// Handle image display in terminal
     fn handle_image(&mut self, output: &mut String, url: &str, title: &str) -> Result<()> {
         if !self.enable_images {
@@ -219,7 +392,8 @@ impl MarkdownRenderer {
         use base64::Engine;
         
         // Parse data URL: data:image/png;base64,iVBORw0KGgoAAAANS...
-        let re = Regex::new(r"data:image/([^;]+);base64,(.+)").unwrap();
+        static DATA_URL: OnceLock<Regex> = OnceLock::new();
+        let re = DATA_URL.get_or_init(|| Regex::new(r"data:image/([^;]+);base64,(.+)").unwrap());
         if let Some(captures) = re.captures(data_url) {
             let format = &captures[1];
             let base64_data = &captures[2];
@@ -229,20 +403,20 @@ impl MarkdownRenderer {
                 .decode(base64_data)
                 .context("Failed to decode base64 image data")?;
             
-            // Write to temporary file
-            let temp_path = format!("/tmp/whois_image_{}.{}", 
-                std::process::id(), format);
-            std::fs::write(&temp_path, &image_data)
-                .context("Failed to write temporary image file")?;
-            
-            // Display image
-            let config = ViuerConfig {
-                width: Some(80),
-                height: Some(24),
-                ..Default::default()
+            let shown = if self.image_protocol == ImageProtocol::Blocks {
+                // viuer draws from a file
+                let temp_path = format!("/tmp/whois_image_{}.{}", 
+                    std::process::id(), format);
+                std::fs::write(&temp_path, &image_data)
+                    .context("Failed to write temporary image file")?;
+                let shown = self.draw_blocks(&temp_path);
+                let _ = std::fs::remove_file(&temp_path);
+                shown
+            } else {
+                self.draw_inline(output, &image_data)
             };
             
-            match print_from_file(&temp_path, &config) {
+            match shown {
                 Ok(_) => {
                     if !title.is_empty() {
                         output.push_str(&format!("\n{}\n", title.bright_green()));
@@ -253,9 +427,6 @@ impl MarkdownRenderer {
                         if !title.is_empty() { title } else { "embedded image" }));
                 }
             }
-            
-            // Clean up
-            let _ = std::fs::remove_file(&temp_path);
         } else {
             output.push_str(&format!("[Invalid data URL: {}]\n", 
                 if !title.is_empty() { title } else { "embedded image" }));
@@ -266,7 +437,26 @@ impl MarkdownRenderer {
 
     #[cfg(feature = "images")]
     fn handle_remote_image(&mut self, output: &mut String, url: &str, title: &str) -> Result<()> {
-        // For now, just show as link - could implement downloading in the future
+        // Download into the image cache; when offline or the image is rejected, show a link
+        let shown = ImageCache::default_dir()
+            .context("No image cache directory")
+            .and_then(|dir| ImageCache::new(dir).fetch(url))
+            .and_then(|path| {
+                if self.image_protocol == ImageProtocol::Blocks {
+                    self.draw_blocks(&path.to_string_lossy())
+                } else {
+                    self.draw_inline(output, &std::fs::read(&path)?)
+                }
+            });
+        match shown {
+            Ok(()) => {
+                if !title.is_empty() {
+                    output.push_str(&format!("\n{}\n", title.bright_green()));
+                }
+                return Ok(());
+            }
+            Err(err) => debug!("Showing {} as a link: {:#}", url, err),
+        }
         if !title.is_empty() {
             output.push_str(&format!("[Remote Image: {}] ({})\n", title.bright_green(), url.bright_black()));
         } else {
@@ -277,13 +467,15 @@ impl MarkdownRenderer {
 
     #[cfg(feature = "images")]
     fn handle_local_image(&mut self, output: &mut String, path: &str, title: &str) -> Result<()> {
-        let config = ViuerConfig {
-            width: Some(80),
-            height: Some(24),
-            ..Default::default()
+        let shown = if self.image_protocol == ImageProtocol::Blocks {
+            self.draw_blocks(path)
+        } else {
+            std::fs::read(path)
+                .map_err(anyhow::Error::from)
+                .and_then(|data| self.draw_inline(output, &data))
         };
         
-        match print_from_file(path, &config) {
+        match shown {
             Ok(_) => {
                 if !title.is_empty() {
                     output.push_str(&format!("\n{}\n", title.bright_green()));
@@ -298,36 +490,105 @@ impl MarkdownRenderer {
         Ok(())
     }
 
+    /// Draw an image file with Unicode half blocks, straight to the terminal
+    #[cfg(feature = "images")]
+    fn draw_blocks(&self, path: &str) -> Result<()> {
+        let config = ViuerConfig {
+            width: Some(IMAGE_COLUMNS),
+            height: Some(24),
+            use_kitty: false,
+            use_iterm: false,
+            ..Default::default()
+        };
+        print_from_file(path, &config)
+            .map(|_| ())
+            .map_err(|err| anyhow::anyhow!("{}", err))
+    }
+
+    /// Add the escape sequence of a Kitty, iTerm2 or Sixel image to the output
+    #[cfg(feature = "images")]
+    fn draw_inline(&self, output: &mut String, data: &[u8]) -> Result<()> {
+        output.push_str(&graphics::encode(self.image_protocol, data, IMAGE_COLUMNS)?);
+        output.push('\n');
+        Ok(())
+    }
+
     /// Strip HTML tags from text
     fn strip_html(&self, html: &str) -> String {
-        let re = Regex::new(r"<[^>]*>").unwrap();
+        static TAG: OnceLock<Regex> = OnceLock::new();
+        let re = TAG.get_or_init(|| Regex::new(r"<[^>]*>").unwrap());
         re.replace_all(html, "").to_string()
     }
 
-    /// Check if text contains markdown syntax
+    /// Check if text is Markdown: at least `MARKDOWN_THRESHOLD` distinct constructs must appear,
+    /// so plain WHOIS text with the odd asterisk or backtick is left alone
     pub fn is_markdown(text: &str) -> bool {
-        // Simple heuristics to detect markdown
-        let markdown_patterns = [
-            r"^#{1,6}\s",          // Headers
-            r"\*\*.*\*\*",         // Bold
-            r"\*.*\*",             // Italic
-            r"`.*`",               // Inline code
-            r"```",                // Code blocks
-            r"^\s*[-*+]\s",        // Unordered lists
-            r"^\s*\d+\.\s",        // Ordered lists
-            r"\[.*\]\(.*\)",       // Links
-            r"!\[.*\]\(.*\)",      // Images
-            r"^\s*>",              // Blockquotes
-        ];
-        
-        for pattern in &markdown_patterns {
-            let re = Regex::new(pattern).unwrap();
-            if re.is_match(text) {
-                return true;
-            }
+        Self::markdown_score(text) >= MARKDOWN_THRESHOLD
+    }
+
+    /// Number of distinct Markdown constructs found in the text
+    pub fn markdown_score(text: &str) -> usize {
+        // The start of an answer tells its format; scanning all of a huge one would only cost time
+        let end = (0..=text.len().min(MARKDOWN_SCAN_BYTES)).rev().find(|&index| text.is_char_boundary(index)).unwrap_or(0);
+        let text = &text[..end];
+        static PATTERNS: OnceLock<Vec<Regex>> = OnceLock::new();
+        let patterns = PATTERNS.get_or_init(|| {
+            [
+                r"\*\*[^*\s]([^*\n]*[^*\s])?\*\*|__[^_\s]([^_\n]*[^_\s])?__", // Bold
+                r"(?m)(^|[\s(])[*_][^*_\s][^*_\n]*[^*_\s][*_]([\s).,;:!?]|$)", // Italic
+                r"`[^`\n]+`",                                         // Inline code
+                r"(?m)^```",                                          // Code blocks
+                r"(?m)^\s*[-*+] \S.*\n\s*[-*+] \S",                    // Unordered lists of two or more items
+                r"(?m)^\s*\d+\. \S.*\n\s*\d+\. \S",                  // Ordered lists of two or more items
+                r"(^|[^!])\[[^\]\n]+\]\([^)\s]+\)",                   // Links
+                r"!\[[^\]\n]*\]\([^)\s]+\)",                          // Images
+                r"(?m)^> \S",                                        // Blockquotes
+                r"~~[^~\n]+~~",                                      // Strikethrough
+                r"\[\^[^\]\s]+\]",                                    // Footnotes
+                r"(?m)^\|.*\|\s*\n\|[\s:|-]+\|",                       // Tables
+            ]
+            .iter()
+            .map(|pattern| Regex::new(pattern).unwrap())
+            .collect()
+        });
+        let headings = usize::from(has_heading(text));
+        headings + patterns.iter().filter(|pattern| pattern.is_match(text)).count()
+    }
+}
+
+/// Whether the text has a `#` heading; runs of `#` lines are comment blocks (ARIN, JPRS), not headings
+fn has_heading(text: &str) -> bool {
+    static HEADING: OnceLock<Regex> = OnceLock::new();
+    let heading = HEADING.get_or_init(|| Regex::new(r"^#{1,6} \S").unwrap());
+    let lines: Vec<&str> = text.lines().collect();
+    let is_comment = |index: Option<usize>| index.and_then(|index| lines.get(index)).is_some_and(|line| line.starts_with('#'));
+    (0..lines.len()).any(|index| {
+        heading.is_match(lines[index]) && !is_comment(index.checked_sub(1)) && !is_comment(Some(index + 1))
+    })
+}
+
+/// Number of a footnote label, assigned in order of first appearance
+fn footnote_number(footnotes: &mut Vec<String>, label: &str) -> usize {
+    match footnotes.iter().position(|known| known == label) {
+        Some(index) => index + 1,
+        None => {
+            footnotes.push(label.to_string());
+            footnotes.len()
         }
-        
-        false
+    }
+}
+
+/// A number in superscript digits
+fn superscript(number: usize) -> String {
+    const DIGITS: [char; 10] = ['⁰', '¹', '²', '³', '⁴', '⁵', '⁶', '⁷', '⁸', '⁹'];
+    number.to_string().chars().map(|digit| DIGITS[digit as usize - '0' as usize]).collect()
+}
+
+/// Columns taken by a list marker: `• ` or the item number followed by `. `
+fn marker_width(number: Option<u64>) -> usize {
+    match number {
+        Some(number) => number.to_string().len() + 2,
+        None => 2,
     }
 }
 
@@ -337,14 +598,21 @@ mod tests {
 
     #[test]
     fn test_is_markdown() {
-        assert!(MarkdownRenderer::is_markdown("# Header"));
-        assert!(MarkdownRenderer::is_markdown("**bold text**"));
-        assert!(MarkdownRenderer::is_markdown("- list item"));
-        assert!(MarkdownRenderer::is_markdown("[link](http://example.com)"));
-        assert!(MarkdownRenderer::is_markdown("![image](image.png)"));
-        assert!(MarkdownRenderer::is_markdown("> blockquote"));
-        assert!(MarkdownRenderer::is_markdown("```code```"));
+        assert!(MarkdownRenderer::is_markdown("# Header\n\nSome **bold text**"));
+        assert!(MarkdownRenderer::is_markdown("- list item\n- [link](http://example.com)"));
+        assert!(MarkdownRenderer::is_markdown("![image](image.png)\n> blockquote"));
+        assert!(MarkdownRenderer::is_markdown("```\ncode\n```\n\nUse `whois -v`"));
         assert!(!MarkdownRenderer::is_markdown("plain text"));
+        assert!(!MarkdownRenderer::is_markdown("# Header"));
+        assert!(!MarkdownRenderer::is_markdown("**bold text**"));
+    }
+
+    #[test]
+    fn test_whois_text_is_not_markdown() {
+        let ripe = "% This is the RIPE Database query service.\n\naut-num: AS64500\nremarks: *** Peering policy ***\nremarks: * contact noc@example.net *\nremarks: ----------------\nsource: RIPE\n";
+        assert!(!MarkdownRenderer::is_markdown(ripe));
+        let arin = "#\n# ARIN WHOIS data and services are subject to the Terms of Use\n# available at: https://www.arin.net/resources/registry/whois/tou/\n#\nNetRange: 192.0.2.0 - 192.0.2.255\nComment: `legacy` block\n";
+        assert!(!MarkdownRenderer::is_markdown(arin));
     }
 
     #[test]
@@ -368,4 +636,99 @@ mod tests {
         let result = renderer.render("- Item 1\n- Item 2\n\n1. Numbered\n2. List").unwrap();
         assert!(!result.is_empty());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_paragraph_wrapping() {
+        let paragraph = "This network is operated by the example registry and announced from several locations. ".repeat(3);
+        let mut renderer = MarkdownRenderer::new(false).with_width(Some(40));
+        let result = renderer.render(&format!("{}\n\n- {}", paragraph, paragraph)).unwrap();
+        assert!(result.lines().count() > 6);
+        assert!(result.lines().all(|line| layout::display_width(line) <= 40));
+    }
+
+    #[test]
+    fn test_ordered_list_numbering() {
+        let mut renderer = MarkdownRenderer::new(false);
+        let result = renderer.render("1. First\n2. Second\n3. Third\n\n---\n\n7. Seven\n8. Eight").unwrap();
+        let items: Vec<&str> = result.lines().filter(|line| !line.is_empty() && !line.contains('─')).collect();
+        assert_eq!(items, vec!["1. First", "2. Second", "3. Third", "7. Seven", "8. Eight"]);
+    }
+
+    #[test]
+    fn test_nested_lists() {
+        let mut renderer = MarkdownRenderer::new(false);
+        let markdown = "1. Registries\n   - RIPE\n   - ARIN\n2. Servers\n   1. whois.ripe.net\n   2. whois.arin.net\n- Unordered\n  1. Nested number";
+        let result = renderer.render(markdown).unwrap();
+        let lines: Vec<&str> = result.lines().filter(|line| !line.is_empty()).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "1. Registries",
+                "   • RIPE",
+                "   • ARIN",
+                "2. Servers",
+                "   1. whois.ripe.net",
+                "   2. whois.arin.net",
+                "• Unordered",
+                "  1. Nested number",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_gfm_extensions() {
+        let mut renderer = MarkdownRenderer::new(false);
+        let markdown = "- [x] Route object created\n- [ ] RPKI ROA signed\n\nMoved to ~~AS64500~~ AS64501[^moved].\n\n[^moved]: Since the 2024 merger.";
+        let result = crate::ansi::strip_ansi(&renderer.render(markdown).unwrap());
+        let lines: Vec<&str> = result.lines().filter(|line| !line.is_empty()).collect();
+        assert_eq!(lines[0], "✓ Route object created");
+        assert_eq!(lines[1], "☐ RPKI ROA signed");
+        assert_eq!(lines[2], "Moved to AS64500 AS64501¹.");
+        assert_eq!(lines[3], "¹ Since the 2024 merger.");
+        assert_eq!(superscript(120), "¹²⁰");
+        assert!(MarkdownRenderer::is_markdown("was ~~AS64500~~[^moved]"));
+    }
+
+    #[test]
+    fn test_links_without_hyperlinks() {
+        let mut renderer = MarkdownRenderer::new(false).with_hyperlinks(false);
+        let result = renderer.render("See [the RIPE database](https://apps.db.ripe.net) or <https://rdap.org>.").unwrap();
+        assert_eq!(
+            crate::ansi::strip_ansi(result.trim()),
+            "See the RIPE database (https://apps.db.ripe.net) or https://rdap.org."
+        );
+    }
+
+    #[test]
+    fn test_split_mixed() {
+        let answer = "aut-num: AS64500\nX-WHOIS-MARKDOWN-BEGIN\n## Peering\n\n- **IX**: AMS-IX\nX-WHOIS-MARKDOWN-END\nsource: RIPE\n";
+        assert_eq!(
+            split_mixed(answer).unwrap(),
+            vec![
+                MixedBlock::Plain("aut-num: AS64500\n".to_string()),
+                MixedBlock::Markdown("## Peering\n\n- **IX**: AMS-IX\n".to_string()),
+                MixedBlock::Plain("source: RIPE\n".to_string()),
+            ]
+        );
+
+        let fenced = "remarks: policy below\n```markdown\n# Policy\n```\n";
+        assert_eq!(split_mixed(fenced).unwrap()[1], MixedBlock::Markdown("# Policy\n".to_string()));
+        assert_eq!(split_mixed("aut-num: AS64500\n```\ncode\n```\n"), None);
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn test_markdown_score_any_text(text in "(?s)\\PC{0,2000}") {
+            proptest::prop_assert!(MarkdownRenderer::markdown_score(&text) <= 13);
+            let _ = split_mixed(&text);
+        }
+    }
+
+    #[test]
+    fn test_markdown_scan_limit() {
+        // Three-byte characters put the scan limit inside a character
+        let text = format!("{}\n# Heading\n**bold**", "€".repeat(MARKDOWN_SCAN_BYTES / 3 + 1));
+        assert!(!MarkdownRenderer::is_markdown(&text));
+        assert!(MarkdownRenderer::is_markdown("\n# Heading\n**bold**"));
+    }
+}
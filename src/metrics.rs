@@ -0,0 +1,209 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+
+/// Upper bounds (in seconds) of the upstream latency histogram buckets
+pub const LATENCY_BUCKETS: [f64; 9] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+/// Cumulative latency histogram for one upstream server
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        for (bucket, bound) in self.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+            if seconds <= bound {
+                *bucket += 1;
+            }
+        }
+        self.count += 1;
+        self.sum += seconds;
+    }
+}
+
+#[derive(Debug, Default)]
+struct MetricsInner {
+    lookups: BTreeMap<&'static str, u64>,
+    cache_hits: u64,
+    cache_misses: u64,
+    upstream_queries: BTreeMap<String, u64>,
+    upstream_errors: BTreeMap<String, u64>,
+    upstream_latency: BTreeMap<String, Histogram>,
+}
+
+/// Query metrics in Prometheus text exposition format
+/// Shared between threads; the query engine records per-upstream figures, the caller records lookup outcomes
+#[derive(Debug, Default)]
+pub struct Metrics {
+    inner: Mutex<MetricsInner>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the outcome of a complete lookup
+    pub fn record_lookup(&self, success: bool) {
+        let status = if success { "success" } else { "error" };
+        if let Ok(mut inner) = self.inner.lock() {
+            *inner.lookups.entry(status).or_default() += 1;
+        }
+    }
+
+    /// Record whether a lookup was answered from the cache
+    pub fn record_cache(&self, hit: bool) {
+        if let Ok(mut inner) = self.inner.lock() {
+            if hit {
+                inner.cache_hits += 1;
+            } else {
+                inner.cache_misses += 1;
+            }
+        }
+    }
+
+    /// Record a successful query against an upstream server
+    pub fn record_upstream_query(&self, upstream: &str, latency: Duration) {
+        if let Ok(mut inner) = self.inner.lock() {
+            *inner.upstream_queries.entry(upstream.to_string()).or_default() += 1;
+            inner
+                .upstream_latency
+                .entry(upstream.to_string())
+                .or_default()
+                .observe(latency.as_secs_f64());
+        }
+    }
+
+    /// Record a failed query against an upstream server
+    pub fn record_upstream_error(&self, upstream: &str) {
+        if let Ok(mut inner) = self.inner.lock() {
+            *inner.upstream_errors.entry(upstream.to_string()).or_default() += 1;
+        }
+    }
+
+    /// Render all metrics in Prometheus text format
+    pub fn render(&self) -> String {
+        let inner = match self.inner.lock() {
+            Ok(inner) => inner,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let mut out = String::new();
+
+        write_header(&mut out, "whois_lookups_total", "counter", "Lookups performed, by outcome");
+        for (status, count) in &inner.lookups {
+            let _ = writeln!(out, "whois_lookups_total{{status=\"{}\"}} {}", status, count);
+        }
+
+        write_header(&mut out, "whois_cache_hits_total", "counter", "Lookups answered from the cache");
+        let _ = writeln!(out, "whois_cache_hits_total {}", inner.cache_hits);
+        write_header(&mut out, "whois_cache_misses_total", "counter", "Lookups the cache had no fresh answer for");
+        let _ = writeln!(out, "whois_cache_misses_total {}", inner.cache_misses);
+        let cache_lookups = inner.cache_hits + inner.cache_misses;
+        if cache_lookups > 0 {
+            write_header(&mut out, "whois_cache_hit_ratio", "gauge", "Share of cache lookups that were hits");
+            let _ = writeln!(out, "whois_cache_hit_ratio {}", inner.cache_hits as f64 / cache_lookups as f64);
+        }
+
+        write_header(&mut out, "whois_upstream_queries_total", "counter", "Queries answered by each upstream WHOIS server");
+        for (upstream, count) in &inner.upstream_queries {
+            let _ = writeln!(out, "whois_upstream_queries_total{{upstream=\"{}\"}} {}", escape_label(upstream), count);
+        }
+
+        write_header(&mut out, "whois_upstream_errors_total", "counter", "Failed queries to each upstream WHOIS server");
+        for (upstream, count) in &inner.upstream_errors {
+            let _ = writeln!(out, "whois_upstream_errors_total{{upstream=\"{}\"}} {}", escape_label(upstream), count);
+        }
+
+        write_header(&mut out, "whois_upstream_latency_seconds", "histogram", "Upstream query latency in seconds");
+        for (upstream, histogram) in &inner.upstream_latency {
+            let upstream = escape_label(upstream);
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(histogram.buckets) {
+                let _ = writeln!(out, "whois_upstream_latency_seconds_bucket{{upstream=\"{}\",le=\"{}\"}} {}", upstream, bound, count);
+            }
+            let _ = writeln!(out, "whois_upstream_latency_seconds_bucket{{upstream=\"{}\",le=\"+Inf\"}} {}", upstream, histogram.count);
+            let _ = writeln!(out, "whois_upstream_latency_seconds_sum{{upstream=\"{}\"}} {}", upstream, histogram.sum);
+            let _ = writeln!(out, "whois_upstream_latency_seconds_count{{upstream=\"{}\"}} {}", upstream, histogram.count);
+        }
+
+        out
+    }
+
+    /// Write the metrics for the node_exporter textfile collector
+    /// The file is replaced atomically so a scrape never sees partial output
+    pub fn write_textfile(&self, path: &Path) -> Result<()> {
+        let tmp_path = path.with_extension("prom.tmp");
+        std::fs::write(&tmp_path, self.render())
+            .with_context(|| format!("Failed to write metrics to {}", tmp_path.display()))?;
+        std::fs::rename(&tmp_path, path)
+            .with_context(|| format!("Failed to move metrics into place at {}", path.display()))
+    }
+}
+
+fn write_header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+/// Escape a label value per the exposition format
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_buckets_are_cumulative() {
+        let mut histogram = Histogram::default();
+        histogram.observe(0.2);
+        histogram.observe(3.0);
+        assert_eq!(histogram.buckets[0], 0); // le 0.05
+        assert_eq!(histogram.buckets[2], 1); // le 0.25
+        assert_eq!(histogram.buckets[6], 2); // le 5.0
+        assert_eq!(histogram.count, 2);
+    }
+
+    #[test]
+    fn test_render_prometheus_format() {
+        let metrics = Metrics::new();
+        metrics.record_lookup(true);
+        metrics.record_lookup(false);
+        metrics.record_upstream_query("whois.ripe.net:43", Duration::from_millis(120));
+        metrics.record_upstream_error("whois.arin.net:43");
+        assert!(!metrics.render().contains("whois_cache_hit_ratio"));
+        metrics.record_cache(true);
+        metrics.record_cache(false);
+        metrics.record_cache(false);
+        metrics.record_cache(false);
+
+        let output = metrics.render();
+        assert!(output.contains("whois_cache_hits_total 1\n"));
+        assert!(output.contains("whois_cache_misses_total 3\n"));
+        assert!(output.contains("whois_cache_hit_ratio 0.25\n"));
+        assert!(output.contains("# TYPE whois_lookups_total counter"));
+        assert!(output.contains("whois_lookups_total{status=\"success\"} 1"));
+        assert!(output.contains("whois_lookups_total{status=\"error\"} 1"));
+        assert!(output.contains("whois_upstream_queries_total{upstream=\"whois.ripe.net:43\"} 1"));
+        assert!(output.contains("whois_upstream_errors_total{upstream=\"whois.arin.net:43\"} 1"));
+        assert!(output.contains("whois_upstream_latency_seconds_bucket{upstream=\"whois.ripe.net:43\",le=\"0.25\"} 1"));
+        assert!(output.contains("whois_upstream_latency_seconds_bucket{upstream=\"whois.ripe.net:43\",le=\"+Inf\"} 1"));
+        assert!(output.contains("whois_upstream_latency_seconds_count{upstream=\"whois.ripe.net:43\"} 1"));
+    }
+
+    #[test]
+    fn test_escape_label() {
+        assert_eq!(escape_label("a\"b\\c\n"), "a\\\"b\\\\c\\n");
+    }
+}
@@ -0,0 +1,247 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use crate::rpsl::{self, Block};
+
+/// Attributes holding an address range or prefix, in the order they are preferred
+/// Only the first one found in an object is annotated, so ARIN's `CIDR:` does not repeat `NetRange:`
+const NETBLOCK_ATTRIBUTES: &[&str] = &["inetnum", "inet6num", "NetRange", "route", "route6", "CIDR"];
+
+/// Counts from this power of two up are written as `2^n`
+const POWER_NOTATION_BITS: u32 = 32;
+
+/// An inclusive address range of a single family
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Netblock {
+    pub start: IpAddr,
+    pub end: IpAddr,
+}
+
+impl Netblock {
+    /// Parse `193.0.0.0 - 193.0.7.255` or `193.0.0.0/21`
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if let Some((start, end)) = value.split_once('-') {
+            let start: IpAddr = start.trim().parse().ok()?;
+            let end: IpAddr = end.trim().parse().ok()?;
+            let block = Self { start, end };
+            return (start.is_ipv4() == end.is_ipv4() && block.first() <= block.last()).then_some(block);
+        }
+        let (address, length) = value.split_once('/')?;
+        let address: IpAddr = address.trim().parse().ok()?;
+        let length: u32 = length.trim().parse().ok()?;
+        let bits = family_bits(&address);
+        if length > bits {
+            return None;
+        }
+        let host_mask = low_mask(bits - length);
+        let first = to_u128(&address) & !host_mask;
+        Some(Self {
+            start: from_u128(first, address.is_ipv4()),
+            end: from_u128(first | host_mask, address.is_ipv4()),
+        })
+    }
+
+    /// First address as a number, for ordering blocks
+    pub(crate) fn first(&self) -> u128 {
+        to_u128(&self.start)
+    }
+
+    /// Last address as a number
+    pub(crate) fn last(&self) -> u128 {
+        to_u128(&self.end)
+    }
+
+    /// Whether `other` lies entirely inside this block
+    pub fn contains(&self, other: &Netblock) -> bool {
+        self.start.is_ipv4() == other.start.is_ipv4() && self.first() <= other.first() && other.last() <= self.last()
+    }
+
+    /// Number of addresses, `None` only for the whole IPv6 space (2^128)
+    pub fn size(&self) -> Option<u128> {
+        (self.last() - self.first()).checked_add(1)
+    }
+
+    /// Smallest list of CIDR prefixes covering exactly this range
+    pub fn to_cidrs(&self) -> Vec<(IpAddr, u32)> {
+        let bits = family_bits(&self.start);
+        let ipv4 = self.start.is_ipv4();
+        let (mut current, last) = (self.first(), self.last());
+        let mut cidrs = Vec::new();
+        loop {
+            // Largest block aligned at `current` that still fits in the range
+            let aligned = if current == 0 { bits } else { current.trailing_zeros().min(bits) };
+            let remaining = last - current;
+            let fits = match remaining.checked_add(1) {
+                Some(count) => 127 - count.leading_zeros(),
+                None => 128,
+            };
+            let host_bits = aligned.min(fits);
+            cidrs.push((from_u128(current, ipv4), bits - host_bits));
+            match current.checked_add(low_mask(host_bits)) {
+                Some(end) if end < last => current = end + 1,
+                _ => break,
+            }
+        }
+        cidrs
+    }
+
+    /// One-line summary, e.g. `193.0.0.0 - 193.0.7.255 = 193.0.0.0/21 (2048 addresses, 2046 usable)`
+    pub fn describe(&self) -> String {
+        let cidrs = self
+            .to_cidrs()
+            .iter()
+            .map(|(address, length)| format!("{}/{}", address, length))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("{} - {} = {} ({})", self.start, self.end, cidrs, self.size_note())
+    }
+
+    /// Address count with usable hosts (IPv4) or the number of /64 subnets (IPv6)
+    fn size_note(&self) -> String {
+        let bits = family_bits(&self.start);
+        let addresses = format_count(self.size());
+        let prefix = match self.to_cidrs().as_slice() {
+            [(_, length)] => Some(*length),
+            _ => None,
+        };
+        match (self.start, prefix) {
+            (IpAddr::V4(_), Some(length)) => {
+                // /31 point-to-point links use both addresses (RFC 3021)
+                let usable = match length {
+                    32 => 1,
+                    31 => 2,
+                    _ => (1u128 << (bits - length)) - 2,
+                };
+                format!("{} addresses, {} usable", addresses, usable)
+            }
+            (IpAddr::V6(_), Some(length)) if length < 64 => {
+                format!("{} addresses, {} /64 subnets", addresses, format_count(Some(1u128 << (64 - length))))
+            }
+            _ => format!("{} addresses", addresses),
+        }
+    }
+}
+
+/// Append a CIDR/range summary to every inetnum, inet6num, NetRange and route object
+/// Annotations are `%` comment lines, so the result still parses as the original objects
+pub fn annotate(response: &str) -> String {
+    let mut blocks = rpsl::parse(response);
+    let mut changed = false;
+    for block in &mut blocks {
+        let Block::Object(object) = block else {
+            continue;
+        };
+        let netblock = object.attributes.iter().find_map(|attr| {
+            NETBLOCK_ATTRIBUTES
+                .iter()
+                .any(|name| attr.name.eq_ignore_ascii_case(name))
+                .then(|| Netblock::parse(attr.value.split(',').next().unwrap_or_default()))
+                .flatten()
+        });
+        if let Some(netblock) = netblock {
+            object.lines.push(format!("% netblock: {}", netblock.describe()));
+            changed = true;
+        }
+    }
+    if !changed {
+        return response.to_string();
+    }
+    let mut output = rpsl::render(&blocks);
+    if response.ends_with('\n') {
+        output.push('\n');
+    }
+    output
+}
+
+fn family_bits(address: &IpAddr) -> u32 {
+    if address.is_ipv4() {
+        32
+    } else {
+        128
+    }
+}
+
+/// Mask with the lowest `bits` bits set
+fn low_mask(bits: u32) -> u128 {
+    if bits >= 128 {
+        u128::MAX
+    } else {
+        (1u128 << bits) - 1
+    }
+}
+
+fn to_u128(address: &IpAddr) -> u128 {
+    match address {
+        IpAddr::V4(v4) => u128::from(u32::from(*v4)),
+        IpAddr::V6(v6) => u128::from(*v6),
+    }
+}
+
+fn from_u128(value: u128, ipv4: bool) -> IpAddr {
+    if ipv4 {
+        IpAddr::V4(Ipv4Addr::from(value as u32))
+    } else {
+        IpAddr::V6(Ipv6Addr::from(value))
+    }
+}
+
+/// Plain number, or `2^n` for large powers of two such as IPv6 prefix sizes
+fn format_count(count: Option<u128>) -> String {
+    match count {
+        None => "2^128".to_string(),
+        Some(count) if count.is_power_of_two() && count.trailing_zeros() >= POWER_NOTATION_BITS => {
+            format!("2^{}", count.trailing_zeros())
+        }
+        Some(count) => count.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_range_to_single_cidr() {
+        let block = Netblock::parse("193.0.0.0 - 193.0.7.255").unwrap();
+        assert_eq!(block.to_cidrs(), vec![("193.0.0.0".parse().unwrap(), 21)]);
+        assert_eq!(block.describe(), "193.0.0.0 - 193.0.7.255 = 193.0.0.0/21 (2048 addresses, 2046 usable)");
+        assert_eq!(
+            Netblock::parse("192.0.2.0/31").unwrap().describe(),
+            "192.0.2.0 - 192.0.2.1 = 192.0.2.0/31 (2 addresses, 2 usable)"
+        );
+    }
+
+    #[test]
+    fn test_unaligned_range_splits() {
+        let block = Netblock::parse("10.0.0.0 - 10.0.1.127").unwrap();
+        assert_eq!(block.describe(), "10.0.0.0 - 10.0.1.127 = 10.0.0.0/24, 10.0.1.0/25 (384 addresses)");
+        assert_eq!(Netblock::parse("0.0.0.0 - 255.255.255.255").unwrap().to_cidrs().len(), 1);
+        assert!(Netblock::parse("10.0.1.0 - 10.0.0.0").is_none());
+        assert!(Netblock::parse("10.0.0.0 - 2001:db8::").is_none());
+    }
+
+    #[test]
+    fn test_ipv6_prefix() {
+        let block = Netblock::parse("2001:db8::/32").unwrap();
+        assert_eq!(block.end, "2001:db8:ffff:ffff:ffff:ffff:ffff:ffff".parse::<IpAddr>().unwrap());
+        assert_eq!(
+            block.describe(),
+            "2001:db8:: - 2001:db8:ffff:ffff:ffff:ffff:ffff:ffff = 2001:db8::/32 (2^96 addresses, 2^32 /64 subnets)"
+        );
+        assert_eq!(Netblock::parse("::/0").unwrap().size(), None);
+    }
+
+    #[test]
+    fn test_annotate_objects() {
+        let response = "% comment\n\ninetnum:   193.0.0.0 - 193.0.7.255\nnetname:   RIPE-NCC\nsource:    RIPE\n\nperson:    Someone\nsource:    RIPE\n";
+        let annotated = annotate(response);
+        assert!(annotated.contains("source:    RIPE\n% netblock: 193.0.0.0 - 193.0.7.255 = 193.0.0.0/21"));
+        assert_eq!(annotated.matches("% netblock:").count(), 1);
+        assert!(annotated.ends_with('\n'));
+
+        // ARIN answers carry both NetRange and CIDR; only the first is summarized
+        let arin = "NetRange:       8.8.8.0 - 8.8.8.255\nCIDR:           8.8.8.0/24\nNetName:        GOGL\n";
+        assert_eq!(annotate(arin).matches("% netblock:").count(), 1);
+        assert_eq!(annotate("domain: example.com\n"), "domain: example.com\n");
+    }
+}
@@ -0,0 +1,181 @@
+use std::net::IpAddr;
+
+use anyhow::Result;
+
+/// Longest domain name allowed by RFC 1035
+const MAX_DOMAIN_LENGTH: usize = 253;
+/// Longest label allowed by RFC 1035
+const MAX_LABEL_LENGTH: usize = 63;
+
+/// Clean up a target before it is sent to a server
+/// - trims whitespace and strips URL schemes, credentials, ports and paths
+/// - lowercases host names and rewrites IP addresses in canonical form
+/// - normalizes `as3333`, `ASN3333` and `AS 3333` to `AS3333` (and bare numbers with `asn`)
+/// - rejects control characters and malformed host names with an explanation
+///
+/// Queries containing spaces (server flags such as `-i origin AS3333`) and object
+/// handles without dots are passed through as typed.
+pub fn normalize_target(input: &str, asn: bool) -> Result<String> {
+    let target = input.trim();
+    if target.is_empty() {
+        anyhow::bail!("Empty query");
+    }
+    if let Some(c) = target.chars().find(|c| c.is_control()) {
+        anyhow::bail!("Invalid query {:?}: contains control character {:?}", target, c);
+    }
+
+    if let Some(normalized) = normalize_asn(target, asn)? {
+        return Ok(normalized);
+    }
+    if target.contains(char::is_whitespace) {
+        return Ok(target.to_string());
+    }
+
+    let target = strip_url(target);
+    if let Some(normalized) = normalize_ip(target) {
+        return Ok(normalized);
+    }
+    // Handles, emails and other object keys are left for the server to interpret
+    if !target.contains('.') || target.contains('@') {
+        return Ok(target.to_string());
+    }
+    normalize_domain(target)
+}
+
+/// `AS3333`, `as3333`, `ASN3333`, `AS 3333`, asdot `AS1.10`, or a bare number with `asn`
+fn normalize_asn(target: &str, asn: bool) -> Result<Option<String>> {
+    let upper = target.to_uppercase();
+    let digits = match upper.strip_prefix("ASN").or_else(|| upper.strip_prefix("AS")) {
+        Some(rest) => rest.trim_start(),
+        // Bare numbers must be plain, so IPv4 addresses are not mistaken for asdot
+        None if asn && upper.chars().all(|c| c.is_ascii_digit()) => upper.as_str(),
+        None => return Ok(None),
+    };
+    if digits.is_empty() || digits.matches('.').count() > 1 || !digits.chars().all(|c| c.is_ascii_digit() || c == '.') {
+        // e.g. AS-SET names such as AS-EXAMPLE
+        return Ok(None);
+    }
+
+    let number = match digits.split_once('.') {
+        Some((high, low)) => {
+            let (Ok(high), Ok(low)) = (high.parse::<u16>(), low.parse::<u16>()) else {
+                anyhow::bail!("Invalid ASN {:?}: asdot notation needs two numbers up to 65535", target);
+            };
+            (u32::from(high) << 16) | u32::from(low)
+        }
+        None => digits
+            .parse::<u32>()
+            .map_err(|_| anyhow::anyhow!("Invalid ASN {:?}: AS numbers go up to 4294967295", target))?,
+    };
+    Ok(Some(format!("AS{}", number)))
+}
+
+/// Reduce `https://user@example.com:8443/path?q` to `example.com`
+/// Without a scheme only host paths are dropped, so prefixes like `192.0.2.0/24` stay intact
+pub(crate) fn strip_url(target: &str) -> &str {
+    let (has_scheme, rest) = match target.split_once("://") {
+        Some((_, rest)) => (true, rest),
+        None => (false, target),
+    };
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or(rest);
+    if !has_scheme && (authority.len() == rest.len() || authority.parse::<IpAddr>().is_ok() || authority.contains(':')) {
+        return target;
+    }
+    let host = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+    // Bracketed IPv6 literal, with or without a port
+    if let Some(inner) = host.strip_prefix('[') {
+        return inner.split(']').next().unwrap_or(inner);
+    }
+    match host.rsplit_once(':') {
+        Some((name, port)) if !name.contains(':') && port.chars().all(|c| c.is_ascii_digit()) => name,
+        _ => host,
+    }
+}
+
+/// Canonical form of an IP address or prefix (`2001:DB8::/32` → `2001:db8::/32`)
+fn normalize_ip(target: &str) -> Option<String> {
+    let (address, prefix) = match target.split_once('/') {
+        Some((address, prefix)) => (address, Some(prefix)),
+        None => (target, None),
+    };
+    let address: IpAddr = address.parse().ok()?;
+    match prefix {
+        Some(prefix) => {
+            let length: u8 = prefix.parse().ok()?;
+            let max = if address.is_ipv4() { 32 } else { 128 };
+            (length <= max).then(|| format!("{}/{}", address, length))
+        }
+        None => Some(address.to_string()),
+    }
+}
+
+/// Lowercase a host name and check its labels
+fn normalize_domain(target: &str) -> Result<String> {
+    let domain = target.trim_end_matches('.').to_lowercase();
+    if domain.len() > MAX_DOMAIN_LENGTH {
+        anyhow::bail!("Invalid domain name {:?}: longer than {} characters", target, MAX_DOMAIN_LENGTH);
+    }
+    for label in domain.split('.') {
+        if label.is_empty() {
+            anyhow::bail!("Invalid domain name {:?}: empty label (check for repeated or leading dots)", target);
+        }
+        if label.len() > MAX_LABEL_LENGTH {
+            anyhow::bail!("Invalid domain name {:?}: label {:?} is longer than {} characters", target, label, MAX_LABEL_LENGTH);
+        }
+        if label.starts_with('-') || label.ends_with('-') {
+            anyhow::bail!("Invalid domain name {:?}: label {:?} starts or ends with a hyphen", target, label);
+        }
+        // Non-ASCII labels are internationalized names; leave them to the registry
+        if let Some(c) = label.chars().find(|c| c.is_ascii() && !(c.is_ascii_alphanumeric() || *c == '-' || *c == '_')) {
+            anyhow::bail!("Invalid domain name {:?}: unexpected character {:?}", target, c);
+        }
+    }
+    Ok(domain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_asn_forms() {
+        assert_eq!(normalize_target("as3333", false).unwrap(), "AS3333");
+        assert_eq!(normalize_target("ASN3333", false).unwrap(), "AS3333");
+        assert_eq!(normalize_target(" AS 3333 ", false).unwrap(), "AS3333");
+        assert_eq!(normalize_target("AS1.10", false).unwrap(), "AS65546");
+        assert_eq!(normalize_target("3333", true).unwrap(), "AS3333");
+        assert_eq!(normalize_target("3333", false).unwrap(), "3333");
+        assert_eq!(normalize_target("192.0.2.1", true).unwrap(), "192.0.2.1");
+        assert_eq!(normalize_target("AS-EXAMPLE", false).unwrap(), "AS-EXAMPLE");
+        assert!(normalize_target("AS99999999999", false).is_err());
+    }
+
+    #[test]
+    fn test_normalize_urls_and_hosts() {
+        assert_eq!(normalize_target("https://Example.COM/path?q=1", false).unwrap(), "example.com");
+        assert_eq!(normalize_target("http://user@example.com:8080", false).unwrap(), "example.com");
+        assert_eq!(normalize_target("example.com/about", false).unwrap(), "example.com");
+        assert_eq!(normalize_target("Example.com.", false).unwrap(), "example.com");
+        assert_eq!(normalize_target("http://[2001:DB8::1]:80/", false).unwrap(), "2001:db8::1");
+    }
+
+    #[test]
+    fn test_normalize_ip_prefixes() {
+        assert_eq!(normalize_target("192.0.2.0/24", false).unwrap(), "192.0.2.0/24");
+        assert_eq!(normalize_target("2001:DB8:0::/32", false).unwrap(), "2001:db8::/32");
+        assert_eq!(normalize_target("2001:db8::1", false).unwrap(), "2001:db8::1");
+    }
+
+    #[test]
+    fn test_passthrough_and_rejects() {
+        assert_eq!(normalize_target("-i origin AS3333", false).unwrap(), "-i origin AS3333");
+        assert_eq!(normalize_target("RIPE-NCC-HM-MNT", false).unwrap(), "RIPE-NCC-HM-MNT");
+        assert_eq!(normalize_target("noc@example.net", false).unwrap(), "noc@example.net");
+
+        assert!(normalize_target("   ", false).is_err());
+        assert!(normalize_target("example.com\r\nAS1", false).is_err());
+        assert!(normalize_target("exa!mple.com", false).is_err());
+        assert!(normalize_target("example..com", false).is_err());
+        assert!(normalize_target("-example.com", false).is_err());
+    }
+}
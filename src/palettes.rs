@@ -0,0 +1,136 @@
+use anyhow::Result;
+
+/// A built-in flag palette
+pub struct BuiltinPalette {
+    pub name: &'static str,
+    /// Other names accepted for the palette
+    pub aliases: &'static [&'static str],
+    /// Stripes from top to bottom; black stripes are lifted to grey so they stay visible on dark terminals
+    pub colors: &'static [(u8, u8, u8)],
+}
+
+const GREY: (u8, u8, u8) = (0x60, 0x60, 0x60);
+const WHITE: (u8, u8, u8) = (0xff, 0xff, 0xff);
+
+/// Flags selectable with `--pride NAME`
+pub const BUILTIN_PALETTES: &[BuiltinPalette] = &[
+    BuiltinPalette {
+        name: "trans",
+        aliases: &["mtf"],
+        colors: &[(0x5b, 0xcf, 0xfa), (0xf5, 0xab, 0xb9), WHITE, (0xf5, 0xab, 0xb9), (0x5b, 0xcf, 0xfa)],
+    },
+    BuiltinPalette {
+        name: "rainbow",
+        aliases: &["pride"],
+        colors: &[(0xe4, 0x03, 0x03), (0xff, 0x8c, 0x00), (0xff, 0xed, 0x00), (0x00, 0x80, 0x26), (0x24, 0x40, 0x8e), (0x73, 0x29, 0x82)],
+    },
+    BuiltinPalette {
+        name: "bi",
+        aliases: &["bisexual"],
+        colors: &[(0xd6, 0x02, 0x70), (0xd6, 0x02, 0x70), (0x9b, 0x4f, 0x96), (0x00, 0x38, 0xa8), (0x00, 0x38, 0xa8)],
+    },
+    BuiltinPalette {
+        name: "nb",
+        aliases: &["nonbinary", "enby"],
+        colors: &[(0xfc, 0xf4, 0x34), WHITE, (0x9c, 0x59, 0xd1), GREY],
+    },
+    BuiltinPalette {
+        name: "lesbian",
+        aliases: &[],
+        colors: &[(0xd5, 0x2d, 0x00), (0xff, 0x9a, 0x56), WHITE, (0xd3, 0x62, 0xa4), (0xa3, 0x02, 0x62)],
+    },
+    BuiltinPalette {
+        name: "pan",
+        aliases: &["pansexual"],
+        colors: &[(0xff, 0x21, 0x8c), (0xff, 0xd8, 0x00), (0x21, 0xb1, 0xff)],
+    },
+    BuiltinPalette {
+        name: "ace",
+        aliases: &["asexual"],
+        colors: &[GREY, (0xa3, 0xa3, 0xa3), WHITE, (0x80, 0x00, 0x80)],
+    },
+    BuiltinPalette {
+        name: "aro",
+        aliases: &["aromantic"],
+        colors: &[(0x3d, 0xa5, 0x42), (0xa7, 0xd3, 0x79), WHITE, (0xa9, 0xa9, 0xa9), GREY],
+    },
+    BuiltinPalette {
+        name: "genderfluid",
+        aliases: &[],
+        colors: &[(0xff, 0x76, 0xa4), WHITE, (0xc0, 0x11, 0xd7), GREY, (0x2f, 0x3c, 0xbe)],
+    },
+];
+
+/// Stripe colors laid over the field names of an answer, one per non-empty line
+/// Values keep the normal highlighting so fields and values stay apart
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Palette {
+    pub colors: Vec<(u8, u8, u8)>,
+}
+
+impl Palette {
+    /// Trans flag, the palette of `--mtf`
+    pub fn trans() -> Self {
+        Self::builtin("trans").expect("trans palette is built in")
+    }
+
+    /// Built-in palette by name or alias
+    pub fn builtin(name: &str) -> Option<Self> {
+        let name = name.to_lowercase();
+        BUILTIN_PALETTES
+            .iter()
+            .find(|palette| palette.name == name || palette.aliases.contains(&name.as_str()))
+            .map(|palette| Self { colors: palette.colors.to_vec() })
+    }
+
+    /// Palette from `#RRGGBB` strings, as written in the config file
+    pub fn from_hex(colors: &[String]) -> Result<Self> {
+        if colors.is_empty() {
+            anyhow::bail!("A palette needs at least one color");
+        }
+        let colors = colors
+            .iter()
+            .map(|color| {
+                let hex = color.trim().trim_start_matches('#');
+                let channel = |range: std::ops::Range<usize>| hex.get(range).and_then(|part| u8::from_str_radix(part, 16).ok());
+                match (hex.len(), channel(0..2), channel(2..4), channel(4..6)) {
+                    (6, Some(r), Some(g), Some(b)) => Ok((r, g, b)),
+                    _ => anyhow::bail!("Invalid palette color {:?}, expected #RRGGBB", color),
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { colors })
+    }
+}
+
+/// Names of the built-in palettes
+pub fn names() -> Vec<&'static str> {
+    BUILTIN_PALETTES.iter().map(|palette| palette.name).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_palette_from_hex() {
+        let palette = Palette::from_hex(&["#5BCFFA".to_string(), "f5abb9".to_string()]).unwrap();
+        assert_eq!(palette.colors, vec![(91, 207, 250), (245, 171, 185)]);
+        assert!(Palette::from_hex(&["#12345".to_string()]).is_err());
+        assert!(Palette::from_hex(&[]).is_err());
+    }
+
+    #[test]
+    fn test_builtin_names_and_aliases() {
+        assert_eq!(Palette::builtin("MTF"), Some(Palette::trans()));
+        assert_eq!(Palette::builtin("enby"), Palette::builtin("nb"));
+        assert_eq!(Palette::builtin("bi").unwrap().colors.len(), 5);
+        assert_eq!(Palette::builtin("plaid"), None);
+        // Names and aliases are unique
+        let mut all: Vec<&str> = BUILTIN_PALETTES.iter().flat_map(|palette| std::iter::once(palette.name).chain(palette.aliases.iter().copied())).collect();
+        let count = all.len();
+        all.sort();
+        all.dedup();
+        assert_eq!(all.len(), count);
+    }
+}
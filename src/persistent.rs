@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use tracing::debug;
+
+use crate::timing::{reset_error, timed_connect, HopTiming};
+
+/// A connection in RIPE/IRRd persistent mode (`-k`)
+/// In this mode each answer ends with two empty lines and the connection stays open
+pub struct PersistentConnection {
+    address: String,
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    /// Whether `-k` has been sent yet
+    keepalive: bool,
+}
+
+impl PersistentConnection {
+    /// Connect, returning the connection with its DNS and connect times
+    pub fn connect(address: &str, timeout: Duration) -> Result<(Self, Duration, Duration)> {
+        let (stream, dns, connect) = timed_connect(address, timeout)?;
+        let reader = BufReader::new(stream.try_clone().context("Failed to clone WHOIS connection")?);
+        let connection = Self {
+            address: address.to_string(),
+            reader,
+            writer: stream,
+            keepalive: false,
+        };
+        Ok((connection, dns, connect))
+    }
+
+    /// Send one query and read its answer
+    /// Returns the answer, the write time, the time to first byte, and whether the connection is still usable
+    pub fn exchange(&mut self, query: &str) -> Result<(String, Duration, Duration, bool)> {
+        // The first query switches the connection into persistent mode
+        let line = if self.keepalive {
+            format!("{}\r\n", query)
+        } else {
+            format!("-k {}\r\n", query)
+        };
+        self.keepalive = true;
+
+        let write_start = Instant::now();
+        self.writer.write_all(line.as_bytes()).context("Failed to write query to WHOIS server")?;
+        let write = write_start.elapsed();
+
+        let read_start = Instant::now();
+        let mut first_byte = None;
+        let mut response = String::new();
+        let mut empty_run = 0;
+        let mut open = false;
+        loop {
+            let mut line = String::new();
+            let read = self.reader.read_line(&mut line).map_err(|err| reset_error(err, &self.address, response.clone().into_bytes()))?;
+            if read == 0 {
+                break;
+            }
+            first_byte.get_or_insert_with(|| read_start.elapsed());
+            if line.trim_end_matches(['\r', '\n']).is_empty() {
+                empty_run += 1;
+                // Two empty lines after some content end the answer
+                if empty_run == 2 && !response.trim().is_empty() {
+                    open = true;
+                    break;
+                }
+            } else {
+                empty_run = 0;
+            }
+            response.push_str(&line);
+        }
+
+        let mut response = response.trim_end().to_string();
+        if !response.is_empty() {
+            response.push('\n');
+        }
+        Ok((response, write, first_byte.unwrap_or_default(), open))
+    }
+}
+
+/// Idle persistent connections shared by the lookups of a bulk run
+#[derive(Default)]
+pub struct ConnectionPool {
+    idle: Mutex<HashMap<String, Vec<PersistentConnection>>>,
+}
+
+impl ConnectionPool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of idle connections kept for a server
+    pub fn idle_count(&self, address: &str) -> usize {
+        self.idle.lock().map(|idle| idle.get(address).map_or(0, Vec::len)).unwrap_or(0)
+    }
+
+    /// Query a server, reusing an idle connection when there is one
+    /// A reused connection that fails is dropped and the query is retried on a fresh one
+    pub fn query(&self, address: &str, query: &str, timeout: Duration) -> Result<(String, HopTiming)> {
+        let start = Instant::now();
+
+        if let Some(mut connection) = self.checkout(address) {
+            match connection.exchange(query) {
+                // A server that timed out the idle connection just closes it
+                Ok((response, write, first_byte, open)) if open || !response.is_empty() => {
+                    debug!("Reused persistent connection to {}", address);
+                    if open {
+                        self.checkin(connection);
+                    }
+                    let timing = hop(address, Duration::ZERO, Duration::ZERO, write, first_byte, start);
+                    return Ok((response, timing));
+                }
+                Ok(_) => debug!("Persistent connection to {} was closed, reconnecting", address),
+                Err(err) => debug!("Persistent connection to {} failed ({:#}), reconnecting", address, err),
+            }
+        }
+
+        let (mut connection, dns, connect) = PersistentConnection::connect(address, timeout)?;
+        let (response, write, first_byte, open) = connection.exchange(query)?;
+        if open {
+            self.checkin(connection);
+        } else {
+            debug!("{} closed the connection, persistent mode not supported", address);
+        }
+        Ok((response, hop(address, dns, connect, write, first_byte, start)))
+    }
+
+    fn checkout(&self, address: &str) -> Option<PersistentConnection> {
+        self.idle.lock().ok()?.get_mut(address)?.pop()
+    }
+
+    fn checkin(&self, connection: PersistentConnection) {
+        if let Ok(mut idle) = self.idle.lock() {
+            idle.entry(connection.address.clone()).or_default().push(connection);
+        }
+    }
+}
+
+fn hop(address: &str, dns: Duration, connect: Duration, write: Duration, first_byte: Duration, start: Instant) -> HopTiming {
+    HopTiming {
+        server: address.to_string(),
+        probe: None,
+        dns,
+        connect,
+        write,
+        first_byte,
+        total: start.elapsed(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::thread;
+
+    #[test]
+    fn test_queries_share_one_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let server = thread::spawn(move || {
+            // Only one connection is accepted; a second connect would hang the test
+            let (mut socket, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(socket.try_clone().unwrap());
+            for (expected, answer) in [("-k AS1\r\n", "aut-num: AS1\n"), ("AS2\r\n", "aut-num: AS2\n\nremarks: x\n")] {
+                let mut request = String::new();
+                reader.read_line(&mut request).unwrap();
+                assert_eq!(request, expected);
+                socket.write_all(format!("{}\n\n", answer).as_bytes()).unwrap();
+            }
+        });
+
+        let pool = ConnectionPool::new();
+        let (first, first_timing) = pool.query(&address, "AS1", Duration::from_secs(5)).unwrap();
+        let (second, second_timing) = pool.query(&address, "AS2", Duration::from_secs(5)).unwrap();
+        server.join().unwrap();
+
+        assert_eq!(first, "aut-num: AS1\n");
+        assert_eq!(second, "aut-num: AS2\n\nremarks: x\n");
+        assert_eq!(pool.idle_count(&address), 1);
+        assert!(first_timing.total >= first_timing.connect);
+        assert_eq!(second_timing.connect, Duration::ZERO);
+    }
+
+    #[test]
+    fn test_server_closing_is_not_pooled() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let server = thread::spawn(move || {
+            let (mut socket, _) = listener.accept().unwrap();
+            let mut request = String::new();
+            BufReader::new(socket.try_clone().unwrap()).read_line(&mut request).unwrap();
+            socket.write_all(b"aut-num: AS1\n").unwrap();
+        });
+
+        let pool = ConnectionPool::new();
+        let (response, _) = pool.query(&address, "AS1", Duration::from_secs(5)).unwrap();
+        server.join().unwrap();
+        assert_eq!(response, "aut-num: AS1\n");
+        assert_eq!(pool.idle_count(&address), 0);
+    }
+}
@@ -0,0 +1,263 @@
+use std::io::Write;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use tracing::debug;
+
+use crate::colorize::Tone;
+use crate::tr;
+
+const SIGNED_MESSAGE: &str = "-----BEGIN PGP SIGNED MESSAGE-----";
+const SIGNATURE_BEGIN: &str = "-----BEGIN PGP SIGNATURE-----";
+const SIGNATURE_END: &str = "-----END PGP SIGNATURE-----";
+
+/// Trust settings for `--verify`, the `[pgp]` table of the config
+///
+/// ```toml
+/// [pgp]
+/// verify = true
+/// keyring = "~/.config/whois/dn42.gpg"
+/// trusted_keys = ["0123456789ABCDEF0123456789ABCDEF01234567"]
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct PgpConfig {
+    /// Always verify signed responses, as with `--verify`
+    pub verify: bool,
+    /// Keyring used instead of the default gpg keyring
+    pub keyring: Option<PathBuf>,
+    /// Fingerprints (or long key IDs) whose signatures count as verified;
+    /// when empty, keys gpg trusts fully or ultimately do
+    pub trusted_keys: Vec<String>,
+}
+
+/// Outcome of checking the signature of a response
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verification {
+    /// Good signature from a trusted key; `unsigned_lines` counts the data lines of the
+    /// response outside the signed part, which the signature says nothing about
+    Verified { fingerprint: String, unsigned_lines: usize },
+    /// Good signature from a key that is not trusted, expired or revoked
+    Untrusted { fingerprint: String },
+    /// The signature does not match the signed text
+    Bad { key: String },
+    /// The signing key is not in the keyring
+    MissingKey { key: String },
+    /// No inline or detached signature in the response
+    Unsigned,
+    /// gpg could not be run or gave no answer
+    Failed(String),
+}
+
+impl Verification {
+    pub fn is_verified(&self) -> bool {
+        matches!(self, Verification::Verified { .. })
+    }
+
+    pub fn tone(&self) -> Tone {
+        match self {
+            Verification::Verified { unsigned_lines: 0, .. } => Tone::Good,
+            Verification::Bad { .. } => Tone::Bad,
+            _ => Tone::Warning,
+        }
+    }
+
+    /// `%` comment line shown after the response
+    pub fn render(&self) -> String {
+        match self {
+            Verification::Verified { fingerprint, unsigned_lines: 0 } => format!("% {}", tr!("pgp-verified", fingerprint = fingerprint)),
+            Verification::Verified { fingerprint, unsigned_lines } => {
+                format!("% {}", tr!("pgp-verified-partial", fingerprint = fingerprint, lines = *unsigned_lines))
+            }
+            Verification::Untrusted { fingerprint } => format!("% {}", tr!("pgp-untrusted", fingerprint = fingerprint)),
+            Verification::Bad { key } => format!("% {}", tr!("pgp-bad", key = key)),
+            Verification::MissingKey { key } => format!("% {}", tr!("pgp-missing-key", key = key)),
+            Verification::Unsigned => format!("% {}", tr!("pgp-unsigned")),
+            Verification::Failed(reason) => format!("% {}", tr!("pgp-failed", reason = reason)),
+        }
+    }
+}
+
+/// Signed part of a response
+#[derive(Debug, PartialEq, Eq)]
+enum Signed<'a> {
+    /// Clearsigned message, from `BEGIN PGP SIGNED MESSAGE` to the end of the signature
+    Inline(&'a str),
+    /// Armored signature at the end of the response, covering all text before it
+    Detached { data: &'a str, signature: &'a str },
+}
+
+/// Check an inline (clearsigned) or trailing detached signature with gpg
+pub fn verify(response: &str, config: &PgpConfig) -> Verification {
+    let Some((signed, span)) = find_signed(response) else {
+        return Verification::Unsigned;
+    };
+    let mut verification = match run_gpg(&signed, config) {
+        Ok(status) => evaluate(&status, &config.trusted_keys),
+        Err(err) => Verification::Failed(format!("{:#}", err)),
+    };
+    if let Verification::Verified { unsigned_lines, .. } = &mut verification {
+        *unsigned_lines = count_unsigned_lines(response, span);
+    }
+    verification
+}
+
+/// Data lines outside the signed part of a response; blank lines and `%` or `#` comments do not count
+fn count_unsigned_lines(response: &str, signed: Range<usize>) -> usize {
+    [&response[..signed.start], &response[signed.end..]]
+        .iter()
+        .flat_map(|text| text.lines())
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('%') && !line.starts_with('#'))
+        .count()
+}
+
+/// The signed part of a response and where it is, signature included
+fn find_signed(response: &str) -> Option<(Signed<'_>, Range<usize>)> {
+    let line_start = |marker: &str| {
+        response.match_indices(marker).map(|(index, _)| index).find(|&index| index == 0 || response[..index].ends_with('\n'))
+    };
+    let begin = line_start(SIGNATURE_BEGIN)?;
+    let end = begin + response[begin..].find(SIGNATURE_END)? + SIGNATURE_END.len();
+    match line_start(SIGNED_MESSAGE).filter(|&start| start < begin) {
+        Some(start) => Some((Signed::Inline(&response[start..end]), start..end)),
+        None => Some((Signed::Detached { data: &response[..begin], signature: &response[begin..end] }, 0..end)),
+    }
+}
+
+/// `[GNUPG:]` status lines of `gpg --verify`
+fn run_gpg(signed: &Signed, config: &PgpConfig) -> Result<String> {
+    let mut command = Command::new("gpg");
+    command.args(["--batch", "--no-tty", "--status-fd", "1"]);
+    if let Some(keyring) = &config.keyring {
+        command.arg("--no-default-keyring").arg("--keyring").arg(keyring);
+    }
+    let (input, signature_file) = match signed {
+        Signed::Inline(message) => {
+            command.args(["--verify", "-"]);
+            (*message, None)
+        }
+        Signed::Detached { data, signature } => {
+            // Created with a random name and mode 0600, removed when dropped
+            let mut file = tempfile::Builder::new()
+                .prefix("whois-signature-")
+                .suffix(".asc")
+                .tempfile()
+                .context("Failed to create a file for the signature")?;
+            file.write_all(signature.as_bytes()).context("Failed to write the signature")?;
+            command.arg("--verify").arg(file.path()).arg("-");
+            (*data, Some(file))
+        }
+    };
+
+    debug!("Verifying PGP signature with {:?}", command);
+    let output = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("gpg is not installed")
+        .and_then(|mut child| {
+            if let Some(mut stdin) = child.stdin.take() {
+                stdin.write_all(input.as_bytes()).context("Failed to pass the response to gpg")?;
+            }
+            child.wait_with_output().context("gpg did not finish")
+        });
+    drop(signature_file);
+    let output = output?;
+    debug!("gpg: {}", String::from_utf8_lossy(&output.stderr).trim());
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Verification from gpg status lines and the configured trusted keys
+fn evaluate(status: &str, trusted_keys: &[String]) -> Verification {
+    let mut fingerprint = None;
+    let mut trusted_by_gpg = false;
+    let mut expired_or_revoked = false;
+    for line in status.lines() {
+        let mut words = line.split_whitespace().skip_while(|word| *word == "[GNUPG:]");
+        let keyword = words.next().unwrap_or("");
+        let key = words.next().unwrap_or("").to_string();
+        match keyword {
+            "BADSIG" => return Verification::Bad { key },
+            "NO_PUBKEY" | "ERRSIG" => return Verification::MissingKey { key },
+            "EXPKEYSIG" | "REVKEYSIG" => expired_or_revoked = true,
+            "VALIDSIG" => fingerprint = Some(key),
+            "TRUST_FULLY" | "TRUST_ULTIMATE" => trusted_by_gpg = true,
+            _ => {}
+        }
+    }
+    let Some(fingerprint) = fingerprint else {
+        return Verification::Failed("gpg found no valid signature".to_string());
+    };
+    let trusted = if trusted_keys.is_empty() {
+        trusted_by_gpg
+    } else {
+        trusted_keys.iter().any(|key| {
+            let key = normalize_key(key);
+            // Short key IDs are too easy to collide with
+            key.len() >= 16 && fingerprint.to_uppercase().ends_with(&key)
+        })
+    };
+    if trusted && !expired_or_revoked {
+        Verification::Verified { fingerprint, unsigned_lines: 0 }
+    } else {
+        Verification::Untrusted { fingerprint }
+    }
+}
+
+/// Fingerprint as gpg prints it in status lines: upper case, no spaces or `0x`
+fn normalize_key(key: &str) -> String {
+    let key: String = key.chars().filter(|c| !c.is_whitespace()).collect();
+    key.trim_start_matches("0x").trim_start_matches("0X").to_uppercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FINGERPRINT: &str = "0123456789ABCDEF0123456789ABCDEF01234567";
+
+    #[test]
+    fn test_find_signed() {
+        let inline = "% comment\n-----BEGIN PGP SIGNED MESSAGE-----\nHash: SHA256\n\naut-num: AS4242420000\n\
+                      -----BEGIN PGP SIGNATURE-----\n\niQEz\n-----END PGP SIGNATURE-----\n";
+        let Some((Signed::Inline(message), span)) = find_signed(inline) else {
+            panic!("clearsigned message not found");
+        };
+        assert!(message.starts_with(SIGNED_MESSAGE) && message.ends_with(SIGNATURE_END));
+        assert_eq!(count_unsigned_lines(inline, span), 0);
+        let injected = format!("aut-num: AS4242420001\nmnt-by: EVIL-MNT\n{}remarks: after\n", inline);
+        let (_, span) = find_signed(&injected).unwrap();
+        assert_eq!(count_unsigned_lines(&injected, span), 3);
+
+        let detached = "aut-num: AS4242420000\n-----BEGIN PGP SIGNATURE-----\n\niQEz\n-----END PGP SIGNATURE-----\n";
+        assert_eq!(
+            find_signed(detached),
+            Some((Signed::Detached { data: "aut-num: AS4242420000\n", signature: &detached[22..detached.len() - 1] }, 0..detached.len() - 1))
+        );
+        assert_eq!(find_signed("aut-num: AS4242420000\n"), None);
+        assert_eq!(find_signed("remarks: -----BEGIN PGP SIGNATURE-----\n"), None);
+    }
+
+    #[test]
+    fn test_evaluate_status() {
+        let good = format!("[GNUPG:] GOODSIG 0123456789ABCDEF dn42\n[GNUPG:] VALIDSIG {} 2024-01-01 1704067200\n", FINGERPRINT);
+        assert_eq!(evaluate(&good, &[]), Verification::Untrusted { fingerprint: FINGERPRINT.to_string() });
+        assert!(evaluate(&format!("{}[GNUPG:] TRUST_ULTIMATE 0 pgp\n", good), &[]).is_verified());
+        assert!(evaluate(&good, &["0x0123 4567 89AB CDEF 0123 4567 89ab cdef 0123 4567".to_string()]).is_verified());
+        assert!(evaluate(&good, &["89abcdef01234567".to_string()]).is_verified());
+        assert!(!evaluate(&good, &["01234567".to_string()]).is_verified());
+        assert!(!evaluate(&good.replace("GOODSIG", "EXPKEYSIG"), &[FINGERPRINT.to_string()]).is_verified());
+
+        assert_eq!(evaluate("[GNUPG:] BADSIG 0123456789ABCDEF dn42\n", &[]), Verification::Bad { key: "0123456789ABCDEF".to_string() });
+        assert_eq!(
+            evaluate("[GNUPG:] ERRSIG 0123456789ABCDEF 1 8 01 1704067200 9 -\n[GNUPG:] NO_PUBKEY 0123456789ABCDEF\n", &[]),
+            Verification::MissingKey { key: "0123456789ABCDEF".to_string() }
+        );
+        assert!(matches!(evaluate("", &[]), Verification::Failed(_)));
+    }
+}
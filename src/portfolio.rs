@@ -0,0 +1,166 @@
+use anyhow::Result;
+use tracing::{info, warn};
+
+use crate::i18n::pad;
+use crate::query::WhoisQuery;
+use crate::rpsl::{self, Block, RpslObject};
+use crate::servers::WhoisServer;
+use crate::tr;
+
+/// Object types that make up an organisation's resources
+const RESOURCE_TYPES: &str = "inetnum,inet6num,aut-num";
+
+/// Maintainers shared by every resource a registry hands out; inverse queries on them would list the whole region
+const REGISTRY_MAINTAINER_PREFIXES: &[&str] = &["RIPE-NCC-", "APNIC-", "AFRINIC-", "ARIN-", "LACNIC-"];
+
+/// A resource held by an organisation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Resource {
+    pub class: String,
+    pub key: String,
+    /// `netname:` or `as-name:`
+    pub name: Option<String>,
+    /// How the resource was found: `org` or `mnt-by <MNT>`
+    pub via: String,
+}
+
+/// Whether a maintainer belongs to a registry rather than to the holder of a resource
+pub fn is_registry_maintainer(name: &str) -> bool {
+    let upper = name.to_uppercase();
+    REGISTRY_MAINTAINER_PREFIXES.iter().any(|prefix| upper.starts_with(prefix))
+}
+
+/// Maintainers of an organisation worth an inverse `mnt-by` query: its own `mnt-ref` and `mnt-by`
+pub fn own_maintainers(organisation: &RpslObject) -> Vec<String> {
+    let mut maintainers: Vec<String> = Vec::new();
+    for name in organisation.values("mnt-ref").chain(organisation.values("mnt-by")) {
+        if is_registry_maintainer(name) {
+            continue;
+        }
+        let upper = name.to_uppercase();
+        if !maintainers.contains(&upper) {
+            maintainers.push(upper);
+        }
+    }
+    maintainers
+}
+
+/// Resources in an inverse-query answer
+fn resources_in(response: &str, via: &str) -> Vec<Resource> {
+    rpsl::parse(response)
+        .into_iter()
+        .filter_map(|block| match block {
+            Block::Object(object) => Some(object),
+            Block::Text(_) => None,
+        })
+        .filter(|object| RESOURCE_TYPES.split(',').any(|class| object.class().eq_ignore_ascii_case(class)))
+        .map(|object| Resource {
+            class: object.class().to_lowercase(),
+            key: object.key().to_string(),
+            name: object.get("netname").or_else(|| object.get("as-name")).map(str::to_string),
+            via: via.to_string(),
+        })
+        .collect()
+}
+
+/// Merge resources found by several queries, keeping the first way each was found
+pub fn merge(lists: Vec<Vec<Resource>>) -> Vec<Resource> {
+    let mut merged: Vec<Resource> = Vec::new();
+    for resource in lists.into_iter().flatten() {
+        if !merged.iter().any(|known| known.class == resource.class && known.key == resource.key) {
+            merged.push(resource);
+        }
+    }
+    // aut-num first, then address blocks
+    merged.sort_by_key(|resource| match resource.class.as_str() {
+        "aut-num" => 0,
+        "inetnum" => 1,
+        _ => 2,
+    });
+    merged
+}
+
+/// All inetnum, inet6num and aut-num objects referencing an organisation or maintained by its maintainers
+/// Uses RIPE-style inverse queries (`-i org`, `-i mnt-by`); a failing maintainer query is skipped with a warning
+pub fn lookup(query: &WhoisQuery, server: &WhoisServer, handle: &str) -> Result<Vec<Resource>> {
+    let response = query.query_direct(&format!("-r -T organisation {}", handle), server)?;
+    let organisation = rpsl::parse(&response).into_iter().find_map(|block| match block {
+        Block::Object(object) if object.class().eq_ignore_ascii_case("organisation") => Some(object),
+        _ => None,
+    });
+    let Some(organisation) = organisation else {
+        anyhow::bail!("Organisation {} not found on {}", handle, server.host);
+    };
+
+    let inverse = |attribute: &str, value: &str| {
+        query.query_direct(&format!("-r -T {} -i {} {}", RESOURCE_TYPES, attribute, value), server)
+    };
+    let mut lists = vec![resources_in(&inverse("org", handle)?, "org")];
+    for maintainer in own_maintainers(&organisation) {
+        info!("Listing resources maintained by {}", maintainer);
+        match inverse("mnt-by", &maintainer) {
+            Ok(response) => lists.push(resources_in(&response, &format!("mnt-by {}", maintainer))),
+            Err(err) => warn!("Inverse query for {} failed: {:#}", maintainer, err),
+        }
+    }
+    Ok(merge(lists))
+}
+
+/// Resource table as `%` comment lines
+pub fn render(handle: &str, resources: &[Resource]) -> String {
+    let mut lines = vec![
+        format!("% ─── {} ───", tr!("portfolio-heading", handle = handle, count = resources.len())),
+        format!(
+            "% {} {} {} {}",
+            pad(&tr!("portfolio-type"), 9),
+            pad(&tr!("portfolio-resource"), 40),
+            pad(&tr!("portfolio-name"), 24),
+            tr!("portfolio-via")
+        ),
+    ];
+    lines.extend(resources.iter().map(|resource| {
+        format!(
+            "% {:<9} {:<40} {:<24} {}",
+            resource.class,
+            resource.key,
+            resource.name.as_deref().unwrap_or("-"),
+            resource.via
+        )
+    }));
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_own_maintainers_skip_registry() {
+        let blocks = rpsl::parse("organisation: ORG-EX1-RIPE\nmnt-ref: EXAMPLE-MNT\nmnt-by: RIPE-NCC-HM-MNT\nmnt-by: example-mnt\nmnt-by: OTHER-MNT\n");
+        let Block::Object(organisation) = &blocks[0] else {
+            panic!("expected an object");
+        };
+        assert_eq!(own_maintainers(organisation), vec!["EXAMPLE-MNT", "OTHER-MNT"]);
+    }
+
+    #[test]
+    fn test_merge_resources() {
+        let by_org = resources_in(
+            "inetnum: 192.0.2.0 - 192.0.2.255\nnetname: EXAMPLE-NET\n\naut-num: AS64500\nas-name: EXAMPLE-AS\n",
+            "org",
+        );
+        let by_mnt = resources_in(
+            "aut-num: AS64500\nas-name: EXAMPLE-AS\n\ninet6num: 2001:db8::/32\nnetname: EXAMPLE-V6\n\nroute: 192.0.2.0/24\n",
+            "mnt-by EXAMPLE-MNT",
+        );
+        let merged = merge(vec![by_org, by_mnt]);
+        assert_eq!(merged.len(), 3);
+        assert_eq!(merged[0].key, "AS64500");
+        assert_eq!(merged[0].via, "org");
+        assert_eq!(merged[2].via, "mnt-by EXAMPLE-MNT");
+
+        let table = render("ORG-EX1-RIPE", &merged);
+        assert!(table.starts_with("% ─── Resources of ORG-EX1-RIPE (3) ───"));
+        assert!(table.contains("% aut-num   AS64500"));
+    }
+}
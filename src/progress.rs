@@ -0,0 +1,116 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+
+use crate::query::ProgressCallback;
+
+/// Spinner refresh interval
+const TICK_INTERVAL_MS: u64 = 100;
+
+/// Progress display for one or more running queries
+/// Draws to stderr; when disabled every operation is a no-op
+pub struct QueryProgress {
+    multi: MultiProgress,
+    overall: Option<ProgressBar>,
+}
+
+/// Spinner tracking a single target
+pub struct TargetProgress {
+    spinner: ProgressBar,
+    overall: Option<ProgressBar>,
+}
+
+impl QueryProgress {
+    /// Create a progress display for the given number of targets
+    pub fn new(total_targets: usize, enabled: bool) -> Self {
+        let draw_target = if enabled {
+            ProgressDrawTarget::stderr()
+        } else {
+            ProgressDrawTarget::hidden()
+        };
+        let multi = MultiProgress::with_draw_target(draw_target);
+
+        // Only bulk runs get an overall bar
+        let overall = if total_targets > 1 {
+            let bar = multi.add(ProgressBar::new(total_targets as u64));
+            bar.set_style(
+                ProgressStyle::with_template("{bar:30.cyan/blue} {pos}/{len} targets ({elapsed})")
+                    .unwrap_or_else(|_| ProgressStyle::default_bar()),
+            );
+            Some(bar)
+        } else {
+            None
+        };
+
+        Self { multi, overall }
+    }
+
+    /// Start a spinner for a target
+    pub fn start_target(&self, target: &str) -> TargetProgress {
+        let spinner = self.multi.add(ProgressBar::new_spinner());
+        spinner.set_style(
+            ProgressStyle::with_template("{spinner:.green} {prefix:.bold} {msg}")
+                .unwrap_or_else(|_| ProgressStyle::default_spinner()),
+        );
+        spinner.set_prefix(target.to_string());
+        spinner.set_message("starting");
+        spinner.enable_steady_tick(Duration::from_millis(TICK_INTERVAL_MS));
+
+        TargetProgress {
+            spinner,
+            overall: self.overall.clone(),
+        }
+    }
+
+    /// Remove all progress output
+    pub fn finish(&self) {
+        if let Some(overall) = &self.overall {
+            overall.finish_and_clear();
+        }
+        let _ = self.multi.clear();
+    }
+}
+
+impl TargetProgress {
+    /// Callback for the query engine to report the current server and referral hops
+    pub fn callback(&self) -> ProgressCallback {
+        let spinner = self.spinner.clone();
+        Arc::new(move |message: &str| spinner.set_message(message.to_string()))
+    }
+
+    /// Mark the target as done
+    pub fn finish(self) {
+        self.spinner.finish_and_clear();
+        if let Some(overall) = &self.overall {
+            overall.inc(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hidden_progress_tracks_targets() {
+        let progress = QueryProgress::new(3, false);
+        let overall = progress.overall.clone().unwrap();
+
+        for target in ["example.com", "AS3333", "8.8.8.8"] {
+            let target_progress = progress.start_target(target);
+            (target_progress.callback())("Querying whois.ripe.net:43");
+            assert_eq!(target_progress.spinner.message(), "Querying whois.ripe.net:43");
+            target_progress.finish();
+        }
+
+        assert_eq!(overall.position(), 3);
+        progress.finish();
+    }
+
+    #[test]
+    fn test_single_target_has_no_overall_bar() {
+        let progress = QueryProgress::new(1, false);
+        assert!(progress.overall.is_none());
+    }
+}
@@ -1,7 +1,22 @@
+use std::collections::HashMap;
+#[cfg(feature = "net")]
 use std::io::{Read, Write};
-use std::net::TcpStream;
+use std::sync::Mutex;
+#[cfg(feature = "net")]
 use std::time::Duration;
+#[cfg(feature = "net")]
+use std::time::Instant;
+#[cfg(feature = "net")]
 use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+#[cfg(feature = "net")]
+use tracing::{instrument, trace, Span};
+use crate::compression::SUPPORTED_ENCODINGS;
+#[cfg(feature = "net")]
+use crate::compression::MAX_RESPONSE_BYTES;
+#[cfg(feature = "net")]
+use crate::timing::{timed_connect, timed_exchange, HopTiming};
 
 /// WHOIS-COLOR Protocol v1.1
 /// A backward-compatible extension protocol for server-side colorization,
@@ -9,7 +24,7 @@ use anyhow::{Context, Result};
 pub struct WhoisColorProtocol;
 
 /// Server capability information
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ServerCapabilities {
     pub supports_color: bool,
     pub color_schemes: Vec<String>,
@@ -17,6 +32,43 @@ pub struct ServerCapabilities {
     pub supports_markdown: bool,
     pub supports_images: bool,
     pub image_formats: Vec<String>,
+    /// Response compressions the server can apply (v1.2 extension)
+    pub encodings: Vec<String>,
+    /// Server can split large answers into pages (v1.2 extension)
+    #[serde(default)]
+    pub supports_paging: bool,
+    /// Token schemes accepted in `X-WHOIS-AUTH`, e.g. `bearer` (v1.2 extension)
+    #[serde(default)]
+    pub auth_schemes: Vec<String>,
+    /// Server refuses queries without a token
+    #[serde(default)]
+    pub auth_required: bool,
+}
+
+/// Storage for probe results, so a server is not probed before every query
+/// Implementations decide how long an entry stays valid
+pub trait CapabilityCache: Send + Sync {
+    fn get(&self, address: &str) -> Option<ServerCapabilities>;
+    fn put(&self, address: &str, capabilities: &ServerCapabilities);
+}
+
+/// Capability cache living as long as the process, for sessions such as `whois repl`
+#[derive(Debug, Default)]
+pub struct MemoryCapabilityCache {
+    entries: Mutex<HashMap<String, ServerCapabilities>>,
+}
+
+impl CapabilityCache for MemoryCapabilityCache {
+    fn get(&self, address: &str) -> Option<ServerCapabilities> {
+        self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).get(address).cloned()
+    }
+
+    fn put(&self, address: &str, capabilities: &ServerCapabilities) {
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(address.to_string(), capabilities.clone());
+    }
 }
 
 impl Default for ServerCapabilities {
@@ -28,6 +80,10 @@ impl Default for ServerCapabilities {
             supports_markdown: false,
             supports_images: false,
             image_formats: vec![],
+            encodings: vec![],
+            supports_paging: false,
+            auth_schemes: vec![],
+            auth_required: false,
         }
     }
 }
@@ -39,31 +95,54 @@ pub const CAPABILITY_PROBE: &str = "X-WHOIS-COLOR-PROBE: v1.1\r\n";
 pub const COLOR_REQUEST_PREFIX: &str = "X-WHOIS-COLOR: ";
 pub const MARKDOWN_REQUEST_PREFIX: &str = "X-WHOIS-MARKDOWN: ";
 pub const IMAGE_REQUEST_PREFIX: &str = "X-WHOIS-IMAGES: ";
+pub const ACCEPT_ENCODING_PREFIX: &str = "X-WHOIS-ACCEPT-ENCODING: ";
+pub const CONTENT_ENCODING_PREFIX: &str = "X-WHOIS-CONTENT-ENCODING: ";
+/// Short spelling of `X-WHOIS-CONTENT-ENCODING` used by some servers
+pub const ENCODING_PREFIX: &str = "X-WHOIS-ENCODING: ";
+pub const AUTH_REQUEST_PREFIX: &str = "X-WHOIS-AUTH: ";
+pub const PAGE_REQUEST_PREFIX: &str = "X-WHOIS-PAGE: ";
+pub const CONTINUATION_PREFIX: &str = "X-WHOIS-CONTINUATION: ";
+/// First line of an answer telling whether the server formatted it as Markdown
+pub const MARKDOWN_APPLIED_PREFIX: &str = "X-WHOIS-MARKDOWN-APPLIED: ";
+/// Lines around a Markdown section inside an otherwise plain answer
+pub const MARKDOWN_BEGIN_LINE: &str = "X-WHOIS-MARKDOWN-BEGIN";
+pub const MARKDOWN_END_LINE: &str = "X-WHOIS-MARKDOWN-END";
+/// Page token of the first request of a paged query
+pub const FIRST_PAGE: &str = "first";
+/// Most pages reassembled for one answer
+pub const MAX_PAGES: usize = 256;
 pub const CAPABILITY_RESPONSE_PREFIX: &str = "X-WHOIS-COLOR-SUPPORT: ";
 pub const CAPABILITY_TIMEOUT_MS: u64 = 2000; // 2 seconds for capability probe
+/// Most bytes read in answer to a capability probe
+#[cfg(feature = "net")]
+const MAX_PROBE_RESPONSE: u64 = 64 * 1024;
+/// Longer capability lines are ignored rather than parsed
+const MAX_CAPABILITY_LINE: usize = 4096;
 
 impl WhoisColorProtocol {
     /// Probe server for color protocol support
     /// This method sends a capability probe and waits for a response
     /// If no response or timeout, assumes standard WHOIS server
+    #[cfg(feature = "net")]
     pub fn probe_capabilities(
         &self, 
         server_address: &str,
-        verbose: bool
     ) -> Result<ServerCapabilities> {
-        if verbose {
-            println!("Probing color capabilities for: {}", server_address);
-        }
+        self.probe_capabilities_within(server_address, Duration::from_millis(CAPABILITY_TIMEOUT_MS))
+    }
+
+    /// Same as `probe_capabilities`, waiting at most `timeout` for the connection and each read
+    #[instrument(name = "probe", skip(self))]
+    #[cfg(feature = "net")]
+    pub fn probe_capabilities_within(
+        &self,
+        server_address: &str,
+        timeout: Duration,
+    ) -> Result<ServerCapabilities> {
+        debug!("Probing color capabilities for: {}", server_address);
 
-        let mut stream = TcpStream::connect(server_address)
+        let (mut stream, _, _) = timed_connect(server_address, timeout)
             .with_context(|| format!("Cannot connect to server for capability probe: {}", server_address))?;
-        
-        // Set shorter timeout for capability probe
-        stream.set_read_timeout(Some(Duration::from_millis(CAPABILITY_TIMEOUT_MS)))
-            .context("Failed to set read timeout for capability probe")?;
-        
-        stream.set_write_timeout(Some(Duration::from_millis(CAPABILITY_TIMEOUT_MS)))
-            .context("Failed to set write timeout for capability probe")?;
 
         // Send capability probe
         // Format: "X-WHOIS-COLOR-PROBE: v1.0\r\n\r\n"
@@ -71,27 +150,21 @@ impl WhoisColorProtocol {
         
         if let Err(_) = stream.write_all(probe_query.as_bytes()) {
             // If write fails, assume standard WHOIS server
-            if verbose {
-                println!("Capability probe write failed, assuming standard WHOIS");
-            }
+            debug!("Capability probe write failed, assuming standard WHOIS");
             return Ok(ServerCapabilities::default());
         }
 
         // Try to read response
         let mut response = String::new();
-        match stream.read_to_string(&mut response) {
+        match (&mut stream).take(MAX_PROBE_RESPONSE).read_to_string(&mut response) {
             Ok(_) => {
                 let capabilities = self.parse_capability_response(&response);
-                if verbose {
-                    println!("Server capabilities: {:?}", capabilities);
-                }
+                debug!("Server capabilities: {:?}", capabilities);
                 Ok(capabilities)
             }
             Err(_) => {
                 // Timeout or read error - assume standard WHOIS server
-                if verbose {
-                    println!("No capability response, assuming standard WHOIS");
-                }
+                debug!("No capability response, assuming standard WHOIS");
                 Ok(ServerCapabilities::default())
             }
         }
@@ -100,10 +173,11 @@ impl WhoisColorProtocol {
     /// Parse capability response from server
     /// Expected format: "X-WHOIS-COLOR-SUPPORT: v1.1 schemes=ripe,bgptools,mtf markdown=true images=png,jpg\r\n"
     /// Legacy format: "X-WHOIS-COLOR-SUPPORT: v1.0 schemes=ripe,bgptools,mtf\r\n"
-    fn parse_capability_response(&self, response: &str) -> ServerCapabilities {
+    /// v1.2 servers may add "encoding=gzip,deflate"
+    pub fn parse_capability_response(&self, response: &str) -> ServerCapabilities {
         for line in response.lines() {
             let line = line.trim();
-            if line.starts_with(CAPABILITY_RESPONSE_PREFIX) {
+            if line.starts_with(CAPABILITY_RESPONSE_PREFIX) && line.len() <= MAX_CAPABILITY_LINE {
                 return self.parse_capability_line(&line[CAPABILITY_RESPONSE_PREFIX.len()..]);
             }
         }
@@ -127,6 +201,10 @@ impl WhoisColorProtocol {
             supports_markdown: false,
             supports_images: false,
             image_formats: vec![],
+            encodings: vec![],
+            supports_paging: false,
+            auth_schemes: vec![],
+            auth_required: false,
         };
 
         // Parse additional parameters
@@ -146,6 +224,22 @@ impl WhoisColorProtocol {
                     .map(|s| s.trim().to_string())
                     .filter(|s| !s.is_empty())
                     .collect();
+            } else if let Some(encoding_part) = part.strip_prefix("encoding=") {
+                capabilities.encodings = encoding_part
+                    .split(',')
+                    .map(|s| s.trim().to_lowercase())
+                    .filter(|s| !s.is_empty())
+                    .collect();
+            } else if let Some(paging_part) = part.strip_prefix("paging=") {
+                capabilities.supports_paging = paging_part == "true";
+            } else if let Some(auth_part) = part.strip_prefix("auth=") {
+                capabilities.auth_schemes = auth_part
+                    .split(',')
+                    .map(|s| s.trim().to_lowercase())
+                    .filter(|s| !s.is_empty())
+                    .collect();
+            } else if let Some(required_part) = part.strip_prefix("auth-required=") {
+                capabilities.auth_required = required_part == "true";
             }
         }
 
@@ -154,6 +248,7 @@ impl WhoisColorProtocol {
 
     /// Perform query with enhanced protocol support (color, markdown, images)
     /// Falls back gracefully for older servers
+    #[cfg(feature = "net")]
     pub fn query_with_enhanced_protocol(
         &self,
         server_address: &str,
@@ -162,44 +257,129 @@ impl WhoisColorProtocol {
         preferred_scheme: Option<&str>,
         enable_markdown: bool,
         enable_images: bool,
-        verbose: bool
     ) -> Result<String> {
-        let mut stream = TcpStream::connect(server_address)
-            .with_context(|| format!("Cannot connect to WHOIS server: {}", server_address))?;
-        
-        stream.set_read_timeout(Some(Duration::from_secs(10)))
-            .context("Failed to set read timeout")?;
-        
-        stream.set_write_timeout(Some(Duration::from_secs(10)))
-            .context("Failed to set write timeout")?;
+        self.query_with_enhanced_protocol_timed(
+            server_address,
+            query,
+            capabilities,
+            preferred_scheme,
+            enable_markdown,
+            enable_images,
+            None,
+            None,
+            Duration::from_secs(10),
+        )
+        .map(|(response, _)| response)
+    }
 
-        let query_string = if capabilities.supports_color || capabilities.supports_markdown || capabilities.supports_images {
-            self.build_enhanced_query(query, capabilities, preferred_scheme, enable_markdown, enable_images, verbose)
+    /// Same as `query_with_enhanced_protocol`, with a timeout and per-phase timings
+    /// `auth_token` is only sent to servers that advertise bearer tokens; `charset` is that of the answer when not UTF-8
+    #[instrument(name = "connection", skip(self, capabilities, auth_token), fields(bytes))]
+    #[cfg(feature = "net")]
+    pub fn query_with_enhanced_protocol_timed(
+        &self,
+        server_address: &str,
+        query: &str,
+        capabilities: &ServerCapabilities,
+        preferred_scheme: Option<&str>,
+        enable_markdown: bool,
+        enable_images: bool,
+        auth_token: Option<&str>,
+        charset: Option<&str>,
+        timeout: Duration,
+    ) -> Result<(String, HopTiming)> {
+        if capabilities.auth_required && auth_token.is_none() {
+            anyhow::bail!("{} requires an auth token, set auth_token or auth_keyring in its [servers] config", server_address);
+        }
+        let query_string =
+            self.build_query(query, capabilities, preferred_scheme, enable_markdown, enable_images, auth_token);
+
+        if capabilities.supports_color {
+            debug!("Sending color-enabled query");
+        }
+        if capabilities.supports_markdown && enable_markdown {
+            debug!("Requesting Markdown format");
+        }
+        if capabilities.supports_images && enable_images {
+            debug!("Requesting image support");
+        }
+        trace!("Sending query to {}: {:?}", server_address, redact_auth(&query_string));
+
+        let (response, timing) = if capabilities.supports_paging {
+            self.query_pages(server_address, &query_string, timeout, charset)?
         } else {
-            // Standard WHOIS query
-            format!("{}\r\n", query)
+            timed_exchange(server_address, query_string.as_bytes(), timeout, charset)?
         };
+        trace!("Received {} bytes from {}", response.len(), server_address);
+        Span::current().record("bytes", response.len());
+        
+        Ok((response, timing))
+    }
 
-        if verbose {
-            if capabilities.supports_color {
-                println!("Sending color-enabled query");
+    /// Send a paged query and reassemble the answer by following continuation tokens
+    /// Each page is a separate connection; timings cover the first page, with the total for all of them
+    #[cfg(feature = "net")]
+    fn query_pages(&self, server_address: &str, query_string: &str, timeout: Duration, charset: Option<&str>) -> Result<(String, HopTiming)> {
+        let start = Instant::now();
+        let request = |token: &str| format!("{}{}\r\n{}", PAGE_REQUEST_PREFIX, token, query_string);
+        let (mut page, mut timing) = timed_exchange(server_address, request(FIRST_PAGE).as_bytes(), timeout, charset)?;
+        let mut response = String::new();
+        let mut tokens: Vec<String> = Vec::new();
+        loop {
+            let (content, token) = split_continuation(&page);
+            // Each page is capped on its own; the reassembled answer must fit the same limit
+            if response.len() + content.len() > MAX_RESPONSE_BYTES {
+                anyhow::bail!("Paged response from {} is larger than {} MiB", server_address, MAX_RESPONSE_BYTES / (1024 * 1024));
             }
-            if capabilities.supports_markdown && enable_markdown {
-                println!("Requesting Markdown format");
+            response.push_str(content);
+            let Some(token) = token.map(str::to_string) else {
+                break;
+            };
+            if tokens.contains(&token) {
+                anyhow::bail!("{} repeated continuation token {}", server_address, token);
             }
-            if capabilities.supports_images && enable_images {
-                println!("Requesting image support");
+            if tokens.len() + 1 >= MAX_PAGES {
+                anyhow::bail!("{} sent more than {} pages", server_address, MAX_PAGES);
             }
+            debug!("Fetching page {} from {}", tokens.len() + 2, server_address);
+            page = timed_exchange(server_address, request(&token).as_bytes(), timeout, charset)?.0;
+            tokens.push(token);
         }
+        timing.total = start.elapsed();
+        Ok((response, timing))
+    }
 
-        stream.write_all(query_string.as_bytes())
-            .context("Failed to write query to WHOIS server")?;
-        
-        let mut response = String::new();
-        stream.read_to_string(&mut response)
-            .context("Failed to read response from WHOIS server")?;
-        
-        Ok(response)
+    /// Query string sent to a server with the given capabilities
+    /// A plain `query\r\n` when none of the requested features is supported and no token is sent
+    pub fn build_query(
+        &self,
+        query: &str,
+        capabilities: &ServerCapabilities,
+        preferred_scheme: Option<&str>,
+        enable_markdown: bool,
+        enable_images: bool,
+        auth_token: Option<&str>,
+    ) -> String {
+        let query_string = if capabilities.supports_color
+            || capabilities.supports_markdown
+            || capabilities.supports_images
+            || !capabilities.encodings.is_empty()
+        {
+            self.build_enhanced_query(query, capabilities, preferred_scheme, enable_markdown, enable_images)
+        } else {
+            // Standard WHOIS query
+            format!("{}\r\n", query)
+        };
+        match auth_token {
+            Some(token) if capabilities.auth_schemes.iter().any(|scheme| scheme == "bearer") => {
+                format!("{}bearer {}\r\n{}", AUTH_REQUEST_PREFIX, token, query_string)
+            }
+            Some(_) => {
+                debug!("Server does not accept bearer tokens, not sending one");
+                query_string
+            }
+            None => query_string,
+        }
     }
 
     /// Build query string with enhanced protocol headers
@@ -212,16 +392,13 @@ impl WhoisColorProtocol {
         preferred_scheme: Option<&str>,
         enable_markdown: bool,
         enable_images: bool,
-        verbose: bool
     ) -> String {
         let mut headers = String::new();
         
         // Add color header if supported
         if capabilities.supports_color {
             if let Some(scheme) = self.select_color_scheme(capabilities, preferred_scheme) {
-                if verbose {
-                    println!("Requesting server-side coloring with scheme: {}", scheme);
-                }
+                debug!("Requesting server-side coloring with scheme: {}", scheme);
                 headers.push_str(&format!("{}scheme={}\r\n", COLOR_REQUEST_PREFIX, scheme));
             }
         }
@@ -236,6 +413,12 @@ impl WhoisColorProtocol {
             let formats = capabilities.image_formats.join(",");
             headers.push_str(&format!("{}{}\r\n", IMAGE_REQUEST_PREFIX, formats));
         }
+
+        // Ask for a compressed answer when both sides support an encoding
+        if let Some(encodings) = self.select_encodings(capabilities) {
+            debug!("Accepting compressed response: {}", encodings);
+            headers.push_str(&format!("{}{}\r\n", ACCEPT_ENCODING_PREFIX, encodings));
+        }
         
         if headers.is_empty() {
             // No protocol features, use standard query
@@ -255,20 +438,15 @@ impl WhoisColorProtocol {
         query: &str,
         capabilities: &ServerCapabilities,
         preferred_scheme: Option<&str>,
-        verbose: bool
     ) -> String {
         let scheme = self.select_color_scheme(capabilities, preferred_scheme);
         
         if let Some(scheme) = scheme {
-            if verbose {
-                println!("Requesting server-side coloring with scheme: {}", scheme);
-            }
+            debug!("Requesting server-side coloring with scheme: {}", scheme);
             format!("{}scheme={}\r\n{}\r\n", COLOR_REQUEST_PREFIX, scheme, query)
         } else {
             // No suitable scheme, use standard query
-            if verbose {
-                println!("No suitable color scheme, falling back to standard query");
-            }
+            debug!("No suitable color scheme, falling back to standard query");
             format!("{}\r\n", query)
         }
     }
@@ -294,6 +472,16 @@ impl WhoisColorProtocol {
         capabilities.color_schemes.first().cloned()
     }
 
+    /// Encodings supported by both the client and the server, comma-separated
+    fn select_encodings(&self, capabilities: &ServerCapabilities) -> Option<String> {
+        let shared: Vec<&str> = SUPPORTED_ENCODINGS
+            .iter()
+            .map(|encoding| encoding.name())
+            .filter(|name| capabilities.encodings.iter().any(|offered| offered == name))
+            .collect();
+        (!shared.is_empty()).then(|| shared.join(","))
+    }
+
     /// Check if response contains server-generated colors
     /// Server-colored responses should contain color control sequences
     pub fn is_server_colored(&self, response: &str) -> bool {
@@ -304,6 +492,41 @@ impl WhoisColorProtocol {
     }
 }
 
+/// Query string with the token of an `X-WHOIS-AUTH` line hidden, for logging
+#[cfg(feature = "net")]
+fn redact_auth(query_string: &str) -> String {
+    query_string
+        .split_inclusive('\n')
+        .map(|line| {
+            if line.starts_with(AUTH_REQUEST_PREFIX) {
+                format!("{}bearer ***\r\n", AUTH_REQUEST_PREFIX)
+            } else {
+                line.to_string()
+            }
+        })
+        .collect()
+}
+
+/// Split a page into its content and the continuation token on its last line, if any
+pub fn split_continuation(page: &str) -> (&str, Option<&str>) {
+    let trimmed = page.trim_end();
+    let start = trimmed.rfind('\n').map(|index| index + 1).unwrap_or(0);
+    match trimmed[start..].strip_prefix(CONTINUATION_PREFIX) {
+        Some(token) if !token.trim().is_empty() => (&page[..start], Some(token.trim())),
+        _ => (page, None),
+    }
+}
+
+/// Split the `X-WHOIS-MARKDOWN-APPLIED` line off the start of an answer
+/// Returns whether the server says the body is Markdown, `None` without the line
+pub fn split_markdown_marker(response: &str) -> (Option<bool>, &str) {
+    let Some(rest) = response.strip_prefix(MARKDOWN_APPLIED_PREFIX) else {
+        return (None, response);
+    };
+    let (value, body) = rest.split_once('\n').unwrap_or((rest, ""));
+    (Some(value.trim().eq_ignore_ascii_case("true")), body)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -371,6 +594,10 @@ mod tests {
             supports_markdown: false,
             supports_images: false,
             image_formats: vec![],
+            encodings: vec![],
+            supports_paging: false,
+            auth_schemes: vec![],
+            auth_required: false,
         };
         
         let scheme = protocol.select_color_scheme(&capabilities, Some("bgptools"));
@@ -387,6 +614,10 @@ mod tests {
             supports_markdown: false,
             supports_images: false,
             image_formats: vec![],
+            encodings: vec![],
+            supports_paging: false,
+            auth_schemes: vec![],
+            auth_required: false,
         };
         
         let scheme = protocol.select_color_scheme(&capabilities, Some("invalid"));
@@ -412,9 +643,13 @@ mod tests {
             supports_markdown: false,
             supports_images: false,
             image_formats: vec![],
+            encodings: vec![],
+            supports_paging: false,
+            auth_schemes: vec![],
+            auth_required: false,
         };
         
-        let query = protocol.build_enhanced_query("example.com", &capabilities, Some("ripe"), false, false, false);
+        let query = protocol.build_enhanced_query("example.com", &capabilities, Some("ripe"), false, false);
         assert_eq!(query, "X-WHOIS-COLOR: scheme=ripe\r\nexample.com\r\n");
     }
 
@@ -428,13 +663,67 @@ mod tests {
             supports_markdown: true,
             supports_images: true,
             image_formats: vec!["png".to_string(), "jpg".to_string()],
+            encodings: vec![],
+            supports_paging: false,
+            auth_schemes: vec![],
+            auth_required: false,
         };
         
-        let query = protocol.build_enhanced_query("example.com", &capabilities, Some("ripe"), true, true, false);
+        let query = protocol.build_enhanced_query("example.com", &capabilities, Some("ripe"), true, true);
         let expected = "X-WHOIS-COLOR: scheme=ripe\r\nX-WHOIS-MARKDOWN: true\r\nX-WHOIS-IMAGES: png,jpg\r\nexample.com\r\n";
         assert_eq!(query, expected);
     }
 
+    #[test]
+    fn test_build_enhanced_query_encoding() {
+        let protocol = WhoisColorProtocol;
+        let capabilities = protocol.parse_capability_response("X-WHOIS-COLOR-SUPPORT: v1.2 encoding=br,GZIP\r\n");
+        assert_eq!(capabilities.encodings, vec!["br", "gzip"]);
+
+        let query = protocol.build_enhanced_query("AS64500", &capabilities, None, false, false);
+        assert_eq!(query, "X-WHOIS-ACCEPT-ENCODING: gzip\r\nAS64500\r\n");
+
+        let capabilities = protocol.parse_capability_response("X-WHOIS-COLOR-SUPPORT: v1.2 encoding=br\r\n");
+        assert_eq!(protocol.build_enhanced_query("AS64500", &capabilities, None, false, false), "AS64500\r\n");
+    }
+
+    #[test]
+    fn test_auth_header() {
+        let protocol = WhoisColorProtocol;
+        let capabilities = protocol.parse_capability_response("X-WHOIS-COLOR-SUPPORT: v1.2 auth=bearer auth-required=true\r\n");
+        assert!(capabilities.auth_required);
+
+        let query = protocol.build_query("10.0.0.0/8", &capabilities, None, false, false, Some("s3cret"));
+        assert_eq!(query, "X-WHOIS-AUTH: bearer s3cret\r\n10.0.0.0/8\r\n");
+        // Tokens are never sent to servers that do not ask for them
+        let plain = ServerCapabilities::default();
+        assert_eq!(protocol.build_query("10.0.0.0/8", &plain, None, false, false, Some("s3cret")), "10.0.0.0/8\r\n");
+    }
+
+    #[test]
+    #[cfg(feature = "net")]
+    fn test_auth_required_without_token() {
+        let protocol = WhoisColorProtocol;
+        let capabilities = protocol.parse_capability_response("X-WHOIS-COLOR-SUPPORT: v1.2 auth=bearer auth-required=true\r\n");
+        let query = protocol.build_query("10.0.0.0/8", &capabilities, None, false, false, Some("s3cret"));
+        assert_eq!(redact_auth(&query), "X-WHOIS-AUTH: bearer ***\r\n10.0.0.0/8\r\n");
+
+        let err = protocol
+            .query_with_enhanced_protocol_timed(
+                "127.0.0.1:9",
+                "10.0.0.0/8",
+                &capabilities,
+                None,
+                false,
+                false,
+                None,
+                None,
+                Duration::from_secs(1),
+            )
+            .unwrap_err();
+        assert!(err.to_string().contains("requires an auth token"));
+    }
+
     #[test]
     fn test_build_color_query_legacy() {
         let protocol = WhoisColorProtocol;
@@ -445,9 +734,13 @@ mod tests {
             supports_markdown: false,
             supports_images: false,
             image_formats: vec![],
+            encodings: vec![],
+            supports_paging: false,
+            auth_schemes: vec![],
+            auth_required: false,
         };
         
-        let query = protocol.build_color_query("example.com", &capabilities, Some("ripe"), false);
+        let query = protocol.build_color_query("example.com", &capabilities, Some("ripe"));
         assert_eq!(query, "X-WHOIS-COLOR: scheme=ripe\r\nexample.com\r\n");
     }
 
@@ -456,7 +749,7 @@ mod tests {
         let protocol = WhoisColorProtocol;
         let capabilities = ServerCapabilities::default();
         
-        let query = protocol.build_color_query("example.com", &capabilities, Some("ripe"), false);
+        let query = protocol.build_color_query("example.com", &capabilities, Some("ripe"));
         assert_eq!(query, "example.com\r\n");
     }
 
@@ -468,4 +761,98 @@ mod tests {
         assert!(protocol.is_server_colored("X-WHOIS-COLOR-APPLIED: ripe\ntext"));
         assert!(!protocol.is_server_colored("plain text"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_split_continuation() {
+        let page = "aut-num: AS64500\nX-WHOIS-CONTINUATION: abc123\r\n";
+        assert_eq!(split_continuation(page), ("aut-num: AS64500\n", Some("abc123")));
+        assert_eq!(split_continuation("source: RIPE\n"), ("source: RIPE\n", None));
+        assert_eq!(split_continuation("X-WHOIS-CONTINUATION: \n"), ("X-WHOIS-CONTINUATION: \n", None));
+
+        let capabilities = WhoisColorProtocol.parse_capability_response("X-WHOIS-COLOR-SUPPORT: v1.2 schemes=ripe paging=true\r\n");
+        assert!(capabilities.supports_paging);
+    }
+
+    #[test]
+    fn test_split_markdown_marker() {
+        assert_eq!(split_markdown_marker("X-WHOIS-MARKDOWN-APPLIED: true\r\n# AS64500\n"), (Some(true), "# AS64500\n"));
+        assert_eq!(split_markdown_marker("X-WHOIS-MARKDOWN-APPLIED: false\n*** note ***\n"), (Some(false), "*** note ***\n"));
+        assert_eq!(split_markdown_marker("aut-num: AS64500\n"), (None, "aut-num: AS64500\n"));
+    }
+
+    #[test]
+    #[cfg(feature = "net")]
+    fn test_paged_response_reassembled() {
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let server = std::thread::spawn(move || {
+            let mut requests = Vec::new();
+            for (page, socket) in ["first\nX-WHOIS-CONTINUATION: t2\n", "second\nX-WHOIS-CONTINUATION: t3\n", "third\n"]
+                .iter()
+                .zip(listener.incoming())
+            {
+                let mut socket = socket.unwrap();
+                let mut request = [0u8; 512];
+                let read = socket.read(&mut request).unwrap();
+                requests.push(String::from_utf8_lossy(&request[..read]).lines().next().unwrap().to_string());
+                socket.write_all(page.as_bytes()).unwrap();
+            }
+            requests
+        });
+
+        let capabilities = ServerCapabilities {
+            supports_color: true,
+            color_schemes: vec!["ripe".to_string()],
+            supports_paging: true,
+            ..Default::default()
+        };
+        let (response, _) = WhoisColorProtocol
+            .query_with_enhanced_protocol_timed(&address, "AS64500", &capabilities, None, false, false, None, None, Duration::from_secs(5))
+            .unwrap();
+        assert_eq!(response, "first\nsecond\nthird\n");
+        assert_eq!(server.join().unwrap(), vec!["X-WHOIS-PAGE: first", "X-WHOIS-PAGE: t2", "X-WHOIS-PAGE: t3"]);
+    }
+
+    #[cfg(feature = "net")]
+    #[test]
+    fn test_paged_response_total_limit() {
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        std::thread::spawn(move || {
+            // Two pages that are each within the limit but exceed it together
+            let half = "a".repeat(MAX_RESPONSE_BYTES / 2 + 1);
+            for (page, socket) in [format!("{}\nX-WHOIS-CONTINUATION: t2\n", half), half].iter().zip(listener.incoming()) {
+                let mut socket = socket.unwrap();
+                let _ = socket.read(&mut [0u8; 512]).unwrap();
+                let _ = socket.write_all(page.as_bytes());
+            }
+        });
+
+        let capabilities = ServerCapabilities { supports_paging: true, ..Default::default() };
+        let err = WhoisColorProtocol
+            .query_with_enhanced_protocol_timed(&address, "AS64500", &capabilities, None, false, false, None, None, Duration::from_secs(30))
+            .unwrap_err();
+        assert!(err.to_string().contains("larger than"), "{:#}", err);
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn test_parse_any_capability_line(line in "\\PC{0,300}") {
+            let capabilities = WhoisColorProtocol.parse_capability_response(&format!("{}{}\r\n", CAPABILITY_RESPONSE_PREFIX, line));
+            match line.split_whitespace().next() {
+                Some(version) => proptest::prop_assert_eq!(capabilities.protocol_version, version),
+                None => proptest::prop_assert!(!capabilities.supports_color),
+            }
+        }
+    }
+
+    #[test]
+    fn test_overlong_capability_line_ignored() {
+        let line = format!("{}v1.1 schemes={}\r\n", CAPABILITY_RESPONSE_PREFIX, "ripe,".repeat(MAX_CAPABILITY_LINE));
+        assert!(!WhoisColorProtocol.parse_capability_response(&line).supports_color);
+    }
+}
@@ -0,0 +1,174 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use publicsuffix::{List, Psl};
+use tracing::debug;
+
+use crate::config::Config;
+use crate::normalize::strip_url;
+
+/// File name of a user-provided list next to the config file
+pub const LIST_FILE_NAME: &str = "public_suffix_list.dat";
+
+/// Snapshot of https://publicsuffix.org/list/public_suffix_list.dat shipped with the binary
+const BUILTIN_LIST: &str = include_str!("../data/public_suffix_list.dat");
+
+/// Public Suffix List used to find the registrable part of host names
+pub struct SuffixList {
+    list: List,
+}
+
+impl SuffixList {
+    /// Parse a list in the publicsuffix.org format
+    pub fn parse(contents: &str) -> Result<Self> {
+        let list: List = contents
+            .parse()
+            .map_err(|err| anyhow::anyhow!("Invalid public suffix list: {}", err))?;
+        Ok(Self { list })
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read public suffix list {}", path.display()))?;
+        Self::parse(&contents)
+    }
+
+    /// The snapshot shipped with the binary
+    pub fn builtin() -> Self {
+        Self::parse(BUILTIN_LIST).expect("built-in public suffix list is valid")
+    }
+
+    /// Load the list from an explicit path, `public_suffix_list.dat` in the config directory,
+    /// or fall back to the built-in snapshot
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        if let Some(path) = path {
+            return Self::from_file(path);
+        }
+        match Self::user_path().filter(|path| path.exists()) {
+            Some(path) => {
+                debug!("Using public suffix list {}", path.display());
+                Self::from_file(&path)
+            }
+            None => Ok(Self::builtin()),
+        }
+    }
+
+    /// Location of a user-updated list: next to the default config file
+    pub fn user_path() -> Option<PathBuf> {
+        Some(Config::default_path()?.parent()?.join(LIST_FILE_NAME))
+    }
+
+    /// Public suffix of a host name (`mail.example.co.uk` → `co.uk`), if the list knows it
+    pub fn public_suffix(&self, host: &str) -> Option<String> {
+        let host = host.trim_end_matches('.').to_lowercase();
+        let suffix = self.list.suffix(host.as_bytes())?;
+        if !suffix.is_known() {
+            return None;
+        }
+        std::str::from_utf8(suffix.as_bytes()).ok().map(str::to_string)
+    }
+
+    /// Registrable domain of a host name (`mail.example.co.uk` → `example.co.uk`)
+    /// `None` for public suffixes themselves and names under unknown suffixes
+    pub fn registrable_domain(&self, host: &str) -> Option<String> {
+        let host = host.trim_end_matches('.').to_lowercase();
+        let domain = self.list.domain(host.as_bytes())?;
+        if !domain.suffix().is_known() {
+            return None;
+        }
+        std::str::from_utf8(domain.as_bytes()).ok().map(str::to_string)
+    }
+}
+
+/// Where an extracted host name came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Indicator {
+    Url,
+    Email,
+}
+
+impl std::fmt::Display for Indicator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Indicator::Url => write!(f, "URL"),
+            Indicator::Email => write!(f, "email address"),
+        }
+    }
+}
+
+/// Host name inside a URL (`https://mail.example.com/x`) or email address (`abuse@example.com`)
+/// Anything else, including queries with spaces, returns `None`
+pub fn extract_host(input: &str) -> Option<(Indicator, String)> {
+    let input = input.trim();
+    if input.is_empty() || input.contains(char::is_whitespace) {
+        return None;
+    }
+    let (indicator, host) = if input.contains("://") {
+        (Indicator::Url, strip_url(input))
+    } else {
+        let (local, domain) = input.trim_start_matches("mailto:").rsplit_once('@')?;
+        if local.is_empty() || !domain.contains('.') {
+            return None;
+        }
+        (Indicator::Email, domain)
+    };
+    (!host.is_empty()).then(|| (indicator, host.trim_end_matches('.').to_lowercase()))
+}
+
+/// Domain to query for a pasted URL or email address
+/// Uses the registrable domain when a list is available, otherwise the full host name;
+/// IP addresses in URLs are returned as they are
+pub fn extract_domain(input: &str, suffixes: Option<&SuffixList>) -> Option<(Indicator, String)> {
+    let (indicator, host) = extract_host(input)?;
+    if host.parse::<std::net::IpAddr>().is_ok() {
+        return Some((indicator, host));
+    }
+    let domain = suffixes
+        .and_then(|suffixes| suffixes.registrable_domain(&host))
+        .unwrap_or(host);
+    Some((indicator, domain))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIST: &str = "// ===BEGIN ICANN DOMAINS===\ncom\nuk\nco.uk\n// ===END ICANN DOMAINS===\n";
+
+    #[test]
+    fn test_registrable_domain() {
+        let suffixes = SuffixList::parse(LIST).unwrap();
+        assert_eq!(suffixes.registrable_domain("mail.internal.foo.co.uk").as_deref(), Some("foo.co.uk"));
+        assert_eq!(suffixes.registrable_domain("WWW.Example.com.").as_deref(), Some("example.com"));
+        assert_eq!(suffixes.registrable_domain("co.uk"), None);
+    }
+
+    #[test]
+    fn test_builtin_snapshot() {
+        let suffixes = SuffixList::builtin();
+        assert_eq!(suffixes.registrable_domain("mail.internal.foo.co.uk").as_deref(), Some("foo.co.uk"));
+        assert_eq!(suffixes.public_suffix("shop.example.us.com").as_deref(), Some("us.com"));
+        assert_eq!(suffixes.public_suffix("www.example.com").as_deref(), Some("com"));
+    }
+
+    #[test]
+    fn test_extract_from_url_and_email() {
+        let suffixes = SuffixList::parse(LIST).unwrap();
+        assert_eq!(
+            extract_domain("https://login.shop.example.co.uk:8443/reset?x=1", Some(&suffixes)),
+            Some((Indicator::Url, "example.co.uk".to_string()))
+        );
+        assert_eq!(
+            extract_domain("Abuse@Mail.Example.com", Some(&suffixes)),
+            Some((Indicator::Email, "example.com".to_string()))
+        );
+        assert_eq!(
+            extract_domain("http://192.0.2.1/login", Some(&suffixes)),
+            Some((Indicator::Url, "192.0.2.1".to_string()))
+        );
+        // Without a list the full host is kept
+        assert_eq!(extract_domain("mailto:noc@mail.example.com", None).unwrap().1, "mail.example.com");
+        assert_eq!(extract_domain("example.com", Some(&suffixes)), None);
+        assert_eq!(extract_domain("-i origin AS3333", Some(&suffixes)), None);
+    }
+}
@@ -0,0 +1,187 @@
+//! Python module `whois_cli`, built with maturin (see `pyproject.toml`)
+//!
+//! ```python
+//! import whois_cli
+//!
+//! result = whois_cli.query("AS3333", timeout=10)
+//! results = whois_cli.bulk_query(["AS3333", "example.com"], concurrency=4)
+//! ```
+
+use std::time::Duration;
+
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+
+use crate::bulk::{BulkRunner, DEFAULT_CONCURRENCY};
+use crate::embed::{self, QueryOptions};
+use crate::error::kind_of;
+use crate::json;
+use crate::query::QueryResult;
+use crate::rpsl::{self, Block};
+
+create_exception!(whois_cli, WhoisError, PyException, "A failed lookup; `kind` is e.g. `timeout` or `rate-limited`");
+
+/// Parse a JSON record into a dict
+fn loads<'py>(py: Python<'py>, record: &str) -> PyResult<Bound<'py, PyDict>> {
+    Ok(py.import("json")?.call_method1("loads", (record,))?.downcast_into::<PyDict>()?)
+}
+
+/// The JSON record of a lookup as a dict, with the RPSL objects of the answer under `objects`
+fn result_dict<'py>(py: Python<'py>, target: &str, result: &QueryResult) -> PyResult<Bound<'py, PyDict>> {
+    let record = loads(py, &json::result_record(target, result))?;
+    let objects = PyList::empty(py);
+    for block in rpsl::parse(&result.response) {
+        let Block::Object(object) = block else {
+            continue;
+        };
+        let dict = PyDict::new(py);
+        dict.set_item("class", object.class())?;
+        dict.set_item("key", object.key())?;
+        // Pairs rather than a dict: attributes repeat and their order matters
+        let attributes: Vec<(&str, &str)> = object.attributes.iter().map(|attr| (attr.name.as_str(), attr.value.as_str())).collect();
+        dict.set_item("attributes", attributes)?;
+        objects.append(dict)?;
+    }
+    record.set_item("objects", objects)?;
+    Ok(record)
+}
+
+/// A timeout in seconds as milliseconds; `ValueError` when it is not positive, is NaN or is too large
+fn timeout_ms(timeout: Option<f64>) -> PyResult<Option<u64>> {
+    timeout
+        .map(|seconds| {
+            Duration::try_from_secs_f64(seconds)
+                .ok()
+                .and_then(|timeout| u64::try_from(timeout.as_millis()).ok())
+                .filter(|&ms| ms > 0)
+                .ok_or_else(|| PyValueError::new_err(format!("invalid timeout: {}", seconds)))
+        })
+        .transpose()
+}
+
+fn lookup_error(py: Python<'_>, error: &anyhow::Error) -> PyResult<PyErr> {
+    let exception = WhoisError::new_err(format!("{:#}", error));
+    exception.value(py).setattr("kind", kind_of(error))?;
+    Ok(exception)
+}
+
+/// Look up one target; raises `WhoisError` when the lookup fails
+#[pyfunction]
+#[pyo3(signature = (target, *, server=None, port=43, timeout=None, follow_referrals=true, dn42=false, bgptools=false, asn=false))]
+fn query<'py>(
+    py: Python<'py>,
+    target: &str,
+    server: Option<String>,
+    port: u16,
+    timeout: Option<f64>,
+    follow_referrals: bool,
+    dn42: bool,
+    bgptools: bool,
+    asn: bool,
+) -> PyResult<Bound<'py, PyDict>> {
+    let options = QueryOptions { server, port, timeout_ms: timeout_ms(timeout)?, follow_referrals, dn42, bgptools, asn };
+    match py.allow_threads(|| embed::lookup(target, &options)) {
+        Ok((target, result)) => result_dict(py, &target, &result),
+        Err(err) => Err(lookup_error(py, &err)?),
+    }
+}
+
+/// Look up several targets in parallel; failures are `{"target", "error", "kind"}` dicts
+/// Results are in the order of `targets`
+#[pyfunction]
+#[pyo3(signature = (targets, *, concurrency=DEFAULT_CONCURRENCY, server=None, port=43, timeout=None, follow_referrals=true, dn42=false, bgptools=false, asn=false))]
+fn bulk_query<'py>(
+    py: Python<'py>,
+    targets: Vec<String>,
+    concurrency: usize,
+    server: Option<String>,
+    port: u16,
+    timeout: Option<f64>,
+    follow_referrals: bool,
+    dn42: bool,
+    bgptools: bool,
+    asn: bool,
+) -> PyResult<Bound<'py, PyList>> {
+    let options = QueryOptions { server, port, timeout_ms: timeout_ms(timeout)?, follow_referrals, dn42, bgptools, asn };
+    let outcomes = py.allow_threads(|| {
+        BulkRunner::new(true).with_concurrency(concurrency).run(&targets, |target| embed::lookup(target, &options))
+    });
+    let results = PyList::empty(py);
+    for outcome in outcomes {
+        match outcome.result {
+            Ok((target, result)) => results.append(result_dict(py, &target, &result)?)?,
+            Err(err) => results.append(loads(py, &json::error_record(&outcome.target, &err))?)?,
+        }
+    }
+    Ok(results)
+}
+
+#[pymodule]
+fn whois_cli(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_function(wrap_pyfunction!(query, module)?)?;
+    module.add_function(wrap_pyfunction!(bulk_query, module)?)?;
+    module.add("WhoisError", module.py().get_type::<WhoisError>())?;
+    module.add("__version__", env!("CARGO_PKG_VERSION"))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    /// WHOIS server on localhost answering `answer` to one query
+    fn serve(answer: &'static str) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            let (mut socket, _) = listener.accept().unwrap();
+            let _ = socket.read(&mut [0u8; 512]).unwrap();
+            socket.write_all(answer.as_bytes()).unwrap();
+        });
+        port
+    }
+
+    #[test]
+    fn test_query() {
+        pyo3::prepare_freethreaded_python();
+        let port = serve("% comment\n\naut-num:        AS64500\nas-name:        EXAMPLE-AS\nremarks:        one\nremarks:        two\n");
+        Python::with_gil(|py| {
+            let record = query(py, "as64500", Some("127.0.0.1".to_string()), port, Some(5.0), false, false, false, false).unwrap();
+            assert_eq!(record.get_item("target").unwrap().unwrap().extract::<String>().unwrap(), "AS64500");
+            assert_eq!(record.get_item("classification").unwrap().unwrap().extract::<String>().unwrap(), "answer");
+
+            let objects = record.get_item("objects").unwrap().unwrap();
+            assert_eq!(objects.len().unwrap(), 1);
+            let object = objects.get_item(0).unwrap();
+            assert_eq!(object.get_item("class").unwrap().extract::<String>().unwrap(), "aut-num");
+            let attributes: Vec<(String, String)> = object.get_item("attributes").unwrap().extract().unwrap();
+            assert_eq!(attributes[3], ("remarks".to_string(), "two".to_string()));
+        });
+    }
+
+    #[test]
+    fn test_query_errors() {
+        pyo3::prepare_freethreaded_python();
+        // A port nothing listens on
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        Python::with_gil(|py| {
+            let err = query(py, "AS64500", Some("127.0.0.1".to_string()), port, Some(2.0), false, false, false, false).unwrap_err();
+            assert!(err.is_instance_of::<WhoisError>(py));
+            assert_eq!(err.value(py).getattr("kind").unwrap().extract::<String>().unwrap(), "error");
+
+            for timeout in [-1.0, f64::NAN, f64::INFINITY, 1e300, 0.0] {
+                let err = query(py, "AS64500", None, 43, Some(timeout), false, false, false, false).unwrap_err();
+                assert!(err.is_instance_of::<PyValueError>(py), "{}", timeout);
+            }
+
+            let results = bulk_query(py, vec!["AS64500".to_string()], 1, Some("127.0.0.1".to_string()), port, Some(2.0), false, false, false, false).unwrap();
+            let failure = results.get_item(0).unwrap();
+            assert_eq!(failure.get_item("kind").unwrap().extract::<String>().unwrap(), "error");
+            assert!(failure.get_item("error").unwrap().extract::<String>().unwrap().contains("127.0.0.1"));
+        });
+    }
+}
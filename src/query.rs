@@ -1,62 +1,82 @@
-use std::io::{Read, Write};
-use std::net::TcpStream;
-use std::time::Duration;
-use anyhow::{Context, Result};
-use crate::servers::{WhoisServer, ServerSelector, DEFAULT_WHOIS_SERVER};
-use crate::protocol::WhoisColorProtocol;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use anyhow::{anyhow, Result};
+use tracing::{debug, info, info_span, instrument, trace, warn, Span};
+use crate::error::QueryError;
+use serde::Serialize;
+use crate::classify::{classify_response, is_rate_limited, Classification, EmptyResultPolicy};
+use crate::servers::{default_fallback_chain, FallbackCondition, FallbackStep, Language, RecordType, ServerRegistry, WhoisServer, ServerSelector, DEFAULT_WHOIS_PORT, DEFAULT_WHOIS_SERVER, NIC_GUESS_NAME};
+use crate::protocol::{split_markdown_marker, CapabilityCache, ServerCapabilities, WhoisColorProtocol, CAPABILITY_TIMEOUT_MS};
+use crate::metrics::Metrics;
+use crate::ratelimit::{HostLimiter, HostPermit};
+use crate::rules::CleanupLevel;
+use crate::timing::{timed_exchange, HopTiming, QueryTiming};
+use crate::allocations;
+use crate::ansi::strip_ansi;
+use crate::referral::{extract_referral, is_nir_server, ReferralScheme};
+use crate::rwhois;
+use crate::persistent::ConnectionPool;
+use crate::deadline::Deadline;
+use crate::psl::SuffixList;
+use crate::related::{is_domain_target, RelatedNetworks};
+use crate::contacts::{self, ContactRef, MAX_CONTACT_QUERIES};
+use crate::rdap;
 
 const TIMEOUT_SECONDS: u64 = 10;
+/// Maximum number of referrals embedded in responses followed per lookup
+const MAX_EMBEDDED_REFERRALS: usize = 3;
 
-/// Check if a WHOIS response is effectively empty or indicates no results
-fn is_empty_result(response: &str) -> bool {
-    let response = response.trim();
-    
-    // Obviously empty
-    if response.is_empty() {
-        return true;
-    }
-    
-    // Common empty response indicators (case-insensitive)
-    let response_lower = response.to_lowercase();
-    let empty_indicators = [
-        "no found",
-        "no match",
-        "not found",
-        "no data found",
-        "no entries found",
-        "no records found",
-        "no such domain",
-        "no whois server is known",
-        "object does not exist",
-        "%error: no objects found",
-        "% no objects found",
-    ];
-    
-    for indicator in &empty_indicators {
-        if response_lower.contains(indicator) {
-            return true;
-        }
+/// Check if a WHOIS response is effectively empty or indicates no results, using the built-in policy
+pub fn is_empty_result(response: &str) -> bool {
+    EmptyResultPolicy::default().is_empty(response)
+}
+
+/// Turn a read interrupted by Ctrl-C or a connection reset into a result holding the text received so far
+fn recover_partial(result: Result<QueryResult>) -> Result<QueryResult> {
+    let Err(err) = result else {
+        return result;
+    };
+    let (server, received, connection_reset) = match err.downcast_ref::<QueryError>() {
+        Some(QueryError::Interrupted { server, received }) => (server, received, false),
+        Some(QueryError::ConnectionReset { server, received }) => (server, received, true),
+        _ => return Err(err),
+    };
+    if connection_reset {
+        warn!("{}, showing the partial answer", err);
+    }
+    let mut partial = QueryResult::new(received.clone(), WhoisServer::from_spec(server, DEFAULT_WHOIS_PORT)?);
+    partial.partial = true;
+    partial.connection_reset = connection_reset;
+    Ok(partial)
+}
+
+/// Text received before a connection reset, if that is how a lookup failed
+fn reset_text(result: &Result<QueryResult>) -> Option<&str> {
+    match result.as_ref().err()?.downcast_ref::<QueryError>()? {
+        QueryError::ConnectionReset { received, .. } => Some(received),
+        _ => None,
     }
-    
-    // Check if response only contains comment lines (lines starting with % or #)
-    let content_lines: Vec<&str> = response
-        .lines()
-        .map(|line| line.trim())
-        .filter(|line| !line.is_empty())
-        .filter(|line| !line.starts_with('%') && !line.starts_with('#'))
-        .collect();
-    
-    if content_lines.is_empty() {
-        return true;
-    }
-    
-    // Check if response is very short (less than 30 characters) and likely just headers/boilerplate
-    // Only apply this for extremely short responses that have minimal content
-    if response.len() < 30 && content_lines.join(" ").len() < 10 {
-        return true;
-    }
-    
-    false
+}
+
+fn describe_condition(condition: FallbackCondition) -> &'static str {
+    match condition {
+        FallbackCondition::Empty => "Empty result",
+        FallbackCondition::Timeout => "Timeout",
+        FallbackCondition::RateLimited => "Rate limited",
+    }
+}
+
+/// Which fallback step produced a result, and why it ran
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FallbackInfo {
+    /// 1-based position in the fallback chain
+    pub step: usize,
+    pub server: String,
+    /// Outcome of the previous server that triggered the step
+    pub reason: FallbackCondition,
+    /// Server whose outcome triggered the step
+    pub from: String,
 }
 
 #[derive(Debug)]
@@ -64,6 +84,18 @@ pub struct QueryResult {
     pub response: String,
     pub server_used: WhoisServer,
     pub server_colored: bool,
+    /// Per-hop timings, filled in by the top-level query methods
+    pub timing: QueryTiming,
+    /// How the response was classified by the server's empty-result policy
+    pub classification: Classification,
+    /// Set when the response came from the fallback chain
+    pub fallback: Option<FallbackInfo>,
+    /// Set when the run was interrupted or the connection reset and the response is incomplete
+    pub partial: bool,
+    /// Set when the response is incomplete because the server reset the connection
+    pub connection_reset: bool,
+    /// Whether the server said the response is Markdown (`X-WHOIS-MARKDOWN-APPLIED`); `None` when it did not say
+    pub markdown: Option<bool>,
 }
 
 impl QueryResult {
@@ -72,6 +104,12 @@ impl QueryResult {
             response, 
             server_used,
             server_colored: false,
+            timing: QueryTiming::default(),
+            classification: Classification::Answer,
+            fallback: None,
+            partial: false,
+            connection_reset: false,
+            markdown: None,
         }
     }
 
@@ -80,79 +118,544 @@ impl QueryResult {
             response, 
             server_used,
             server_colored,
+            timing: QueryTiming::default(),
+            classification: Classification::Answer,
+            fallback: None,
+            partial: false,
+            connection_reset: false,
+            markdown: None,
         }
     }
 }
 
+/// Callback receiving short status updates (current server, referral hops) while a query runs
+pub type ProgressCallback = Arc<dyn Fn(&str) + Send + Sync>;
+
+/// WHOIS query engine
+/// Diagnostics are emitted as `tracing` events; install a subscriber to see them
 pub struct WhoisQuery {
-    verbose: bool,
+    progress: Option<ProgressCallback>,
+    metrics: Option<Arc<Metrics>>,
+    limiter: Option<Arc<HostLimiter>>,
+    fallback_chain: Vec<FallbackStep>,
+    registry: Arc<ServerRegistry>,
+    cleanup: CleanupLevel,
+    language: Option<Language>,
+    record_type: Option<RecordType>,
+    full_output: bool,
+    inverse: Option<String>,
+    object_types: Option<String>,
+    follow_referrals: bool,
+    pool: Option<Arc<ConnectionPool>>,
+    deadline: Deadline,
+    suffixes: Option<Arc<SuffixList>>,
+    capability_cache: Option<Arc<dyn CapabilityCache>>,
+    retry_reset: bool,
+    hops: Mutex<Vec<HopTiming>>,
+}
+
+impl Default for WhoisQuery {
+    fn default() -> Self {
+        Self {
+            progress: None,
+            metrics: None,
+            limiter: None,
+            fallback_chain: default_fallback_chain(),
+            registry: Arc::new(ServerRegistry::default()),
+            cleanup: CleanupLevel::default(),
+            language: None,
+            record_type: None,
+            full_output: false,
+            inverse: None,
+            object_types: None,
+            follow_referrals: true,
+            pool: None,
+            deadline: Deadline::none(),
+            suffixes: None,
+            capability_cache: None,
+            retry_reset: false,
+            hops: Mutex::new(Vec::new()),
+        }
+    }
 }
 
 impl WhoisQuery {
-    pub fn new(verbose: bool) -> Self {
-        Self { verbose }
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Report progress updates to a callback
+    pub fn with_progress(mut self, progress: ProgressCallback) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Record per-upstream query counts, errors and latency
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Apply per-server politeness limits to every connection
+    pub fn with_limiter(mut self, limiter: Arc<HostLimiter>) -> Self {
+        self.limiter = Some(limiter);
+        self
+    }
+
+    /// Replace the servers tried when a lookup comes back empty, times out or is rate limited
+    pub fn with_fallback_chain(mut self, chain: Vec<FallbackStep>) -> Self {
+        self.fallback_chain = chain;
+        self
+    }
+
+    /// Use per-server profiles, e.g. empty-result heuristics and cleanup rules
+    /// A shared registry can be passed as an `Arc` so it is not rebuilt for every query
+    pub fn with_registry(mut self, registry: impl Into<Arc<ServerRegistry>>) -> Self {
+        self.registry = registry.into();
+        self
+    }
+
+    /// Per-server profiles the query uses
+    pub fn registry(&self) -> &ServerRegistry {
+        &self.registry
+    }
+
+    /// Choose which cleanup rules run, e.g. to strip disclaimers and banners
+    pub fn with_cleanup(mut self, level: CleanupLevel) -> Self {
+        self.cleanup = level;
+        self
+    }
+
+    /// Ask registries that support it for answers in a given language
+    pub fn with_language(mut self, language: Language) -> Self {
+        self.language = Some(language);
+        self
+    }
+
+    /// Follow referrals embedded in answers, e.g. ARIN `ReferralServer:` lines (on by default)
+    pub fn with_follow_referrals(mut self, follow: bool) -> Self {
+        self.follow_referrals = follow;
+        self
+    }
+
+    /// Reuse `-k` persistent connections from a shared pool for servers that support them
+    pub fn with_connection_pool(mut self, pool: Arc<ConnectionPool>) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+
+    /// Stop the lookup, including referrals and fallbacks, once the deadline passes
+    pub fn with_deadline(mut self, deadline: Deadline) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    /// Route domains under multi-label public suffixes (`us.com`, `eu.org`) to their own registries
+    pub fn with_suffix_list(mut self, suffixes: Arc<SuffixList>) -> Self {
+        self.suffixes = Some(suffixes);
+        self
+    }
+
+    /// Reuse capability probe results across queries and runs
+    pub fn with_capability_cache(mut self, cache: Arc<dyn CapabilityCache>) -> Self {
+        self.capability_cache = Some(cache);
+        self
+    }
+
+    /// After a connection reset, run the lookup once more from scratch and compare the answers
+    pub fn with_reset_retry(mut self, retry: bool) -> Self {
+        self.retry_reset = retry;
+        self
+    }
+
+    /// Run a lookup, and with `with_reset_retry` once more if a connection reset cut it short;
+    /// a complete retry replaces the partial answer, otherwise the longer partial answer is kept
+    fn retry_after_reset(&self, lookup: impl Fn() -> Result<QueryResult>) -> Result<QueryResult> {
+        let first = lookup();
+        let Some(received) = reset_text(&first).filter(|_| self.retry_reset) else {
+            return first;
+        };
+        warn!("Connection reset after {} bytes, querying again", received.len());
+        self.reset_hops();
+        let retry = lookup();
+        match (&retry, reset_text(&retry)) {
+            (Ok(result), _) => {
+                let complete = strip_ansi(&result.response);
+                if complete.contains(received.trim_end()) && complete.len() > received.len() {
+                    warn!("The first answer was truncated: {} of {} bytes arrived before the reset", received.len(), complete.len());
+                } else if !complete.contains(received.trim_end()) {
+                    warn!("The retry answered differently from the {} bytes received before the reset", received.len());
+                }
+                retry
+            }
+            (Err(_), Some(again)) if again.len() > received.len() => retry,
+            _ => first,
+        }
+    }
+
+    /// Server a lookup starts at, before referrals and fallbacks
+    pub fn first_server(&self, domain: &str, use_dn42: bool, use_bgptools: bool, explicit_server: Option<&str>, port: u16) -> Result<WhoisServer> {
+        let server = ServerSelector::select_server(domain, use_dn42, use_bgptools, explicit_server, port)?;
+        Ok(self.route_by_suffix(domain, server))
+    }
+
+    /// Server a target is expected to end up at, for spreading a bulk run over servers
+    /// Lookups that start at IANA are keyed by the RIR of an address or ASN and by the TLD
+    /// of a domain, since IANA refers each of those to its own server
+    pub fn queue_key(&self, target: &str, use_dn42: bool, use_bgptools: bool, explicit_server: Option<&str>, port: u16) -> Result<String> {
+        let server = self.first_server(target, use_dn42, use_bgptools, explicit_server, port)?;
+        if server.name != "IANA" {
+            return Ok(server.address());
+        }
+        if let Some(rir) = allocations::rir_server(target) {
+            return Ok(WhoisServer::custom(rir, port).address());
+        }
+        match target.trim_end_matches('.').rsplit_once('.') {
+            Some((_, tld)) if !tld.is_empty() && is_domain_target(target) => Ok(format!(".{}", tld.to_lowercase())),
+            _ => Ok(server.address()),
+        }
+    }
+
+    /// Send a domain straight to its suffix registry when IANA's TLD referral would miss it
+    fn route_by_suffix(&self, domain: &str, server: WhoisServer) -> WhoisServer {
+        if server.name != "IANA" {
+            return server;
+        }
+        let Some(suffix) = self.suffixes.as_ref().and_then(|suffixes| suffixes.public_suffix(domain)) else {
+            return server;
+        };
+        match ServerSelector::suffix_server(&suffix, server.port) {
+            Some(registry) if suffix.contains('.') => {
+                info!("{} is under public suffix {}, using: {}", domain, suffix, registry.host);
+                registry
+            }
+            _ => server,
+        }
+    }
+
+    /// Classify a response with the empty-result policy of the server that sent it
+    pub fn classify(&self, result: &QueryResult) -> Classification {
+        classify_response(&result.response, self.registry.empty_policy(&result.server_used.host))
+    }
+
+    /// Classify a lookup outcome for the fallback chain; `None` means no fallback applies
+    fn classify_outcome(&self, outcome: &Result<QueryResult>) -> Option<FallbackCondition> {
+        match outcome {
+            Ok(result) => match self.classify(result) {
+                Classification::Answer => None,
+                Classification::Empty => Some(FallbackCondition::Empty),
+                Classification::RateLimited => Some(FallbackCondition::RateLimited),
+            },
+            Err(err) => match err.downcast_ref::<QueryError>() {
+                Some(QueryError::Timeout { .. }) => Some(FallbackCondition::Timeout),
+                Some(QueryError::RateLimited { .. }) => Some(FallbackCondition::RateLimited),
+                // Nothing more may run once the lookup is out of time or cancelled
+                Some(QueryError::DeadlineExceeded { .. } | QueryError::Cancelled | QueryError::Interrupted { .. }) | None => None,
+                // The partial answer is shown rather than replaced by another server's
+                Some(QueryError::ConnectionReset { .. }) => None,
+            },
+        }
+    }
+
+    /// Wait for permission to connect to a server, if a limiter is set
+    fn acquire(&self, address: &str) -> Option<HostPermit<'_>> {
+        self.limiter.as_ref().map(|limiter| limiter.acquire(address))
+    }
+
+    /// Send a status update to the progress callback, if any
+    fn report(&self, message: &str) {
+        if let Some(progress) = &self.progress {
+            progress(message);
+        }
+    }
+
+    /// Record the timings of a completed hop
+    fn record_hop(&self, hop: HopTiming) {
+        info!("Timing {}", hop.summary());
+        if let Some(metrics) = &self.metrics {
+            metrics.record_upstream_query(&hop.server, hop.total);
+        }
+        if let Ok(mut hops) = self.hops.lock() {
+            hops.push(hop);
+        }
+    }
+
+    /// Count a failed upstream query
+    fn record_error(&self, upstream: &str) {
+        if let Some(metrics) = &self.metrics {
+            metrics.record_upstream_error(upstream);
+        }
+    }
+
+    /// Restrict queries to one record type on servers with a query syntax for it (ARIN `n`, `a`, `o`, `p`)
+    pub fn with_record_type(mut self, record_type: RecordType) -> Self {
+        self.record_type = Some(record_type);
+        self
+    }
+
+    /// Ask servers with a query syntax for it for full output (ARIN `+`)
+    pub fn with_full_output(mut self, full: bool) -> Self {
+        self.full_output = full;
+        self
+    }
+
+    /// Look up objects referring to the query through an attribute (`-i`), on servers that take it
+    pub fn with_inverse(mut self, attribute: impl Into<String>) -> Self {
+        self.inverse = Some(attribute.into());
+        self
+    }
+
+    /// Only return objects of the given comma-separated types (`-T`), on servers that take it
+    pub fn with_object_types(mut self, types: impl Into<String>) -> Self {
+        self.object_types = Some(types.into());
+        self
+    }
+
+    /// Query string as a server expects it: `-i`/`-T` flags it supports, language suffix,
+    /// record type and full-output prefixes
+    fn server_query(&self, host: &str, query: &str) -> String {
+        let query = self.registry.localize_query(host, query, self.language);
+        let query = self.registry.typed_query(host, &query, self.record_type, self.full_output);
+        let supported = self.registry.query_flags(host);
+        let mut flags = Vec::new();
+        if let Some(attribute) = self.inverse.as_deref().filter(|_| supported.inverse) {
+            flags.push(format!("-i {}", attribute));
+        }
+        if let Some(types) = self.object_types.as_deref().filter(|_| supported.types) {
+            flags.push(format!("-T {}", types));
+        }
+        if flags.is_empty() {
+            return query;
+        }
+        format!("{} {}", flags.join(" "), query)
+    }
+
+    /// Forget hops left over from a previous lookup
+    fn reset_hops(&self) {
+        if let Ok(mut hops) = self.hops.lock() {
+            hops.clear();
+        }
+    }
+
+    /// Attach the hops recorded since `start` and the classification to a finished lookup,
+    /// then apply the answering server's language selection and cleanup rules
+    fn finish_result(&self, mut result: QueryResult, start: Instant) -> QueryResult {
+        let hops = self.hops.lock().map(|mut hops| std::mem::take(&mut *hops)).unwrap_or_default();
+        result.timing = QueryTiming { hops, total: start.elapsed() };
+        result.classification = self.classify(&result);
+        let host = &result.server_used.host;
+        let response = self.registry.select_language(host, &result.response, self.language);
+        result.response = self.registry.clean_response(host, &response, self.cleanup);
+        info!("Lookup finished in {}", crate::timing::format_duration(result.timing.total));
+        result
     }
 
     /// Perform a direct WHOIS query to a specific server
+    #[instrument(name = "connection", skip_all, fields(server = %server.address(), query = %query, bytes))]
     pub fn query_direct(&self, query: &str, server: &WhoisServer) -> Result<String> {
         let address = server.address();
         
-        if self.verbose {
-            println!("Connecting to: {}", address);
-        }
+        info!("Connecting to: {}", address);
+        self.report(&format!("querying {}", address));
 
-        let mut stream = TcpStream::connect(&address)
-            .with_context(|| format!("Cannot connect to WHOIS server: {}", address))?;
-        
-        stream.set_read_timeout(Some(Duration::from_secs(TIMEOUT_SECONDS)))
-            .context("Failed to set read timeout")?;
-        
-        stream.set_write_timeout(Some(Duration::from_secs(TIMEOUT_SECONDS)))
-            .context("Failed to set write timeout")?;
-        
-        let query_string = format!("{}\r\n", query);
-        stream.write_all(query_string.as_bytes())
-            .context("Failed to write query to WHOIS server")?;
-        
-        let mut response = String::new();
-        stream.read_to_string(&mut response)
-            .context("Failed to read response from WHOIS server")?;
+        let query_string = format!("{}\r\n", self.server_query(&server.host, query));
+        trace!("Sending query to {}: {:?}", address, query_string);
+        let _permit = self.acquire(&address);
+        let timeout = self.deadline.step_timeout(Duration::from_secs(TIMEOUT_SECONDS), &address)?;
+        let exchange = match &self.pool {
+            Some(pool) if self.registry.supports_persistent(&server.host) => {
+                pool.query(&address, query_string.trim_end(), timeout)
+            }
+            _ => timed_exchange(&address, query_string.as_bytes(), timeout, self.registry.charset(&server.host)),
+        };
+        let (response, timing) = exchange
+            .inspect_err(|_| self.record_error(&address))
+            .map_err(|err| self.deadline.explain(err, &address))?;
+        trace!("Received {} bytes from {}", response.len(), address);
+        Span::current().record("bytes", response.len());
+        self.record_hop(timing);
         
         Ok(response)
     }
 
+    /// Less- and more-specific networks around an IP or prefix, using the server's hierarchy flags
+    /// Returns `None` when the server has no such flags (e.g. ARIN, LACNIC)
+    pub fn query_related(&self, target: &str, server: &WhoisServer) -> Result<Option<RelatedNetworks>> {
+        let Some(flags) = self.registry.hierarchy_flags(&server.host) else {
+            return Ok(None);
+        };
+        let less_specific = self.query_direct(&format!("{} {}", flags.less_specific, target), server)?;
+        let more_specific = self.query_direct(&format!("{} {}", flags.more_specific, target), server)?;
+        Ok(Some(RelatedNetworks::from_responses(&less_specific, &more_specific)))
+    }
+
+    /// Org and POC records an answer refers to, fetched with the server's org/POC query prefixes
+    /// At most `MAX_CONTACT_QUERIES` handles are looked up; a failed lookup is skipped.
+    /// Returns `None` when the server has no such prefixes (only ARIN has them built in)
+    pub fn query_contacts(&self, response: &str, server: &WhoisServer) -> Option<Vec<(ContactRef, String)>> {
+        let syntax = self.registry.query_syntax(&server.host)?;
+        syntax.prefix(RecordType::Org).and(syntax.prefix(RecordType::Poc))?;
+        let references = contacts::references(&strip_ansi(response));
+        if references.len() > MAX_CONTACT_QUERIES {
+            debug!("Fetching {} of {} referenced contacts", MAX_CONTACT_QUERIES, references.len());
+        }
+        let mut records = Vec::new();
+        for reference in references.into_iter().take(MAX_CONTACT_QUERIES) {
+            let query = syntax.apply(&reference.handle, Some(reference.record_type), false);
+            match self.query_direct(&query, server) {
+                Ok(record) => records.push((reference, record)),
+                Err(err) => warn!("Lookup of {} failed: {:#}", reference.handle, err),
+            }
+        }
+        Some(records)
+    }
+
+    /// Answer from a server's RDAP endpoint, for servers that prefer RDAP (LACNIC built in)
+    /// When RDAP fails the server is asked on port 43, where a rate-limit refusal becomes
+    /// `QueryError::RateLimited`; `None` when RDAP does not apply to the server or query
+    fn query_preferring_rdap(&self, query: &str, server: &WhoisServer) -> Option<Result<QueryResult>> {
+        let base_url = self.registry.preferred_rdap(&server.host)?;
+        let path = rdap::path_for(query)?;
+        self.report(&format!("querying {}", base_url));
+        let rdap_server = WhoisServer::new(server.host.clone(), server.port, "RDAP");
+        let answer = self
+            .deadline
+            .step_timeout(Duration::from_secs(TIMEOUT_SECONDS), base_url)
+            .and_then(|timeout| rdap::fetch(base_url, &path, timeout));
+        match answer {
+            Ok(Some(object)) => return Some(Ok(QueryResult::new(rdap::to_whois(&object), rdap_server))),
+            Ok(None) => return Some(Ok(QueryResult::new(format!("% No entries found for '{}' at {}\n", query, base_url), rdap_server))),
+            Err(err) => warn!("RDAP lookup at {} failed ({:#}), trying port 43", base_url, err),
+        }
+        Some(self.query_direct(query, server).and_then(|response| {
+            if is_rate_limited(&response) {
+                return Err(QueryError::RateLimited { server: server.address() }.into());
+            }
+            Ok(QueryResult::new(response, server.clone()))
+        }))
+    }
+
+    /// Perform an RWhois query to a specific server
+    #[instrument(name = "connection", skip_all, fields(server = %server.address(), query = %query, protocol = "rwhois", bytes))]
+    pub fn query_rwhois(&self, query: &str, server: &WhoisServer) -> Result<String> {
+        let address = server.address();
+
+        info!("Connecting to RWhois server: {}", address);
+        self.report(&format!("querying {}", address));
+
+        let _permit = self.acquire(&address);
+        let timeout = self.deadline.step_timeout(Duration::from_secs(TIMEOUT_SECONDS), &address)?;
+        let (response, timing) = rwhois::timed_query(&address, query, timeout)
+            .inspect_err(|_| self.record_error(&address))
+            .map_err(|err| self.deadline.explain(err, &address))?;
+        trace!("Received {} bytes from {}", response.len(), address);
+        Span::current().record("bytes", response.len());
+        self.record_hop(timing);
+
+        Ok(response)
+    }
+
+    /// Follow referrals embedded in an answer until one yields data of its own
+    /// A referral that fails or comes back empty keeps the answer that pointed to it
+    fn follow_embedded_referrals(&self, domain: &str, mut result: QueryResult) -> QueryResult {
+        if !self.follow_referrals {
+            return result;
+        }
+        for _ in 0..MAX_EMBEDDED_REFERRALS {
+            let Some(referral) = extract_referral(&strip_ansi(&result.response)) else {
+                break;
+            };
+            let server = referral.to_server();
+            if server.address().eq_ignore_ascii_case(&result.server_used.address()) {
+                break;
+            }
+
+            let from = result.server_used.host.clone();
+            info!("{} refers to {}", from, referral);
+            self.report(&format!("referral hop: {} -> {}", from, referral.host));
+            let _referral = info_span!("referral", from = %from, to = %referral.host).entered();
+
+            let answer = match referral.scheme {
+                // NIRs answer in their local language unless English is asked for (JPNIC `/e`)
+                ReferralScheme::Whois if is_nir_server(&server.host) && self.language.is_none() => {
+                    self.query_direct(&self.registry.localize_query(&server.host, domain, Some(Language::En)), &server)
+                }
+                ReferralScheme::Whois => self.query_direct(domain, &server),
+                ReferralScheme::Rwhois => self.query_rwhois(domain, &server),
+            };
+            match answer {
+                Ok(response) if self.registry.empty_policy(&server.host).is_empty(&response) => {
+                    info!("Referral to {} returned nothing, keeping answer from {}", referral, from);
+                    break;
+                }
+                Ok(response) => result = QueryResult::new(response, server),
+                Err(err) => {
+                    warn!("Referral to {} failed ({:#}), keeping answer from {}", referral, err, from);
+                    break;
+                }
+            }
+        }
+        result
+    }
+
+    /// Server IANA refers a query to
+    /// Domains under new gTLDs without a `whois:` entry get `whois.nic.<tld>`; anything else falls back to the default server
+    fn iana_referral(&self, query: &str, iana_response: &str, port: u16) -> WhoisServer {
+        if let Some(host) = ServerSelector::extract_whois_server(iana_response) {
+            if host != DEFAULT_WHOIS_SERVER {
+                info!("IANA referred to: {}", host);
+            }
+            return WhoisServer::custom(host, port);
+        }
+        if let Some(host) = ServerSelector::nic_server_for(query) {
+            info!("IANA lists no WHOIS server, trying new gTLD convention: {}", host);
+            return WhoisServer::new(host, port, NIC_GUESS_NAME);
+        }
+        info!("No referral found, using default: {}", DEFAULT_WHOIS_SERVER);
+        WhoisServer::custom(DEFAULT_WHOIS_SERVER, port)
+    }
+
+    /// Log a failed `whois.nic.<tld>` guess and return the default server to use instead
+    fn nic_guess_failed(&self, guess: &WhoisServer, err: &anyhow::Error) -> WhoisServer {
+        info!("{} did not answer ({:#}), using default: {}", guess.host, err, DEFAULT_WHOIS_SERVER);
+        self.report(&format!("referral hop: {} -> {}", guess.host, DEFAULT_WHOIS_SERVER));
+        WhoisServer::custom(DEFAULT_WHOIS_SERVER, guess.port)
+    }
+
     /// Perform a WHOIS query with IANA referral if needed
     pub fn query_with_referral(&self, query: &str, initial_server: &WhoisServer) -> Result<QueryResult> {
         if initial_server.name == "IANA" {
-            if self.verbose {
-                println!("Querying IANA at: {}", initial_server.address());
-            }
+            info!("Querying IANA at: {}", initial_server.address());
 
             // First query IANA
             let iana_response = self.query_direct(query, initial_server)?;
             
             // Extract the referral WHOIS server from IANA's response
-            let whois_server_host = ServerSelector::extract_whois_server(&iana_response)
-                .unwrap_or_else(|| DEFAULT_WHOIS_SERVER.to_string());
+            let final_server = self.iana_referral(query, &iana_response, initial_server.port);
+            self.report(&format!("referral hop: IANA -> {}", final_server.host));
             
-            let final_server = WhoisServer::custom(whois_server_host, initial_server.port);
-            
-            if self.verbose {
-                if final_server.host != DEFAULT_WHOIS_SERVER {
-                    println!("IANA referred to: {}", final_server.host);
-                } else {
-                    println!("No referral found, using default: {}", DEFAULT_WHOIS_SERVER);
+            // Query the actual WHOIS server
+            let _referral = info_span!("referral", from = "IANA", to = %final_server.host).entered();
+            if let Some(result) = self.query_preferring_rdap(query, &final_server) {
+                return result;
+            }
+            match self.query_direct(query, &final_server) {
+                Ok(final_response) => Ok(QueryResult::new(final_response, final_server)),
+                Err(err) if final_server.name == NIC_GUESS_NAME => {
+                    let default = self.nic_guess_failed(&final_server, &err);
+                    let response = self.query_direct(query, &default)?;
+                    Ok(QueryResult::new(response, default))
                 }
+                Err(err) => Err(err),
             }
-            
-            // Query the actual WHOIS server
-            let final_response = self.query_direct(query, &final_server)?;
-            
-            Ok(QueryResult::new(final_response, final_server))
+        } else if initial_server.is_rwhois() {
+            let response = self.query_rwhois(query, initial_server)?;
+            Ok(QueryResult::new(response, initial_server.clone()))
         } else {
             // Direct query to specified server
-            if self.verbose {
-                println!("Using {} server: {}", initial_server.name, initial_server.address());
+            info!("Using {} server: {}", initial_server.name, initial_server.address());
+            if let Some(result) = self.query_preferring_rdap(query, initial_server) {
+                return result;
             }
 
             let response = self.query_direct(query, initial_server)?;
@@ -169,33 +672,29 @@ impl WhoisQuery {
         explicit_server: Option<&str>,
         port: u16,
     ) -> Result<QueryResult> {
-        let server = ServerSelector::select_server(
-            domain,
-            use_dn42,
-            use_bgptools,
-            explicit_server,
-            port,
-        );
+        let start = Instant::now();
+        self.reset_hops();
+        let server = self.first_server(domain, use_dn42, use_bgptools, explicit_server, port)?;
 
-        let result = self.query_with_referral(domain, &server)?;
-        
-        // Check if result is empty and fallback to RADB if needed
-        // Only fallback if we're not already using a specific server (DN42, BGPtools, or explicit server)
-        if is_empty_result(&result.response) && 
-           !use_dn42 && !use_bgptools && explicit_server.is_none() && 
-           server.name != "RADB" {
-            
-            if self.verbose {
-                println!("Empty result from RIR servers, trying RADB fallback...");
+        let result = self.retry_after_reset(|| {
+            let result = self.query_with_referral(domain, &server);
+
+            let result = result.map(|result| self.follow_embedded_referrals(domain, result));
+
+            // Only fall back if we're not already using a specific server (DN42, BGPtools, or explicit server)
+            if !use_dn42 && !use_bgptools && explicit_server.is_none() {
+                self.run_fallback_chain(domain, &server, result, false, false, false, None)
+            } else {
+                result
             }
-            
-            return self.try_radb_fallback(domain, false, false, false, None);
-        }
+        });
+        let result = recover_partial(result)?;
         
-        Ok(result)
+        Ok(self.finish_result(result, start))
     }
 
     /// Query with enhanced protocol support (v1.1 with markdown and images)
+    #[instrument(name = "lookup", skip_all, fields(target = %domain))]
     pub fn query_with_enhanced_protocol(
         &self,
         domain: &str,
@@ -208,34 +707,30 @@ impl WhoisQuery {
         port: u16,
         preferred_color_scheme: Option<&str>,
     ) -> Result<QueryResult> {
-        let server = ServerSelector::select_server(
-            domain,
-            use_dn42,
-            use_bgptools,
-            explicit_server,
-            port,
-        );
+        let start = Instant::now();
+        self.reset_hops();
+        let server = self.first_server(domain, use_dn42, use_bgptools, explicit_server, port)?;
 
-        let result = if use_server_color || enable_markdown || enable_images {
-            self.query_with_enhanced_protocol_impl(domain, &server, preferred_color_scheme, enable_markdown, enable_images)?
-        } else {
-            self.query_with_referral(domain, &server)?
-        };
+        let result = self.retry_after_reset(|| {
+            // RWhois servers do not speak the enhanced protocol
+            let result = if (use_server_color || enable_markdown || enable_images) && !server.is_rwhois() {
+                self.query_with_enhanced_protocol_impl(domain, &server, preferred_color_scheme, enable_markdown, enable_images)
+            } else {
+                self.query_with_referral(domain, &server)
+            };
 
-        // Check if result is empty and fallback to RADB if needed
-        // Only fallback if we're not already using a specific server (DN42, BGPtools, or explicit server)
-        if is_empty_result(&result.response) && 
-           !use_dn42 && !use_bgptools && explicit_server.is_none() && 
-           server.name != "RADB" {
-            
-            if self.verbose {
-                println!("Empty result from RIR servers, trying RADB fallback...");
+            let result = result.map(|result| self.follow_embedded_referrals(domain, result));
+
+            // Only fall back if we're not already using a specific server (DN42, BGPtools, or explicit server)
+            if !use_dn42 && !use_bgptools && explicit_server.is_none() {
+                self.run_fallback_chain(domain, &server, result, use_server_color, enable_markdown, enable_images, preferred_color_scheme)
+            } else {
+                result
             }
-            
-            return self.try_radb_fallback(domain, use_server_color, enable_markdown, enable_images, preferred_color_scheme);
-        }
+        });
+        let result = recover_partial(result)?;
 
-        Ok(result)
+        Ok(self.finish_result(result, start))
     }
 
     /// Legacy method for backward compatibility
@@ -250,34 +745,29 @@ impl WhoisQuery {
         port: u16,
         preferred_color_scheme: Option<&str>,
     ) -> Result<QueryResult> {
-        let server = ServerSelector::select_server(
-            domain,
-            use_dn42,
-            use_bgptools,
-            explicit_server,
-            port,
-        );
+        let start = Instant::now();
+        self.reset_hops();
+        let server = self.first_server(domain, use_dn42, use_bgptools, explicit_server, port)?;
 
-        let result = if use_server_color {
-            self.query_with_enhanced_protocol_impl(domain, &server, preferred_color_scheme, false, false)?
-        } else {
-            self.query_with_referral(domain, &server)?
-        };
+        let result = self.retry_after_reset(|| {
+            let result = if use_server_color && !server.is_rwhois() {
+                self.query_with_enhanced_protocol_impl(domain, &server, preferred_color_scheme, false, false)
+            } else {
+                self.query_with_referral(domain, &server)
+            };
 
-        // Check if result is empty and fallback to RADB if needed
-        // Only fallback if we're not already using a specific server (DN42, BGPtools, or explicit server)
-        if is_empty_result(&result.response) && 
-           !use_dn42 && !use_bgptools && explicit_server.is_none() && 
-           server.name != "RADB" {
-            
-            if self.verbose {
-                println!("Empty result from RIR servers, trying RADB fallback...");
+            let result = result.map(|result| self.follow_embedded_referrals(domain, result));
+
+            // Only fall back if we're not already using a specific server (DN42, BGPtools, or explicit server)
+            if !use_dn42 && !use_bgptools && explicit_server.is_none() {
+                self.run_fallback_chain(domain, &server, result, use_server_color, false, false, preferred_color_scheme)
+            } else {
+                result
             }
-            
-            return self.try_radb_fallback(domain, use_server_color, false, false, preferred_color_scheme);
-        }
+        });
+        let result = recover_partial(result)?;
 
-        Ok(result)
+        Ok(self.finish_result(result, start))
     }
 
     /// Implementation of enhanced protocol query (v1.1)
@@ -293,26 +783,21 @@ impl WhoisQuery {
         
         if server.name == "IANA" {
             // Handle IANA referral first
-            if self.verbose {
-                println!("Querying IANA at: {}", server.address());
-            }
+            info!("Querying IANA at: {}", server.address());
 
             let iana_response = self.query_direct(domain, server)?;
-            let whois_server_host = ServerSelector::extract_whois_server(&iana_response)
-                .unwrap_or_else(|| DEFAULT_WHOIS_SERVER.to_string());
-            
-            let final_server = WhoisServer::custom(whois_server_host, server.port);
-            
-            if self.verbose {
-                if final_server.host != DEFAULT_WHOIS_SERVER {
-                    println!("IANA referred to: {}", final_server.host);
-                } else {
-                    println!("No referral found, using default: {}", DEFAULT_WHOIS_SERVER);
-                }
-            }
+            let final_server = self.iana_referral(domain, &iana_response, server.port);
+            self.report(&format!("referral hop: IANA -> {}", final_server.host));
 
             // Try enhanced protocol with final server
-            return self.try_enhanced_protocol_query(domain, &final_server, &protocol, preferred_color_scheme, enable_markdown, enable_images);
+            let _referral = info_span!("referral", from = "IANA", to = %final_server.host).entered();
+            return match self.try_enhanced_protocol_query(domain, &final_server, &protocol, preferred_color_scheme, enable_markdown, enable_images) {
+                Err(err) if final_server.name == NIC_GUESS_NAME => {
+                    let default = self.nic_guess_failed(&final_server, &err);
+                    self.try_enhanced_protocol_query(domain, &default, &protocol, preferred_color_scheme, enable_markdown, enable_images)
+                }
+                result => result,
+            };
         } else {
             // Direct server query with enhanced protocol
             return self.try_enhanced_protocol_query(domain, server, &protocol, preferred_color_scheme, enable_markdown, enable_images);
@@ -320,7 +805,19 @@ impl WhoisQuery {
     }
 
 
+    /// Probe a server's WHOIS-COLOR capabilities; servers without support yield the defaults
+    /// Not limited per host: the probe runs beside the query it belongs to, which holds the permit
+    fn probe(&self, protocol: &WhoisColorProtocol, address: &str) -> Result<(ServerCapabilities, Duration)> {
+        self.report(&format!("probing {}", address));
+        let probe_start = Instant::now();
+        let probe_timeout = self.deadline.step_timeout(Duration::from_millis(CAPABILITY_TIMEOUT_MS), address)?;
+        let capabilities = protocol.probe_capabilities_within(address, probe_timeout).unwrap_or_default();
+        Ok((capabilities, probe_start.elapsed()))
+    }
+
     /// Try enhanced protocol query with all v1.1 features
+    /// Without cached capabilities, a plain query is sent while the server is probed; its answer is
+    /// used when the server turns out to be a standard WHOIS server, so only enhanced servers cost a second query
     fn try_enhanced_protocol_query(
         &self,
         domain: &str,
@@ -330,47 +827,182 @@ impl WhoisQuery {
         enable_markdown: bool,
         enable_images: bool,
     ) -> Result<QueryResult> {
-        // Probe server capabilities
-        let capabilities = protocol.probe_capabilities(&server.address(), self.verbose)
-            .unwrap_or_default(); // Use default (no support) if probe fails
-
-        // Perform query based on capabilities
-        let response = protocol.query_with_enhanced_protocol(
-            &server.address(),
-            domain,
+        if let Some(result) = self.query_preferring_rdap(domain, server) {
+            return result;
+        }
+        let address = server.address();
+        let query = self.server_query(&server.host, domain);
+        let auth_token = self.registry.auth_token(&server.host);
+        let cached = self.capability_cache.as_ref().and_then(|cache| cache.get(&address));
+        let (capabilities, probe_time, plain) = match cached {
+            Some(capabilities) => {
+                debug!("Using cached capabilities for {}", address);
+                (capabilities, None, None)
+            }
+            None => {
+                let (probed, plain) = thread::scope(|scope| {
+                    let probe = scope.spawn(|| self.probe(protocol, &address));
+                    let plain = self.send_query(server, &query, &ServerCapabilities::default(), None, false, false, None);
+                    let probed = probe.join().unwrap_or_else(|_| Err(anyhow!("Capability probe of {} failed unexpectedly", address)));
+                    (probed, plain)
+                });
+                let (capabilities, probe_time) = probed?;
+                if let Some(cache) = &self.capability_cache {
+                    cache.put(&address, &capabilities);
+                }
+                (capabilities, Some(probe_time), Some(plain))
+            }
+        };
+
+        // The plain answer stands unless the capabilities change what would be sent
+        let enhanced = protocol.build_query(
+            &query,
             &capabilities,
             preferred_color_scheme,
             enable_markdown,
             enable_images,
-            self.verbose
-        )?;
+            auth_token.as_deref(),
+        );
+        let standard = enhanced == format!("{}\r\n", query) && !capabilities.auth_required;
+        let (response, mut timing) = match plain {
+            Some(plain) if standard => {
+                debug!("{} is a standard WHOIS server, using the plain answer", address);
+                plain?
+            }
+            _ => self.send_query(
+                server,
+                &query,
+                &capabilities,
+                preferred_color_scheme,
+                enable_markdown,
+                enable_images,
+                auth_token.as_deref(),
+            )?,
+        };
+        timing.probe = probe_time;
+        self.record_hop(timing);
 
-        let server_colored = protocol.is_server_colored(&response);
-        Ok(QueryResult::new_with_color(response, server.clone(), server_colored))
+        let (markdown, response) = split_markdown_marker(&response);
+        let server_colored = protocol.is_server_colored(response);
+        let mut result = QueryResult::new_with_color(response.to_string(), server.clone(), server_colored);
+        result.markdown = markdown;
+        Ok(result)
+    }
+
+    /// Send one query built for the given capabilities, within the remaining deadline
+    fn send_query(
+        &self,
+        server: &WhoisServer,
+        query: &str,
+        capabilities: &ServerCapabilities,
+        preferred_color_scheme: Option<&str>,
+        enable_markdown: bool,
+        enable_images: bool,
+        auth_token: Option<&str>,
+    ) -> Result<(String, HopTiming)> {
+        let address = &server.address();
+        self.report(&format!("querying {}", address));
+        let _permit = self.acquire(address);
+        let timeout = self.deadline.step_timeout(Duration::from_secs(TIMEOUT_SECONDS), address)?;
+        WhoisColorProtocol
+            .query_with_enhanced_protocol_timed(
+                address,
+                query,
+                capabilities,
+                preferred_color_scheme,
+                enable_markdown,
+                enable_images,
+                auth_token,
+                self.registry.charset(&server.host),
+                timeout,
+            )
+            .inspect_err(|_| self.record_error(address))
+            .map_err(|err| self.deadline.explain(err, address))
     }
 
-    /// Try RADB fallback when RIR servers return empty results
-    fn try_radb_fallback(
+    /// Walk the fallback chain while the latest outcome matches a step's conditions
+    /// Each step that runs replaces the outcome; if a step fails, the last real answer is kept
+    fn run_fallback_chain(
         &self,
         domain: &str,
+        primary: &WhoisServer,
+        result: Result<QueryResult>,
         use_server_color: bool,
         enable_markdown: bool,
         enable_images: bool,
         preferred_color_scheme: Option<&str>,
     ) -> Result<QueryResult> {
-        let radb_server = WhoisServer::radb();
-        
-        if self.verbose {
-            println!("Querying RADB at: {}", radb_server.address());
+        let mut outcome = result;
+        let mut last_answer: Option<QueryResult> = None;
+        let mut answered_by = None;
+
+        for (index, step) in self.fallback_chain.iter().enumerate() {
+            let Some(condition) = self.classify_outcome(&outcome) else {
+                break;
+            };
+            if !step.applies_to(condition) {
+                continue;
+            }
+            let server = step.to_server();
+            if server.host == primary.host {
+                continue;
+            }
+
+            let source = outcome.as_ref().map(|result| result.server_used.host.clone()).unwrap_or_else(|_| primary.host.clone());
+            info!("{} from {}, trying fallback step {}: {}", describe_condition(condition), source, index + 1, server.address());
+            self.report(&format!("fallback: {}", server.host));
+            let _fallback = info_span!("fallback", step = index + 1, server = %server.host).entered();
+
+            if let Ok(result) = outcome {
+                last_answer = Some(result);
+            }
+            outcome = self
+                .query_fallback_server(domain, &server, use_server_color, enable_markdown, enable_images, preferred_color_scheme)
+                .map(|mut result| {
+                    result.fallback = Some(FallbackInfo {
+                        step: index + 1,
+                        server: server.address(),
+                        reason: condition,
+                        from: source,
+                    });
+                    result
+                });
+            answered_by = Some((index + 1, server.address()));
         }
-        
+
+        if let Some((step, address)) = answered_by {
+            match &outcome {
+                Err(err) => {
+                    if let Some(answer) = last_answer {
+                        info!("Fallback failed ({:#}), keeping answer from {}", err, answer.server_used.host);
+                        return Ok(answer);
+                    }
+                }
+                Ok(_) if self.classify_outcome(&outcome).is_none() => {
+                    info!("Answer from fallback step {}: {}", step, address);
+                }
+                Ok(_) => {}
+            }
+        }
+        outcome
+    }
+
+    /// Query a single fallback server
+    fn query_fallback_server(
+        &self,
+        domain: &str,
+        server: &WhoisServer,
+        use_server_color: bool,
+        enable_markdown: bool,
+        enable_images: bool,
+        preferred_color_scheme: Option<&str>,
+    ) -> Result<QueryResult> {
         if use_server_color || enable_markdown || enable_images {
-            // Try enhanced protocol with RADB
-            self.query_with_enhanced_protocol_impl(domain, &radb_server, preferred_color_scheme, enable_markdown, enable_images)
+            // Try enhanced protocol with the fallback server
+            self.query_with_enhanced_protocol_impl(domain, server, preferred_color_scheme, enable_markdown, enable_images)
         } else {
-            // Direct query to RADB
-            let response = self.query_direct(domain, &radb_server)?;
-            Ok(QueryResult::new(response, radb_server))
+            let response = self.query_direct(domain, server)?;
+            Ok(QueryResult::new(response, server.clone()))
         }
     }
 
@@ -379,6 +1011,282 @@ impl WhoisQuery {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+    use crate::servers::{IANA_WHOIS_SERVER, RADB_WHOIS_SERVER};
+
+    /// Serve a single WHOIS response on a local port
+    fn one_shot_server(response: &'static str) -> (WhoisServer, thread::JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = thread::spawn(move || {
+            let (mut socket, _) = listener.accept().unwrap();
+            let mut request = [0u8; 256];
+            let _ = socket.read(&mut request);
+            socket.write_all(response.as_bytes()).unwrap();
+        });
+        (WhoisServer::new("127.0.0.1", port, "Test"), handle)
+    }
+
+    #[test]
+    fn test_classify_outcome() {
+        let query = WhoisQuery::new();
+        let server = WhoisServer::radb();
+        let empty = Ok(QueryResult::new("% No entries found".to_string(), server.clone()));
+        assert_eq!(query.classify_outcome(&empty), Some(FallbackCondition::Empty));
+
+        let answer = Ok(QueryResult::new("aut-num: AS3333\nas-name: RIPE-NCC-AS\n".to_string(), server));
+        assert_eq!(query.classify_outcome(&answer), None);
+
+        let timeout: Result<QueryResult> = Err(QueryError::Timeout { server: "x:43".to_string() }.into());
+        assert_eq!(query.classify_outcome(&timeout), Some(FallbackCondition::Timeout));
+
+        let other: Result<QueryResult> = Err(anyhow::anyhow!("connection refused"));
+        assert_eq!(query.classify_outcome(&other), None);
+    }
+
+    #[test]
+    fn test_inverse_and_type_flags_per_server() {
+        let query = WhoisQuery::new().with_inverse("origin").with_object_types("route,route6");
+        assert_eq!(query.server_query("whois.afrinic.net", "AS37100"), "-i origin -T route,route6 AS37100");
+        assert_eq!(query.server_query("whois.radb.net", "AS37100"), "-i origin -T route,route6 AS37100");
+        assert_eq!(query.server_query("whois.arin.net", "AS37100"), "AS37100");
+        assert_eq!(query.server_query("whois.iana.org", "AS37100"), "AS37100");
+    }
+
+    #[test]
+    fn test_classify_uses_server_profile() {
+        let mut registry = ServerRegistry::default();
+        registry.profiles.insert(
+            "whois.nic.example".to_string(),
+            crate::servers::ServerProfile {
+                empty_result: Some(EmptyResultPolicy { short_response_length: 0, ..Default::default() }),
+                ..Default::default()
+            },
+        );
+        let query = WhoisQuery::new().with_registry(registry);
+
+        let terse = QueryResult::new("active".to_string(), WhoisServer::custom("whois.nic.example", 43));
+        assert_eq!(query.classify(&terse), Classification::Answer);
+        let elsewhere = QueryResult::new("active".to_string(), WhoisServer::radb());
+        assert_eq!(query.classify(&elsewhere), Classification::Empty);
+    }
+
+    #[test]
+    fn test_rdap_failure_falls_back_to_port_43() {
+        let (server, handle) = one_shot_server("% Query rate limit exceeded. Please try again later.\n");
+        let mut registry = ServerRegistry::default();
+        let profile = registry.profiles.entry("127.0.0.1".to_string()).or_default();
+        // Nothing listens on port 1, so the RDAP request fails
+        profile.rdap = Some("http://127.0.0.1:1/rdap".to_string());
+        profile.prefer_rdap = Some(true);
+        let query = WhoisQuery::new().with_registry(registry);
+
+        let err = query.query_with_referral("200.3.12.1", &server).unwrap_err();
+        handle.join().unwrap();
+        assert!(matches!(err.downcast_ref::<QueryError>(), Some(QueryError::RateLimited { .. })));
+        assert!(query.query_preferring_rdap("example.com.br", &server).is_none());
+    }
+
+    #[test]
+    fn test_fallback_chain_skips_steps_by_condition() {
+        let (answer_server, handle) = one_shot_server("route: 192.0.2.0/24\norigin: AS64500\nsource: TEST\n");
+        let chain = vec![
+            // Only applies to timeouts, so it must be skipped
+            FallbackStep::new(&WhoisServer::new("127.0.0.1", 1, "Unused"), &[FallbackCondition::Timeout]),
+            FallbackStep::new(&answer_server, &[FallbackCondition::Empty]),
+        ];
+        let query = WhoisQuery::new().with_fallback_chain(chain);
+
+        let primary = WhoisServer::default();
+        let empty = Ok(QueryResult::new("% No entries found".to_string(), primary.clone()));
+        let result = query
+            .run_fallback_chain("192.0.2.0/24", &primary, empty, false, false, false, None)
+            .unwrap();
+        handle.join().unwrap();
+
+        assert_eq!(result.server_used.port, answer_server.port);
+        assert!(result.response.contains("origin: AS64500"));
+        let fallback = result.fallback.unwrap();
+        assert_eq!(fallback.step, 2);
+        assert_eq!(fallback.reason, FallbackCondition::Empty);
+        assert_eq!(fallback.from, primary.host);
+    }
+
+    #[test]
+    fn test_fallback_failure_keeps_previous_answer() {
+        // Nothing listens on port 1, so the fallback step fails
+        let chain = vec![FallbackStep::new(&WhoisServer::new("127.0.0.1", 1, "Down"), &[FallbackCondition::Empty])];
+        let query = WhoisQuery::new().with_fallback_chain(chain);
+
+        let primary = WhoisServer::default();
+        let empty = Ok(QueryResult::new("% No entries found".to_string(), primary.clone()));
+        let result = query
+            .run_fallback_chain("192.0.2.0/24", &primary, empty, false, false, false, None)
+            .unwrap();
+        assert_eq!(result.server_used.host, primary.host);
+    }
+
+    #[test]
+    fn test_deadline_cuts_lookup_short() {
+        // Accepts the connection but never answers
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = thread::spawn(move || {
+            let (socket, _) = listener.accept().unwrap();
+            thread::sleep(Duration::from_secs(1));
+            drop(socket);
+        });
+
+        let start = Instant::now();
+        let query = WhoisQuery::new().with_deadline(Deadline::after(Duration::from_millis(300)).unwrap());
+        let err = query.query("AS64500", false, false, Some("127.0.0.1"), port).unwrap_err();
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert!(matches!(err.downcast_ref::<QueryError>(), Some(QueryError::DeadlineExceeded { .. })));
+        assert_eq!(query.classify_outcome(&Err(err)), None);
+        handle.join().unwrap();
+    }
+
+    /// Serve `connections` requests, answering probes with `capabilities` and queries with the request itself
+    fn echo_server(capabilities: &'static str, connections: usize) -> (u16, thread::JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = thread::spawn(move || {
+            for socket in listener.incoming().take(connections) {
+                let mut socket = socket.unwrap();
+                let mut request = [0u8; 512];
+                let read = socket.read(&mut request).unwrap();
+                let request = String::from_utf8_lossy(&request[..read]).to_string();
+                let answer = if request.starts_with("X-WHOIS-COLOR-PROBE") {
+                    capabilities.to_string()
+                } else {
+                    format!("% request: {:?}\naut-num: AS64500\n", request)
+                };
+                socket.write_all(answer.as_bytes()).unwrap();
+            }
+        });
+        (port, handle)
+    }
+
+    #[test]
+    fn test_plain_answer_used_for_standard_server() {
+        // One probe and one plain query, nothing else
+        let (port, handle) = echo_server("", 2);
+        let query = WhoisQuery::new();
+        let result = query
+            .query_with_enhanced_protocol("AS64500", false, false, true, false, false, Some("127.0.0.1"), port, None)
+            .unwrap();
+        assert!(result.response.contains(r#"% request: "AS64500\r\n""#));
+        assert!(result.timing.hops[0].probe.is_some());
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_enhanced_server_queried_again() {
+        let (port, handle) = echo_server("X-WHOIS-COLOR-SUPPORT: v1.0 schemes=ripe\r\n", 3);
+        let query = WhoisQuery::new();
+        let result = query
+            .query_with_enhanced_protocol("AS64500", false, false, true, false, false, Some("127.0.0.1"), port, None)
+            .unwrap();
+        assert!(result.response.contains("X-WHOIS-COLOR: scheme=ripe"));
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_route_by_suffix() {
+        let query = WhoisQuery::new().with_suffix_list(Arc::new(SuffixList::builtin()));
+        assert_eq!(query.route_by_suffix("example.us.com", WhoisServer::iana()).host, "whois.centralnic.com");
+        assert_eq!(query.route_by_suffix("example.co.uk", WhoisServer::iana()).host, IANA_WHOIS_SERVER);
+        assert_eq!(query.route_by_suffix("example.us.com", WhoisServer::radb()).host, RADB_WHOIS_SERVER);
+        assert_eq!(WhoisQuery::new().route_by_suffix("example.us.com", WhoisServer::iana()).host, IANA_WHOIS_SERVER);
+    }
+
+    #[test]
+    fn test_queue_key() {
+        let query = WhoisQuery::new().with_suffix_list(Arc::new(SuffixList::builtin()));
+        let key = |target: &str| query.queue_key(target, false, false, None, 43).unwrap();
+        // ARIN and RIPE targets queue apart, domains by their TLD
+        assert_eq!(key("8.8.8.8"), "whois.arin.net:43");
+        assert_eq!(key("AS15169"), "whois.arin.net:43");
+        assert_eq!(key("193.0.0.0/21"), "whois.ripe.net:43");
+        assert_eq!(key("AS3333"), "whois.ripe.net:43");
+        assert_ne!(key("8.8.8.8"), key("193.0.6.139"));
+        assert_eq!(key("example.com"), ".com");
+        assert_eq!(key("Example.ORG."), ".org");
+        assert_eq!(key("example.us.com"), "whois.centralnic.com:43");
+        assert_eq!(key("10.0.0.1"), "whois.iana.org:43");
+        assert_eq!(query.queue_key("8.8.8.8", false, false, Some("whois.radb.net"), 43).unwrap(), "whois.radb.net:43");
+    }
+
+    #[test]
+    fn test_recover_partial() {
+        let interrupted = QueryError::Interrupted {
+            server: "whois.radb.net:43".to_string(),
+            received: "route: 192.0.2.0/24\n".to_string(),
+        };
+        let result = recover_partial(Err(interrupted.into())).unwrap();
+        assert!(result.partial);
+        assert_eq!(result.server_used.address(), "whois.radb.net:43");
+        assert_eq!(result.response, "route: 192.0.2.0/24\n");
+
+        assert!(!result.connection_reset);
+        assert!(recover_partial(Err(QueryError::Cancelled.into())).is_err());
+    }
+
+    /// Answer one connection per entry; a `true` entry sends its text and then resets the connection
+    fn resetting_server(answers: &'static [(&'static str, bool)]) -> (u16, thread::JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = thread::spawn(move || {
+            for ((answer, reset), socket) in answers.iter().zip(listener.incoming()) {
+                let mut socket = socket.unwrap();
+                if *reset {
+                    // Closing with the request still unread makes the kernel send a reset
+                    socket.write_all(answer.as_bytes()).unwrap();
+                    thread::sleep(Duration::from_millis(200));
+                } else {
+                    let mut request = [0u8; 512];
+                    let _ = socket.read(&mut request).unwrap();
+                    socket.write_all(answer.as_bytes()).unwrap();
+                }
+            }
+        });
+        (port, handle)
+    }
+
+    #[test]
+    fn test_connection_reset_keeps_partial_answer() {
+        const FULL: &str = "aut-num: AS64500\nas-name: EXAMPLE\nsource: TEST\n";
+        let (port, handle) = resetting_server(&[("aut-num: AS64500\n", true)]);
+        let result = WhoisQuery::new().query("AS64500", false, false, Some("127.0.0.1"), port).unwrap();
+        handle.join().unwrap();
+        assert!(result.partial && result.connection_reset);
+        assert_eq!(result.response, "aut-num: AS64500\n");
+
+        let (port, handle) = resetting_server(&[("aut-num: AS64500\n", true), (FULL, false)]);
+        let result = WhoisQuery::new().with_reset_retry(true).query("AS64500", false, false, Some("127.0.0.1"), port).unwrap();
+        handle.join().unwrap();
+        assert!(!result.partial);
+        assert_eq!(result.response, FULL);
+    }
+
+    #[test]
+    fn test_follows_embedded_referral() {
+        let (authoritative, authoritative_handle) = one_shot_server("inetnum: 192.0.2.0 - 192.0.2.255\nnetname: EXAMPLE-NET\n");
+        let stub = Box::leak(format!("NetRange: 192.0.2.0 - 192.0.2.255\nReferralServer: whois://{}\n", authoritative.address()).into_boxed_str());
+        let (primary, primary_handle) = one_shot_server(stub);
+
+        let result = WhoisQuery::new()
+            .query("192.0.2.1", false, false, Some(&primary.host), primary.port)
+            .unwrap();
+        primary_handle.join().unwrap();
+        authoritative_handle.join().unwrap();
+
+        assert_eq!(result.server_used.address(), authoritative.address());
+        assert!(result.response.contains("EXAMPLE-NET"));
+        assert_eq!(result.timing.hops.len(), 2);
+    }
 
     #[test]
     fn test_is_empty_result_completely_empty() {
@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// Per-server politeness limits shared by every query in a run
+/// Caps the number of concurrent connections to each server and enforces a
+/// minimum delay between connections to the same server
+pub struct HostLimiter {
+    per_host: usize,
+    delay: Duration,
+    hosts: Mutex<HashMap<String, HostState>>,
+    released: Condvar,
+}
+
+#[derive(Default)]
+struct HostState {
+    active: usize,
+    last_start: Option<Instant>,
+}
+
+/// Held while a connection to a server is open; frees the slot when dropped
+pub struct HostPermit<'a> {
+    limiter: &'a HostLimiter,
+    host: String,
+}
+
+impl HostLimiter {
+    pub fn new(per_host: usize, delay: Duration) -> Self {
+        Self {
+            per_host: per_host.max(1),
+            delay,
+            hosts: Mutex::new(HashMap::new()),
+            released: Condvar::new(),
+        }
+    }
+
+    /// Wait until a connection to `host` is allowed
+    pub fn acquire(&self, host: &str) -> HostPermit<'_> {
+        let mut hosts = self.lock();
+        loop {
+            let state = hosts.entry(host.to_string()).or_default();
+            let ready_at = state.last_start.map(|last| last + self.delay);
+            let now = Instant::now();
+
+            if state.active < self.per_host {
+                match ready_at {
+                    Some(ready_at) if ready_at > now => {
+                        let wait = ready_at - now;
+                        hosts = self
+                            .released
+                            .wait_timeout(hosts, wait)
+                            .map(|(guard, _)| guard)
+                            .unwrap_or_else(|poisoned| poisoned.into_inner().0);
+                    }
+                    _ => {
+                        state.active += 1;
+                        state.last_start = Some(now);
+                        return HostPermit {
+                            limiter: self,
+                            host: host.to_string(),
+                        };
+                    }
+                }
+            } else {
+                hosts = self
+                    .released
+                    .wait(hosts)
+                    .unwrap_or_else(|poisoned| poisoned.into_inner());
+            }
+        }
+    }
+
+    fn release(&self, host: &str) {
+        if let Some(state) = self.lock().get_mut(host) {
+            state.active = state.active.saturating_sub(1);
+        }
+        self.released.notify_all();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, HostState>> {
+        self.hosts.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Drop for HostPermit<'_> {
+    fn drop(&mut self) {
+        self.limiter.release(&self.host);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+
+    #[test]
+    fn test_delay_between_connections_to_same_host() {
+        let limiter = HostLimiter::new(1, Duration::from_millis(50));
+        let start = Instant::now();
+        drop(limiter.acquire("whois.ripe.net"));
+        drop(limiter.acquire("whois.ripe.net"));
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[test]
+    fn test_other_hosts_are_not_delayed() {
+        let limiter = HostLimiter::new(1, Duration::from_secs(10));
+        let _ripe = limiter.acquire("whois.ripe.net");
+        let start = Instant::now();
+        let _arin = limiter.acquire("whois.arin.net");
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_per_host_concurrency_cap() {
+        let limiter = HostLimiter::new(2, Duration::ZERO);
+        let running = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+
+        thread::scope(|scope| {
+            for _ in 0..6 {
+                scope.spawn(|| {
+                    let _permit = limiter.acquire("whois.radb.net");
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    thread::sleep(Duration::from_millis(10));
+                    running.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+        });
+
+        assert!(peak.load(Ordering::SeqCst) <= 2);
+    }
+}
@@ -0,0 +1,201 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+use tracing::info;
+
+use crate::error::QueryError;
+use crate::related::is_network_target;
+use crate::ripestat::parse_asn;
+
+/// Width of the attribute name column in converted answers, as in RIPE-style output
+const NAME_WIDTH: usize = 16;
+
+/// RDAP path of a query: `ip/…` for addresses and prefixes, `autnum/…` for ASNs and
+/// `entity/…` for handles; domains and free text have no RDAP path here
+pub fn path_for(query: &str) -> Option<String> {
+    let query = query.trim();
+    if is_network_target(query) {
+        return Some(format!("ip/{}", query));
+    }
+    if let Some(asn) = parse_asn(&query.to_uppercase()).or_else(|| query.parse().ok()) {
+        return Some(format!("autnum/{}", asn));
+    }
+    let handle = !query.is_empty() && !query.contains('.') && query.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+    handle.then(|| format!("entity/{}", query))
+}
+
+/// Fetch an RDAP object; `None` when the server has no such object
+/// HTTP 429 is reported as `QueryError::RateLimited`
+pub fn fetch(base_url: &str, path: &str, timeout: Duration) -> Result<Option<Value>> {
+    let url = format!("{}/{}", base_url.trim_end_matches('/'), path);
+    info!("Fetching RDAP {}", url);
+    let agent = ureq::AgentBuilder::new()
+        .timeout(timeout)
+        .user_agent(concat!("whois-cli/", env!("CARGO_PKG_VERSION")))
+        .build();
+    match agent.get(&url).set("Accept", "application/rdap+json").call() {
+        Ok(response) => Ok(Some(response.into_json().with_context(|| format!("Invalid RDAP response from {}", url))?)),
+        Err(ureq::Error::Status(404, _)) => Ok(None),
+        Err(ureq::Error::Status(429, _)) => Err(QueryError::RateLimited { server: base_url.to_string() }.into()),
+        Err(err) => Err(err).with_context(|| format!("RDAP request to {} failed", url)),
+    }
+}
+
+/// An RDAP object as WHOIS text: the object itself, then one block per related entity
+pub fn to_whois(object: &Value) -> String {
+    let mut blocks = vec![object_lines(object)];
+    let mut seen: Vec<String> = Vec::new();
+    let mut pending: Vec<&Value> = entities(object).into_iter().rev().collect();
+    while let Some(entity) = pending.pop() {
+        let handle = entity["handle"].as_str().unwrap_or_default().to_string();
+        if !handle.is_empty() && seen.contains(&handle) {
+            continue;
+        }
+        seen.push(handle);
+        blocks.push(object_lines(entity));
+        pending.extend(entities(entity).into_iter().rev());
+    }
+    blocks.into_iter().filter(|block| !block.is_empty()).map(|block| block.join("\n")).collect::<Vec<_>>().join("\n\n") + "\n"
+}
+
+fn entities(object: &Value) -> Vec<&Value> {
+    object["entities"].as_array().map(|list| list.iter().collect()).unwrap_or_default()
+}
+
+fn object_lines(object: &Value) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut push = |name: &str, value: &str| {
+        if !value.trim().is_empty() {
+            lines.push(format!("{:<width$}{}", format!("{}:", name), value.trim(), width = NAME_WIDTH));
+        }
+    };
+    let text = |key: &str| object[key].as_str().unwrap_or_default();
+
+    match text("objectClassName") {
+        "ip network" => {
+            let class = if text("ipVersion") == "v6" { "inet6num" } else { "inetnum" };
+            push(class, &format!("{} - {}", text("startAddress"), text("endAddress")));
+            for cidr in object["cidr0_cidrs"].as_array().into_iter().flatten() {
+                let prefix = cidr["v4prefix"].as_str().or(cidr["v6prefix"].as_str()).unwrap_or_default();
+                push("cidr", &format!("{}/{}", prefix, cidr["length"]));
+            }
+            push("netname", text("name"));
+            push("handle", text("handle"));
+            push("parent", text("parentHandle"));
+        }
+        "autnum" => {
+            let (start, end) = (object["startAutnum"].as_u64(), object["endAutnum"].as_u64());
+            match (start, end) {
+                (Some(start), Some(end)) if end != start => push("aut-num", &format!("AS{} - AS{}", start, end)),
+                (Some(start), _) => push("aut-num", &format!("AS{}", start)),
+                _ => push("aut-num", text("handle")),
+            }
+            push("as-name", text("name"));
+        }
+        _ => {
+            push("entity", text("handle"));
+            let roles = string_list(&object["roles"]);
+            push("roles", &roles.join(", "));
+            vcard_lines(&object["vcardArray"], &mut push);
+        }
+    }
+    push("type", text("type"));
+    push("status", &string_list(&object["status"]).join(", "));
+    push("country", text("country"));
+    for remark in object["remarks"].as_array().into_iter().flatten() {
+        for line in string_list(&remark["description"]) {
+            push("remarks", &line);
+        }
+    }
+    for event in object["events"].as_array().into_iter().flatten() {
+        let name = match event["eventAction"].as_str() {
+            Some("registration") => "created",
+            Some("last changed") => "last-modified",
+            _ => continue,
+        };
+        push(name, event["eventDate"].as_str().unwrap_or_default());
+    }
+    lines
+}
+
+/// Name, email, phone and address of a jCard (`["vcard", [[name, params, type, value], ...]]`)
+fn vcard_lines(vcard: &Value, push: &mut impl FnMut(&str, &str)) {
+    for property in vcard[1].as_array().into_iter().flatten() {
+        let value = &property[3];
+        match property[0].as_str() {
+            Some("fn") => push("name", value.as_str().unwrap_or_default()),
+            Some("email") => push("e-mail", value.as_str().unwrap_or_default()),
+            Some("tel") => push("phone", value.as_str().unwrap_or_default().trim_start_matches("tel:")),
+            Some("adr") => {
+                let address = match property[1]["label"].as_str() {
+                    Some(label) => label.replace('\n', ", "),
+                    None => string_list(value).join(", "),
+                };
+                push("address", &address);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Strings of a JSON array, flattening nested arrays (jCard address parts)
+fn string_list(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(text) if !text.is_empty() => vec![text.clone()],
+        Value::Array(items) => items.iter().flat_map(string_list).collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_path_for() {
+        assert_eq!(path_for("200.3.12.1").as_deref(), Some("ip/200.3.12.1"));
+        assert_eq!(path_for("2801:10::/32").as_deref(), Some("ip/2801:10::/32"));
+        assert_eq!(path_for("as28000").as_deref(), Some("autnum/28000"));
+        assert_eq!(path_for("28000").as_deref(), Some("autnum/28000"));
+        assert_eq!(path_for("BR-ABCD-LACNIC").as_deref(), Some("entity/BR-ABCD-LACNIC"));
+        assert_eq!(path_for("example.com.br"), None);
+    }
+
+    #[test]
+    fn test_to_whois() {
+        let network = json!({
+            "objectClassName": "ip network",
+            "handle": "200.3.12.0/22",
+            "startAddress": "200.3.12.0",
+            "endAddress": "200.3.15.255",
+            "ipVersion": "v4",
+            "cidr0_cidrs": [{"v4prefix": "200.3.12.0", "length": 22}],
+            "type": "ALLOCATED PA",
+            "status": ["active"],
+            "country": "UY",
+            "events": [{"eventAction": "registration", "eventDate": "1999-01-01T00:00:00Z"}],
+            "entities": [{
+                "objectClassName": "entity",
+                "handle": "UY-LACN-LACNIC",
+                "roles": ["registrant"],
+                "vcardArray": ["vcard", [["version", {}, "text", "4.0"], ["fn", {}, "text", "LACNIC"],
+                    ["adr", {"label": "Rambla Republica de Mexico 6125\nMontevideo"}, "text", ["", "", "", "", "", "", ""]]]],
+                "entities": [{"objectClassName": "entity", "handle": "ABU-LACNIC", "roles": ["abuse"],
+                    "vcardArray": ["vcard", [["email", {}, "text", "abuse@lacnic.net"], ["tel", {}, "uri", "tel:+598-2-604-2222"]]]}]
+            }]
+        });
+        assert_eq!(
+            to_whois(&network),
+            "inetnum:        200.3.12.0 - 200.3.15.255\ncidr:           200.3.12.0/22\nhandle:         200.3.12.0/22\n\
+             type:           ALLOCATED PA\nstatus:         active\ncountry:        UY\ncreated:        1999-01-01T00:00:00Z\n\n\
+             entity:         UY-LACN-LACNIC\nroles:          registrant\nname:           LACNIC\n\
+             address:        Rambla Republica de Mexico 6125, Montevideo\n\n\
+             entity:         ABU-LACNIC\nroles:          abuse\ne-mail:         abuse@lacnic.net\nphone:          +598-2-604-2222\n"
+        );
+
+        let autnum = json!({"objectClassName": "autnum", "startAutnum": 28000, "endAutnum": 28000, "name": "LACNIC"});
+        assert_eq!(to_whois(&autnum), "aut-num:        AS28000\nas-name:        LACNIC\n");
+    }
+}
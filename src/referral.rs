@@ -0,0 +1,181 @@
+use std::fmt;
+use std::net::Ipv6Addr;
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+use crate::servers::{split_host_port, WhoisServer, DEFAULT_WHOIS_PORT};
+
+/// Default RWhois port
+pub const RWHOIS_PORT: u16 = 4321;
+
+/// National Internet Registries APNIC delegates address space to: name, full name and WHOIS server
+pub const NIRS: &[(&str, &str, &str)] = &[
+    ("JPNIC", "Japan Network Information Center", "whois.nic.ad.jp"),
+    ("KRNIC", "Korea Internet & Security Agency", "whois.kisa.or.kr"),
+    ("TWNIC", "Taiwan Network Information Center", "whois.twnic.net"),
+];
+
+/// Protocol a referral points to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReferralScheme {
+    Whois,
+    Rwhois,
+}
+
+/// A server named inside a response, e.g. ARIN's `ReferralServer:` line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Referral {
+    pub scheme: ReferralScheme,
+    pub host: String,
+    pub port: u16,
+}
+
+impl Referral {
+    pub fn to_server(&self) -> WhoisServer {
+        let name = match self.scheme {
+            ReferralScheme::Whois => "Referral",
+            ReferralScheme::Rwhois => "RWhois",
+        };
+        WhoisServer::new(self.host.clone(), self.port, name)
+    }
+}
+
+impl fmt::Display for Referral {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let scheme = match self.scheme {
+            ReferralScheme::Whois => "whois",
+            ReferralScheme::Rwhois => "rwhois",
+        };
+        write!(f, "{}://{}:{}", scheme, self.host, self.port)
+    }
+}
+
+/// Find a referral to another server in a response
+/// Recognizes `ReferralServer: whois://host[:port]` / `rwhois://host[:port]` (ARIN),
+/// remarks such as "please query whois.nic.ad.jp" and APNIC blocks delegated to an NIR
+pub fn extract_referral(response: &str) -> Option<Referral> {
+    static REFERRAL_SERVER: OnceLock<Regex> = OnceLock::new();
+    let referral_server = REFERRAL_SERVER.get_or_init(|| Regex::new(r"(?im)^\s*ReferralServer:\s*(\S+)").unwrap());
+    if let Some(referral) = referral_server
+        .captures(response)
+        .and_then(|caps| parse_referral_url(&caps[1]))
+    {
+        return Some(referral);
+    }
+
+    // Only host names that look like WHOIS servers, so links to web pages are not followed
+    static PLEASE_QUERY: OnceLock<Regex> = OnceLock::new();
+    let please_query = PLEASE_QUERY.get_or_init(|| {
+        Regex::new(
+            r"(?i)\bplease (?:query|use|see)\s+(?:the\s+)?(?:whois\s+(?:server\s+)?)?((?:r?whois://)?[a-z0-9.-]*whois[a-z0-9-]*\.[a-z0-9.-]+(?::\d+)?)",
+        )
+        .unwrap()
+    });
+    please_query
+        .captures(response)
+        .and_then(|caps| parse_referral_url(caps[1].trim_end_matches('.')))
+        .or_else(|| nir_referral(response))
+}
+
+/// NIR server for an APNIC block delegated to it: a `JPNIC-`/`KRNIC-`/`TWNIC-` netname,
+/// the NIR's full name in `descr:`, or remarks like "Refer to JPNIC" / "allocated to KRNIC"
+fn nir_referral(response: &str) -> Option<Referral> {
+    static NETNAME: OnceLock<Regex> = OnceLock::new();
+    let netname = NETNAME.get_or_init(|| Regex::new(r"(?im)^netname:\s*(JPNIC|KRNIC|TWNIC)-").unwrap());
+    static REMARK: OnceLock<Regex> = OnceLock::new();
+    let remark = REMARK.get_or_init(|| Regex::new(r"(?i)\b(?:refer to|allocated to|delegated to|query)\s+(?:the\s+)?(JPNIC|KRNIC|TWNIC)\b").unwrap());
+    let name = netname
+        .captures(response)
+        .or_else(|| remark.captures(response))
+        .map(|caps| caps[1].to_uppercase());
+    let (_, _, host) = NIRS.iter().find(|(nir, full_name, _)| {
+        name.as_deref() == Some(*nir)
+            || response.lines().any(|line| line.starts_with("descr:") && line.to_lowercase().contains(&full_name.to_lowercase()))
+    })?;
+    parse_referral_url(host)
+}
+
+/// Whether a host is the WHOIS server of an NIR
+pub fn is_nir_server(host: &str) -> bool {
+    NIRS.iter().any(|(_, _, server)| server.eq_ignore_ascii_case(host))
+}
+
+/// Parse `whois://host:port`, `rwhois://host:port` or a bare host name
+pub fn parse_referral_url(url: &str) -> Option<Referral> {
+    let (scheme, rest) = match url.split_once("://") {
+        Some((scheme, rest)) if scheme.eq_ignore_ascii_case("whois") => (ReferralScheme::Whois, rest),
+        Some((scheme, rest)) if scheme.eq_ignore_ascii_case("rwhois") => (ReferralScheme::Rwhois, rest),
+        // http(s) links and other schemes are not WHOIS servers
+        Some(_) => return None,
+        None => (ReferralScheme::Whois, url),
+    };
+    let authority = rest.split('/').next().unwrap_or(rest);
+    let default_port = match scheme {
+        ReferralScheme::Whois => DEFAULT_WHOIS_PORT,
+        ReferralScheme::Rwhois => RWHOIS_PORT,
+    };
+    let (host, port) = split_host_port(authority, default_port).ok()?;
+    if !(host.parse::<Ipv6Addr>().is_ok() || (host.contains('.') && !host.contains(':'))) {
+        return None;
+    }
+    Some(Referral {
+        scheme,
+        host: host.to_lowercase(),
+        port,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arin_referral_server() {
+        let response = "NetRange:       192.0.2.0 - 192.0.2.255\nReferralServer:  rwhois://rwhois.example.net:4321/\n";
+        assert_eq!(
+            extract_referral(response),
+            Some(Referral {
+                scheme: ReferralScheme::Rwhois,
+                host: "rwhois.example.net".to_string(),
+                port: 4321,
+            })
+        );
+
+        let response = "ReferralServer:  whois://whois.apnic.net\n";
+        let referral = extract_referral(response).unwrap();
+        assert_eq!(referral.scheme, ReferralScheme::Whois);
+        assert_eq!(referral.to_server().address(), "whois.apnic.net:43");
+    }
+
+    #[test]
+    fn test_please_query_remark() {
+        let response = "inetnum:  192.0.2.0 - 192.0.2.255\nremarks:  This block was transferred, please query whois.nic.ad.jp.\n";
+        assert_eq!(extract_referral(response).unwrap().host, "whois.nic.ad.jp");
+        assert_eq!(extract_referral("aut-num: AS3333\nremarks: please see our website\n"), None);
+        assert_eq!(extract_referral("remarks: please see www.example.net/abuse\n"), None);
+    }
+
+    #[test]
+    fn test_nir_delegations() {
+        let jpnic = "inetnum:        133.0.0.0 - 133.255.255.255\nnetname:        JPNIC-NET-JP\ncountry:        JP\n";
+        assert_eq!(extract_referral(jpnic).unwrap().host, "whois.nic.ad.jp");
+        let krnic = "inetnum:  211.104.0.0 - 211.119.255.255\nnetname:  KORNET\nremarks:  This IP address space has been allocated to KRNIC.\n";
+        assert_eq!(extract_referral(krnic).unwrap().host, "whois.kisa.or.kr");
+        let twnic = "inetnum:  1.160.0.0 - 1.175.255.255\nnetname:  HINET-NET\ndescr:    Taiwan Network Information Center\n";
+        assert_eq!(extract_referral(twnic).unwrap().host, "whois.twnic.net");
+        assert!(is_nir_server("WHOIS.NIC.AD.JP"));
+        assert_eq!(extract_referral("inetnum:  203.0.113.0 - 203.0.113.255\nnetname:  TEST-NET-3\ncountry:  AU\n"), None);
+    }
+
+    #[test]
+    fn test_parse_referral_url() {
+        assert_eq!(parse_referral_url("rwhois://rwhois.example.net").unwrap().port, RWHOIS_PORT);
+        assert_eq!(parse_referral_url("whois.example.net:4343").unwrap().port, 4343);
+        assert_eq!(parse_referral_url("https://rdap.arin.net/registry"), None);
+        assert_eq!(parse_referral_url("localhost"), None);
+        let referral = parse_referral_url("whois://[2001:DB8::43]:4343").unwrap();
+        assert_eq!((referral.host.as_str(), referral.port), ("2001:db8::43", 4343));
+        assert_eq!(parse_referral_url("whois.example.net:port"), None);
+    }
+}
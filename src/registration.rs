@@ -0,0 +1,308 @@
+use serde::Serialize;
+
+use crate::ansi::strip_ansi;
+use crate::dates;
+use crate::i18n::pad;
+use crate::tr;
+
+/// Expiry window used when `--expiring-within` is not given
+pub const DEFAULT_WARNING_DAYS: i64 = crate::colorize::EXPIRY_WARNING_DAYS;
+
+/// Who has locked a domain against transfers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Lock {
+    /// `serverTransferProhibited`, set by the registry
+    Registry,
+    /// `clientTransferProhibited`, set by the registrar
+    Registrar,
+    None,
+}
+
+impl Lock {
+    fn label(self) -> &'static str {
+        match self {
+            Lock::Registry => "registry",
+            Lock::Registrar => "registrar",
+            Lock::None => "none",
+        }
+    }
+}
+
+/// Where a domain stands against the expiry window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Standing {
+    Expired,
+    Expiring,
+    /// No expiration date in the answer
+    Unknown,
+    /// The lookup failed
+    Error,
+    Ok,
+}
+
+impl Standing {
+    /// Whether the domain needs attention before the window closes
+    pub fn is_alert(self) -> bool {
+        matches!(self, Standing::Expired | Standing::Expiring)
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Standing::Expired => "expired",
+            Standing::Expiring => "expiring",
+            Standing::Unknown => "unknown",
+            Standing::Error => "error",
+            Standing::Ok => "ok",
+        }
+    }
+}
+
+/// Registration details of a domain, as extracted from its WHOIS answer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Registration {
+    pub domain: String,
+    pub registrar: Option<String>,
+    /// Expiration date as `YYYY-MM-DD`
+    pub expires: Option<String>,
+    pub days_left: Option<i64>,
+    pub lock: Lock,
+    pub nameservers: Vec<String>,
+    pub dnssec: Option<bool>,
+    pub standing: Standing,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Registration {
+    /// Registration details from a WHOIS answer; `today` and the window are in days
+    pub fn parse(domain: &str, response: &str, today: i64, window: i64) -> Self {
+        let mut registration = Registration {
+            domain: domain.to_string(),
+            registrar: None,
+            expires: None,
+            days_left: None,
+            lock: Lock::None,
+            nameservers: Vec::new(),
+            dnssec: None,
+            standing: Standing::Unknown,
+            error: None,
+        };
+        let response = strip_ansi(response);
+        for line in response.lines().filter(|line| !line.starts_with(['%', '#', '>'])) {
+            let Some((name, value)) = line.split_once(':') else {
+                continue;
+            };
+            let name = name.trim().to_lowercase();
+            let value = value.trim();
+            if value.is_empty() {
+                continue;
+            }
+            match name.as_str() {
+                "registrar" | "sponsoring registrar" | "registrar name" if registration.registrar.is_none() => {
+                    registration.registrar = Some(value.to_string());
+                }
+                "domain status" | "status" => {
+                    let status = value.split_whitespace().next().unwrap_or_default().to_lowercase();
+                    if status == "servertransferprohibited" {
+                        registration.lock = Lock::Registry;
+                    } else if status == "clienttransferprohibited" && registration.lock == Lock::None {
+                        registration.lock = Lock::Registrar;
+                    }
+                }
+                "name server" | "nserver" | "nameserver" | "nameservers" => {
+                    if let Some(host) = value.split_whitespace().next() {
+                        let host = host.trim_end_matches('.').to_lowercase();
+                        if !registration.nameservers.contains(&host) {
+                            registration.nameservers.push(host);
+                        }
+                    }
+                }
+                "dnssec" if registration.dnssec.is_none() => {
+                    let value = value.to_lowercase();
+                    registration.dnssec = Some(!value.contains("unsigned") && (value.contains("signed") || value == "yes"));
+                }
+                name if (name.contains("expir") || name == "paid-till") && registration.expires.is_none() => {
+                    if let Some(day) = dates::parse_day(value) {
+                        registration.expires = value.get(..10).map(|date| date.replace(['.', '/'], "-"));
+                        registration.days_left = Some(day - today);
+                    }
+                }
+                _ => {}
+            }
+        }
+        registration.standing = match registration.days_left {
+            Some(days) if days < 0 => Standing::Expired,
+            Some(days) if days <= window => Standing::Expiring,
+            Some(_) => Standing::Ok,
+            None => Standing::Unknown,
+        };
+        registration
+    }
+
+    /// Row for a domain whose lookup failed
+    pub fn failed(domain: &str, error: &anyhow::Error) -> Self {
+        Registration {
+            domain: domain.to_string(),
+            registrar: None,
+            expires: None,
+            days_left: None,
+            lock: Lock::None,
+            nameservers: Vec::new(),
+            dnssec: None,
+            standing: Standing::Error,
+            error: Some(format!("{:#}", error)),
+        }
+    }
+
+    fn dnssec_label(&self) -> &'static str {
+        match self.dnssec {
+            Some(true) => "signed",
+            Some(false) => "unsigned",
+            None => "-",
+        }
+    }
+}
+
+/// Domains of a list or zone file: the first word of each line, without the trailing dot;
+/// comments (`;`, `#`), directives (`$ORIGIN`, `$TTL`) and `@` are skipped
+pub fn read_domains(text: &str) -> Vec<String> {
+    let mut domains: Vec<String> = Vec::new();
+    for line in text.lines() {
+        let line = line.split([';', '#']).next().unwrap_or_default();
+        let Some(word) = line.split_whitespace().next() else {
+            continue;
+        };
+        if word.starts_with('$') || word == "@" || line.starts_with(char::is_whitespace) {
+            continue;
+        }
+        let domain = word.trim_start_matches("*.").trim_end_matches('.').to_lowercase();
+        if !domain.is_empty() && !domains.contains(&domain) {
+            domains.push(domain);
+        }
+    }
+    domains
+}
+
+/// Soonest expiry first; domains without a date and failed lookups last
+pub fn sort(registrations: &mut [Registration]) {
+    registrations.sort_by_key(|registration| (registration.days_left.is_none(), registration.days_left, registration.domain.clone()));
+}
+
+/// Portfolio table as `%` comment lines
+pub fn render(registrations: &[Registration], window: i64) -> String {
+    let alerts = registrations.iter().filter(|registration| registration.standing.is_alert()).count();
+    let mut lines = vec![
+        format!("% ─── {} ───", tr!("registration-heading", count = registrations.len(), alerts = alerts, days = window)),
+        format!(
+            "% {} {} {} {} {} {} {}",
+            pad(&tr!("registration-domain"), 28),
+            pad(&tr!("registration-registrar"), 28),
+            pad(&tr!("registration-expires"), 10),
+            pad(&tr!("registration-days"), 6),
+            pad(&tr!("registration-lock"), 9),
+            pad(&tr!("registration-dnssec"), 8),
+            tr!("registration-nameservers")
+        ),
+    ];
+    for registration in registrations {
+        let row = match &registration.error {
+            Some(error) => format!("% {:<28} {}", registration.domain, error),
+            None => format!(
+                "% {:<28} {:<28} {:<10} {:<6} {:<9} {:<8} {}",
+                registration.domain,
+                registration.registrar.as_deref().unwrap_or("-"),
+                registration.expires.as_deref().unwrap_or("-"),
+                registration.days_left.map_or("-".to_string(), |days| days.to_string()),
+                registration.lock.label(),
+                registration.dnssec_label(),
+                registration.nameservers.join(", ")
+            ),
+        };
+        lines.push(row.trim_end().to_string());
+    }
+    lines.join("\n")
+}
+
+/// Portfolio as CSV with a header row
+pub fn to_csv(registrations: &[Registration]) -> String {
+    let mut lines = vec!["domain,registrar,expires,days_left,lock,dnssec,nameservers,status,error".to_string()];
+    for registration in registrations {
+        let fields = [
+            registration.domain.clone(),
+            registration.registrar.clone().unwrap_or_default(),
+            registration.expires.clone().unwrap_or_default(),
+            registration.days_left.map(|days| days.to_string()).unwrap_or_default(),
+            registration.lock.label().to_string(),
+            registration.dnssec.map(|_| registration.dnssec_label().to_string()).unwrap_or_default(),
+            registration.nameservers.join(" "),
+            registration.standing.label().to_string(),
+            registration.error.clone().unwrap_or_default(),
+        ];
+        lines.push(fields.iter().map(|field| csv_field(field)).collect::<Vec<_>>().join(","));
+    }
+    lines.join("\n")
+}
+
+/// A CSV field, quoted when it holds a comma, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "   Domain Name: EXAMPLE.COM\n   Registry Expiry Date: 2024-08-13T04:00:00Z\n\
+                              Registrar: RESERVED-Internet Assigned Numbers Authority\n\
+                           Domain Status: clientDeleteProhibited https://icann.org/epp#clientDeleteProhibited\n\
+                           Domain Status: clientTransferProhibited https://icann.org/epp#clientTransferProhibited\n\
+                           Name Server: A.IANA-SERVERS.NET\nName Server: B.IANA-SERVERS.NET\nDNSSEC: signedDelegation\n\
+                           >>> Last update of whois database: 2024-03-01T00:00:00Z <<<\n";
+
+    #[test]
+    fn test_parse_registration() {
+        let today = dates::days_from_civil(2024, 7, 1).unwrap();
+        let registration = Registration::parse("example.com", EXAMPLE, today, 60);
+        assert_eq!(registration.registrar.as_deref(), Some("RESERVED-Internet Assigned Numbers Authority"));
+        assert_eq!((registration.expires.as_deref(), registration.days_left), (Some("2024-08-13"), Some(43)));
+        assert_eq!(registration.lock, Lock::Registrar);
+        assert_eq!(registration.nameservers, ["a.iana-servers.net", "b.iana-servers.net"]);
+        assert_eq!(registration.dnssec, Some(true));
+        assert_eq!(registration.standing, Standing::Expiring);
+        assert_eq!(Registration::parse("example.com", EXAMPLE, today, 30).standing, Standing::Ok);
+        assert_eq!(Registration::parse("example.com", "No match for \"EXAMPLE.TEST\".", today, 30).standing, Standing::Unknown);
+    }
+
+    #[test]
+    fn test_read_domains() {
+        let zone = "$ORIGIN example.\n; portfolio\nexample.com.   IN NS ns1\n  IN NS ns2\n*.example.net\n@ IN SOA x y\nexample.org # renew\nEXAMPLE.COM\n";
+        assert_eq!(read_domains(zone), ["example.com", "example.net", "example.org"]);
+    }
+
+    #[test]
+    fn test_csv_and_table() {
+        let today = dates::days_from_civil(2024, 7, 1).unwrap();
+        let mut registrations = vec![
+            Registration::failed("broken.example", &anyhow::anyhow!("timed out, retry later")),
+            Registration::parse("example.com", EXAMPLE, today, 60),
+        ];
+        sort(&mut registrations);
+        let csv = to_csv(&registrations);
+        let rows: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            rows[1],
+            "example.com,RESERVED-Internet Assigned Numbers Authority,2024-08-13,43,registrar,signed,a.iana-servers.net b.iana-servers.net,expiring,"
+        );
+        assert_eq!(rows[2], "broken.example,,,,none,,,error,\"timed out, retry later\"");
+
+        let table = render(&registrations, 60);
+        assert!(table.starts_with("% ─── Domain portfolio: 2 domain(s), 1 expiring within 60 days ───\n"));
+        assert!(table.ends_with("% broken.example               timed out, retry later"));
+    }
+}
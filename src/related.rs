@@ -0,0 +1,169 @@
+use std::net::IpAddr;
+
+use crate::netblock::Netblock;
+use crate::rpsl::{self, Block};
+use crate::tr;
+
+/// Object classes that describe a network in a less/more-specific answer
+const NETWORK_CLASSES: &[&str] = &["inetnum", "inet6num", "route", "route6"];
+
+/// Whether a target is an IP address or prefix, the only queries `--related` applies to
+pub fn is_network_target(target: &str) -> bool {
+    let address = target.split('/').next().unwrap_or(target);
+    address.parse::<IpAddr>().is_ok()
+}
+
+/// Whether a target is a domain name, the only queries `--ct` and `--mail-check` apply to
+pub fn is_domain_target(target: &str) -> bool {
+    target.contains('.') && !is_network_target(target)
+}
+
+/// One network from a hierarchy answer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Network {
+    pub class: String,
+    /// Primary key as the server wrote it (`193.0.0.0 - 193.0.7.255`, `193.0.0.0/21`)
+    pub key: String,
+    /// `netname:` of address blocks, `origin:` of routes
+    pub label: Option<String>,
+    pub block: Netblock,
+}
+
+impl Network {
+    fn contains(&self, other: &Network) -> bool {
+        self.block.contains(&other.block)
+    }
+}
+
+/// Networks in a response, in the order they appear
+/// Objects whose key is not a range or prefix are skipped
+pub fn networks(response: &str) -> Vec<Network> {
+    rpsl::parse(response)
+        .into_iter()
+        .filter_map(|block| {
+            let Block::Object(object) = block else {
+                return None;
+            };
+            let class = object.class().to_lowercase();
+            if !NETWORK_CLASSES.contains(&class.as_str()) {
+                return None;
+            }
+            let key = object.key().to_string();
+            let block = Netblock::parse(&key)?;
+            let label = object.get("netname").or_else(|| object.get("origin")).map(str::to_string);
+            Some(Network { class, key, label, block })
+        })
+        .collect()
+}
+
+/// Covering and contained networks around a queried address or prefix
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RelatedNetworks {
+    /// Networks containing the target, including an exact match
+    pub less_specific: Vec<Network>,
+    /// Networks inside the target
+    pub more_specific: Vec<Network>,
+}
+
+impl RelatedNetworks {
+    /// Build from the raw less- and more-specific answers
+    pub fn from_responses(less_specific: &str, more_specific: &str) -> Self {
+        Self {
+            less_specific: networks(less_specific),
+            more_specific: networks(more_specific),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.less_specific.is_empty() && self.more_specific.is_empty()
+    }
+
+    /// Indented tree of all networks, widest first, as `%` comment lines
+    /// Routes and address blocks with the same range are listed side by side at the same depth
+    pub fn render(&self, target: &str) -> String {
+        let mut all: Vec<&Network> = Vec::new();
+        for network in self.less_specific.iter().chain(&self.more_specific) {
+            if !all.iter().any(|known| known.class == network.class && known.block == network.block) {
+                all.push(network);
+            }
+        }
+        // Parents sort before their children: by start address, then widest first
+        all.sort_by(|a, b| {
+            a.block
+                .first()
+                .cmp(&b.block.first())
+                .then(b.block.last().cmp(&a.block.last()))
+                .then(a.class.cmp(&b.class))
+        });
+
+        let mut lines = vec![format!("% ─── {} ───", tr!("related-heading", target = target, count = all.len()))];
+        if all.is_empty() {
+            lines.push(format!("% {}", tr!("related-none")));
+        }
+        let mut parents: Vec<&Network> = Vec::new();
+        for network in all {
+            while parents
+                .last()
+                .is_some_and(|parent| !parent.contains(network) || parent.block == network.block)
+            {
+                parents.pop();
+            }
+            let marker = if self.more_specific.iter().any(|more| more.block == network.block) {
+                tr!("related-more")
+            } else {
+                tr!("related-less")
+            };
+            let label = network.label.as_deref().map(|label| format!("  {}", label)).unwrap_or_default();
+            lines.push(format!(
+                "% {}{:<8} {}{}  [{}]",
+                "  ".repeat(parents.len()),
+                network.class,
+                network.key,
+                label,
+                marker
+            ));
+            parents.push(network);
+        }
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LESS: &str = "% Information related to '193.0.0.0 - 193.0.23.255'\n\ninetnum:        193.0.0.0 - 193.0.23.255\nnetname:        RIPE-NCC\nsource:         RIPE\n\ninetnum:        193.0.0.0 - 193.0.7.255\nnetname:        RIPE-NCC-NET\nsource:         RIPE\n\nroute:          193.0.0.0/21\norigin:         AS3333\nsource:         RIPE\n";
+    const MORE: &str = "inetnum:        193.0.0.0 - 193.0.0.255\nnetname:        RIPE-NCC-SERVICES\nsource:         RIPE\n";
+
+    #[test]
+    fn test_network_targets() {
+        assert!(is_network_target("193.0.0.0/21"));
+        assert!(is_network_target("2001:db8::1"));
+        assert!(!is_network_target("AS3333"));
+        assert!(!is_network_target("example.com"));
+        assert!(is_domain_target("example.com") && !is_domain_target("193.0.6.139") && !is_domain_target("AS3333"));
+    }
+
+    #[test]
+    fn test_networks_from_response() {
+        let found = networks(LESS);
+        assert_eq!(found.len(), 3);
+        assert_eq!(found[1].label.as_deref(), Some("RIPE-NCC-NET"));
+        assert_eq!(found[2].class, "route");
+        assert_eq!(found[2].label.as_deref(), Some("AS3333"));
+    }
+
+    #[test]
+    fn test_render_hierarchy() {
+        let related = RelatedNetworks::from_responses(LESS, MORE);
+        let rendered = related.render("193.0.0.0/21");
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(lines[0], "% ─── Related networks for 193.0.0.0/21 (4) ───");
+        assert!(lines[1].starts_with("% inetnum  193.0.0.0 - 193.0.23.255  RIPE-NCC"));
+        assert!(lines[2].starts_with("%   inetnum  193.0.0.0 - 193.0.7.255"));
+        assert!(lines[3].starts_with("%   route    193.0.0.0/21  AS3333"));
+        assert!(lines[4].starts_with("%     inetnum  193.0.0.0 - 193.0.0.255"));
+        assert!(lines[4].ends_with("[more specific]"));
+        assert!(RelatedNetworks::default().render("192.0.2.1").contains("No less- or more-specific"));
+    }
+}
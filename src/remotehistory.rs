@@ -0,0 +1,227 @@
+#[cfg(feature = "net")]
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::Value;
+#[cfg(feature = "net")]
+use tracing::info;
+
+use crate::fingerprint::{fingerprint, normalized};
+use crate::history::{diff_lines, render_diff};
+use crate::i18n::pad;
+use crate::tr;
+
+/// WHOIS History API of WhoisXML API, whose answer format other archives also follow
+pub const WHOISXMLAPI_URL: &str = "https://whois-history.whoisxmlapi.com/api/v1";
+#[cfg(feature = "net")]
+const DEFAULT_TIMEOUT_SECONDS: u64 = 30;
+
+/// Historical WHOIS archive used by `whois history DOMAIN --remote`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HistoryApiConfig {
+    /// Base URL of a WhoisXML API-compatible WHOIS History API
+    pub url: String,
+    pub api_key: Option<String>,
+    /// Read the key from the system keyring, stored with `whois auth set HOST` for the URL's host
+    pub keyring: bool,
+}
+
+impl Default for HistoryApiConfig {
+    fn default() -> Self {
+        Self { url: WHOISXMLAPI_URL.to_string(), api_key: None, keyring: false }
+    }
+}
+
+impl HistoryApiConfig {
+    /// Host of the API URL, which is also its keyring account
+    pub fn host(&self) -> &str {
+        let rest = self.url.split_once("://").map_or(self.url.as_str(), |(_, rest)| rest);
+        rest.split(['/', '?', ':']).next().unwrap_or(rest)
+    }
+
+    /// The configured API key, or the one kept in the keyring
+    pub fn api_key(&self) -> Result<String> {
+        if let Some(key) = self.api_key.as_deref().filter(|key| !key.trim().is_empty()) {
+            return Ok(key.trim().to_string());
+        }
+        let host = self.host();
+        if !self.keyring {
+            anyhow::bail!("`--remote` needs an API key: set `api_key` in the [history_api] config section, or `keyring = true` and run `whois auth set {}`", host);
+        }
+        crate::secrets::lookup(&crate::secrets::account_name(host)?)?
+            .with_context(|| format!("No API key for {} in the keyring; store one with `whois auth set {}`", host, host))
+    }
+}
+
+/// A past WHOIS record of a domain, as kept by an archive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteRecord {
+    /// When the archive captured the record (`2019-04-01 08:12`)
+    pub captured: String,
+    pub registrar: Option<String>,
+    /// Record text without comments, banners or volatile lines, as fingerprinted
+    pub text: String,
+}
+
+/// Client for a WhoisXML API-compatible WHOIS History API
+#[cfg(feature = "net")]
+pub struct HistoryApi {
+    base_url: String,
+    api_key: String,
+    timeout: Duration,
+}
+
+#[cfg(feature = "net")]
+impl HistoryApi {
+    pub fn new(base_url: impl Into<String>, api_key: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            api_key: api_key.into(),
+            timeout: Duration::from_secs(DEFAULT_TIMEOUT_SECONDS),
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Archived records of a domain, oldest first
+    pub fn records(&self, domain: &str) -> Result<Vec<RemoteRecord>> {
+        info!("Fetching archived records of {} from {}", domain, self.base_url);
+        let agent = ureq::AgentBuilder::new()
+            .timeout(self.timeout)
+            .user_agent(concat!("whois-cli/", env!("CARGO_PKG_VERSION")))
+            .build();
+        let body: Value = agent
+            .get(&self.base_url)
+            .query("apiKey", &self.api_key)
+            .query("domainName", domain)
+            .query("mode", "purchase")
+            .query("outputFormat", "JSON")
+            .call()
+            .map_err(|err| match err {
+                // The URL would show the API key
+                ureq::Error::Status(status, _) => anyhow::anyhow!("WHOIS history API at {} answered HTTP {}", self.base_url, status),
+                ureq::Error::Transport(transport) => anyhow::anyhow!("WHOIS history API at {} failed: {}", self.base_url, transport.kind()),
+            })?
+            .into_json()
+            .with_context(|| format!("Invalid WHOIS history API response from {}", self.base_url))?;
+        if let Some(message) = body["messages"].as_str() {
+            anyhow::bail!("WHOIS history API at {}: {}", self.base_url, message);
+        }
+        Ok(parse_records(&body))
+    }
+}
+
+/// Records of a WHOIS History API answer, oldest first; a record whose data did not
+/// change since the one before it is left out
+pub fn parse_records(body: &Value) -> Vec<RemoteRecord> {
+    let mut records: Vec<RemoteRecord> = body["records"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|record| {
+            let captured = [&record["audit"]["createdDate"], &record["audit"]["updatedDate"], &record["updatedDateISO8601"]]
+                .into_iter()
+                .find_map(Value::as_str)?;
+            Some(RemoteRecord {
+                captured: captured.get(..16).unwrap_or(captured).replace('T', " "),
+                registrar: record["registrarName"].as_str().filter(|name| !name.is_empty()).map(str::to_string),
+                text: normalized(&record_text(record)),
+            })
+        })
+        .collect();
+    records.sort_by(|a, b| a.captured.cmp(&b.captured));
+    records.dedup_by(|later, earlier| fingerprint(&later.text) == fingerprint(&earlier.text));
+    records
+}
+
+/// The archived answer, or one built from the parsed fields when the archive kept none
+fn record_text(record: &Value) -> String {
+    if let Some(raw) = [&record["rawText"], &record["cleanText"]].into_iter().find_map(Value::as_str).filter(|raw| !raw.trim().is_empty()) {
+        return raw.to_string();
+    }
+    let values = |value: &Value| -> Vec<String> {
+        match value {
+            Value::String(text) => vec![text.clone()],
+            Value::Array(items) => items.iter().filter_map(Value::as_str).map(str::to_string).collect(),
+            _ => Vec::new(),
+        }
+    };
+    let fields = [
+        ("Domain Name", &record["domainName"]),
+        ("Registrar", &record["registrarName"]),
+        ("Creation Date", &record["createdDateISO8601"]),
+        ("Updated Date", &record["updatedDateISO8601"]),
+        ("Registry Expiry Date", &record["expiresDateISO8601"]),
+        ("Domain Status", &record["status"]),
+        ("Name Server", &record["nameServers"]),
+        ("Registrant Organization", &record["registrantContact"]["organization"]),
+        ("Registrant Country", &record["registrantContact"]["country"]),
+    ];
+    fields
+        .iter()
+        .flat_map(|(name, value)| values(value).into_iter().map(move |value| format!("{}: {}", name, value)))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Table of the archived records and the changes between consecutive ones
+pub fn render(domain: &str, host: &str, records: &[RemoteRecord]) -> String {
+    let mut lines = vec![
+        format!("% ─── {} ───", tr!("history-remote-heading", key = domain, count = records.len(), archive = host)),
+        format!("% {} {} {}", pad(&tr!("history-revision"), 6), pad(&tr!("history-date"), 18), tr!("history-registrar")),
+    ];
+    lines.extend(records.iter().enumerate().map(|(index, record)| {
+        format!("% {:<6} {:<18} {}", index + 1, record.captured, record.registrar.as_deref().unwrap_or("-"))
+    }));
+    for (index, pair) in records.windows(2).enumerate() {
+        let revision = index as u32 + 1;
+        lines.push(String::new());
+        lines.push(render_diff(revision, revision + 1, &diff_lines(&pair[0].text, &pair[1].text)));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ansi::strip_ansi;
+
+    const ANSWER: &str = r#"{"recordsCount": 3, "records": [
+        {"domainName": "example.com", "registrarName": "Registrar B", "audit": {"createdDate": "2021-06-01 10:00:00 UTC"},
+         "rawText": "Domain Name: EXAMPLE.COM\nRegistrar: Registrar B\n>>> Last update of whois database: 2021-06-01T10:00:00Z <<<"},
+        {"domainName": "example.com", "registrarName": "Registrar A", "audit": {"createdDate": "2019-03-01 08:12:40 UTC"},
+         "createdDateISO8601": "1995-08-14T04:00:00Z", "nameServers": ["a.iana-servers.net", "b.iana-servers.net"]},
+        {"domainName": "example.com", "registrarName": "Registrar B", "audit": {"createdDate": "2022-01-01 00:00:00 UTC"},
+         "rawText": "Domain Name:  EXAMPLE.COM\nRegistrar: Registrar B\n>>> Last update of whois database: 2022-01-01T00:00:00Z <<<"}
+    ]}"#;
+
+    #[test]
+    fn test_parse_records() {
+        let records = parse_records(&serde_json::from_str(ANSWER).unwrap());
+        let captured: Vec<&str> = records.iter().map(|record| record.captured.as_str()).collect();
+        assert_eq!(captured, ["2019-03-01 08:12", "2021-06-01 10:00"]);
+        assert_eq!(
+            records[0].text,
+            "domain name: example.com\nregistrar: Registrar A\ncreation date: 1995-08-14T04:00:00Z\nname server: a.iana-servers.net\nname server: b.iana-servers.net"
+        );
+        assert!(parse_records(&serde_json::json!({"recordsCount": 0})).is_empty());
+
+        let rendered = strip_ansi(&render("example.com", "whois-history.whoisxmlapi.com", &records));
+        assert!(rendered.contains("% 2      2021-06-01 10:00   Registrar B"));
+        assert!(rendered.contains("% ─── Changes from version 1 to 2 ───\n- domain name: example.com\n- registrar: Registrar A\n"));
+        assert!(rendered.contains("+ domain name: EXAMPLE.COM"));
+    }
+
+    #[test]
+    fn test_api_key() {
+        let config = HistoryApiConfig { api_key: Some(" secret ".to_string()), ..Default::default() };
+        assert_eq!(config.api_key().unwrap(), "secret");
+        assert_eq!(config.host(), "whois-history.whoisxmlapi.com");
+        assert!(HistoryApiConfig::default().api_key().unwrap_err().to_string().contains("whois auth set whois-history.whoisxmlapi.com"));
+    }
+}
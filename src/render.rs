@@ -0,0 +1,234 @@
+use anyhow::Result;
+
+use crate::ansi::{parse_segments, AnsiColor, AnsiStyle};
+use crate::html::escape_html;
+
+/// Renders colored terminal output into SVG/PNG "screenshots"
+pub struct ImageRenderer {
+    /// Font size in pixels
+    font_size: f32,
+    /// Padding around the text block in pixels
+    padding: f32,
+}
+
+/// Terminal background and default foreground colors
+const BACKGROUND_COLOR: &str = "#1e1e1e";
+const FOREGROUND_COLOR: &str = "#d4d4d4";
+/// Monospace advance width relative to font size
+const CHAR_WIDTH_RATIO: f32 = 0.6;
+/// Line height relative to font size
+const LINE_HEIGHT_RATIO: f32 = 1.4;
+
+impl ImageRenderer {
+    pub fn new() -> Self {
+        Self {
+            font_size: 14.0,
+            padding: 16.0,
+        }
+    }
+
+    /// Render ANSI-colored terminal output as an SVG document
+    pub fn render_svg(&self, output: &str) -> String {
+        let lines = Self::split_lines(output);
+        let char_width = self.font_size * CHAR_WIDTH_RATIO;
+        let line_height = self.font_size * LINE_HEIGHT_RATIO;
+
+        let columns = lines
+            .iter()
+            .map(|line| line.iter().map(|(text, _)| text.chars().count()).sum::<usize>())
+            .max()
+            .unwrap_or(0);
+        let width = (self.padding * 2.0 + columns as f32 * char_width).round();
+        let height = (self.padding * 2.0 + lines.len() as f32 * line_height).round();
+
+        let mut svg = String::new();
+        svg.push_str(&format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n",
+            width, height, width, height
+        ));
+        svg.push_str(&format!(
+            "<rect width=\"100%\" height=\"100%\" rx=\"6\" fill=\"{}\"/>\n",
+            BACKGROUND_COLOR
+        ));
+        svg.push_str(&format!(
+            "<g font-family=\"DejaVu Sans Mono, Menlo, Consolas, monospace\" font-size=\"{}\" fill=\"{}\" xml:space=\"preserve\">\n",
+            self.font_size, FOREGROUND_COLOR
+        ));
+
+        for (row, line) in lines.iter().enumerate() {
+            let baseline = self.padding + row as f32 * line_height + self.font_size;
+            let mut column = 0usize;
+
+            // Background fills are drawn first so text stays on top
+            for (text, style) in line {
+                let len = text.chars().count();
+                if let Some(bg) = style.bg {
+                    svg.push_str(&format!(
+                        "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\"/>\n",
+                        self.padding + column as f32 * char_width,
+                        baseline - self.font_size,
+                        len as f32 * char_width,
+                        line_height,
+                        bg.to_hex()
+                    ));
+                }
+                column += len;
+            }
+
+            if line.iter().all(|(text, _)| text.trim().is_empty()) {
+                continue;
+            }
+
+            svg.push_str(&format!("<text x=\"{}\" y=\"{:.1}\">", self.padding, baseline));
+            for (text, style) in line {
+                svg.push_str(&Self::tspan(text, style));
+            }
+            svg.push_str("</text>\n");
+        }
+
+        svg.push_str("</g>\n</svg>\n");
+        svg
+    }
+
+    /// Render ANSI-colored terminal output as a PNG image
+    #[cfg(feature = "render-png")]
+    pub fn render_png(&self, output: &str) -> Result<Vec<u8>> {
+        use anyhow::{anyhow, Context};
+        use resvg::{tiny_skia, usvg};
+
+        let svg = self.render_svg(output);
+
+        let mut options = usvg::Options::default();
+        options.fontdb_mut().load_system_fonts();
+
+        let tree = usvg::Tree::from_str(&svg, &options)
+            .context("Failed to parse generated SVG")?;
+        let size = tree.size().to_int_size();
+        let mut pixmap = tiny_skia::Pixmap::new(size.width(), size.height())
+            .ok_or_else(|| anyhow!("Invalid image size: {}x{}", size.width(), size.height()))?;
+
+        resvg::render(&tree, tiny_skia::Transform::default(), &mut pixmap.as_mut());
+
+        pixmap.encode_png().context("Failed to encode PNG image")
+    }
+
+    /// PNG rendering is unavailable without the `render-png` feature
+    #[cfg(not(feature = "render-png"))]
+    pub fn render_png(&self, _output: &str) -> Result<Vec<u8>> {
+        anyhow::bail!("PNG export requires the `render-png` feature")
+    }
+
+    /// Split styled segments into lines of (text, style) runs
+    fn split_lines(output: &str) -> Vec<Vec<(String, AnsiStyle)>> {
+        let mut lines: Vec<Vec<(String, AnsiStyle)>> = vec![Vec::new()];
+
+        for segment in parse_segments(output) {
+            for (i, part) in segment.text.split('\n').enumerate() {
+                if i > 0 {
+                    lines.push(Vec::new());
+                }
+                let part = part.trim_end_matches('\r').replace('\t', "    ");
+                if !part.is_empty() {
+                    lines.last_mut().unwrap().push((part, segment.style));
+                }
+            }
+        }
+
+        // Drop the empty line produced by a trailing newline
+        if lines.len() > 1 && lines.last().is_some_and(|line| line.is_empty()) {
+            lines.pop();
+        }
+
+        lines
+    }
+
+    /// Build a <tspan> for a styled run of text
+    fn tspan(text: &str, style: &AnsiStyle) -> String {
+        let mut attrs = String::new();
+        if let Some(fg) = style.fg {
+            attrs.push_str(&format!(" fill=\"{}\"", fg.to_hex()));
+        } else if style.dim {
+            attrs.push_str(&format!(" fill=\"{}\"", AnsiColor::Basic(8).to_hex()));
+        }
+        if style.bold {
+            attrs.push_str(" font-weight=\"bold\"");
+        }
+        if style.italic {
+            attrs.push_str(" font-style=\"italic\"");
+        }
+        if style.underline {
+            attrs.push_str(" text-decoration=\"underline\"");
+        } else if style.strikethrough {
+            attrs.push_str(" text-decoration=\"line-through\"");
+        }
+
+        if attrs.is_empty() {
+            escape_html(text)
+        } else {
+            format!("<tspan{}>{}</tspan>", attrs, escape_html(text))
+        }
+    }
+}
+
+impl Default for ImageRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_svg_structure() {
+        let renderer = ImageRenderer::new();
+        let svg = renderer.render_svg("aut-num: AS3333\nsource: RIPE");
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.contains("aut-num: AS3333"));
+        assert!(svg.contains("source: RIPE"));
+        assert_eq!(svg.matches("<text ").count(), 2);
+        assert!(svg.trim_end().ends_with("</svg>"));
+    }
+
+    #[test]
+    fn test_render_svg_colors() {
+        let renderer = ImageRenderer::new();
+        let svg = renderer.render_svg("\x1b[1;91mAS3333\x1b[0m \x1b[38;2;91;207;250mblue\x1b[0m");
+        assert!(svg.contains("<tspan fill=\"#f14c4c\" font-weight=\"bold\">AS3333</tspan>"));
+        assert!(svg.contains("<tspan fill=\"#5bcffa\">blue</tspan>"));
+    }
+
+    #[test]
+    fn test_render_svg_escapes_text() {
+        let renderer = ImageRenderer::new();
+        let svg = renderer.render_svg("descr: <A & B>");
+        assert!(svg.contains("descr: &lt;A &amp; B&gt;"));
+    }
+
+    #[test]
+    fn test_render_svg_dimensions() {
+        let renderer = ImageRenderer::new();
+        let svg = renderer.render_svg("0123456789\n\nabc\n");
+        // 10 columns * 8.4px + 32px padding, 3 lines * 19.6px + 32px padding
+        assert!(svg.contains("width=\"116\" height=\"91\""));
+    }
+
+    #[cfg(feature = "render-png")]
+    #[test]
+    fn test_render_png_signature() {
+        let renderer = ImageRenderer::new();
+        let png = renderer.render_png("\x1b[91mAS3333\x1b[0m").unwrap();
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+    }
+
+    #[test]
+    fn test_split_lines_across_segments() {
+        let lines = ImageRenderer::split_lines("\x1b[91mred\nstill red\x1b[0m plain");
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0][0].0, "red");
+        assert_eq!(lines[1][0].0, "still red");
+        assert_eq!(lines[1][1].0, " plain");
+        assert!(lines[1][1].1.is_plain());
+    }
+}
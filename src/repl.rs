@@ -0,0 +1,288 @@
+use std::io::{BufRead, IsTerminal};
+use std::path::PathBuf;
+
+use anyhow::{Context as _, Result};
+use clap::Parser;
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+
+use crate::cli::Cli;
+use crate::config::Config;
+
+const HISTORY_FILE_NAME: &str = "repl_history";
+/// Previous targets offered for tab completion
+const MAX_REMEMBERED: usize = 500;
+const PROMPT: &str = "whois> ";
+
+/// Session commands; anything else on a line is a query
+const COMMANDS: &[(&str, &str)] = &[
+    (".set", "FLAGS...   add flags to every later query, e.g. .set -s whois.radb.net --dedup"),
+    (".unset", "          drop the session flags"),
+    (".show", "           print the session flags"),
+    (".help", "           print this help"),
+    (".quit", "           leave (also .exit or Ctrl-D)"),
+];
+
+/// One line typed at the prompt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Input {
+    /// Targets, optionally with flags for this query only
+    Query(Vec<String>),
+    Set(Vec<String>),
+    Unset,
+    Show,
+    Help,
+    Quit,
+}
+
+/// Parse a line with shell quoting; blank lines and `#` comments are `None`
+pub fn parse_line(line: &str) -> Result<Option<Input>> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+    let words = shlex::split(line).with_context(|| format!("Unbalanced quotes in {}", line))?;
+    let Some((first, rest)) = words.split_first() else {
+        return Ok(None);
+    };
+    let input = match first.as_str() {
+        ".set" if rest.is_empty() => anyhow::bail!("Usage: .set FLAGS..."),
+        ".set" => Input::Set(rest.to_vec()),
+        ".unset" => Input::Unset,
+        ".show" => Input::Show,
+        ".help" => Input::Help,
+        ".quit" | ".exit" => Input::Quit,
+        command if command.starts_with('.') => anyhow::bail!("Unknown command {}, see .help", command),
+        _ => Input::Query(words),
+    };
+    Ok(Some(input))
+}
+
+/// Help listing the session commands
+pub fn help() -> String {
+    let mut lines = vec!["Type targets and flags as on the command line, e.g. AS3333 --related".to_string()];
+    lines.extend(COMMANDS.iter().map(|(name, text)| format!("  {}{}", name, text)));
+    lines.join("\n")
+}
+
+/// Settings and completions kept for the length of a session
+#[derive(Debug, Default)]
+pub struct ReplState {
+    /// Flags from `whois repl ...` and `.set`, put before the words of every query
+    flags: Vec<String>,
+    /// Earlier targets, oldest first
+    targets: Vec<String>,
+}
+
+impl ReplState {
+    /// Session starting with the flags `whois repl` was given
+    pub fn new(flags: Vec<String>) -> Self {
+        Self { flags, targets: Vec::new() }
+    }
+
+    pub fn flags(&self) -> &[String] {
+        &self.flags
+    }
+
+    /// Add session flags; later flags override earlier ones of the same option
+    pub fn set(&mut self, flags: &[String]) -> Result<()> {
+        let mut words = vec!["whois".to_string()];
+        words.extend(self.flags.iter().chain(flags).cloned());
+        words.extend(["--".to_string(), "AS0".to_string()]);
+        let cli = Cli::try_parse_from(&words).map_err(|err| anyhow::anyhow!("{}", err.render().to_string().trim_end()))?;
+        if cli.domain.len() != 1 {
+            anyhow::bail!("Only flags can be set, not targets: {}", flags.join(" "));
+        }
+        self.flags.extend(flags.iter().cloned());
+        Ok(())
+    }
+
+    pub fn unset(&mut self) {
+        self.flags.clear();
+    }
+
+    /// Arguments of a query: the session flags, then the words typed
+    pub fn args(&self, words: &[String]) -> Result<Cli> {
+        let words = std::iter::once("whois".to_string()).chain(self.flags.iter().cloned()).chain(words.iter().cloned());
+        Cli::try_parse_from(words).map_err(|err| anyhow::anyhow!("{}", err.render().to_string().trim_end()))
+    }
+
+    /// Offer targets for completion, most recent last
+    pub fn remember(&mut self, targets: &[String]) {
+        for target in targets {
+            self.targets.retain(|known| known != target);
+            self.targets.push(target.clone());
+        }
+        let excess = self.targets.len().saturating_sub(MAX_REMEMBERED);
+        self.targets.drain(..excess);
+    }
+
+    /// Completions of a word: session commands for `.`, otherwise earlier targets, most recent first
+    pub fn complete(&self, word: &str) -> Vec<String> {
+        if word.starts_with('.') {
+            return COMMANDS.iter().map(|(name, _)| name.to_string()).filter(|name| name.starts_with(word)).collect();
+        }
+        let word = word.to_lowercase();
+        self.targets.iter().rev().filter(|target| target.to_lowercase().starts_with(&word)).cloned().collect()
+    }
+}
+
+/// Line editor helper completing commands and earlier targets
+struct ReplHelper {
+    state: ReplState,
+}
+
+impl Completer for ReplHelper {
+    type Candidate = String;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<String>)> {
+        let start = line[..pos].rfind(char::is_whitespace).map_or(0, |index| index + 1);
+        Ok((start, self.state.complete(&line[start..pos])))
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ReplHelper {}
+
+impl Validator for ReplHelper {}
+
+impl Helper for ReplHelper {}
+
+/// Where lines come from: an editor with history on a terminal, plain stdin for scripts
+enum Source {
+    Terminal(Box<Editor<ReplHelper, DefaultHistory>>),
+    Script(ReplState),
+}
+
+/// Reader of session input, keeping the session state
+pub struct Prompt {
+    source: Source,
+    history: Option<PathBuf>,
+}
+
+impl Prompt {
+    /// Interactive prompt when stdin is a terminal; otherwise lines are read from stdin
+    /// without a prompt, so `whois repl < queries.txt` runs a script
+    pub fn open(state: ReplState) -> Result<Self> {
+        if !std::io::stdin().is_terminal() {
+            return Ok(Self { source: Source::Script(state), history: None });
+        }
+        let mut editor = Editor::new().context("Cannot start the line editor")?;
+        editor.set_helper(Some(ReplHelper { state }));
+        let history = history_path();
+        if let Some(path) = &history {
+            // No history yet on the first run
+            let _ = editor.load_history(path);
+        }
+        Ok(Self { source: Source::Terminal(Box::new(editor)), history })
+    }
+
+    pub fn is_interactive(&self) -> bool {
+        matches!(self.source, Source::Terminal(_))
+    }
+
+    pub fn state(&self) -> &ReplState {
+        match &self.source {
+            Source::Terminal(editor) => &editor.helper().expect("helper set in open").state,
+            Source::Script(state) => state,
+        }
+    }
+
+    pub fn state_mut(&mut self) -> &mut ReplState {
+        match &mut self.source {
+            Source::Terminal(editor) => &mut editor.helper_mut().expect("helper set in open").state,
+            Source::Script(state) => state,
+        }
+    }
+
+    /// Next line, `None` at the end of input; Ctrl-C at the prompt discards the line
+    pub fn next_line(&mut self) -> Result<Option<String>> {
+        match &mut self.source {
+            Source::Terminal(editor) => loop {
+                match editor.readline(PROMPT) {
+                    Ok(line) => {
+                        if !line.trim().is_empty() {
+                            let _ = editor.add_history_entry(line.as_str());
+                        }
+                        return Ok(Some(line));
+                    }
+                    Err(ReadlineError::Interrupted) => continue,
+                    Err(ReadlineError::Eof) => return Ok(None),
+                    Err(err) => return Err(err).context("Failed to read the prompt"),
+                }
+            },
+            Source::Script(_) => {
+                let mut line = String::new();
+                match std::io::stdin().lock().read_line(&mut line).context("Failed to read stdin")? {
+                    0 => Ok(None),
+                    _ => Ok(Some(line)),
+                }
+            }
+        }
+    }
+
+    /// Keep the command history for the next session
+    pub fn save_history(&mut self) -> Result<()> {
+        let (Source::Terminal(editor), Some(path)) = (&mut self.source, &self.history) else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        editor.save_history(path).with_context(|| format!("Failed to save the history to {}", path.display()))
+    }
+}
+
+/// History file next to the config file
+fn history_path() -> Option<PathBuf> {
+    Some(Config::default_path()?.parent()?.join(HISTORY_FILE_NAME))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_lines() {
+        assert_eq!(parse_line("  # comment").unwrap(), None);
+        assert_eq!(parse_line("AS3333 -i 'mnt-by'").unwrap(), Some(Input::Query(vec!["AS3333".into(), "-i".into(), "mnt-by".into()])));
+        assert_eq!(parse_line(".set -s whois.radb.net").unwrap(), Some(Input::Set(vec!["-s".into(), "whois.radb.net".into()])));
+        assert_eq!(parse_line(".exit").unwrap(), Some(Input::Quit));
+        assert!(parse_line(".set").is_err());
+        assert!(parse_line(".frobnicate").is_err());
+        assert!(parse_line("AS3333 'unterminated").is_err());
+    }
+
+    #[test]
+    fn test_session_flags() {
+        let mut state = ReplState::new(vec!["--dedup".to_string()]);
+        state.set(&["-s".to_string(), "whois.radb.net".to_string()]).unwrap();
+        assert!(state.set(&["--no-such-flag".to_string()]).is_err());
+        assert!(state.set(&["AS1".to_string()]).is_err());
+        assert_eq!(state.flags(), ["--dedup", "-s", "whois.radb.net"]);
+
+        // A flag typed with the query wins over the session's
+        let args = state.args(&["-s".to_string(), "whois.ripe.net".to_string(), "AS3333".to_string()]).unwrap();
+        assert_eq!(args.server.as_deref(), Some("whois.ripe.net"));
+        assert!(args.dedup);
+        state.unset();
+        assert_eq!(state.args(&["AS3333".to_string()]).unwrap().server, None);
+    }
+
+    #[test]
+    fn test_completions() {
+        let mut state = ReplState::default();
+        state.remember(&["AS3333".to_string(), "as-example".to_string(), "example.com".to_string()]);
+        state.remember(&["AS3333".to_string()]);
+        assert_eq!(state.complete("as"), ["AS3333", "as-example"]);
+        assert_eq!(state.complete(".s"), [".set", ".show"]);
+    }
+}
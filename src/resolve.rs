@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use tracing::{info, warn};
+
+use crate::mailcheck::DohResolver;
+
+/// DNS-over-HTTPS endpoint for WHOIS server names the system resolver cannot resolve, set once from `--doh`
+static DOH_URL: OnceLock<String> = OnceLock::new();
+/// Addresses found over DNS-over-HTTPS by host, so a batch asks once per server
+static RESOLVED: OnceLock<Mutex<HashMap<String, Vec<IpAddr>>>> = OnceLock::new();
+/// Where connections to a server really go, from the config's `server_overrides`, keyed by lower case host
+static OVERRIDES: OnceLock<HashMap<String, String>> = OnceLock::new();
+
+/// Resolve WHOIS server names over `url` when the system resolver fails
+pub fn set_doh(url: impl Into<String>) -> Result<()> {
+    DOH_URL.set(url.into()).map_err(|_| anyhow::anyhow!("The DNS-over-HTTPS endpoint is already set"))
+}
+
+/// The endpoint set with `set_doh`, if any
+pub fn doh_url() -> Option<&'static str> {
+    DOH_URL.get().map(String::as_str)
+}
+
+/// Send connections for a host (or `host:port`) to another address instead, like a hosts file entry
+pub fn set_overrides(overrides: &HashMap<String, String>) -> Result<()> {
+    let overrides = overrides.iter().map(|(server, target)| (server.trim().to_lowercase(), target.trim().to_string())).collect();
+    OVERRIDES.set(overrides).map_err(|_| anyhow::anyhow!("The server overrides are already set"))
+}
+
+/// The address connections for `address` go to: its override, if any, with the port of
+/// `address` unless the override names one
+pub fn redirect(address: &str) -> String {
+    let overrides = OVERRIDES.get();
+    let Some(((host, port), overrides)) = split_address(address).zip(overrides) else {
+        return address.to_string();
+    };
+    let target = overrides.get(&address.to_lowercase()).or_else(|| overrides.get(&host.to_lowercase()));
+    match target {
+        Some(target) => with_port(target, port),
+        None => address.to_string(),
+    }
+}
+
+/// `target` with `port` added when it names none: `10.0.0.5` and `::1` get it, `mirror:4343` keeps its own
+fn with_port(target: &str, port: u16) -> String {
+    if let Ok(ip) = target.parse::<IpAddr>() {
+        return SocketAddr::new(ip, port).to_string();
+    }
+    match split_address(target) {
+        Some(_) => target.to_string(),
+        None => format!("{}:{}", target, port),
+    }
+}
+
+fn resolved() -> std::sync::MutexGuard<'static, HashMap<String, Vec<IpAddr>>> {
+    RESOLVED.get_or_init(Default::default).lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Addresses of a `host:port` server address, after `server_overrides`: from the system
+/// resolver, or over DNS-over-HTTPS when that fails and `--doh` is set
+pub fn resolve(address: &str, timeout: Duration) -> Result<Vec<SocketAddr>> {
+    let redirected = redirect(address);
+    if redirected != address {
+        info!("Connecting to {} for {} (server_overrides)", redirected, address);
+    }
+    let address = redirected.as_str();
+    let split = split_address(address);
+    if let Some((host, port)) = split {
+        if let Some(addresses) = resolved().get(&host.to_lowercase()) {
+            return Ok(addresses.iter().map(|ip| SocketAddr::new(*ip, port)).collect());
+        }
+    }
+
+    let error = match address.to_socket_addrs() {
+        Ok(addresses) => {
+            let addresses: Vec<SocketAddr> = addresses.collect();
+            if !addresses.is_empty() {
+                return Ok(addresses);
+            }
+            anyhow::anyhow!("no addresses found")
+        }
+        Err(err) => err.into(),
+    };
+    let (Some(url), Some((host, port))) = (doh_url(), split) else {
+        return Err(error.context(format!("Cannot resolve WHOIS server: {}", address)));
+    };
+
+    warn!("System resolver failed for {} ({}); resolving over {}", host, error, url);
+    let addresses = DohResolver::new()
+        .with_base_url(url)
+        .with_timeout(timeout)
+        .addresses(host)
+        .with_context(|| format!("Cannot resolve WHOIS server: {}", address))?;
+    if addresses.is_empty() {
+        anyhow::bail!("Cannot resolve WHOIS server: {}: no addresses found over {}", address, url);
+    }
+    info!("Resolved {} over DNS-over-HTTPS to {:?}", host, addresses);
+    resolved().insert(host.to_lowercase(), addresses.clone());
+    Ok(addresses.into_iter().map(|ip| SocketAddr::new(ip, port)).collect())
+}
+
+/// Host and port of `host:port` or `[v6]:port`
+fn split_address(address: &str) -> Option<(&str, u16)> {
+    let (host, port) = address.rsplit_once(':')?;
+    let host = host.strip_prefix('[').and_then(|host| host.strip_suffix(']')).unwrap_or(host);
+    Some((host, port.parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve() {
+        assert_eq!(split_address("whois.ripe.net:43"), Some(("whois.ripe.net", 43)));
+        assert_eq!(split_address("[2001:67c:2e8:22::c100:68b]:43"), Some(("2001:67c:2e8:22::c100:68b", 43)));
+        assert_eq!(split_address("whois.ripe.net"), None);
+
+        assert_eq!(resolve("127.0.0.1:4343", Duration::from_secs(1)).unwrap(), ["127.0.0.1:4343".parse().unwrap()]);
+        // Names resolved over DNS-over-HTTPS are not asked again
+        resolved().insert("whois.resolve-test.invalid".to_string(), vec!["192.0.2.43".parse().unwrap()]);
+        assert_eq!(resolve("WHOIS.resolve-test.invalid:43", Duration::from_secs(1)).unwrap(), ["192.0.2.43:43".parse().unwrap()]);
+        assert!(resolve("whois.unknown.invalid:43", Duration::from_secs(1)).unwrap_err().to_string().contains("Cannot resolve WHOIS server"));
+    }
+
+    #[test]
+    fn test_redirect() {
+        let overrides = HashMap::from([
+            ("WHOIS.OVERRIDE-A.TEST".to_string(), "10.0.0.5:4343".to_string()),
+            ("whois.override-b.test".to_string(), "::1".to_string()),
+            ("whois.override-c.test:4321".to_string(), "mirror.lab".to_string()),
+        ]);
+        set_overrides(&overrides).unwrap();
+        assert_eq!(redirect("whois.override-a.test:43"), "10.0.0.5:4343");
+        assert_eq!(redirect("whois.override-b.test:43"), "[::1]:43");
+        assert_eq!(redirect("whois.override-c.test:4321"), "mirror.lab:4321");
+        assert_eq!(redirect("whois.override-c.test:43"), "whois.override-c.test:43");
+        assert_eq!(resolve("whois.override-b.test:4343", Duration::from_secs(1)).unwrap(), ["[::1]:4343".parse().unwrap()]);
+    }
+}
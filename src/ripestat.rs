@@ -0,0 +1,412 @@
+#[cfg(feature = "net")]
+use std::time::Duration;
+
+#[cfg(feature = "net")]
+use anyhow::{Context, Result};
+use serde_json::Value;
+#[cfg(feature = "net")]
+use tracing::{debug, info};
+
+use crate::i18n::pad;
+use crate::tr;
+
+/// Base URL of the RIPEstat Data API
+pub const RIPESTAT_URL: &str = "https://stat.ripe.net/data";
+/// Identifies the client to RIPEstat, as its usage guidelines ask
+#[cfg(feature = "net")]
+const SOURCE_APP: &str = "whois-cli";
+#[cfg(feature = "net")]
+const DEFAULT_TIMEOUT_SECONDS: u64 = 10;
+
+/// AS number of a normalized `AS3333` target
+pub fn parse_asn(target: &str) -> Option<u32> {
+    target.strip_prefix("AS")?.parse().ok()
+}
+
+/// A prefix seen announced by an AS
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnnouncedPrefix {
+    pub prefix: String,
+    /// Start of the earliest visibility window, as reported (`2023-01-01T00:00:00`)
+    pub first_seen: Option<String>,
+    /// End of the latest visibility window
+    pub last_seen: Option<String>,
+}
+
+/// Which side of an adjacency a neighbour is on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NeighbourSide {
+    /// Seen to the left of the AS in paths: a likely upstream
+    Upstream,
+    /// Seen to the right: a likely downstream or customer
+    Downstream,
+    Uncertain,
+}
+
+impl std::fmt::Display for NeighbourSide {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NeighbourSide::Upstream => write!(f, "upstream"),
+            NeighbourSide::Downstream => write!(f, "downstream"),
+            NeighbourSide::Uncertain => write!(f, "uncertain"),
+        }
+    }
+}
+
+/// A BGP neighbour of an AS
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Neighbour {
+    pub asn: u32,
+    pub side: NeighbourSide,
+    /// Number of RIS peers that see the adjacency
+    pub power: u64,
+    pub v4_peers: u64,
+    pub v6_peers: u64,
+}
+
+/// Routing status and abuse contact of a resource, for `--stat`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StatSummary {
+    pub resource: String,
+    /// Origin ASNs of the resource (IPs and prefixes)
+    pub origins: Vec<u32>,
+    /// RIS peers seeing the resource, out of all peers: (seeing, total) for IPv4 and IPv6
+    pub visibility_v4: Option<(u64, u64)>,
+    pub visibility_v6: Option<(u64, u64)>,
+    /// When the resource was first seen in routing, as reported
+    pub first_seen: Option<String>,
+    /// Prefixes announced by an ASN: (IPv4, IPv6)
+    pub announced: Option<(u64, u64)>,
+    pub abuse_contacts: Vec<String>,
+    /// RIR holding the resource (`ripe`, `arin`, ...)
+    pub authoritative_rir: Option<String>,
+}
+
+impl StatSummary {
+    /// Summary block as `%` comment lines
+    pub fn render(&self) -> String {
+        let mut lines = vec![format!("% ─── {} ───", tr!("stat-heading", resource = self.resource))];
+        let mut field = |name: String, value: String| lines.push(format!("% {}{}", pad(&format!("{}:", name), 16), value));
+        if !self.origins.is_empty() {
+            let origins: Vec<String> = self.origins.iter().map(|asn| format!("AS{}", asn)).collect();
+            field(tr!("stat-origin"), origins.join(", "));
+        }
+        let visibility: Vec<String> = [("IPv4", self.visibility_v4), ("IPv6", self.visibility_v6)]
+            .into_iter()
+            .filter_map(|(family, seen)| {
+                let (seeing, total) = seen.filter(|(_, total)| *total > 0)?;
+                Some(tr!("stat-visibility-value", family = family, seeing = seeing, total = total))
+            })
+            .collect();
+        if !visibility.is_empty() {
+            field(tr!("stat-visibility"), visibility.join(", "));
+        }
+        if let Some(first_seen) = &self.first_seen {
+            field(tr!("stat-first-seen"), first_seen.clone());
+        }
+        if let Some((v4, v6)) = self.announced {
+            field(tr!("stat-announced"), tr!("stat-announced-value", v4 = v4, v6 = v6));
+        }
+        let abuse = if self.abuse_contacts.is_empty() {
+            tr!("stat-no-abuse")
+        } else {
+            self.abuse_contacts.join(", ")
+        };
+        match &self.authoritative_rir {
+            Some(rir) => field(tr!("stat-abuse"), format!("{} ({})", abuse, rir.to_uppercase())),
+            None => field(tr!("stat-abuse"), abuse),
+        }
+        lines.join("\n")
+    }
+}
+
+/// Client for the RIPEstat Data API
+/// Works over HTTPS, so it is also usable where port 43 is blocked
+#[cfg(feature = "net")]
+pub struct RipeStat {
+    base_url: String,
+    timeout: Duration,
+}
+
+#[cfg(feature = "net")]
+impl Default for RipeStat {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "net")]
+impl RipeStat {
+    pub fn new() -> Self {
+        Self {
+            base_url: RIPESTAT_URL.to_string(),
+            timeout: Duration::from_secs(DEFAULT_TIMEOUT_SECONDS),
+        }
+    }
+
+    /// Use another API endpoint, e.g. a mirror or a local test server
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Fetch one data call and return its `data` object
+    pub fn fetch(&self, call: &str, resource: &str) -> Result<Value> {
+        let url = format!("{}/{}/data.json", self.base_url, call);
+        info!("Fetching RIPEstat {} for {}", call, resource);
+        let agent = ureq::AgentBuilder::new()
+            .timeout(self.timeout)
+            .user_agent(concat!("whois-cli/", env!("CARGO_PKG_VERSION")))
+            .build();
+        let body: Value = agent
+            .get(&url)
+            .query("resource", resource)
+            .query("sourceapp", SOURCE_APP)
+            .call()
+            .with_context(|| format!("RIPEstat {} request failed", call))?
+            .into_json()
+            .with_context(|| format!("Invalid RIPEstat {} response", call))?;
+        data_of(body, call)
+    }
+
+    /// Prefixes announced by an AS over the last two weeks
+    pub fn announced_prefixes(&self, asn: u32) -> Result<Vec<AnnouncedPrefix>> {
+        Ok(parse_prefixes(&self.fetch("announced-prefixes", &format!("AS{}", asn))?))
+    }
+
+    /// BGP neighbours of an AS, strongest adjacency first
+    pub fn neighbours(&self, asn: u32) -> Result<Vec<Neighbour>> {
+        Ok(parse_neighbours(&self.fetch("asn-neighbours", &format!("AS{}", asn))?))
+    }
+
+    /// Routing status and abuse contact of an ASN, IP address or prefix
+    /// The abuse contact is optional: its lookup failing leaves the field empty
+    pub fn summary(&self, resource: &str) -> Result<StatSummary> {
+        let mut summary = parse_routing_status(resource, &self.fetch("routing-status", resource)?);
+        match self.fetch("abuse-contact-finder", resource) {
+            Ok(data) => {
+                summary.abuse_contacts = string_list(&data["abuse_contacts"]);
+                summary.authoritative_rir = data["authoritative_rir"].as_str().map(str::to_string);
+            }
+            Err(err) => debug!("Abuse contact lookup failed: {:#}", err),
+        }
+        Ok(summary)
+    }
+}
+
+/// Summary fields from a `routing-status` data object
+pub fn parse_routing_status(resource: &str, data: &Value) -> StatSummary {
+    let visibility = |family: &str| {
+        let entry = &data["visibility"][family];
+        Some((entry["ris_peers_seeing"].as_u64()?, entry["total_ris_peers"].as_u64()?))
+    };
+    let space = &data["announced_space"];
+    let announced = match (space["v4"]["prefixes"].as_u64(), space["v6"]["prefixes"].as_u64()) {
+        (None, None) => None,
+        (v4, v6) => Some((v4.unwrap_or(0), v6.unwrap_or(0))),
+    };
+    let origins = data["origins"]
+        .as_array()
+        .map(|origins| origins.iter().filter_map(|origin| u32::try_from(origin["origin"].as_u64()?).ok()).collect())
+        .unwrap_or_default();
+    StatSummary {
+        resource: resource.to_string(),
+        origins,
+        visibility_v4: visibility("v4"),
+        visibility_v6: visibility("v6"),
+        first_seen: data["first_seen"]["time"].as_str().map(str::to_string),
+        // Announced space is only meaningful for ASNs; IP lookups report the covering prefix
+        announced: if parse_asn(resource).is_some() { announced } else { None },
+        ..Default::default()
+    }
+}
+
+#[cfg(feature = "net")]
+fn string_list(value: &Value) -> Vec<String> {
+    value
+        .as_array()
+        .map(|items| items.iter().filter_map(Value::as_str).map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// Unwrap the `data` object of a response, failing on a non-`ok` status
+#[cfg(feature = "net")]
+fn data_of(mut body: Value, call: &str) -> Result<Value> {
+    let status = body.get("status").and_then(Value::as_str).unwrap_or("missing");
+    if status != "ok" {
+        let message = body.get("messages").map(Value::to_string).unwrap_or_default();
+        anyhow::bail!("RIPEstat {} returned status {} {}", call, status, message);
+    }
+    debug!("RIPEstat {} data call version {}", call, body["version"]);
+    Ok(body["data"].take())
+}
+
+/// Prefixes from an `announced-prefixes` data object, in the order RIPEstat lists them
+pub fn parse_prefixes(data: &Value) -> Vec<AnnouncedPrefix> {
+    let Some(prefixes) = data["prefixes"].as_array() else {
+        return Vec::new();
+    };
+    prefixes
+        .iter()
+        .filter_map(|entry| {
+            let prefix = entry["prefix"].as_str()?.to_string();
+            let timelines = entry["timelines"].as_array().map(Vec::as_slice).unwrap_or_default();
+            let first_seen = timelines.iter().filter_map(|window| window["starttime"].as_str()).min();
+            let last_seen = timelines.iter().filter_map(|window| window["endtime"].as_str()).max();
+            Some(AnnouncedPrefix {
+                prefix,
+                first_seen: first_seen.map(str::to_string),
+                last_seen: last_seen.map(str::to_string),
+            })
+        })
+        .collect()
+}
+
+/// Neighbours from an `asn-neighbours` data object, sorted by power
+pub fn parse_neighbours(data: &Value) -> Vec<Neighbour> {
+    let Some(entries) = data["neighbours"].as_array() else {
+        return Vec::new();
+    };
+    let mut neighbours: Vec<Neighbour> = entries
+        .iter()
+        .filter_map(|entry| {
+            let side = match entry["type"].as_str()? {
+                "left" => NeighbourSide::Upstream,
+                "right" => NeighbourSide::Downstream,
+                _ => NeighbourSide::Uncertain,
+            };
+            Some(Neighbour {
+                asn: u32::try_from(entry["asn"].as_u64()?).ok()?,
+                side,
+                power: entry["power"].as_u64().unwrap_or(0),
+                v4_peers: entry["v4_peers"].as_u64().unwrap_or(0),
+                v6_peers: entry["v6_peers"].as_u64().unwrap_or(0),
+            })
+        })
+        .collect();
+    neighbours.sort_by(|a, b| b.power.cmp(&a.power).then(a.asn.cmp(&b.asn)));
+    neighbours
+}
+
+/// Prefix table as `%` comment lines, IPv4 before IPv6
+pub fn render_prefixes(asn: u32, prefixes: &[AnnouncedPrefix]) -> String {
+    let mut sorted: Vec<&AnnouncedPrefix> = prefixes.iter().collect();
+    sorted.sort_by_key(|prefix| prefix.prefix.contains(':'));
+    let mut lines = vec![
+        format!("% ─── {} ───", tr!("prefixes-heading", asn = asn, count = prefixes.len())),
+        format!("% {} {} {}", pad(&tr!("prefixes-prefix"), 44), pad(&tr!("prefixes-first-seen"), 20), tr!("prefixes-last-seen")),
+    ];
+    lines.extend(sorted.into_iter().map(|prefix| {
+        format!(
+            "% {:<44} {:<20} {}",
+            prefix.prefix,
+            prefix.first_seen.as_deref().unwrap_or("-"),
+            prefix.last_seen.as_deref().unwrap_or("-")
+        )
+    }));
+    lines.join("\n")
+}
+
+/// Neighbour table as `%` comment lines
+pub fn render_neighbours(asn: u32, neighbours: &[Neighbour]) -> String {
+    let mut lines = vec![
+        format!("% ─── {} ───", tr!("neighbours-heading", asn = asn, count = neighbours.len())),
+        format!(
+            "% {:<12} {} {:>6} {:>9} {:>9}",
+            "ASN",
+            pad(&tr!("neighbours-side"), 11),
+            tr!("neighbours-power"),
+            tr!("neighbours-v4"),
+            tr!("neighbours-v6")
+        ),
+    ];
+    lines.extend(neighbours.iter().map(|neighbour| {
+        format!(
+            "% {:<12} {:<11} {:>6} {:>9} {:>9}",
+            format!("AS{}", neighbour.asn),
+            neighbour.side.to_string(),
+            neighbour.power,
+            neighbour.v4_peers,
+            neighbour.v6_peers
+        )
+    }));
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_prefixes() {
+        let data = json!({"prefixes": [
+            {"prefix": "2001:67c:2e8::/48", "timelines": [{"starttime": "2023-03-01T00:00:00", "endtime": "2023-03-15T00:00:00"}]},
+            {"prefix": "193.0.0.0/21", "timelines": [
+                {"starttime": "2023-03-05T00:00:00", "endtime": "2023-03-10T00:00:00"},
+                {"starttime": "2023-03-01T00:00:00", "endtime": "2023-03-02T00:00:00"}
+            ]}
+        ]});
+        let prefixes = parse_prefixes(&data);
+        assert_eq!(prefixes.len(), 2);
+        assert_eq!(prefixes[1].first_seen.as_deref(), Some("2023-03-01T00:00:00"));
+        assert_eq!(prefixes[1].last_seen.as_deref(), Some("2023-03-10T00:00:00"));
+
+        let table = render_prefixes(3333, &prefixes);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines[0], "% ─── Prefixes announced by AS3333 (2) ───");
+        assert!(lines[2].starts_with("% 193.0.0.0/21 "));
+    }
+
+    #[test]
+    fn test_parse_neighbours() {
+        let data = json!({"neighbours": [
+            {"asn": 64500, "type": "right", "power": 3, "v4_peers": 2, "v6_peers": 1},
+            {"asn": 1299, "type": "left", "power": 120, "v4_peers": 100, "v6_peers": 90},
+            {"asn": "bad", "type": "left", "power": 1}
+        ]});
+        let neighbours = parse_neighbours(&data);
+        assert_eq!(neighbours.len(), 2);
+        assert_eq!(neighbours[0].asn, 1299);
+        assert_eq!(neighbours[0].side, NeighbourSide::Upstream);
+        assert!(render_neighbours(3333, &neighbours).contains("% AS64500      downstream"));
+    }
+
+    #[test]
+    fn test_routing_status_summary() {
+        let data = json!({
+            "first_seen": {"prefix": "193.0.0.0/21", "origin": "3333", "time": "2004-01-01T00:00:00"},
+            "visibility": {"v4": {"ris_peers_seeing": 330, "total_ris_peers": 335}, "v6": {"ris_peers_seeing": 0, "total_ris_peers": 0}},
+            "announced_space": {"v4": {"prefixes": 4, "ips": 4096}, "v6": {"prefixes": 2, "48s": 3}},
+            "origins": []
+        });
+        let mut summary = parse_routing_status("AS3333", &data);
+        assert_eq!(summary.announced, Some((4, 2)));
+        assert_eq!(summary.visibility_v4, Some((330, 335)));
+        summary.abuse_contacts = vec!["abuse@ripe.net".to_string()];
+        summary.authoritative_rir = Some("ripe".to_string());
+
+        let rendered = summary.render();
+        assert!(rendered.contains("% Visibility:     IPv4 330/335 RIS peers\n"));
+        assert!(rendered.contains("% Announced:      4 IPv4 and 2 IPv6 prefixes"));
+        assert!(rendered.ends_with("% Abuse contact:  abuse@ripe.net (RIPE)"));
+
+        let prefix = parse_routing_status("193.0.0.0/21", &json!({"origins": [{"origin": 3333}], "announced_space": {"v4": {"prefixes": 1}}}));
+        assert_eq!(prefix.origins, vec![3333]);
+        assert_eq!(prefix.announced, None);
+    }
+
+    #[test]
+    #[cfg(feature = "net")]
+    fn test_error_status() {
+        assert!(data_of(json!({"status": "error", "messages": [["error", "bad resource"]]}), "x").is_err());
+        assert_eq!(data_of(json!({"status": "ok", "data": {"a": 1}}), "x").unwrap(), json!({"a": 1}));
+        assert_eq!(parse_asn("AS3333"), Some(3333));
+        assert_eq!(parse_asn("193.0.0.1"), None);
+    }
+}
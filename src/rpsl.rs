@@ -0,0 +1,683 @@
+use std::collections::HashMap;
+
+use crate::ansi::strip_ansi;
+
+/// Longest attribute value kept, continuation lines included; longer lines are not attributes
+/// and further continuation lines are dropped from the parsed value
+pub const MAX_ATTRIBUTE_LENGTH: usize = 64 * 1024;
+/// Most blocks a response is split into; everything after the last one is kept as one text block
+pub const MAX_BLOCKS: usize = 100_000;
+
+/// One `name: value` attribute of an RPSL object
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attribute {
+    pub name: String,
+    /// Value with continuation lines joined by `\n`
+    pub value: String,
+    /// Index of the attribute's first line within the object
+    pub line: usize,
+}
+
+/// An RPSL-style object: a block of `name: value` lines
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RpslObject {
+    pub attributes: Vec<Attribute>,
+    /// Original lines, including any server-side coloring and inline comments
+    pub lines: Vec<String>,
+}
+
+/// A blank-line separated block of a response
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Block {
+    Object(RpslObject),
+    /// Comments, banners and anything else that is not an object
+    Text(String),
+}
+
+impl RpslObject {
+    /// Object class, i.e. the name of the first attribute
+    pub fn class(&self) -> &str {
+        self.attributes.first().map_or("", |attr| attr.name.as_str())
+    }
+
+    /// Primary key, i.e. the value of the first attribute
+    pub fn key(&self) -> &str {
+        self.attributes.first().map_or("", |attr| attr.value.as_str())
+    }
+
+    /// Values of every attribute with the given name (case-insensitive)
+    pub fn values<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.attributes
+            .iter()
+            .filter(move |attr| attr.name.eq_ignore_ascii_case(name))
+            .map(|attr| attr.value.as_str())
+    }
+
+    /// First value of an attribute
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|attr| attr.name.eq_ignore_ascii_case(name))
+            .map(|attr| attr.value.as_str())
+    }
+
+    /// Render the object back to text
+    pub fn to_text(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    /// Attributes normalized for comparison, optionally leaving some names out
+    fn normalized(&self, ignore: &[&str]) -> Vec<(String, String)> {
+        self.attributes
+            .iter()
+            .filter(|attr| !ignore.iter().any(|name| attr.name.eq_ignore_ascii_case(name)))
+            .map(|attr| {
+                let value = attr.value.split_whitespace().collect::<Vec<_>>().join(" ");
+                (attr.name.to_lowercase(), value)
+            })
+            .collect()
+    }
+}
+
+/// Split a response into objects and text blocks
+pub fn parse(response: &str) -> Vec<Block> {
+    parse_with_lines(response).into_iter().map(|(_, block)| block).collect()
+}
+
+/// Split a response into objects and text blocks, each with the index of its first line
+pub fn parse_with_lines(response: &str) -> Vec<(usize, Block)> {
+    let mut blocks = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    let mut start = 0;
+    let mut lines = response.lines().enumerate();
+    while let Some((index, line)) = lines.next() {
+        if blocks.len() + 1 >= MAX_BLOCKS && current.is_empty() {
+            // The rest is not worth splitting up: one text block keeps it intact
+            let rest: Vec<&str> = std::iter::once(line).chain(lines.by_ref().map(|(_, line)| line)).collect();
+            blocks.push((index, text_block(&rest)));
+            return blocks;
+        }
+        if strip_ansi(line).trim().is_empty() {
+            if !current.is_empty() {
+                blocks.push((start, parse_block(&current)));
+                current.clear();
+            }
+        } else {
+            if current.is_empty() {
+                start = index;
+            }
+            current.push(line);
+        }
+    }
+    if !current.is_empty() {
+        blocks.push((start, parse_block(&current)));
+    }
+    blocks
+}
+
+/// Render blocks back to text, separated by blank lines
+pub fn render(blocks: &[Block]) -> String {
+    blocks
+        .iter()
+        .map(block_text)
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Parse one block; it is an object only if every non-comment line is an attribute or continuation
+fn parse_block(lines: &[&str]) -> Block {
+    let mut attributes: Vec<Attribute> = Vec::new();
+    for (index, raw) in lines.iter().enumerate() {
+        let line = strip_ansi(raw);
+        if line.starts_with('%') || line.starts_with('#') {
+            continue;
+        }
+        // Continuation lines start with whitespace or `+`
+        if line.starts_with([' ', '\t', '+']) {
+            match attributes.last_mut() {
+                Some(attr) => {
+                    let continued = line.trim_start_matches('+').trim();
+                    if attr.value.len() + continued.len() < MAX_ATTRIBUTE_LENGTH {
+                        attr.value.push('\n');
+                        attr.value.push_str(continued);
+                    }
+                    continue;
+                }
+                None => return text_block(lines),
+            }
+        }
+        match parse_attribute(&line) {
+            Some((name, value)) => attributes.push(Attribute { name, value, line: index }),
+            None => return text_block(lines),
+        }
+    }
+
+    if attributes.is_empty() {
+        return text_block(lines);
+    }
+    Block::Object(RpslObject {
+        attributes,
+        lines: lines.iter().map(|line| line.to_string()).collect(),
+    })
+}
+
+fn text_block(lines: &[&str]) -> Block {
+    Block::Text(lines.join("\n"))
+}
+
+/// Split an `name: value` line; names are letters, digits, `-` and `_`
+pub(crate) fn parse_attribute(line: &str) -> Option<(String, String)> {
+    if line.len() > MAX_ATTRIBUTE_LENGTH {
+        return None;
+    }
+    let (name, value) = line.split_once(':')?;
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return None;
+    }
+    Some((name.to_string(), value.trim().to_string()))
+}
+
+/// First copy of an object during dedup, with the sources of later copies
+struct SeenObject {
+    /// Position in the kept blocks
+    index: usize,
+    sources: Vec<String>,
+}
+
+/// Collapse objects that appear more than once, e.g. the same route from several IRR sources
+/// Objects are compared ignoring `source:`; the first copy keeps its position and its
+/// `source:` line is annotated with the other sources. Responses without duplicates are returned unchanged.
+pub fn dedup_objects(response: &str) -> String {
+    let blocks = parse(response);
+    let mut kept: Vec<Block> = Vec::new();
+    // Keyed by the attributes without `source:`
+    let mut seen: HashMap<Vec<(String, String)>, SeenObject> = HashMap::new();
+    let mut removed = false;
+
+    for block in blocks {
+        if let Block::Object(object) = &block {
+            let normalized = object.normalized(&["source"]);
+            if let Some(first) = seen.get_mut(&normalized) {
+                if let Some(source) = object.get("source") {
+                    if !first.sources.iter().any(|known| known == source) {
+                        first.sources.push(source.to_string());
+                    }
+                }
+                removed = true;
+                continue;
+            }
+            seen.insert(normalized, SeenObject { index: kept.len(), sources: Vec::new() });
+        }
+        kept.push(block);
+    }
+
+    if !removed {
+        return response.to_string();
+    }
+
+    for SeenObject { index, sources } in seen.into_values() {
+        let Block::Object(object) = &mut kept[index] else {
+            continue;
+        };
+        let own = object.get("source").map(str::to_string);
+        let others: Vec<&str> = sources
+            .iter()
+            .map(String::as_str)
+            .filter(|source| Some(*source) != own.as_deref())
+            .collect();
+        if others.is_empty() {
+            continue;
+        }
+        let others = others.join(", ");
+        match object.attributes.iter().find(|attr| attr.name.eq_ignore_ascii_case("source")) {
+            Some(attr) => object.lines[attr.line].push_str(&format!("  # also in: {}", others)),
+            None => object.lines.push(format!("% also in: {}", others)),
+        }
+    }
+
+    let mut output = render(&kept);
+    if response.ends_with('\n') {
+        output.push('\n');
+    }
+    output
+}
+
+/// Section order for `group_by_type`: title and the object classes it collects
+const TYPE_GROUPS: &[(&str, &[&str])] = &[
+    ("Routes", &["route", "route6"]),
+    ("Autonomous systems", &["aut-num", "as-block"]),
+    ("Networks", &["inetnum", "inet6num", "domain"]),
+    ("Sets", &["as-set", "route-set", "rtr-set", "peering-set", "filter-set"]),
+    ("Contacts", &["person", "role", "organisation", "irt"]),
+    ("Maintainers", &["mntner", "key-cert"]),
+];
+
+/// Reorder objects so each type appears together under a section header
+/// Comments between objects travel with the object that follows them; other text before the
+/// first object and after the last stays in place. Classes without a group go last.
+pub fn group_by_type(response: &str) -> String {
+    let blocks = parse(response);
+    let Some(mut first) = blocks.iter().position(|block| matches!(block, Block::Object(_))) else {
+        return response.to_string();
+    };
+    // The RIPE-style header of the first object travels with it as well
+    if first > 0 && matches!(&blocks[first - 1], Block::Text(text) if strip_ansi(text).starts_with("% Information related to")) {
+        first -= 1;
+    }
+    let last = blocks.iter().rposition(|block| matches!(block, Block::Object(_))).unwrap_or(first);
+
+    // Each object with the comment blocks directly before it
+    let mut entries: Vec<(usize, Vec<&Block>)> = Vec::new();
+    let mut pending: Vec<&Block> = Vec::new();
+    for block in &blocks[first..=last] {
+        pending.push(block);
+        if let Block::Object(object) = block {
+            let class = object.class().to_lowercase();
+            let group = TYPE_GROUPS
+                .iter()
+                .position(|(_, classes)| classes.contains(&class.as_str()))
+                .unwrap_or(TYPE_GROUPS.len());
+            entries.push((group, std::mem::take(&mut pending)));
+        }
+    }
+
+    let mut sections: Vec<String> = blocks[..first].iter().map(block_text).collect();
+    for group in 0..=TYPE_GROUPS.len() {
+        let members: Vec<&Vec<&Block>> = entries
+            .iter()
+            .filter(|(entry_group, _)| *entry_group == group)
+            .map(|(_, blocks)| blocks)
+            .collect();
+        if members.is_empty() {
+            continue;
+        }
+        let title = TYPE_GROUPS.get(group).map_or("Other objects", |(title, _)| title);
+        sections.push(format!("% ─── {} ({}) ───", title, members.len()));
+        sections.extend(members.into_iter().flatten().map(|block| block_text(block)));
+    }
+    sections.extend(blocks[last + 1..].iter().map(block_text));
+
+    let mut output = sections.join("\n\n");
+    if response.ends_with('\n') {
+        output.push('\n');
+    }
+    output
+}
+
+/// Policy attributes summarized by `fold_policy`
+const POLICY_ATTRIBUTES: &[&str] = &["import", "export", "mp-import", "mp-export"];
+/// Fewest lines of one policy attribute that are folded
+pub const FOLD_POLICY_MIN: usize = 10;
+/// Peers listed by count under a folded policy
+const TOP_PEERS: usize = 5;
+
+/// Peer of an import/export line: the AS or set named after the first `from` or `to`
+pub fn policy_peer(value: &str) -> Option<String> {
+    let mut words = value.split_whitespace();
+    words.find(|word| word.eq_ignore_ascii_case("from") || word.eq_ignore_ascii_case("to"))?;
+    words.next().map(|peer| peer.to_uppercase())
+}
+
+/// Peers of an object's import/export lines of one attribute, most lines first
+pub fn policy_peers(object: &RpslObject, name: &str) -> Vec<(String, usize)> {
+    let mut peers: Vec<(String, usize)> = Vec::new();
+    for peer in object.values(name).filter_map(policy_peer) {
+        match peers.iter_mut().find(|(known, _)| *known == peer) {
+            Some((_, count)) => *count += 1,
+            None => peers.push((peer, 1)),
+        }
+    }
+    // Stable sort keeps the order of first appearance among equal counts
+    peers.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    peers
+}
+
+/// Replace long import/export lists with one summary line each, counting lines and peers
+/// Attributes with fewer than `FOLD_POLICY_MIN` lines are kept
+pub fn fold_policy(response: &str) -> String {
+    let mut folded = false;
+    let blocks: Vec<Block> = parse(response)
+        .into_iter()
+        .map(|block| match block {
+            Block::Object(object) => match fold_object(&object) {
+                Some(lines) => {
+                    folded = true;
+                    Block::Text(lines.join("\n"))
+                }
+                None => Block::Object(object),
+            },
+            text => text,
+        })
+        .collect();
+    if !folded {
+        return response.to_string();
+    }
+    let mut output = render(&blocks);
+    if response.ends_with('\n') {
+        output.push('\n');
+    }
+    output
+}
+
+/// Lines of an object with its long policy attributes folded, or `None` if none is long enough
+fn fold_object(object: &RpslObject) -> Option<Vec<String>> {
+    // Each attribute spans the lines up to the next attribute
+    let span = |index: usize| {
+        let start = object.attributes[index].line;
+        let end = object.attributes.get(index + 1).map_or(object.lines.len(), |next| next.line);
+        start..end
+    };
+    let mut summaries: Vec<(usize, Vec<String>)> = Vec::new();
+    let mut dropped = vec![false; object.lines.len()];
+    for name in POLICY_ATTRIBUTES {
+        let indices: Vec<usize> = (0..object.attributes.len())
+            .filter(|&index| object.attributes[index].name.eq_ignore_ascii_case(name))
+            .collect();
+        if indices.len() < FOLD_POLICY_MIN {
+            continue;
+        }
+        for &index in &indices {
+            span(index).for_each(|line| dropped[line] = true);
+        }
+        let first = strip_ansi(&object.lines[object.attributes[indices[0]].line]);
+        let (field, rest) = first.split_once(':').unwrap_or((name, " "));
+        let spacing = &rest[..rest.len() - rest.trim_start().len()];
+        let spacing = if spacing.is_empty() { " " } else { spacing };
+        let peers = policy_peers(object, name);
+        let mut summary = vec![format!(
+            "{}:{}{} lines from {} unique {} — use --full-policy to expand",
+            field,
+            spacing,
+            thousands(indices.len()),
+            thousands(peers.len()),
+            if peers.len() == 1 { "peer" } else { "peers" }
+        )];
+        if !peers.is_empty() {
+            let top: Vec<String> = peers.iter().take(TOP_PEERS).map(|(peer, count)| format!("{} ({})", peer, count)).collect();
+            summary.push(format!("{}top peers: {}", " ".repeat(field.len() + 1 + spacing.len()), top.join(", ")));
+        }
+        summaries.push((object.attributes[indices[0]].line, summary));
+    }
+    if summaries.is_empty() {
+        return None;
+    }
+
+    let mut lines = Vec::with_capacity(object.lines.len());
+    for (index, line) in object.lines.iter().enumerate() {
+        if let Some((_, summary)) = summaries.iter().find(|(at, _)| *at == index) {
+            lines.extend(summary.iter().cloned());
+        } else if !dropped[index] {
+            lines.push(line.clone());
+        }
+    }
+    Some(lines)
+}
+
+/// Integer with `,` between groups of three digits
+fn thousands(number: usize) -> String {
+    let digits = number.to_string();
+    let mut output = String::with_capacity(digits.len() + digits.len() / 3);
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index).is_multiple_of(3) {
+            output.push(',');
+        }
+        output.push(digit);
+    }
+    output
+}
+
+/// Pad attribute names so every value of an object starts in the same column,
+/// one space past the longest name; continuation lines are indented to match
+/// Text blocks, comments, `+` continuations and server-colored objects are left as they are
+pub fn align(response: &str) -> String {
+    let lines: Vec<&str> = response.split('\n').collect();
+    let mut output: Vec<String> = Vec::with_capacity(lines.len());
+    let mut start = 0;
+    while start < lines.len() {
+        let end = lines[start..]
+            .iter()
+            .position(|line| strip_ansi(line).trim().is_empty())
+            .map_or(lines.len(), |offset| start + offset);
+        if start == end {
+            output.push(lines[start].to_string());
+            start += 1;
+            continue;
+        }
+        let block = &lines[start..end];
+        match parse_block(block) {
+            Block::Object(object) if !block.iter().any(|line| line.contains('\x1b')) => {
+                output.extend(align_object(&object));
+            }
+            _ => output.extend(block.iter().map(|line| line.to_string())),
+        }
+        start = end;
+    }
+    output.join("\n")
+}
+
+fn align_object(object: &RpslObject) -> Vec<String> {
+    let column = object.attributes.iter().map(|attr| attr.name.len()).max().unwrap_or(0) + 2;
+    object
+        .lines
+        .iter()
+        .map(|line| {
+            if line.starts_with(['%', '#', '+']) {
+                return line.clone();
+            }
+            if line.starts_with([' ', '\t']) {
+                return format!("{}{}", " ".repeat(column), line.trim_start());
+            }
+            match line.split_once(':') {
+                Some((name, value)) if !value.trim().is_empty() => {
+                    format!("{:width$}{}", format!("{}:", name), value.trim_start(), width = column)
+                }
+                Some((name, _)) => format!("{}:", name),
+                None => line.clone(),
+            }
+        })
+        .collect()
+}
+
+fn block_text(block: &Block) -> String {
+    match block {
+        Block::Object(object) => object.to_text(),
+        Block::Text(text) => text.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_objects_and_text() {
+        let blocks = parse("% Information related to 'AS3333'\n\naut-num:   AS3333\nas-name:   RIPE-NCC-AS\nremarks:   first\n           second\n+ third\nsource:    RIPE\n");
+        assert_eq!(blocks.len(), 2);
+        assert!(matches!(&blocks[0], Block::Text(text) if text.starts_with("% Information")));
+
+        let Block::Object(object) = &blocks[1] else {
+            panic!("expected an object");
+        };
+        assert_eq!(object.class(), "aut-num");
+        assert_eq!(object.key(), "AS3333");
+        assert_eq!(object.get("remarks"), Some("first\nsecond\nthird"));
+        assert_eq!(object.get("SOURCE"), Some("RIPE"));
+        assert_eq!(object.attributes[3].line, 5);
+    }
+
+    #[test]
+    fn test_non_attribute_lines_make_text() {
+        let blocks = parse("   Domain Name: EXAMPLE.COM\n   Registrar: Example\n\nNOTICE: The expiration date\ndisplayed in this record\n");
+        assert!(blocks.iter().all(|block| matches!(block, Block::Text(_))));
+        assert_eq!(render(&blocks), "   Domain Name: EXAMPLE.COM\n   Registrar: Example\n\nNOTICE: The expiration date\ndisplayed in this record");
+    }
+
+    #[test]
+    fn test_parser_limits() {
+        let long = format!("remarks: start\n{}", format!("+ {}\n", "x".repeat(1000)).repeat(100));
+        let Block::Object(object) = &parse(&long)[0] else {
+            panic!("expected an object");
+        };
+        assert!(object.get("remarks").unwrap().len() < MAX_ATTRIBUTE_LENGTH);
+        assert!(matches!(&parse(&format!("descr: {}", "x".repeat(MAX_ATTRIBUTE_LENGTH)))[0], Block::Text(_)));
+
+        let many = "a: 1\n\n".repeat(MAX_BLOCKS + 10);
+        let blocks = parse(&many);
+        assert_eq!(blocks.len(), MAX_BLOCKS);
+        assert!(matches!(blocks.last(), Some(Block::Text(text)) if text.matches("a: 1").count() == 11));
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn test_parse_any_text(response in "(?s).{0,2000}") {
+            let _ = render(&parse(&response));
+            let _ = dedup_objects(&response);
+            let _ = fold_policy(&response);
+            let _ = align(&response);
+        }
+
+        #[test]
+        fn test_parse_keeps_every_line(
+            lines in proptest::collection::vec("[a-z-]{1,12}:[ a-zA-Z0-9./]{0,30}|[ +][a-z ]{0,20}|%[ a-z]{0,20}|", 0..60)
+        ) {
+            let response = lines.join("\n");
+            let kept: usize = parse(&response)
+                .iter()
+                .map(|block| match block {
+                    Block::Object(object) => object.lines.len(),
+                    Block::Text(text) => text.lines().count(),
+                })
+                .sum();
+            proptest::prop_assert_eq!(kept, response.lines().filter(|line| !line.trim().is_empty()).count());
+        }
+    }
+
+    #[test]
+    fn test_dedup_annotates_sources() {
+        let response = "\
+route:          192.0.2.0/24
+origin:         AS64500
+source:         RADB
+
+route:          192.0.2.0/24
+origin:         AS64500
+source:         NTTCOM
+
+route:          198.51.100.0/24
+origin:         AS64500
+source:         RADB
+
+route:          192.0.2.0/24
+origin:         AS64500
+source:         LEVEL3
+";
+        assert_eq!(
+            dedup_objects(response),
+            "\
+route:          192.0.2.0/24
+origin:         AS64500
+source:         RADB  # also in: NTTCOM, LEVEL3
+
+route:          198.51.100.0/24
+origin:         AS64500
+source:         RADB
+"
+        );
+    }
+
+    #[test]
+    fn test_dedup_leaves_unique_responses_untouched() {
+        let response = "route: 192.0.2.0/24\norigin: AS64500\n\n\n\nroute: 192.0.2.0/24\norigin: AS64501\n";
+        assert_eq!(dedup_objects(response), response);
+    }
+
+    #[test]
+    fn test_fold_policy() {
+        let mut response = String::from("aut-num:        AS64500\n");
+        for peer in 1..=12 {
+            response.push_str(&format!("import:         from AS{} accept ANY\n", peer % 3 + 1));
+            response.push_str(&format!("export:         to AS{} announce AS64500\n", peer));
+        }
+        response.push_str("mp-import:      afi ipv6.unicast from AS1 accept ANY\nsource:         RIPE\n");
+        let folded = fold_policy(&response);
+        assert_eq!(
+            folded,
+            "aut-num:        AS64500\n\
+             import:         12 lines from 3 unique peers — use --full-policy to expand\n\
+             \x20               top peers: AS2 (4), AS3 (4), AS1 (4)\n\
+             export:         12 lines from 12 unique peers — use --full-policy to expand\n\
+             \x20               top peers: AS1 (1), AS2 (1), AS3 (1), AS4 (1), AS5 (1)\n\
+             mp-import:      afi ipv6.unicast from AS1 accept ANY\n\
+             source:         RIPE\n"
+        );
+        assert_eq!(fold_policy("aut-num: AS1\nimport: from AS2 accept ANY\n"), "aut-num: AS1\nimport: from AS2 accept ANY\n");
+    }
+
+    #[test]
+    fn test_policy_peer_and_thousands() {
+        assert_eq!(policy_peer("afi ipv6.unicast from as-example accept ANY").as_deref(), Some("AS-EXAMPLE"));
+        assert_eq!(policy_peer("accept ANY"), None);
+        assert_eq!(thousands(2431), "2,431");
+        assert_eq!(thousands(987), "987");
+        assert_eq!(thousands(1234567), "1,234,567");
+    }
+
+    #[test]
+    fn test_align_values() {
+        let response = "% Information related to 'AS3333'\n\naut-num: AS3333\nas-name:\tRIPE-NCC-AS\nmp-import:   afi ipv6.unicast from AS1 accept ANY\nremarks:\n  continued\n+\nsource: RIPE\n";
+        assert_eq!(
+            align(response),
+            "% Information related to 'AS3333'\n\naut-num:   AS3333\nas-name:   RIPE-NCC-AS\nmp-import: afi ipv6.unicast from AS1 accept ANY\nremarks:\n           continued\n+\nsource:    RIPE\n"
+        );
+        let colored = "\x1b[96maut-num\x1b[0m: AS3333\nas-name: X";
+        assert_eq!(align(colored), colored);
+    }
+
+    #[test]
+    fn test_group_by_type() {
+        let response = "\
+% Banner
+
+% Information related to 'AS64500'
+
+aut-num:        AS64500
+
+person:         Jane Doe
+
+route:          192.0.2.0/24
+
+% Information related to '198.51.100.0/24'
+
+route:          198.51.100.0/24
+
+% Footer
+";
+        assert_eq!(
+            group_by_type(response),
+            "\
+% Banner
+
+% ─── Routes (2) ───
+
+route:          192.0.2.0/24
+
+% Information related to '198.51.100.0/24'
+
+route:          198.51.100.0/24
+
+% ─── Autonomous systems (1) ───
+
+% Information related to 'AS64500'
+
+aut-num:        AS64500
+
+% ─── Contacts (1) ───
+
+person:         Jane Doe
+
+% Footer
+"
+        );
+        assert_eq!(group_by_type("% comments only\n"), "% comments only\n");
+    }
+}
@@ -0,0 +1,376 @@
+use std::sync::OnceLock;
+
+use regex::Regex;
+use serde::{Deserialize, Deserializer};
+
+use crate::ansi::strip_ansi;
+
+/// What a rule does with the lines its pattern matches
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RuleAction {
+    /// Remove every matching line
+    Drop,
+    /// Remove the whole paragraph (blank-line delimited block) around a matching line
+    DropBlock,
+    /// Remove the run of `%`/`#` comment lines around a matching line
+    DropCommentBlock,
+    /// Keep the first matching line and remove later ones
+    Fold,
+}
+
+/// How aggressively responses are cleaned up
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CleanupLevel {
+    /// Only rules that are neither disclaimers nor boilerplate
+    #[default]
+    Standard,
+    /// Also remove legal disclaimers (`--no-disclaimer`)
+    NoDisclaimer,
+    /// Also remove comment banners and other boilerplate (`--no-legal`/`--short`)
+    Short,
+}
+
+/// Response cleanup rule, e.g. from a `[[servers."host".rules]]` config entry
+///
+/// ```toml
+/// [[servers."whois.arin.net".rules]]
+/// name = "arin-terms"
+/// pattern = "^# ARIN WHOIS data and services"
+/// action = "drop-block"
+/// disclaimer = true
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct ResponseRule {
+    #[serde(default)]
+    pub name: String,
+    /// Regex matched against each line, with ANSI colors removed
+    #[serde(deserialize_with = "deserialize_regex")]
+    pub pattern: Regex,
+    pub action: RuleAction,
+    /// Only applied when disclaimers are stripped (`--no-disclaimer` or `--short`)
+    #[serde(default)]
+    pub disclaimer: bool,
+    /// Only applied to short output (`--short`)
+    #[serde(default)]
+    pub boilerplate: bool,
+}
+
+impl PartialEq for ResponseRule {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+            && self.pattern.as_str() == other.pattern.as_str()
+            && self.action == other.action
+            && self.disclaimer == other.disclaimer
+            && self.boilerplate == other.boilerplate
+    }
+}
+
+impl ResponseRule {
+    /// Create a rule from a known-good pattern
+    pub fn new(name: &str, pattern: &str, action: RuleAction) -> Self {
+        Self {
+            name: name.to_string(),
+            pattern: Regex::new(pattern).expect("built-in rule pattern must be valid"),
+            action,
+            disclaimer: false,
+            boilerplate: false,
+        }
+    }
+
+    /// Mark the rule as a disclaimer, applied only with `--no-disclaimer` or `--short`
+    pub fn as_disclaimer(mut self) -> Self {
+        self.disclaimer = true;
+        self
+    }
+
+    /// Mark the rule as boilerplate, applied only with `--short`
+    pub fn as_boilerplate(mut self) -> Self {
+        self.boilerplate = true;
+        self
+    }
+
+    /// Check if the rule runs at a cleanup level
+    pub fn applies_at(&self, level: CleanupLevel) -> bool {
+        if self.boilerplate {
+            level >= CleanupLevel::Short
+        } else if self.disclaimer {
+            level >= CleanupLevel::NoDisclaimer
+        } else {
+            true
+        }
+    }
+}
+
+fn deserialize_regex<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Regex, D::Error> {
+    let pattern = String::deserialize(deserializer)?;
+    Regex::new(&pattern).map_err(serde::de::Error::custom)
+}
+
+/// Rules applied to every server
+/// Compiled once; registries get cheap clones, which share the compiled patterns
+pub fn default_rules() -> Vec<ResponseRule> {
+    static RULES: OnceLock<Vec<ResponseRule>> = OnceLock::new();
+    RULES.get_or_init(|| vec![
+        ResponseRule::new("last-update", r"(?i)^>>>\s*last update of whois database", RuleAction::Fold),
+        // Banners shared by the RIPE-derived databases (RIPE, APNIC, AFRINIC, RADB)
+        ResponseRule::new("filtered-note", r"^% Note: this output has been filtered", RuleAction::DropCommentBlock)
+            .as_boilerplate(),
+        ResponseRule::new("served-by", r"^% This query was served by", RuleAction::Drop).as_boilerplate(),
+    ]).clone()
+}
+
+/// Built-in rules for servers with known quirks, keyed by host name
+pub fn builtin_server_rules() -> Vec<(&'static str, Vec<ResponseRule>)> {
+    static RULES: OnceLock<Vec<(&'static str, Vec<ResponseRule>)>> = OnceLock::new();
+    RULES.get_or_init(|| vec![
+        (
+            "whois.arin.net",
+            vec![
+                ResponseRule::new("arin-terms", r"^# ARIN WHOIS data and services", RuleAction::DropBlock)
+                    .as_disclaimer(),
+                ResponseRule::new("arin-copyright", r"^# Copyright \d{4}-\d{4}, American Registry", RuleAction::DropBlock)
+                    .as_disclaimer(),
+                ResponseRule::new("arin-section-markers", r"^# (start|end)\s*$", RuleAction::Drop).as_boilerplate(),
+            ],
+        ),
+        (
+            "whois.ripe.net",
+            vec![
+                ResponseRule::new("ripe-banner", r"^% This is the RIPE Database query service", RuleAction::DropCommentBlock)
+                    .as_boilerplate(),
+                ResponseRule::new("ripe-terms", r"^% The RIPE Database is subject to Terms and Conditions", RuleAction::DropCommentBlock)
+                    .as_disclaimer(),
+            ],
+        ),
+        (
+            "whois.apnic.net",
+            vec![
+                ResponseRule::new("apnic-banner", r"^% \[whois\.apnic\.net\]", RuleAction::Drop).as_boilerplate(),
+                ResponseRule::new("apnic-terms", r"^% Whois data copyright terms", RuleAction::Drop).as_disclaimer(),
+            ],
+        ),
+        (
+            "whois.afrinic.net",
+            vec![
+                ResponseRule::new("afrinic-banner", r"(?i)^% This is the AfriNIC Whois server", RuleAction::DropCommentBlock)
+                    .as_boilerplate(),
+                ResponseRule::new("afrinic-terms", r"^% The WHOIS is subject to Terms and Conditions", RuleAction::DropCommentBlock)
+                    .as_disclaimer(),
+            ],
+        ),
+        (
+            "whois.lacnic.net",
+            vec![
+                ResponseRule::new("lacnic-banner", r"^% Joint Whois - whois\.lacnic\.net", RuleAction::DropCommentBlock)
+                    .as_boilerplate(),
+                ResponseRule::new("lacnic-resource", r"^% LACNIC resource:", RuleAction::Drop).as_boilerplate(),
+                ResponseRule::new("lacnic-terms", r"^% Copyright LACNIC", RuleAction::DropCommentBlock).as_disclaimer(),
+            ],
+        ),
+        (
+            "whois.verisign-grs.com",
+            vec![
+                ResponseRule::new("verisign-registrars-ad", r"^Domain names in the \.com and \.net domains", RuleAction::DropBlock),
+                ResponseRule::new("verisign-registry-ad", r"^The Registry database contains ONLY", RuleAction::DropBlock),
+                ResponseRule::new("verisign-notice", r"^NOTICE: The expiration date displayed", RuleAction::DropBlock)
+                    .as_disclaimer(),
+                ResponseRule::new("verisign-terms", r"^TERMS OF USE: ", RuleAction::DropBlock)
+                    .as_disclaimer(),
+            ],
+        ),
+    ]).clone()
+}
+
+/// Apply the rules that run at `level` to a response
+/// Responses no rule matches are returned unchanged
+pub fn apply_rules<'a>(
+    response: &str,
+    rules: impl IntoIterator<Item = &'a ResponseRule>,
+    level: CleanupLevel,
+) -> String {
+    let lines: Vec<&str> = response.lines().collect();
+    let plain: Vec<String> = lines.iter().map(|line| strip_ansi(line)).collect();
+    let mut keep = vec![true; lines.len()];
+
+    for rule in rules.into_iter().filter(|rule| rule.applies_at(level)) {
+        let mut seen = false;
+        for index in 0..plain.len() {
+            if !rule.pattern.is_match(&plain[index]) {
+                continue;
+            }
+            match rule.action {
+                RuleAction::Drop => keep[index] = false,
+                RuleAction::DropBlock => {
+                    let (start, end) = paragraph_bounds(&plain, index);
+                    keep[start..end].iter_mut().for_each(|line| *line = false);
+                }
+                RuleAction::DropCommentBlock => {
+                    let (start, end) = comment_bounds(&plain, index);
+                    keep[start..end].iter_mut().for_each(|line| *line = false);
+                }
+                RuleAction::Fold => {
+                    if seen {
+                        keep[index] = false;
+                    }
+                    seen = true;
+                }
+            }
+        }
+    }
+
+    if keep.iter().all(|&kept| kept) {
+        return response.to_string();
+    }
+
+    // Collapse the blank lines left behind by removed blocks, judged on the visible text
+    // so lines holding only server color codes count as blank
+    let mut output: Vec<(&str, bool)> = Vec::new();
+    for ((line, plain), _) in lines.iter().zip(&plain).zip(&keep).filter(|(_, &kept)| kept) {
+        let blank = plain.trim().is_empty();
+        if blank && output.last().map_or(true, |&(_, last_blank)| last_blank) {
+            continue;
+        }
+        output.push((line, blank));
+    }
+    while output.last().is_some_and(|&(_, blank)| blank) {
+        output.pop();
+    }
+
+    let mut cleaned = output.iter().map(|&(line, _)| line).collect::<Vec<_>>().join("\n");
+    if response.ends_with('\n') && !cleaned.is_empty() {
+        cleaned.push('\n');
+    }
+    cleaned
+}
+
+/// Range of the non-blank block containing `index`
+fn paragraph_bounds(lines: &[String], index: usize) -> (usize, usize) {
+    let mut start = index;
+    while start > 0 && !lines[start - 1].trim().is_empty() {
+        start -= 1;
+    }
+    let mut end = index + 1;
+    while end < lines.len() && !lines[end].trim().is_empty() {
+        end += 1;
+    }
+    (start, end)
+}
+
+/// Range of the `%`/`#` comment lines around `index`
+/// A bare `%` or `#` line counts as part of the block
+fn comment_bounds(lines: &[String], index: usize) -> (usize, usize) {
+    let is_comment = |line: &String| {
+        let line = line.trim_start();
+        line.starts_with('%') || line.starts_with('#')
+    };
+    if !is_comment(&lines[index]) {
+        return (index, index + 1);
+    }
+    let mut start = index;
+    while start > 0 && is_comment(&lines[start - 1]) {
+        start -= 1;
+    }
+    let mut end = index + 1;
+    while end < lines.len() && is_comment(&lines[end]) {
+        end += 1;
+    }
+    (start, end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARIN_RESPONSE: &str = "\
+#
+# ARIN WHOIS data and services are subject to the Terms of Use
+# available at: https://www.arin.net/resources/registry/whois/tou/
+#
+
+NetRange:       8.8.8.0 - 8.8.8.255
+OrgName:        Google LLC
+
+#
+# ARIN WHOIS data and services are subject to the Terms of Use
+#
+";
+
+    fn arin_rules() -> Vec<ResponseRule> {
+        builtin_server_rules()
+            .into_iter()
+            .find(|(host, _)| *host == "whois.arin.net")
+            .map(|(_, rules)| rules)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_disclaimer_rules_need_opt_in() {
+        let rules = arin_rules();
+        assert_eq!(apply_rules(ARIN_RESPONSE, &rules, CleanupLevel::Standard), ARIN_RESPONSE);
+        assert_eq!(
+            apply_rules(ARIN_RESPONSE, &rules, CleanupLevel::NoDisclaimer),
+            "NetRange:       8.8.8.0 - 8.8.8.255\nOrgName:        Google LLC\n"
+        );
+    }
+
+    #[test]
+    fn test_fold_duplicate_last_update_lines() {
+        let response = "Domain Name: EXAMPLE.COM\n>>> Last update of whois database: 2024-01-01 <<<\n\nRegistrar: Example\n>>> Last update of WHOIS database: 2024-01-02 <<<\n";
+        let cleaned = apply_rules(response, &default_rules(), CleanupLevel::Standard);
+        assert_eq!(cleaned.matches("ast update of").count(), 1);
+        assert!(cleaned.contains("2024-01-01"));
+        assert!(cleaned.contains("Registrar: Example"));
+    }
+
+    #[test]
+    fn test_drop_rule_from_config() {
+        let rule: ResponseRule = toml::from_str("pattern = \"^remarks: ad\"\naction = \"drop\"").unwrap();
+        assert_eq!(
+            apply_rules("aut-num: AS1\nremarks: ad here\nremarks: real\n", [&rule], CleanupLevel::Standard),
+            "aut-num: AS1\nremarks: real\n"
+        );
+        assert!(toml::from_str::<ResponseRule>("pattern = \"(\"\naction = \"drop\"").is_err());
+    }
+
+    #[test]
+    fn test_short_keeps_genuine_remarks() {
+        let response = "\
+% This is the RIPE Database query service.
+% The objects are in RPSL format.
+%
+% The RIPE Database is subject to Terms and Conditions.
+% See https://apps.db.ripe.net/docs/HTML-Terms-And-Conditions
+
+% Note: this output has been filtered.
+%       To receive output for a database update, use the \"-B\" flag.
+
+% Information related to 'AS3333'
+
+aut-num:        AS3333
+remarks:        % not a banner
+remarks:        The RIPE Database is subject to Terms and Conditions
+
+% This query was served by the RIPE Database Query Service version 1.112 (SHETLAND)
+";
+        let mut rules = default_rules();
+        rules.extend(
+            builtin_server_rules()
+                .into_iter()
+                .find(|(host, _)| *host == "whois.ripe.net")
+                .map(|(_, rules)| rules)
+                .unwrap(),
+        );
+
+        let short = apply_rules(response, &rules, CleanupLevel::Short);
+        assert_eq!(
+            short,
+            "% Information related to 'AS3333'\n\naut-num:        AS3333\nremarks:        % not a banner\nremarks:        The RIPE Database is subject to Terms and Conditions\n"
+        );
+
+        // Without --short only the terms block goes, and it takes its comment run with it
+        let no_disclaimer = apply_rules(response, &rules, CleanupLevel::NoDisclaimer);
+        assert!(no_disclaimer.starts_with("% Note: this output has been filtered."));
+        assert!(no_disclaimer.contains("This query was served by"));
+    }
+}
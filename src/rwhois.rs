@@ -0,0 +1,253 @@
+use std::io::{BufRead, BufReader, Write};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use tracing::debug;
+
+use crate::timing::{read_error, timed_connect, HopTiming};
+
+/// Version announced in the `-rwhois` directive
+const CLIENT_VERSION: &str = "V-1.5 whois-cli";
+
+/// One RWhois object, from `class:attribute:value` lines
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RwhoisRecord {
+    pub class: String,
+    pub attributes: Vec<(String, String)>,
+}
+
+/// Reply to one RWhois command, up to its `%ok` or `%error` line
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RwhoisReply {
+    pub lines: Vec<String>,
+    /// The `%error ...` line, if the command failed
+    pub error: Option<String>,
+}
+
+/// Send a query to an RWhois (RFC 2167) server and read the reply
+/// The session announces the client with `-rwhois`, asks the server to close after the
+/// answer with `-holdconnect off`, then sends the query. Replies are framed by their
+/// `%ok`/`%error` line rather than by the connection closing.
+pub fn timed_query(address: &str, query: &str, timeout: Duration) -> Result<(String, HopTiming)> {
+    let start = Instant::now();
+    let (stream, dns, connect) = timed_connect(address, timeout)?;
+    let mut reader = BufReader::new(stream.try_clone().context("Failed to clone RWhois connection")?);
+    let mut writer = stream;
+
+    let banner = read_line(&mut reader, address)?.unwrap_or_default();
+    if !banner.starts_with("%rwhois") {
+        anyhow::bail!("Not an RWhois server: {} (greeting: {:?})", address, banner.trim());
+    }
+    debug!("RWhois banner from {}: {}", address, banner.trim());
+
+    // Older servers reject the directives; the query still works without them
+    for directive in [format!("-rwhois {}", CLIENT_VERSION), "-holdconnect off".to_string()] {
+        send(&mut writer, &directive)?;
+        let reply = read_reply(&mut reader, address)?;
+        if let Some(error) = reply.error {
+            debug!("{} rejected {:?}: {}", address, directive, error);
+        }
+    }
+
+    let write_start = Instant::now();
+    send(&mut writer, query)?;
+    let write = write_start.elapsed();
+
+    let read_start = Instant::now();
+    let mut first_byte = None;
+    let reply = read_reply_with(&mut reader, address, || {
+        first_byte.get_or_insert_with(|| read_start.elapsed());
+    })?;
+    // Politely end the session; the server may already have closed it
+    let _ = send(&mut writer, "-quit");
+
+    let timing = HopTiming {
+        server: address.to_string(),
+        probe: None,
+        dns,
+        connect,
+        write,
+        first_byte: first_byte.unwrap_or_default(),
+        total: start.elapsed(),
+    };
+    Ok((format_reply(&reply), timing))
+}
+
+fn send(writer: &mut impl Write, line: &str) -> Result<()> {
+    writer
+        .write_all(format!("{}\r\n", line).as_bytes())
+        .context("Failed to write to RWhois server")
+}
+
+fn read_reply(reader: &mut impl BufRead, address: &str) -> Result<RwhoisReply> {
+    read_reply_with(reader, address, || {})
+}
+
+/// Read lines up to `%ok`/`%error`, or the end of the stream
+fn read_reply_with(reader: &mut impl BufRead, address: &str, mut on_line: impl FnMut()) -> Result<RwhoisReply> {
+    let mut reply = RwhoisReply::default();
+    while let Some(line) = read_line(reader, address)? {
+        on_line();
+        let line = line.trim_end_matches(['\r', '\n']).to_string();
+        if line == "%ok" {
+            break;
+        }
+        if line.starts_with("%error") {
+            reply.error = Some(line);
+            break;
+        }
+        reply.lines.push(line);
+    }
+    Ok(reply)
+}
+
+/// Read one line; `None` at end of stream
+fn read_line(reader: &mut impl BufRead, address: &str) -> Result<Option<String>> {
+    let mut line = String::new();
+    let read = reader.read_line(&mut line).map_err(|err| read_error(err, address))?;
+    Ok((read > 0).then_some(line))
+}
+
+/// Group `class:attribute:value` lines into records
+/// A record starts at each `Class-Name` attribute or when the class changes
+pub fn parse_records(lines: &[String]) -> Vec<RwhoisRecord> {
+    let mut records: Vec<RwhoisRecord> = Vec::new();
+    for line in lines {
+        let mut parts = line.splitn(3, ':');
+        let (Some(class), Some(attribute), Some(value)) = (parts.next(), parts.next(), parts.next()) else {
+            continue;
+        };
+        if class.is_empty() || class.starts_with('%') {
+            continue;
+        }
+        let starts_record = match records.last() {
+            Some(record) => record.class != class || attribute.eq_ignore_ascii_case("Class-Name"),
+            None => true,
+        };
+        if starts_record {
+            records.push(RwhoisRecord {
+                class: class.to_string(),
+                attributes: Vec::new(),
+            });
+        }
+        if let Some(record) = records.last_mut() {
+            record.attributes.push((attribute.to_string(), value.trim().to_string()));
+        }
+    }
+    records
+}
+
+/// Render a reply as `attribute: value` blocks, one per record
+/// `%referral` lines become `ReferralServer:` lines so they are followed like ARIN referrals
+pub fn format_reply(reply: &RwhoisReply) -> String {
+    let mut blocks: Vec<String> = parse_records(&reply.lines)
+        .iter()
+        .map(|record| {
+            record
+                .attributes
+                .iter()
+                .filter(|(name, _)| !name.eq_ignore_ascii_case("Class-Name"))
+                .map(|(name, value)| format!("{:<16}{}", format!("{}:", name), value))
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .filter(|block| !block.is_empty())
+        .collect();
+
+    let referrals: Vec<String> = reply
+        .lines
+        .iter()
+        .filter_map(|line| line.strip_prefix("%referral"))
+        .map(|target| format!("ReferralServer: {}", target.trim()))
+        .collect();
+    if !referrals.is_empty() {
+        blocks.push(referrals.join("\n"));
+    }
+    if let Some(error) = &reply.error {
+        blocks.push(error.clone());
+    }
+
+    let mut output = blocks.join("\n\n");
+    if !output.is_empty() {
+        output.push('\n');
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::thread;
+
+    #[test]
+    fn test_reply_framed_by_ok() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let server = thread::spawn(move || {
+            let (mut socket, _) = listener.accept().unwrap();
+            socket.write_all(b"%rwhois V-1.5:003fff:00 rwhois.example.net\r\n").unwrap();
+            let mut reader = BufReader::new(socket.try_clone().unwrap());
+            let mut request = String::new();
+            for expected in ["-rwhois V-1.5 whois-cli\r\n", "-holdconnect off\r\n"] {
+                request.clear();
+                reader.read_line(&mut request).unwrap();
+                assert_eq!(request, expected);
+                socket.write_all(b"%ok\r\n").unwrap();
+            }
+            request.clear();
+            reader.read_line(&mut request).unwrap();
+            assert_eq!(request, "192.0.2.1\r\n");
+            socket
+                .write_all(b"network:Class-Name:network\r\nnetwork:IP-Network:192.0.2.0/24\r\nnetwork:Org-Name:Example ISP\r\n%ok\r\n")
+                .unwrap();
+            // Keep the connection open until the client quits
+            request.clear();
+            let _ = reader.read_line(&mut request);
+        });
+
+        let (response, timing) = timed_query(&address, "192.0.2.1", Duration::from_secs(5)).unwrap();
+        server.join().unwrap();
+        assert_eq!(response, "IP-Network:     192.0.2.0/24\nOrg-Name:       Example ISP\n");
+        assert_eq!(timing.server, address);
+    }
+
+    #[test]
+    fn test_rejects_plain_whois_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let server = thread::spawn(move || {
+            let (mut socket, _) = listener.accept().unwrap();
+            socket.write_all(b"% This is a WHOIS server\n").unwrap();
+        });
+
+        assert!(timed_query(&address, "192.0.2.1", Duration::from_secs(5)).is_err());
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_format_records_and_referrals() {
+        let reply = RwhoisReply {
+            lines: vec![
+                "network:Class-Name:network".to_string(),
+                "network:ID:NET-192-0-2-0-1".to_string(),
+                "network:Class-Name:network".to_string(),
+                "network:ID:NET-192-0-2-128-1".to_string(),
+                "contact:Email:noc@example.net".to_string(),
+                "%referral rwhois://rwhois.example.net:4321/auth-area=192.0.2.0/24".to_string(),
+            ],
+            error: None,
+        };
+        assert_eq!(parse_records(&reply.lines).len(), 3);
+        assert_eq!(
+            format_reply(&reply),
+            "ID:             NET-192-0-2-0-1\n\nID:             NET-192-0-2-128-1\n\nEmail:          noc@example.net\n\nReferralServer: rwhois://rwhois.example.net:4321/auth-area=192.0.2.0/24\n"
+        );
+
+        let missing = RwhoisReply {
+            lines: Vec::new(),
+            error: Some("%error 230 No Objects Found".to_string()),
+        };
+        assert_eq!(format_reply(&missing), "%error 230 No Objects Found\n");
+    }
+}
@@ -0,0 +1,792 @@
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use crate::dates;
+use crate::lint::is_valid_email;
+use crate::rpsl::{self, Block, RpslObject};
+
+use Count::*;
+use Presence::*;
+
+/// Whether an attribute has to be present
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Presence {
+    Mandatory,
+    Optional,
+    /// Set by the registry; allowed in an object but never required
+    Generated,
+}
+
+/// How many times an attribute may appear
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Count {
+    Single,
+    Multiple,
+}
+
+/// Attributes of an object class, in template order
+pub type ClassSchema = &'static [(&'static str, Presence, Count)];
+
+const AUT_NUM: ClassSchema = &[
+    ("aut-num", Mandatory, Single),
+    ("as-name", Mandatory, Single),
+    ("descr", Optional, Multiple),
+    ("member-of", Optional, Multiple),
+    ("import-via", Optional, Multiple),
+    ("import", Optional, Multiple),
+    ("mp-import", Optional, Multiple),
+    ("export-via", Optional, Multiple),
+    ("export", Optional, Multiple),
+    ("mp-export", Optional, Multiple),
+    ("default", Optional, Multiple),
+    ("mp-default", Optional, Multiple),
+    ("remarks", Optional, Multiple),
+    ("org", Optional, Single),
+    ("sponsoring-org", Optional, Single),
+    ("admin-c", Mandatory, Multiple),
+    ("tech-c", Mandatory, Multiple),
+    ("abuse-c", Optional, Single),
+    ("status", Generated, Single),
+    ("notify", Optional, Multiple),
+    ("mnt-by", Mandatory, Multiple),
+    ("created", Generated, Single),
+    ("last-modified", Generated, Single),
+    ("source", Mandatory, Single),
+];
+
+const INETNUM: ClassSchema = &[
+    ("inetnum", Mandatory, Single),
+    ("netname", Mandatory, Single),
+    ("descr", Optional, Multiple),
+    ("country", Mandatory, Multiple),
+    ("geofeed", Optional, Single),
+    ("geoloc", Optional, Single),
+    ("language", Optional, Multiple),
+    ("org", Optional, Single),
+    ("sponsoring-org", Optional, Single),
+    ("admin-c", Mandatory, Multiple),
+    ("tech-c", Mandatory, Multiple),
+    ("abuse-c", Optional, Single),
+    ("status", Mandatory, Single),
+    ("remarks", Optional, Multiple),
+    ("notify", Optional, Multiple),
+    ("mnt-by", Mandatory, Multiple),
+    ("mnt-lower", Optional, Multiple),
+    ("mnt-domains", Optional, Multiple),
+    ("mnt-routes", Optional, Multiple),
+    ("mnt-irt", Optional, Multiple),
+    ("created", Generated, Single),
+    ("last-modified", Generated, Single),
+    ("source", Mandatory, Single),
+];
+
+const INET6NUM: ClassSchema = &[
+    ("inet6num", Mandatory, Single),
+    ("netname", Mandatory, Single),
+    ("descr", Optional, Multiple),
+    ("country", Mandatory, Multiple),
+    ("geofeed", Optional, Single),
+    ("geoloc", Optional, Single),
+    ("language", Optional, Multiple),
+    ("org", Optional, Single),
+    ("sponsoring-org", Optional, Single),
+    ("admin-c", Mandatory, Multiple),
+    ("tech-c", Mandatory, Multiple),
+    ("abuse-c", Optional, Single),
+    ("status", Mandatory, Single),
+    ("assignment-size", Optional, Single),
+    ("remarks", Optional, Multiple),
+    ("notify", Optional, Multiple),
+    ("mnt-by", Mandatory, Multiple),
+    ("mnt-lower", Optional, Multiple),
+    ("mnt-domains", Optional, Multiple),
+    ("mnt-routes", Optional, Multiple),
+    ("mnt-irt", Optional, Multiple),
+    ("created", Generated, Single),
+    ("last-modified", Generated, Single),
+    ("source", Mandatory, Single),
+];
+
+const ROUTE: ClassSchema = &[
+    ("route", Mandatory, Single),
+    ("descr", Optional, Multiple),
+    ("origin", Mandatory, Single),
+    ("pingable", Optional, Multiple),
+    ("ping-hdl", Optional, Multiple),
+    ("holes", Optional, Multiple),
+    ("org", Optional, Multiple),
+    ("member-of", Optional, Multiple),
+    ("inject", Optional, Multiple),
+    ("aggr-mtd", Optional, Single),
+    ("aggr-bndry", Optional, Single),
+    ("export-comps", Optional, Single),
+    ("components", Optional, Single),
+    ("remarks", Optional, Multiple),
+    ("notify", Optional, Multiple),
+    ("mnt-lower", Optional, Multiple),
+    ("mnt-routes", Optional, Multiple),
+    ("mnt-by", Mandatory, Multiple),
+    ("created", Generated, Single),
+    ("last-modified", Generated, Single),
+    ("source", Mandatory, Single),
+];
+
+const ROUTE6: ClassSchema = &[
+    ("route6", Mandatory, Single),
+    ("descr", Optional, Multiple),
+    ("origin", Mandatory, Single),
+    ("pingable", Optional, Multiple),
+    ("ping-hdl", Optional, Multiple),
+    ("holes", Optional, Multiple),
+    ("org", Optional, Multiple),
+    ("member-of", Optional, Multiple),
+    ("inject", Optional, Multiple),
+    ("aggr-mtd", Optional, Single),
+    ("aggr-bndry", Optional, Single),
+    ("export-comps", Optional, Single),
+    ("components", Optional, Single),
+    ("remarks", Optional, Multiple),
+    ("notify", Optional, Multiple),
+    ("mnt-lower", Optional, Multiple),
+    ("mnt-routes", Optional, Multiple),
+    ("mnt-by", Mandatory, Multiple),
+    ("created", Generated, Single),
+    ("last-modified", Generated, Single),
+    ("source", Mandatory, Single),
+];
+
+const PERSON: ClassSchema = &[
+    ("person", Mandatory, Single),
+    ("address", Mandatory, Multiple),
+    ("phone", Mandatory, Multiple),
+    ("fax-no", Optional, Multiple),
+    ("e-mail", Optional, Multiple),
+    ("org", Optional, Multiple),
+    ("nic-hdl", Mandatory, Single),
+    ("remarks", Optional, Multiple),
+    ("notify", Optional, Multiple),
+    ("mnt-by", Mandatory, Multiple),
+    ("mnt-ref", Optional, Multiple),
+    ("created", Generated, Single),
+    ("last-modified", Generated, Single),
+    ("source", Mandatory, Single),
+];
+
+const ROLE: ClassSchema = &[
+    ("role", Mandatory, Single),
+    ("address", Mandatory, Multiple),
+    ("phone", Optional, Multiple),
+    ("fax-no", Optional, Multiple),
+    ("e-mail", Mandatory, Multiple),
+    ("org", Optional, Multiple),
+    ("admin-c", Optional, Multiple),
+    ("tech-c", Optional, Multiple),
+    ("nic-hdl", Mandatory, Single),
+    ("remarks", Optional, Multiple),
+    ("notify", Optional, Multiple),
+    ("abuse-mailbox", Optional, Single),
+    ("mnt-by", Mandatory, Multiple),
+    ("mnt-ref", Optional, Multiple),
+    ("created", Generated, Single),
+    ("last-modified", Generated, Single),
+    ("source", Mandatory, Single),
+];
+
+const MNTNER: ClassSchema = &[
+    ("mntner", Mandatory, Single),
+    ("descr", Optional, Multiple),
+    ("org", Optional, Multiple),
+    ("admin-c", Mandatory, Multiple),
+    ("tech-c", Optional, Multiple),
+    ("upd-to", Mandatory, Multiple),
+    ("mnt-nfy", Optional, Multiple),
+    ("auth", Mandatory, Multiple),
+    ("remarks", Optional, Multiple),
+    ("notify", Optional, Multiple),
+    ("mnt-by", Mandatory, Multiple),
+    ("created", Generated, Single),
+    ("last-modified", Generated, Single),
+    ("source", Mandatory, Single),
+];
+
+const ORGANISATION: ClassSchema = &[
+    ("organisation", Mandatory, Single),
+    ("org-name", Mandatory, Single),
+    ("org-type", Mandatory, Single),
+    ("descr", Optional, Multiple),
+    ("remarks", Optional, Multiple),
+    ("address", Mandatory, Multiple),
+    ("country", Optional, Single),
+    ("phone", Optional, Multiple),
+    ("fax-no", Optional, Multiple),
+    ("e-mail", Mandatory, Multiple),
+    ("geoloc", Optional, Single),
+    ("language", Optional, Multiple),
+    ("org", Optional, Multiple),
+    ("admin-c", Optional, Multiple),
+    ("tech-c", Optional, Multiple),
+    ("abuse-c", Optional, Single),
+    ("ref-nfy", Optional, Multiple),
+    ("mnt-ref", Mandatory, Multiple),
+    ("notify", Optional, Multiple),
+    ("mnt-by", Mandatory, Multiple),
+    ("created", Generated, Single),
+    ("last-modified", Generated, Single),
+    ("source", Mandatory, Single),
+];
+
+const AS_SET: ClassSchema = &[
+    ("as-set", Mandatory, Single),
+    ("descr", Optional, Multiple),
+    ("members", Optional, Multiple),
+    ("mbrs-by-ref", Optional, Multiple),
+    ("remarks", Optional, Multiple),
+    ("org", Optional, Multiple),
+    ("tech-c", Mandatory, Multiple),
+    ("admin-c", Mandatory, Multiple),
+    ("notify", Optional, Multiple),
+    ("mnt-by", Mandatory, Multiple),
+    ("mnt-lower", Optional, Multiple),
+    ("created", Generated, Single),
+    ("last-modified", Generated, Single),
+    ("source", Mandatory, Single),
+];
+
+const ROUTE_SET: ClassSchema = &[
+    ("route-set", Mandatory, Single),
+    ("descr", Optional, Multiple),
+    ("members", Optional, Multiple),
+    ("mp-members", Optional, Multiple),
+    ("mbrs-by-ref", Optional, Multiple),
+    ("remarks", Optional, Multiple),
+    ("org", Optional, Multiple),
+    ("tech-c", Mandatory, Multiple),
+    ("admin-c", Mandatory, Multiple),
+    ("notify", Optional, Multiple),
+    ("mnt-by", Mandatory, Multiple),
+    ("mnt-lower", Optional, Multiple),
+    ("created", Generated, Single),
+    ("last-modified", Generated, Single),
+    ("source", Mandatory, Single),
+];
+
+const DOMAIN: ClassSchema = &[
+    ("domain", Mandatory, Single),
+    ("descr", Optional, Multiple),
+    ("org", Optional, Multiple),
+    ("admin-c", Mandatory, Multiple),
+    ("tech-c", Mandatory, Multiple),
+    ("zone-c", Mandatory, Multiple),
+    ("nserver", Optional, Multiple),
+    ("ds-rdata", Optional, Multiple),
+    ("remarks", Optional, Multiple),
+    ("notify", Optional, Multiple),
+    ("mnt-by", Mandatory, Multiple),
+    ("created", Generated, Single),
+    ("last-modified", Generated, Single),
+    ("source", Mandatory, Single),
+];
+
+const DN42_AUT_NUM: ClassSchema = &[
+    ("aut-num", Mandatory, Single),
+    ("as-name", Mandatory, Single),
+    ("descr", Optional, Single),
+    ("member-of", Optional, Multiple),
+    ("import", Optional, Multiple),
+    ("export", Optional, Multiple),
+    ("mp-import", Optional, Multiple),
+    ("mp-export", Optional, Multiple),
+    ("default", Optional, Multiple),
+    ("mp-default", Optional, Multiple),
+    ("org", Optional, Single),
+    ("admin-c", Optional, Multiple),
+    ("tech-c", Optional, Multiple),
+    ("remarks", Optional, Multiple),
+    ("mnt-by", Mandatory, Multiple),
+    ("source", Mandatory, Single),
+];
+
+const DN42_INETNUM: ClassSchema = &[
+    ("inetnum", Mandatory, Single),
+    ("cidr", Mandatory, Single),
+    ("netname", Mandatory, Single),
+    ("descr", Optional, Single),
+    ("country", Optional, Multiple),
+    ("org", Optional, Single),
+    ("nserver", Optional, Multiple),
+    ("ds-rdata", Optional, Multiple),
+    ("policy", Optional, Single),
+    ("status", Optional, Single),
+    ("admin-c", Optional, Multiple),
+    ("tech-c", Optional, Multiple),
+    ("zone-c", Optional, Multiple),
+    ("remarks", Optional, Multiple),
+    ("mnt-by", Mandatory, Multiple),
+    ("mnt-lower", Optional, Multiple),
+    ("mnt-routes", Optional, Multiple),
+    ("source", Mandatory, Single),
+];
+
+const DN42_INET6NUM: ClassSchema = &[
+    ("inet6num", Mandatory, Single),
+    ("cidr", Mandatory, Single),
+    ("netname", Mandatory, Single),
+    ("descr", Optional, Single),
+    ("country", Optional, Multiple),
+    ("org", Optional, Single),
+    ("nserver", Optional, Multiple),
+    ("ds-rdata", Optional, Multiple),
+    ("policy", Optional, Single),
+    ("status", Optional, Single),
+    ("admin-c", Optional, Multiple),
+    ("tech-c", Optional, Multiple),
+    ("zone-c", Optional, Multiple),
+    ("remarks", Optional, Multiple),
+    ("mnt-by", Mandatory, Multiple),
+    ("mnt-lower", Optional, Multiple),
+    ("mnt-routes", Optional, Multiple),
+    ("source", Mandatory, Single),
+];
+
+const DN42_ROUTE: ClassSchema = &[
+    ("route", Mandatory, Single),
+    ("origin", Mandatory, Multiple),
+    ("max-length", Optional, Single),
+    ("member-of", Optional, Multiple),
+    ("descr", Optional, Single),
+    ("pingable", Optional, Multiple),
+    ("org", Optional, Single),
+    ("admin-c", Optional, Multiple),
+    ("tech-c", Optional, Multiple),
+    ("remarks", Optional, Multiple),
+    ("mnt-by", Mandatory, Multiple),
+    ("mnt-lower", Optional, Multiple),
+    ("source", Mandatory, Single),
+];
+
+const DN42_ROUTE6: ClassSchema = &[
+    ("route6", Mandatory, Single),
+    ("origin", Mandatory, Multiple),
+    ("max-length", Optional, Single),
+    ("member-of", Optional, Multiple),
+    ("descr", Optional, Single),
+    ("pingable", Optional, Multiple),
+    ("org", Optional, Single),
+    ("admin-c", Optional, Multiple),
+    ("tech-c", Optional, Multiple),
+    ("remarks", Optional, Multiple),
+    ("mnt-by", Mandatory, Multiple),
+    ("mnt-lower", Optional, Multiple),
+    ("source", Mandatory, Single),
+];
+
+const DN42_PERSON: ClassSchema = &[
+    ("person", Mandatory, Single),
+    ("nic-hdl", Mandatory, Single),
+    ("contact", Optional, Multiple),
+    ("e-mail", Optional, Multiple),
+    ("pgp-fingerprint", Optional, Multiple),
+    ("www", Optional, Multiple),
+    ("abuse-mailbox", Optional, Single),
+    ("remarks", Optional, Multiple),
+    ("mnt-by", Mandatory, Multiple),
+    ("source", Mandatory, Single),
+];
+
+const DN42_ROLE: ClassSchema = &[
+    ("role", Mandatory, Single),
+    ("nic-hdl", Mandatory, Single),
+    ("admin-c", Optional, Multiple),
+    ("tech-c", Optional, Multiple),
+    ("abuse-c", Optional, Multiple),
+    ("contact", Optional, Multiple),
+    ("e-mail", Optional, Multiple),
+    ("www", Optional, Multiple),
+    ("abuse-mailbox", Optional, Single),
+    ("trouble", Optional, Multiple),
+    ("remarks", Optional, Multiple),
+    ("mnt-by", Mandatory, Multiple),
+    ("source", Mandatory, Single),
+];
+
+const DN42_MNTNER: ClassSchema = &[
+    ("mntner", Mandatory, Single),
+    ("descr", Optional, Single),
+    ("admin-c", Optional, Multiple),
+    ("tech-c", Optional, Multiple),
+    ("auth", Optional, Multiple),
+    ("remarks", Optional, Multiple),
+    ("mnt-by", Mandatory, Multiple),
+    ("source", Mandatory, Single),
+];
+
+const DN42_ORGANISATION: ClassSchema = &[
+    ("organisation", Mandatory, Single),
+    ("org-name", Mandatory, Single),
+    ("descr", Optional, Single),
+    ("admin-c", Optional, Multiple),
+    ("tech-c", Optional, Multiple),
+    ("abuse-c", Optional, Single),
+    ("e-mail", Optional, Multiple),
+    ("www", Optional, Multiple),
+    ("remarks", Optional, Multiple),
+    ("mnt-by", Mandatory, Multiple),
+    ("source", Mandatory, Single),
+];
+
+const DN42_AS_SET: ClassSchema = &[
+    ("as-set", Mandatory, Single),
+    ("descr", Optional, Single),
+    ("members", Optional, Multiple),
+    ("mbrs-by-ref", Optional, Multiple),
+    ("admin-c", Optional, Multiple),
+    ("tech-c", Optional, Multiple),
+    ("remarks", Optional, Multiple),
+    ("mnt-by", Mandatory, Multiple),
+    ("source", Mandatory, Single),
+];
+
+const DN42_ROUTE_SET: ClassSchema = &[
+    ("route-set", Mandatory, Single),
+    ("descr", Optional, Single),
+    ("members", Optional, Multiple),
+    ("mp-members", Optional, Multiple),
+    ("mbrs-by-ref", Optional, Multiple),
+    ("admin-c", Optional, Multiple),
+    ("tech-c", Optional, Multiple),
+    ("remarks", Optional, Multiple),
+    ("mnt-by", Mandatory, Multiple),
+    ("source", Mandatory, Single),
+];
+
+const DN42_DOMAIN: ClassSchema = &[
+    ("domain", Mandatory, Single),
+    ("descr", Optional, Single),
+    ("nserver", Optional, Multiple),
+    ("ds-rdata", Optional, Multiple),
+    ("country", Optional, Multiple),
+    ("org", Optional, Single),
+    ("admin-c", Optional, Multiple),
+    ("tech-c", Optional, Multiple),
+    ("remarks", Optional, Multiple),
+    ("mnt-by", Mandatory, Multiple),
+    ("source", Mandatory, Single),
+];
+
+/// Registry whose object rules apply
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flavor {
+    /// RIPE database, also followed by most IRR databases
+    Ripe,
+    /// DN42 registry (git repository of flat files)
+    Dn42,
+}
+
+impl Flavor {
+    /// Flavor of an object, from its `source:`
+    pub fn of(object: &RpslObject) -> Self {
+        match object.get("source") {
+            Some(source) if source.eq_ignore_ascii_case("DN42") => Flavor::Dn42,
+            _ => Flavor::Ripe,
+        }
+    }
+
+    /// `source:` of objects in this registry
+    pub fn source(self) -> &'static str {
+        match self {
+            Flavor::Ripe => "RIPE",
+            Flavor::Dn42 => "DN42",
+        }
+    }
+
+    fn classes(self) -> &'static [(&'static str, ClassSchema)] {
+        match self {
+            Flavor::Ripe => CLASSES,
+            Flavor::Dn42 => DN42_CLASSES,
+        }
+    }
+}
+
+/// Classes with a built-in schema
+const CLASSES: &[(&str, ClassSchema)] = &[
+    ("aut-num", AUT_NUM),
+    ("inetnum", INETNUM),
+    ("inet6num", INET6NUM),
+    ("route", ROUTE),
+    ("route6", ROUTE6),
+    ("person", PERSON),
+    ("role", ROLE),
+    ("mntner", MNTNER),
+    ("organisation", ORGANISATION),
+    ("as-set", AS_SET),
+    ("route-set", ROUTE_SET),
+    ("domain", DOMAIN),
+];
+
+/// Classes of the DN42 registry
+const DN42_CLASSES: &[(&str, ClassSchema)] = &[
+    ("aut-num", DN42_AUT_NUM),
+    ("inetnum", DN42_INETNUM),
+    ("inet6num", DN42_INET6NUM),
+    ("route", DN42_ROUTE),
+    ("route6", DN42_ROUTE6),
+    ("person", DN42_PERSON),
+    ("role", DN42_ROLE),
+    ("mntner", DN42_MNTNER),
+    ("organisation", DN42_ORGANISATION),
+    ("as-set", DN42_AS_SET),
+    ("route-set", DN42_ROUTE_SET),
+    ("domain", DN42_DOMAIN),
+];
+
+/// Attributes whose values are email addresses
+const EMAIL_ATTRIBUTES: &[&str] = &["e-mail", "abuse-mailbox", "upd-to", "mnt-nfy", "notify", "ref-nfy"];
+/// Attributes naming a contact handle
+const HANDLE_ATTRIBUTES: &[&str] = &["nic-hdl", "admin-c", "tech-c", "zone-c", "abuse-c", "ping-hdl"];
+/// Attributes naming a maintainer
+const MAINTAINER_ATTRIBUTES: &[&str] = &["mntner", "mnt-by", "mnt-lower", "mnt-routes", "mnt-domains", "mnt-ref", "mnt-irt"];
+
+/// A problem found in an object file, at a 1-based line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    pub line: usize,
+    pub message: String,
+}
+
+/// Schema of an object class
+pub fn class(name: &str, flavor: Flavor) -> Option<ClassSchema> {
+    flavor.classes().iter().find(|(class, _)| class.eq_ignore_ascii_case(name)).map(|(_, schema)| *schema)
+}
+
+/// Names of the classes with a built-in schema
+pub fn class_names(flavor: Flavor) -> Vec<&'static str> {
+    flavor.classes().iter().map(|(class, _)| *class).collect()
+}
+
+/// Empty object of a class in attribute order, as printed by RIPE's `-t`, ready to fill in
+/// Generated attributes are left out; `source:` is filled in
+pub fn template(name: &str, flavor: Flavor) -> Option<String> {
+    let schema = class(name, flavor)?;
+    let lines: Vec<String> = schema
+        .iter()
+        .filter(|(_, presence, _)| *presence != Generated)
+        .map(|(attribute, presence, count)| {
+            let label = format!("{}:", attribute);
+            if *attribute == "source" {
+                return format!("{:<16}{}", label, flavor.source());
+            }
+            let presence = if *presence == Mandatory { "mandatory" } else { "optional" };
+            let count = if *count == Single { "single" } else { "multiple" };
+            format!("{:<16}# {}, {}", label, presence, count)
+        })
+        .collect();
+    Some(lines.join("\n"))
+}
+
+/// Check every object of an RPSL file against its class schema
+pub fn validate(text: &str) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    for (start, block) in rpsl::parse_with_lines(text) {
+        match block {
+            Block::Object(object) => problems.extend(validate_object(&object, start)),
+            Block::Text(text) => {
+                // A line that is not `name: value`, or else a continuation before any attribute
+                let lines: Vec<(usize, &str)> = text.lines().enumerate().filter(|(_, line)| !line.starts_with(['%', '#'])).collect();
+                let bad = lines
+                    .iter()
+                    .find(|(_, line)| !line.starts_with([' ', '\t', '+']) && rpsl::parse_attribute(line).is_none())
+                    .or(lines.first());
+                if let Some((offset, _)) = bad {
+                    problems.push(Problem {
+                        line: start + offset + 1,
+                        message: "not an attribute line (expected `name: value`)".to_string(),
+                    });
+                }
+            }
+        }
+    }
+    problems
+}
+
+/// Problems of one object; `start` is the index of its first line in the file
+pub fn validate_object(object: &RpslObject, start: usize) -> Vec<Problem> {
+    let class_name = object.class().to_lowercase();
+    let first_line = start + 1;
+    let flavor = Flavor::of(object);
+    let Some(schema) = class(&class_name, flavor) else {
+        return vec![Problem {
+            line: first_line,
+            message: format!("unknown class {} (known: {})", class_name, class_names(flavor).join(", ")),
+        }];
+    };
+
+    let mut problems = Vec::new();
+    for attribute in &object.attributes {
+        let line = start + attribute.line + 1;
+        let name = attribute.name.to_lowercase();
+        if !schema.iter().any(|(known, _, _)| *known == name) {
+            problems.push(Problem { line, message: format!("{} is not an attribute of {}", name, class_name) });
+            continue;
+        }
+        if let Some(message) = check_value(&name, &attribute.value) {
+            problems.push(Problem { line, message });
+        }
+    }
+    for (name, presence, count) in schema {
+        let found: Vec<usize> = object
+            .attributes
+            .iter()
+            .filter(|attribute| attribute.name.eq_ignore_ascii_case(name))
+            .map(|attribute| start + attribute.line + 1)
+            .collect();
+        if found.is_empty() && *presence == Mandatory {
+            problems.push(Problem { line: first_line, message: format!("mandatory attribute {} is missing", name) });
+        }
+        if found.len() > 1 && *count == Single {
+            problems.push(Problem { line: found[1], message: format!("{} may appear only once", name) });
+        }
+    }
+    problems.sort_by_key(|problem| problem.line);
+    problems
+}
+
+/// Problems as `FILE:LINE: message` lines
+pub fn render(file: &str, problems: &[Problem]) -> String {
+    problems
+        .iter()
+        .map(|problem| format!("{}:{}: {}", file, problem.line, problem.message))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Syntax problem of a value, for the attributes whose syntax is checked
+fn check_value(name: &str, value: &str) -> Option<String> {
+    let value = value.split('#').next().unwrap_or_default().trim();
+    if value.is_empty() {
+        return (!matches!(name, "remarks" | "descr")).then(|| format!("{} has no value", name));
+    }
+    let valid = match name {
+        "aut-num" | "origin" => is_as_number(value),
+        "route" => is_ipv4_prefix(value),
+        "route6" | "inet6num" => is_ipv6_prefix(value),
+        "inetnum" => is_ipv4_range(value) || is_ipv4_prefix(value),
+        "country" => value.len() == 2 && value.chars().all(|c| c.is_ascii_alphabetic()),
+        "as-set" => is_set_name(value, "AS-"),
+        "route-set" => is_set_name(value, "RS-"),
+        "cidr" => is_ipv4_prefix(value) || is_ipv6_prefix(value),
+        "max-length" => value.parse::<u8>().is_ok_and(|length| length <= 128),
+        "created" | "last-modified" => dates::parse_day(value).is_some(),
+        "source" => is_object_name(value),
+        _ if EMAIL_ATTRIBUTES.contains(&name) => is_valid_email(value),
+        _ if HANDLE_ATTRIBUTES.contains(&name) || MAINTAINER_ATTRIBUTES.contains(&name) => is_object_name(value),
+        _ => true,
+    };
+    (!valid).then(|| format!("invalid {}: {}", name, value))
+}
+
+fn is_as_number(value: &str) -> bool {
+    value.len() > 2 && value[..2].eq_ignore_ascii_case("AS") && value[2..].parse::<u32>().is_ok()
+}
+
+/// Letters, digits, `-` and `_`, starting with a letter or digit
+fn is_object_name(value: &str) -> bool {
+    value.starts_with(|c: char| c.is_ascii_alphanumeric()) && value.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Set name such as `AS-EXAMPLE` or hierarchical `AS64500:AS-CUSTOMERS`
+fn is_set_name(value: &str, prefix: &str) -> bool {
+    let parts: Vec<&str> = value.split(':').collect();
+    parts.iter().all(|part| is_object_name(part) || is_as_number(part))
+        && parts.iter().any(|part| part.len() > prefix.len() && part[..prefix.len()].eq_ignore_ascii_case(prefix))
+}
+
+fn is_ipv4_prefix(value: &str) -> bool {
+    let Some((address, length)) = value.split_once('/') else {
+        return false;
+    };
+    match (address.parse::<Ipv4Addr>(), length.parse::<u32>()) {
+        (Ok(address), Ok(length)) if length <= 32 => u32::from(address).checked_shl(length).unwrap_or(0) == 0,
+        _ => false,
+    }
+}
+
+fn is_ipv6_prefix(value: &str) -> bool {
+    let Some((address, length)) = value.split_once('/') else {
+        return false;
+    };
+    match (address.parse::<Ipv6Addr>(), length.parse::<u32>()) {
+        (Ok(address), Ok(length)) if length <= 128 => u128::from(address).checked_shl(length).unwrap_or(0) == 0,
+        _ => false,
+    }
+}
+
+fn is_ipv4_range(value: &str) -> bool {
+    let Some((first, last)) = value.split_once('-') else {
+        return false;
+    };
+    match (first.trim().parse::<Ipv4Addr>(), last.trim().parse::<Ipv4Addr>()) {
+        (Ok(first), Ok(last)) => first <= last,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_object() {
+        let object = "route6:         2001:db8::/32\norigin:         AS64500\nmnt-by:         EXAMPLE-MNT\nsource:         RIPE\n";
+        assert_eq!(validate(object), Vec::new());
+    }
+
+    #[test]
+    fn test_reports_problems_with_lines() {
+        let text = "% comment\n\nroute:          192.0.2.1/24\ndescr:          Example\norigin:         64500\nsource:         RIPE\nsource:         TEST\ncolour:         blue\n";
+        let problems = validate(text);
+        let rendered = render("route.rpsl", &problems);
+        assert_eq!(
+            rendered,
+            "route.rpsl:3: invalid route: 192.0.2.1/24\n\
+             route.rpsl:3: mandatory attribute mnt-by is missing\n\
+             route.rpsl:5: invalid origin: 64500\n\
+             route.rpsl:7: source may appear only once\n\
+             route.rpsl:8: colour is not an attribute of route"
+        );
+    }
+
+    #[test]
+    fn test_templates() {
+        let ripe = template("ROUTE6", Flavor::Ripe).unwrap();
+        let lines: Vec<&str> = ripe.lines().collect();
+        assert_eq!(lines[0], "route6:         # mandatory, single");
+        assert_eq!(lines[1], "descr:          # optional, multiple");
+        assert_eq!(lines.last(), Some(&"source:         RIPE"));
+        assert!(!ripe.contains("last-modified"));
+
+        let dn42 = template("route6", Flavor::Dn42).unwrap();
+        assert!(dn42.contains("max-length:     # optional, single"));
+        assert!(dn42.ends_with("source:         DN42"));
+        assert!(template("peering-set", Flavor::Ripe).is_none());
+    }
+
+    #[test]
+    fn test_dn42_objects_use_dn42_schema() {
+        let object = "route6:         fd42:d42:d42::/48\norigin:         AS4242420000\nmax-length:     64\nmnt-by:         EXAMPLE-MNT\nsource:         DN42\n";
+        assert_eq!(validate(object), Vec::new());
+        // An unfilled template lists every empty mandatory attribute
+        let problems = validate(&template("route6", Flavor::Dn42).unwrap());
+        assert!(problems.iter().any(|problem| problem.message == "route6 has no value"));
+    }
+
+    #[test]
+    fn test_value_syntax() {
+        assert!(is_ipv4_prefix("192.0.2.0/24") && !is_ipv4_prefix("192.0.2.0/33"));
+        assert!(is_ipv4_range("192.0.2.0 - 192.0.2.255") && !is_ipv4_range("192.0.2.9 - 192.0.2.1"));
+        assert!(is_set_name("AS64500:AS-CUSTOMERS", "AS-") && !is_set_name("CUSTOMERS", "AS-"));
+        assert_eq!(validate("foo: bar\n")[0].message.split(" (").next(), Some("unknown class foo"));
+        assert_eq!(
+            validate("aut-num: AS1\nnot an attribute\n"),
+            vec![Problem { line: 2, message: "not an attribute line (expected `name: value`)".to_string() }]
+        );
+    }
+}
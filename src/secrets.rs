@@ -0,0 +1,103 @@
+use std::io::{BufRead, IsTerminal};
+
+use anyhow::{Context, Result};
+
+/// Keyring service holding every secret; the account names the backend,
+/// e.g. the host of a WHOIS server that asks for an `X-WHOIS-AUTH` token
+pub const KEYRING_SERVICE: &str = "whois-cli";
+
+/// Keyring account of a backend: trimmed and lower case, like configured server hosts
+pub fn account_name(account: &str) -> Result<String> {
+    let account = account.trim().to_lowercase();
+    if account.is_empty() || account.chars().any(char::is_whitespace) {
+        anyhow::bail!("Invalid keyring account {:?}", account);
+    }
+    Ok(account)
+}
+
+/// Secret for `whois auth set`: prompted for without echo on a terminal,
+/// otherwise the first line of stdin so it can be piped from a password manager
+pub fn read_secret(account: &str) -> Result<String> {
+    let secret = if std::io::stdin().is_terminal() {
+        prompt(account)?
+    } else {
+        let mut line = String::new();
+        std::io::stdin().lock().read_line(&mut line).context("Failed to read the secret from stdin")?;
+        line
+    };
+    let secret = secret.trim_end_matches(['\r', '\n']).to_string();
+    if secret.is_empty() {
+        anyhow::bail!("Empty secret for {}", account);
+    }
+    Ok(secret)
+}
+
+#[cfg(feature = "keyring")]
+fn prompt(account: &str) -> Result<String> {
+    rpassword::prompt_password(format!("Secret for {}: ", account)).context("Failed to read the secret")
+}
+
+#[cfg(not(feature = "keyring"))]
+fn prompt(_account: &str) -> Result<String> {
+    anyhow::bail!("The system keyring requires the `keyring` feature")
+}
+
+#[cfg(feature = "keyring")]
+fn entry(account: &str) -> Result<keyring::Entry> {
+    keyring::Entry::new(KEYRING_SERVICE, account).with_context(|| format!("Cannot open keyring entry for {}", account))
+}
+
+/// Store a secret, replacing any earlier one of the account
+#[cfg(feature = "keyring")]
+pub fn store(account: &str, secret: &str) -> Result<()> {
+    entry(account)?
+        .set_password(secret)
+        .with_context(|| format!("Failed to store the secret for {} in the system keyring", account))
+}
+
+/// Delete the secret of an account
+#[cfg(feature = "keyring")]
+pub fn remove(account: &str) -> Result<()> {
+    match entry(account)?.delete_credential() {
+        Ok(()) => Ok(()),
+        Err(keyring::Error::NoEntry) => anyhow::bail!("No secret stored for {}", account),
+        Err(err) => Err(err).with_context(|| format!("Failed to remove the secret for {} from the system keyring", account)),
+    }
+}
+
+/// Secret of an account, `None` when none is stored
+#[cfg(feature = "keyring")]
+pub fn lookup(account: &str) -> Result<Option<String>> {
+    match entry(account)?.get_password() {
+        Ok(secret) => Ok(Some(secret)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(err) => Err(err).with_context(|| format!("Failed to read the secret for {} from the system keyring", account)),
+    }
+}
+
+#[cfg(not(feature = "keyring"))]
+pub fn store(_account: &str, _secret: &str) -> Result<()> {
+    anyhow::bail!("The system keyring requires the `keyring` feature")
+}
+
+#[cfg(not(feature = "keyring"))]
+pub fn remove(_account: &str) -> Result<()> {
+    anyhow::bail!("The system keyring requires the `keyring` feature")
+}
+
+#[cfg(not(feature = "keyring"))]
+pub fn lookup(_account: &str) -> Result<Option<String>> {
+    anyhow::bail!("The system keyring requires the `keyring` feature")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_account_names() {
+        assert_eq!(account_name(" WHOIS.IPAM.Internal ").unwrap(), "whois.ipam.internal");
+        assert!(account_name("").is_err());
+        assert!(account_name("two words").is_err());
+    }
+}
@@ -1,4 +1,15 @@
+use std::collections::HashMap;
 use std::env;
+use std::net::SocketAddr;
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::classify::EmptyResultPolicy;
+use crate::referral::parse_referral_url;
+use crate::rules::{apply_rules, builtin_server_rules, default_rules, CleanupLevel, ResponseRule};
 
 pub const IANA_WHOIS_SERVER: &str = "whois.iana.org";
 pub const DEFAULT_WHOIS_SERVER: &str = "whois.ripe.net";
@@ -9,6 +20,51 @@ pub const BGPTOOLS_WHOIS_SERVER: &str = "bgp.tools";
 pub const BGPTOOLS_WHOIS_PORT: u16 = 43;
 pub const RADB_WHOIS_SERVER: &str = "whois.radb.net";
 pub const RADB_WHOIS_PORT: u16 = 43;
+pub const ALTDB_WHOIS_SERVER: &str = "whois.altdb.net";
+pub const ALTDB_WHOIS_PORT: u16 = 43;
+/// Name given to `whois.nic.<tld>` servers guessed for new gTLDs
+pub const NIC_GUESS_NAME: &str = "gTLD NIC";
+
+/// Legacy gTLDs and infrastructure TLDs that predate the `whois.nic.<tld>` convention
+const LEGACY_TLDS: &[&str] = &[
+    "com", "net", "org", "info", "biz", "edu", "gov", "mil", "int", "arpa", "name", "pro",
+    "aero", "coop", "museum", "mobi", "asia", "tel", "travel", "jobs", "cat", "post",
+];
+
+/// Name given to registries of multi-label public suffixes such as `us.com`
+pub const SUFFIX_REGISTRY_NAME: &str = "Suffix registry";
+
+/// Registries for multi-label public suffixes whose TLD server has no data for them
+/// IANA would send `example.us.com` to Verisign, which only knows `us.com` itself
+const SUFFIX_WHOIS_SERVERS: &[(&str, &str)] = &[
+    ("ae.org", "whois.centralnic.com"),
+    ("africa.com", "whois.centralnic.com"),
+    ("br.com", "whois.centralnic.com"),
+    ("cn.com", "whois.centralnic.com"),
+    ("co.com", "whois.centralnic.com"),
+    ("com.de", "whois.centralnic.com"),
+    ("com.se", "whois.centralnic.com"),
+    ("de.com", "whois.centralnic.com"),
+    ("eu.com", "whois.centralnic.com"),
+    ("gb.net", "whois.centralnic.com"),
+    ("gr.com", "whois.centralnic.com"),
+    ("hu.net", "whois.centralnic.com"),
+    ("in.net", "whois.centralnic.com"),
+    ("jp.net", "whois.centralnic.com"),
+    ("jpn.com", "whois.centralnic.com"),
+    ("mex.com", "whois.centralnic.com"),
+    ("ru.com", "whois.centralnic.com"),
+    ("sa.com", "whois.centralnic.com"),
+    ("se.net", "whois.centralnic.com"),
+    ("uk.com", "whois.centralnic.com"),
+    ("uk.net", "whois.centralnic.com"),
+    ("us.com", "whois.centralnic.com"),
+    ("us.org", "whois.centralnic.com"),
+    ("za.bz", "whois.centralnic.com"),
+    ("za.com", "whois.centralnic.com"),
+    ("eu.org", "whois.eu.org"),
+    ("ac.uk", "whois.ja.net"),
+];
 
 #[derive(Debug, Clone)]
 pub struct WhoisServer {
@@ -46,12 +102,58 @@ impl WhoisServer {
         Self::new(RADB_WHOIS_SERVER, RADB_WHOIS_PORT, "RADB")
     }
 
+    pub fn altdb() -> Self {
+        Self::new(ALTDB_WHOIS_SERVER, ALTDB_WHOIS_PORT, "ALTDB")
+    }
+
     pub fn custom(host: impl Into<String>, port: u16) -> Self {
         Self::new(host.into(), port, "Custom")
     }
 
+    /// Server given as `host`, `host:port`, an IPv6 literal, or `[v6]:port`;
+    /// `port` is used when the spec has none; an unparseable port is an error
+    pub fn from_spec(spec: &str, port: u16) -> Result<Self> {
+        let (host, port) = split_host_port(spec, port)?;
+        Ok(Self::custom(host, port))
+    }
+
+    /// `host:port`, with IPv6 literals in brackets (`[2001:db8::43]:43`)
     pub fn address(&self) -> String {
-        format!("{}:{}", self.host, self.port)
+        if self.host.contains(':') {
+            format!("[{}]:{}", self.host, self.port)
+        } else {
+            format!("{}:{}", self.host, self.port)
+        }
+    }
+
+    /// Check if the server speaks RWhois rather than plain WHOIS
+    pub fn is_rwhois(&self) -> bool {
+        self.name == "RWhois"
+    }
+}
+
+/// Split a server spec into host and port: `host`, `host:port`, a bare IPv6 literal
+/// (`2001:db8::43`, never taken as having a port) or a bracketed one (`[2001:db8::43]:4343`)
+/// A port that is not a number from 0 to 65535 is an error
+pub fn split_host_port(spec: &str, default_port: u16) -> Result<(String, u16)> {
+    let spec = spec.trim();
+    if let Ok(address) = spec.parse::<SocketAddr>() {
+        return Ok((address.ip().to_string(), address.port()));
+    }
+    let parse_port = |port: &str| port.parse::<u16>().with_context(|| format!("Invalid port {:?} in server {}", port, spec));
+    if let Some(inner) = spec.strip_prefix('[') {
+        let (host, rest) = inner.split_once(']').with_context(|| format!("Missing ']' in server {}", spec))?;
+        return match rest {
+            "" => Ok((host.to_string(), default_port)),
+            _ => match rest.strip_prefix(':') {
+                Some(port) => Ok((host.to_string(), parse_port(port)?)),
+                None => anyhow::bail!("Unexpected {:?} after ']' in server {}", rest, spec),
+            },
+        };
+    }
+    match spec.rsplit_once(':') {
+        Some((host, port)) if !host.contains(':') => Ok((host.to_string(), parse_port(port)?)),
+        _ => Ok((spec.to_string(), default_port)),
     }
 }
 
@@ -81,6 +183,30 @@ impl ServerSelector {
         None
     }
 
+    /// Registry for a multi-label public suffix (`us.com`, `eu.org`) that IANA's TLD referral would miss
+    pub fn suffix_server(suffix: &str, port: u16) -> Option<WhoisServer> {
+        let suffix = suffix.trim_end_matches('.').to_lowercase();
+        SUFFIX_WHOIS_SERVERS
+            .iter()
+            .find(|(name, _)| *name == suffix)
+            .map(|(_, host)| WhoisServer::new(*host, port, SUFFIX_REGISTRY_NAME))
+    }
+
+    /// Conventional `whois.nic.<tld>` server for a domain under a new gTLD
+    /// ccTLDs, legacy gTLDs, IP addresses and prefixes yield `None`
+    pub fn nic_server_for(query: &str) -> Option<String> {
+        let query = query.trim().trim_end_matches('.').to_lowercase();
+        if query.parse::<std::net::IpAddr>().is_ok() || !query.contains('.') || query.contains(['/', ' ', ':']) {
+            return None;
+        }
+        let tld = query.rsplit('.').next()?;
+        let valid = tld.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+        if tld.len() < 3 || !valid || tld.chars().all(|c| c.is_ascii_digit()) || LEGACY_TLDS.contains(&tld) {
+            return None;
+        }
+        Some(format!("whois.nic.{}", tld))
+    }
+
     /// Get server from environment variable if available
     pub fn from_env() -> Option<String> {
         env::var("WHOIS_SERVER").ok()
@@ -93,25 +219,664 @@ impl ServerSelector {
         use_bgptools: bool,
         explicit_server: Option<&str>,
         port: u16,
-    ) -> WhoisServer {
+    ) -> Result<WhoisServer> {
         // Priority: special flags > explicit server > environment > default
         if use_dn42 || domain.to_uppercase().starts_with("AS42424") {
-            return WhoisServer::dn42();
+            return Ok(WhoisServer::dn42());
         }
 
         if use_bgptools {
-            return WhoisServer::bgptools();
+            return Ok(WhoisServer::bgptools());
         }
 
         if let Some(server) = explicit_server {
-            return WhoisServer::custom(server, port);
+            // `rwhois://host[:port]` selects an RWhois server directly
+            if server.to_lowercase().starts_with("rwhois://") {
+                if let Some(referral) = parse_referral_url(server) {
+                    return Ok(referral.to_server());
+                }
+            }
+            return WhoisServer::from_spec(server, port);
         }
 
         if let Some(env_server) = Self::from_env() {
-            return WhoisServer::custom(env_server, port);
+            return WhoisServer::from_spec(&env_server, port);
         }
 
         // Default: use IANA for referral
-        WhoisServer::iana()
+        Ok(WhoisServer::iana())
+    }
+} 
+
+/// Outcome of a query step that makes the next fallback step run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FallbackCondition {
+    /// The server answered but had no matching objects
+    Empty,
+    /// The server did not answer in time
+    Timeout,
+    /// The server refused the query because of rate limiting
+    RateLimited,
+}
+
+/// One server in the fallback chain, tried when the previous step ended in one of `on`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct FallbackStep {
+    pub server: String,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    #[serde(default)]
+    pub name: Option<String>,
+    pub on: Vec<FallbackCondition>,
+}
+
+fn default_port() -> u16 {
+    DEFAULT_WHOIS_PORT
+}
+
+impl FallbackStep {
+    pub fn new(server: &WhoisServer, on: &[FallbackCondition]) -> Self {
+        Self {
+            server: server.host.clone(),
+            port: server.port,
+            name: Some(server.name.clone()),
+            on: on.to_vec(),
+        }
+    }
+
+    /// Check if this step should run after the given outcome
+    pub fn applies_to(&self, condition: FallbackCondition) -> bool {
+        self.on.contains(&condition)
+    }
+
+    pub fn to_server(&self) -> WhoisServer {
+        WhoisServer::new(
+            self.server.clone(),
+            self.port,
+            self.name.clone().unwrap_or_else(|| self.server.clone()),
+        )
+    }
+}
+
+/// Built-in fallback chain used when the configuration does not define one
+/// RIR servers that come back empty are retried on RADB
+pub fn default_fallback_chain() -> Vec<FallbackStep> {
+    vec![FallbackStep::new(&WhoisServer::radb(), &[FallbackCondition::Empty])]
+}
+
+/// Answer language requested with `--lang`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Language {
+    /// English
+    En,
+    /// The registry's local language
+    Local,
+}
+
+/// How a registry selects the language of its answers
+/// Registries either take a query suffix (JPRS `/e`) or answer bilingually with marked sections (KRNIC)
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct LanguageSupport {
+    /// Appended to the query for English answers
+    pub english_suffix: Option<String>,
+    /// Appended to the query for local-language answers
+    pub local_suffix: Option<String>,
+    /// Line that starts the English section of a bilingual answer
+    pub english_marker: Option<String>,
+    /// Line that starts the local-language section of a bilingual answer
+    pub local_marker: Option<String>,
+}
+
+impl LanguageSupport {
+    /// Query with the suffix for the requested language, if the registry has one
+    pub fn localize_query(&self, query: &str, language: Language) -> String {
+        let suffix = match language {
+            Language::En => self.english_suffix.as_deref(),
+            Language::Local => self.local_suffix.as_deref(),
+        };
+        match suffix {
+            Some(suffix) if !query.ends_with(suffix) => format!("{}{}", query, suffix),
+            _ => query.to_string(),
+        }
+    }
+
+    /// Keep only the requested section of a bilingual answer, plus any shared header before the first section
+    /// Answers without the markers are returned unchanged
+    pub fn select_section(&self, response: &str, language: Language) -> String {
+        let (Some(english), Some(local)) = (&self.english_marker, &self.local_marker) else {
+            return response.to_string();
+        };
+        let (wanted, other) = match language {
+            Language::En => (english, local),
+            Language::Local => (local, english),
+        };
+        let lines: Vec<&str> = response.lines().collect();
+        let find = |marker: &str| lines.iter().position(|line| line.trim() == marker);
+        let (Some(wanted_at), Some(other_at)) = (find(wanted), find(other)) else {
+            return response.to_string();
+        };
+
+        let header_end = wanted_at.min(other_at);
+        let section_end = if other_at > wanted_at { other_at } else { lines.len() };
+        let mut selected = lines[..header_end].to_vec();
+        selected.extend_from_slice(&lines[wanted_at..section_end]);
+        let mut output = selected.join("\n").trim_end().to_string();
+        if response.ends_with('\n') {
+            output.push('\n');
+        }
+        output
+    }
+}
+
+/// Registries with built-in language handling
+fn builtin_language_support() -> Vec<(&'static str, LanguageSupport)> {
+    let suffix = |english: &str| LanguageSupport {
+        english_suffix: Some(english.to_string()),
+        ..Default::default()
+    };
+    let sections = |english: &str, local: &str| LanguageSupport {
+        english_marker: Some(english.to_string()),
+        local_marker: Some(local.to_string()),
+        ..Default::default()
+    };
+    vec![
+        ("whois.jprs.jp", suffix("/e")),
+        ("whois.nic.ad.jp", suffix("/e")),
+        ("whois.kisa.or.kr", sections("# ENGLISH", "# KOREAN(UTF8)")),
+        ("whois.nic.or.kr", sections("# ENGLISH", "# KOREAN(UTF8)")),
+    ]
+}
+
+/// Query flags a server takes for less- and more-specific networks (RIPE `-L`/`-M`)
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct HierarchyFlags {
+    /// Prepended to the query for the covering networks, including an exact match
+    pub less_specific: String,
+    /// Prepended to the query for the networks inside it
+    pub more_specific: String,
+}
+
+impl HierarchyFlags {
+    fn new(less_specific: &str, more_specific: &str) -> Self {
+        Self {
+            less_specific: less_specific.to_string(),
+            more_specific: more_specific.to_string(),
+        }
+    }
+}
+
+/// Record type a query is restricted to with `--arin-type`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum RecordType {
+    /// Networks
+    Net,
+    /// Autonomous system numbers
+    Asn,
+    /// Organizations
+    Org,
+    /// Points of contact
+    Poc,
+}
+
+/// Query prefixes of servers with their own query syntax, such as ARIN's `n + 8.8.8.8`
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct QuerySyntax {
+    /// Prefix asking for full output, e.g. ARIN's `+`
+    pub full: Option<String>,
+    /// Prefix restricting the query to networks
+    pub net: Option<String>,
+    /// Prefix restricting the query to ASNs
+    pub asn: Option<String>,
+    /// Prefix restricting the query to organizations
+    pub org: Option<String>,
+    /// Prefix restricting the query to points of contact
+    pub poc: Option<String>,
+}
+
+impl QuerySyntax {
+    /// Prefix of a record type, if the server has one
+    pub fn prefix(&self, record_type: RecordType) -> Option<&str> {
+        match record_type {
+            RecordType::Net => self.net.as_deref(),
+            RecordType::Asn => self.asn.as_deref(),
+            RecordType::Org => self.org.as_deref(),
+            RecordType::Poc => self.poc.as_deref(),
+        }
+    }
+
+    /// Query with the prefixes for a record type and full output, type first
+    /// A record type the query already starts with wins over the requested one
+    pub fn apply(&self, query: &str, record_type: Option<RecordType>, full: bool) -> String {
+        let types = [RecordType::Net, RecordType::Asn, RecordType::Org, RecordType::Poc].map(|record_type| self.prefix(record_type));
+        let mut words: Vec<&str> = query.split_whitespace().collect();
+        let given_type = words.first().copied().filter(|word| types.contains(&Some(*word)));
+        if given_type.is_some() {
+            words.remove(0);
+        }
+        let given_full = words.first().is_some_and(|word| Some(*word) == self.full.as_deref());
+        if given_full {
+            words.remove(0);
+        }
+        let type_prefix = given_type.or_else(|| record_type.and_then(|record_type| self.prefix(record_type)));
+        let full_prefix = self.full.as_deref().filter(|_| full || given_full);
+        if type_prefix.is_none() && full_prefix.is_none() {
+            return query.to_string();
+        }
+        [type_prefix, full_prefix].into_iter().flatten().chain(words).collect::<Vec<_>>().join(" ")
+    }
+}
+
+/// ARIN's query syntax: a record type letter and `+` before the query
+fn builtin_query_syntax() -> Vec<(&'static str, QuerySyntax)> {
+    let prefix = |letter: &str| Some(letter.to_string());
+    vec![(
+        "whois.arin.net",
+        QuerySyntax {
+            full: prefix("+"),
+            net: prefix("n"),
+            asn: prefix("a"),
+            org: prefix("o"),
+            poc: prefix("p"),
+        },
+    )]
+}
+
+/// Registries with built-in hierarchy flags
+/// RIPE database servers also take `-r` so contact objects are left out; IRRd has no contacts to skip
+fn builtin_hierarchy_flags() -> Vec<(&'static str, HierarchyFlags)> {
+    let ripe = HierarchyFlags::new("-r -L", "-r -M");
+    let irrd = HierarchyFlags::new("-L", "-M");
+    vec![
+        (DEFAULT_WHOIS_SERVER, ripe.clone()),
+        ("whois.apnic.net", ripe.clone()),
+        ("whois.afrinic.net", ripe),
+        (RADB_WHOIS_SERVER, irrd.clone()),
+        (ALTDB_WHOIS_SERVER, irrd),
+    ]
+}
+
+/// RIPE-style query flags a server understands; flags it lacks are left out of its queries
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct QueryFlags {
+    /// `-i ATTRIBUTE` inverse lookups
+    pub inverse: bool,
+    /// `-T TYPES` object type filters
+    pub types: bool,
+}
+
+/// Registries that take `-i` and `-T`: the RIPE database software (RIPE, APNIC, AFRINIC) and IRRd
+fn builtin_query_flags() -> Vec<(&'static str, QueryFlags)> {
+    let both = QueryFlags { inverse: true, types: true };
+    [DEFAULT_WHOIS_SERVER, "whois.apnic.net", "whois.afrinic.net", RADB_WHOIS_SERVER, ALTDB_WHOIS_SERVER]
+        .into_iter()
+        .map(|host| (host, both))
+        .collect()
+}
+
+/// Per-server settings, keyed by host name in the registry
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ServerProfile {
+    /// Empty-result heuristics for this server, replacing the global policy
+    pub empty_result: Option<EmptyResultPolicy>,
+    /// Response cleanup rules for this server, replacing the built-in ones
+    pub rules: Option<Vec<ResponseRule>>,
+    /// How to ask this server for answers in a given language
+    pub lang: Option<LanguageSupport>,
+    /// Whether the server keeps connections open for several queries with `-k`
+    pub persistent: Option<bool>,
+    /// How to ask this server for less- and more-specific networks with `--related`
+    pub hierarchy: Option<HierarchyFlags>,
+    /// Whether the server takes `-i` and `-T`
+    pub flags: Option<QueryFlags>,
+    /// Whether the server speaks IRRd's `!` query protocol; probed with `!v` when unset
+    pub irrd: Option<bool>,
+    /// Query prefixes for `--arin-type` and `--arin-full`
+    pub query_syntax: Option<QuerySyntax>,
+    /// RDAP base URL of the registry, e.g. `https://rdap.lacnic.net/rdap`
+    pub rdap: Option<String>,
+    /// Ask the RDAP endpoint first for addresses, ASNs and handles, using port 43 when it fails
+    pub prefer_rdap: Option<bool>,
+    /// Bearer token sent in `X-WHOIS-AUTH` to servers that accept one
+    pub auth_token: Option<String>,
+    /// Read the token from the system keyring instead (feature `keyring`)
+    pub auth_keyring: Option<bool>,
+    /// Character set of the server's answers when it is not UTF-8, e.g. `ISO-2022-JP` or `EUC-KR`
+    pub charset: Option<String>,
+}
+
+/// Server profiles plus the global defaults they override
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerRegistry {
+    pub empty_result: EmptyResultPolicy,
+    /// Cleanup rules applied to every server, before any per-server rules
+    pub rules: Vec<ResponseRule>,
+    pub profiles: HashMap<String, ServerProfile>,
+}
+
+impl Default for ServerRegistry {
+    fn default() -> Self {
+        let mut profiles: HashMap<String, ServerProfile> = HashMap::new();
+        for (host, rules) in builtin_server_rules() {
+            profiles.entry(host.to_string()).or_default().rules = Some(rules);
+        }
+        for (host, lang) in builtin_language_support() {
+            profiles.entry(host.to_string()).or_default().lang = Some(lang);
+        }
+        for (host, flags) in builtin_hierarchy_flags() {
+            profiles.entry(host.to_string()).or_default().hierarchy = Some(flags);
+        }
+        for (host, flags) in builtin_query_flags() {
+            profiles.entry(host.to_string()).or_default().flags = Some(flags);
+        }
+        for (host, syntax) in builtin_query_syntax() {
+            profiles.entry(host.to_string()).or_default().query_syntax = Some(syntax);
+        }
+        for (host, charset) in [
+            ("whois.jprs.jp", "ISO-2022-JP"),
+            ("whois.nic.ad.jp", "ISO-2022-JP"),
+            ("whois.kisa.or.kr", "EUC-KR"),
+            ("whois.nic.or.kr", "EUC-KR"),
+        ] {
+            profiles.entry(host.to_string()).or_default().charset = Some(charset.to_string());
+        }
+        // LACNIC rate-limits port 43 much harder than its RDAP service
+        let lacnic = profiles.entry("whois.lacnic.net".to_string()).or_default();
+        lacnic.rdap = Some("https://rdap.lacnic.net/rdap".to_string());
+        lacnic.prefer_rdap = Some(true);
+        // RIPE-style and IRRd servers support `-k` persistent connections
+        for host in [RADB_WHOIS_SERVER, ALTDB_WHOIS_SERVER, DEFAULT_WHOIS_SERVER] {
+            profiles.entry(host.to_string()).or_default().persistent = Some(true);
+        }
+        for host in [RADB_WHOIS_SERVER, ALTDB_WHOIS_SERVER] {
+            profiles.entry(host.to_string()).or_default().irrd = Some(true);
+        }
+        // The RIPE database software answers `!` queries with an error
+        profiles.entry(DEFAULT_WHOIS_SERVER.to_string()).or_default().irrd = Some(false);
+        Self {
+            empty_result: EmptyResultPolicy::default(),
+            rules: default_rules(),
+            profiles,
+        }
+    }
+}
+
+impl ServerRegistry {
+    /// Profile for a host, if one is defined
+    pub fn profile(&self, host: &str) -> Option<&ServerProfile> {
+        self.profiles.get(&host.to_lowercase())
+    }
+
+    /// Empty-result policy that applies to a host
+    pub fn empty_policy(&self, host: &str) -> &EmptyResultPolicy {
+        self.profile(host)
+            .and_then(|profile| profile.empty_result.as_ref())
+            .unwrap_or(&self.empty_result)
+    }
+
+    /// Cleanup rules that apply to a host: global rules first, then the host's own
+    pub fn rules_for(&self, host: &str) -> impl Iterator<Item = &ResponseRule> {
+        let own = self.profile(host).and_then(|profile| profile.rules.as_deref()).unwrap_or_default();
+        self.rules.iter().chain(own)
+    }
+
+    /// Check if several queries to a host can share one `-k` connection
+    pub fn supports_persistent(&self, host: &str) -> bool {
+        self.profile(host).and_then(|profile| profile.persistent).unwrap_or(false)
+    }
+
+    /// Whether a host speaks IRRd's `!` protocol, if its profile says
+    pub fn speaks_irrd(&self, host: &str) -> Option<bool> {
+        self.profile(host).and_then(|profile| profile.irrd)
+    }
+
+    /// Less/more-specific query flags of a host, if it has any
+    pub fn hierarchy_flags(&self, host: &str) -> Option<&HierarchyFlags> {
+        self.profile(host).and_then(|profile| profile.hierarchy.as_ref())
+    }
+
+    /// Token for `X-WHOIS-AUTH`: the host's `auth_token`, or its keyring entry with `auth_keyring`
+    pub fn auth_token(&self, host: &str) -> Option<String> {
+        let profile = self.profile(host)?;
+        match (&profile.auth_token, profile.auth_keyring) {
+            (Some(token), _) => Some(token.clone()),
+            (None, Some(true)) => keyring_token(host),
+            _ => None,
+        }
+    }
+
+    /// Character set a host answers in, when it is not UTF-8
+    pub fn charset(&self, host: &str) -> Option<&str> {
+        self.profile(host).and_then(|profile| profile.charset.as_deref())
+    }
+
+    /// Query string for a host, with the language suffix it needs, if any
+    pub fn localize_query(&self, host: &str, query: &str, language: Option<Language>) -> String {
+        match (language, self.profile(host).and_then(|profile| profile.lang.as_ref())) {
+            (Some(language), Some(lang)) => lang.localize_query(query, language),
+            _ => query.to_string(),
+        }
+    }
+
+    /// `-i`/`-T` support of a host; hosts without a profile take neither
+    pub fn query_flags(&self, host: &str) -> QueryFlags {
+        self.profile(host).and_then(|profile| profile.flags).unwrap_or_default()
+    }
+
+    /// RDAP base URL to ask before port 43, for hosts that prefer RDAP
+    pub fn preferred_rdap(&self, host: &str) -> Option<&str> {
+        let profile = self.profile(host)?;
+        profile.rdap.as_deref().filter(|_| profile.prefer_rdap == Some(true))
+    }
+
+    /// Record type and full-output prefixes of a host, if it has any
+    pub fn query_syntax(&self, host: &str) -> Option<&QuerySyntax> {
+        self.profile(host).and_then(|profile| profile.query_syntax.as_ref())
+    }
+
+    /// Query string for a host with its prefixes for a record type and full output, if it has any
+    pub fn typed_query(&self, host: &str, query: &str, record_type: Option<RecordType>, full: bool) -> String {
+        match self.query_syntax(host) {
+            Some(syntax) => syntax.apply(query, record_type, full),
+            None => query.to_string(),
+        }
+    }
+
+    /// Keep the requested language section of a host's bilingual answer
+    pub fn select_language(&self, host: &str, response: &str, language: Option<Language>) -> String {
+        match (language, self.profile(host).and_then(|profile| profile.lang.as_ref())) {
+            (Some(language), Some(lang)) => lang.select_section(response, language),
+            _ => response.to_string(),
+        }
+    }
+
+    /// Apply the host's cleanup rules to a response
+    pub fn clean_response(&self, host: &str, response: &str, level: CleanupLevel) -> String {
+        apply_rules(response, self.rules_for(host), level)
+    }
+}
+
+/// Token stored in the system keyring for a host, with `whois auth set HOST`
+fn keyring_token(host: &str) -> Option<String> {
+    let token = crate::secrets::account_name(host).and_then(|account| crate::secrets::lookup(&account));
+    match token {
+        Ok(Some(token)) => Some(token),
+        Ok(None) => {
+            warn!("No keyring token for {}; store one with `whois auth set {}`", host, host);
+            None
+        }
+        Err(err) => {
+            warn!("auth_keyring for {}: {:#}", host, err);
+            None
+        }
     }
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_chain_is_radb_on_empty() {
+        let chain = default_fallback_chain();
+        assert_eq!(chain.len(), 1);
+        assert_eq!(chain[0].to_server().host, RADB_WHOIS_SERVER);
+        assert!(chain[0].applies_to(FallbackCondition::Empty));
+        assert!(!chain[0].applies_to(FallbackCondition::Timeout));
+    }
+
+    #[test]
+    fn test_fallback_step_to_server_defaults_name_to_host() {
+        let step = FallbackStep {
+            server: ALTDB_WHOIS_SERVER.to_string(),
+            port: 43,
+            name: None,
+            on: vec![FallbackCondition::Empty],
+        };
+        let server = step.to_server();
+        assert_eq!(server.name, ALTDB_WHOIS_SERVER);
+        assert_eq!(server.address(), "whois.altdb.net:43");
+    }
+
+    #[test]
+    fn test_registry_empty_policy_per_host() {
+        let terse = EmptyResultPolicy {
+            short_response_length: 0,
+            ..Default::default()
+        };
+        let mut registry = ServerRegistry::default();
+        registry.profiles.insert(
+            "whois.nic.example".to_string(),
+            ServerProfile {
+                empty_result: Some(terse.clone()),
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(registry.empty_policy("WHOIS.NIC.EXAMPLE"), &terse);
+        assert_eq!(registry.empty_policy("whois.ripe.net"), &EmptyResultPolicy::default());
+    }
+
+    #[test]
+    fn test_registry_rules_per_host() {
+        let registry = ServerRegistry::default();
+        assert_eq!(registry.rules_for("whois.radb.net").count(), registry.rules.len());
+        assert!(registry.rules_for("WHOIS.ARIN.NET").any(|rule| rule.name == "arin-terms"));
+        assert!(registry.supports_persistent("whois.radb.net"));
+        assert!(!registry.supports_persistent("whois.arin.net"));
+        assert_eq!(registry.speaks_irrd("whois.altdb.net"), Some(true));
+        assert_eq!((registry.speaks_irrd("whois.ripe.net"), registry.speaks_irrd("rr.ntt.net")), (Some(false), None));
+        assert_eq!(registry.hierarchy_flags("whois.apnic.net").unwrap().less_specific, "-r -L");
+        assert_eq!(registry.hierarchy_flags("whois.radb.net").unwrap().more_specific, "-M");
+        assert!(registry.hierarchy_flags("whois.arin.net").is_none());
+        assert!(registry.query_flags("whois.afrinic.net").inverse && registry.query_flags("whois.radb.net").types);
+        assert_eq!(registry.query_flags("whois.lacnic.net"), QueryFlags::default());
+
+        let response = "Domain names in the .com and .net domains can now be registered\nwith many different competing registrars.\n\nDomain Name: EXAMPLE.COM\n";
+        assert_eq!(
+            registry.clean_response("whois.verisign-grs.com", response, CleanupLevel::Standard),
+            "Domain Name: EXAMPLE.COM\n"
+        );
+        assert_eq!(registry.clean_response("whois.radb.net", response, CleanupLevel::Short), response);
+    }
+
+    #[test]
+    fn test_lacnic_prefers_rdap() {
+        let mut registry = ServerRegistry::default();
+        assert_eq!(registry.preferred_rdap("whois.lacnic.net"), Some("https://rdap.lacnic.net/rdap"));
+        assert_eq!(registry.preferred_rdap("whois.ripe.net"), None);
+        registry.profiles.get_mut("whois.lacnic.net").unwrap().prefer_rdap = Some(false);
+        assert_eq!(registry.preferred_rdap("whois.lacnic.net"), None);
+    }
+
+    #[test]
+    fn test_arin_query_syntax() {
+        let registry = ServerRegistry::default();
+        assert_eq!(registry.typed_query("whois.arin.net", "8.8.8.8", Some(RecordType::Net), true), "n + 8.8.8.8");
+        assert_eq!(registry.typed_query("whois.arin.net", "GOGL", Some(RecordType::Org), false), "o GOGL");
+        assert_eq!(registry.typed_query("WHOIS.ARIN.NET", "AS15169", None, true), "+ AS15169");
+        assert_eq!(registry.typed_query("whois.arin.net", "n + 8.8.8.8", Some(RecordType::Net), true), "n + 8.8.8.8");
+        assert_eq!(registry.typed_query("whois.arin.net", "p ABUSE5250-ARIN", Some(RecordType::Net), true), "p + ABUSE5250-ARIN");
+        assert_eq!(registry.typed_query("whois.arin.net", "8.8.8.8", None, false), "8.8.8.8");
+        assert_eq!(registry.typed_query("whois.ripe.net", "193.0.0.1", Some(RecordType::Net), true), "193.0.0.1");
+    }
+
+    #[test]
+    fn test_language_suffix_per_host() {
+        let registry = ServerRegistry::default();
+        assert_eq!(registry.localize_query("whois.jprs.jp", "example.jp", Some(Language::En)), "example.jp/e");
+        assert_eq!(registry.localize_query("whois.jprs.jp", "example.jp/e", Some(Language::En)), "example.jp/e");
+        assert_eq!(registry.localize_query("whois.jprs.jp", "example.jp", Some(Language::Local)), "example.jp");
+        assert_eq!(registry.localize_query("whois.jprs.jp", "example.jp", None), "example.jp");
+        assert_eq!(registry.localize_query("whois.ripe.net", "AS3333", Some(Language::En)), "AS3333");
+    }
+
+    #[test]
+    fn test_bilingual_section_selection() {
+        let registry = ServerRegistry::default();
+        let response = "query : 1.11.0.0\n\n# KOREAN(UTF8)\n조회하신 IPv4주소는\n\n# ENGLISH\nKRNIC is not an ISP\n";
+        assert_eq!(
+            registry.select_language("whois.kisa.or.kr", response, Some(Language::En)),
+            "query : 1.11.0.0\n\n# ENGLISH\nKRNIC is not an ISP\n"
+        );
+        assert_eq!(
+            registry.select_language("whois.kisa.or.kr", response, Some(Language::Local)),
+            "query : 1.11.0.0\n\n# KOREAN(UTF8)\n조회하신 IPv4주소는\n"
+        );
+        assert_eq!(registry.select_language("whois.kisa.or.kr", response, None), response);
+    }
+
+    #[test]
+    fn test_select_rwhois_server() {
+        let server = ServerSelector::select_server("192.0.2.1", false, false, Some("rwhois://rwhois.example.net"), 43).unwrap();
+        assert!(server.is_rwhois());
+        assert_eq!(server.address(), "rwhois.example.net:4321");
+        assert!(!ServerSelector::select_server("192.0.2.1", false, false, Some("whois.example.net"), 43).unwrap().is_rwhois());
+    }
+
+    #[test]
+    fn test_ipv6_server_specs() {
+        assert_eq!(split_host_port("2001:db8::43", 43).unwrap(), ("2001:db8::43".to_string(), 43));
+        assert_eq!(split_host_port("[2001:db8::43]", 43).unwrap(), ("2001:db8::43".to_string(), 43));
+        assert_eq!(split_host_port("[2001:db8::43]:4343", 43).unwrap(), ("2001:db8::43".to_string(), 4343));
+        assert_eq!(split_host_port("192.0.2.43:4343", 43).unwrap(), ("192.0.2.43".to_string(), 4343));
+        assert_eq!(split_host_port("whois.example.net:4343", 43).unwrap(), ("whois.example.net".to_string(), 4343));
+        assert_eq!(split_host_port("whois.example.net", 4343).unwrap(), ("whois.example.net".to_string(), 4343));
+
+        // A bad port is an error rather than part of the host name
+        for spec in ["whois.example.net:badport", "whois.example.net:", "192.0.2.43:70000", "[2001:db8::43]:x", "[2001:db8::43]x", "[2001:db8::43"] {
+            assert!(split_host_port(spec, 43).is_err(), "{}", spec);
+        }
+        assert!(WhoisServer::from_spec("whois.example.net:badport", 43).is_err());
+
+        let server = ServerSelector::select_server("AS64500", false, false, Some("2001:db8::43"), 43).unwrap();
+        assert_eq!(server.host, "2001:db8::43");
+        assert_eq!(server.address(), "[2001:db8::43]:43");
+        assert_eq!(WhoisServer::from_spec("[2001:db8::43]:4343", 43).unwrap().address(), "[2001:db8::43]:4343");
+        assert!(server.address().parse::<SocketAddr>().is_ok());
+    }
+
+    #[test]
+    fn test_suffix_server() {
+        let server = ServerSelector::suffix_server("US.com", 43).unwrap();
+        assert_eq!(server.address(), "whois.centralnic.com:43");
+        assert_eq!(server.name, SUFFIX_REGISTRY_NAME);
+        assert_eq!(ServerSelector::suffix_server("eu.org", 43).unwrap().host, "whois.eu.org");
+        assert!(ServerSelector::suffix_server("co.uk", 43).is_none());
+    }
+
+    #[test]
+    fn test_nic_server_for_new_gtlds() {
+        assert_eq!(ServerSelector::nic_server_for("example.shop"), Some("whois.nic.shop".to_string()));
+        assert_eq!(ServerSelector::nic_server_for("Sub.Example.XN--P1ACF."), Some("whois.nic.xn--p1acf".to_string()));
+        assert_eq!(ServerSelector::nic_server_for("example.com"), None);
+        assert_eq!(ServerSelector::nic_server_for("example.de"), None);
+        assert_eq!(ServerSelector::nic_server_for("192.0.2.1"), None);
+        assert_eq!(ServerSelector::nic_server_for("192.0.2.0/24"), None);
+        assert_eq!(ServerSelector::nic_server_for("AS3333"), None);
+    }
+}
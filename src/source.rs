@@ -0,0 +1,175 @@
+use std::io::{self, ErrorKind};
+use std::net::{IpAddr, SocketAddr, TcpStream};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use anyhow::Result;
+use rustix::event::{poll, PollFd, PollFlags, Timespec};
+use rustix::io::Errno;
+use rustix::net::{AddressFamily, SocketType};
+
+/// Local side of outgoing WHOIS connections, set once from `--source-ip` or `--interface`
+static SOURCE: OnceLock<Source> = OnceLock::new();
+
+/// Where outgoing WHOIS connections come from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// A local address, e.g. the tunnel address DN42 peers expect
+    Ip(IpAddr),
+    /// An interface whose address is used for the server's address family
+    Interface(String),
+}
+
+impl Source {
+    /// Local address to bind for a connection to `remote`; fails when the source has no
+    /// address of the remote's family
+    pub fn local_address(&self, remote: &SocketAddr) -> io::Result<SocketAddr> {
+        match self {
+            Source::Ip(ip) if ip.is_ipv4() == remote.is_ipv4() => Ok(SocketAddr::new(*ip, 0)),
+            Source::Ip(ip) => Err(io::Error::new(
+                ErrorKind::AddrNotAvailable,
+                format!("source address {} cannot reach {}", ip, remote),
+            )),
+            Source::Interface(name) => {
+                let addresses = interface_addresses(name)?;
+                if addresses.is_empty() {
+                    return Err(io::Error::new(ErrorKind::NotFound, format!("interface {} has no addresses", name)));
+                }
+                pick_address(&addresses, remote).map(|ip| SocketAddr::new(ip, 0)).ok_or_else(|| {
+                    io::Error::new(
+                        ErrorKind::AddrNotAvailable,
+                        format!("interface {} has no IPv{} address for {}", name, if remote.is_ipv4() { 4 } else { 6 }, remote),
+                    )
+                })
+            }
+        }
+    }
+}
+
+/// Make every later WHOIS connection come from `source`
+pub fn set(source: Source) -> Result<()> {
+    SOURCE.set(source).map_err(|_| anyhow::anyhow!("The source address is already set"))
+}
+
+/// The source set with `set`, if any
+pub fn get() -> Option<&'static Source> {
+    SOURCE.get()
+}
+
+/// Address of the interface to use for `remote`: one of the same family,
+/// preferring addresses that are not IPv6 link-local
+fn pick_address(addresses: &[IpAddr], remote: &SocketAddr) -> Option<IpAddr> {
+    let same_family = || addresses.iter().filter(|ip| ip.is_ipv4() == remote.is_ipv4());
+    let link_local = |ip: &&IpAddr| matches!(ip, IpAddr::V6(v6) if (v6.segments()[0] & 0xffc0) == 0xfe80);
+    same_family().find(|ip| !link_local(ip)).or_else(|| same_family().next()).copied()
+}
+
+/// Connect from `local` to `remote`, giving up after `timeout`
+pub fn connect_from(remote: &SocketAddr, local: SocketAddr, timeout: Duration) -> io::Result<TcpStream> {
+    let family = if remote.is_ipv4() { AddressFamily::INET } else { AddressFamily::INET6 };
+    let socket = rustix::net::socket(family, SocketType::STREAM, None)?;
+    rustix::net::bind(&socket, &local)?;
+    rustix::io::ioctl_fionbio(&socket, true)?;
+    match rustix::net::connect(&socket, remote) {
+        Ok(()) => {}
+        Err(err) if err == Errno::INPROGRESS || err == Errno::WOULDBLOCK => {
+            let wait = Timespec::try_from(timeout).map_err(|_| io::Error::from(ErrorKind::InvalidInput))?;
+            let mut fds = [PollFd::new(&socket, PollFlags::OUT)];
+            if poll(&mut fds, Some(&wait))? == 0 {
+                return Err(io::Error::new(ErrorKind::TimedOut, "connection timed out"));
+            }
+            rustix::net::sockopt::socket_error(&socket)??;
+        }
+        Err(err) => return Err(err.into()),
+    }
+    rustix::io::ioctl_fionbio(&socket, false)?;
+    Ok(TcpStream::from(socket))
+}
+
+/// Addresses assigned to a network interface
+#[cfg(unix)]
+fn interface_addresses(name: &str) -> io::Result<Vec<IpAddr>> {
+    use std::ffi::CStr;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    let mut list: *mut libc::ifaddrs = std::ptr::null_mut();
+    // SAFETY: getifaddrs fills `list` with a linked list that is released with freeifaddrs below
+    if unsafe { libc::getifaddrs(&mut list) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let mut addresses = Vec::new();
+    let mut entry = list;
+    while !entry.is_null() {
+        // SAFETY: `entry` is a node of the list returned by getifaddrs, which is still alive
+        let ifaddr = unsafe { &*entry };
+        entry = ifaddr.ifa_next;
+        // SAFETY: ifa_name is a NUL-terminated string owned by the list
+        if ifaddr.ifa_addr.is_null() || unsafe { CStr::from_ptr(ifaddr.ifa_name) }.to_bytes() != name.as_bytes() {
+            continue;
+        }
+        // SAFETY: the address family tells which sockaddr type ifa_addr points to
+        let address = unsafe {
+            match i32::from((*ifaddr.ifa_addr).sa_family) {
+                libc::AF_INET => {
+                    let v4 = &*(ifaddr.ifa_addr as *const libc::sockaddr_in);
+                    Some(IpAddr::V4(Ipv4Addr::from(u32::from_be(v4.sin_addr.s_addr))))
+                }
+                libc::AF_INET6 => {
+                    let v6 = &*(ifaddr.ifa_addr as *const libc::sockaddr_in6);
+                    Some(IpAddr::V6(Ipv6Addr::from(v6.sin6_addr.s6_addr)))
+                }
+                _ => None,
+            }
+        };
+        addresses.extend(address);
+    }
+    // SAFETY: `list` came from getifaddrs and is not used afterwards
+    unsafe { libc::freeifaddrs(list) };
+    Ok(addresses)
+}
+
+#[cfg(not(unix))]
+fn interface_addresses(name: &str) -> io::Result<Vec<IpAddr>> {
+    Err(io::Error::new(
+        ErrorKind::Unsupported,
+        format!("--interface {} is not supported on this platform, use --source-ip", name),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_local_address_matches_family() {
+        let v4: SocketAddr = "192.0.2.43:43".parse().unwrap();
+        let v6: SocketAddr = "[2001:db8::43]:43".parse().unwrap();
+        let source = Source::Ip("198.51.100.7".parse().unwrap());
+        assert_eq!(source.local_address(&v4).unwrap(), "198.51.100.7:0".parse().unwrap());
+        assert!(source.local_address(&v6).is_err());
+
+        let addresses: Vec<IpAddr> = ["fe80::1", "203.0.113.1", "2001:db8::7"].iter().map(|ip| ip.parse().unwrap()).collect();
+        assert_eq!(pick_address(&addresses, &v6), Some("2001:db8::7".parse().unwrap()));
+        assert_eq!(pick_address(&addresses, &v4), Some("203.0.113.1".parse().unwrap()));
+        assert_eq!(pick_address(&addresses[..1], &v4), None);
+    }
+
+    #[test]
+    fn test_connect_from_loopback() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let remote = listener.local_addr().unwrap();
+        let stream = connect_from(&remote, "127.0.0.1:0".parse().unwrap(), Duration::from_secs(5)).unwrap();
+        let (_, peer) = listener.accept().unwrap();
+        assert_eq!(peer, stream.local_addr().unwrap());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_loopback_interface_addresses() {
+        // `lo` on Linux, `lo0` on the BSDs and macOS
+        let addresses: Vec<IpAddr> = ["lo", "lo0"].iter().flat_map(|name| interface_addresses(name).unwrap_or_default()).collect();
+        assert!(addresses.iter().any(IpAddr::is_loopback));
+        assert!(interface_addresses("no-such-if0").unwrap().is_empty());
+    }
+}
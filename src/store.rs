@@ -0,0 +1,419 @@
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use tracing::{debug, warn};
+
+use crate::config::Config;
+use crate::fingerprint::fingerprint;
+use crate::protocol::{CapabilityCache, ServerCapabilities};
+
+/// File name of the database next to the config file
+pub const STORE_FILE_NAME: &str = "whois.sqlite";
+/// How long a capability probe result is reused
+pub const DEFAULT_CAPABILITY_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS responses (
+    target     TEXT NOT NULL,
+    host       TEXT NOT NULL,
+    port       INTEGER NOT NULL,
+    response   TEXT NOT NULL,
+    fetched_at INTEGER NOT NULL,
+    fingerprint TEXT,
+    lookup     TEXT,
+    server_colored INTEGER NOT NULL DEFAULT 0,
+    markdown   INTEGER
+);
+CREATE TABLE IF NOT EXISTS jobs (
+    job         TEXT NOT NULL,
+    target      TEXT NOT NULL,
+    output      TEXT,
+    error       TEXT,
+    recorded_at INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS capabilities (
+    address      TEXT PRIMARY KEY,
+    capabilities TEXT NOT NULL,
+    probed_at    INTEGER NOT NULL
+);
+";
+
+/// Created after `add_columns`, as they cover columns old databases lack
+const INDEXES: &str = "
+CREATE INDEX IF NOT EXISTS responses_target ON responses (target, fetched_at);
+CREATE INDEX IF NOT EXISTS responses_lookup ON responses (lookup, fetched_at);
+";
+
+/// An answer as kept in the store, with what the server said about its formatting
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredAnswer {
+    pub host: String,
+    pub port: u16,
+    pub response: String,
+    /// The server colored the answer itself
+    pub server_colored: bool,
+    /// `X-WHOIS-MARKDOWN-APPLIED` of the answer; `None` when the server did not say
+    pub markdown: Option<bool>,
+}
+
+/// Row counts and size of the store, for `whois cache stats`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StoreStats {
+    pub responses: u64,
+    /// Distinct targets among the stored responses
+    pub targets: u64,
+    pub job_entries: u64,
+    pub jobs: u64,
+    pub capabilities: u64,
+    pub oldest: Option<i64>,
+    pub size_bytes: u64,
+}
+
+impl StoreStats {
+    pub fn render(&self, path: &Path) -> String {
+        let oldest = match self.oldest {
+            Some(oldest) => format!("{} ago", format_age(now() - oldest)),
+            None => "-".to_string(),
+        };
+        [
+            format!("Database:      {}", path.display()),
+            format!("Size:          {} KiB", self.size_bytes.div_ceil(1024)),
+            format!("Responses:     {} ({} targets, oldest {})", self.responses, self.targets, oldest),
+            format!("Job entries:   {} in {} job(s)", self.job_entries, self.jobs),
+            format!("Capabilities:  {} server(s)", self.capabilities),
+        ]
+        .join("\n")
+    }
+}
+
+/// SQLite store for cached answers, bulk-job history and capability probes
+pub struct Store {
+    connection: Mutex<Connection>,
+    path: PathBuf,
+    capability_ttl: Duration,
+}
+
+impl Store {
+    /// Open or create the database at `path`
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+        }
+        let connection = Connection::open(path)
+            .with_context(|| format!("Failed to open cache database {}", path.display()))?;
+        connection.execute_batch(SCHEMA).context("Failed to create cache tables")?;
+        add_columns(&connection)?;
+        connection.execute_batch(INDEXES).context("Failed to create cache indexes")?;
+        Ok(Self {
+            connection: Mutex::new(connection),
+            path: path.to_path_buf(),
+            capability_ttl: DEFAULT_CAPABILITY_TTL,
+        })
+    }
+
+    /// Default location: next to the config file
+    pub fn default_path() -> Option<PathBuf> {
+        Some(Config::default_path()?.parent()?.join(STORE_FILE_NAME))
+    }
+
+    pub fn with_capability_ttl(mut self, ttl: Duration) -> Self {
+        self.capability_ttl = ttl;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The database connection, for modules that read or write whole tables
+    pub(crate) fn connection(&self) -> std::sync::MutexGuard<'_, Connection> {
+        // Statements are atomic, so a panic while the lock was held leaves nothing half-written
+        self.connection.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Keep an answer with its fingerprint; every answer is kept, so the table doubles as lookup history
+    /// `lookup` is the cache key: the target with the server and the flags that change the answer
+    pub fn record_response(&self, target: &str, lookup: &str, answer: &StoredAnswer) -> Result<()> {
+        self.connection()
+            .execute(
+                "INSERT INTO responses (target, host, port, response, fetched_at, fingerprint, lookup, server_colored, markdown)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                params![
+                    target,
+                    answer.host,
+                    answer.port,
+                    answer.response,
+                    now(),
+                    fingerprint(&answer.response),
+                    lookup,
+                    answer.server_colored,
+                    answer.markdown
+                ],
+            )
+            .context("Failed to store response")?;
+        Ok(())
+    }
+
+    /// Newest answer for a target fetched before `before` (seconds since the epoch):
+    /// (host, port, response, fetched_at)
+    pub fn snapshot_before(&self, target: &str, before: i64) -> Result<Option<(String, u16, String, i64)>> {
+        self.connection()
+            .query_row(
+                "SELECT host, port, response, fetched_at FROM responses
+                 WHERE target = ?1 COLLATE NOCASE AND fetched_at < ?2 ORDER BY fetched_at DESC, rowid DESC LIMIT 1",
+                params![target, before],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .optional()
+            .context("Failed to read cached snapshot")
+    }
+
+    /// Fingerprint of the latest stored answer for a cache key, however old
+    pub fn last_fingerprint(&self, lookup: &str) -> Result<Option<String>> {
+        self.connection()
+            .query_row(
+                "SELECT fingerprint FROM responses WHERE lookup = ?1 ORDER BY fetched_at DESC, rowid DESC LIMIT 1",
+                params![lookup],
+                |row| row.get(0),
+            )
+            .optional()
+            .map(Option::flatten)
+            .context("Failed to read cached fingerprint")
+    }
+
+    /// Latest answer for a cache key younger than `max_age`, with its age
+    pub fn cached_response(&self, lookup: &str, max_age: Duration) -> Result<Option<(StoredAnswer, Duration)>> {
+        let oldest = now() - max_age.as_secs() as i64;
+        let row = self
+            .connection()
+            .query_row(
+                "SELECT host, port, response, server_colored, markdown, fetched_at FROM responses
+                 WHERE lookup = ?1 AND fetched_at >= ?2 ORDER BY fetched_at DESC, rowid DESC LIMIT 1",
+                params![lookup, oldest],
+                |row| {
+                    let answer = StoredAnswer {
+                        host: row.get(0)?,
+                        port: row.get(1)?,
+                        response: row.get(2)?,
+                        server_colored: row.get(3)?,
+                        markdown: row.get(4)?,
+                    };
+                    Ok((answer, row.get::<_, i64>(5)?))
+                },
+            )
+            .optional()
+            .context("Failed to read cached response")?;
+        Ok(row.map(|(answer, fetched_at)| (answer, Duration::from_secs((now() - fetched_at).max(0) as u64))))
+    }
+
+    /// Record one finished target of a bulk job
+    pub fn record_job(&self, job: &str, target: &str, output: Option<&str>, error: Option<&str>) -> Result<()> {
+        self.connection()
+            .execute(
+                "INSERT INTO jobs (job, target, output, error, recorded_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![job, target, output, error, now()],
+            )
+            .context("Failed to store job entry")?;
+        Ok(())
+    }
+
+    pub fn stats(&self) -> Result<StoreStats> {
+        let connection = self.connection();
+        let count = |sql: &str| connection.query_row(sql, [], |row| row.get::<_, i64>(0)).map(|count| count as u64);
+        Ok(StoreStats {
+            responses: count("SELECT COUNT(*) FROM responses")?,
+            targets: count("SELECT COUNT(DISTINCT target) FROM responses")?,
+            job_entries: count("SELECT COUNT(*) FROM jobs")?,
+            jobs: count("SELECT COUNT(DISTINCT job) FROM jobs")?,
+            capabilities: count("SELECT COUNT(*) FROM capabilities")?,
+            oldest: connection.query_row("SELECT MIN(fetched_at) FROM responses", [], |row| row.get(0))?,
+            size_bytes: std::fs::metadata(&self.path).map(|meta| meta.len()).unwrap_or(0),
+        })
+    }
+
+    /// Delete entries older than `older_than`, or everything; returns the number of rows removed
+    pub fn purge(&self, older_than: Option<Duration>) -> Result<usize> {
+        let cutoff = match older_than {
+            Some(age) => now() - age.as_secs() as i64,
+            None => i64::MAX,
+        };
+        let connection = self.connection();
+        let mut removed = 0;
+        for (table, column) in [("responses", "fetched_at"), ("jobs", "recorded_at"), ("capabilities", "probed_at")] {
+            removed += connection
+                .execute(&format!("DELETE FROM {} WHERE {} < ?1", table, column), params![cutoff])
+                .with_context(|| format!("Failed to purge {}", table))?;
+        }
+        connection.execute_batch("VACUUM").context("Failed to compact cache database")?;
+        Ok(removed)
+    }
+}
+
+impl CapabilityCache for Store {
+    fn get(&self, address: &str) -> Option<ServerCapabilities> {
+        let oldest = now() - self.capability_ttl.as_secs() as i64;
+        let json: Option<String> = self
+            .connection()
+            .query_row(
+                "SELECT capabilities FROM capabilities WHERE address = ?1 AND probed_at >= ?2",
+                params![address, oldest],
+                |row| row.get(0),
+            )
+            .optional()
+            .inspect_err(|err| debug!("Capability cache read failed: {}", err))
+            .ok()
+            .flatten();
+        json.and_then(|json| serde_json::from_str(&json).ok())
+    }
+
+    fn put(&self, address: &str, capabilities: &ServerCapabilities) {
+        let Ok(json) = serde_json::to_string(capabilities) else {
+            return;
+        };
+        let result = self.connection().execute(
+            "INSERT OR REPLACE INTO capabilities (address, capabilities, probed_at) VALUES (?1, ?2, ?3)",
+            params![address, json, now()],
+        );
+        if let Err(err) = result {
+            warn!("Failed to cache capabilities of {}: {}", address, err);
+        }
+    }
+}
+
+/// Add the columns of `responses` that databases created before them lack, and fill in fingerprints
+/// Old answers get no cache key, so they stay in the history but are never served from the cache
+fn add_columns(connection: &Connection) -> Result<()> {
+    for (column, definition) in [
+        ("fingerprint", "TEXT"),
+        ("lookup", "TEXT"),
+        ("server_colored", "INTEGER NOT NULL DEFAULT 0"),
+        ("markdown", "INTEGER"),
+    ] {
+        let has_column = connection
+            .prepare("SELECT 1 FROM pragma_table_info('responses') WHERE name = ?1")?
+            .exists([column])?;
+        if !has_column {
+            connection
+                .execute_batch(&format!("ALTER TABLE responses ADD COLUMN {} {}", column, definition))
+                .with_context(|| format!("Failed to add {} to the cache", column))?;
+        }
+    }
+    fill_fingerprints(connection)
+}
+
+/// Compute the fingerprint of stored answers that have none, e.g. imported ones
+pub(crate) fn fill_fingerprints(connection: &Connection) -> Result<()> {
+    let missing: Vec<(i64, String)> = connection
+        .prepare("SELECT rowid, response FROM responses WHERE fingerprint IS NULL")?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+    for (rowid, response) in missing {
+        connection.execute("UPDATE responses SET fingerprint = ?1 WHERE rowid = ?2", params![fingerprint(&response), rowid])?;
+    }
+    Ok(())
+}
+
+pub(crate) fn now() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs() as i64).unwrap_or(0)
+}
+
+/// Rough age such as `3m`, `5h` or `2d`
+pub fn format_age(seconds: i64) -> String {
+    match seconds {
+        s if s < 60 => format!("{}s", s.max(0)),
+        s if s < 3600 => format!("{}m", s / 60),
+        s if s < 86400 => format!("{}h", s / 3600),
+        s => format!("{}d", s / 86400),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_store(name: &str) -> Store {
+        let path = std::env::temp_dir().join(format!("whois-store-{}-{}.sqlite", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        Store::open(&path).unwrap()
+    }
+
+    #[test]
+    fn test_cached_response_roundtrip() {
+        let store = temp_store("responses");
+        let lookup = "-h whois.ripe.net:43 AS3333";
+        assert!(store.cached_response(lookup, Duration::from_secs(60)).unwrap().is_none());
+        let answer = StoredAnswer {
+            host: "whois.ripe.net".to_string(),
+            port: 43,
+            response: "aut-num: AS3333\n".to_string(),
+            server_colored: true,
+            markdown: Some(false),
+        };
+        store.record_response("AS3333", lookup, &answer).unwrap();
+        let (cached, _) = store.cached_response(lookup, Duration::from_secs(60)).unwrap().unwrap();
+        assert_eq!(cached, answer);
+        // Another server or flag is another key
+        assert!(store.cached_response("-h whois.radb.net:43 AS3333", Duration::from_secs(60)).unwrap().is_none());
+        assert!(store.cached_response("AS3333", Duration::from_secs(60)).unwrap().is_none());
+        assert_eq!(store.last_fingerprint(lookup).unwrap(), Some(fingerprint("aut-num: AS3333")));
+        assert_eq!(store.last_fingerprint("-h whois.ripe.net:43 AS3334").unwrap(), None);
+
+        store.record_job("job.jsonl", "AS3333", Some("ok"), None).unwrap();
+        let stats = store.stats().unwrap();
+        assert_eq!((stats.responses, stats.targets, stats.job_entries, stats.jobs), (1, 1, 1, 1));
+
+        assert_eq!(store.purge(Some(Duration::from_secs(3600))).unwrap(), 0);
+        assert_eq!(store.purge(None).unwrap(), 2);
+        let _ = std::fs::remove_file(store.path());
+    }
+
+    #[test]
+    fn test_columns_added_to_old_database() {
+        let path = std::env::temp_dir().join(format!("whois-store-old-{}.sqlite", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let old = Connection::open(&path).unwrap();
+        old.execute_batch(
+            "CREATE TABLE responses (target TEXT NOT NULL, host TEXT NOT NULL, port INTEGER NOT NULL, response TEXT NOT NULL, fetched_at INTEGER NOT NULL);
+             INSERT INTO responses VALUES ('AS3333', 'whois.ripe.net', 43, 'aut-num: AS3333', 1);",
+        )
+        .unwrap();
+        drop(old);
+        let store = Store::open(&path).unwrap();
+        let stored: Option<String> =
+            store.connection().query_row("SELECT fingerprint FROM responses", [], |row| row.get(0)).unwrap();
+        assert_eq!(stored, Some(fingerprint("aut-num: AS3333")));
+        assert!(store.cached_response("AS3333", Duration::from_secs(u32::MAX as u64)).unwrap().is_none());
+        assert_eq!(store.snapshot_before("as3333", 2).unwrap().map(|snapshot| snapshot.3), Some(1));
+        assert_eq!(store.snapshot_before("AS3333", 1).unwrap(), None);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_capability_cache() {
+        let store = temp_store("capabilities");
+        let capabilities = ServerCapabilities {
+            supports_color: true,
+            color_schemes: vec!["ripe".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(store.get("whois.example:43"), None);
+        store.put("whois.example:43", &capabilities);
+        assert_eq!(store.get("whois.example:43"), Some(capabilities));
+
+        let expired = Store::open(store.path()).unwrap().with_capability_ttl(Duration::ZERO);
+        std::thread::sleep(Duration::from_millis(1100));
+        assert_eq!(expired.get("whois.example:43"), None);
+        let _ = std::fs::remove_file(store.path());
+    }
+
+    #[test]
+    fn test_format_age() {
+        assert_eq!(format_age(42), "42s");
+        assert_eq!(format_age(7200), "2h");
+        assert_eq!(format_age(3 * 86400), "3d");
+    }
+}
@@ -0,0 +1,311 @@
+use std::fmt::Write as _;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use encoding_rs::Encoding;
+use tracing::debug;
+
+use crate::compression::{decode_response, MAX_RESPONSE_BYTES};
+use crate::deadline::is_cancelled;
+use crate::error::QueryError;
+use crate::source;
+
+/// Longest a blocking read waits before checking whether the run was cancelled
+const CANCEL_POLL: Duration = Duration::from_millis(200);
+
+/// Per-phase timings for one server in the lookup chain
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HopTiming {
+    pub server: String,
+    /// Time spent on the capability probe, if one was sent; it runs alongside a plain query
+    pub probe: Option<Duration>,
+    pub dns: Duration,
+    pub connect: Duration,
+    pub write: Duration,
+    pub first_byte: Duration,
+    pub total: Duration,
+}
+
+/// Timings for a complete lookup, including every referral hop
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueryTiming {
+    pub hops: Vec<HopTiming>,
+    pub total: Duration,
+}
+
+impl HopTiming {
+    /// One-line summary of the hop
+    pub fn summary(&self) -> String {
+        let mut line = format!("{}:", self.server);
+        if let Some(probe) = self.probe {
+            let _ = write!(line, " probe {}", format_duration(probe));
+        }
+        let _ = write!(
+            line,
+            " dns {} connect {} write {} first byte {} total {}",
+            format_duration(self.dns),
+            format_duration(self.connect),
+            format_duration(self.write),
+            format_duration(self.first_byte),
+            format_duration(self.total),
+        );
+        line
+    }
+}
+
+impl QueryTiming {
+    /// Multi-line summary with one line per hop
+    pub fn summary(&self) -> String {
+        let mut out = format!("total {}", format_duration(self.total));
+        for (index, hop) in self.hops.iter().enumerate() {
+            let _ = write!(out, "\n  hop {}: {}", index + 1, hop.summary());
+        }
+        out
+    }
+}
+
+/// Format a duration in milliseconds with one decimal
+pub fn format_duration(duration: Duration) -> String {
+    format!("{:.1} ms", duration.as_secs_f64() * 1000.0)
+}
+
+/// Resolve and connect, returning the stream with the DNS and connect times
+/// Read and write timeouts are set on the stream; a connect timeout becomes `QueryError::Timeout`
+pub fn timed_connect(address: &str, timeout: Duration) -> Result<(TcpStream, Duration, Duration)> {
+    let start = Instant::now();
+
+    let addrs = crate::resolve::resolve(address, timeout)?;
+    let dns = start.elapsed();
+
+    let connect_start = Instant::now();
+    let stream = connect_any(&addrs, timeout).map_err(|err| match err.kind() {
+        ErrorKind::TimedOut => anyhow::Error::new(QueryError::Timeout { server: address.to_string() }),
+        _ => anyhow::Error::new(err).context(format!("Cannot connect to WHOIS server: {}", address)),
+    })?;
+    let connect = connect_start.elapsed();
+
+    stream.set_read_timeout(Some(timeout))
+        .context("Failed to set read timeout")?;
+    stream.set_write_timeout(Some(timeout))
+        .context("Failed to set write timeout")?;
+    Ok((stream, dns, connect))
+}
+
+/// Map a read error to `QueryError::Timeout` when the server stopped answering
+pub fn read_error(err: std::io::Error, address: &str) -> anyhow::Error {
+    if is_timeout(&err) {
+        anyhow::Error::new(QueryError::Timeout { server: address.to_string() })
+    } else {
+        anyhow::Error::new(err).context("Failed to read response from WHOIS server")
+    }
+}
+
+/// Error for a read cut short by cancellation, keeping the text received so far
+fn interrupted(address: &str, buffer: Vec<u8>) -> anyhow::Error {
+    match received_text(buffer) {
+        Some(received) => QueryError::Interrupted { server: address.to_string(), received }.into(),
+        None => QueryError::Cancelled.into(),
+    }
+}
+
+/// Map a read error to `QueryError::ConnectionReset` when the server dropped the connection
+/// after sending part of the answer, so the text received so far is not lost
+pub fn reset_error(err: std::io::Error, address: &str, buffer: Vec<u8>) -> anyhow::Error {
+    if !is_reset(&err) {
+        return read_error(err, address);
+    }
+    match received_text(buffer) {
+        Some(received) => QueryError::ConnectionReset { server: address.to_string(), received }.into(),
+        None => read_error(err, address),
+    }
+}
+
+/// Text of a cut-short answer, if any arrived
+fn received_text(buffer: Vec<u8>) -> Option<String> {
+    // A truncated compressed body cannot be decoded, so only plain text is kept
+    let received = decode_response(buffer).ok().filter(|received| !received.is_empty())?;
+    Some(String::from_utf8_lossy(&received).into_owned())
+}
+
+fn is_timeout(err: &std::io::Error) -> bool {
+    matches!(err.kind(), ErrorKind::TimedOut | ErrorKind::WouldBlock)
+}
+
+fn is_reset(err: &std::io::Error) -> bool {
+    matches!(err.kind(), ErrorKind::ConnectionReset | ErrorKind::ConnectionAborted | ErrorKind::BrokenPipe | ErrorKind::UnexpectedEof)
+}
+
+/// Text of an answer: UTF-8, or the `charset` of the server's profile
+pub fn decode_text(bytes: Vec<u8>, charset: Option<&str>) -> Result<String> {
+    let Some(label) = charset else {
+        return String::from_utf8(bytes).context("Response is not UTF-8; set the server's charset in its [servers] config");
+    };
+    let encoding = Encoding::for_label(label.as_bytes()).with_context(|| format!("Unknown charset {}", label))?;
+    let (text, _, malformed) = encoding.decode(&bytes);
+    if malformed {
+        debug!("Replaced bytes that are not valid {} in the response", encoding.name());
+    }
+    Ok(text.into_owned())
+}
+
+/// Resolve, connect, send a query and read the full response, timing each phase
+/// The answer is decoded from `charset`, or from UTF-8 without one
+pub fn timed_exchange(address: &str, query: &[u8], timeout: Duration, charset: Option<&str>) -> Result<(String, HopTiming)> {
+    let start = Instant::now();
+    let (mut stream, dns, connect) = timed_connect(address, timeout)?;
+
+    let write_start = Instant::now();
+    stream.write_all(query)
+        .context("Failed to write query to WHOIS server")?;
+    let write = write_start.elapsed();
+
+    // Time to first byte is measured from the end of the write
+    let read_start = Instant::now();
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let mut first_byte = None;
+    // Wake up regularly so an interrupted run does not wait out the whole timeout
+    stream.set_read_timeout(Some(timeout.min(CANCEL_POLL)))
+        .context("Failed to set read timeout")?;
+    let mut last_data = Instant::now();
+    loop {
+        let read = match stream.read(&mut chunk) {
+            Ok(read) => read,
+            Err(_) if is_cancelled() => return Err(interrupted(address, buffer)),
+            Err(err) if is_timeout(&err) && last_data.elapsed() < timeout => continue,
+            Err(err) => return Err(reset_error(err, address, buffer)),
+        };
+        last_data = Instant::now();
+        if first_byte.is_none() {
+            first_byte = Some(read_start.elapsed());
+        }
+        if read == 0 {
+            break;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+        if buffer.len() > MAX_RESPONSE_BYTES {
+            anyhow::bail!("{} sent more than {} MiB", address, MAX_RESPONSE_BYTES / (1024 * 1024));
+        }
+    }
+
+    // Gateways may hand back a gzip/deflate body; decode it before treating it as text
+    let response = decode_text(decode_response(buffer)?, charset)
+        .context("Failed to read response from WHOIS server")?;
+
+    let timing = HopTiming {
+        server: address.to_string(),
+        probe: None,
+        dns,
+        connect,
+        write,
+        first_byte: first_byte.unwrap_or_default(),
+        total: start.elapsed(),
+    };
+    Ok((response, timing))
+}
+
+/// Connect to the first reachable address, from the source set with `--source-ip` or `--interface`
+fn connect_any(addrs: &[SocketAddr], timeout: Duration) -> std::io::Result<TcpStream> {
+    let mut last_error = None;
+    for addr in addrs {
+        let attempt = match source::get() {
+            Some(source) => source.local_address(addr).and_then(|local| source::connect_from(addr, local, timeout)),
+            None => TcpStream::connect_timeout(addr, timeout),
+        };
+        match attempt {
+            Ok(stream) => return Ok(stream),
+            Err(err) => last_error = Some(err),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| {
+        std::io::Error::new(ErrorKind::NotFound, "no addresses resolved")
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::thread;
+
+    #[test]
+    fn test_decode_text() {
+        assert_eq!(decode_text(b"AS3333\n".to_vec(), None).unwrap(), "AS3333\n");
+        assert!(decode_text(vec![0xc7, 0xd1, 0xb1, 0xb9], None).is_err());
+        let registry = crate::servers::ServerRegistry::default();
+        assert_eq!(decode_text(b"\x1b$BF|K\\\x1b(B".to_vec(), registry.charset("whois.jprs.jp")).unwrap(), "日本");
+        assert_eq!(decode_text(vec![0xc7, 0xd1, 0xb1, 0xb9], registry.charset("whois.kisa.or.kr")).unwrap(), "한국");
+        assert!(decode_text(Vec::new(), Some("klingon")).is_err());
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_micros(1500)), "1.5 ms");
+        assert_eq!(format_duration(Duration::ZERO), "0.0 ms");
+    }
+
+    #[test]
+    fn test_summary_includes_every_hop() {
+        let timing = QueryTiming {
+            hops: vec![
+                HopTiming {
+                    server: "whois.iana.org:43".to_string(),
+                    total: Duration::from_millis(20),
+                    ..Default::default()
+                },
+                HopTiming {
+                    server: "whois.ripe.net:43".to_string(),
+                    probe: Some(Duration::from_millis(5)),
+                    total: Duration::from_millis(30),
+                    ..Default::default()
+                },
+            ],
+            total: Duration::from_millis(55),
+        };
+        let summary = timing.summary();
+        assert!(summary.starts_with("total 55.0 ms"));
+        assert!(summary.contains("hop 1: whois.iana.org:43:"));
+        assert!(summary.contains("hop 2: whois.ripe.net:43: probe 5.0 ms"));
+    }
+
+    #[test]
+    fn test_timed_exchange_timeout_is_typed() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        // Accept but never answer
+        let server = thread::spawn(move || {
+            let (socket, _) = listener.accept().unwrap();
+            thread::sleep(Duration::from_millis(300));
+            drop(socket);
+        });
+
+        let err = timed_exchange(&address, b"AS3333\r\n", Duration::from_millis(50), None).unwrap_err();
+        server.join().unwrap();
+        assert_eq!(
+            err.downcast_ref::<QueryError>(),
+            Some(&QueryError::Timeout { server: address })
+        );
+    }
+
+    #[test]
+    fn test_timed_exchange_local_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let server = thread::spawn(move || {
+            let (mut socket, _) = listener.accept().unwrap();
+            let mut request = [0u8; 64];
+            let read = socket.read(&mut request).unwrap();
+            assert_eq!(&request[..read], b"AS3333\r\n");
+            socket.write_all(b"aut-num: AS3333\n").unwrap();
+        });
+
+        let (response, timing) = timed_exchange(&address, b"AS3333\r\n", Duration::from_secs(5), None).unwrap();
+        server.join().unwrap();
+
+        assert_eq!(response, "aut-num: AS3333\n");
+        assert_eq!(timing.server, address);
+        assert!(timing.total >= timing.connect);
+    }
+}
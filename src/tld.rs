@@ -0,0 +1,137 @@
+use anyhow::Result;
+
+use crate::i18n::pad;
+use crate::query::WhoisQuery;
+use crate::servers::WhoisServer;
+use crate::tr;
+
+/// Width of the label column of the summary card
+const LABEL_WIDTH: usize = 19;
+
+/// Delegation record of a top-level domain in the IANA root zone database
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Delegation {
+    /// TLD without the leading dot, lower case
+    pub tld: String,
+    /// Sponsoring organisation, i.e. the registry operator
+    pub operator: Option<String>,
+    pub whois: Option<String>,
+    pub status: Option<String>,
+    pub created: Option<String>,
+    pub changed: Option<String>,
+    pub nameservers: Vec<String>,
+    /// Whether the root zone holds DS records for the TLD
+    pub signed: bool,
+}
+
+impl Delegation {
+    /// Summary card shown above the IANA record
+    pub fn render(&self) -> String {
+        let unknown = || "-".to_string();
+        let mut lines = vec![format!("% ─── {} ───", tr!("tld-heading", tld = self.tld))];
+        let mut push = |label: String, value: String| lines.push(format!("% {}{}", pad(&format!("{}:", label), LABEL_WIDTH), value));
+        push(tr!("tld-operator"), self.operator.clone().unwrap_or_else(unknown));
+        push(tr!("tld-whois"), self.whois.clone().unwrap_or_else(|| tr!("tld-no-whois")));
+        push(tr!("tld-status"), self.status.clone().unwrap_or_else(unknown));
+        push(tr!("tld-created"), self.created.clone().unwrap_or_else(unknown));
+        push(tr!("tld-changed"), self.changed.clone().unwrap_or_else(unknown));
+        let nameservers = if self.nameservers.is_empty() { unknown() } else { self.nameservers.join(", ") };
+        push(tr!("tld-nameservers", count = self.nameservers.len()), nameservers);
+        push(tr!("tld-dnssec"), if self.signed { tr!("tld-signed") } else { tr!("tld-unsigned") });
+        lines.join("\n")
+    }
+}
+
+/// TLD as IANA names it: no leading or trailing dot, lower case
+pub fn normalize(tld: &str) -> String {
+    tld.trim().trim_matches('.').to_lowercase()
+}
+
+/// Delegation record of a TLD from IANA's answer; `None` when IANA has no such TLD
+pub fn parse(tld: &str, response: &str) -> Option<Delegation> {
+    let mut delegation = Delegation { tld: normalize(tld), ..Default::default() };
+    let mut found = false;
+    // The first `organisation:` is the sponsor; later ones belong to the contacts
+    let mut in_contact = false;
+    for line in response.lines().filter(|line| !line.starts_with('%')) {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match name.trim() {
+            "domain" => found = value.eq_ignore_ascii_case(&delegation.tld),
+            "contact" => in_contact = true,
+            "organisation" if !in_contact && delegation.operator.is_none() => delegation.operator = Some(value.to_string()),
+            // `A0.NIC.IO 65.22.160.17 2a01:8840:9e:0:0:0:0:17`
+            "nserver" => {
+                if let Some(host) = value.split_whitespace().next() {
+                    delegation.nameservers.push(host.to_lowercase());
+                }
+            }
+            "ds-rdata" => delegation.signed = true,
+            "whois" if !value.is_empty() => delegation.whois = Some(value.to_lowercase()),
+            "status" => delegation.status = Some(value.to_string()),
+            "created" => delegation.created = Some(value.to_string()),
+            "changed" => delegation.changed = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    found.then_some(delegation)
+}
+
+/// Query IANA for a TLD; the delegation and the raw record
+pub fn lookup(query: &WhoisQuery, server: &WhoisServer, tld: &str) -> Result<(Delegation, String)> {
+    let tld = normalize(tld);
+    let response = query.query_direct(&tld, server)?;
+    match parse(&tld, &response) {
+        Some(delegation) => Ok((delegation, response)),
+        None => anyhow::bail!("{} has no delegation record for .{}", server.host, tld),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IO: &str = "% IANA WHOIS server\n% for more information on IANA, visit http://www.iana.org\n\n\
+                      domain:       IO\n\n\
+                      organisation: Internet Computer Bureau Limited\n\
+                      address:      c/o Sure (Diego Garcia) Limited\n\n\
+                      contact:      administrative\n\
+                      organisation: Internet Computer Bureau Limited\n\n\
+                      nserver:      A0.NIC.IO 65.22.160.17 2a01:8840:9e:0:0:0:0:17\n\
+                      nserver:      B0.NIC.IO 65.22.161.17 2a01:8840:9f:0:0:0:0:17\n\
+                      ds-rdata:     57355 8 2 95a57c3bab7849dbcddf7c72ada71a88146b141110318ca5be672057e865c3e2\n\n\
+                      whois:        whois.nic.io\n\n\
+                      status:       ACTIVE\n\
+                      remarks:      Registration information: http://www.nic.io/\n\n\
+                      created:      1997-09-16\n\
+                      changed:      2024-05-16\n\
+                      source:       IANA\n";
+
+    #[test]
+    fn test_parse_delegation() {
+        let delegation = parse(".IO", IO).unwrap();
+        assert_eq!(delegation.tld, "io");
+        assert_eq!(delegation.operator.as_deref(), Some("Internet Computer Bureau Limited"));
+        assert_eq!(delegation.whois.as_deref(), Some("whois.nic.io"));
+        assert_eq!(delegation.nameservers, ["a0.nic.io", "b0.nic.io"]);
+        assert!(delegation.signed);
+        assert_eq!((delegation.created.as_deref(), delegation.changed.as_deref()), (Some("1997-09-16"), Some("2024-05-16")));
+
+        assert_eq!(parse("zz", "% IANA WHOIS server\n\n% This query returned 0 objects.\n"), None);
+        // A second-level answer is not the TLD's delegation
+        assert_eq!(parse("io", "domain:       EXAMPLE.IO\nwhois:        whois.nic.io\n"), None);
+    }
+
+    #[test]
+    fn test_render_card() {
+        let card = parse("io", IO).unwrap().render();
+        assert!(card.starts_with("% ─── Delegation of .io ───\n% Registry operator: Internet Computer Bureau Limited\n"));
+        assert!(card.contains("% Name servers (2):  a0.nic.io, b0.nic.io\n"));
+        assert!(card.ends_with("% DNSSEC:            signed"));
+
+        let bare = Delegation { tld: "example".to_string(), ..Default::default() }.render();
+        assert!(bare.contains("% WHOIS server:      none\n"));
+    }
+}
@@ -0,0 +1,54 @@
+use crate::protocol::{LEGACY_VERSION, PROTOCOL_VERSION};
+
+/// Width of the label column of the report
+const LABEL_WIDTH: usize = 10;
+
+/// v1.2 protocol extensions the client understands, as named in PROTOCOL.md
+const EXTENSIONS: &[&str] = &["compression", "auth", "paging", "markdown-marker", "markdown-sections", "signed"];
+
+/// Cargo features compiled into this binary
+pub fn features() -> Vec<&'static str> {
+    let features = [
+        ("images", cfg!(feature = "images")),
+        ("clipboard", cfg!(feature = "clipboard")),
+        ("render", cfg!(feature = "render")),
+        ("render-png", cfg!(feature = "render-png")),
+        ("sqlite", cfg!(feature = "sqlite")),
+        ("keyring", cfg!(feature = "keyring")),
+    ];
+    features.iter().filter(|(_, enabled)| *enabled).map(|(name, _)| *name).collect()
+}
+
+/// Extended version report for `whois version` and `--version --verbose`,
+/// meant to be pasted into bug reports
+pub fn report() -> String {
+    let features = features();
+    let rows = [
+        ("commit", env!("WHOIS_GIT_COMMIT").to_string()),
+        ("target", env!("WHOIS_BUILD_TARGET").to_string()),
+        ("profile", env!("WHOIS_BUILD_PROFILE").to_string()),
+        ("features", if features.is_empty() { "none".to_string() } else { features.join(", ") }),
+        // ureq is built with its default rustls backend for the HTTPS data sources
+        ("tls", "rustls".to_string()),
+        (
+            "protocol",
+            format!("WHOIS-COLOR v{}, v{} (v1.2 extensions: {})", LEGACY_VERSION, PROTOCOL_VERSION, EXTENSIONS.join(", ")),
+        ),
+    ];
+    let mut lines = vec![format!("whois-cli {}", env!("CARGO_PKG_VERSION"))];
+    lines.extend(rows.iter().map(|(label, value)| format!("{:<width$}{}", format!("{}:", label), value, width = LABEL_WIDTH)));
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        let report = report();
+        assert!(report.starts_with(concat!("whois-cli ", env!("CARGO_PKG_VERSION"), "\ncommit:   ")));
+        assert!(report.contains("\nprotocol: WHOIS-COLOR v1.0, v1.1 (v1.2 extensions: compression, "));
+        assert_eq!(features().contains(&"sqlite"), cfg!(feature = "sqlite"));
+    }
+}
@@ -0,0 +1,66 @@
+//! Browser bindings for the text processing of whois-cli, so a web page renders WHOIS
+//! answers with the same logic as the command line. The page fetches the answer itself;
+//! build with `wasm-pack build wasm --target web`.
+
+use clap::ValueEnum;
+use wasm_bindgen::prelude::*;
+use whois_cli::html::{stylesheet as html_stylesheet, HtmlRenderer};
+use whois_cli::registration::{Registration, DEFAULT_WARNING_DAYS};
+use whois_cli::{dates, is_rir_response, OutputColorizer, RirHyperlinkProcessor, Theme};
+
+/// Colors on, whatever the environment says, and the day from the browser's clock
+fn prepare() {
+    colored::control::set_override(true);
+    dates::set_today((js_sys::Date::now() / 86_400_000.0).floor() as i64);
+}
+
+fn parse_theme(theme: Option<String>) -> Result<Theme, JsError> {
+    match theme {
+        Some(name) => Theme::from_str(&name, true).map_err(|_| JsError::new(&format!("Unknown theme {}", name))),
+        None => Ok(Theme::Default),
+    }
+}
+
+/// Colorize an answer as `whois` does in a terminal, with ANSI escape codes
+/// RIR answers get OSC 8 hyperlinks when `links` is set
+#[wasm_bindgen]
+pub fn colorize(text: &str, theme: Option<String>, links: bool) -> Result<String, JsError> {
+    prepare();
+    let theme = parse_theme(theme)?;
+    let colored = OutputColorizer::colorize_themed(text, OutputColorizer::detect_scheme(text), theme);
+    if links && is_rir_response(text) {
+        Ok(RirHyperlinkProcessor::new().process(&colored))
+    } else {
+        Ok(colored)
+    }
+}
+
+/// Colorize an answer into an HTML fragment for a `<pre>`, RIR objects linked to their database
+/// Answers a WHOIS-COLOR server already colored keep the server's colors
+#[wasm_bindgen(js_name = renderHtml)]
+pub fn render_html(text: &str, theme: Option<String>) -> Result<String, JsError> {
+    let colored = if text.contains('\x1b') { text.to_string() } else { colorize(text, theme, false)? };
+    let linked = if is_rir_response(&colored) { RirHyperlinkProcessor::new().process(&colored) } else { colored };
+    Ok(HtmlRenderer::render_fragment(&linked))
+}
+
+/// Stylesheet for the classes used by `renderHtml`
+#[wasm_bindgen]
+pub fn stylesheet() -> String {
+    html_stylesheet()
+}
+
+/// Registration summary of a domain's answer: registrar, expiry, lock, nameservers and DNSSEC
+#[wasm_bindgen]
+pub fn summary(domain: &str, text: &str) -> Result<JsValue, JsError> {
+    prepare();
+    let registration = Registration::parse(domain, text, dates::today(), DEFAULT_WARNING_DAYS);
+    let json = serde_json::to_string(&registration)?;
+    js_sys::JSON::parse(&json).map_err(|_| JsError::new("Cannot convert the summary"))
+}
+
+/// Visible text of an answer, without color or hyperlink escape codes
+#[wasm_bindgen(js_name = stripAnsi)]
+pub fn strip_ansi(text: &str) -> String {
+    whois_cli::strip_ansi(text)
+}